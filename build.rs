@@ -0,0 +1,66 @@
+//! Compiles the gRPC definitions under `proto/`, and publishes the locked
+//! versions of the dependencies a tape is computed by.
+//!
+//! The descriptors are produced by `protox`, a pure-Rust protobuf compiler, so
+//! building the crate does not require `protoc` on the machine.
+
+use std::path::Path;
+
+/// The dependencies whose code decides a tape's numbers, and the variable each
+/// locked version is published under for `session::engine`.
+const ENGINE_PACKAGES: [(&str, &str); 2] = [
+    ("optionstratlib", "OCS_OPTIONSTRATLIB_VERSION"),
+    ("positive", "OCS_POSITIVE_VERSION"),
+];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let protos = ["proto/optionchain/v2/simulations.proto"];
+    let descriptors = protox::compile(protos, ["proto"])?;
+
+    tonic_prost_build::configure()
+        // The client is what the tests drive the server with; shipping it
+        // costs nothing and gives Rust consumers a ready-made stub.
+        .build_client(true)
+        .compile_fds(descriptors)?;
+
+    for proto in protos {
+        println!("cargo:rerun-if-changed={proto}");
+    }
+
+    publish_engine_versions()
+}
+
+/// Reads each engine package's version out of `Cargo.lock`.
+///
+/// The lockfile, not the manifest: `optionstratlib = "0.18"` admits every
+/// patch release, and a patch release is exactly what can move a price. A
+/// build without a lockfile beside the manifest — the crate compiled as
+/// someone else's dependency — publishes `unlocked`, which no recorded engine
+/// version matches by accident.
+fn publish_engine_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let lock = Path::new(&std::env::var("CARGO_MANIFEST_DIR")?).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    let contents = std::fs::read_to_string(&lock).unwrap_or_default();
+
+    for (package, variable) in ENGINE_PACKAGES {
+        let version = locked_version(&contents, package).unwrap_or("unlocked");
+        println!("cargo:rustc-env={variable}={version}");
+    }
+    Ok(())
+}
+
+/// The version of the first `[[package]]` entry named `package`.
+fn locked_version<'a>(lock: &'a str, package: &str) -> Option<&'a str> {
+    let name = format!("name = \"{package}\"");
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == name {
+            return lines
+                .next()?
+                .trim()
+                .strip_prefix("version = \"")?
+                .strip_suffix('"');
+        }
+    }
+    None
+}
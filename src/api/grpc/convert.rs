@@ -0,0 +1,467 @@
+//! Conversions between the protobuf messages and the REST DTOs.
+//!
+//! Inbound messages become the REST request types and are validated by the
+//! same conversions the handlers use; outbound ones are built from the REST
+//! response types. Nothing here decides anything about a simulation — it only
+//! changes the spelling.
+
+use super::proto;
+use crate::api::rest::export::{Dataset, ExportQuery, Format};
+use crate::api::rest::models::{ApiTimeFrame, ApiWalkType};
+use crate::api::rest::requests_v2::CreateSimulationRequest;
+use crate::api::rest::responses_v2::{
+    ContractResponse, CursorResponse, ExpiryChainResponse, OptionQuoteResponse,
+    ScheduleRuleResponse, SimulationParametersResponse, SimulationResponse, SnapshotResponse,
+};
+use crate::api::rest::series::Side;
+use crate::session::ExpiryRule;
+use crate::utils::ChainError;
+use chrono::{DateTime, Utc, Weekday};
+use tonic::Status;
+
+/// Maps the error boundary onto gRPC status codes.
+///
+/// The same classes the REST `map_error` distinguishes, one code each; see the
+/// module docs for the table. As over REST, an internal failure is not
+/// described to the caller.
+pub(super) fn status(error: ChainError) -> Status {
+    match error {
+        ChainError::NotFound(_) => Status::not_found(error.to_string()),
+        ChainError::AlreadyExists(_) => Status::already_exists(error.to_string()),
+        // Another writer committed first: the client re-reads and retries,
+        // which is what ABORTED asks of it.
+        ChainError::Conflict(_) => Status::aborted(error.to_string()),
+        ChainError::InvalidState(_) => Status::failed_precondition(error.to_string()),
+        ChainError::Validation { .. } => Status::invalid_argument(error.to_string()),
+        ChainError::SimulatorError(reason) => Status::out_of_range(reason),
+        _ => Status::internal("Internal server error"),
+    }
+}
+
+/// Narrows a wire integer to `usize`, naming the field it came from.
+fn size(field: &str, value: u64) -> Result<usize, ChainError> {
+    usize::try_from(value).map_err(|_| ChainError::Validation {
+        field: field.to_string(),
+        reason: format!("{value} does not fit this platform's address space"),
+    })
+}
+
+/// Parses a weekday as the JSON surface spells it, `Mon` to `Fri`.
+fn weekday(field: &str, raw: &str) -> Result<Weekday, ChainError> {
+    raw.parse().map_err(|_| ChainError::Validation {
+        field: field.to_string(),
+        reason: format!("must be a weekday such as \"Mon\", got {raw:?}"),
+    })
+}
+
+impl TryFrom<proto::ScheduleRule> for ExpiryRule {
+    type Error = ChainError;
+
+    fn try_from(rule: proto::ScheduleRule) -> Result<Self, Self::Error> {
+        // A repeated field cannot be absent, only empty; empty is read as
+        // absent so that a weekly rule without days is "required", and a
+        // daily rule is not told its empty list is not allowed.
+        let weekdays = if rule.weekdays.is_empty() {
+            None
+        } else {
+            Some(
+                rule.weekdays
+                    .iter()
+                    .map(|raw| weekday("schedules.weekdays", raw))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )
+        };
+        let day = rule
+            .weekday
+            .as_deref()
+            .map(|raw| weekday("schedules.weekday", raw))
+            .transpose()?;
+
+        ExpiryRule::from_flat(
+            rule.rule_id,
+            rule.kind,
+            size("schedules.target_count", rule.target_count)?,
+            weekdays,
+            day,
+            rule.month,
+        )
+    }
+}
+
+impl TryFrom<proto::TimeFrame> for ApiTimeFrame {
+    type Error = ChainError;
+
+    fn try_from(time_frame: proto::TimeFrame) -> Result<Self, Self::Error> {
+        use proto::TimeFrameUnit as Unit;
+        use proto::time_frame::Value;
+
+        let invalid = |reason: String| ChainError::Validation {
+            field: "time_frame".to_string(),
+            reason,
+        };
+        match time_frame.value {
+            Some(Value::Custom(periods)) => Ok(ApiTimeFrame::Custom(periods)),
+            Some(Value::Unit(raw)) => match Unit::try_from(raw) {
+                Ok(Unit::Microsecond) => Ok(ApiTimeFrame::Microsecond),
+                Ok(Unit::Millisecond) => Ok(ApiTimeFrame::Millisecond),
+                Ok(Unit::Second) => Ok(ApiTimeFrame::Second),
+                Ok(Unit::Minute) => Ok(ApiTimeFrame::Minute),
+                Ok(Unit::Hour) => Ok(ApiTimeFrame::Hour),
+                Ok(Unit::Day) => Ok(ApiTimeFrame::Day),
+                Ok(Unit::Week) => Ok(ApiTimeFrame::Week),
+                Ok(Unit::Month) => Ok(ApiTimeFrame::Month),
+                Ok(Unit::Quarter) => Ok(ApiTimeFrame::Quarter),
+                Ok(Unit::Year) => Ok(ApiTimeFrame::Year),
+                Ok(Unit::Unspecified) | Err(_) => {
+                    Err(invalid(format!("must name a time frame unit, got {raw}")))
+                }
+            },
+            None => Err(invalid("is required".to_string())),
+        }
+    }
+}
+
+impl TryFrom<proto::WalkMethod> for ApiWalkType {
+    type Error = ChainError;
+
+    fn try_from(method: proto::WalkMethod) -> Result<Self, Self::Error> {
+        use proto::walk_method::Model;
+
+        Ok(match method.model {
+            Some(Model::Brownian(walk)) => ApiWalkType::Brownian {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+            },
+            Some(Model::GeometricBrownian(walk)) => ApiWalkType::GeometricBrownian {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+            },
+            Some(Model::LogReturns(walk)) => ApiWalkType::LogReturns {
+                dt: walk.dt,
+                expected_return: walk.expected_return,
+                volatility: walk.volatility,
+                autocorrelation: walk.autocorrelation,
+            },
+            Some(Model::MeanReverting(walk)) => ApiWalkType::MeanReverting {
+                dt: walk.dt,
+                volatility: walk.volatility,
+                speed: walk.speed,
+                mean: walk.mean,
+            },
+            Some(Model::JumpDiffusion(walk)) => ApiWalkType::JumpDiffusion {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+                intensity: walk.intensity,
+                jump_mean: walk.jump_mean,
+                jump_volatility: walk.jump_volatility,
+            },
+            Some(Model::Garch(walk)) => ApiWalkType::Garch {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+                alpha: walk.alpha,
+                beta: walk.beta,
+            },
+            Some(Model::Heston(walk)) => ApiWalkType::Heston {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+                kappa: walk.kappa,
+                theta: walk.theta,
+                xi: walk.xi,
+                rho: walk.rho,
+            },
+            Some(Model::Custom(walk)) => ApiWalkType::Custom {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+                vov: walk.vov,
+                vol_speed: walk.vol_speed,
+                vol_mean: walk.vol_mean,
+            },
+            Some(Model::Telegraph(walk)) => ApiWalkType::Telegraph {
+                dt: walk.dt,
+                drift: walk.drift,
+                volatility: walk.volatility,
+                lambda_up: walk.lambda_up,
+                lambda_down: walk.lambda_down,
+                vol_multiplier_up: walk.vol_multiplier_up,
+                vol_multiplier_down: walk.vol_multiplier_down,
+            },
+            Some(Model::Historical(walk)) => ApiWalkType::Historical {
+                timeframe: required("method.timeframe", walk.timeframe)?.try_into()?,
+                prices: walk.prices,
+                symbol: walk.symbol,
+            },
+            None => {
+                return Err(ChainError::Validation {
+                    field: "method".to_string(),
+                    reason: "must name a walk model".to_string(),
+                });
+            }
+        })
+    }
+}
+
+/// Unwraps a message field proto3 cannot make mandatory.
+fn required<T>(field: &str, value: Option<T>) -> Result<T, ChainError> {
+    value.ok_or_else(|| ChainError::Validation {
+        field: field.to_string(),
+        reason: "is required".to_string(),
+    })
+}
+
+impl TryFrom<proto::CreateSimulationRequest> for CreateSimulationRequest {
+    type Error = ChainError;
+
+    fn try_from(request: proto::CreateSimulationRequest) -> Result<Self, Self::Error> {
+        let start_at = request
+            .start_at
+            .as_deref()
+            .map(|raw| {
+                DateTime::parse_from_rfc3339(raw)
+                    .map(|instant| instant.with_timezone(&Utc))
+                    .map_err(|error| ChainError::Validation {
+                        field: "start_at".to_string(),
+                        reason: format!("must be an RFC 3339 instant, got {raw:?}: {error}"),
+                    })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            symbol: request.symbol,
+            steps: size("steps", request.steps)?,
+            start_at,
+            step_interval_seconds: request.step_interval_seconds,
+            timezone: request.timezone,
+            calendar: request.calendar,
+            expiration_time: request.expiration_time,
+            schedules: request
+                .schedules
+                .into_iter()
+                .map(ExpiryRule::try_from)
+                .collect::<Result<_, _>>()?,
+            initial_price: request.initial_price,
+            volatility: request.volatility,
+            risk_free_rate: request.risk_free_rate,
+            dividend_yield: request.dividend_yield,
+            method: required("method", request.method)?.try_into()?,
+            time_frame: required("time_frame", request.time_frame)?.try_into()?,
+            chain_size: request
+                .chain_size
+                .map(|value| size("chain_size", value))
+                .transpose()?,
+            strike_interval: request.strike_interval,
+            skew_slope: request.skew_slope,
+            smile_curve: request.smile_curve,
+            spread: request.spread,
+            seed: request.seed,
+            tags: request.tags,
+            retention_seconds: request.retention_seconds,
+            pinned: request.pinned,
+        })
+    }
+}
+
+/// Reads an export request into the query the REST handler takes.
+pub(super) fn export_query(
+    request: &proto::ExportSimulationRequest,
+) -> Result<ExportQuery, ChainError> {
+    let dataset = match proto::ExportDataset::try_from(request.dataset) {
+        Ok(proto::ExportDataset::Underlying) => Dataset::Underlying,
+        Ok(proto::ExportDataset::Volatility) => Dataset::Volatility,
+        Ok(proto::ExportDataset::OptionChains) => Dataset::OptionChains,
+        Ok(proto::ExportDataset::Unspecified) | Err(_) => {
+            return Err(ChainError::Validation {
+                field: "dataset".to_string(),
+                reason: "must be underlying, volatility or option_chains".to_string(),
+            });
+        }
+    };
+    let format = match proto::ExportFormat::try_from(request.format) {
+        Ok(proto::ExportFormat::Json) => Format::Json,
+        Ok(proto::ExportFormat::Ndjson) => Format::Ndjson,
+        Ok(proto::ExportFormat::Csv) => Format::Csv,
+        Ok(proto::ExportFormat::Parquet) => Format::Parquet,
+        Ok(proto::ExportFormat::Arrow) => Format::Arrow,
+        Ok(proto::ExportFormat::Unspecified) | Err(_) => {
+            return Err(ChainError::Validation {
+                field: "format".to_string(),
+                reason: "must be json, ndjson, csv, parquet or arrow".to_string(),
+            });
+        }
+    };
+
+    Ok(ExportQuery {
+        dataset,
+        format,
+        from_step: request
+            .from_step
+            .map(|value| size("from_step", value))
+            .transpose()?,
+        to_step: request
+            .to_step
+            .map(|value| size("to_step", value))
+            .transpose()?,
+        digest: request.digest,
+        labels: list(&request.labels),
+        min_dte: request.min_dte,
+        max_dte: request.max_dte,
+        min_moneyness: request.min_moneyness,
+        max_moneyness: request.max_moneyness,
+        min_delta: request.min_delta,
+        max_delta: request.max_delta,
+        side: match proto::ExportSide::try_from(request.side) {
+            Ok(proto::ExportSide::Call) => Some(Side::Call),
+            Ok(proto::ExportSide::Put) => Some(Side::Put),
+            Ok(proto::ExportSide::Unspecified) => None,
+            Err(_) => {
+                return Err(ChainError::Validation {
+                    field: "side".to_string(),
+                    reason: "must be call or put".to_string(),
+                });
+            }
+        },
+        columns: list(&request.columns),
+        every: request
+            .every
+            .map(|value| size("every", value))
+            .transpose()?,
+        after: request.after.clone(),
+    })
+}
+
+/// Joins a repeated field into the comma-separated list the REST query
+/// carries, or `None` when it is empty.
+fn list(values: &[String]) -> Option<String> {
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+/// Widens a count for the wire. Lossless on every supported platform.
+fn wide(value: usize) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
+
+impl From<CursorResponse> for proto::Cursor {
+    fn from(cursor: CursorResponse) -> Self {
+        Self {
+            current_step: wide(cursor.current_step),
+            total_steps: wide(cursor.total_steps),
+        }
+    }
+}
+
+impl From<ScheduleRuleResponse> for proto::ScheduleRule {
+    fn from(rule: ScheduleRuleResponse) -> Self {
+        Self {
+            rule_id: rule.rule_id,
+            kind: rule.kind,
+            target_count: wide(rule.target_count),
+            weekdays: rule.weekdays.unwrap_or_default(),
+            weekday: rule.weekday,
+            month: rule.month,
+        }
+    }
+}
+
+impl From<SimulationParametersResponse> for proto::SimulationParameters {
+    fn from(parameters: SimulationParametersResponse) -> Self {
+        Self {
+            symbol: parameters.symbol,
+            steps: wide(parameters.steps),
+            seed: parameters.seed,
+            effective_start: parameters.effective_start,
+            step_interval_seconds: parameters.step_interval_seconds,
+            time_frame: parameters.time_frame,
+            timezone: parameters.timezone,
+            calendar: parameters.calendar,
+            tzdb_version: parameters.tzdb_version,
+            engine_version: parameters.engine_version,
+            expiration_time: parameters.expiration_time,
+            schedules: parameters.schedules.into_iter().map(Into::into).collect(),
+            initial_price: parameters.initial_price,
+            volatility: parameters.volatility,
+            risk_free_rate: parameters.risk_free_rate,
+            dividend_yield: parameters.dividend_yield,
+            method_json: parameters.method.to_string(),
+            chain_size: parameters.chain_size.map(wide),
+            strike_interval: parameters.strike_interval,
+            skew_slope: parameters.skew_slope,
+            smile_curve: parameters.smile_curve,
+            spread: parameters.spread,
+        }
+    }
+}
+
+impl From<SimulationResponse> for proto::Simulation {
+    fn from(simulation: SimulationResponse) -> Self {
+        Self {
+            id: simulation.id,
+            state: simulation.state,
+            version: simulation.version,
+            cursor: Some(simulation.cursor.into()),
+            created_at: simulation.created_at,
+            updated_at: simulation.updated_at,
+            parameters: Some(simulation.parameters.into()),
+            tags: simulation.tags,
+            retention_seconds: simulation.retention_seconds,
+            pinned: simulation.pinned,
+        }
+    }
+}
+
+impl From<OptionQuoteResponse> for proto::OptionQuote {
+    fn from(quote: OptionQuoteResponse) -> Self {
+        Self {
+            bid: quote.bid,
+            ask: quote.ask,
+            mid: quote.mid,
+            delta: quote.delta,
+        }
+    }
+}
+
+impl From<ContractResponse> for proto::Contract {
+    fn from(contract: ContractResponse) -> Self {
+        Self {
+            strike: contract.strike,
+            implied_volatility: contract.implied_volatility,
+            gamma: contract.gamma,
+            call: Some(contract.call.into()),
+            put: Some(contract.put.into()),
+        }
+    }
+}
+
+impl From<ExpiryChainResponse> for proto::ExpiryChain {
+    fn from(chain: ExpiryChainResponse) -> Self {
+        Self {
+            expires_at: chain.expires_at,
+            days_to_expiration: chain.days_to_expiration,
+            labels: chain.labels,
+            contracts: chain.contracts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<SnapshotResponse> for proto::Snapshot {
+    fn from(snapshot: SnapshotResponse) -> Self {
+        Self {
+            id: snapshot.id,
+            state: snapshot.state,
+            version: snapshot.version,
+            cursor: Some(snapshot.cursor.into()),
+            simulated_at: snapshot.simulated_at,
+            underlying: Some(proto::Underlying {
+                symbol: snapshot.underlying.symbol,
+                price: snapshot.underlying.price,
+                base_volatility: snapshot.underlying.base_volatility,
+            }),
+            chains: snapshot.chains.into_iter().map(Into::into).collect(),
+            digest: snapshot.digest,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! The v2 simulation API over gRPC.
+//!
+//! A second transport over the same [`SimulationManager`](crate::session::SimulationManager),
+//! not a second implementation. Every call converts its protobuf message into
+//! the REST DTO the matching handler takes and goes through the same
+//! validation, the same manager method and the same compare-and-swap, so a
+//! simulation created over one protocol can be advanced over the other and the
+//! two cannot disagree about where its cursor is.
+//!
+//! The messages are defined in `proto/optionchain/v2/simulations.proto` and
+//! mirror `SimulationResponse` and `SnapshotResponse` field for field.
+//! `ExportSimulation` is a server-streaming call over the REST export's own
+//! bounded producer: concatenating the chunks gives the REST download byte for
+//! byte.
+//!
+//! # Status codes
+//!
+//! | `ChainError`      | REST | gRPC                  |
+//! |-------------------|------|-----------------------|
+//! | `NotFound`        | 404  | `NOT_FOUND`           |
+//! | `AlreadyExists`   | 409  | `ALREADY_EXISTS`      |
+//! | `Conflict`        | 409  | `ABORTED`             |
+//! | `InvalidState`    | 400  | `FAILED_PRECONDITION` |
+//! | `Validation`      | 400  | `INVALID_ARGUMENT`    |
+//! | `SimulatorError`  | 410  | `OUT_OF_RANGE`        |
+//! | anything else     | 500  | `INTERNAL`            |
+//!
+//! A stale `expected_step` is `FAILED_PRECONDITION`, REST's `412`.
+
+mod convert;
+mod service;
+
+/// The generated protobuf messages, server and client for `optionchain.v2`.
+#[allow(missing_docs, clippy::all, clippy::pedantic)]
+pub mod proto {
+    tonic::include_proto!("optionchain.v2");
+}
+
+pub use service::start_grpc_server;
@@ -0,0 +1,534 @@
+//! The `optionchain.v2.Simulations` service.
+
+use super::convert::{export_query, status};
+use super::proto;
+use super::proto::simulations_server::{Simulations, SimulationsServer};
+use crate::api::rest::coding::Coding;
+use crate::api::rest::export::start_export;
+use crate::api::rest::handlers_v2::parse_id;
+use crate::api::rest::models::ListenOn;
+use crate::api::rest::requests_v2::CreateSimulationRequest;
+use crate::api::rest::responses_v2::{SimulationResponse, snapshot_response};
+use crate::session::{SimulationManager, SimulationOptions, SimulationParametersV2};
+use crate::utils::ChainError;
+use futures::stream::Stream;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{info, warn};
+
+/// Serves the v2 simulations over the shared manager.
+pub(crate) struct SimulationService {
+    manager: Arc<SimulationManager>,
+}
+
+impl SimulationService {
+    /// Wraps the service for a tonic router.
+    pub(crate) fn server(manager: Arc<SimulationManager>) -> SimulationsServer<Self> {
+        SimulationsServer::new(Self { manager })
+    }
+}
+
+/// Starts the gRPC server.
+///
+/// Runs until the server stops or fails. `simulation_manager` is the same one
+/// handed to [`start_server`](crate::api::start_server): that sharing is what
+/// lets a REST client and a gRPC client advance one simulation, each seeing
+/// the other's steps and each held to the same compare-and-swap.
+///
+/// # Errors
+///
+/// Returns an error when the address cannot be bound or the server fails.
+pub async fn start_grpc_server(
+    simulation_manager: Arc<SimulationManager>,
+    listen_on: ListenOn,
+    port: u16,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let ip: IpAddr = listen_on.as_str().parse()?;
+    let address = SocketAddr::new(ip, port);
+
+    info!("Starting gRPC server on {}", address);
+
+    tonic::transport::Server::builder()
+        .add_service(SimulationService::server(simulation_manager))
+        .serve(address)
+        .await?;
+    Ok(())
+}
+
+/// The export chunks, as a server stream.
+type ExportStream = Pin<Box<dyn Stream<Item = Result<proto::ExportChunk, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Simulations for SimulationService {
+    async fn create_simulation(
+        &self,
+        request: Request<proto::CreateSimulationRequest>,
+    ) -> Result<Response<proto::Simulation>, Status> {
+        info!("gRPC CreateSimulation");
+
+        let body = CreateSimulationRequest::try_from(request.into_inner()).map_err(status)?;
+        // Taken before the parameter conversion consumes the request, exactly
+        // as the REST handler does.
+        let options = SimulationOptions::try_from(&body).map_err(status)?;
+        let parameters = SimulationParametersV2::try_from(body).map_err(status)?;
+
+        let simulation = self
+            .manager
+            .create(parameters, options)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(SimulationResponse::from(&simulation).into()))
+    }
+
+    async fn get_simulation(
+        &self,
+        request: Request<proto::SimulationRef>,
+    ) -> Result<Response<proto::Simulation>, Status> {
+        let id = parse_id(&request.get_ref().id).map_err(status)?;
+        info!(%id, "gRPC GetSimulation");
+
+        let simulation = self.manager.get(id).await.map_err(status)?;
+        Ok(Response::new(SimulationResponse::from(&simulation).into()))
+    }
+
+    async fn peek_snapshot(
+        &self,
+        request: Request<proto::SimulationRef>,
+    ) -> Result<Response<proto::Snapshot>, Status> {
+        let id = parse_id(&request.get_ref().id).map_err(status)?;
+        info!(%id, "gRPC PeekSnapshot");
+
+        let (simulation, snapshot) = self.manager.peek(id).await.map_err(status)?;
+        Ok(Response::new(
+            snapshot_response(&simulation, &snapshot).into(),
+        ))
+    }
+
+    async fn advance_simulation(
+        &self,
+        request: Request<proto::AdvanceSimulationRequest>,
+    ) -> Result<Response<proto::Snapshot>, Status> {
+        let request = request.into_inner();
+        let id = parse_id(&request.id).map_err(status)?;
+        info!(%id, "gRPC AdvanceSimulation");
+
+        // The same transport-level precondition as REST's `expected_step`:
+        // resolved before anything is built, so a mismatch consumes nothing.
+        // The advance then commits against the revision that was checked, so
+        // a concurrent advance in between is a conflict rather than a step
+        // taken from a cursor the client never named.
+        let mut expected_version = None;
+        if let Some(expected) = request.expected_step {
+            let simulation = self.manager.get(id).await.map_err(status)?;
+            if u64::try_from(simulation.current_step).ok() != Some(expected) {
+                return Err(Status::failed_precondition(format!(
+                    "expected_step does not match the simulation's current cursor ({})",
+                    simulation.current_step
+                )));
+            }
+            expected_version = Some(simulation.version);
+        }
+
+        let (simulation, snapshot) = self
+            .manager
+            .advance_expecting(id, expected_version)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(
+            snapshot_response(&simulation, &snapshot).into(),
+        ))
+    }
+
+    async fn delete_simulation(
+        &self,
+        request: Request<proto::SimulationRef>,
+    ) -> Result<Response<proto::DeleteSimulationResponse>, Status> {
+        let id = parse_id(&request.get_ref().id).map_err(status)?;
+        info!(%id, "gRPC DeleteSimulation");
+
+        if self.manager.delete(id).await.map_err(status)? {
+            Ok(Response::new(proto::DeleteSimulationResponse {}))
+        } else {
+            Err(status(ChainError::NotFound(format!(
+                "Simulation with id {id} not found"
+            ))))
+        }
+    }
+
+    type ExportSimulationStream = ExportStream;
+
+    async fn export_simulation(
+        &self,
+        request: Request<proto::ExportSimulationRequest>,
+    ) -> Result<Response<Self::ExportSimulationStream>, Status> {
+        let request = request.into_inner();
+        let id = parse_id(&request.id).map_err(status)?;
+        info!(%id, "gRPC ExportSimulation");
+
+        let query = export_query(&request).map_err(status)?;
+        // gRPC negotiates its own message compression, so the chunks go out as
+        // the format encodes them.
+        let export = start_export(
+            &self.manager,
+            self.manager.warehouse(),
+            id,
+            &query,
+            Coding::Identity,
+        )
+        .await
+        .map_err(status)?;
+
+        // Dropping the stream — which is what tonic does when the client goes
+        // away — drops the receiver, and the producer's next send ends it.
+        let chunks = futures::stream::unfold(export.receiver, |mut receiver| async move {
+            let chunk = match receiver.recv().await? {
+                Ok(data) => Ok(proto::ExportChunk { data }),
+                Err(error) => {
+                    warn!(%error, "a v2 export failed after the stream had started");
+                    Err(status(error))
+                }
+            };
+            Some((chunk, receiver))
+        });
+        Ok(Response::new(Box::pin(chunks)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::routes::configure_v2_routes;
+    use crate::infrastructure::SimulationV2Config;
+    use crate::session::InMemorySimulationStore;
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use futures::StreamExt;
+    use proto::simulations_client::SimulationsClient;
+    use tokio::net::TcpListener;
+    use tonic::Code;
+    use tonic::transport::Channel;
+    use tonic::transport::server::TcpIncoming;
+
+    /// The reference configuration the REST tests create, as a protobuf
+    /// request.
+    fn reference_request() -> proto::CreateSimulationRequest {
+        let rule = |rule_id: &str, kind: &str, target_count: u64| proto::ScheduleRule {
+            rule_id: rule_id.to_string(),
+            kind: kind.to_string(),
+            target_count,
+            ..Default::default()
+        };
+        proto::CreateSimulationRequest {
+            symbol: "SPX".to_string(),
+            steps: 4,
+            start_at: Some("2026-01-05T14:30:00Z".to_string()),
+            step_interval_seconds: Some(86_400),
+            timezone: "America/New_York".to_string(),
+            expiration_time: "17:00".to_string(),
+            schedules: vec![
+                rule("zero_dte", "daily", 1),
+                proto::ScheduleRule {
+                    weekdays: vec!["Mon".to_string(), "Wed".to_string(), "Fri".to_string()],
+                    ..rule("weeklies", "weekly", 3)
+                },
+                proto::ScheduleRule {
+                    weekday: Some("Fri".to_string()),
+                    ..rule("monthlies", "monthly", 12)
+                },
+            ],
+            initial_price: 5000.0,
+            volatility: 0.18,
+            risk_free_rate: 0.04,
+            dividend_yield: 0.012,
+            method: Some(proto::WalkMethod {
+                model: Some(proto::walk_method::Model::Brownian(proto::DriftWalk {
+                    dt: 0.004,
+                    drift: 0.0,
+                    volatility: 0.18,
+                })),
+            }),
+            time_frame: Some(proto::TimeFrame {
+                value: Some(proto::time_frame::Value::Unit(
+                    proto::TimeFrameUnit::Day as i32,
+                )),
+            }),
+            chain_size: Some(3),
+            strike_interval: Some(25.0),
+            spread: Some(0.02),
+            seed: Some(42),
+            ..Default::default()
+        }
+    }
+
+    fn manager() -> Arc<SimulationManager> {
+        Arc::new(SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        ))
+    }
+
+    /// Serves the service on an ephemeral port and connects a client to it.
+    async fn client(manager: Arc<SimulationManager>) -> SimulationsClient<Channel> {
+        let listener = match TcpListener::bind("127.0.0.1:0").await {
+            Ok(listener) => listener,
+            Err(error) => panic!("an ephemeral port must bind: {error}"),
+        };
+        let address = match listener.local_addr() {
+            Ok(address) => address,
+            Err(error) => panic!("a bound listener has an address: {error}"),
+        };
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(SimulationService::server(manager))
+                .serve_with_incoming(TcpIncoming::from(listener)),
+        );
+        match SimulationsClient::connect(format!("http://{address}")).await {
+            Ok(client) => client,
+            Err(error) => panic!("the client must connect: {error}"),
+        }
+    }
+
+    async fn create(client: &mut SimulationsClient<Channel>) -> proto::Simulation {
+        match client.create_simulation(reference_request()).await {
+            Ok(response) => response.into_inner(),
+            Err(error) => panic!("the reference request must create: {error}"),
+        }
+    }
+
+    fn current_step(snapshot: &proto::Snapshot) -> u64 {
+        match &snapshot.cursor {
+            Some(cursor) => cursor.current_step,
+            None => panic!("a snapshot always carries its cursor"),
+        }
+    }
+
+    /// One simulation, two protocols: each sees the other's advances, and the
+    /// `expected_step` precondition holds across them.
+    #[actix_web::test]
+    async fn test_grpc_and_rest_advance_the_same_simulation() {
+        let manager = manager();
+        let mut client = client(Arc::clone(&manager)).await;
+        let app = actix_test::init_service(
+            App::new().configure(|cfg| configure_v2_routes(cfg, Arc::clone(&manager), None)),
+        )
+        .await;
+
+        let created = create(&mut client).await;
+        let id = created.id.clone();
+
+        let first = match client
+            .advance_simulation(proto::AdvanceSimulationRequest {
+                id: id.clone(),
+                expected_step: Some(0),
+            })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(error) => panic!("the first advance must succeed: {error}"),
+        };
+        assert_eq!(current_step(&first), 1);
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{id}/step?expected_step=1"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let stale = client
+            .advance_simulation(proto::AdvanceSimulationRequest {
+                id: id.clone(),
+                expected_step: Some(1),
+            })
+            .await;
+        match stale {
+            Ok(_) => panic!("a stale expected_step must not advance"),
+            Err(error) => assert_eq!(error.code(), Code::FailedPrecondition),
+        }
+
+        let read = match client
+            .get_simulation(proto::SimulationRef { id: id.clone() })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(error) => panic!("the simulation must be readable: {error}"),
+        };
+        assert_eq!(read.cursor.map(|cursor| cursor.current_step), Some(2));
+        assert_eq!(read.state, "in_progress");
+
+        // The peek is the REST peek, field for field.
+        let peeked = match client
+            .peek_snapshot(proto::SimulationRef { id: id.clone() })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(error) => panic!("the peek must succeed: {error}"),
+        };
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/snapshot"))
+            .to_request();
+        let rest: SnapshotResponseBody = actix_test::call_and_read_body_json(&app, request).await;
+        assert_eq!(peeked.simulated_at, rest.simulated_at);
+        assert_eq!(peeked.chains.len(), rest.chains.len());
+        assert_eq!(
+            peeked.underlying.map(|underlying| underlying.price),
+            Some(rest.underlying.price)
+        );
+    }
+
+    /// Just the fields the cross-protocol comparison reads.
+    #[derive(serde::Deserialize)]
+    struct SnapshotResponseBody {
+        simulated_at: String,
+        underlying: UnderlyingBody,
+        chains: Vec<serde_json::Value>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct UnderlyingBody {
+        price: f64,
+    }
+
+    /// Each error class arrives as its documented status code.
+    #[actix_web::test]
+    async fn test_errors_map_to_grpc_status_codes() {
+        let mut client = client(manager()).await;
+
+        let malformed = client
+            .get_simulation(proto::SimulationRef {
+                id: "not-a-uuid".to_string(),
+            })
+            .await;
+        assert_eq!(
+            malformed.err().map(|e| e.code()),
+            Some(Code::InvalidArgument)
+        );
+
+        let unknown = client
+            .peek_snapshot(proto::SimulationRef {
+                id: uuid::Uuid::new_v4().to_string(),
+            })
+            .await;
+        assert_eq!(unknown.err().map(|e| e.code()), Some(Code::NotFound));
+
+        let id = create(&mut client).await.id;
+        for _ in 0..4 {
+            let advance = client
+                .advance_simulation(proto::AdvanceSimulationRequest {
+                    id: id.clone(),
+                    expected_step: None,
+                })
+                .await;
+            if let Err(error) = advance {
+                panic!("every step must advance: {error}");
+            }
+        }
+        let completed = client
+            .advance_simulation(proto::AdvanceSimulationRequest {
+                id: id.clone(),
+                expected_step: None,
+            })
+            .await;
+        assert_eq!(completed.err().map(|e| e.code()), Some(Code::OutOfRange));
+
+        if let Err(error) = client
+            .delete_simulation(proto::SimulationRef { id: id.clone() })
+            .await
+        {
+            panic!("the delete must succeed: {error}");
+        }
+        let deleted = client
+            .delete_simulation(proto::SimulationRef { id: id.clone() })
+            .await;
+        assert_eq!(deleted.err().map(|e| e.code()), Some(Code::NotFound));
+    }
+
+    /// A request REST would refuse is refused here, naming the same field.
+    #[actix_web::test]
+    async fn test_create_validates_like_rest() {
+        let mut client = client(manager()).await;
+
+        let without_method = proto::CreateSimulationRequest {
+            method: None,
+            ..reference_request()
+        };
+        match client.create_simulation(without_method).await {
+            Ok(_) => panic!("a request without a walk model must be refused"),
+            Err(error) => {
+                assert_eq!(error.code(), Code::InvalidArgument);
+                assert!(error.message().contains("method"), "{}", error.message());
+            }
+        }
+
+        let mut misplaced = reference_request();
+        misplaced.schedules[0].weekdays = vec!["Mon".to_string()];
+        match client.create_simulation(misplaced).await {
+            Ok(_) => panic!("weekdays on a daily rule must be refused"),
+            Err(error) => {
+                assert_eq!(error.code(), Code::InvalidArgument);
+                assert!(
+                    error.message().contains("schedules.zero_dte.weekdays"),
+                    "{}",
+                    error.message()
+                );
+            }
+        }
+    }
+
+    /// The streamed export concatenates to the REST download, byte for byte.
+    #[actix_web::test]
+    async fn test_export_streams_the_rest_bytes() {
+        let manager = manager();
+        let mut client = client(Arc::clone(&manager)).await;
+        let app = actix_test::init_service(
+            App::new().configure(|cfg| configure_v2_routes(cfg, Arc::clone(&manager), None)),
+        )
+        .await;
+        let id = create(&mut client).await.id;
+
+        let mut stream = match client
+            .export_simulation(proto::ExportSimulationRequest {
+                id: id.clone(),
+                dataset: proto::ExportDataset::OptionChains as i32,
+                format: proto::ExportFormat::Csv as i32,
+                from_step: None,
+                to_step: None,
+                digest: false,
+                ..Default::default()
+            })
+            .await
+        {
+            Ok(response) => response.into_inner(),
+            Err(error) => panic!("the export must start: {error}"),
+        };
+        let mut streamed = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => streamed.extend_from_slice(&chunk.data),
+                Err(error) => panic!("the export must not fail midway: {error}"),
+            }
+        }
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/simulations/{id}/export?dataset=option_chains&format=csv"
+            ))
+            .to_request();
+        let downloaded = actix_test::call_and_read_body(&app, request).await;
+        assert!(!streamed.is_empty());
+        assert_eq!(streamed, downloaded.to_vec());
+
+        let unspecified = client
+            .export_simulation(proto::ExportSimulationRequest {
+                id,
+                ..Default::default()
+            })
+            .await;
+        assert_eq!(
+            unspecified.err().map(|e| e.code()),
+            Some(Code::InvalidArgument)
+        );
+    }
+}
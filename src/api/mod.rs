@@ -1,5 +1,7 @@
+pub(crate) mod grpc;
 pub(crate) mod rest;
 
+pub use grpc::{proto, start_grpc_server};
 pub use rest::controller::start_server;
 pub use rest::models::ListenOn;
 pub use rest::patch::Patch;
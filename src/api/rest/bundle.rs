@@ -0,0 +1,542 @@
+//! Multi-dataset ZIP bundles of a v2 simulation.
+//!
+//! `GET /api/v2/simulations/{id}/export/bundle` streams one ZIP holding what a
+//! backtest always downloads together: `manifest.json`, then the
+//! `underlying`, `volatility` and `option_chains` datasets of a range, each in
+//! the requested format and byte-identical to its own export.
+//!
+//! The manifest is the simulation's replay manifest — the document
+//! `GET /{id}/manifest` returns — so a bundle carries the effective parameters
+//! it was produced from, and `POST /import` recreates the simulation from it.
+//!
+//! # Written as it goes
+//!
+//! The archive is never held in memory. It is written in ZIP's streaming form:
+//! every entry's sizes and CRC follow its data in a data descriptor, so nothing
+//! is ever seeked back to, and the central directory goes out last. The
+//! archive's sink is drained after every chunk onto the export's bounded
+//! channel, so a bundle keeps the export's backpressure and cancellation, and
+//! its memory is one step's rows plus the deflater's window.
+//!
+//! The datasets are walked one after the other, because a ZIP entry has to be
+//! finished before the next begins. Only `option_chains` prices chains — or
+//! every dataset, with `digest=true` — so the two per-step walks cost almost
+//! nothing next to it.
+//!
+//! # Deterministic
+//!
+//! Every entry is stamped with the ZIP epoch, 1980-01-01, and `0644`, so a
+//! repeated bundle is byte-identical like any export. Text and Arrow entries
+//! are deflated; Parquet is already ZSTD-compressed and is stored as it is.
+
+use crate::api::rest::coding::Coding;
+use crate::api::rest::error::map_error;
+use crate::api::rest::export::{
+    CHANNEL_CAPACITY, Dataset, Encoder, Format, RowStream, StepRange, StoredSteps, encode_range,
+};
+use crate::api::rest::filter::Selection;
+use crate::api::rest::handlers_v2::{SimulationPath, parse_id};
+use crate::infrastructure::SimulationSnapshotRepository;
+use crate::session::{SimulationManager, SimulationManifest, SimulationParametersV2};
+use crate::utils::ChainError;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use tracing::{info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// The datasets a bundle carries, in entry order.
+const DATASETS: [Dataset; 3] = [
+    Dataset::Underlying,
+    Dataset::Volatility,
+    Dataset::OptionChains,
+];
+
+/// The name of the manifest's entry.
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+/// Query parameters for a bundle: an export's, without the dataset.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct BundleQuery {
+    /// Which encoding every dataset is written in.
+    pub(crate) format: Format,
+    /// First step to include, inclusive. Defaults to `0`.
+    #[serde(default)]
+    pub(crate) from_step: Option<usize>,
+    /// Last step to include, inclusive. Defaults to the final generated step.
+    #[serde(default)]
+    pub(crate) to_step: Option<usize>,
+    /// Appends the running chained digest to every row of every dataset.
+    #[serde(default)]
+    pub(crate) digest: bool,
+}
+
+/// One dataset's entry, ready to be written.
+struct Entry {
+    name: String,
+    dataset: Dataset,
+    options: SimpleFileOptions,
+    encoder: Encoder,
+}
+
+/// The archive's sink: a buffer drained after every write, so the bytes the
+/// deflater lets go of leave for the channel straight away.
+///
+/// Shared with the producer that drains it, which is why it is a handle: the
+/// ZIP writer owns its sink and surrenders it only when the archive is done.
+#[derive(Clone, Default)]
+struct Drained(Rc<RefCell<Vec<u8>>>);
+
+impl Drained {
+    /// Takes everything written since the last call.
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.borrow_mut())
+    }
+}
+
+impl Write for Drained {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/export/bundle",
+    description = "Export every dataset of a step range in one ZIP: manifest.json, the \
+        simulation's replay manifest with its effective parameters, then underlying, volatility \
+        and option_chains in the requested format, each byte-identical to its own export. The \
+        archive is streamed as it is written and never held in memory. Read-only, like the \
+        export, and byte-identical on a repeat.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("format" = String, Query, description = "json | ndjson | csv | parquet | arrow"),
+        ("from_step" = Option<usize>, Query, description = "First step, inclusive; defaults to 0"),
+        ("to_step" = Option<usize>, Query, description = "Last step, inclusive; defaults to the final step"),
+        ("digest" = Option<bool>, Query, description = "Append the chained digest through each row's step to every dataset")
+    ),
+    responses(
+        (status = 200, description = "The ZIP, streamed", content_type = "application/zip", body = Vec<u8>),
+        (status = 400, description = "Unknown format, malformed id or an invalid range; body carries `error` and `field`"),
+        (status = 404, description = "Simulation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(manager, snapshots, query), level = "debug")]
+pub(crate) async fn export_bundle(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    snapshots: Option<web::Data<Arc<dyn SimulationSnapshotRepository>>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<BundleQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match start_bundle(
+        &manager,
+        snapshots.map(|repository| Arc::clone(repository.get_ref())),
+        id,
+        &query,
+    )
+    .await
+    {
+        Ok((filename, receiver)) => HttpResponse::Ok()
+            .content_type("application/zip")
+            .insert_header((
+                actix_web::http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ))
+            .streaming(RowStream { receiver }),
+        Err(error) => map_error(error),
+    }
+}
+
+/// Validates a bundle request and spawns its producer.
+///
+/// As [`super::export::start_export`]: everything that can be rejected is
+/// rejected here, every encoder included, before the archive's first byte.
+/// Returns the suggested filename and the chunks.
+async fn start_bundle(
+    manager: &SimulationManager,
+    snapshots: Option<Arc<dyn SimulationSnapshotRepository>>,
+    id: Uuid,
+    query: &BundleQuery,
+) -> Result<(String, mpsc::Receiver<Result<Vec<u8>, ChainError>>), ChainError> {
+    let simulation = manager.get(id).await?;
+    manager.check_engine(&simulation)?;
+    let manifest = serde_json::to_vec_pretty(&SimulationManifest::of(&simulation))
+        .map_err(|e| ChainError::Internal(format!("failed to encode the bundle manifest: {e}")))?;
+    let parameters = simulation.parameters.clone();
+    let range = StepRange::bounded(
+        query.from_step,
+        query.to_step,
+        parameters.steps,
+        manager.config().max_export_rows,
+    )?;
+
+    let format = query.format;
+    let digest = query.digest;
+    let entries = DATASETS
+        .into_iter()
+        .map(|dataset| {
+            Ok(Entry {
+                name: format!("{}.{}", dataset.as_str(), format.extension()),
+                dataset,
+                options: entry_options(format),
+                encoder: Encoder::new(
+                    format,
+                    dataset,
+                    digest,
+                    Selection::default(),
+                    Coding::Identity,
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>, ChainError>>()?;
+
+    // One warehouse reader per walk that prices chains; the handle is taken
+    // here, on the runtime, because the producer will not be on one.
+    let handle = Handle::current();
+    let stored = move |dataset: Dataset| {
+        snapshots
+            .clone()
+            .filter(|_| dataset.needs_chains() || digest)
+            .map(|repository| StoredSteps::new(repository, id, handle.clone()))
+    };
+
+    let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        let produced = produce(
+            &parameters,
+            &manifest,
+            entries,
+            digest,
+            range,
+            stored,
+            &sender,
+        );
+        if let Err(error) = produced {
+            // A send failure means the client went away, which is not an error
+            // worth reporting to anyone.
+            let _ = sender.blocking_send(Err(error));
+        }
+    });
+
+    let filename = format!("{}-bundle-{}.zip", simulation.id, range.from);
+    Ok((filename, receiver))
+}
+
+/// How one dataset is stored in the archive.
+///
+/// Fixed timestamp and permissions, so the archive is a function of its
+/// contents; `large_file` because an `option_chains` entry can pass 4 GiB and a
+/// streamed entry has to declare that before its data.
+fn entry_options(format: Format) -> SimpleFileOptions {
+    let method = match format {
+        Format::Parquet => CompressionMethod::Stored,
+        _ => CompressionMethod::Deflated,
+    };
+    manifest_options()
+        .compression_method(method)
+        .large_file(true)
+}
+
+/// How the manifest is stored in the archive.
+fn manifest_options() -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644)
+}
+
+/// Writes the archive and sends it as it fills.
+///
+/// Runs on a blocking thread. Returns as soon as a send fails, which is how a
+/// disconnected client stops the work.
+fn produce(
+    parameters: &SimulationParametersV2,
+    manifest: &[u8],
+    entries: Vec<Entry>,
+    digest: bool,
+    range: StepRange,
+    stored: impl Fn(Dataset) -> Option<StoredSteps>,
+    sender: &mpsc::Sender<Result<Vec<u8>, ChainError>>,
+) -> Result<(), ChainError> {
+    let sink = Drained::default();
+    let mut archive = ZipWriter::new_stream(sink.clone());
+    let send = |chunk: Vec<u8>| chunk.is_empty() || sender.blocking_send(Ok(chunk)).is_ok();
+
+    archive
+        .start_file(MANIFEST_ENTRY, manifest_options())
+        .map_err(zip_error)?;
+    archive.write_all(manifest).map_err(io_error)?;
+    if !send(sink.take()) {
+        return Ok(());
+    }
+
+    for entry in entries {
+        archive
+            .start_file(entry.name, entry.options)
+            .map_err(zip_error)?;
+        let finished = encode_range(
+            parameters,
+            entry.dataset,
+            entry.encoder,
+            digest,
+            range,
+            stored(entry.dataset),
+            |chunk| {
+                archive.write_all(&chunk).map_err(io_error)?;
+                Ok(send(sink.take()))
+            },
+        )?;
+        if !finished {
+            return Ok(());
+        }
+    }
+
+    archive.finish().map_err(zip_error)?;
+    // The last send; a client gone by now changes nothing.
+    send(sink.take());
+    Ok(())
+}
+
+/// Maps an archive failure into the error boundary.
+#[cold]
+fn zip_error(error: zip::result::ZipError) -> ChainError {
+    ChainError::Internal(format!("failed to write the export bundle: {error}"))
+}
+
+/// Maps a write into the archive failing into the error boundary.
+#[cold]
+fn io_error(error: std::io::Error) -> ChainError {
+    ChainError::Internal(format!("failed to write the export bundle: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::routes::configure_v2_routes;
+    use crate::infrastructure::SimulationV2Config;
+    use crate::session::InMemorySimulationStore;
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use serde_json::{Value, json};
+    use std::io::{Cursor, Read};
+
+    macro_rules! v2_service {
+        () => {{
+            let manager = Arc::new(SimulationManager::new(
+                Arc::new(InMemorySimulationStore::new()),
+                SimulationV2Config::default(),
+            ));
+            actix_test::init_service(
+                App::new().configure(|cfg| configure_v2_routes(cfg, manager.clone(), None)),
+            )
+            .await
+        }};
+    }
+
+    macro_rules! create {
+        ($app:expr) => {{
+            let request = actix_test::TestRequest::post()
+                .uri("/api/v2/simulations")
+                .set_json(json!({
+                    "symbol": "SPX",
+                    "steps": 3,
+                    "start_at": "2026-01-05T14:30:00Z",
+                    "step_interval_seconds": 86400,
+                    "timezone": "America/New_York",
+                    "expiration_time": "17:00",
+                    "schedules": [
+                        { "rule_id": "weeklies", "kind": "weekly", "target_count": 2,
+                          "weekdays": ["Fri"] }
+                    ],
+                    "initial_price": 5000.0,
+                    "volatility": 0.18,
+                    "risk_free_rate": 0.04,
+                    "dividend_yield": 0.0,
+                    "method": { "Brownian": { "dt": 0.004, "drift": 0.0, "volatility": 0.18 } },
+                    "time_frame": "Day",
+                    "chain_size": 3,
+                    "strike_interval": 25.0,
+                    "spread": 0.02,
+                    "seed": 42
+                }))
+                .to_request();
+            let response = actix_test::call_service(&$app, request).await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let body: Value = actix_test::read_body_json(response).await;
+            match body.get("id").and_then(Value::as_str) {
+                Some(id) => id.to_string(),
+                None => panic!("the response must carry an id: {body}"),
+            }
+        }};
+    }
+
+    /// GETs a path, returning the status and the body.
+    macro_rules! get {
+        ($app:expr, $uri:expr) => {{
+            let response = actix_test::call_service(
+                &$app,
+                actix_test::TestRequest::get().uri(&$uri).to_request(),
+            )
+            .await;
+            let status = response.status();
+            (status, actix_test::read_body(response).await)
+        }};
+    }
+
+    /// Every entry of an archive, in order, with its compression method.
+    fn entries(archive: &[u8]) -> Vec<(String, CompressionMethod, Vec<u8>)> {
+        let mut archive = match zip::ZipArchive::new(Cursor::new(archive)) {
+            Ok(archive) => archive,
+            Err(error) => panic!("a bundle must be a readable ZIP: {error}"),
+        };
+        (0..archive.len())
+            .map(|index| {
+                let mut file = match archive.by_index(index) {
+                    Ok(file) => file,
+                    Err(error) => panic!("entry {index} must open: {error}"),
+                };
+                let mut contents = Vec::new();
+                if let Err(error) = file.read_to_end(&mut contents) {
+                    panic!("entry {index} must read: {error}");
+                }
+                (file.name().to_string(), file.compression(), contents)
+            })
+            .collect()
+    }
+
+    /// A bundle holds the manifest and every dataset, each exactly as its own
+    /// export and its own manifest endpoint serve it.
+    #[actix_web::test]
+    async fn test_a_bundle_carries_the_manifest_and_every_dataset() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v2/simulations/{id}/export/bundle?format=csv&to_step=1"
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(actix_web::http::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok()),
+            Some("application/zip")
+        );
+        let archive = entries(&actix_test::read_body(response).await);
+
+        let names: Vec<&str> = archive.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "manifest.json",
+                "underlying.csv",
+                "volatility.csv",
+                "option_chains.csv"
+            ]
+        );
+
+        let (_, manifest) = get!(app, format!("/api/v2/simulations/{id}/manifest"));
+        match (
+            serde_json::from_slice::<Value>(&archive[0].2),
+            serde_json::from_slice::<Value>(&manifest),
+        ) {
+            (Ok(bundled), Ok(served)) => assert_eq!(bundled, served),
+            other => panic!("both manifests must be JSON: {other:?}"),
+        }
+
+        for (name, method, contents) in &archive[1..] {
+            let dataset = name.trim_end_matches(".csv");
+            let (status, export) = get!(
+                app,
+                format!("/api/v2/simulations/{id}/export?dataset={dataset}&format=csv&to_step=1")
+            );
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(contents.as_slice(), export.as_ref(), "{name}");
+            assert_eq!(*method, CompressionMethod::Deflated, "{name}");
+        }
+    }
+
+    /// A Parquet bundle stores its already-compressed entries as they are, and
+    /// a repeated bundle is byte-identical.
+    #[actix_web::test]
+    async fn test_a_parquet_bundle_is_stored_and_deterministic() {
+        let app = v2_service!();
+        let id = create!(app);
+        let uri = format!("/api/v2/simulations/{id}/export/bundle?format=parquet&digest=true");
+
+        let (status, first) = get!(app, uri);
+        let (_, second) = get!(app, uri);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(first, second, "a bundle must repeat byte for byte");
+
+        let (_, export) = get!(
+            app,
+            format!(
+                "/api/v2/simulations/{id}/export?dataset=option_chains&format=parquet&digest=true"
+            )
+        );
+        match entries(&first).last() {
+            Some((name, method, contents)) => {
+                assert_eq!(name, "option_chains.parquet");
+                assert_eq!(*method, CompressionMethod::Stored);
+                assert_eq!(contents.as_slice(), export.as_ref());
+            }
+            None => panic!("a bundle must carry entries"),
+        }
+    }
+
+    /// A bundle is refused like an export — before any byte of the archive.
+    #[actix_web::test]
+    async fn test_a_bundle_is_validated_before_it_starts() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (status, _) = get!(
+            app,
+            format!("/api/v2/simulations/{id}/export/bundle?format=csv&to_step=3")
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get!(
+            app,
+            format!("/api/v2/simulations/{id}/export/bundle?format=xlsx")
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get!(
+            app,
+            format!(
+                "/api/v2/simulations/{}/export/bundle?format=csv",
+                Uuid::new_v4()
+            )
+        );
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,236 @@
+//! `Content-Encoding` for a streamed v2 export.
+//!
+//! An export can be asked for gzip or zstd through `Accept-Encoding`, and the
+//! choice is negotiated by the client's q-values with actix's own parser: zstd
+//! wins a tie, identity is the answer when nothing else is acceptable, and a
+//! header that does not parse is treated as absent. Either way the response
+//! says `Vary: Accept-Encoding`, so a cache keeps the variants apart.
+//!
+//! # Why not the compression middleware
+//!
+//! The compression happens in the **producer**, on the blocking thread, chunk
+//! by chunk: each encoded chunk goes into the encoder and whatever compressed
+//! bytes it has let go of are what travel over the bounded channel. The
+//! channel therefore still carries the backpressure — a client that stops
+//! reading stops the pricing, compressed or not — and the CPU the compression
+//! costs is spent off the Actix workers, next to the pricing it shrinks.
+//!
+//! The encoder is never flushed mid-stream. A sync flush per chunk would send
+//! data sooner, but a one-row `underlying` step is a few dozen bytes and
+//! flushing each would give most of the ratio back; the encoder emits as its
+//! blocks fill, and the remainder goes out with the trailer.
+//!
+//! Both encodings are deterministic: the gzip header carries no timestamp and
+//! the zstd level is fixed, so a repeated compressed export is byte-identical
+//! exactly like an uncompressed one.
+
+use crate::utils::ChainError;
+use actix_web::HttpRequest;
+use actix_web::http::header::{AcceptEncoding, Encoding, Header};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::Write;
+
+/// The zstd level: the library's default, spelled out so it cannot drift.
+const ZSTD_LEVEL: i32 = 3;
+
+/// The content coding an export is sent in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Coding {
+    /// The encoded bytes as they are.
+    Identity,
+    /// RFC 1952 gzip.
+    Gzip,
+    /// RFC 8878 Zstandard.
+    Zstd,
+}
+
+impl Coding {
+    /// The coding a request's `Accept-Encoding` prefers among those supported.
+    ///
+    /// Identity when the header is absent or malformed, when it names no
+    /// coding but `*`, and when the client accepts nothing the service offers
+    /// — an unencoded body is a better answer to a download than a `406`.
+    #[must_use]
+    pub(crate) fn negotiate(req: &HttpRequest) -> Self {
+        let Ok(accepted) = AcceptEncoding::parse(req) else {
+            return Coding::Identity;
+        };
+        let supported = [Encoding::zstd(), Encoding::gzip(), Encoding::identity()];
+        match accepted.negotiate(supported.iter()) {
+            Some(encoding) if encoding == Encoding::zstd() => Coding::Zstd,
+            Some(encoding) if encoding == Encoding::gzip() => Coding::Gzip,
+            _ => Coding::Identity,
+        }
+    }
+
+    /// The `Content-Encoding` value, or `None` for identity.
+    #[must_use]
+    pub(crate) fn header_value(self) -> Option<&'static str> {
+        match self {
+            Coding::Identity => None,
+            Coding::Gzip => Some("gzip"),
+            Coding::Zstd => Some("zstd"),
+        }
+    }
+}
+
+/// Compresses an export's chunks as they are produced.
+pub(crate) enum Compressor {
+    /// Passes chunks through untouched.
+    Identity,
+    /// A gzip stream into a buffer drained after every chunk.
+    Gzip(GzEncoder<Vec<u8>>),
+    /// A zstd frame into a buffer drained after every chunk.
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl Compressor {
+    /// Starts a compressor for a coding.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the zstd context cannot be created.
+    pub(crate) fn new(coding: Coding) -> Result<Self, ChainError> {
+        Ok(match coding {
+            Coding::Identity => Compressor::Identity,
+            Coding::Gzip => Compressor::Gzip(GzEncoder::new(Vec::new(), Compression::default())),
+            Coding::Zstd => Compressor::Zstd(
+                zstd::stream::write::Encoder::new(Vec::new(), ZSTD_LEVEL).map_err(coding_error)?,
+            ),
+        })
+    }
+
+    /// Feeds a chunk in and returns the compressed bytes it released, which
+    /// may be none.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the encoder fails.
+    pub(crate) fn encode(&mut self, chunk: Vec<u8>) -> Result<Vec<u8>, ChainError> {
+        match self {
+            Compressor::Identity => Ok(chunk),
+            Compressor::Gzip(encoder) => {
+                encoder.write_all(&chunk).map_err(coding_error)?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+            Compressor::Zstd(encoder) => {
+                encoder.write_all(&chunk).map_err(coding_error)?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+        }
+    }
+
+    /// Ends the stream, returning whatever is still buffered and the trailer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the encoder fails.
+    pub(crate) fn finish(self) -> Result<Vec<u8>, ChainError> {
+        match self {
+            Compressor::Identity => Ok(Vec::new()),
+            Compressor::Gzip(encoder) => encoder.finish().map_err(coding_error),
+            Compressor::Zstd(encoder) => encoder.finish().map_err(coding_error),
+        }
+    }
+}
+
+/// Maps a compression failure into the error boundary.
+#[cold]
+fn coding_error(error: std::io::Error) -> ChainError {
+    ChainError::Internal(format!("failed to compress an export chunk: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header;
+    use actix_web::test::TestRequest;
+    use std::io::Read;
+
+    /// The coding a request with this `Accept-Encoding` negotiates.
+    fn negotiated(accept: Option<&str>) -> Coding {
+        let mut request = TestRequest::default();
+        if let Some(accept) = accept {
+            request = request.insert_header((header::ACCEPT_ENCODING, accept));
+        }
+        Coding::negotiate(&request.to_http_request())
+    }
+
+    /// Compresses `chunks` one at a time, as the producer does.
+    fn compressed(coding: Coding, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut compressor = match Compressor::new(coding) {
+            Ok(compressor) => compressor,
+            Err(error) => panic!("the compressor must start: {error}"),
+        };
+        let mut body = Vec::new();
+        for chunk in chunks {
+            match compressor.encode(chunk.to_vec()) {
+                Ok(bytes) => body.extend(bytes),
+                Err(error) => panic!("a chunk must compress: {error}"),
+            }
+        }
+        match compressor.finish() {
+            Ok(bytes) => body.extend(bytes),
+            Err(error) => panic!("the stream must close: {error}"),
+        }
+        body
+    }
+
+    /// The client's q-values decide, zstd wins a tie, and anything the service
+    /// cannot offer — a bare wildcard included — falls back to identity.
+    #[test]
+    fn test_the_coding_follows_the_client_preference() {
+        assert_eq!(negotiated(None), Coding::Identity);
+        assert_eq!(negotiated(Some("gzip")), Coding::Gzip);
+        assert_eq!(negotiated(Some("zstd")), Coding::Zstd);
+        assert_eq!(negotiated(Some("gzip, zstd")), Coding::Zstd);
+        assert_eq!(negotiated(Some("zstd;q=0.5, gzip")), Coding::Gzip);
+        assert_eq!(negotiated(Some("br")), Coding::Identity);
+        assert_eq!(negotiated(Some("gzip;q=0")), Coding::Identity);
+        assert_eq!(negotiated(Some("*")), Coding::Identity);
+        assert_eq!(negotiated(Some(";;;q=nope")), Coding::Identity);
+    }
+
+    /// A chunked gzip stream decodes to the chunks, in order.
+    #[test]
+    fn test_a_gzip_stream_round_trips() {
+        let body = compressed(
+            Coding::Gzip,
+            &[b"step,price\r\n", b"0,5000\r\n", b"1,5001\r\n"],
+        );
+        let mut decoded = String::new();
+        if let Err(error) =
+            flate2::read::GzDecoder::new(body.as_slice()).read_to_string(&mut decoded)
+        {
+            panic!("the stream must decode: {error}");
+        }
+        assert_eq!(decoded, "step,price\r\n0,5000\r\n1,5001\r\n");
+    }
+
+    /// A chunked zstd stream decodes to the chunks, in order, and repeats
+    /// byte for byte.
+    #[test]
+    fn test_a_zstd_stream_round_trips_deterministically() {
+        let chunks: &[&[u8]] = &[b"{\"step\":0}\n", b"{\"step\":1}\n"];
+        let body = compressed(Coding::Zstd, chunks);
+        match zstd::decode_all(body.as_slice()) {
+            Ok(decoded) => assert_eq!(decoded, b"{\"step\":0}\n{\"step\":1}\n"),
+            Err(error) => panic!("the stream must decode: {error}"),
+        }
+        assert_eq!(body, compressed(Coding::Zstd, chunks));
+        assert_eq!(
+            compressed(Coding::Gzip, chunks),
+            compressed(Coding::Gzip, chunks)
+        );
+    }
+
+    /// Identity passes every chunk through untouched and adds no trailer.
+    #[test]
+    fn test_identity_is_a_pass_through() {
+        assert_eq!(compressed(Coding::Identity, &[b"a", b"b"]), b"ab");
+        assert_eq!(Coding::Identity.header_value(), None);
+        assert_eq!(Coding::Gzip.header_value(), Some("gzip"));
+        assert_eq!(Coding::Zstd.header_value(), Some("zstd"));
+    }
+}
@@ -0,0 +1,746 @@
+//! Typed, columnar encodings of a v2 export.
+//!
+//! JSON and CSV render every value as text; a columnar export keeps the types.
+//! A step becomes one Arrow [`RecordBatch`] whose columns are the CSV header's,
+//! in the CSV header's order, so switching format never renames a field:
+//!
+//! | Column | Type |
+//! |--------|------|
+//! | `step` | `UInt64` |
+//! | `simulated_at`, `expires_at` | `Timestamp(ms, UTC)` |
+//! | `symbol`, `labels` | `Dictionary(Int32, Utf8)` |
+//! | prices, volatilities, Greeks, `days_to_expiration`, `strike` | `Float64` |
+//! | `digest` | `Utf8` |
+//!
+//! An absent quote is a null, never `0` or `NaN`. Every number goes through
+//! the same views the text formats use, so a Parquet cell and a CSV field are
+//! the same `f64`.
+//!
+//! # Parquet
+//!
+//! [`ParquetSink`] accumulates batches into row groups of **whole steps** and
+//! closes one at the first step boundary past [`WINDOW_ROWS`]. What
+//! a closed row group wrote is drained and streamed at once, so memory is
+//! bounded by one row group however long the range — the same guarantee the
+//! CSV export gives, in the same bounded channel. The window is counted in
+//! rows rather than steps because the datasets differ by four orders of
+//! magnitude per step: a window of steps sized for `option_chains` would slice
+//! `underlying` into groups of a few dozen rows.
+//!
+//! # Arrow IPC
+//!
+//! [`ArrowSink`] writes the IPC **streaming** format: the schema message up
+//! front, then one batch per window of steps — [`ARROW_WINDOW_STEPS`] steps, or
+//! fewer once the window passes [`WINDOW_ROWS`] rows — then the end-of-stream
+//! marker. The schema is fixed per dataset, so a consumer can read it before
+//! the first row is priced. Each window is its own batch with its own
+//! dictionaries, which the streaming format allows and `pyarrow` and
+//! `arrow-rs` readers accept.
+//!
+//! The bytes of either format are a function of the batches, the writer
+//! settings pinned here and the `parquet` and `arrow` releases, so two exports
+//! of the same range by the same build are byte-identical.
+
+use crate::api::rest::export::{Dataset, StepChains};
+use crate::api::rest::filter::{Projection, RowFilter};
+use crate::domain::factors::FactorRow;
+use crate::utils::ChainError;
+use arrow_array::builder::{
+    Float64Builder, StringBuilder, StringDictionaryBuilder, TimestampMillisecondBuilder,
+    UInt64Builder,
+};
+use arrow_array::types::Int32Type;
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::sync::Arc;
+
+/// The row count at which a Parquet row group or an Arrow batch closes, at the
+/// next step boundary.
+///
+/// Large enough that a reader's per-group overhead is negligible, small enough
+/// that the writer's buffered column data stays in the low megabytes.
+pub(super) const WINDOW_ROWS: usize = 65_536;
+
+/// The most steps one Arrow batch spans.
+///
+/// A stream is consumed as it arrives, so a factor dataset — one row per step
+/// — must not wait for [`WINDOW_ROWS`] steps before its first batch. Sixty-four
+/// is the export's warehouse window, so a stored window becomes one batch.
+pub(super) const ARROW_WINDOW_STEPS: usize = 64;
+
+/// The zone every timestamp column is declared in.
+const UTC: &str = "UTC";
+
+/// The Arrow type of one column of a dataset.
+#[must_use]
+fn column_type(name: &str) -> DataType {
+    match name {
+        "step" => DataType::UInt64,
+        "simulated_at" | "expires_at" => {
+            DataType::Timestamp(TimeUnit::Millisecond, Some(UTC.into()))
+        }
+        "symbol" | "labels" => {
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+        }
+        "digest" => DataType::Utf8,
+        _ => DataType::Float64,
+    }
+}
+
+/// Whether a column may hold nulls: only the quotes and Greeks, which
+/// upstream may leave unquoted.
+#[must_use]
+fn nullable(name: &str) -> bool {
+    matches!(
+        name,
+        "call_bid"
+            | "call_ask"
+            | "call_mid"
+            | "call_delta"
+            | "put_bid"
+            | "put_ask"
+            | "put_mid"
+            | "put_delta"
+            | "gamma"
+    )
+}
+
+/// The Arrow schema of a dataset, with a trailing `digest` column when asked.
+#[must_use]
+pub(super) fn schema(dataset: Dataset, digest: bool) -> SchemaRef {
+    let mut fields: Vec<Field> = dataset
+        .header()
+        .iter()
+        .map(|name| Field::new(*name, column_type(name), nullable(name)))
+        .collect();
+    if digest {
+        fields.push(Field::new("digest", column_type("digest"), false));
+    }
+    Arc::new(Schema::new(fields))
+}
+
+/// The schema of a dataset narrowed to a projection's columns, the `digest`
+/// column kept when asked.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Internal`] if the projection names a column the
+/// dataset does not have, which parsing rules out.
+pub(super) fn projected_schema(
+    dataset: Dataset,
+    digest: bool,
+    projection: &Projection,
+) -> Result<SchemaRef, ChainError> {
+    let mut indices = projection.indices(dataset);
+    if digest {
+        indices.push(dataset.header().len());
+    }
+    schema(dataset, digest)
+        .project(&indices)
+        .map(Arc::new)
+        .map_err(|e| ChainError::Internal(format!("failed to project an export schema: {e}")))
+}
+
+/// An instant as the milliseconds the timestamp columns store.
+#[must_use]
+#[inline]
+fn millis(instant: DateTime<Utc>) -> i64 {
+    instant.timestamp_millis()
+}
+
+/// The column builders of one batch, in schema order.
+///
+/// One struct for every dataset: the factor datasets simply leave the chain
+/// columns untouched and [`Columns::finish`] takes only the columns the schema
+/// names — a projected schema included, which is why a batch starts from fresh
+/// builders rather than leaving the unnamed ones to grow.
+struct Columns {
+    step: UInt64Builder,
+    simulated_at: TimestampMillisecondBuilder,
+    symbol: StringDictionaryBuilder<Int32Type>,
+    price: Float64Builder,
+    base_volatility: Float64Builder,
+    expires_at: TimestampMillisecondBuilder,
+    labels: StringDictionaryBuilder<Int32Type>,
+    days_to_expiration: Float64Builder,
+    strike: Float64Builder,
+    implied_volatility: Float64Builder,
+    call_bid: Float64Builder,
+    call_ask: Float64Builder,
+    call_mid: Float64Builder,
+    call_delta: Float64Builder,
+    put_bid: Float64Builder,
+    put_ask: Float64Builder,
+    put_mid: Float64Builder,
+    put_delta: Float64Builder,
+    gamma: Float64Builder,
+    digest: StringBuilder,
+}
+
+impl Columns {
+    fn new() -> Self {
+        Self {
+            step: UInt64Builder::new(),
+            simulated_at: TimestampMillisecondBuilder::new().with_timezone(UTC),
+            symbol: StringDictionaryBuilder::new(),
+            price: Float64Builder::new(),
+            base_volatility: Float64Builder::new(),
+            expires_at: TimestampMillisecondBuilder::new().with_timezone(UTC),
+            labels: StringDictionaryBuilder::new(),
+            days_to_expiration: Float64Builder::new(),
+            strike: Float64Builder::new(),
+            implied_volatility: Float64Builder::new(),
+            call_bid: Float64Builder::new(),
+            call_ask: Float64Builder::new(),
+            call_mid: Float64Builder::new(),
+            call_delta: Float64Builder::new(),
+            put_bid: Float64Builder::new(),
+            put_ask: Float64Builder::new(),
+            put_mid: Float64Builder::new(),
+            put_delta: Float64Builder::new(),
+            gamma: Float64Builder::new(),
+            digest: StringBuilder::new(),
+        }
+    }
+
+    /// The columns every row starts with.
+    fn push_key(&mut self, step: u64, simulated_at: i64, symbol: &str, digest: Option<&str>) {
+        self.step.append_value(step);
+        self.simulated_at.append_value(simulated_at);
+        self.symbol.append_value(symbol);
+        if let Some(digest) = digest {
+            self.digest.append_value(digest);
+        }
+    }
+
+    /// Appends every row one step contributes that `filter` keeps, and
+    /// returns how many.
+    fn push_step(
+        &mut self,
+        dataset: Dataset,
+        symbol: &str,
+        row: &FactorRow,
+        chains: Option<StepChains<'_>>,
+        filter: &RowFilter,
+        digest: Option<&str>,
+    ) -> Result<usize, ChainError> {
+        let step = u64::try_from(row.step).map_err(|_| {
+            ChainError::Internal(format!("step {} does not fit a column", row.step))
+        })?;
+        let simulated_at = millis(row.simulated_at);
+
+        match dataset {
+            Dataset::Underlying => {
+                self.push_key(step, simulated_at, symbol, digest);
+                self.price.append_value(row.spot.to_f64());
+                Ok(1)
+            }
+            Dataset::Volatility => {
+                self.push_key(step, simulated_at, symbol, digest);
+                self.base_volatility
+                    .append_value(row.base_volatility.to_f64());
+                Ok(1)
+            }
+            Dataset::OptionChains => {
+                let spot = row.spot.to_f64();
+                let mut rows: usize = 0;
+                for expiration in chains
+                    .into_iter()
+                    .flat_map(|chains| filter.expirations(chains))
+                {
+                    let expires_at = millis(expiration.expires_at);
+                    let labels = expiration.labels.join("|");
+                    for quote in filter.quotes(expiration, spot) {
+                        self.push_key(step, simulated_at, symbol, digest);
+                        self.expires_at.append_value(expires_at);
+                        self.labels.append_value(&labels);
+                        self.days_to_expiration
+                            .append_value(expiration.days_to_expiration);
+                        self.strike.append_value(quote.strike);
+                        self.implied_volatility
+                            .append_value(quote.implied_volatility);
+                        self.call_bid.append_option(quote.call_bid);
+                        self.call_ask.append_option(quote.call_ask);
+                        self.call_mid.append_option(quote.call_mid);
+                        self.call_delta.append_option(quote.call_delta);
+                        self.put_bid.append_option(quote.put_bid);
+                        self.put_ask.append_option(quote.put_ask);
+                        self.put_mid.append_option(quote.put_mid);
+                        self.put_delta.append_option(quote.put_delta);
+                        self.gamma.append_option(quote.gamma);
+                        rows = rows.saturating_add(1);
+                    }
+                }
+                Ok(rows)
+            }
+        }
+    }
+
+    /// Everything appended so far as one batch of `schema`, leaving the
+    /// builders empty for the next.
+    fn batch(&mut self, schema: &SchemaRef) -> Result<RecordBatch, ChainError> {
+        let mut filled = std::mem::replace(self, Columns::new());
+        RecordBatch::try_new(Arc::clone(schema), filled.finish(schema)?)
+            .map_err(|e| ChainError::Internal(format!("failed to assemble an export batch: {e}")))
+    }
+
+    /// Finishes the columns `schema` names, in its order.
+    fn finish(&mut self, schema: &SchemaRef) -> Result<Vec<ArrayRef>, ChainError> {
+        schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let column: ArrayRef = match field.name().as_str() {
+                    "step" => Arc::new(self.step.finish()),
+                    "simulated_at" => Arc::new(self.simulated_at.finish()),
+                    "symbol" => Arc::new(self.symbol.finish()),
+                    "price" => Arc::new(self.price.finish()),
+                    "base_volatility" => Arc::new(self.base_volatility.finish()),
+                    "expires_at" => Arc::new(self.expires_at.finish()),
+                    "labels" => Arc::new(self.labels.finish()),
+                    "days_to_expiration" => Arc::new(self.days_to_expiration.finish()),
+                    "strike" => Arc::new(self.strike.finish()),
+                    "implied_volatility" => Arc::new(self.implied_volatility.finish()),
+                    "call_bid" => Arc::new(self.call_bid.finish()),
+                    "call_ask" => Arc::new(self.call_ask.finish()),
+                    "call_mid" => Arc::new(self.call_mid.finish()),
+                    "call_delta" => Arc::new(self.call_delta.finish()),
+                    "put_bid" => Arc::new(self.put_bid.finish()),
+                    "put_ask" => Arc::new(self.put_ask.finish()),
+                    "put_mid" => Arc::new(self.put_mid.finish()),
+                    "put_delta" => Arc::new(self.put_delta.finish()),
+                    "gamma" => Arc::new(self.gamma.finish()),
+                    "digest" => Arc::new(self.digest.finish()),
+                    other => {
+                        return Err(ChainError::Internal(format!(
+                            "no column builder for export column {other}"
+                        )));
+                    }
+                };
+                Ok(column)
+            })
+            .collect()
+    }
+}
+
+/// The rows one step contributes, as a batch of `schema`.
+///
+/// The columnar twin of the text formats' row builders, reading the same
+/// views. A step with no rows — an `option_chains` step with no live chain —
+/// is an empty batch.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Internal`] if the columns do not match the schema,
+/// which would be a bug in this module rather than in the request.
+pub(super) fn step_batch(
+    schema: &SchemaRef,
+    dataset: Dataset,
+    symbol: &str,
+    row: &FactorRow,
+    chains: Option<StepChains<'_>>,
+    filter: &RowFilter,
+    digest: Option<&str>,
+) -> Result<RecordBatch, ChainError> {
+    let mut columns = Columns::new();
+    columns.push_step(dataset, symbol, row, chains, filter, digest)?;
+    columns.batch(schema)
+}
+
+/// Maps a Parquet encoding failure into the error boundary.
+#[cold]
+fn parquet_error(error: parquet::errors::ParquetError) -> ChainError {
+    ChainError::Internal(format!("failed to encode a Parquet export: {error}"))
+}
+
+/// A Parquet file written a row group at a time into memory and drained as it
+/// goes.
+///
+/// The writer buffers its output, so a drain returns whatever has reached the
+/// buffer so far rather than exactly one row group. Concatenating every drain
+/// and the footer [`ParquetSink::finish`] returns is the file, byte for byte.
+pub(super) struct ParquetSink {
+    writer: Option<ArrowWriter<Vec<u8>>>,
+}
+
+impl ParquetSink {
+    /// Opens a file of `schema`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the writer rejects the schema.
+    pub(super) fn new(schema: SchemaRef) -> Result<Self, ChainError> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .build();
+        let writer =
+            ArrowWriter::try_new(Vec::new(), schema, Some(properties)).map_err(parquet_error)?;
+        Ok(Self {
+            writer: Some(writer),
+        })
+    }
+
+    /// Appends one step's batch, closing the row group when it is full.
+    ///
+    /// Returns the bytes ready to send, which is empty until a row group
+    /// closes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the file was already finished or
+    /// the batch cannot be encoded.
+    pub(super) fn write(&mut self, batch: &RecordBatch) -> Result<Vec<u8>, ChainError> {
+        let writer = self.writer.as_mut().ok_or_else(|| {
+            ChainError::Internal("the Parquet export was already finished".to_string())
+        })?;
+        writer.write(batch).map_err(parquet_error)?;
+        if writer.in_progress_rows() < WINDOW_ROWS {
+            return Ok(Vec::new());
+        }
+        writer.flush().map_err(parquet_error)?;
+        Ok(std::mem::take(writer.inner_mut()))
+    }
+
+    /// Closes the last row group and writes the footer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the file was already finished or
+    /// the footer cannot be written.
+    pub(super) fn finish(&mut self) -> Result<Vec<u8>, ChainError> {
+        let writer = self.writer.take().ok_or_else(|| {
+            ChainError::Internal("the Parquet export was already finished".to_string())
+        })?;
+        writer.into_inner().map_err(parquet_error)
+    }
+}
+
+/// Maps an Arrow IPC encoding failure into the error boundary.
+#[cold]
+fn arrow_error(error: arrow_schema::ArrowError) -> ChainError {
+    ChainError::Internal(format!("failed to encode an Arrow export: {error}"))
+}
+
+/// An Arrow IPC stream assembled a window of steps at a time.
+///
+/// Steps are appended to one set of builders and become a batch when the
+/// window closes, so the stream carries a few large batches rather than one
+/// per step.
+pub(super) struct ArrowSink {
+    writer: Option<StreamWriter<Vec<u8>>>,
+    schema: SchemaRef,
+    dataset: Dataset,
+    columns: Columns,
+    steps: usize,
+    rows: usize,
+}
+
+impl ArrowSink {
+    /// Opens a stream of `schema` and writes its schema message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the writer rejects the schema.
+    pub(super) fn new(schema: SchemaRef, dataset: Dataset) -> Result<Self, ChainError> {
+        let writer = StreamWriter::try_new(Vec::new(), &schema).map_err(arrow_error)?;
+        Ok(Self {
+            writer: Some(writer),
+            schema,
+            dataset,
+            columns: Columns::new(),
+            steps: 0,
+            rows: 0,
+        })
+    }
+
+    /// Takes whatever the stream has written so far.
+    pub(super) fn drain(&mut self) -> Vec<u8> {
+        self.writer
+            .as_mut()
+            .map(|writer| std::mem::take(writer.get_mut()))
+            .unwrap_or_default()
+    }
+
+    /// Appends one step, writing the window's batch when it is full.
+    ///
+    /// Returns the bytes ready to send, which is empty until a window closes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the stream was already finished or
+    /// the batch cannot be encoded.
+    pub(super) fn push(
+        &mut self,
+        symbol: &str,
+        row: &FactorRow,
+        chains: Option<StepChains<'_>>,
+        filter: &RowFilter,
+        digest: Option<&str>,
+    ) -> Result<Vec<u8>, ChainError> {
+        let added = self
+            .columns
+            .push_step(self.dataset, symbol, row, chains, filter, digest)?;
+        self.steps = self.steps.saturating_add(1);
+        self.rows = self.rows.saturating_add(added);
+        if self.steps < ARROW_WINDOW_STEPS && self.rows < WINDOW_ROWS {
+            return Ok(Vec::new());
+        }
+        self.close_window()?;
+        Ok(self.drain())
+    }
+
+    /// Writes the open window's batch, if it has any steps.
+    fn close_window(&mut self) -> Result<(), ChainError> {
+        if self.steps == 0 {
+            return Ok(());
+        }
+        let batch = self.columns.batch(&self.schema)?;
+        self.steps = 0;
+        self.rows = 0;
+        let writer = self.writer.as_mut().ok_or_else(|| {
+            ChainError::Internal("the Arrow export was already finished".to_string())
+        })?;
+        writer.write(&batch).map_err(arrow_error)
+    }
+
+    /// Writes the last window and the end-of-stream marker.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] if the stream was already finished or
+    /// the marker cannot be written.
+    pub(super) fn finish(&mut self) -> Result<Vec<u8>, ChainError> {
+        self.close_window()?;
+        let mut writer = self.writer.take().ok_or_else(|| {
+            ChainError::Internal("the Arrow export was already finished".to_string())
+        })?;
+        writer.finish().map_err(arrow_error)?;
+        writer.into_inner().map_err(arrow_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::web::Bytes;
+    use arrow_array::Array;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, TimestampMillisecondType};
+    use chrono::TimeZone;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use positive::pos_or_panic;
+
+    fn factor_row(step: usize) -> FactorRow {
+        let simulated_at = match Utc.with_ymd_and_hms(2026, 1, 5, 14, 30, 0).single() {
+            Some(instant) => instant + chrono::Duration::days(step as i64),
+            None => panic!("the test instant must be valid"),
+        };
+        FactorRow {
+            step,
+            simulated_at,
+            spot: pos_or_panic!(5000.0 + step as f64),
+            base_volatility: pos_or_panic!(0.18),
+        }
+    }
+
+    /// Writes `steps` underlying rows and returns the whole file.
+    fn underlying_file(steps: usize) -> Vec<u8> {
+        let schema = schema(Dataset::Underlying, false);
+        let mut sink = match ParquetSink::new(Arc::clone(&schema)) {
+            Ok(sink) => sink,
+            Err(error) => panic!("the sink must open: {error}"),
+        };
+        let mut file = Vec::new();
+        for step in 0..steps {
+            let batch = match step_batch(
+                &schema,
+                Dataset::Underlying,
+                "SPX",
+                &factor_row(step),
+                None,
+                &RowFilter::default(),
+                None,
+            ) {
+                Ok(batch) => batch,
+                Err(error) => panic!("the batch must build: {error}"),
+            };
+            match sink.write(&batch) {
+                Ok(chunk) => file.extend(chunk),
+                Err(error) => panic!("the batch must encode: {error}"),
+            }
+        }
+        match sink.finish() {
+            Ok(footer) => file.extend(footer),
+            Err(error) => panic!("the file must close: {error}"),
+        }
+        file
+    }
+
+    /// The columns carry the CSV header's names and the documented types.
+    #[test]
+    fn test_the_schema_follows_the_csv_header_with_typed_columns() {
+        let schema = schema(Dataset::OptionChains, true);
+        let names: Vec<&str> = schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect();
+        let mut expected = Dataset::OptionChains.header().to_vec();
+        expected.push("digest");
+
+        assert_eq!(names, expected);
+        match schema.field_with_name("labels") {
+            Ok(field) => assert!(matches!(field.data_type(), DataType::Dictionary(_, _))),
+            Err(error) => panic!("labels must be a column: {error}"),
+        }
+        match schema.field_with_name("call_bid") {
+            Ok(field) => assert!(field.is_nullable()),
+            Err(error) => panic!("call_bid must be a column: {error}"),
+        }
+        match schema.field_with_name("strike") {
+            Ok(field) => assert!(!field.is_nullable()),
+            Err(error) => panic!("strike must be a column: {error}"),
+        }
+    }
+
+    /// A written file reads back with its values and its types intact.
+    #[test]
+    fn test_a_parquet_file_round_trips() {
+        let file = underlying_file(3);
+        let reader = match ParquetRecordBatchReaderBuilder::try_new(Bytes::from(file))
+            .and_then(|builder| builder.build())
+        {
+            Ok(reader) => reader,
+            Err(error) => panic!("the file must open: {error}"),
+        };
+        let batches: Vec<RecordBatch> = match reader.collect::<Result<_, _>>() {
+            Ok(batches) => batches,
+            Err(error) => panic!("the file must read: {error}"),
+        };
+
+        let rows: usize = batches.iter().map(RecordBatch::num_rows).sum();
+        assert_eq!(rows, 3);
+        match batches.first() {
+            Some(batch) => {
+                let prices = batch.column(3).as_primitive::<Float64Type>();
+                assert_eq!(prices.value(2), 5002.0);
+                let instants = batch.column(1).as_primitive::<TimestampMillisecondType>();
+                assert_eq!(instants.value(0), millis(factor_row(0).simulated_at));
+                assert_eq!(instants.timezone(), Some(UTC));
+                assert_eq!(batch.column(2).null_count(), 0);
+            }
+            None => panic!("the file must carry a batch"),
+        }
+    }
+
+    /// Row groups close at step boundaries once they are full, and each is
+    /// streamed as it closes rather than held to the end.
+    #[test]
+    fn test_row_groups_are_flushed_as_they_fill() {
+        let schema = schema(Dataset::Underlying, false);
+        let mut sink = match ParquetSink::new(Arc::clone(&schema)) {
+            Ok(sink) => sink,
+            Err(error) => panic!("the sink must open: {error}"),
+        };
+        let mut streamed_early = false;
+        for step in 0..=WINDOW_ROWS {
+            let batch = match step_batch(
+                &schema,
+                Dataset::Underlying,
+                "SPX",
+                &factor_row(step),
+                None,
+                &RowFilter::default(),
+                None,
+            ) {
+                Ok(batch) => batch,
+                Err(error) => panic!("the batch must build: {error}"),
+            };
+            match sink.write(&batch) {
+                Ok(chunk) => streamed_early |= !chunk.is_empty(),
+                Err(error) => panic!("the batch must encode: {error}"),
+            }
+        }
+
+        assert!(streamed_early, "a full row group must be streamed");
+    }
+
+    /// The same rows always encode to the same bytes.
+    #[test]
+    fn test_a_parquet_file_is_deterministic() {
+        assert_eq!(underlying_file(5), underlying_file(5));
+    }
+
+    /// Writes `steps` underlying rows as an Arrow stream, returning the schema
+    /// message and the rest separately.
+    fn underlying_stream(steps: usize) -> (Vec<u8>, Vec<u8>) {
+        let mut sink = match ArrowSink::new(schema(Dataset::Underlying, true), Dataset::Underlying)
+        {
+            Ok(sink) => sink,
+            Err(error) => panic!("the stream must open: {error}"),
+        };
+        let head = sink.drain();
+        let mut rest = Vec::new();
+        for step in 0..steps {
+            match sink.push(
+                "SPX",
+                &factor_row(step),
+                None,
+                &RowFilter::default(),
+                Some("ab"),
+            ) {
+                Ok(chunk) => rest.extend(chunk),
+                Err(error) => panic!("the step must encode: {error}"),
+            }
+        }
+        match sink.finish() {
+            Ok(tail) => rest.extend(tail),
+            Err(error) => panic!("the stream must close: {error}"),
+        }
+        (head, rest)
+    }
+
+    /// An Arrow stream opens with its schema, carries one batch per window of
+    /// steps, and reads back whole.
+    #[test]
+    fn test_an_arrow_stream_carries_a_batch_per_window() {
+        use arrow_ipc::reader::StreamReader;
+
+        let steps = ARROW_WINDOW_STEPS * 2 + 2;
+        let (head, rest) = underlying_stream(steps);
+        assert!(!head.is_empty(), "the schema must be sent before any step");
+
+        let stream = [head, rest].concat();
+        let reader = match StreamReader::try_new(stream.as_slice(), None) {
+            Ok(reader) => reader,
+            Err(error) => panic!("the stream must open: {error}"),
+        };
+        assert_eq!(reader.schema(), schema(Dataset::Underlying, true));
+        let batches: Vec<RecordBatch> = match reader.collect::<Result<_, _>>() {
+            Ok(batches) => batches,
+            Err(error) => panic!("the stream must read: {error}"),
+        };
+
+        let sizes: Vec<usize> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(sizes, vec![ARROW_WINDOW_STEPS, ARROW_WINDOW_STEPS, 2]);
+        match batches.last() {
+            Some(batch) => {
+                let prices = batch.column(3).as_primitive::<Float64Type>();
+                assert_eq!(prices.value(1), 5000.0 + (steps - 1) as f64);
+                assert_eq!(batch.column(4).as_string::<i32>().value(0), "ab");
+            }
+            None => panic!("the stream must carry a batch"),
+        }
+    }
+
+    /// The same steps always encode to the same stream.
+    #[test]
+    fn test_an_arrow_stream_is_deterministic() {
+        assert_eq!(underlying_stream(70), underlying_stream(70));
+    }
+}
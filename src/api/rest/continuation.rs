@@ -0,0 +1,338 @@
+//! Continuation tokens: resuming a v2 export where a dropped connection left it.
+//!
+//! A long NDJSON export carries, every [`ROWS_PER_TOKEN`] rows, one extra line
+//! of its own:
+//!
+//! ```text
+//! {"continuation":"v2.3f0c….2.9a41c07d2be35f60.417.1.3"}
+//! ```
+//!
+//! The token names the simulation, the snapshot generation, the query and the
+//! position of the **next** row — its step, the ordinal of its expiration
+//! among the ones the export keeps, and the ordinal of its strike within that
+//! expiration. A client whose download broke off sends the last token it
+//! received back as `after=`, with the query otherwise unchanged, and receives
+//! exactly the bytes that followed that line in the uninterrupted export: the
+//! rows from the position on, with their own token lines in the same places.
+//!
+//! The query is carried as a fingerprint of its normalized form — dataset,
+//! format, digest, the **resolved** step range and stride, and the parsed
+//! filters and projection. The ordinals are counted after filtering, so a
+//! position only means something in the export that produced it; a resume
+//! whose query differs is refused rather than answered with rows that do not
+//! continue anything. Resolving the range first is what catches an open-ended
+//! `to_step` that would now end somewhere else, while letting a client spell
+//! the same query differently — `to_step` explicit or not, columns reordered.
+//!
+//! Determinism is what makes this safe. The same query over the same
+//! simulation renders the same rows in the same order, so a position is a
+//! coordinate in a tape that does not move. Ordinals rather than strikes or
+//! instants keep the coordinate exact — no float goes through the token — and
+//! counting them after the filters keeps it meaningful for a filtered export.
+//!
+//! The cadence is counted from the last token, not from the start of the
+//! export. A resumed export starts exactly where a token was issued, where the
+//! count was zero, so its token lines fall on the same rows as the original's
+//! without the token having to carry a row count.
+//!
+//! # Why only NDJSON
+//!
+//! It is the one format whose suffix is a valid document on its own and that
+//! can carry a line the rows do not: a JSON array, a CSV header, a Parquet
+//! footer and an Arrow schema all belong to the whole file. `after=` is refused
+//! for the others rather than answered with bytes that would not splice, and
+//! every export says which kind it is in [`RESUMABLE_HEADER`], so a client
+//! learns before a connection drops whether it will be able to resume.
+
+/// The response header telling a client whether an export can be resumed with
+/// `after=`: `true` for NDJSON, `false` for every other format.
+pub(crate) const RESUMABLE_HEADER: &str = "X-OCS-Resumable";
+
+use crate::infrastructure::CURRENT_SNAPSHOT_GENERATION;
+use crate::utils::ChainError;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// How many rows go out between two tokens.
+///
+/// A token line costs about a hundred bytes, so this keeps the overhead under
+/// one percent for the smallest rows while bounding what a reconnect repeats.
+pub(crate) const ROWS_PER_TOKEN: usize = 256;
+
+/// The version prefix every token this build issues starts with.
+const TOKEN_VERSION: &str = "v2";
+
+/// The fingerprint of an export's normalized query, as a token carries it.
+///
+/// The first eight bytes of the SHA-256 of `canonical`. Nothing secret rides
+/// on it — it tells a resume from a different query, not a forgery from a
+/// token.
+#[must_use]
+pub(crate) fn query_fingerprint(canonical: &str) -> u64 {
+    let digest = Sha256::digest(canonical.as_bytes());
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(head)
+}
+
+/// Where a row sits in an export: its step, then the ordinal of its expiration
+/// and of its strike among the ones the export keeps.
+///
+/// Ordered the way the rows are written, so "at or after a position" is a
+/// plain comparison. A per-step dataset has one row per step at `(step, 0, 0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Position {
+    pub(crate) step: usize,
+    pub(crate) expiration: usize,
+    pub(crate) strike: usize,
+}
+
+impl Position {
+    /// The position right after this one's row, within the same expiration.
+    ///
+    /// Past the expiration's last strike it still sorts before every row that
+    /// follows, which is all a resume needs of it.
+    #[must_use]
+    fn next(self) -> Self {
+        Self {
+            strike: self.strike.saturating_add(1),
+            ..self
+        }
+    }
+}
+
+/// A parsed `after=` token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ContinuationToken {
+    pub(crate) simulation: Uuid,
+    pub(crate) generation: u64,
+    /// The [`query_fingerprint`] of the export it was issued in.
+    pub(crate) query: u64,
+    pub(crate) position: Position,
+}
+
+impl ContinuationToken {
+    /// The token for `position` in the export of `simulation` whose query
+    /// fingerprints to `query`, as this build issues it.
+    #[must_use]
+    pub(crate) fn new(simulation: Uuid, query: u64, position: Position) -> Self {
+        Self {
+            simulation,
+            generation: CURRENT_SNAPSHOT_GENERATION,
+            query,
+            position,
+        }
+    }
+
+    /// The token's wire form: dot-separated, URL-safe, and opaque to clients.
+    #[must_use]
+    pub(crate) fn encode(&self) -> String {
+        format!(
+            "{TOKEN_VERSION}.{}.{}.{:016x}.{}.{}.{}",
+            self.simulation.simple(),
+            self.generation,
+            self.query,
+            self.position.step,
+            self.position.expiration,
+            self.position.strike
+        )
+    }
+
+    /// Parses a token and checks it was issued for `simulation`, in an
+    /// export whose query fingerprints to `query`, by a build writing the same
+    /// snapshot generation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `after` when the token does
+    /// not parse, names another simulation, comes from another generation —
+    /// whose tape this build no longer serves — or from another query.
+    pub(crate) fn parse(token: &str, simulation: Uuid, query: u64) -> Result<Self, ChainError> {
+        let malformed = || ChainError::Validation {
+            field: "after".to_string(),
+            reason: format!("is not a continuation token this service issued, got {token:?}"),
+        };
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let [
+            version,
+            id,
+            generation,
+            fingerprint,
+            step,
+            expiration,
+            strike,
+        ] = parts.as_slice()
+        else {
+            return Err(malformed());
+        };
+        if *version != TOKEN_VERSION {
+            return Err(malformed());
+        }
+        let number = |part: &str| part.parse::<usize>().map_err(|_| malformed());
+        let parsed = Self {
+            simulation: Uuid::parse_str(id).map_err(|_| malformed())?,
+            generation: generation.parse().map_err(|_| malformed())?,
+            query: u64::from_str_radix(fingerprint, 16).map_err(|_| malformed())?,
+            position: Position {
+                step: number(step)?,
+                expiration: number(expiration)?,
+                strike: number(strike)?,
+            },
+        };
+
+        if parsed.simulation != simulation {
+            return Err(ChainError::Validation {
+                field: "after".to_string(),
+                reason: format!("was issued for simulation {}", parsed.simulation),
+            });
+        }
+        if parsed.generation != CURRENT_SNAPSHOT_GENERATION {
+            return Err(ChainError::Validation {
+                field: "after".to_string(),
+                reason: format!(
+                    "was issued against snapshot generation {}, this service serves {CURRENT_SNAPSHOT_GENERATION}",
+                    parsed.generation
+                ),
+            });
+        }
+        if parsed.query != query {
+            return Err(ChainError::Validation {
+                field: "after".to_string(),
+                reason: "was issued for a different query; a resume must repeat the dataset, \
+                         step range, filters and columns of the export it continues"
+                    .to_string(),
+            });
+        }
+        Ok(parsed)
+    }
+}
+
+/// Decides which rows an export sends and where its tokens go.
+#[derive(Debug, Clone)]
+pub(crate) struct Continuation {
+    simulation: Uuid,
+    query: u64,
+    /// The first position a resumed export sends; `None` sends everything.
+    after: Option<Position>,
+    /// Rows sent since the last token, or since the export (re)started.
+    since: usize,
+}
+
+impl Continuation {
+    /// Issues tokens for the export of `simulation` whose query fingerprints
+    /// to `query`, resuming at `after`.
+    #[must_use]
+    pub(crate) fn new(simulation: Uuid, query: u64, after: Option<Position>) -> Self {
+        Self {
+            simulation,
+            query,
+            after,
+            since: 0,
+        }
+    }
+
+    /// Whether the row at `position` is sent: everything at or after the
+    /// resume point.
+    #[must_use]
+    pub(crate) fn keeps(&self, position: Position) -> bool {
+        self.after.is_none_or(|after| position >= after)
+    }
+
+    /// Counts a row that was sent, returning the token line due after it.
+    pub(crate) fn sent(&mut self, position: Position) -> Option<serde_json::Value> {
+        self.since = self.since.saturating_add(1);
+        if self.since < ROWS_PER_TOKEN {
+            return None;
+        }
+        self.since = 0;
+        let token = ContinuationToken::new(self.simulation, self.query, position.next());
+        Some(serde_json::json!({ "continuation": token.encode() }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(step: usize, expiration: usize, strike: usize) -> Position {
+        Position {
+            step,
+            expiration,
+            strike,
+        }
+    }
+
+    /// A token survives its own wire form, and names the row after the one it
+    /// follows.
+    #[test]
+    fn test_a_token_round_trips() {
+        let simulation = Uuid::new_v4();
+        let query = query_fingerprint("dataset=option_chains");
+        let token = ContinuationToken::new(simulation, query, at(417, 1, 3));
+        match ContinuationToken::parse(&token.encode(), simulation, query) {
+            Ok(parsed) => assert_eq!(parsed, token),
+            Err(error) => panic!("an issued token must parse: {error}"),
+        }
+        assert!(at(4, 0, 9) < at(4, 1, 0));
+        assert!(at(4, 2, 0) < at(5, 0, 0));
+        assert_eq!(at(4, 1, 3).next(), at(4, 1, 4));
+    }
+
+    /// Garbage, a token from before the query was fingerprinted, and another
+    /// simulation's, generation's or query's token are all refused by name.
+    #[test]
+    fn test_a_foreign_or_malformed_token_is_refused() {
+        let simulation = Uuid::new_v4();
+        let query = query_fingerprint("dataset=underlying");
+        let foreign = ContinuationToken::new(Uuid::new_v4(), query, at(1, 0, 0)).encode();
+        let stale = ContinuationToken {
+            generation: CURRENT_SNAPSHOT_GENERATION + 1,
+            ..ContinuationToken::new(simulation, query, at(1, 0, 0))
+        }
+        .encode();
+        let requeried =
+            ContinuationToken::new(simulation, query_fingerprint("dataset=chains"), at(1, 0, 0))
+                .encode();
+        let unversioned = format!(
+            "v1.{}.{CURRENT_SNAPSHOT_GENERATION}.1.0.0",
+            simulation.simple()
+        );
+
+        for token in [
+            "",
+            "yesterday",
+            "v0.0.0.0.0.0.0",
+            "v2.not-a-uuid.2.00.1.0.0",
+            "v2.00000000000000000000000000000000.2.00.-1.0.0",
+            "v2.00000000000000000000000000000000.2.not-hex.1.0.0",
+            unversioned.as_str(),
+            foreign.as_str(),
+            stale.as_str(),
+            requeried.as_str(),
+        ] {
+            match ContinuationToken::parse(token, simulation, query) {
+                Err(ChainError::Validation { field, .. }) => assert_eq!(field, "after"),
+                other => panic!("{token:?} must be refused, got {other:?}"),
+            }
+        }
+    }
+
+    /// A token goes out after every `ROWS_PER_TOKEN` rows, and only the rows
+    /// at or after the resume point are kept.
+    #[test]
+    fn test_tokens_follow_the_cadence_and_resume_in_place() {
+        let simulation = Uuid::new_v4();
+        let mut continuation = Continuation::new(simulation, 0, None);
+        let issued: Vec<usize> = (0..ROWS_PER_TOKEN * 2)
+            .filter(|&row| continuation.sent(at(row, 0, 0)).is_some())
+            .collect();
+        assert_eq!(issued, vec![ROWS_PER_TOKEN - 1, ROWS_PER_TOKEN * 2 - 1]);
+
+        let resumed = Continuation::new(simulation, 0, Some(at(3, 1, 0)));
+        assert!(!resumed.keeps(at(2, 5, 5)));
+        assert!(!resumed.keeps(at(3, 0, 7)));
+        assert!(resumed.keeps(at(3, 1, 0)));
+        assert!(resumed.keeps(at(4, 0, 0)));
+    }
+}
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use tracing::info;
 
 use crate::api::rest::routes::configure_routes;
-use crate::infrastructure::{MetricsCollector, MetricsMiddleware, MongoDBRepository};
+use crate::infrastructure::{EventSink, MetricsCollector, MetricsMiddleware};
 
 /// Starts an HTTP server with the given configuration.
 ///
@@ -14,6 +14,7 @@ use crate::infrastructure::{MetricsCollector, MetricsMiddleware, MongoDBReposito
 ///
 /// * `session_manager` - A shared reference to the `SessionManager`, used to manage user sessions.
 /// * `metrics_collector` - A shared reference to the `MetricsCollector`, used for collecting server metrics.
+/// * `event_sink` - Where the v1 handlers record session events and chain steps.
 /// * `snapshots` - The v2 snapshot warehouse, when snapshot persistence is enabled. It is the same
 ///   repository the manager files snapshots into, shared so the v2 export can read them back and
 ///   prefer a persisted step over replaying it. `None` leaves the export replaying every step.
@@ -46,7 +47,7 @@ pub async fn start_server(
     session_manager: Arc<SessionManager>,
     simulation_manager: Arc<SimulationManager>,
     metrics_collector: Arc<MetricsCollector>,
-    mongodb_repo: Arc<MongoDBRepository>,
+    event_sink: Arc<dyn EventSink>,
     listen_on: ListenOn,
     port: u16,
 ) -> std::io::Result<()> {
@@ -63,7 +64,7 @@ pub async fn start_server(
                     session_manager.clone(),
                     simulation_manager.clone(),
                     metrics_collector.clone(),
-                    mongodb_repo.clone(),
+                    event_sink.clone(),
                 )
             })
     })
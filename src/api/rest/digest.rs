@@ -0,0 +1,256 @@
+//! Content digests of a v2 simulation's tape.
+//!
+//! Exports are byte-identical by construction; a digest is how a client checks
+//! that without downloading the bytes. A CI job that pins a golden tape stores
+//! sixty-four hex characters instead of gigabytes of CSV, and fails when a
+//! dependency upgrade moves a single quote.
+//!
+//! # What is hashed
+//!
+//! A step's **canonical rendering** is its rows exactly as the CSV export
+//! writes them, without the header: the `underlying` record, then the
+//! `volatility` record, then every `option_chains` record, each terminated by
+//! CRLF. Its **step digest** `h(k)` is the SHA-256 of those bytes. Reusing the
+//! export's own rendering is the point: a step digests the same whichever
+//! dataset or format it was exported in, and a step read back from the
+//! warehouse digests exactly like a replayed one.
+//!
+//! Step digests are **chained**, so the last one fingerprints the whole range:
+//!
+//! ```text
+//! c(from_step - 1) = 32 zero bytes
+//! c(k)             = SHA-256(c(k - 1) ‖ h(k))
+//! ```
+//!
+//! over the raw 32-byte values. Everything on the wire is lowercase hex, so a
+//! client folding snapshot digests decodes each before hashing.
+//!
+//! # Where digests appear
+//!
+//! - Every snapshot carries its step digest `h(k)` in `digest`.
+//! - `GET /api/v2/simulations/{id}/digest` returns `c(to_step)` for a range,
+//!   without streaming any rows.
+//! - An export with `digest=true` appends `c(k)` to every row of step `k`, so
+//!   its last row carries the digest the endpoint returns for the same range.
+//!
+//! A digest covers the chains whatever the dataset, so computing one prices
+//! every step the warehouse does not hold — the cost of an `option_chains`
+//! export, without the bandwidth.
+
+use crate::api::rest::error::map_error;
+use crate::api::rest::export::{
+    Dataset, StepChains, StepRange, StoredSteps, csv_rows, encode_csv, render_instant, walk,
+};
+use crate::api::rest::filter::RowFilter;
+use crate::api::rest::handlers_v2::{SimulationPath, parse_id};
+use crate::api::rest::responses_v2::TapeDigestResponse;
+use crate::domain::factors::FactorRow;
+use crate::domain::series::SeriesSnapshot;
+use crate::infrastructure::SimulationSnapshotRepository;
+use crate::session::{SimulationManager, SimulationParametersV2};
+use crate::utils::ChainError;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+use tracing::{info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The datasets a canonical rendering concatenates, in order.
+const CANONICAL_DATASETS: [Dataset; 3] = [
+    Dataset::Underlying,
+    Dataset::Volatility,
+    Dataset::OptionChains,
+];
+
+/// A running chained digest over consecutive steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct TapeDigest {
+    current: [u8; 32],
+}
+
+impl TapeDigest {
+    /// The chain before its first step: thirty-two zero bytes.
+    #[must_use]
+    pub(super) fn new() -> Self {
+        Self { current: [0; 32] }
+    }
+
+    /// Folds the next step's digest into the chain.
+    pub(super) fn push(&mut self, step: [u8; 32]) {
+        let mut hasher = Sha256::new();
+        hasher.update(self.current);
+        hasher.update(step);
+        self.current = hasher.finalize().into();
+    }
+
+    /// The chain so far, as lowercase hex.
+    #[must_use]
+    pub(super) fn to_hex(&self) -> String {
+        hex::encode(self.current)
+    }
+}
+
+/// The step digest of one step: the SHA-256 of its canonical rendering.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Internal`] if a record cannot be encoded.
+pub(super) fn step_digest(
+    parameters: &SimulationParametersV2,
+    row: &FactorRow,
+    chains: StepChains<'_>,
+) -> Result<[u8; 32], ChainError> {
+    let simulated_at = render_instant(row.simulated_at);
+    let symbol = parameters.symbol.as_str();
+    let mut hasher = Sha256::new();
+    // One encoding per dataset: a CSV writer insists every record it writes
+    // has the same length, and the three datasets do not.
+    for dataset in CANONICAL_DATASETS {
+        let records = csv_rows(
+            dataset,
+            row.step,
+            &simulated_at,
+            symbol,
+            row,
+            Some(chains),
+            &RowFilter::default(),
+        );
+        hasher.update(encode_csv(&records)?);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// The step digest of a served snapshot, as lowercase hex.
+///
+/// Encoding into memory does not fail in practice; if it ever did, the empty
+/// string is a digest no client can mistake for a match.
+#[must_use]
+pub(crate) fn snapshot_digest(
+    parameters: &SimulationParametersV2,
+    snapshot: &SeriesSnapshot,
+) -> String {
+    let row = FactorRow {
+        step: snapshot.step,
+        simulated_at: snapshot.simulated_at,
+        spot: snapshot.spot,
+        base_volatility: snapshot.base_volatility,
+    };
+    step_digest(parameters, &row, StepChains::Replayed(snapshot))
+        .map(hex::encode)
+        .unwrap_or_default()
+}
+
+/// Query parameters for a tape digest.
+///
+/// The export's names, so one range string serves both; `from` and `to` are
+/// accepted as shorthands.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct DigestQuery {
+    /// First step to cover, inclusive. Defaults to `0`.
+    #[serde(default, alias = "from")]
+    pub(crate) from_step: Option<usize>,
+    /// Last step to cover, inclusive. Defaults to the final generated step.
+    #[serde(default, alias = "to")]
+    pub(crate) to_step: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/digest",
+    description = "The chained SHA-256 digest of a step range of the simulation's tape, without \
+        streaming it. Each step's digest is the SHA-256 of its rows as the CSV export writes \
+        them (underlying, volatility, then option_chains, no header); the chain starts from 32 \
+        zero bytes and folds each step as SHA-256(previous || step). The result equals the \
+        `digest` of the last row of an export of the same range with digest=true, and folding \
+        the `digest` of each snapshot over the range reproduces it. Read-only.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("from_step" = Option<usize>, Query, description = "First step, inclusive; defaults to 0. Alias: from"),
+        ("to_step" = Option<usize>, Query, description = "Last step, inclusive; defaults to the final step. Alias: to")
+    ),
+    responses(
+        (status = 200, description = "The digest", body = TapeDigestResponse),
+        (status = 400, description = "Malformed id or an invalid range; body carries `error` and `field`"),
+        (status = 404, description = "Simulation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(manager, snapshots, query), level = "debug")]
+pub(crate) async fn tape_digest(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    snapshots: Option<web::Data<Arc<dyn SimulationSnapshotRepository>>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<DigestQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match digest_range(
+        &manager,
+        snapshots.map(|repository| Arc::clone(repository.get_ref())),
+        id,
+        &query,
+    )
+    .await
+    {
+        Ok(digest) => HttpResponse::Ok().json(digest),
+        Err(error) => map_error(error),
+    }
+}
+
+/// Validates a range and computes its chained digest off the runtime.
+///
+/// The same split as an export: one read of the simulation, then a walk on a
+/// blocking thread that prefers the warehouse. Dropping the returned future —
+/// a client that hung up — closes the channel, and the walk stops at its next
+/// step instead of pricing the rest for nobody.
+async fn digest_range(
+    manager: &SimulationManager,
+    snapshots: Option<Arc<dyn SimulationSnapshotRepository>>,
+    id: Uuid,
+    query: &DigestQuery,
+) -> Result<TapeDigestResponse, ChainError> {
+    let simulation = manager.get(id).await?;
+    manager.check_engine(&simulation)?;
+    let parameters = simulation.parameters.clone();
+    let range = StepRange::bounded(
+        query.from_step,
+        query.to_step,
+        parameters.steps,
+        manager.config().max_export_rows,
+    )?;
+    let stored = snapshots.map(|repository| StoredSteps::new(repository, id, Handle::current()));
+
+    let (sender, receiver) = oneshot::channel();
+    tokio::task::spawn_blocking(move || {
+        let mut chain = TapeDigest::new();
+        let walked = walk(&parameters, true, range, stored, |row, chains| {
+            let chains = chains.ok_or_else(|| {
+                ChainError::Internal(format!("the walk priced no chains at step {}", row.step))
+            })?;
+            chain.push(step_digest(&parameters, row, chains)?);
+            Ok(!sender.is_closed())
+        });
+        // Nobody is listening when the walk stopped early.
+        let _ = sender.send(walked.map(|_| chain.to_hex()));
+    });
+
+    let digest = receiver.await.map_err(|_| {
+        ChainError::Internal("the digest task ended without a result".to_string())
+    })??;
+    Ok(TapeDigestResponse {
+        id: simulation.id.to_string(),
+        from_step: range.from,
+        to_step: range.to,
+        digest,
+    })
+}
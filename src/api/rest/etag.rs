@@ -0,0 +1,128 @@
+//! `ETag`, `If-None-Match` and `If-Match` for a v2 simulation.
+//!
+//! The entity tag is the simulation's revision and cursor, `"{version}-{step}"`.
+//! The revision alone would do — every write bumps it, a touch included — but
+//! carrying the step makes a tag readable in a log, and costs nothing.
+//!
+//! The tag is strong. Everything a `GET` returns for a simulation is a pure
+//! function of its document, and the document never changes without a new
+//! revision, so two responses under one tag are identical byte for byte.
+//!
+//! # Two preconditions
+//!
+//! - **`If-None-Match`** on a read answers `304` when the client's copy is
+//!   still current, compared weakly as RFC 9110 prescribes. A malformed value is
+//!   ignored: the worst that does is send a body the client already has.
+//! - **`If-Match`** on a write is the revision check in HTTP's own words. A tag
+//!   that does not match is `412` with the current tag, exactly like a wrong
+//!   `expected_step`, and the write then commits against the revision the tag
+//!   named — so a writer that slips in between is a `412` too, not a lost
+//!   update. `*` only requires that the simulation exists. A malformed value
+//!   is `400`, never ignored: ignoring it would run the write unconditionally.
+
+use crate::session::SessionV2;
+use crate::utils::ChainError;
+use actix_web::http::header::{ETag, EntityTag, Header, IfMatch, IfNoneMatch};
+use actix_web::{HttpRequest, HttpResponse};
+
+/// The simulation's entity tag.
+#[must_use]
+pub(crate) fn entity_tag(simulation: &SessionV2) -> EntityTag {
+    EntityTag::new_strong(format!(
+        "{}-{}",
+        simulation.version, simulation.current_step
+    ))
+}
+
+/// The simulation's `ETag` header.
+#[must_use]
+pub(crate) fn etag(simulation: &SessionV2) -> ETag {
+    ETag(entity_tag(simulation))
+}
+
+/// Whether the request's `If-None-Match` names the simulation as it is now.
+#[must_use]
+pub(crate) fn not_modified(req: &HttpRequest, simulation: &SessionV2) -> bool {
+    if !req.headers().contains_key(IfNoneMatch::name()) {
+        return false;
+    }
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => {
+            let current = entity_tag(simulation);
+            tags.iter().any(|tag| tag.weak_eq(&current))
+        }
+        Err(_) => false,
+    }
+}
+
+/// The `304` for a client whose copy is current.
+#[must_use]
+pub(crate) fn not_modified_response(simulation: &SessionV2) -> HttpResponse {
+    HttpResponse::NotModified()
+        .insert_header(etag(simulation))
+        .finish()
+}
+
+/// The request's `If-Match`, if it sent one.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Validation`] on `If-Match` when the value is not `*`
+/// or a list of entity tags.
+pub(crate) fn if_match(req: &HttpRequest) -> Result<Option<IfMatch>, ChainError> {
+    if !req.headers().contains_key(IfMatch::name()) {
+        return Ok(None);
+    }
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Items(tags)) if tags.is_empty() => Err(malformed_if_match()),
+        Ok(condition) => Ok(Some(condition)),
+        Err(_) => Err(malformed_if_match()),
+    }
+}
+
+/// Whether the simulation still matches `condition`.
+#[must_use]
+pub(crate) fn matches(condition: &IfMatch, simulation: &SessionV2) -> bool {
+    match condition {
+        IfMatch::Any => true,
+        IfMatch::Items(tags) => {
+            let current = entity_tag(simulation);
+            tags.iter().any(|tag| tag.strong_eq(&current))
+        }
+    }
+}
+
+/// The revision a write under a matching `condition` must commit against.
+///
+/// `*` matches any revision, so it pins none.
+#[must_use]
+pub(crate) fn pinned_version(condition: &IfMatch, simulation: &SessionV2) -> Option<u64> {
+    match condition {
+        IfMatch::Any => None,
+        IfMatch::Items(_) => Some(simulation.version),
+    }
+}
+
+/// The `412` for an `If-Match` the simulation no longer matches.
+///
+/// The same shape as the `expected_step` body, plus the current tag, which is
+/// also sent as the `ETag` header so a client can retry without a re-read.
+#[must_use]
+pub(crate) fn precondition_failed(simulation: &SessionV2) -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+        .insert_header(etag(simulation))
+        .json(serde_json::json!({
+            "error": "If-Match does not match the simulation's current ETag",
+            "current_step": simulation.current_step,
+            "etag": entity_tag(simulation).to_string(),
+        }))
+}
+
+/// The `400` for an `If-Match` that is neither `*` nor a list of tags.
+fn malformed_if_match() -> ChainError {
+    ChainError::Validation {
+        field: "If-Match".to_string(),
+        reason: "must be * or a list of quoted entity tags".to_string(),
+    }
+}
@@ -0,0 +1,594 @@
+//! A Server-Sent Events feed of a v2 simulation's advances.
+//!
+//! `GET /api/v2/simulations/{id}/events` is the push feed for clients that
+//! cannot hold a WebSocket — a browser `EventSource`, or `curl -N`. It is
+//! read-only: one `advance` event per committed advance, whoever made it,
+//! carrying the served step's `simulated_at`, spot and base volatility, and
+//! with `summary=true` a compact per-expiration summary rather than the whole
+//! chain.
+//!
+//! # Resuming
+//!
+//! Every event's SSE `id` is the step it served. A client that reconnects with
+//! `Last-Event-ID: n` — which `EventSource` does on its own — first receives
+//! every step after `n` the simulation has already served, rebuilt by
+//! deterministic replay, and then the live feed. A replayed event is
+//! byte-identical to the live one it stands in for, because an event is built
+//! from the snapshot alone. The same replay fills the gap when a slow reader
+//! falls behind the live feed, so an event is never silently dropped: at most
+//! [`MAX_REPLAY_STEPS`] are rebuilt per gap, and anything older is reported in a
+//! `lagged` event.
+//!
+//! Only served steps are replayed. A seek jumps the cursor over steps nobody
+//! was served, and the simulation records them (see
+//! [`SessionV2::skipped`]); the catch-up sends one `seek` event per such run,
+//! carrying `from_step` and `to_step`, in place of the advances that never
+//! happened. The live feed reaches a seek the same way, when the advance after
+//! it arrives.
+//!
+//! The feed ends with a `completed` event after the simulation's last step,
+//! and with a `deleted` or `expired` event when the simulation goes away
+//! first. Both come from this process's own delete and retention sweep. A
+//! simulation that vanishes any other way — its document expired in the store
+//! ahead of the sweep, or another replica deleted it — is noticed on the next
+//! keep-alive, which re-reads it, and reported as `expired`; either way the
+//! feed does not outlive its simulation.
+
+use crate::api::rest::error::map_error;
+use crate::api::rest::handlers_v2::{SimulationPath, parse_id};
+use crate::api::rest::responses_v2::advance_event;
+use crate::domain::series::SeriesSnapshot;
+use crate::session::{Advanced, Ended, Ending, SessionState, SessionV2, SimulationManager};
+use crate::utils::ChainError;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use futures::stream::Stream;
+use serde::{Deserialize, Serialize};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// The most steps rebuilt to close one gap.
+///
+/// Each is a priced snapshot, so an unbounded catch-up would let a client with
+/// an ancient `Last-Event-ID` buy seconds of CPU with one reconnect. A client
+/// that needs the whole history wants the export.
+pub(crate) const MAX_REPLAY_STEPS: usize = 256;
+
+/// How often an idle feed sends a comment line.
+///
+/// Keeps intermediaries from reaping a connection that is quiet because the
+/// simulation is.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How many encoded events may wait for a slow client.
+///
+/// The bound is the backpressure: once it is full the producer stops reading
+/// the advance events, and the broadcast channel's own lag reporting takes
+/// over — which the replay then closes.
+const EVENT_BUFFER: usize = 16;
+
+/// Query parameters for the feed.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct EventsQuery {
+    /// Include a per-expiration summary in every event. Defaults to `false`.
+    #[serde(default)]
+    pub(crate) summary: bool,
+}
+
+/// Parses `Last-Event-ID`, if the client sent one.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Validation`] naming the header when it is not a step.
+fn last_event_id(req: &HttpRequest) -> Result<Option<usize>, ChainError> {
+    let Some(value) = req.headers().get("Last-Event-ID") else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|raw| raw.trim().parse::<usize>().ok())
+        .map(Some)
+        .ok_or_else(|| ChainError::Validation {
+            field: "Last-Event-ID".to_string(),
+            reason: "must be the step of a previous event".to_string(),
+        })
+}
+
+/// Encodes one SSE event.
+fn encode(event: &str, id: Option<usize>, data: &str) -> web::Bytes {
+    let mut frame = String::with_capacity(data.len() + 48);
+    if let Some(id) = id {
+        frame.push_str(&format!("id: {id}\n"));
+    }
+    frame.push_str(&format!("event: {event}\ndata: {data}\n\n"));
+    web::Bytes::from(frame)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/events",
+    description = "A Server-Sent Events feed with one `advance` event per committed advance, \
+        whoever made it. Each event's id is the step it served; reconnecting with \
+        `Last-Event-ID` first replays every served step after it (at most 256, older ones \
+        reported in a `lagged` event), rebuilt deterministically and identical to the live \
+        events. Steps a seek jumped over were never served and come as one `seek` event \
+        carrying from_step and to_step. `summary=true` adds a compact per-expiration \
+        summary. The feed ends with a `completed` event, or with `deleted` or `expired` \
+        when the simulation goes away first.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("summary" = Option<bool>, Query, description = "Include per-expiration summaries; defaults to false"),
+        ("Last-Event-ID" = Option<usize>, Header, description = "Resume after this step")
+    ),
+    responses(
+        (status = 200, description = "The event stream", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Malformed id, summary or Last-Event-ID"),
+        (status = 404, description = "Simulation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn simulation_events(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<EventsQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+    let resume_after = match last_event_id(&req) {
+        Ok(resume_after) => resume_after,
+        Err(error) => return map_error(error),
+    };
+
+    // Subscribed before the read, so an advance that commits in between is
+    // both in the live feed and skipped as a duplicate of the replay.
+    let events = manager.subscribe();
+    let endings = manager.subscribe_endings();
+    let simulation = match manager.get(id).await {
+        Ok(simulation) => simulation,
+        Err(error) => return map_error(error),
+    };
+
+    let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+    let feed = Feed {
+        manager: Arc::clone(manager.get_ref()),
+        id,
+        summary: query.summary,
+        sender,
+    };
+    actix_web::rt::spawn(feed.run(simulation, resume_after, events, endings));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"))
+        .streaming(EventStream { receiver })
+}
+
+/// The producer behind one feed.
+struct Feed {
+    manager: Arc<SimulationManager>,
+    id: Uuid,
+    summary: bool,
+    sender: mpsc::Sender<web::Bytes>,
+}
+
+impl Feed {
+    /// Replays what the client missed, then follows the live events until the
+    /// simulation completes or goes away, or the client leaves.
+    async fn run(
+        self,
+        simulation: SessionV2,
+        resume_after: Option<usize>,
+        mut events: broadcast::Receiver<Advanced>,
+        mut endings: broadcast::Receiver<Ended>,
+    ) {
+        // The next step this client has not been sent.
+        let mut next = resume_after.map_or(simulation.current_step, |step| step + 1);
+        if self
+            .catch_up(&simulation, &mut next, simulation.current_step)
+            .await
+            .is_err()
+        {
+            return;
+        }
+        if simulation.state == SessionState::Completed {
+            let _ = self.completed(&simulation).await;
+            return;
+        }
+
+        let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+        keep_alive.tick().await;
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) if event.simulation.id == self.id => {
+                        let served = event.snapshot.step;
+                        if served < next {
+                            continue;
+                        }
+                        if self.catch_up(&event.simulation, &mut next, served).await.is_err()
+                            || self.send_advance(&event.snapshot).await.is_err()
+                        {
+                            return;
+                        }
+                        next = served + 1;
+                        if event.simulation.state == SessionState::Completed {
+                            let _ = self.completed(&event.simulation).await;
+                            return;
+                        }
+                    }
+                    // The next event of this simulation will show how far
+                    // behind the feed is, and the replay closes the gap.
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                },
+                ended = endings.recv() => match ended {
+                    Ok(ended) if ended.id == self.id => {
+                        let _ = self.ended(ended.ending).await;
+                        return;
+                    }
+                    // A missed ending is caught by the next keep-alive.
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                },
+                _ = keep_alive.tick() => {
+                    // Only `NotFound` is proof the simulation is gone; a
+                    // store that cannot answer right now is asked again on
+                    // the next tick.
+                    if let Err(ChainError::NotFound(_)) = self.manager.get(self.id).await {
+                        let _ = self.ended(Ending::Expired).await;
+                        return;
+                    }
+                    if self.sender.send(web::Bytes::from_static(b": keep-alive\n\n")).await.is_err() {
+                        return;
+                    }
+                }
+                () = self.sender.closed() => return,
+            }
+        }
+    }
+
+    /// Sends every step from `next` up to, not including, `upto` that
+    /// `simulation` served, rebuilding each from its tape, and a `seek` event
+    /// for each run of steps a seek jumped over instead.
+    async fn catch_up(
+        &self,
+        simulation: &SessionV2,
+        next: &mut usize,
+        upto: usize,
+    ) -> Result<(), ()> {
+        let served = simulation.served_between(*next, upto);
+        if served > MAX_REPLAY_STEPS {
+            let missed = served - MAX_REPLAY_STEPS;
+            let data = serde_json::json!({ "missed": missed }).to_string();
+            self.send(encode("lagged", None, &data)).await?;
+            // Past the `missed` oldest served steps, hopping a skipped run
+            // whole: one iteration per run, not per step.
+            let mut remaining = missed;
+            while remaining > 0 {
+                if let Some(run) = simulation.skipped_run(*next) {
+                    *next = run.to;
+                    continue;
+                }
+                let served_until = simulation
+                    .skipped
+                    .iter()
+                    .map(|run| run.from)
+                    .find(|&from| from > *next)
+                    .unwrap_or(upto);
+                let taken = remaining.min(served_until - *next);
+                *next += taken;
+                remaining -= taken;
+            }
+        }
+
+        while *next < upto {
+            if let Some(run) = simulation.skipped_run(*next) {
+                let data = serde_json::json!({ "from_step": *next, "to_step": run.to }).to_string();
+                self.send(encode("seek", None, &data)).await?;
+                *next = run.to;
+                continue;
+            }
+            match self.manager.served_snapshot(simulation, *next).await {
+                Ok(snapshot) => self.send_advance(&snapshot).await?,
+                Err(error) => {
+                    debug!(simulation_id = %self.id, step = *next, %error, "Could not replay a step");
+                    return Err(());
+                }
+            }
+            *next += 1;
+        }
+        Ok(())
+    }
+
+    async fn send_advance(&self, snapshot: &SeriesSnapshot) -> Result<(), ()> {
+        let event = advance_event(self.id, snapshot, self.summary);
+        let data = serde_json::to_string(&event).map_err(|_| ())?;
+        self.send(encode("advance", Some(snapshot.step), &data))
+            .await
+    }
+
+    async fn completed(&self, simulation: &SessionV2) -> Result<(), ()> {
+        let data = serde_json::json!({
+            "simulation_id": self.id.to_string(),
+            "total_steps": simulation.total_steps,
+        })
+        .to_string();
+        self.send(encode("completed", None, &data)).await
+    }
+
+    async fn ended(&self, ending: Ending) -> Result<(), ()> {
+        let data = serde_json::json!({ "simulation_id": self.id.to_string() }).to_string();
+        self.send(encode(ending.as_str(), None, &data)).await
+    }
+
+    async fn send(&self, frame: web::Bytes) -> Result<(), ()> {
+        self.sender.send(frame).await.map_err(|_| ())
+    }
+}
+
+/// Streams encoded events from the producer as an HTTP body.
+///
+/// Dropping this — which is what actix does when the client disconnects —
+/// closes the receiver, and the producer ends on its next send.
+struct EventStream {
+    receiver: mpsc::Receiver<web::Bytes>,
+}
+
+impl Stream for EventStream {
+    type Item = Result<web::Bytes, actix_web::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx).map(|frame| frame.map(Ok))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::routes::configure_v2_routes;
+    use crate::infrastructure::SimulationV2Config;
+    use crate::session::InMemorySimulationStore;
+    use actix_web::App;
+    use actix_web::http::StatusCode;
+    use actix_web::test as actix_test;
+    use serde_json::{Value, json};
+
+    fn body(steps: usize) -> Value {
+        json!({
+            "symbol": "SPX",
+            "steps": steps,
+            "start_at": "2026-01-05T14:30:00Z",
+            "step_interval_seconds": 86400,
+            "timezone": "America/New_York",
+            "expiration_time": "17:00",
+            "schedules": [
+                { "rule_id": "zero_dte", "kind": "daily", "target_count": 1 },
+                { "rule_id": "weeklies", "kind": "weekly", "target_count": 3,
+                  "weekdays": ["Mon", "Wed", "Fri"] }
+            ],
+            "initial_price": 5000.0,
+            "volatility": 0.18,
+            "risk_free_rate": 0.04,
+            "dividend_yield": 0.0,
+            "method": { "Brownian": { "dt": 0.004, "drift": 0.0, "volatility": 0.18 } },
+            "time_frame": "Day",
+            "chain_size": 3,
+            "strike_interval": 25.0,
+            "spread": 0.02,
+            "seed": 42
+        })
+    }
+
+    /// Mounts the real v2 routes, returning the manager as well so a test can
+    /// advance a simulation the way a playback or another client would.
+    macro_rules! v2_service {
+        () => {{
+            let manager = Arc::new(SimulationManager::new(
+                Arc::new(InMemorySimulationStore::new()),
+                SimulationV2Config::default(),
+            ));
+            let app = actix_test::init_service(App::new().configure({
+                let manager = Arc::clone(&manager);
+                move |cfg| configure_v2_routes(cfg, manager, None)
+            }))
+            .await;
+            (app, manager)
+        }};
+    }
+
+    macro_rules! create {
+        ($app:expr, $steps:expr) => {{
+            let request = actix_test::TestRequest::post()
+                .uri("/api/v2/simulations")
+                .set_json(body($steps))
+                .to_request();
+            let response = actix_test::call_service(&$app, request).await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let body: Value = actix_test::read_body_json(response).await;
+            match body.get("id").and_then(Value::as_str).map(Uuid::parse_str) {
+                Some(Ok(id)) => id,
+                _ => panic!("the response must carry an id: {body}"),
+            }
+        }};
+    }
+
+    async fn advance(manager: &SimulationManager, id: Uuid) {
+        if let Err(error) = manager.advance(id).await {
+            panic!("the advance must succeed: {error}");
+        }
+    }
+
+    /// Splits a feed into its events, dropping keep-alive comments.
+    fn events(body: &[u8]) -> Vec<String> {
+        String::from_utf8_lossy(body)
+            .split("\n\n")
+            .filter(|event| !event.is_empty() && !event.starts_with(':'))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// A live feed carries one event per advance, whoever made it, and ends
+    /// after the last; a resumed feed replays the same events byte for byte.
+    #[actix_web::test]
+    async fn test_a_resumed_feed_replays_the_live_events_exactly() {
+        let (app, manager) = v2_service!();
+        let id = create!(app, 3);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events?summary=true"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        for _ in 0..3 {
+            advance(&manager, id).await;
+        }
+        let live = events(&actix_test::read_body(response).await);
+
+        assert_eq!(live.len(), 4, "three advances and a completion: {live:?}");
+        assert!(live[0].starts_with("id: 0\nevent: advance\n"));
+        assert!(live[2].starts_with("id: 2\nevent: advance\n"));
+        assert!(live[3].starts_with("event: completed\n"));
+        assert!(live[0].contains("\"atm_strike\""), "summary was asked for");
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events?summary=true"))
+            .insert_header(("Last-Event-ID", "0"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let resumed = events(&actix_test::read_body(response).await);
+
+        assert_eq!(resumed, live[1..].to_vec());
+    }
+
+    /// Steps a seek jumped over are never passed off as served: the live feed
+    /// and a resumed one both send a `seek` event in their place, and agree.
+    #[actix_web::test]
+    async fn test_a_catch_up_across_a_seek_replays_only_served_steps() {
+        let (app, manager) = v2_service!();
+        let id = create!(app, 6);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        advance(&manager, id).await;
+        if let Err(error) = manager.seek(id, 4).await {
+            panic!("the seek must succeed: {error}");
+        }
+        advance(&manager, id).await;
+        advance(&manager, id).await;
+        let live = events(&actix_test::read_body(response).await);
+
+        assert_eq!(
+            live.len(),
+            5,
+            "two advances either side of a seek: {live:?}"
+        );
+        assert!(live[0].starts_with("id: 0\nevent: advance\n"));
+        assert_eq!(
+            live[1],
+            "event: seek\ndata: {\"from_step\":1,\"to_step\":4}"
+        );
+        assert!(live[2].starts_with("id: 4\nevent: advance\n"));
+        assert!(live[3].starts_with("id: 5\nevent: advance\n"));
+        assert!(live[4].starts_with("event: completed\n"));
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events"))
+            .insert_header(("Last-Event-ID", "0"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        let resumed = events(&actix_test::read_body(response).await);
+        assert_eq!(resumed, live[1..].to_vec());
+    }
+
+    /// A feed on a simulation deleted before its last step ends with a
+    /// `deleted` event rather than keep-alives forever.
+    #[actix_web::test]
+    async fn test_a_feed_ends_when_its_simulation_is_deleted() {
+        let (app, manager) = v2_service!();
+        let id = create!(app, 5);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        advance(&manager, id).await;
+        match manager.delete(id).await {
+            Ok(deleted) => assert!(deleted, "the simulation must have existed"),
+            Err(error) => panic!("the delete must succeed: {error}"),
+        }
+        let feed = events(&actix_test::read_body(response).await);
+
+        assert_eq!(feed.len(), 2, "one advance and the ending: {feed:?}");
+        assert!(feed[0].starts_with("id: 0\nevent: advance\n"));
+        assert_eq!(
+            feed[1],
+            format!("event: deleted\ndata: {{\"simulation_id\":\"{id}\"}}")
+        );
+    }
+
+    /// Without `summary` an event carries the factor values only.
+    #[actix_web::test]
+    async fn test_an_event_without_a_summary_has_no_chains() {
+        let (app, manager) = v2_service!();
+        let id = create!(app, 1);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        advance(&manager, id).await;
+        let feed = events(&actix_test::read_body(response).await);
+
+        let data = match feed[0].split("data: ").nth(1) {
+            Some(data) => data,
+            None => panic!("the event must carry data: {feed:?}"),
+        };
+        let event: Value = match serde_json::from_str(data) {
+            Ok(event) => event,
+            Err(error) => panic!("the data must be JSON: {error}"),
+        };
+        assert_eq!(event.get("step"), Some(&json!(0)));
+        assert!(event.get("spot").is_some_and(Value::is_f64));
+        assert!(event.get("chains").is_none());
+    }
+
+    /// A `Last-Event-ID` that is not a step is refused by name, and an
+    /// unknown simulation is a 404 before any stream starts.
+    #[actix_web::test]
+    async fn test_a_bad_resume_point_or_id_is_refused() {
+        let (app, _manager) = v2_service!();
+        let id = create!(app, 2);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/events"))
+            .insert_header(("Last-Event-ID", "yesterday"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("field"), Some(&json!("Last-Event-ID")));
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{}/events", Uuid::new_v4()))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
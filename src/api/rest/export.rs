@@ -1,9 +1,12 @@
 //! Bulk export of a v2 simulation's complete tape.
 //!
 //! `GET /api/v2/simulations/{id}/export` replays a simulation from step zero —
-//! or over a requested range — and streams it as JSON or CSV. It is what turns
-//! a walked-one-request-at-a-time simulation into something a backtester can
-//! load in one go.
+//! or over a requested range — and streams it as JSON, NDJSON, CSV, Parquet or
+//! an Arrow IPC stream, gzip- or zstd-compressed when the client asks. It is what turns a walked-one-request-at-a-time
+//! simulation into something a backtester can load in one go.
+//! Rows, columns and steps can be narrowed before anything is rendered; see
+//! [`super::filter`]. An NDJSON export carries continuation tokens and resumes
+//! from one with `after=`; see [`super::continuation`].
 //!
 //! # Read-only, in the strong sense
 //!
@@ -26,6 +29,10 @@
 //! receiver so the producer's next send fails and the task ends. Cancellation
 //! costs one row of wasted work.
 //!
+//! Compression, when `Accept-Encoding` asks for it, happens on that same thread
+//! between the encoder and the channel (see [`super::coding`]), so a compressed
+//! download is throttled by its reader exactly like an uncompressed one.
+//!
 //! # Where the chains come from
 //!
 //! When snapshot persistence is on, an `option_chains` export prefers the
@@ -48,16 +55,30 @@
 //! whole-second RFC 3339, and numbers use Rust's shortest round-trip
 //! formatting — no locale, no thousands separators. That is what lets a
 //! backtest harness cache a download and know it is still current.
-
+//!
+//! With `digest=true` every row also carries the chained content digest
+//! through its step (see [`super::digest`]), so a client can check a download
+//! against `GET /{id}/digest` without keeping a reference copy.
+
+use crate::api::rest::coding::{Coding, Compressor};
+use crate::api::rest::columnar::{self, ArrowSink, ParquetSink};
+use crate::api::rest::continuation::{
+    Continuation, ContinuationToken, Position, RESUMABLE_HEADER, query_fingerprint,
+};
+use crate::api::rest::digest::{TapeDigest, step_digest};
 use crate::api::rest::error::map_error;
-use crate::domain::factors::FactorTape;
+use crate::api::rest::filter::{RowFilter, Selection};
+use crate::api::rest::series::Side;
+use crate::domain::factors::{FactorRow, FactorTape};
 use crate::domain::series::{SeriesBuilder, SeriesSnapshot};
 use crate::infrastructure::{
-    CURRENT_SNAPSHOT_GENERATION, QuoteRow, SimulationSnapshotRepository, SnapshotRecord,
+    CURRENT_SNAPSHOT_GENERATION, QuoteRow, SimulationSnapshotRepository, SnapshotRangeQuery,
+    SnapshotRecord,
 };
-use crate::session::{SimulationManager, SimulationParametersV2};
+use crate::session::{SimulationManager, SimulationParametersV2, engine_version};
 use crate::utils::ChainError;
 use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use arrow_schema::SchemaRef;
 use chrono::{DateTime, SecondsFormat, Utc};
 use futures::stream::Stream;
 use optionstratlib::chains::OptionData;
@@ -78,7 +99,7 @@ use uuid::Uuid;
 /// Small on purpose: it is the backpressure. A slow client fills it, the
 /// producer blocks on the next send, and no unbounded queue of priced chains
 /// accumulates in memory.
-const CHANNEL_CAPACITY: usize = 16;
+pub(super) const CHANNEL_CAPACITY: usize = 16;
 
 /// How many steps one warehouse round trip asks for.
 ///
@@ -113,8 +134,14 @@ pub(crate) enum Dataset {
 pub(crate) enum Format {
     /// A single valid JSON array of row objects, streamed.
     Json,
+    /// One JSON row object per line, each terminated by `\n`.
+    Ndjson,
     /// RFC 4180 CSV with a header row and CRLF line endings.
     Csv,
+    /// Apache Parquet with typed columns; see [`super::columnar`].
+    Parquet,
+    /// An Arrow IPC stream with the same columns, a batch per window of steps.
+    Arrow,
 }
 
 impl Format {
@@ -123,24 +150,31 @@ impl Format {
     fn content_type(self) -> &'static str {
         match self {
             Format::Json => "application/json",
+            Format::Ndjson => "application/x-ndjson",
             Format::Csv => "text/csv; charset=utf-8",
+            Format::Parquet => "application/vnd.apache.parquet",
+            Format::Arrow => "application/vnd.apache.arrow.stream",
         }
     }
 
     /// The extension of the suggested download filename.
     #[must_use]
-    fn extension(self) -> &'static str {
+    pub(super) fn extension(self) -> &'static str {
         match self {
             Format::Json => "json",
+            Format::Ndjson => "ndjson",
             Format::Csv => "csv",
+            Format::Parquet => "parquet",
+            Format::Arrow => "arrows",
         }
     }
 }
 
 impl Dataset {
-    /// The dataset's name, used in the suggested filename.
+    /// The dataset's name, used in the suggested filename and as a bundle's
+    /// entry name.
     #[must_use]
-    fn as_str(self) -> &'static str {
+    pub(super) fn as_str(self) -> &'static str {
         match self {
             Dataset::Underlying => "underlying",
             Dataset::Volatility => "volatility",
@@ -150,7 +184,7 @@ impl Dataset {
 
     /// The CSV header, in the order the rows are written.
     #[must_use]
-    fn header(self) -> &'static [&'static str] {
+    pub(super) fn header(self) -> &'static [&'static str] {
         match self {
             Dataset::Underlying => &["step", "simulated_at", "symbol", "price"],
             Dataset::Volatility => &["step", "simulated_at", "symbol", "base_volatility"],
@@ -181,7 +215,7 @@ impl Dataset {
     /// `underlying` and `volatility` read straight off the factor tape, so a
     /// multi-year export of either is nearly free — no chain is ever built.
     #[must_use]
-    fn needs_chains(self) -> bool {
+    pub(super) fn needs_chains(self) -> bool {
         matches!(self, Dataset::OptionChains)
     }
 }
@@ -199,13 +233,56 @@ pub(crate) struct ExportQuery {
     /// Last step to include, inclusive. Defaults to the final generated step.
     #[serde(default)]
     pub(crate) to_step: Option<usize>,
+    /// Appends the running chained digest to every row. See
+    /// [`super::digest`]; it prices the chains whatever the dataset.
+    #[serde(default)]
+    pub(crate) digest: bool,
+    /// Keeps the expirations carrying any of these comma-separated rule ids.
+    /// This and every filter through `side` apply to `option_chains` only;
+    /// see [`super::filter`].
+    #[serde(default)]
+    pub(crate) labels: Option<String>,
+    /// Keeps the expirations at least this many days out, inclusive.
+    #[serde(default)]
+    pub(crate) min_dte: Option<f64>,
+    /// Keeps the expirations at most this many days out, inclusive.
+    #[serde(default)]
+    pub(crate) max_dte: Option<f64>,
+    /// Keeps the strikes at or above this `strike / spot`.
+    #[serde(default)]
+    pub(crate) min_moneyness: Option<f64>,
+    /// Keeps the strikes at or below this `strike / spot`.
+    #[serde(default)]
+    pub(crate) max_moneyness: Option<f64>,
+    /// Keeps the strikes whose absolute `side` delta is at least this.
+    #[serde(default)]
+    pub(crate) min_delta: Option<f64>,
+    /// Keeps the strikes whose absolute `side` delta is at most this.
+    #[serde(default)]
+    pub(crate) max_delta: Option<f64>,
+    /// Keeps one side's quote columns, and says whose delta a band reads.
+    #[serde(default)]
+    pub(crate) side: Option<Side>,
+    /// The comma-separated columns to keep; they come back in the dataset's
+    /// order. Defaults to every column.
+    #[serde(default)]
+    pub(crate) columns: Option<String>,
+    /// Exports `from_step` and every `every`-th step after it. Defaults to 1.
+    #[serde(default)]
+    pub(crate) every: Option<usize>,
+    /// Resumes an `ndjson` export at a continuation token one of its lines
+    /// carried; see [`super::continuation`].
+    #[serde(default)]
+    pub(crate) after: Option<String>,
 }
 
-/// A validated, inclusive step range.
+/// A validated, inclusive step range, walked `stride` steps at a time.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct StepRange {
-    from: usize,
-    to: usize,
+pub(super) struct StepRange {
+    pub(super) from: usize,
+    pub(super) to: usize,
+    /// `1` walks every step; `k` walks `from` and every `k`-th step after it.
+    pub(super) stride: usize,
 }
 
 impl StepRange {
@@ -215,8 +292,52 @@ impl StepRange {
     ///
     /// Returns [`ChainError::Validation`] naming `from_step` or `to_step` when
     /// a bound is past the tape, when the range is reversed, or when it would
-    /// produce more rows than the configured cap allows.
+    /// produce more rows than the configured cap allows; and naming `every`
+    /// when it is zero, or above one alongside `digest` — the chained digest
+    /// covers every step, so a decimated export could not carry it.
     fn resolve(query: &ExportQuery, steps: usize, max_rows: usize) -> Result<Self, ChainError> {
+        let stride = query.every.unwrap_or(1);
+        if stride == 0 {
+            return Err(ChainError::Validation {
+                field: "every".to_string(),
+                reason: "must be at least 1".to_string(),
+            });
+        }
+        if stride > 1 && query.digest {
+            return Err(ChainError::Validation {
+                field: "every".to_string(),
+                reason: "cannot skip steps with digest=true; the digest chains every step"
+                    .to_string(),
+            });
+        }
+
+        // The cap counts the steps produced, so a decimated range may span
+        // more of the tape than an undecimated one.
+        let range = Self::bounded(query.from_step, query.to_step, steps, usize::MAX)?;
+        let produced = (range.to.saturating_sub(range.from) / stride).saturating_add(1);
+        if produced > max_rows {
+            return Err(ChainError::Validation {
+                field: "to_step".to_string(),
+                reason: format!(
+                    "the requested range covers {produced} steps, above the {max_rows} the service will export in one request"
+                ),
+            });
+        }
+
+        Ok(Self { stride, ..range })
+    }
+
+    /// Validates a pair of optional bounds against a simulation's horizon.
+    ///
+    /// # Errors
+    ///
+    /// As [`StepRange::resolve`].
+    pub(super) fn bounded(
+        from_step: Option<usize>,
+        to_step: Option<usize>,
+        steps: usize,
+        max_rows: usize,
+    ) -> Result<Self, ChainError> {
         let last = steps.checked_sub(1).ok_or_else(|| {
             ChainError::Internal(
                 "a simulation with no steps cannot exist; `steps >= 1` is validated at creation"
@@ -224,8 +345,8 @@ impl StepRange {
             )
         })?;
 
-        let from = query.from_step.unwrap_or(0);
-        let to = query.to_step.unwrap_or(last);
+        let from = from_step.unwrap_or(0);
+        let to = to_step.unwrap_or(last);
 
         if from > last {
             return Err(ChainError::Validation {
@@ -263,19 +384,57 @@ impl StepRange {
             });
         }
 
-        Ok(Self { from, to })
+        Ok(Self {
+            from,
+            to,
+            stride: 1,
+        })
+    }
+
+    /// The range a resumed export walks to reach `after`.
+    ///
+    /// Without a digest the walk starts at the token's step, so a resume costs
+    /// only what is left. With one it still starts at `from`: the chain through
+    /// the first resumed row covers every step before it, and the rows before
+    /// the token are simply not sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `after` when the token's step
+    /// is not one this range walks — a token from an export with other bounds
+    /// or another `every`.
+    fn resumed(self, after: Position, digest: bool) -> Result<Self, ChainError> {
+        let walked = after.step >= self.from
+            && after.step <= self.to
+            && (after.step - self.from).is_multiple_of(self.stride);
+        if !walked {
+            return Err(ChainError::Validation {
+                field: "after".to_string(),
+                reason: format!(
+                    "resumes at step {}, which this range does not export; repeat the original query",
+                    after.step
+                ),
+            });
+        }
+        if digest {
+            return Ok(self);
+        }
+        Ok(Self {
+            from: after.step,
+            ..self
+        })
     }
 
     /// The steps the range covers.
     fn steps(self) -> impl Iterator<Item = usize> {
-        self.from..=self.to
+        (self.from..=self.to).step_by(self.stride)
     }
 }
 
 /// Renders an instant the way every v2 timestamp is rendered.
 #[must_use]
 #[inline]
-fn render_instant(instant: DateTime<Utc>) -> String {
+pub(super) fn render_instant(instant: DateTime<Utc>) -> String {
     instant.to_rfc3339_opts(SecondsFormat::Secs, true)
 }
 
@@ -295,18 +454,18 @@ fn render_optional(value: Option<f64>) -> String {
 /// value to the wire's `f64` happens here and only here, which is what makes a
 /// persisted row and a replayed row byte-identical rather than merely similar.
 #[derive(Debug, Clone, Copy, PartialEq)]
-struct QuoteView {
-    strike: f64,
-    implied_volatility: f64,
-    call_bid: Option<f64>,
-    call_ask: Option<f64>,
-    call_mid: Option<f64>,
-    call_delta: Option<f64>,
-    put_bid: Option<f64>,
-    put_ask: Option<f64>,
-    put_mid: Option<f64>,
-    put_delta: Option<f64>,
-    gamma: Option<f64>,
+pub(super) struct QuoteView {
+    pub(super) strike: f64,
+    pub(super) implied_volatility: f64,
+    pub(super) call_bid: Option<f64>,
+    pub(super) call_ask: Option<f64>,
+    pub(super) call_mid: Option<f64>,
+    pub(super) call_delta: Option<f64>,
+    pub(super) put_bid: Option<f64>,
+    pub(super) put_ask: Option<f64>,
+    pub(super) put_mid: Option<f64>,
+    pub(super) put_delta: Option<f64>,
+    pub(super) gamma: Option<f64>,
 }
 
 impl QuoteView {
@@ -349,7 +508,7 @@ impl QuoteView {
 
 /// The strikes of one expiration, from whichever source produced them.
 #[derive(Debug, Clone, Copy)]
-enum QuoteSource<'a> {
+pub(super) enum QuoteSource<'a> {
     /// Upstream's priced chain, iterated by ascending strike.
     Replayed(&'a OptionChain),
     /// The stored rows, which the repository returns by ascending strike.
@@ -362,7 +521,7 @@ impl<'a> QuoteSource<'a> {
     /// Exactly one of the two options is `Some`, so concatenating them with
     /// [`Iterator::chain`] *is* the branch — one concrete iterator type, no
     /// boxing on a path that runs once per contract.
-    fn quotes(self) -> impl Iterator<Item = QuoteView> + 'a {
+    pub(super) fn quotes(self) -> impl Iterator<Item = QuoteView> + 'a {
         let replayed = match self {
             QuoteSource::Replayed(chain) => Some(chain.iter()),
             QuoteSource::Stored(_) => None,
@@ -382,11 +541,11 @@ impl<'a> QuoteSource<'a> {
 
 /// One expiration of one step, from whichever source produced it.
 #[derive(Debug, Clone, Copy)]
-struct ExpirationView<'a> {
-    expires_at: DateTime<Utc>,
-    days_to_expiration: f64,
-    labels: &'a [String],
-    quotes: QuoteSource<'a>,
+pub(super) struct ExpirationView<'a> {
+    pub(super) expires_at: DateTime<Utc>,
+    pub(super) days_to_expiration: f64,
+    pub(super) labels: &'a [String],
+    pub(super) quotes: QuoteSource<'a>,
 }
 
 /// The chains of one step, from whichever source produced them.
@@ -396,7 +555,7 @@ struct ExpirationView<'a> {
 /// the two sources one view — instead of two row builders that happen to agree
 /// today — is what makes preferring the warehouse safe.
 #[derive(Debug, Clone, Copy)]
-enum StepChains<'a> {
+pub(super) enum StepChains<'a> {
     /// Priced here and now, from the effective parameters.
     Replayed(&'a SeriesSnapshot),
     /// Read back from the warehouse exactly as it was served.
@@ -405,7 +564,7 @@ enum StepChains<'a> {
 
 impl<'a> StepChains<'a> {
     /// The live expirations, ascending — the order both sources guarantee.
-    fn expirations(self) -> impl Iterator<Item = ExpirationView<'a>> {
+    pub(super) fn expirations(self) -> impl Iterator<Item = ExpirationView<'a>> {
         let replayed = match self {
             StepChains::Replayed(snapshot) => Some(snapshot.chains.iter()),
             StepChains::Stored(_) => None,
@@ -454,6 +613,14 @@ fn window_end(from: usize, window: usize, last: usize) -> usize {
     }
 }
 
+/// Whether a stored row was priced by the running engine.
+///
+/// A row from before the engine was recorded is trusted: its generation is
+/// already part of the coordinate it was read under.
+fn priced_here(recorded: &str) -> bool {
+    recorded.is_empty() || recorded == engine_version()
+}
+
 /// Reads persisted snapshots ahead of the producer, a window of steps at a time.
 ///
 /// Lives on the blocking thread that produces the rows, so its reads have to
@@ -461,7 +628,7 @@ fn window_end(from: usize, window: usize, last: usize) -> usize {
 /// a `spawn_blocking` thread is sound (it is not an async context); doing it
 /// from an Actix worker would not be, which is exactly why the whole producer
 /// runs off the runtime.
-struct StoredSteps {
+pub(super) struct StoredSteps {
     repository: Arc<dyn SimulationSnapshotRepository>,
     runtime: Handle,
     simulation: Uuid,
@@ -471,6 +638,11 @@ struct StoredSteps {
     window_end: Option<usize>,
     /// How many steps a window asks for. Narrows on a refused read.
     window: usize,
+    /// The steps the export walks: every `stride`-th from its first.
+    stride: usize,
+    /// The expiration filters a read can push down, when the rows a step
+    /// renders may be narrowed in the warehouse.
+    rows: Option<RowFilter>,
     /// Set once a read fails for a reason a narrower window cannot fix.
     ///
     /// After that the export replays everything. A warehouse that is down will
@@ -483,7 +655,7 @@ struct StoredSteps {
 impl StoredSteps {
     /// Prepares to read one simulation's persisted tape.
     #[must_use]
-    fn new(
+    pub(super) fn new(
         repository: Arc<dyn SimulationSnapshotRepository>,
         simulation: Uuid,
         runtime: Handle,
@@ -495,15 +667,32 @@ impl StoredSteps {
             loaded: VecDeque::new(),
             window_end: None,
             window: SNAPSHOT_WINDOW_STEPS,
+            stride: 1,
+            rows: None,
             degraded: false,
         }
     }
 
+    /// Reads only the steps on `stride` and, when `rows` is given, only the
+    /// expirations it keeps.
+    ///
+    /// A caller that needs every expiration of a step whatever it renders —
+    /// the chained digest hashes the whole step — passes no `rows`.
+    #[must_use = "builders do nothing unless the value is used"]
+    pub(super) fn narrowed(mut self, stride: usize, rows: Option<RowFilter>) -> Self {
+        self.stride = stride;
+        self.rows = rows;
+        self
+    }
+
     /// The persisted snapshot of `step`, when the warehouse has a complete one.
     ///
     /// `last` bounds the prefetch, so a window never reads past the export's
     /// range. Steps are requested in ascending order, which is what lets the
     /// window be consumed from the front instead of indexed.
+    ///
+    /// A row another engine priced is passed over and the step replayed: the
+    /// export serves the running engine's tape, and must not splice two.
     fn take(&mut self, step: usize, last: usize) -> Option<SnapshotRecord> {
         if self.degraded {
             return None;
@@ -518,7 +707,10 @@ impl StoredSteps {
             self.loaded.pop_front();
         }
         match self.loaded.front() {
-            Some(record) if record.step == step => self.loaded.pop_front(),
+            Some(record) if record.step == step => self
+                .loaded
+                .pop_front()
+                .filter(|record| priced_here(&record.engine_version)),
             _ => None,
         }
     }
@@ -533,7 +725,9 @@ impl StoredSteps {
     fn load(&mut self, from: usize, last: usize) {
         self.loaded.clear();
         loop {
-            let to = window_end(from, self.window, last);
+            // A window is a count of *walked* steps, so it spans `stride` times
+            // as much of the tape.
+            let to = window_end(from, self.window.saturating_mul(self.stride), last);
             match self.read(from, to) {
                 Ok(records) => {
                     debug!(
@@ -578,12 +772,14 @@ impl StoredSteps {
     /// Runs one range read on the runtime and waits for it.
     fn read(&self, from: usize, to: usize) -> Result<Vec<SnapshotRecord>, ChainError> {
         let repository = Arc::clone(&self.repository);
-        let simulation = self.simulation;
-        self.runtime.block_on(async move {
-            repository
-                .read_range(simulation, CURRENT_SNAPSHOT_GENERATION, from, to)
-                .await
-        })
+        let mut query =
+            SnapshotRangeQuery::new(self.simulation, CURRENT_SNAPSHOT_GENERATION, from, to)
+                .every(self.stride);
+        if let Some(rows) = &self.rows {
+            query = rows.narrowing(query);
+        }
+        self.runtime
+            .block_on(async move { repository.read_matching(query).await })
     }
 }
 
@@ -591,8 +787,8 @@ impl StoredSteps {
 ///
 /// Dropping this — which is what actix does when the client disconnects —
 /// closes the receiver, so the producer's next send fails and its task ends.
-struct RowStream {
-    receiver: mpsc::Receiver<Result<Vec<u8>, ChainError>>,
+pub(super) struct RowStream {
+    pub(super) receiver: mpsc::Receiver<Result<Vec<u8>, ChainError>>,
 }
 
 impl Stream for RowStream {
@@ -619,24 +815,45 @@ impl Stream for RowStream {
 #[utoipa::path(
     get,
     path = "/api/v2/simulations/{id}/export",
-    description = "Export a simulation's complete tape, or a step range of it, as JSON or CSV. \
+    description = "Export a simulation's complete tape, or a step range of it, as JSON, NDJSON, \
+        CSV, Parquet or an Arrow IPC stream. \
         Read-only: it replays from an immutable snapshot of the effective parameters and never \
         advances the cursor, changes the state or version, or alters what the next peek returns. \
         A simulation that has not been walked at all exports its whole tape. Where snapshot \
         persistence is enabled, an option_chains export serves the steps the warehouse holds from \
         it and replays the rest; the rows are identical either way. JSON is a single array of row \
-        objects; CSV is RFC 4180 with a header row and CRLF line endings. Repeating the same \
-        export yields byte-identical output.",
+        objects; NDJSON is one row object per line; CSV is RFC 4180 with a header row and CRLF line endings; Parquet carries the CSV \
+        columns typed, ZSTD-compressed, in row groups of whole steps; arrow is the IPC streaming \
+        format with the same typed columns, one record batch per window of steps. Any format is \
+        sent gzip- or zstd-compressed when Accept-Encoding asks for it. An option_chains export can \
+        be filtered by rule label, days to expiration, moneyness and a side's delta band; any \
+        export can be projected with `columns` and decimated with `every`. An NDJSON export writes a \
+        {\"continuation\": token} line every 256 rows; repeating the query with after=token \
+        returns exactly the bytes that followed it. Repeating the same export yields \
+        byte-identical output.",
     params(
         ("id" = String, Path, description = "The simulation's identifier"),
         ("dataset" = String, Query, description = "underlying | volatility | option_chains"),
-        ("format" = String, Query, description = "json | csv"),
+        ("format" = String, Query, description = "json | ndjson | csv | parquet | arrow"),
         ("from_step" = Option<usize>, Query, description = "First step, inclusive; defaults to 0"),
-        ("to_step" = Option<usize>, Query, description = "Last step, inclusive; defaults to the final step")
+        ("to_step" = Option<usize>, Query, description = "Last step, inclusive; defaults to the final step"),
+        ("digest" = Option<bool>, Query, description = "Append the chained digest through each row's step as a final `digest` column or key; prices the chains whatever the dataset"),
+        ("labels" = Option<String>, Query, description = "option_chains only: comma-separated rule ids; keeps the expirations carrying any of them"),
+        ("min_dte" = Option<f64>, Query, description = "option_chains only: keeps the expirations at least this many days out"),
+        ("max_dte" = Option<f64>, Query, description = "option_chains only: keeps the expirations at most this many days out"),
+        ("min_moneyness" = Option<f64>, Query, description = "option_chains only: keeps the strikes with strike / spot at or above this"),
+        ("max_moneyness" = Option<f64>, Query, description = "option_chains only: keeps the strikes with strike / spot at or below this"),
+        ("side" = Option<String>, Query, description = "option_chains only: call | put; keeps that side's quote columns and names the delta a band reads"),
+        ("min_delta" = Option<f64>, Query, description = "option_chains only, with side: keeps the strikes whose absolute delta is at least this"),
+        ("max_delta" = Option<f64>, Query, description = "option_chains only, with side: keeps the strikes whose absolute delta is at most this"),
+        ("columns" = Option<String>, Query, description = "Comma-separated columns to keep; returned in the dataset's order"),
+        ("every" = Option<usize>, Query, description = "Export from_step and every k-th step after it; defaults to 1, and must be 1 with digest"),
+        ("after" = Option<String>, Query, description = "ndjson only: a continuation token from an earlier export of the same query; resumes right after its line"),
+        ("Accept-Encoding" = Option<String>, Header, description = "gzip and zstd are offered; zstd wins a tie, and anything else is sent unencoded")
     ),
     responses(
-        (status = 200, description = "The exported rows, streamed", body = String),
-        (status = 400, description = "Unknown dataset or format, or an invalid range; body carries `error` and `field`"),
+        (status = 200, description = "The exported rows, streamed; `Content-Encoding` names the compression, if any, and `X-OCS-Resumable` is `true` for an ndjson export, which after= can resume, and `false` for every other format", body = String),
+        (status = 400, description = "Unknown dataset or format, an invalid range, or a filter, projection, stride or continuation token that does not parse or does not apply to the export; body carries `error` and `field`"),
         (status = 404, description = "Simulation not found"),
         (status = 500, description = "Internal server error")
     )
@@ -664,38 +881,148 @@ pub(crate) async fn export_simulation(
         }
     };
 
+    let export = match start_export(
+        &manager,
+        snapshots.map(|repository| Arc::clone(repository.get_ref())),
+        id,
+        &query,
+        Coding::negotiate(&req),
+    )
+    .await
+    {
+        Ok(export) => export,
+        Err(error) => return map_error(error),
+    };
+
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(export.format.content_type())
+        .insert_header((
+            actix_web::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", export.filename),
+        ))
+        .insert_header((actix_web::http::header::VARY, "Accept-Encoding"))
+        .insert_header((
+            RESUMABLE_HEADER,
+            if export.format == Format::Ndjson {
+                "true"
+            } else {
+                "false"
+            },
+        ));
+    if let Some(coding) = export.coding.header_value() {
+        response.insert_header((actix_web::http::header::CONTENT_ENCODING, coding));
+    }
+    response.streaming(RowStream {
+        receiver: export.receiver,
+    })
+}
+
+/// An export that has been validated and started.
+///
+/// The producer is already running by the time this exists; reading
+/// `receiver` is what drives it, and dropping it is what stops it.
+pub(crate) struct Export {
+    /// The encoding the chunks are in.
+    pub(crate) format: Format,
+    /// The compression applied on top of `format`.
+    pub(crate) coding: Coding,
+    /// The suggested download filename.
+    pub(crate) filename: String,
+    /// The encoded chunks, in order. An `Err` ends the export.
+    pub(crate) receiver: mpsc::Receiver<Result<Vec<u8>, ChainError>>,
+}
+
+/// Validates an export request and spawns its producer.
+///
+/// Shared by the REST handler and the gRPC service so both transports stream
+/// the same bytes through the same bounded channel. Everything that can be
+/// rejected is rejected here, before the first chunk, while the caller can
+/// still answer with a proper error. `coding` compresses the chunks; the gRPC
+/// service passes identity and leaves compression to the transport.
+///
+/// # Errors
+///
+/// Returns [`ChainError::NotFound`] for an unknown simulation, and
+/// [`ChainError::Validation`] naming `from_step`, `to_step` or `every` for a
+/// range the simulation cannot serve, `after` for a continuation token it
+/// cannot resume (see [`super::continuation`]), or the offending parameter for
+/// a filter or projection that does not parse (see [`super::filter`]).
+pub(crate) async fn start_export(
+    manager: &SimulationManager,
+    snapshots: Option<Arc<dyn SimulationSnapshotRepository>>,
+    id: Uuid,
+    query: &ExportQuery,
+    coding: Coding,
+) -> Result<Export, ChainError> {
     // The one read of shared state. From here on the export owns everything it
     // needs, so the simulation may be advanced, deleted or expired without
     // affecting the download in flight.
-    let simulation = match manager.get(id).await {
-        Ok(simulation) => simulation,
-        Err(error) => return map_error(error),
-    };
+    let simulation = manager.get(id).await?;
+    manager.check_engine(&simulation)?;
     let parameters = simulation.parameters.clone();
 
-    let range = match StepRange::resolve(&query, parameters.steps, manager.config().max_export_rows)
-    {
-        Ok(range) => range,
-        Err(error) => return map_error(error),
-    };
+    let range = StepRange::resolve(query, parameters.steps, manager.config().max_export_rows)?;
+    let selection = Selection::parse(query)?;
 
     let dataset = query.dataset;
     let format = query.format;
+    let digest = query.digest;
+    // Fingerprinted after resolving, so a token binds the range the export
+    // actually walked rather than the bounds the client happened to spell.
+    let fingerprint = query_fingerprint(&format!(
+        "dataset={};format={};digest={digest};from={};to={};every={};{}",
+        dataset.as_str(),
+        format.extension(),
+        range.from,
+        range.to,
+        range.stride,
+        selection.canonical()
+    ));
+    let after = match &query.after {
+        Some(_) if format != Format::Ndjson => {
+            return Err(ChainError::Validation {
+                field: "after".to_string(),
+                reason: "only an ndjson export can resume; the other formats do not splice"
+                    .to_string(),
+            });
+        }
+        Some(token) => Some(ContinuationToken::parse(token, id, fingerprint)?.position),
+        None => None,
+    };
+    let walked = match after {
+        Some(after) => range.resumed(after, digest)?,
+        None => range,
+    };
+    // The digest hashes every expiration of a step, so with it the warehouse
+    // must return whole steps and the filters apply only as rows render.
+    let pushed = (!digest).then(|| selection.rows.clone());
+    let encoder = Encoder::new(format, dataset, digest, selection, coding)?
+        .continued(Continuation::new(id, fingerprint, after));
     let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
 
-    // Only the chains dataset can be served from storage; the other two read the
+    // Only chains can be served from storage; a step that needs none reads the
     // factor tape and would pay a round trip for nothing. The handle is taken
     // here, on the runtime, because the producer that uses it will not be on one.
     let stored = snapshots
-        .filter(|_| dataset.needs_chains())
+        .filter(|_| dataset.needs_chains() || digest)
         .map(|repository| {
-            StoredSteps::new(Arc::clone(repository.get_ref()), id, Handle::current())
+            StoredSteps::new(repository, id, Handle::current()).narrowed(walked.stride, pushed)
         });
 
     // Priced chains are minutes of CPU for a long horizon. Producing them on an
-    // Actix worker would block every other request on that thread.
+    // async worker would block every other request on that thread.
     tokio::task::spawn_blocking(move || {
-        if let Err(error) = produce(&parameters, dataset, format, range, stored, &sender) {
+        let produced = produce(
+            &parameters,
+            dataset,
+            encoder,
+            digest,
+            walked,
+            stored,
+            &sender,
+        );
+        if let Err(error) = produced {
             // A send failure means the client went away, which is not an error
             // worth reporting to anyone.
             let _ = sender.blocking_send(Err(error));
@@ -710,45 +1037,104 @@ pub(crate) async fn export_simulation(
         format.extension()
     );
 
-    HttpResponse::Ok()
-        .content_type(format.content_type())
-        .insert_header((
-            actix_web::http::header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{filename}\""),
-        ))
-        .streaming(RowStream { receiver })
+    Ok(Export {
+        format,
+        coding,
+        filename,
+        receiver,
+    })
 }
 
 /// Produces the range and sends every chunk.
 ///
 /// Runs on a blocking thread. Returns as soon as a send fails, which is how a
-/// disconnected client stops the work.
-///
-/// Each step takes its chains from the warehouse when `stored` has them and
-/// replays them otherwise, so a partially persisted tape costs exactly the
-/// pricing of its gaps.
+/// disconnected client stops the work. A chunk the encoder has nothing to show
+/// for yet is not sent.
 fn produce(
     parameters: &SimulationParametersV2,
     dataset: Dataset,
-    format: Format,
+    encoder: Encoder,
+    digest: bool,
     range: StepRange,
-    mut stored: Option<StoredSteps>,
+    stored: Option<StoredSteps>,
     sender: &mpsc::Sender<Result<Vec<u8>, ChainError>>,
 ) -> Result<(), ChainError> {
+    encode_range(
+        parameters,
+        dataset,
+        encoder,
+        digest,
+        range,
+        stored,
+        |chunk| Ok(chunk.is_empty() || sender.blocking_send(Ok(chunk)).is_ok()),
+    )?;
+    Ok(())
+}
+
+/// Encodes one dataset over a range, handing `emit` every chunk in order —
+/// the document's opening and closing bytes included, and possibly empty.
+///
+/// Runs on a blocking thread. `emit` returning `false` stops the work; the
+/// result says whether the document was written to its end. With `digest`,
+/// every row also carries the chained digest through its step.
+pub(super) fn encode_range(
+    parameters: &SimulationParametersV2,
+    dataset: Dataset,
+    mut encoder: Encoder,
+    digest: bool,
+    range: StepRange,
+    stored: Option<StoredSteps>,
+    mut emit: impl FnMut(Vec<u8>) -> Result<bool, ChainError>,
+) -> Result<bool, ChainError> {
+    if !emit(encoder.prologue()?)? {
+        return Ok(false);
+    }
+
+    let mut chain = digest.then(TapeDigest::new);
+    let finished = walk(
+        parameters,
+        dataset.needs_chains() || digest,
+        range,
+        stored,
+        |row, chains| {
+            let digest = match (&mut chain, chains) {
+                (Some(chain), Some(chains)) => {
+                    chain.push(step_digest(parameters, row, chains)?);
+                    Some(chain.to_hex())
+                }
+                _ => None,
+            };
+            emit(encoder.rows(parameters, row, chains, digest.as_deref())?)
+        },
+    )?;
+
+    if !finished {
+        return Ok(false);
+    }
+    emit(encoder.finish()?)
+}
+
+/// Walks a range in step order, handing `visit` each factor row and — when
+/// `with_chains` — the step's chains.
+///
+/// Runs on a blocking thread. Each step takes its chains from the warehouse
+/// when `stored` has them and replays them otherwise, so a partially persisted
+/// tape costs exactly the pricing of its gaps. `visit` returning `false` stops
+/// the walk; the result says whether it reached the end of the range.
+pub(super) fn walk(
+    parameters: &SimulationParametersV2,
+    with_chains: bool,
+    range: StepRange,
+    mut stored: Option<StoredSteps>,
+    mut visit: impl FnMut(&FactorRow, Option<StepChains<'_>>) -> Result<bool, ChainError>,
+) -> Result<bool, ChainError> {
     let tape = FactorTape::build(parameters, &parameters.method)?;
-    let builder = if dataset.needs_chains() {
+    let builder = if with_chains {
         Some(SeriesBuilder::new(parameters, &tape)?)
     } else {
         None
     };
 
-    let mut writer = Writer::new(format, dataset)?;
-    if let Some(chunk) = writer.prologue()?
-        && sender.blocking_send(Ok(chunk)).is_err()
-    {
-        return Ok(());
-    }
-
     let mut served_from_storage: usize = 0;
     for step in range.steps() {
         let row = tape
@@ -776,60 +1162,189 @@ fn produce(
                 .ok_or_else(|| ChainError::Internal("the step counter overflowed".to_string()))?;
         }
 
-        let chunk = writer.rows(parameters, row.step, row, chains)?;
-        if !chunk.is_empty() && sender.blocking_send(Ok(chunk)).is_err() {
-            return Ok(());
+        if !visit(row, chains)? {
+            return Ok(false);
         }
     }
 
-    if let Some(chunk) = writer.epilogue()?
-        && sender.blocking_send(Ok(chunk)).is_err()
-    {
-        return Ok(());
-    }
-
     if stored.is_some() {
         debug!(
             from_step = range.from,
             to_step = range.to,
             served_from_storage,
-            "Finished a v2 export"
+            "Finished a v2 tape walk"
         );
     }
-    Ok(())
+    Ok(true)
+}
+
+/// A format's writer followed by the requested compression: the whole path
+/// from a priced step to the bytes on the channel.
+pub(super) struct Encoder {
+    writer: Writer,
+    selection: Selection,
+    compressor: Compressor,
+}
+
+impl Encoder {
+    /// Creates an encoder for a dataset, format, selection and coding.
+    pub(super) fn new(
+        format: Format,
+        dataset: Dataset,
+        digest: bool,
+        selection: Selection,
+        coding: Coding,
+    ) -> Result<Self, ChainError> {
+        Ok(Self {
+            writer: Writer::new(format, dataset, digest, &selection)?,
+            selection,
+            compressor: Compressor::new(coding)?,
+        })
+    }
+
+    /// Interleaves continuation tokens with the rows and sends only the rows
+    /// `continuation` keeps. Only NDJSON carries tokens; the other writers
+    /// ignore this.
+    #[must_use = "builders do nothing unless the value is used"]
+    pub(super) fn continued(mut self, continuation: Continuation) -> Self {
+        if let Writer::Ndjson { continued, .. } = &mut self.writer {
+            *continued = Some(continuation);
+        }
+        self
+    }
+
+    /// The compressed bytes that open the document; possibly none.
+    fn prologue(&mut self) -> Result<Vec<u8>, ChainError> {
+        match self.writer.prologue(&self.selection)? {
+            Some(chunk) => self.compressor.encode(chunk),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// The compressed bytes one step contributes; possibly none.
+    fn rows(
+        &mut self,
+        parameters: &SimulationParametersV2,
+        row: &FactorRow,
+        chains: Option<StepChains<'_>>,
+        chained: Option<&str>,
+    ) -> Result<Vec<u8>, ChainError> {
+        let chunk = self
+            .writer
+            .rows(parameters, row, chains, &self.selection, chained)?;
+        self.compressor.encode(chunk)
+    }
+
+    /// The bytes that close the document, the compressor's trailer included.
+    fn finish(mut self) -> Result<Vec<u8>, ChainError> {
+        let mut chunk = match self.writer.epilogue()? {
+            Some(chunk) => self.compressor.encode(chunk)?,
+            None => Vec::new(),
+        };
+        chunk.extend(self.compressor.finish()?);
+        Ok(chunk)
+    }
 }
 
 /// Encodes rows in the requested format.
+///
+/// `digest` says whether every row ends with a `digest` column or key. The
+/// columnar writers fix their projected schema up front; the text writers
+/// project each row as it is rendered.
 enum Writer {
     /// A streamed JSON array. Tracks whether a comma is due.
-    Json { dataset: Dataset, first: bool },
+    Json {
+        dataset: Dataset,
+        digest: bool,
+        first: bool,
+    },
+    /// Newline-delimited JSON: every row is a complete line, so there is
+    /// nothing to open or close. `continued` places the token lines and the
+    /// resume point, when the export issues them.
+    Ndjson {
+        dataset: Dataset,
+        digest: bool,
+        continued: Option<Continuation>,
+    },
     /// RFC 4180 CSV. A writer is built per chunk rather than kept: `csv::Writer`
     /// only surrenders its buffer by consuming itself, and constructing one is
     /// cheap next to pricing a chain.
-    Csv { dataset: Dataset },
+    Csv { dataset: Dataset, digest: bool },
+    /// Apache Parquet. Every step is one batch; a chunk is whatever a closed
+    /// row group left in the sink, so most steps send nothing. Boxed: the
+    /// writer's state dwarfs the other variants.
+    Parquet {
+        dataset: Dataset,
+        digest: bool,
+        schema: SchemaRef,
+        sink: Box<ParquetSink>,
+    },
+    /// An Arrow IPC stream. The schema goes out before the first step and a
+    /// chunk is a closed window's batch, so most steps send nothing.
+    Arrow { digest: bool, sink: Box<ArrowSink> },
 }
 
 impl Writer {
-    /// Creates a writer for a dataset and format.
-    fn new(format: Format, dataset: Dataset) -> Result<Self, ChainError> {
+    /// Creates a writer for a dataset, format and selection.
+    fn new(
+        format: Format,
+        dataset: Dataset,
+        digest: bool,
+        selection: &Selection,
+    ) -> Result<Self, ChainError> {
         Ok(match format {
             Format::Json => Writer::Json {
                 dataset,
+                digest,
                 first: true,
             },
-            Format::Csv => Writer::Csv { dataset },
+            Format::Ndjson => Writer::Ndjson {
+                dataset,
+                digest,
+                continued: None,
+            },
+            Format::Csv => Writer::Csv { dataset, digest },
+            Format::Parquet => {
+                let schema = columnar::projected_schema(dataset, digest, &selection.columns)?;
+                Writer::Parquet {
+                    dataset,
+                    digest,
+                    sink: Box::new(ParquetSink::new(Arc::clone(&schema))?),
+                    schema,
+                }
+            }
+            Format::Arrow => Writer::Arrow {
+                digest,
+                sink: Box::new(ArrowSink::new(
+                    columnar::projected_schema(dataset, digest, &selection.columns)?,
+                    dataset,
+                )?),
+            },
         })
     }
 
     /// The bytes that open the document, if any.
-    fn prologue(&mut self) -> Result<Option<Vec<u8>>, ChainError> {
+    fn prologue(&mut self, selection: &Selection) -> Result<Option<Vec<u8>>, ChainError> {
         match self {
             Writer::Json { .. } => Ok(Some(b"[".to_vec())),
-            Writer::Csv { dataset } => {
-                let header: Vec<String> =
-                    dataset.header().iter().map(ToString::to_string).collect();
+            Writer::Ndjson { .. } => Ok(None),
+            Writer::Csv { dataset, digest } => {
+                let mut header: Vec<String> = selection
+                    .columns
+                    .header(*dataset)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                if *digest {
+                    header.push("digest".to_string());
+                }
                 Ok(Some(encode_csv(&[header])?))
             }
+            // The magic bytes go out with the first row group.
+            Writer::Parquet { .. } => Ok(None),
+            // The schema message, so a reader knows the columns before the
+            // first step is priced.
+            Writer::Arrow { sink, .. } => Ok(Some(sink.drain())),
         }
     }
 
@@ -837,7 +1352,10 @@ impl Writer {
     fn epilogue(&mut self) -> Result<Option<Vec<u8>>, ChainError> {
         match self {
             Writer::Json { .. } => Ok(Some(b"]".to_vec())),
+            Writer::Ndjson { .. } => Ok(None),
             Writer::Csv { .. } => Ok(None),
+            Writer::Parquet { sink, .. } => Ok(Some(sink.finish()?)),
+            Writer::Arrow { sink, .. } => Ok(Some(sink.finish()?)),
         }
     }
 
@@ -847,36 +1365,104 @@ impl Writer {
     /// chains: it is the tape's instant, the same one a stored record was
     /// written from, and taking it from one place keeps the two sources
     /// rendering identically by construction.
+    ///
+    /// `chained` is the chained digest through this step, written to every row
+    /// when the writer carries the column. It still certifies the whole step,
+    /// however few of its rows `selection` keeps.
     fn rows(
         &mut self,
         parameters: &SimulationParametersV2,
-        step: usize,
-        row: &crate::domain::factors::FactorRow,
+        row: &FactorRow,
         chains: Option<StepChains<'_>>,
+        selection: &Selection,
+        chained: Option<&str>,
     ) -> Result<Vec<u8>, ChainError> {
+        let step = row.step;
+        let filter = &selection.rows;
         let simulated_at = render_instant(row.simulated_at);
         let symbol = parameters.symbol.as_str();
+        let chained = chained.unwrap_or_default();
 
         match self {
-            Writer::Json { dataset, first } => {
-                let values = json_rows(*dataset, step, &simulated_at, symbol, row, chains);
+            Writer::Json {
+                dataset,
+                digest,
+                first,
+            } => {
                 let mut chunk = Vec::new();
-                for value in values {
+                for (_, mut value) in
+                    json_rows(*dataset, step, &simulated_at, symbol, row, chains, filter)
+                {
+                    selection.columns.retain(*dataset, &mut value);
+                    if *digest {
+                        append_digest(&mut value, chained);
+                    }
                     if !*first {
                         chunk.push(b',');
                     }
                     *first = false;
-                    let encoded = serde_json::to_vec(&value).map_err(|e| {
-                        ChainError::Internal(format!("failed to encode an export row: {e}"))
-                    })?;
-                    chunk.extend_from_slice(&encoded);
+                    chunk.extend_from_slice(&encode_json(&value)?);
+                }
+                Ok(chunk)
+            }
+            Writer::Ndjson {
+                dataset,
+                digest,
+                continued,
+            } => {
+                let mut chunk = Vec::new();
+                for (position, mut value) in
+                    json_rows(*dataset, step, &simulated_at, symbol, row, chains, filter)
+                {
+                    if continued
+                        .as_ref()
+                        .is_some_and(|continued| !continued.keeps(position))
+                    {
+                        continue;
+                    }
+                    selection.columns.retain(*dataset, &mut value);
+                    if *digest {
+                        append_digest(&mut value, chained);
+                    }
+                    chunk.extend_from_slice(&encode_json(&value)?);
+                    chunk.push(b'\n');
+                    if let Some(token) = continued
+                        .as_mut()
+                        .and_then(|continued| continued.sent(position))
+                    {
+                        chunk.extend_from_slice(&encode_json(&token)?);
+                        chunk.push(b'\n');
+                    }
                 }
                 Ok(chunk)
             }
-            Writer::Csv { dataset } => {
-                let records = csv_rows(*dataset, step, &simulated_at, symbol, row, chains);
+            Writer::Csv { dataset, digest } => {
+                let mut records: Vec<Vec<String>> =
+                    csv_rows(*dataset, step, &simulated_at, symbol, row, chains, filter)
+                        .into_iter()
+                        .map(|record| selection.columns.pick(record))
+                        .collect();
+                if *digest {
+                    for record in &mut records {
+                        record.push(chained.to_string());
+                    }
+                }
                 encode_csv(&records)
             }
+            Writer::Parquet {
+                dataset,
+                digest,
+                schema,
+                sink,
+            } => {
+                let digest = digest.then_some(chained);
+                let batch =
+                    columnar::step_batch(schema, *dataset, symbol, row, chains, filter, digest)?;
+                sink.write(&batch)
+            }
+            Writer::Arrow { digest, sink } => {
+                sink.push(symbol, row, chains, filter, digest.then_some(chained))
+            }
         }
     }
 }
@@ -886,7 +1472,7 @@ impl Writer {
 /// CRLF terminators, as the RFC specifies, and quoting left entirely to the
 /// crate — handling commas, quotes and newlines by hand is the class of bug the
 /// dependency exists to remove.
-fn encode_csv(records: &[Vec<String>]) -> Result<Vec<u8>, ChainError> {
+pub(super) fn encode_csv(records: &[Vec<String>]) -> Result<Vec<u8>, ChainError> {
     let mut writer = csv::WriterBuilder::new()
         .terminator(csv::Terminator::CRLF)
         .from_writer(Vec::new());
@@ -904,56 +1490,87 @@ fn csv_error(error: csv::Error) -> ChainError {
     ChainError::Internal(format!("failed to encode an export row: {error}"))
 }
 
-/// The JSON objects one step contributes.
+/// Adds the chained digest to a row object as a final `digest` key.
+fn append_digest(value: &mut serde_json::Value, chained: &str) {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("digest".to_string(), chained.into());
+    }
+}
+
+/// Encodes one row object compactly — never across lines, which is what lets
+/// NDJSON delimit rows with a bare newline.
+fn encode_json(value: &serde_json::Value) -> Result<Vec<u8>, ChainError> {
+    serde_json::to_vec(value)
+        .map_err(|e| ChainError::Internal(format!("failed to encode an export row: {e}")))
+}
+
+/// The JSON objects one step contributes, of the rows `filter` keeps, each at
+/// its position in the export.
 fn json_rows(
     dataset: Dataset,
     step: usize,
     simulated_at: &str,
     symbol: &str,
-    row: &crate::domain::factors::FactorRow,
+    row: &FactorRow,
     chains: Option<StepChains<'_>>,
-) -> Vec<serde_json::Value> {
+    filter: &RowFilter,
+) -> Vec<(Position, serde_json::Value)> {
+    let at = |expiration, strike| Position {
+        step,
+        expiration,
+        strike,
+    };
     match dataset {
-        Dataset::Underlying => vec![serde_json::json!({
+        Dataset::Underlying => vec![(
+            at(0, 0),
+            serde_json::json!({
             "step": step,
             "simulated_at": simulated_at,
             "symbol": symbol,
             "price": row.spot.to_f64(),
-        })],
-        Dataset::Volatility => vec![serde_json::json!({
+            }),
+        )],
+        Dataset::Volatility => vec![(
+            at(0, 0),
+            serde_json::json!({
             "step": step,
             "simulated_at": simulated_at,
             "symbol": symbol,
             "base_volatility": row.base_volatility.to_f64(),
-        })],
+            }),
+        )],
         Dataset::OptionChains => {
             let Some(chains) = chains else {
                 return Vec::new();
             };
+            let spot = row.spot.to_f64();
             let mut rows = Vec::new();
-            for expiration in chains.expirations() {
+            for (ordinal, expiration) in filter.expirations(chains).enumerate() {
                 let expires_at = render_instant(expiration.expires_at);
                 let labels = expiration.labels.join("|");
-                for quote in expiration.quotes.quotes() {
-                    rows.push(serde_json::json!({
-                        "step": step,
-                        "simulated_at": simulated_at,
-                        "symbol": symbol,
-                        "expires_at": expires_at,
-                        "labels": labels,
-                        "days_to_expiration": expiration.days_to_expiration,
-                        "strike": quote.strike,
-                        "implied_volatility": quote.implied_volatility,
-                        "call_bid": quote.call_bid,
-                        "call_ask": quote.call_ask,
-                        "call_mid": quote.call_mid,
-                        "call_delta": quote.call_delta,
-                        "put_bid": quote.put_bid,
-                        "put_ask": quote.put_ask,
-                        "put_mid": quote.put_mid,
-                        "put_delta": quote.put_delta,
-                        "gamma": quote.gamma,
-                    }));
+                for (strike, quote) in filter.quotes(expiration, spot).enumerate() {
+                    rows.push((
+                        at(ordinal, strike),
+                        serde_json::json!({
+                            "step": step,
+                            "simulated_at": simulated_at,
+                            "symbol": symbol,
+                            "expires_at": expires_at,
+                            "labels": labels,
+                            "days_to_expiration": expiration.days_to_expiration,
+                            "strike": quote.strike,
+                            "implied_volatility": quote.implied_volatility,
+                            "call_bid": quote.call_bid,
+                            "call_ask": quote.call_ask,
+                            "call_mid": quote.call_mid,
+                            "call_delta": quote.call_delta,
+                            "put_bid": quote.put_bid,
+                            "put_ask": quote.put_ask,
+                            "put_mid": quote.put_mid,
+                            "put_delta": quote.put_delta,
+                            "gamma": quote.gamma,
+                        }),
+                    ));
                 }
             }
             rows
@@ -961,14 +1578,16 @@ fn json_rows(
     }
 }
 
-/// The CSV records one step contributes, in the header's order.
-fn csv_rows(
+/// The CSV records one step contributes, in the header's order, of the rows
+/// `filter` keeps.
+pub(super) fn csv_rows(
     dataset: Dataset,
     step: usize,
     simulated_at: &str,
     symbol: &str,
-    row: &crate::domain::factors::FactorRow,
+    row: &FactorRow,
     chains: Option<StepChains<'_>>,
+    filter: &RowFilter,
 ) -> Vec<Vec<String>> {
     match dataset {
         Dataset::Underlying => vec![vec![
@@ -987,13 +1606,14 @@ fn csv_rows(
             let Some(chains) = chains else {
                 return Vec::new();
             };
+            let spot = row.spot.to_f64();
             let mut records = Vec::new();
-            for expiration in chains.expirations() {
+            for expiration in filter.expirations(chains) {
                 let expires_at = render_instant(expiration.expires_at);
                 // Joined with `|` rather than `,` so a multi-label chain stays
                 // one column without depending on quoting to do it.
                 let labels = expiration.labels.join("|");
-                for quote in expiration.quotes.quotes() {
+                for quote in filter.quotes(expiration, spot) {
                     records.push(vec![
                         step.to_string(),
                         simulated_at.to_string(),
@@ -1090,10 +1710,13 @@ mod tests {
     }
 
     macro_rules! create {
-        ($app:expr) => {{
+        ($app:expr) => {
+            create!($app, reference_body())
+        };
+        ($app:expr, $body:expr) => {{
             let request = actix_test::TestRequest::post()
                 .uri("/api/v2/simulations")
-                .set_json(reference_body())
+                .set_json($body)
                 .to_request();
             let response = actix_test::call_service(&$app, request).await;
             assert_eq!(response.status(), StatusCode::CREATED);
@@ -1119,6 +1742,37 @@ mod tests {
         }};
     }
 
+    /// As `export!`, keeping the body as bytes — the only faithful view of a
+    /// binary format.
+    macro_rules! export_bytes {
+        ($app:expr, $id:expr, $query:expr) => {{
+            let uri = format!("/api/v2/simulations/{}/export?{}", $id, $query);
+            let response = actix_test::call_service(
+                &$app,
+                actix_test::TestRequest::get().uri(&uri).to_request(),
+            )
+            .await;
+            let status = response.status();
+            (status, actix_test::read_body(response).await)
+        }};
+    }
+
+    /// Every row of a Parquet export, in file order.
+    fn parquet_batches(body: web::Bytes) -> Vec<arrow_array::RecordBatch> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let reader = match ParquetRecordBatchReaderBuilder::try_new(body)
+            .and_then(|builder| builder.build())
+        {
+            Ok(reader) => reader,
+            Err(error) => panic!("a Parquet export must open: {error}"),
+        };
+        match reader.collect::<Result<Vec<_>, _>>() {
+            Ok(batches) => batches,
+            Err(error) => panic!("a Parquet export must read: {error}"),
+        }
+    }
+
     /// Counts CSV data rows, excluding the header and the trailing terminator.
     fn csv_rows_of(body: &str) -> usize {
         body.split("\r\n").filter(|line| !line.is_empty()).count() - 1
@@ -1286,71 +1940,349 @@ mod tests {
         }
     }
 
-    /// Two simulations with the same seed export identical tapes.
+    /// A Parquet export carries the CSV's rows, typed: the same count, the
+    /// same strikes, and the documented content type and extension.
     #[actix_web::test]
-    async fn test_the_same_seed_exports_an_identical_tape() {
-        let app = v2_service!();
-        let first = create!(app);
-        let second = create!(app);
-        assert_ne!(first, second);
-
-        let (_, left) = export!(app, first, "dataset=option_chains&format=csv");
-        let (_, right) = export!(app, second, "dataset=option_chains&format=csv");
-
-        assert_eq!(left, right);
-    }
+    async fn test_a_parquet_export_carries_the_csv_rows() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::Float64Type;
 
-    /// A different seed exports a different tape, while the schedule-driven
-    /// expirations stay the same.
-    #[actix_web::test]
-    async fn test_a_different_seed_exports_a_different_tape() {
         let app = v2_service!();
-        let baseline = create!(app);
+        let id = create!(app);
 
-        let mut body = reference_body();
-        body["seed"] = json!(43);
         let response = actix_test::call_service(
             &app,
-            actix_test::TestRequest::post()
-                .uri("/api/v2/simulations")
-                .set_json(body)
+            actix_test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v2/simulations/{id}/export?dataset=option_chains&format=parquet"
+                ))
                 .to_request(),
         )
         .await;
-        let created: Value = actix_test::read_body_json(response).await;
-        let other = match created.get("id").and_then(Value::as_str) {
-            Some(id) => id.to_string(),
-            None => panic!("the response must carry an id"),
-        };
-
-        let (_, left) = export!(app, baseline, "dataset=underlying&format=json");
-        let (_, right) = export!(app, other, "dataset=underlying&format=json");
-        assert_ne!(left, right, "a different seed must move the market path");
-
-        let expiries = |body: &str| -> Vec<String> {
-            json_rows_of(body)
-                .iter()
-                .filter_map(|row| row.get("expires_at").and_then(Value::as_str))
-                .map(ToString::to_string)
-                .collect()
-        };
-        let (_, left_chains) = export!(app, baseline, "dataset=option_chains&format=json");
-        let (_, right_chains) = export!(app, other, "dataset=option_chains&format=json");
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
         assert_eq!(
-            expiries(&left_chains),
-            expiries(&right_chains),
-            "expirations come from the schedule, not the seed"
+            headers
+                .get(actix_web::http::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok()),
+            Some("application/vnd.apache.parquet")
         );
+        let disposition = headers
+            .get(actix_web::http::header::CONTENT_DISPOSITION)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        assert!(disposition.ends_with(".parquet\""), "{disposition}");
+        let batches = parquet_batches(actix_test::read_body(response).await);
+
+        let (_, csv) = export!(app, id, "dataset=option_chains&format=csv");
+        let csv_strikes: Vec<f64> = csv
+            .split("\r\n")
+            .skip(1)
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| line.split(',').nth(6).and_then(|field| field.parse().ok()))
+            .collect();
+        let mut parquet_strikes: Vec<f64> = Vec::new();
+        for batch in &batches {
+            match batch.column_by_name("strike") {
+                Some(column) => {
+                    parquet_strikes.extend(column.as_primitive::<Float64Type>().values().iter())
+                }
+                None => panic!("a Parquet export must carry a strike column"),
+            }
+        }
+
+        assert_eq!(csv_rows_of(&csv), parquet_strikes.len());
+        assert_eq!(csv_strikes, parquet_strikes);
     }
 
-    /// An export changes nothing: not the cursor, not the state, not the
-    /// revision, and not what the next peek returns.
+    /// Repeating a Parquet export yields byte-identical output, digest column
+    /// included.
     #[actix_web::test]
-    async fn test_an_export_changes_nothing() {
+    async fn test_a_repeated_parquet_export_is_byte_identical() {
         let app = v2_service!();
         let id = create!(app);
 
-        let before: Value = {
+        for query in [
+            "dataset=underlying&format=parquet",
+            "dataset=option_chains&format=parquet&digest=true",
+        ] {
+            let (status, first) = export_bytes!(app, id, query);
+            let (_, second) = export_bytes!(app, id, query);
+            assert_eq!(status, StatusCode::OK, "{query}");
+            assert_eq!(first, second, "{query} must be byte-identical on a repeat");
+        }
+    }
+
+    /// An Arrow export streams the CSV's rows as IPC record batches with the
+    /// dataset's fixed schema, and repeats byte for byte.
+    #[actix_web::test]
+    async fn test_an_arrow_export_streams_the_csv_rows() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::Float64Type;
+        use arrow_ipc::reader::StreamReader;
+
+        let app = v2_service!();
+        let id = create!(app);
+
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v2/simulations/{id}/export?dataset=option_chains&format=arrow"
+                ))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(actix_web::http::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok()),
+            Some("application/vnd.apache.arrow.stream")
+        );
+        let body = actix_test::read_body(response).await;
+
+        let reader = match StreamReader::try_new(body.as_ref(), None) {
+            Ok(reader) => reader,
+            Err(error) => panic!("an Arrow export must open: {error}"),
+        };
+        assert_eq!(
+            reader.schema(),
+            crate::api::rest::columnar::schema(Dataset::OptionChains, false)
+        );
+        let mut strikes: Vec<f64> = Vec::new();
+        for batch in reader {
+            match batch {
+                Ok(batch) => match batch.column_by_name("strike") {
+                    Some(column) => {
+                        strikes.extend(column.as_primitive::<Float64Type>().values().iter())
+                    }
+                    None => panic!("an Arrow export must carry a strike column"),
+                },
+                Err(error) => panic!("an Arrow export must read: {error}"),
+            }
+        }
+        let (_, csv) = export!(app, id, "dataset=option_chains&format=csv");
+        assert_eq!(csv_rows_of(&csv), strikes.len());
+
+        let (_, again) = export_bytes!(app, id, "dataset=option_chains&format=arrow");
+        assert_eq!(
+            body, again,
+            "an Arrow export must be byte-identical on a repeat"
+        );
+    }
+
+    /// An NDJSON export carries the JSON array's rows, one compact object per
+    /// newline-terminated line.
+    #[actix_web::test]
+    async fn test_an_ndjson_export_carries_the_json_rows_one_per_line() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        for dataset in ["underlying", "volatility", "option_chains"] {
+            let (status, body) = export!(
+                app,
+                id,
+                format!("dataset={dataset}&format=ndjson&digest=true")
+            );
+            let (_, array) = export!(
+                app,
+                id,
+                format!("dataset={dataset}&format=json&digest=true")
+            );
+
+            assert_eq!(status, StatusCode::OK, "{dataset}");
+            assert!(
+                body.ends_with('\n'),
+                "{dataset}: every line must be terminated"
+            );
+            let lines: Vec<Value> = body
+                .lines()
+                .map(|line| match serde_json::from_str(line) {
+                    Ok(value) => value,
+                    Err(error) => panic!("{dataset}: every line must be a row: {error}"),
+                })
+                .collect();
+            assert_eq!(lines, json_rows_of(&array), "{dataset}");
+        }
+    }
+
+    /// `Accept-Encoding` compresses any format, the headers say so, and the
+    /// body decodes to exactly the unencoded export — repeatably.
+    #[actix_web::test]
+    async fn test_a_compressed_export_decodes_to_the_unencoded_bytes() {
+        use std::io::Read;
+
+        let app = v2_service!();
+        let id = create!(app);
+
+        for format in ["csv", "ndjson", "parquet", "arrow"] {
+            let query = format!("dataset=option_chains&format={format}");
+            let (_, plain) = export_bytes!(app, id, query);
+
+            for coding in ["gzip", "zstd"] {
+                let request = || {
+                    actix_test::TestRequest::get()
+                        .uri(&format!("/api/v2/simulations/{id}/export?{query}"))
+                        .insert_header((actix_web::http::header::ACCEPT_ENCODING, coding))
+                        .to_request()
+                };
+                let response = actix_test::call_service(&app, request()).await;
+                assert_eq!(response.status(), StatusCode::OK);
+                let header = |name| {
+                    response
+                        .headers()
+                        .get(name)
+                        .and_then(|value| value.to_str().ok())
+                        .map(ToString::to_string)
+                };
+                assert_eq!(
+                    header(actix_web::http::header::CONTENT_ENCODING).as_deref(),
+                    Some(coding)
+                );
+                assert_eq!(
+                    header(actix_web::http::header::VARY).as_deref(),
+                    Some("Accept-Encoding")
+                );
+                let body = actix_test::read_body(response).await;
+
+                let mut decoded = Vec::new();
+                let read = match coding {
+                    "gzip" => flate2::read::GzDecoder::new(body.as_ref()).read_to_end(&mut decoded),
+                    _ => zstd::stream::read::Decoder::new(body.as_ref())
+                        .and_then(|mut decoder| decoder.read_to_end(&mut decoded)),
+                };
+                if let Err(error) = read {
+                    panic!("a {coding} {format} export must decode: {error}");
+                }
+                assert_eq!(decoded, plain.as_ref(), "{coding} {format}");
+
+                let again =
+                    actix_test::read_body(actix_test::call_service(&app, request()).await).await;
+                assert_eq!(body, again, "{coding} {format} must repeat byte for byte");
+            }
+        }
+
+        let (_, csv) = export_bytes!(app, id, "dataset=option_chains&format=csv");
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v2/simulations/{id}/export?dataset=option_chains&format=csv"
+                ))
+                .insert_header((actix_web::http::header::ACCEPT_ENCODING, "zstd"))
+                .to_request(),
+        )
+        .await;
+        let compressed = actix_test::read_body(response).await;
+        assert!(
+            compressed.len() * 2 < csv.len(),
+            "a chain CSV must compress well: {} of {} bytes",
+            compressed.len(),
+            csv.len()
+        );
+    }
+
+    /// Without `Accept-Encoding` the export is sent as it is, and still says
+    /// it varies on the header.
+    #[actix_web::test]
+    async fn test_an_unencoded_export_carries_no_content_encoding() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v2/simulations/{id}/export?dataset=underlying&format=csv"
+                ))
+                .insert_header((actix_web::http::header::ACCEPT_ENCODING, "br"))
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response
+                .headers()
+                .get(actix_web::http::header::CONTENT_ENCODING)
+                .is_none()
+        );
+        assert!(
+            response
+                .headers()
+                .get(actix_web::http::header::VARY)
+                .is_some()
+        );
+    }
+
+    /// Two simulations with the same seed export identical tapes.
+    #[actix_web::test]
+    async fn test_the_same_seed_exports_an_identical_tape() {
+        let app = v2_service!();
+        let first = create!(app);
+        let second = create!(app);
+        assert_ne!(first, second);
+
+        let (_, left) = export!(app, first, "dataset=option_chains&format=csv");
+        let (_, right) = export!(app, second, "dataset=option_chains&format=csv");
+
+        assert_eq!(left, right);
+    }
+
+    /// A different seed exports a different tape, while the schedule-driven
+    /// expirations stay the same.
+    #[actix_web::test]
+    async fn test_a_different_seed_exports_a_different_tape() {
+        let app = v2_service!();
+        let baseline = create!(app);
+
+        let mut body = reference_body();
+        body["seed"] = json!(43);
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri("/api/v2/simulations")
+                .set_json(body)
+                .to_request(),
+        )
+        .await;
+        let created: Value = actix_test::read_body_json(response).await;
+        let other = match created.get("id").and_then(Value::as_str) {
+            Some(id) => id.to_string(),
+            None => panic!("the response must carry an id"),
+        };
+
+        let (_, left) = export!(app, baseline, "dataset=underlying&format=json");
+        let (_, right) = export!(app, other, "dataset=underlying&format=json");
+        assert_ne!(left, right, "a different seed must move the market path");
+
+        let expiries = |body: &str| -> Vec<String> {
+            json_rows_of(body)
+                .iter()
+                .filter_map(|row| row.get("expires_at").and_then(Value::as_str))
+                .map(ToString::to_string)
+                .collect()
+        };
+        let (_, left_chains) = export!(app, baseline, "dataset=option_chains&format=json");
+        let (_, right_chains) = export!(app, other, "dataset=option_chains&format=json");
+        assert_eq!(
+            expiries(&left_chains),
+            expiries(&right_chains),
+            "expirations come from the schedule, not the seed"
+        );
+    }
+
+    /// An export changes nothing: not the cursor, not the state, not the
+    /// revision, and not what the next peek returns.
+    #[actix_web::test]
+    async fn test_an_export_changes_nothing() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let before: Value = {
             let response = actix_test::call_service(
                 &app,
                 actix_test::TestRequest::get()
@@ -1508,7 +2440,7 @@ mod tests {
         let (status, _) = export!(app, id, "dataset=greeks&format=json");
         assert_eq!(status, StatusCode::BAD_REQUEST);
 
-        let (status, _) = export!(app, id, "dataset=underlying&format=parquet");
+        let (status, _) = export!(app, id, "dataset=underlying&format=xlsx");
         assert_eq!(status, StatusCode::BAD_REQUEST);
     }
 
@@ -1598,6 +2530,364 @@ mod tests {
         }
     }
 
+    // ---- filters, projection and decimation -------------------------------
+
+    /// A label filter keeps only the expirations carrying one of the labels.
+    #[actix_web::test]
+    async fn test_a_label_filter_keeps_only_matching_expirations() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (_, everything) = export!(app, id, "dataset=option_chains&format=json");
+        let (status, body) = export!(app, id, "dataset=option_chains&format=json&labels=zero_dte");
+
+        assert_eq!(status, StatusCode::OK);
+        let rows = json_rows_of(&body);
+        assert!(!rows.is_empty());
+        assert!(rows.len() < json_rows_of(&everything).len());
+        for row in &rows {
+            let labels = row["labels"].as_str().unwrap_or_default();
+            assert!(labels.split('|').any(|label| label == "zero_dte"), "{row}");
+        }
+    }
+
+    /// Moneyness reads each strike against its own step's spot, and the DTE
+    /// band reads the expiration.
+    #[actix_web::test]
+    async fn test_a_moneyness_and_dte_band_narrow_the_rows() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (_, underlying) = export!(app, id, "dataset=underlying&format=json");
+        let spots: Vec<f64> = json_rows_of(&underlying)
+            .iter()
+            .map(|row| row["price"].as_f64().unwrap_or_default())
+            .collect();
+
+        let (status, body) = export!(
+            app,
+            id,
+            "dataset=option_chains&format=json&min_moneyness=0.995&max_moneyness=1.005&min_dte=1"
+        );
+
+        assert_eq!(status, StatusCode::OK);
+        let rows = json_rows_of(&body);
+        assert!(!rows.is_empty());
+        for row in &rows {
+            let step = row["step"].as_u64().unwrap_or_default() as usize;
+            let moneyness = row["strike"].as_f64().unwrap_or_default() / spots[step];
+            assert!((0.995..=1.005).contains(&moneyness), "{row}");
+            assert!(row["days_to_expiration"].as_f64().unwrap_or_default() >= 1.0);
+        }
+    }
+
+    /// A delta band reads the absolute delta of the side named, and the side
+    /// drops the other side's columns.
+    #[actix_web::test]
+    async fn test_a_delta_band_reads_the_named_side() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (status, body) = export!(app, id, "dataset=option_chains&format=json&min_delta=0.3");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("\"field\":\"side\""), "{body}");
+
+        let (status, body) = export!(
+            app,
+            id,
+            "dataset=option_chains&format=json&side=put&min_delta=0.3&max_delta=0.7"
+        );
+        assert_eq!(status, StatusCode::OK);
+        let rows = json_rows_of(&body);
+        assert!(!rows.is_empty());
+        for row in &rows {
+            let delta = row["put_delta"].as_f64().map(f64::abs).unwrap_or(-1.0);
+            assert!((0.3..=0.7).contains(&delta), "{row}");
+            assert!(row.get("call_delta").is_none(), "{row}");
+        }
+    }
+
+    /// A projection keeps the requested columns in the dataset's order in
+    /// every format, and the digest column still follows them.
+    #[actix_web::test]
+    async fn test_a_projection_keeps_the_requested_columns_in_order() {
+        let app = v2_service!();
+        let id = create!(app);
+        let query = "dataset=option_chains&columns=strike,step,call_mid&digest=true";
+        let expected = ["step", "strike", "call_mid", "digest"];
+
+        let (status, csv) = export!(app, id, format!("{query}&format=csv"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(csv.split("\r\n").next(), Some(expected.join(",").as_str()));
+
+        let (_, json) = export!(app, id, format!("{query}&format=json"));
+        for row in json_rows_of(&json) {
+            let mut keys: Vec<&str> = match row.as_object() {
+                Some(object) => object.keys().map(String::as_str).collect(),
+                None => panic!("a row must be an object: {row}"),
+            };
+            keys.sort_unstable();
+            let mut wanted = expected.to_vec();
+            wanted.sort_unstable();
+            assert_eq!(keys, wanted);
+        }
+
+        let (_, parquet) = export_bytes!(app, id, format!("{query}&format=parquet"));
+        let batches = parquet_batches(parquet);
+        let names: Vec<String> = match batches.first() {
+            Some(batch) => batch
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect(),
+            None => panic!("a Parquet export must carry a batch"),
+        };
+        assert_eq!(names, expected);
+        let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, csv_rows_of(&csv));
+
+        let (status, body) = export!(app, id, "dataset=underlying&format=csv&columns=step,strike");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("\"field\":\"columns\""), "{body}");
+    }
+
+    /// `every` walks the first step of the range and every k-th after it, in
+    /// every dataset, and refuses to drop steps a digest would chain.
+    #[actix_web::test]
+    async fn test_every_decimates_the_steps() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (status, body) = export!(app, id, "dataset=underlying&format=json&every=2");
+        assert_eq!(status, StatusCode::OK);
+        let steps: Vec<u64> = json_rows_of(&body)
+            .iter()
+            .map(|row| row["step"].as_u64().unwrap_or(u64::MAX))
+            .collect();
+        assert_eq!(steps, vec![0, 2]);
+
+        let (status, body) = export!(
+            app,
+            id,
+            "dataset=option_chains&format=csv&from_step=1&every=5"
+        );
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            body.split("\r\n")
+                .skip(1)
+                .filter(|line| !line.is_empty())
+                .all(|line| line.starts_with("1,"))
+        );
+
+        for query in ["every=0", "every=2&digest=true"] {
+            let (status, body) = export!(app, id, format!("dataset=underlying&format=csv&{query}"));
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+            assert!(body.contains("\"field\":\"every\""), "{query}: {body}");
+        }
+    }
+
+    /// The per-step datasets have no rows to filter, so a row filter on one
+    /// is a mistake worth reporting rather than ignoring.
+    #[actix_web::test]
+    async fn test_row_filters_are_refused_for_the_factor_datasets() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        for (query, field) in [
+            ("dataset=underlying&labels=weeklies", "labels"),
+            ("dataset=volatility&max_moneyness=1.1", "max_moneyness"),
+            ("dataset=underlying&side=call", "side"),
+        ] {
+            let (status, body) = export!(app, id, format!("{query}&format=csv"));
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+            assert!(
+                body.contains(&format!("\"field\":\"{field}\"")),
+                "{query}: {body}"
+            );
+        }
+    }
+
+    // ---- continuation ----------------------------------------------------
+
+    /// The token lines of an NDJSON export, each with the byte offset just
+    /// past it — where a resume from it must pick up.
+    fn tokens_of(body: &str) -> Vec<(String, usize)> {
+        let mut offset = 0;
+        let mut tokens = Vec::new();
+        for line in body.split_inclusive('\n') {
+            offset += line.len();
+            if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(line)
+                && let Some(Value::String(token)) = object.get("continuation")
+            {
+                tokens.push((token.clone(), offset));
+            }
+        }
+        tokens
+    }
+
+    /// A long NDJSON export carries a token every `ROWS_PER_TOKEN` rows, and
+    /// resuming from any of them yields exactly the bytes that followed it.
+    #[actix_web::test]
+    async fn test_an_ndjson_export_resumes_from_every_token_with_identical_bytes() {
+        use crate::api::rest::continuation::ROWS_PER_TOKEN;
+
+        let app = v2_service!();
+        let mut body = reference_body();
+        body["steps"] = json!(ROWS_PER_TOKEN * 2 + 10);
+        let id = create!(app, body);
+
+        let (status, full) = export!(app, id, "dataset=underlying&format=ndjson");
+        assert_eq!(status, StatusCode::OK);
+        let tokens = tokens_of(&full);
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(full.lines().count(), ROWS_PER_TOKEN * 2 + 10 + tokens.len());
+
+        for (token, offset) in tokens {
+            let (status, resumed) = export!(
+                app,
+                id,
+                format!("dataset=underlying&format=ndjson&after={token}")
+            );
+            assert_eq!(status, StatusCode::OK, "{resumed}");
+            assert_eq!(resumed, full[offset..], "{token}");
+        }
+    }
+
+    /// A token can fall mid-step, between two strikes of an expiration, and a
+    /// resume from it still splices — filtered, projected and digested alike.
+    #[actix_web::test]
+    async fn test_an_option_chains_export_resumes_mid_step() {
+        let app = v2_service!();
+        let mut body = reference_body();
+        body["steps"] = json!(60);
+        let id = create!(app, body);
+
+        for query in [
+            "dataset=option_chains&format=ndjson",
+            "dataset=option_chains&format=ndjson&digest=true&columns=step,strike,call_mid",
+            "dataset=option_chains&format=ndjson&max_moneyness=1.01&every=2",
+        ] {
+            let (status, full) = export!(app, id, query);
+            assert_eq!(status, StatusCode::OK, "{query}");
+            let tokens = tokens_of(&full);
+            assert!(
+                !tokens.is_empty(),
+                "{query}: a long export must carry a token"
+            );
+
+            for (token, offset) in tokens {
+                let (status, resumed) = export!(app, id, format!("{query}&after={token}"));
+                assert_eq!(status, StatusCode::OK, "{query}: {resumed}");
+                assert_eq!(resumed, full[offset..], "{query}: {token}");
+            }
+        }
+    }
+
+    /// Every export says whether `after` can resume it: NDJSON can, nothing
+    /// else can.
+    #[actix_web::test]
+    async fn test_an_export_says_whether_it_can_resume() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        for (format, resumable) in [
+            ("ndjson", "true"),
+            ("json", "false"),
+            ("csv", "false"),
+            ("parquet", "false"),
+            ("arrow", "false"),
+        ] {
+            let request = actix_test::TestRequest::get()
+                .uri(&format!(
+                    "/api/v2/simulations/{id}/export?dataset=underlying&format={format}"
+                ))
+                .to_request();
+            let response = actix_test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::OK, "{format}");
+            assert_eq!(
+                response
+                    .headers()
+                    .get(RESUMABLE_HEADER)
+                    .and_then(|value| value.to_str().ok()),
+                Some(resumable),
+                "{format}"
+            );
+        }
+    }
+
+    /// `after` is refused by name for a format that cannot splice, for a
+    /// token that is not one, and for a token from another range or stride.
+    #[actix_web::test]
+    async fn test_a_resume_that_cannot_splice_is_refused() {
+        use crate::api::rest::continuation::ROWS_PER_TOKEN;
+
+        let app = v2_service!();
+        let mut body = reference_body();
+        body["steps"] = json!(ROWS_PER_TOKEN + 10);
+        let id = create!(app, body);
+        let (status, full) = export!(app, id, "dataset=underlying&format=ndjson");
+        assert_eq!(status, StatusCode::OK);
+        let Some((token, _)) = tokens_of(&full).into_iter().next() else {
+            panic!("a long export must carry a token: {full}");
+        };
+
+        let (status, _) = export!(
+            app,
+            id,
+            format!("dataset=underlying&format=ndjson&after={token}")
+        );
+        assert_eq!(status, StatusCode::OK);
+
+        for query in [
+            format!("format=csv&after={token}"),
+            format!("format=json&after={token}"),
+            "format=ndjson&after=yesterday".to_string(),
+            format!("format=ndjson&every=2&after={token}"),
+            format!("format=ndjson&from_step=2&after={token}"),
+        ] {
+            let (status, body) = export!(app, id, format!("dataset=underlying&{query}"));
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+            assert!(body.contains("\"field\":\"after\""), "{query}: {body}");
+        }
+    }
+
+    /// A token only resumes the query that issued it. A changed filter,
+    /// projection, dataset or end step is refused by name — its ordinals would
+    /// count other rows — while the same query spelled differently resumes.
+    #[actix_web::test]
+    async fn test_a_resume_with_a_changed_query_is_refused() {
+        let app = v2_service!();
+        let mut body = reference_body();
+        body["steps"] = json!(60);
+        let id = create!(app, body);
+
+        let query = "dataset=option_chains&format=ndjson&max_moneyness=1.01";
+        let (status, full) = export!(app, id, query);
+        assert_eq!(status, StatusCode::OK);
+        let Some((token, offset)) = tokens_of(&full).into_iter().next() else {
+            panic!("a long export must carry a token: {full}");
+        };
+
+        for changed in [
+            "dataset=option_chains&format=ndjson&max_moneyness=1.02",
+            "dataset=option_chains&format=ndjson",
+            "dataset=option_chains&format=ndjson&max_moneyness=1.01&columns=step,strike",
+            "dataset=underlying&format=ndjson",
+            "dataset=option_chains&format=ndjson&max_moneyness=1.01&to_step=50",
+        ] {
+            let (status, body) = export!(app, id, format!("{changed}&after={token}"));
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{changed}");
+            assert!(body.contains("\"field\":\"after\""), "{changed}: {body}");
+        }
+
+        // The open-ended range resolved to the last step, so naming it is the
+        // same query.
+        let (status, resumed) = export!(app, id, format!("{query}&to_step=59&after={token}"));
+        assert_eq!(status, StatusCode::OK, "{resumed}");
+        assert_eq!(resumed, full[offset..]);
+    }
+
     // ---- the persisted source --------------------------------------------
 
     /// A warehouse that answers from memory, standing in for ClickHouse.
@@ -1612,6 +2902,8 @@ mod tests {
         failing: bool,
         /// How many range reads the export asked for — the windowing evidence.
         reads: std::sync::atomic::AtomicUsize,
+        /// Every matching read, in order — the pushdown evidence.
+        asked: std::sync::Mutex<Vec<SnapshotRangeQuery>>,
     }
 
     impl FakeWarehouse {
@@ -1628,6 +2920,14 @@ mod tests {
             self.reads.load(std::sync::atomic::Ordering::SeqCst)
         }
 
+        /// The matching reads it has been asked for.
+        fn asked(&self) -> Vec<SnapshotRangeQuery> {
+            match self.asked.lock() {
+                Ok(asked) => asked.clone(),
+                Err(poisoned) => poisoned.into_inner().clone(),
+            }
+        }
+
         /// Files records after the fact.
         ///
         /// Lets a test create the simulation first and persist its real id
@@ -1697,6 +2997,36 @@ mod tests {
             Ok(self.range(from_step, to_step))
         }
 
+        async fn read_matching(
+            &self,
+            query: SnapshotRangeQuery,
+        ) -> Result<Vec<SnapshotRecord>, ChainError> {
+            match self.asked.lock() {
+                Ok(mut asked) => asked.push(query.clone()),
+                Err(poisoned) => poisoned.into_inner().push(query.clone()),
+            }
+            // Narrowed here exactly as the trait's default narrows, so the
+            // export sees what a pushing-down warehouse would return.
+            let records = self
+                .read_range(
+                    query.simulation,
+                    query.generation,
+                    query.from_step,
+                    query.to_step,
+                )
+                .await?;
+            Ok(records
+                .into_iter()
+                .filter(|record| query.keeps_step(record.step))
+                .map(|mut record| {
+                    record
+                        .expirations
+                        .retain(|expiration| query.keeps_expiration(expiration));
+                    record
+                })
+                .collect())
+        }
+
         async fn contract_series(
             &self,
             _query: crate::infrastructure::ContractSeriesQuery,
@@ -1705,6 +3035,11 @@ mod tests {
             // an honest answer rather than a panic waiting to be reached.
             Ok(Vec::new())
         }
+
+        async fn purge(&self, _simulations: &[Uuid]) -> Result<(), ChainError> {
+            // Nor does it purge.
+            Ok(())
+        }
     }
 
     /// The effective parameters of [`reference_body`].
@@ -1748,6 +3083,7 @@ mod tests {
         SnapshotRecord::new(
             simulation,
             CURRENT_SNAPSHOT_GENERATION,
+            engine_version().to_string(),
             snapshot.step,
             snapshot.simulated_at,
             parameters.symbol.clone(),
@@ -1838,6 +3174,28 @@ mod tests {
         );
     }
 
+    /// A persisted step encodes to the same Parquet and Arrow bytes as a
+    /// replayed one.
+    #[actix_web::test]
+    async fn test_a_persisted_step_encodes_to_the_same_columnar_bytes() {
+        let warehouse = Arc::new(FakeWarehouse::default());
+        let app = v2_service!(Some(
+            Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>
+        ));
+        let id = create!(app);
+        let query = "dataset=option_chains&format=parquet";
+
+        let (_, replayed) = export_bytes!(app, id, query);
+        let (_, replayed_arrow) = export_bytes!(app, id, "dataset=option_chains&format=arrow");
+        warehouse.fill(stored_tape(parse_id(&id)));
+        let (status, stored) = export_bytes!(app, id, query);
+        let (_, stored_arrow) = export_bytes!(app, id, "dataset=option_chains&format=arrow");
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(replayed, stored);
+        assert_eq!(replayed_arrow, stored_arrow);
+    }
+
     /// The persisted snapshot is what the export serves, not a replay of it.
     ///
     /// Without this the test above would pass on an export that ignored the
@@ -1969,6 +3327,52 @@ mod tests {
         );
     }
 
+    /// The stride and the expiration filters reach the warehouse read, the
+    /// rows match a replay of the same query, and a digest keeps whole steps.
+    #[actix_web::test]
+    async fn test_filters_are_pushed_down_to_the_warehouse() {
+        let replaying = v2_service!();
+        let replayed_id = create!(replaying);
+        let warehouse = Arc::new(FakeWarehouse::default());
+        let app = v2_service!(Some(
+            Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>
+        ));
+        let id = create!(app);
+        warehouse.fill(stored_tape(parse_id(&id)));
+
+        let query = "dataset=option_chains&format=csv&labels=weeklies&max_dte=30&every=2";
+        let (_, replayed) = export!(replaying, replayed_id, query);
+        let (status, stored) = export!(app, id, query);
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            replayed, stored,
+            "a narrowed read must render like a replay"
+        );
+        match warehouse.asked().as_slice() {
+            [asked] => {
+                assert_eq!(asked.stride, 2);
+                assert_eq!(asked.labels, vec!["weeklies".to_string()]);
+                assert_eq!(asked.max_days_to_expiration, Some(30.0));
+            }
+            other => panic!("one window must have been read, got {other:?}"),
+        }
+
+        let (status, _) = export!(
+            app,
+            id,
+            "dataset=option_chains&format=csv&labels=weeklies&digest=true"
+        );
+        assert_eq!(status, StatusCode::OK);
+        match warehouse.asked().last() {
+            Some(asked) => assert!(
+                !asked.narrows_expirations(),
+                "the digest hashes whole steps, so the read must not drop expirations"
+            ),
+            None => panic!("the digest export must have read the warehouse"),
+        }
+    }
+
     /// A window covers at most its width, and never reaches past the range.
     #[test]
     fn test_a_window_is_bounded_by_its_width_and_by_the_range() {
@@ -1987,6 +3391,168 @@ mod tests {
         );
     }
 
+    // ---- digests ---------------------------------------------------------
+
+    macro_rules! digest {
+        ($app:expr, $id:expr, $query:expr) => {{
+            let uri = format!("/api/v2/simulations/{}/digest?{}", $id, $query);
+            let response = actix_test::call_service(
+                &$app,
+                actix_test::TestRequest::get().uri(&uri).to_request(),
+            )
+            .await;
+            let status = response.status();
+            let body: Value = actix_test::read_body_json(response).await;
+            (status, body)
+        }};
+    }
+
+    fn digest_of(body: &Value) -> String {
+        match body.get("digest").and_then(Value::as_str) {
+            Some(digest) => digest.to_string(),
+            None => panic!("the body must carry a digest: {body}"),
+        }
+    }
+
+    /// The endpoint's digest is the one the last exported row carries, in
+    /// either format and whichever dataset — the chain covers the step, not
+    /// the rendering asked for.
+    #[actix_web::test]
+    async fn test_the_digest_matches_the_last_exported_row() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (status, body) = digest!(app, id, "to=1");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["from_step"], 0);
+        assert_eq!(body["to_step"], 1);
+        let expected = digest_of(&body);
+        assert_eq!(expected.len(), 64, "a SHA-256 is 64 hex characters");
+
+        let (status, csv) = export!(
+            app,
+            id,
+            "dataset=option_chains&format=csv&to_step=1&digest=true"
+        );
+        assert_eq!(status, StatusCode::OK);
+        let lines: Vec<&str> = csv.split("\r\n").filter(|line| !line.is_empty()).collect();
+        assert!(
+            lines[0].ends_with(",digest"),
+            "the header must name the column"
+        );
+        assert_eq!(
+            lines.last().and_then(|line| line.rsplit(',').next()),
+            Some(expected.as_str())
+        );
+
+        let (status, json) = export!(
+            app,
+            id,
+            "dataset=underlying&format=json&to_step=1&digest=true"
+        );
+        assert_eq!(status, StatusCode::OK);
+        let rows = json_rows_of(&json);
+        assert_eq!(rows.len(), 2);
+        assert_ne!(
+            rows[0]["digest"], rows[1]["digest"],
+            "every step moves the chain"
+        );
+        assert_eq!(rows[1]["digest"], Value::from(expected));
+
+        let (_, plain) = export!(app, id, "dataset=underlying&format=csv");
+        assert!(!plain.contains("digest"), "the column is opt-in");
+    }
+
+    /// Folding the digests the snapshots carry reproduces the chain, which is
+    /// how a client checks a tape it walked step by step.
+    #[actix_web::test]
+    async fn test_folding_snapshot_digests_reproduces_the_chain() {
+        use sha2::{Digest, Sha256};
+
+        let app = v2_service!();
+        let id = create!(app);
+
+        let mut chain = [0_u8; 32];
+        for step in 0..3 {
+            let response = actix_test::call_service(
+                &app,
+                actix_test::TestRequest::post()
+                    .uri(&format!("/api/v2/simulations/{id}/step"))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK, "step {step}");
+            let snapshot: Value = actix_test::read_body_json(response).await;
+            let step_digest = match hex::decode(digest_of(&snapshot)) {
+                Ok(bytes) => bytes,
+                Err(error) => panic!("a step digest must be hex: {error}"),
+            };
+
+            let mut hasher = Sha256::new();
+            hasher.update(chain);
+            hasher.update(&step_digest);
+            chain = hasher.finalize().into();
+        }
+
+        let (status, body) = digest!(app, id, "");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(digest_of(&body), hex::encode(chain));
+    }
+
+    /// The digest is a function of the tape: repeatable, different for a
+    /// different seed, and the same whether the warehouse or a replay served
+    /// the steps.
+    #[actix_web::test]
+    async fn test_the_digest_fingerprints_the_tape() {
+        let warehouse = Arc::new(FakeWarehouse::default());
+        let app = v2_service!(Some(
+            Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>
+        ));
+        let id = create!(app);
+
+        let (_, replayed) = digest!(app, id, "");
+        let (_, repeated) = digest!(app, id, "");
+        assert_eq!(digest_of(&replayed), digest_of(&repeated));
+
+        warehouse.fill(stored_tape(parse_id(&id)));
+        let reads = warehouse.reads();
+        let (_, stored) = digest!(app, id, "");
+        assert!(
+            warehouse.reads() > reads,
+            "the digest must consult the warehouse"
+        );
+        assert_eq!(digest_of(&replayed), digest_of(&stored));
+
+        let (_, partial) = digest!(app, id, "from_step=1");
+        assert_ne!(
+            digest_of(&replayed),
+            digest_of(&partial),
+            "the chain starts at the range"
+        );
+
+        let mut body = reference_body();
+        body["seed"] = json!(8);
+        let request = actix_test::TestRequest::post()
+            .uri("/api/v2/simulations")
+            .set_json(body)
+            .to_request();
+        let created: Value =
+            actix_test::read_body_json(actix_test::call_service(&app, request).await).await;
+        let (_, other) = digest!(app, created["id"].as_str().unwrap_or_default(), "");
+        assert_ne!(digest_of(&replayed), digest_of(&other));
+    }
+
+    /// A digest range is validated exactly like an export's.
+    #[actix_web::test]
+    async fn test_a_digest_range_past_the_tape_is_rejected() {
+        let app = v2_service!();
+        let id = create!(app);
+
+        let (status, body) = digest!(app, id, "to_step=3");
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "to_step");
+    }
+
     // ---- ranges and bounds, unit level -----------------------------------
 
     fn query(from: Option<usize>, to: Option<usize>) -> ExportQuery {
@@ -1995,6 +3561,40 @@ mod tests {
             format: Format::Json,
             from_step: from,
             to_step: to,
+            digest: false,
+            labels: None,
+            min_dte: None,
+            max_dte: None,
+            min_moneyness: None,
+            max_moneyness: None,
+            min_delta: None,
+            max_delta: None,
+            side: None,
+            columns: None,
+            every: None,
+            after: None,
+        }
+    }
+
+    /// The export cap counts the steps produced, so a decimated export may
+    /// span a range an undecimated one could not.
+    #[test]
+    fn test_the_cap_counts_decimated_steps() {
+        let mut decimated = query(None, None);
+        decimated.every = Some(100);
+        match StepRange::resolve(&decimated, 10_000, 100) {
+            Ok(range) => {
+                assert_eq!(range.stride, 100);
+                assert_eq!(range.steps().count(), 100);
+                assert_eq!(range.steps().last(), Some(9_900));
+            }
+            Err(error) => panic!("a decimated range within the cap must resolve: {error}"),
+        }
+
+        decimated.every = Some(99);
+        match StepRange::resolve(&decimated, 10_000, 100) {
+            Err(ChainError::Validation { field, .. }) => assert_eq!(field, "to_step"),
+            other => panic!("a decimated range above the cap must fail, got {other:?}"),
         }
     }
 
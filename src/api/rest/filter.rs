@@ -0,0 +1,585 @@
+//! Which rows and columns of a v2 export are kept.
+//!
+//! An `option_chains` step is every expiration times every strike, and most
+//! studies want a sliver of it: one rule's expirations, a band of strikes
+//! around the money, one side's quotes. The query parameters parsed here say
+//! which, and the producer applies them **before** a row is rendered — a
+//! filtered-out contract is never formatted, encoded or compressed, so a
+//! narrow export costs a narrow export's work past the pricing.
+//!
+//! * **Rows** ([`RowFilter`]): `labels` keeps the expirations carrying any of
+//!   the named rule ids; `min_dte`/`max_dte` bound the days to expiration;
+//!   `min_moneyness`/`max_moneyness` bound `strike / spot`; `min_delta` and
+//!   `max_delta` bound the absolute delta of the `side` named, and a contract
+//!   with no delta on that side fails the band. Row filters only make sense on
+//!   `option_chains`, and are refused on the other datasets.
+//! * **Columns** ([`Projection`]): `columns` names the dataset columns to
+//!   keep, and they come back in the dataset's own order whatever order they
+//!   were asked in. `side` drops the other side's quote columns. The `digest`
+//!   column is governed by `digest=` alone.
+//!
+//! Step decimation (`every`) is a property of the range rather than of a row,
+//! so it lives on [`super::export::StepRange`].
+//!
+//! The expiration filters — labels and days to expiration — are also pushed
+//! down into the warehouse read (see [`RowFilter::narrowing`]). They are
+//! re-applied here to whatever comes back, so a stored step and a replayed one
+//! still render the same rows.
+
+use crate::api::rest::export::{Dataset, ExpirationView, ExportQuery, QuoteView, StepChains};
+use crate::api::rest::series::Side;
+use crate::infrastructure::SnapshotRangeQuery;
+use crate::utils::ChainError;
+
+/// The rows of an `option_chains` step an export keeps.
+///
+/// The default keeps every row, which is what every caller without a filter
+/// passes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RowFilter {
+    labels: Vec<String>,
+    min_dte: Option<f64>,
+    max_dte: Option<f64>,
+    min_moneyness: Option<f64>,
+    max_moneyness: Option<f64>,
+    /// The side the delta band reads, with the band. Only set when a bound is.
+    delta: Option<(Side, Option<f64>, Option<f64>)>,
+}
+
+impl RowFilter {
+    /// Whether an expiration passes the label and days-to-expiration filters.
+    #[must_use]
+    pub(super) fn keeps_expiration(&self, expiration: &ExpirationView<'_>) -> bool {
+        let days = expiration.days_to_expiration;
+        (self.labels.is_empty()
+            || expiration
+                .labels
+                .iter()
+                .any(|label| self.labels.contains(label)))
+            && self.min_dte.is_none_or(|min| days >= min)
+            && self.max_dte.is_none_or(|max| days <= max)
+    }
+
+    /// Whether a strike passes the moneyness and delta filters at `spot`.
+    #[must_use]
+    pub(super) fn keeps_quote(&self, quote: &QuoteView, spot: f64) -> bool {
+        let moneyness = quote.strike / spot;
+        let in_moneyness = self.min_moneyness.is_none_or(|min| moneyness >= min)
+            && self.max_moneyness.is_none_or(|max| moneyness <= max);
+
+        let in_delta = match self.delta {
+            None => true,
+            Some((side, min, max)) => {
+                let delta = match side {
+                    Side::Call => quote.call_delta,
+                    Side::Put => quote.put_delta,
+                };
+                delta.map(f64::abs).is_some_and(|delta| {
+                    min.is_none_or(|min| delta >= min) && max.is_none_or(|max| delta <= max)
+                })
+            }
+        };
+
+        in_moneyness && in_delta
+    }
+
+    /// The expirations of a step this filter keeps, ascending.
+    pub(super) fn expirations<'a>(
+        &'a self,
+        chains: StepChains<'a>,
+    ) -> impl Iterator<Item = ExpirationView<'a>> + 'a {
+        chains
+            .expirations()
+            .filter(|expiration| self.keeps_expiration(expiration))
+    }
+
+    /// The strikes of an expiration this filter keeps at `spot`, ascending.
+    pub(super) fn quotes<'a>(
+        &'a self,
+        expiration: ExpirationView<'a>,
+        spot: f64,
+    ) -> impl Iterator<Item = QuoteView> + 'a {
+        expiration
+            .quotes
+            .quotes()
+            .filter(move |quote| self.keeps_quote(quote, spot))
+    }
+
+    /// Narrows a warehouse read to what this filter can decide from a stored
+    /// column: the labels and the days to expiration.
+    #[must_use]
+    pub(super) fn narrowing(&self, query: SnapshotRangeQuery) -> SnapshotRangeQuery {
+        query
+            .labelled(self.labels.clone())
+            .expiring_within(self.min_dte, self.max_dte)
+    }
+
+    /// Parses the row filters of a query.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming the offending parameter when
+    /// a filter is set on a dataset other than `option_chains`, when a bound
+    /// is not a finite number in its range or exceeds its upper bound, when a
+    /// label is empty, or when a delta band names no `side`.
+    fn parse(query: &ExportQuery) -> Result<Self, ChainError> {
+        let set = [
+            ("labels", query.labels.is_some()),
+            ("min_dte", query.min_dte.is_some()),
+            ("max_dte", query.max_dte.is_some()),
+            ("min_moneyness", query.min_moneyness.is_some()),
+            ("max_moneyness", query.max_moneyness.is_some()),
+            ("min_delta", query.min_delta.is_some()),
+            ("max_delta", query.max_delta.is_some()),
+            ("side", query.side.is_some()),
+        ];
+        if query.dataset != Dataset::OptionChains {
+            if let Some((field, _)) = set.iter().find(|(_, set)| *set) {
+                return Err(ChainError::Validation {
+                    field: (*field).to_string(),
+                    reason: format!(
+                        "filters rows of option_chains only, not {}",
+                        query.dataset.as_str()
+                    ),
+                });
+            }
+            return Ok(Self::default());
+        }
+
+        let labels = match &query.labels {
+            Some(labels) => names("labels", labels)?,
+            None => Vec::new(),
+        };
+        let (min_dte, max_dte) =
+            band(("min_dte", query.min_dte), ("max_dte", query.max_dte), None)?;
+        let (min_moneyness, max_moneyness) = band(
+            ("min_moneyness", query.min_moneyness),
+            ("max_moneyness", query.max_moneyness),
+            None,
+        )?;
+        let (min_delta, max_delta) = band(
+            ("min_delta", query.min_delta),
+            ("max_delta", query.max_delta),
+            Some(1.0),
+        )?;
+
+        let delta = match (min_delta.is_some() || max_delta.is_some(), query.side) {
+            (false, _) => None,
+            (true, Some(side)) => Some((side, min_delta, max_delta)),
+            (true, None) => {
+                return Err(ChainError::Validation {
+                    field: "side".to_string(),
+                    reason: "is required with min_delta or max_delta, to say whose delta"
+                        .to_string(),
+                });
+            }
+        };
+
+        Ok(Self {
+            labels,
+            min_dte,
+            max_dte,
+            min_moneyness,
+            max_moneyness,
+            delta,
+        })
+    }
+}
+
+/// The columns of a dataset an export keeps, in the dataset's order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Projection {
+    /// Indices into the dataset's header, ascending; `None` keeps them all.
+    kept: Option<Vec<usize>>,
+}
+
+impl Projection {
+    /// The kept columns' indices into `dataset`'s header, ascending.
+    #[must_use]
+    pub(super) fn indices(&self, dataset: Dataset) -> Vec<usize> {
+        match &self.kept {
+            Some(kept) => kept.clone(),
+            None => (0..dataset.header().len()).collect(),
+        }
+    }
+
+    /// The kept columns' names, in the order the rows carry them.
+    #[must_use]
+    pub(super) fn header(&self, dataset: Dataset) -> Vec<&'static str> {
+        let header = dataset.header();
+        self.indices(dataset)
+            .into_iter()
+            .filter_map(|index| header.get(index).copied())
+            .collect()
+    }
+
+    /// Keeps the projected fields of a full record, in order.
+    #[must_use]
+    pub(super) fn pick(&self, record: Vec<String>) -> Vec<String> {
+        match &self.kept {
+            None => record,
+            Some(kept) => kept
+                .iter()
+                .filter_map(|index| record.get(*index).cloned())
+                .collect(),
+        }
+    }
+
+    /// Removes the unprojected keys of a full row object.
+    pub(super) fn retain(&self, dataset: Dataset, value: &mut serde_json::Value) {
+        if self.kept.is_none() {
+            return;
+        }
+        let header = self.header(dataset);
+        if let Some(object) = value.as_object_mut() {
+            object.retain(|key, _| header.contains(&key.as_str()));
+        }
+    }
+
+    /// Parses `columns` and `side` into a projection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `columns` when a name is
+    /// empty, is not a column of the dataset, or belongs to the side `side`
+    /// excludes.
+    fn parse(query: &ExportQuery) -> Result<Self, ChainError> {
+        let header = query.dataset.header();
+        // The quote columns of the side a `side` filter drops.
+        let excluded = |name: &str| match query.side {
+            Some(Side::Call) => name.starts_with("put_"),
+            Some(Side::Put) => name.starts_with("call_"),
+            None => false,
+        };
+
+        let kept = match &query.columns {
+            None if query.side.is_none() => return Ok(Self::default()),
+            None => (0..header.len())
+                .filter(|index| header.get(*index).is_some_and(|name| !excluded(name)))
+                .collect(),
+            Some(columns) => {
+                let mut kept = Vec::new();
+                for name in names("columns", columns)? {
+                    let Some(index) = header.iter().position(|column| *column == name) else {
+                        return Err(ChainError::Validation {
+                            field: "columns".to_string(),
+                            reason: format!(
+                                "{name:?} is not a column of {}; expected any of {}",
+                                query.dataset.as_str(),
+                                header.join(", ")
+                            ),
+                        });
+                    };
+                    if excluded(&name) {
+                        return Err(ChainError::Validation {
+                            field: "columns".to_string(),
+                            reason: format!("{name:?} is dropped by the side filter"),
+                        });
+                    }
+                    kept.push(index);
+                }
+                kept.sort_unstable();
+                kept.dedup();
+                kept
+            }
+        };
+
+        Ok(Self { kept: Some(kept) })
+    }
+}
+
+/// Everything an export keeps of a step: its rows and its columns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Selection {
+    pub(super) rows: RowFilter,
+    pub(super) columns: Projection,
+}
+
+impl Selection {
+    /// Parses the filters and projection of an export query.
+    ///
+    /// # Errors
+    ///
+    /// As [`RowFilter`] and [`Projection`] parsing: a
+    /// [`ChainError::Validation`] naming the offending parameter.
+    pub(crate) fn parse(query: &ExportQuery) -> Result<Self, ChainError> {
+        Ok(Self {
+            rows: RowFilter::parse(query)?,
+            columns: Projection::parse(query)?,
+        })
+    }
+
+    /// A canonical text of what this selection keeps, for a continuation
+    /// token's query fingerprint.
+    ///
+    /// Two queries that keep the same rows and columns write the same text,
+    /// however they were spelled: labels are a set, columns are indices in the
+    /// dataset's order, and a bound is written in its shortest exact form.
+    #[must_use]
+    pub(super) fn canonical(&self) -> String {
+        let bound = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+        let rows = &self.rows;
+
+        let mut labels = rows.labels.clone();
+        labels.sort_unstable();
+        labels.dedup();
+        let delta = match rows.delta {
+            None => String::new(),
+            Some((side, min, max)) => {
+                let side = match side {
+                    Side::Call => "call",
+                    Side::Put => "put",
+                };
+                format!("{side}:{}:{}", bound(min), bound(max))
+            }
+        };
+        let columns = match &self.columns.kept {
+            None => "*".to_string(),
+            Some(kept) => kept
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+
+        format!(
+            "labels={};dte={}:{};moneyness={}:{};delta={delta};columns={columns}",
+            labels.join(","),
+            bound(rows.min_dte),
+            bound(rows.max_dte),
+            bound(rows.min_moneyness),
+            bound(rows.max_moneyness),
+        )
+    }
+}
+
+/// Splits a comma-separated list, refusing an empty entry.
+fn names(field: &str, list: &str) -> Result<Vec<String>, ChainError> {
+    list.split(',')
+        .map(|name| {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(ChainError::Validation {
+                    field: field.to_string(),
+                    reason: format!("must be a comma-separated list of names, got {list:?}"),
+                });
+            }
+            Ok(name.to_string())
+        })
+        .collect()
+}
+
+/// Validates an inclusive band: both bounds finite, non-negative, at most
+/// `ceiling` when there is one, and in order.
+fn band(
+    (min_field, min): (&str, Option<f64>),
+    (max_field, max): (&str, Option<f64>),
+    ceiling: Option<f64>,
+) -> Result<(Option<f64>, Option<f64>), ChainError> {
+    for (field, bound) in [(min_field, min), (max_field, max)] {
+        let Some(bound) = bound else { continue };
+        let in_range =
+            bound.is_finite() && bound >= 0.0 && ceiling.is_none_or(|ceiling| bound <= ceiling);
+        if !in_range {
+            let range = match ceiling {
+                Some(ceiling) => format!("between 0 and {ceiling}"),
+                None => "a finite, non-negative number".to_string(),
+            };
+            return Err(ChainError::Validation {
+                field: field.to_string(),
+                reason: format!("must be {range}, got {bound}"),
+            });
+        }
+    }
+    if let (Some(min), Some(max)) = (min, max)
+        && min > max
+    {
+        return Err(ChainError::Validation {
+            field: min_field.to_string(),
+            reason: format!("must not exceed {max_field} ({max}), got {min}"),
+        });
+    }
+    Ok((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::export::Format;
+
+    fn query(dataset: Dataset) -> ExportQuery {
+        ExportQuery {
+            dataset,
+            format: Format::Csv,
+            from_step: None,
+            to_step: None,
+            digest: false,
+            labels: None,
+            min_dte: None,
+            max_dte: None,
+            min_moneyness: None,
+            max_moneyness: None,
+            min_delta: None,
+            max_delta: None,
+            side: None,
+            columns: None,
+            every: None,
+            after: None,
+        }
+    }
+
+    fn quote(strike: f64, call_delta: Option<f64>, put_delta: Option<f64>) -> QuoteView {
+        QuoteView {
+            strike,
+            implied_volatility: 0.18,
+            call_bid: None,
+            call_ask: None,
+            call_mid: None,
+            call_delta,
+            put_bid: None,
+            put_ask: None,
+            put_mid: None,
+            put_delta,
+            gamma: None,
+        }
+    }
+
+    fn refused(query: &ExportQuery) -> String {
+        match Selection::parse(query) {
+            Err(ChainError::Validation { field, .. }) => field,
+            other => panic!("the query must be refused, got {other:?}"),
+        }
+    }
+
+    /// No parameter keeps every row and every column.
+    #[test]
+    fn test_an_unfiltered_query_selects_everything() {
+        for dataset in [
+            Dataset::Underlying,
+            Dataset::Volatility,
+            Dataset::OptionChains,
+        ] {
+            match Selection::parse(&query(dataset)) {
+                Ok(selection) => {
+                    assert_eq!(selection, Selection::default());
+                    assert_eq!(selection.columns.header(dataset), dataset.header());
+                }
+                Err(error) => panic!("an unfiltered query must parse: {error}"),
+            }
+        }
+    }
+
+    /// Columns come back in the dataset's order, once each, whatever order
+    /// they were asked in; an unknown column is refused by name.
+    #[test]
+    fn test_a_projection_keeps_the_dataset_order() {
+        let mut asked = query(Dataset::OptionChains);
+        asked.columns = Some("strike, step,call_mid,strike".to_string());
+        match Selection::parse(&asked) {
+            Ok(selection) => {
+                let projection = selection.columns;
+                assert_eq!(
+                    projection.header(Dataset::OptionChains),
+                    vec!["step", "strike", "call_mid"]
+                );
+                let record: Vec<String> = Dataset::OptionChains
+                    .header()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                assert_eq!(projection.pick(record), vec!["step", "strike", "call_mid"]);
+            }
+            Err(error) => panic!("the projection must parse: {error}"),
+        }
+
+        asked.columns = Some("step,bogus".to_string());
+        assert_eq!(refused(&asked), "columns");
+        asked.columns = Some("step,,strike".to_string());
+        assert_eq!(refused(&asked), "columns");
+    }
+
+    /// A side drops the other side's quote columns, and naming one of those
+    /// explicitly is a contradiction rather than something to guess about.
+    #[test]
+    fn test_a_side_drops_the_other_sides_columns() {
+        let mut asked = query(Dataset::OptionChains);
+        asked.side = Some(Side::Put);
+        match Selection::parse(&asked) {
+            Ok(selection) => {
+                let header = selection.columns.header(Dataset::OptionChains);
+                assert!(header.contains(&"put_delta"));
+                assert!(header.contains(&"gamma"));
+                assert!(!header.iter().any(|name| name.starts_with("call_")));
+            }
+            Err(error) => panic!("the side must parse: {error}"),
+        }
+
+        asked.columns = Some("strike,call_bid".to_string());
+        assert_eq!(refused(&asked), "columns");
+    }
+
+    /// Row filters are refused on the per-step datasets, which have no rows
+    /// to filter; a projection is not.
+    #[test]
+    fn test_row_filters_are_refused_off_the_option_chains() {
+        let mut asked = query(Dataset::Underlying);
+        asked.min_dte = Some(1.0);
+        assert_eq!(refused(&asked), "min_dte");
+
+        let mut asked = query(Dataset::Volatility);
+        asked.side = Some(Side::Call);
+        assert_eq!(refused(&asked), "side");
+
+        let mut asked = query(Dataset::Underlying);
+        asked.columns = Some("step,price".to_string());
+        assert!(Selection::parse(&asked).is_ok());
+    }
+
+    /// Bands are finite, in order and in range, and a delta band names its
+    /// side.
+    #[test]
+    fn test_a_band_is_validated() {
+        let mut asked = query(Dataset::OptionChains);
+        asked.min_dte = Some(10.0);
+        asked.max_dte = Some(5.0);
+        assert_eq!(refused(&asked), "min_dte");
+
+        let mut asked = query(Dataset::OptionChains);
+        asked.max_moneyness = Some(f64::NAN);
+        assert_eq!(refused(&asked), "max_moneyness");
+
+        let mut asked = query(Dataset::OptionChains);
+        asked.min_delta = Some(0.25);
+        assert_eq!(refused(&asked), "side");
+        asked.side = Some(Side::Call);
+        assert!(Selection::parse(&asked).is_ok());
+        asked.max_delta = Some(1.5);
+        assert_eq!(refused(&asked), "max_delta");
+
+        let mut asked = query(Dataset::OptionChains);
+        asked.labels = Some("weeklies,".to_string());
+        assert_eq!(refused(&asked), "labels");
+    }
+
+    /// Moneyness reads the strike against the spot, and the delta band reads
+    /// the absolute delta of the side named — a missing delta fails it.
+    #[test]
+    fn test_a_quote_is_kept_by_moneyness_and_delta() {
+        let mut asked = query(Dataset::OptionChains);
+        asked.min_moneyness = Some(0.95);
+        asked.max_moneyness = Some(1.05);
+        asked.side = Some(Side::Put);
+        asked.min_delta = Some(0.25);
+        asked.max_delta = Some(0.75);
+        let filter = match Selection::parse(&asked) {
+            Ok(selection) => selection.rows,
+            Err(error) => panic!("the filter must parse: {error}"),
+        };
+
+        assert!(filter.keeps_quote(&quote(5000.0, Some(0.5), Some(-0.5)), 5000.0));
+        assert!(!filter.keeps_quote(&quote(6000.0, Some(0.5), Some(-0.5)), 5000.0));
+        assert!(!filter.keeps_quote(&quote(5000.0, Some(0.5), Some(-0.1)), 5000.0));
+        assert!(!filter.keeps_quote(&quote(5000.0, Some(0.5), None), 5000.0));
+        assert!(RowFilter::default().keeps_quote(&quote(9000.0, None, None), 5000.0));
+    }
+}
@@ -8,7 +8,7 @@ use crate::api::rest::responses::{
     SessionParametersResponse, SessionResponse, ValidationErrorResponse,
 };
 use crate::api::rest::validation::{self, decimal_field, positive_field, strictly_positive_field};
-use crate::infrastructure::{MetricsCollector, MongoDBRepository};
+use crate::infrastructure::{EventSink, MetricsCollector};
 use crate::session::{Session, SessionManager, SimulationParameters};
 use crate::utils::ChainError;
 use actix_web::{HttpRequest, HttpResponse, Responder, web};
@@ -236,7 +236,7 @@ pub(crate) async fn create_session(
     req: HttpRequest,
     session_manager: web::Data<Arc<SessionManager>>,
     metrics_collector: web::Data<Arc<MetricsCollector>>,
-    mongodb_repo: web::Data<Arc<MongoDBRepository>>,
+    event_sink: web::Data<Arc<dyn EventSink>>,
     json_req: web::Json<CreateSessionRequest>,
 ) -> impl Responder {
     info!("{} {}: body={}", req.method(), req.path(), json_req.0);
@@ -283,8 +283,8 @@ pub(crate) async fn create_session(
                 state: session.state.to_string(),
             };
 
-            // Save to MongoDB
-            if let Err(e) = mongodb_repo
+            // Record the event in the configured sink
+            if let Err(e) = event_sink
                 .save_session_event(
                     session.id,
                     response.clone(),
@@ -292,7 +292,7 @@ pub(crate) async fn create_session(
                 )
                 .await
             {
-                error!(session_id = %session.id, "Failed to save session event to MongoDB: {}", e);
+                error!(session_id = %session.id, "Failed to save session event: {}", e);
                 // Continue as this is not critical for the main flow
             }
             HttpResponse::Created().json(response)
@@ -344,7 +344,7 @@ pub(crate) async fn advance_step(
     req: HttpRequest,
     session_manager: web::Data<Arc<SessionManager>>,
     metrics_collector: web::Data<Arc<MetricsCollector>>,
-    mongodb_repo: web::Data<Arc<MongoDBRepository>>,
+    event_sink: web::Data<Arc<dyn EventSink>>,
     query: web::Query<AdvanceStepQuery>,
 ) -> impl Responder {
     info!(
@@ -393,8 +393,8 @@ pub(crate) async fn advance_step(
             metrics_collector
                 .set_simulation_cache_size(session_manager.simulation_cache_len().await as i64);
 
-            // Save to MongoDB
-            if let Err(e) = mongodb_repo
+            // Record the event in the configured sink
+            if let Err(e) = event_sink
                 .save_chain_step(
                     session_id,
                     response.clone(),
@@ -402,7 +402,7 @@ pub(crate) async fn advance_step(
                 )
                 .await
             {
-                error!(session_id = %session_id, "Failed to save chain step to MongoDB: {}", e);
+                error!(session_id = %session_id, "Failed to save chain step: {}", e);
                 // Continue as this is not critical for the main flow
             }
             HttpResponse::Ok().json(response)
@@ -485,7 +485,7 @@ pub(crate) async fn replace_session(
     session_manager: web::Data<Arc<SessionManager>>,
     metrics_collector: web::Data<Arc<MetricsCollector>>,
     query: web::Query<SessionId>,
-    mongodb_repo: web::Data<Arc<MongoDBRepository>>,
+    event_sink: web::Data<Arc<dyn EventSink>>,
     json_req: web::Json<CreateSessionRequest>,
 ) -> impl Responder {
     info!(
@@ -545,8 +545,8 @@ pub(crate) async fn replace_session(
                 state: session.state.to_string(),
             };
 
-            // Save to MongoDB
-            if let Err(e) = mongodb_repo
+            // Record the event in the configured sink
+            if let Err(e) = event_sink
                 .save_session_event(
                     session_id,
                     response.clone(),
@@ -554,7 +554,7 @@ pub(crate) async fn replace_session(
                 )
                 .await
             {
-                error!(session_id = %session_id, "Failed to save reinitialized session event to MongoDB: {}", e);
+                error!(session_id = %session_id, "Failed to save reinitialized session event: {}", e);
                 // Continue as this is not critical for the main flow
             }
 
@@ -598,7 +598,7 @@ pub(crate) async fn update_session(
     session_manager: web::Data<Arc<SessionManager>>,
     query: web::Query<SessionId>,
     metrics_collector: web::Data<Arc<MetricsCollector>>,
-    mongodb_repo: web::Data<Arc<MongoDBRepository>>,
+    event_sink: web::Data<Arc<dyn EventSink>>,
     json_req: web::Json<UpdateSessionRequest>,
 ) -> impl Responder {
     info!(
@@ -673,8 +673,8 @@ pub(crate) async fn update_session(
                 state: session.state.to_string(),
             };
 
-            // Save to MongoDB
-            if let Err(e) = mongodb_repo
+            // Record the event in the configured sink
+            if let Err(e) = event_sink
                 .save_session_event(
                     session_id,
                     response.clone(),
@@ -682,7 +682,7 @@ pub(crate) async fn update_session(
                 )
                 .await
             {
-                error!(session_id = %session_id, "Failed to save updated session event to MongoDB: {}", e);
+                error!(session_id = %session_id, "Failed to save updated session event: {}", e);
                 // Continue as this is not critical for the main flow
             }
 
@@ -18,95 +18,1138 @@
 //! They are not the same thing and are deliberately not collapsed: `412` means
 //! "the cursor is not where you thought", `409` means "someone else committed
 //! first".
+//!
+//! `If-Match` is the standard HTTP spelling of both at once: the `ETag` every
+//! read carries names the revision, and a write under a tag that no longer
+//! matches is `412` — a precondition the client stated, so a precondition
+//! failure, even when the write lost its race after the check. See
+//! [`crate::api::rest::etag`].
 
 use crate::api::rest::error::map_error;
-use crate::api::rest::requests_v2::CreateSimulationRequest;
-use crate::api::rest::responses_v2::{SimulationResponse, SnapshotResponse, snapshot_response};
-use crate::session::{SessionV2, SimulationManager, SimulationParametersV2};
+use crate::api::rest::etag;
+use crate::api::rest::idempotency::idempotent;
+use crate::api::rest::requests_v2::{
+    CreateSimulationRequest, PurgeSnapshotsRequest, RegisterWebhookRequest, StartPlaybackRequest,
+};
+use crate::api::rest::responses_v2::{
+    MaterializationResponse, PlaybackResponse, PurgeSnapshotsResponse, SimulationListResponse,
+    SimulationResponse, SnapshotResponse, WebhookListResponse, WebhookResponse,
+    materialization_response, playback_response, snapshot_response,
+};
+use crate::session::{
+    SessionState, SessionV2, SimulationFilter, SimulationManager, SimulationManifest,
+    SimulationOptions, SimulationParametersV2,
+};
 use crate::utils::ChainError;
+use actix_web::http::header::IF_NONE_MATCH;
 use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::info;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-/// Path parameter for every per-simulation route.
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
-pub(crate) struct SimulationPath {
-    /// The simulation's identifier.
-    pub(crate) id: String,
+/// Path parameter for every per-simulation route.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct SimulationPath {
+    /// The simulation's identifier.
+    pub(crate) id: String,
+}
+
+/// Path parameter for `DELETE /api/v2/webhooks/{id}`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct WebhookPath {
+    /// The webhook registration's identifier.
+    pub(crate) id: String,
+}
+
+/// Query parameters for the advance command.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct AdvanceQuery {
+    /// Optional expected cursor. When supplied, the advance proceeds only if
+    /// the simulation is at exactly this step; otherwise `412` is returned with
+    /// the actual cursor and nothing is consumed.
+    #[serde(default)]
+    pub(crate) expected_step: Option<usize>,
+}
+
+/// Query parameters for deleting a simulation.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct DeleteQuery {
+    /// Also remove its rows from the snapshot warehouse now, instead of at the
+    /// table TTL. Defaults to `false`.
+    #[serde(default)]
+    pub(crate) purge: bool,
+}
+
+/// Query parameters for importing a manifest.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ImportQuery {
+    /// Import even when the manifest was written under a different tape
+    /// generation or tzdb release. Defaults to `false`.
+    #[serde(default)]
+    pub(crate) force: bool,
+}
+
+/// Query parameters for the seek command.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct SeekQuery {
+    /// The step to move the cursor to. Must not be behind the cursor, and
+    /// must be before the last step.
+    pub(crate) to_step: usize,
+}
+
+/// Query parameters for starting a materialization.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct MaterializeQuery {
+    /// First step to file, inclusive. Defaults to `0`; `from` is accepted too.
+    #[serde(default, alias = "from")]
+    pub(crate) from_step: Option<usize>,
+    /// Last step to file, inclusive. Defaults to the final generated step;
+    /// `to` is accepted too.
+    #[serde(default, alias = "to")]
+    pub(crate) to_step: Option<usize>,
+}
+
+/// How long a wait for the next snapshot lasts when the client names none.
+const DEFAULT_NEXT_TIMEOUT_MS: u64 = 30_000;
+
+/// The longest a wait for the next snapshot may last.
+///
+/// Short enough to sit under common proxy idle timeouts, so a waiting client
+/// gets a `204` it can re-issue rather than a connection its proxy cut.
+const MAX_NEXT_TIMEOUT_MS: u64 = 60_000;
+
+/// Query parameters for waiting on the next snapshot.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct NextSnapshotQuery {
+    /// Milliseconds to wait, `1..=60000`. Defaults to `30000`.
+    #[serde(default)]
+    pub(crate) timeout_ms: Option<u64>,
+}
+
+impl NextSnapshotQuery {
+    /// The wait this query asks for.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] on `timeout_ms` when it is out of
+    /// range.
+    fn timeout(&self) -> Result<Duration, ChainError> {
+        let millis = self.timeout_ms.unwrap_or(DEFAULT_NEXT_TIMEOUT_MS);
+        if !(1..=MAX_NEXT_TIMEOUT_MS).contains(&millis) {
+            return Err(ChainError::Validation {
+                field: "timeout_ms".to_string(),
+                reason: format!("must be between 1 and {MAX_NEXT_TIMEOUT_MS}, got {millis}"),
+            });
+        }
+        Ok(Duration::from_millis(millis))
+    }
+}
+
+/// Page size of a listing when the client names none.
+const DEFAULT_LIST_LIMIT: usize = 50;
+
+/// Largest page a listing serves.
+///
+/// A page is metadata only, but each entry still echoes its full replay inputs,
+/// schedules included; this keeps one response a bounded size.
+const MAX_LIST_LIMIT: usize = 500;
+
+/// Query parameters for the listing.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct ListQuery {
+    /// Exact underlying symbol.
+    #[serde(default)]
+    pub(crate) symbol: Option<String>,
+    /// `initialized`, `in_progress` or `completed`.
+    #[serde(default)]
+    pub(crate) state: Option<String>,
+    /// Only simulations created at or after this RFC 3339 instant.
+    #[serde(default)]
+    pub(crate) created_after: Option<String>,
+    /// Only simulations created strictly before this RFC 3339 instant.
+    #[serde(default)]
+    pub(crate) created_before: Option<String>,
+    /// Comma-separated tags the simulation must all carry.
+    #[serde(default)]
+    pub(crate) tags: Option<String>,
+    /// Page size, `1..=500`. Defaults to `50`.
+    #[serde(default)]
+    pub(crate) limit: Option<usize>,
+    /// Matches to skip. Defaults to `0`; pass the previous page's
+    /// `next_offset` to continue.
+    #[serde(default)]
+    pub(crate) offset: Option<usize>,
+}
+
+/// A listing query resolved into what the store takes.
+#[derive(Debug, PartialEq)]
+struct ListRequest {
+    filter: SimulationFilter,
+    offset: usize,
+    limit: usize,
+}
+
+impl ListQuery {
+    /// Validates every parameter, naming the first that fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming the offending parameter.
+    fn resolve(&self) -> Result<ListRequest, ChainError> {
+        let state = self
+            .state
+            .as_deref()
+            .map(|raw| match raw {
+                "initialized" => Ok(SessionState::Initialized),
+                "in_progress" => Ok(SessionState::InProgress),
+                "completed" => Ok(SessionState::Completed),
+                other => Err(ChainError::Validation {
+                    field: "state".to_string(),
+                    reason: format!(
+                        "must be one of initialized, in_progress, completed; got {other:?}"
+                    ),
+                }),
+            })
+            .transpose()?;
+
+        let limit = self.limit.unwrap_or(DEFAULT_LIST_LIMIT);
+        if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+            return Err(ChainError::Validation {
+                field: "limit".to_string(),
+                reason: format!("must be between 1 and {MAX_LIST_LIMIT}, got {limit}"),
+            });
+        }
+
+        // Filtering on a tag that could never have been stored is a client
+        // mistake worth reporting, not an empty page.
+        let tags = match self.tags.as_deref() {
+            Some(raw) => crate::session::normalize_tags(
+                raw.split(',').map(|tag| tag.trim().to_string()).collect(),
+            )?,
+            None => Vec::new(),
+        };
+
+        Ok(ListRequest {
+            filter: SimulationFilter {
+                symbol: self.symbol.clone(),
+                state,
+                created_after: parse_instant("created_after", self.created_after.as_deref())?,
+                created_before: parse_instant("created_before", self.created_before.as_deref())?,
+                tags,
+            },
+            offset: self.offset.unwrap_or(0),
+            limit,
+        })
+    }
+}
+
+/// Parses an optional RFC 3339 query instant, naming `field` when it is
+/// malformed.
+fn parse_instant(field: &str, raw: Option<&str>) -> Result<Option<SystemTime>, ChainError> {
+    raw.map(|raw| {
+        DateTime::parse_from_rfc3339(raw)
+            .map(|instant| SystemTime::from(instant.with_timezone(&Utc)))
+            .map_err(|error| ChainError::Validation {
+                field: field.to_string(),
+                reason: format!("must be an RFC 3339 instant, got {raw:?}: {error}"),
+            })
+    })
+    .transpose()
+}
+
+/// Parses a path id, reporting a malformed one as a validation failure naming
+/// the field rather than as an opaque `400`.
+pub(crate) fn parse_id(raw: &str) -> Result<Uuid, ChainError> {
+    Uuid::parse_str(raw).map_err(|_| ChainError::Validation {
+        field: "id".to_string(),
+        reason: format!("must be a UUID, got {raw:?}"),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations",
+    description = "Create a deterministic rolling multi-expiration simulation. Resolves the \
+        effective seed, simulated start and step interval once, and returns them with the \
+        normalised schedules — together they are everything needed to replay the run. The \
+        configuration is immutable: changing any of it means creating a new simulation.",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Up to 255 printable ASCII characters. A retry with the same key and body gets the first response back, marked `Idempotent-Replayed: true`, instead of a second simulation")
+    ),
+    request_body = CreateSimulationRequest,
+    responses(
+        (status = 201, description = "Simulation created", body = SimulationResponse),
+        (status = 400, description = "Invalid request; body carries `error` and the offending `field`"),
+        (status = 409, description = "A simulation with the generated id already exists, `pinned` would exceed the pinned quota, or a request with this Idempotency-Key is still running"),
+        (status = 422, description = "The Idempotency-Key was first used with a different request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn create_simulation(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    body: web::Json<CreateSimulationRequest>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    // Fingerprinted as parsed, so a retry that only reformats the JSON still
+    // matches its key.
+    let fingerprinted = serde_json::to_vec(&*body).unwrap_or_default();
+    idempotent(&req, manager.idempotency(), &fingerprinted, async {
+        // Taken from the request before it is consumed by the parameter
+        // conversion, which owns the replay inputs and nothing else.
+        let options = match SimulationOptions::try_from(&*body) {
+            Ok(options) => options,
+            Err(error) => return map_error(error),
+        };
+        let parameters = match SimulationParametersV2::try_from(body.into_inner()) {
+            Ok(parameters) => parameters,
+            Err(error) => return map_error(error),
+        };
+
+        match manager.create(parameters, options).await {
+            Ok(simulation) => HttpResponse::Created().json(SimulationResponse::from(&simulation)),
+            Err(error) => map_error(error),
+        }
+    })
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/import",
+    description = "Recreate a simulation from a replay manifest, under a new id. The manifest \
+        must be unmodified: its digest covers every field. A manifest written under a \
+        different tape generation or tzdb release is refused unless `force=true`, because \
+        the recreated simulation would not serve the tape it describes; a forced import \
+        reports this service's tzdb release in its parameters. Tags, retention and pinning \
+        are not replay inputs and take their defaults.",
+    params(
+        ("force" = Option<bool>, Query, description = "Import despite a tape-generation or tzdb mismatch; defaults to false")
+    ),
+    request_body(content = Object, description = "A manifest from GET /api/v2/simulations/{id}/manifest"),
+    responses(
+        (status = 201, description = "Simulation recreated", body = SimulationResponse),
+        (status = 400, description = "Invalid, edited or mismatched manifest; body carries `error` and the offending `field`"),
+        (status = 409, description = "A simulation with the generated id already exists"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn import_simulation(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    query: web::Query<ImportQuery>,
+    body: web::Json<SimulationManifest>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    match manager.import(body.into_inner(), query.force).await {
+        Ok(simulation) => HttpResponse::Created().json(SimulationResponse::from(&simulation)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations",
+    description = "List simulations, oldest first, one page at a time. Every filter narrows: \
+        `tags` matches simulations carrying all of the listed tags. Metadata only — no \
+        snapshot is built. Pass the returned `next_offset` as `offset` to read the next \
+        page; it is null on the last one.",
+    params(
+        ("symbol" = Option<String>, Query, description = "Exact underlying symbol"),
+        ("state" = Option<String>, Query, description = "initialized, in_progress or completed"),
+        ("created_after" = Option<String>, Query, description = "RFC 3339; inclusive lower bound on creation"),
+        ("created_before" = Option<String>, Query, description = "RFC 3339; exclusive upper bound on creation"),
+        ("tags" = Option<String>, Query, description = "Comma-separated tags the simulation must all carry"),
+        ("limit" = Option<usize>, Query, description = "Page size, 1..=500; defaults to 50"),
+        ("offset" = Option<usize>, Query, description = "Matches to skip; defaults to 0")
+    ),
+    responses(
+        (status = 200, description = "One page of simulations", body = SimulationListResponse),
+        (status = 400, description = "Invalid query parameter; body carries `error` and the offending `field`"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn list_simulations(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    query: web::Query<ListQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let request = match query.resolve() {
+        Ok(request) => request,
+        Err(error) => return map_error(error),
+    };
+
+    match manager
+        .list(&request.filter, request.offset, request.limit)
+        .await
+    {
+        Ok(page) => HttpResponse::Ok().json(SimulationListResponse {
+            simulations: page
+                .simulations
+                .iter()
+                .map(SimulationResponse::from)
+                .collect(),
+            next_offset: page.next_offset,
+        }),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}",
+    description = "Read a simulation's metadata and effective parameters. Does not build a \
+        snapshot and does not move the cursor.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from an earlier response; a match returns 304 without a body")
+    ),
+    responses(
+        (status = 200, description = "The simulation; `ETag` names its revision", body = SimulationResponse),
+        (status = 304, description = "The simulation still matches If-None-Match"),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "Simulation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn get_simulation(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.get(id).await {
+        Ok(simulation) if etag::not_modified(&req, &simulation) => {
+            etag::not_modified_response(&simulation)
+        }
+        Ok(simulation) => HttpResponse::Ok()
+            .insert_header(etag::etag(&simulation))
+            .json(SimulationResponse::from(&simulation)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/manifest",
+    description = "Export the simulation's definition as a replay manifest: the effective \
+        parameters in their exact stored form, with the tzdb release, calendar, tape \
+        generation and crate version they depend on, and a SHA-256 digest over all of it. \
+        POST it to /api/v2/simulations/import, here or on another deployment, to recreate \
+        the run.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "The manifest", body = Object),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "Simulation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn get_manifest(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.manifest(id).await {
+        Ok(manifest) => HttpResponse::Ok().json(manifest),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/touch",
+    description = "Keep a simulation alive: restart its idle-retention window without \
+        moving the cursor. The touch is a new revision, so it changes the `ETag`. A pinned \
+        simulation never expires, so touching one is harmless.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "The window was restarted", body = SimulationResponse),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "Simulation not found"),
+        (status = 409, description = "Concurrent writes kept winning; retry"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn touch_simulation(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.touch(id).await {
+        Ok(simulation) => HttpResponse::Ok()
+            .insert_header(etag::etag(&simulation))
+            .json(SimulationResponse::from(&simulation)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/snapshot",
+    description = "Peek the snapshot at the current cursor. Safe and repeatable: it never \
+        advances the cursor and never persists anything, so calling it twice returns the \
+        same market. To advance, use POST /api/v2/simulations/{id}/step. The `ETag` \
+        names the revision; send it back as If-None-Match to get 304 until the \
+        simulation moves.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("If-None-Match" = Option<String>, Header, description = "An ETag from an earlier response; a match returns 304 without building the snapshot")
+    ),
+    responses(
+        (status = 200, description = "The snapshot at the current cursor", body = SnapshotResponse),
+        (status = 304, description = "The simulation still matches If-None-Match"),
+        (status = 400, description = "Malformed id, or the simulation is in a terminal error state"),
+        (status = 404, description = "Simulation not found"),
+        (status = 410, description = "Simulation completed; there is no current step"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn peek_snapshot(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    // Answered from the document alone, before a snapshot is built, so a
+    // client polling an idle simulation costs one read.
+    if req.headers().contains_key(IF_NONE_MATCH) {
+        match manager.get(id).await {
+            Ok(simulation) if etag::not_modified(&req, &simulation) => {
+                return etag::not_modified_response(&simulation);
+            }
+            Ok(_) => {}
+            Err(error) => return map_error(error),
+        }
+    }
+
+    match manager.peek(id).await {
+        Ok((simulation, snapshot)) => HttpResponse::Ok()
+            .insert_header(etag::etag(&simulation))
+            .json(snapshot_response(&simulation, &snapshot)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/step",
+    description = "Serve the snapshot at the current cursor, then advance exactly once. A \
+        simulation with steps = N serves indices 0..N-1 over N calls; the advance that \
+        serves the last snapshot marks it completed, and any further call returns 410. \
+        Pass `expected_step` to make a retry safe: if a previous attempt already consumed \
+        the step, the call returns 412 with the actual cursor instead of consuming another. \
+        If-Match with the ETag of a previous response does the same against the revision.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("expected_step" = Option<usize>, Query, description = "Expected current cursor; a mismatch returns 412 without advancing"),
+        ("If-Match" = Option<String>, Header, description = "ETags the simulation must still match, or `*`; a mismatch returns 412 without advancing"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Up to 255 printable ASCII characters. A retry with the same key gets the first response back, marked `Idempotent-Replayed: true`, instead of a second advance")
+    ),
+    responses(
+        (status = 200, description = "Served the snapshot and advanced once; `ETag` names the new revision", body = SnapshotResponse),
+        (status = 400, description = "Malformed id or If-Match, or the simulation is in a terminal error state"),
+        (status = 404, description = "Simulation not found"),
+        (status = 409, description = "Another request advanced the simulation first, or a request with this Idempotency-Key is still running; re-read and retry"),
+        (status = 410, description = "Simulation completed; no further steps"),
+        (status = 412, description = "expected_step or If-Match does not match; body carries `error` and `current_step`, plus `etag` for If-Match"),
+        (status = 422, description = "The Idempotency-Key was first used with a different request"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn advance_simulation(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<AdvanceQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    idempotent(&req, manager.idempotency(), &[], async {
+        let id = match parse_id(&path.id) {
+            Ok(id) => id,
+            Err(error) => return map_error(error),
+        };
+
+        let if_match = match etag::if_match(&req) {
+            Ok(if_match) => if_match,
+            Err(error) => return map_error(error),
+        };
+
+        // The preconditions are transport-level checks, resolved before
+        // anything is built or persisted, so a mismatch costs nothing. The
+        // advance then commits against the revision that was checked, so a
+        // writer that slips in between is a `412` too, not a step taken from
+        // a cursor the client never named.
+        let mut expected_version = None;
+        if query.expected_step.is_some() || if_match.is_some() {
+            let simulation = match manager.get(id).await {
+                Ok(simulation) => simulation,
+                Err(error) => return map_error(error),
+            };
+            if let Some(expected) = query.expected_step {
+                if simulation.current_step != expected {
+                    return precondition_failed(&simulation);
+                }
+                expected_version = Some(simulation.version);
+            }
+            if let Some(condition) = &if_match {
+                if !etag::matches(condition, &simulation) {
+                    return etag::precondition_failed(&simulation);
+                }
+                expected_version =
+                    etag::pinned_version(condition, &simulation).or(expected_version);
+            }
+        }
+
+        match manager.advance_expecting(id, expected_version).await {
+            Ok((simulation, snapshot)) => HttpResponse::Ok()
+                .insert_header(etag::etag(&simulation))
+                .json(snapshot_response(&simulation, &snapshot)),
+            Err(ChainError::Conflict(_)) if expected_version.is_some() => {
+                if if_match.is_some() {
+                    if_match_lost(&manager, id).await
+                } else {
+                    expected_step_lost(&manager, id).await
+                }
+            }
+            Err(error) => map_error(error),
+        }
+    })
+    .await
+}
+
+/// The `412` for an `If-Match` write that passed the check and then lost its
+/// compare-and-swap: the tag it named stopped matching in between.
+async fn if_match_lost(manager: &SimulationManager, id: Uuid) -> HttpResponse {
+    match manager.get(id).await {
+        Ok(simulation) => etag::precondition_failed(&simulation),
+        Err(error) => map_error(error),
+    }
+}
+
+/// The `412` for an `expected_step` advance that passed the check and then
+/// lost its compare-and-swap: the cursor it named moved in between.
+async fn expected_step_lost(manager: &SimulationManager, id: Uuid) -> HttpResponse {
+    match manager.get(id).await {
+        Ok(simulation) => precondition_failed(&simulation),
+        Err(error) => map_error(error),
+    }
+}
+
+/// The `412` body: the same shape v1 uses for the same precondition.
+pub(crate) fn precondition_failed(simulation: &SessionV2) -> HttpResponse {
+    HttpResponse::PreconditionFailed().json(serde_json::json!({
+        "error": "expected_step does not match the simulation's current cursor",
+        "current_step": simulation.current_step,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/snapshot/next",
+    description = "Wait for the simulation's next advance and return the snapshot it served, \
+        whoever advanced it — a playback or a client's POST /step. Returns 204 when \
+        `timeout_ms` passes first; re-issue the request to keep waiting.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("timeout_ms" = Option<u64>, Query, description = "Milliseconds to wait, 1..=60000; defaults to 30000")
+    ),
+    responses(
+        (status = 200, description = "The snapshot the next advance served", body = SnapshotResponse),
+        (status = 204, description = "No advance within the timeout"),
+        (status = 400, description = "Malformed id or timeout, or the simulation is in a terminal error state"),
+        (status = 404, description = "Simulation not found"),
+        (status = 410, description = "Simulation completed; there will be no next step"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn next_snapshot(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<NextSnapshotQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+    let timeout = match query.timeout() {
+        Ok(timeout) => timeout,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.next_snapshot(id, timeout).await {
+        Ok(Some((simulation, snapshot))) => HttpResponse::Ok()
+            .insert_header(etag::etag(&simulation))
+            .json(snapshot_response(&simulation, &snapshot)),
+        Ok(None) => HttpResponse::NoContent().finish(),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/seek",
+    description = "Move the cursor forward to `to_step` without serving the steps in between. \
+        The skipped steps are not priced and not filed in the warehouse; they stay \
+        reproducible by replay. Seeking backwards is refused, and seeking to the current \
+        step changes nothing. A running playback continues from the new cursor.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("to_step" = usize, Query, description = "The step to move the cursor to")
+    ),
+    responses(
+        (status = 200, description = "The cursor moved", body = SimulationResponse),
+        (status = 400, description = "Malformed id, `to_step` out of range, or the simulation is in a terminal error state"),
+        (status = 404, description = "Simulation not found"),
+        (status = 409, description = "Another request advanced the simulation first; re-read and retry"),
+        (status = 410, description = "Simulation completed; nothing to seek to"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn seek_simulation(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<SeekQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.seek(id, query.to_step).await {
+        Ok(simulation) => HttpResponse::Ok().json(SimulationResponse::from(&simulation)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/playback",
+    description = "Start advancing the simulation on a wall-clock pace, server-side. Each tick \
+        is an ordinary advance, so manual steps may continue alongside: a tick that loses \
+        the race to one is skipped. The playback ends on its own when the simulation \
+        completes. Playback is held by the replica that started it and is not persisted; \
+        a restart stops it, and starting again resumes from the cursor.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    request_body = StartPlaybackRequest,
+    responses(
+        (status = 201, description = "Playback started", body = PlaybackResponse),
+        (status = 400, description = "Malformed id or pace, or the simulation is in a terminal error state"),
+        (status = 404, description = "Simulation not found"),
+        (status = 409, description = "The simulation is already playing, or the service is at its playback limit"),
+        (status = 410, description = "Simulation completed; nothing left to play"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn start_playback(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    body: web::Json<StartPlaybackRequest>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+    let pace = match body.pace() {
+        Ok(pace) => pace,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.start_playback(id, pace).await {
+        Ok(status) => HttpResponse::Created().json(playback_response(id, &status)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/playback",
+    description = "Read the state of the simulation's playback on this replica.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "The playback", body = PlaybackResponse),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "The simulation has no playback here"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn get_playback(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.playback_status(id) {
+        Ok(status) => HttpResponse::Ok().json(playback_response(id, &status)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/playback/pause",
+    description = "Pause the simulation's playback. Pausing a paused playback changes nothing.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "Paused", body = PlaybackResponse),
+        (status = 400, description = "Malformed id, or the playback has finished"),
+        (status = 404, description = "The simulation has no playback here"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn pause_playback(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.pause_playback(id) {
+        Ok(status) => HttpResponse::Ok().json(playback_response(id, &status)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/playback/resume",
+    description = "Resume a paused playback at its pace; the first tick comes one interval \
+        later. Resuming a playing playback changes nothing.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "Playing", body = PlaybackResponse),
+        (status = 400, description = "Malformed id, or the playback has finished"),
+        (status = 404, description = "The simulation has no playback here"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn resume_playback(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.resume_playback(id) {
+        Ok(status) => HttpResponse::Ok().json(playback_response(id, &status)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/simulations/{id}/playback",
+    description = "Stop the simulation's playback. The simulation keeps its cursor.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "Stopped", body = Object),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "The simulation has no playback here"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn stop_playback(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    if manager.stop_playback(id) {
+        HttpResponse::Ok().json(serde_json::json!({
+            "message": format!("Playback stopped: {id}"),
+            "simulation_id": id.to_string(),
+        }))
+    } else {
+        map_error(ChainError::NotFound(format!(
+            "Simulation {id} has no playback"
+        )))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v2/simulations/{id}/materialize",
+    description = "Start filing a step range of the simulation in the snapshot warehouse, in \
+        the background and without moving the cursor. Steps the warehouse already holds are \
+        skipped, so a job that failed or was cancelled resumes by being started again. The \
+        job is held by the replica that started it and is not persisted.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("from_step" = Option<usize>, Query, description = "First step to file, inclusive; defaults to 0"),
+        ("to_step" = Option<usize>, Query, description = "Last step to file, inclusive; defaults to the last step")
+    ),
+    responses(
+        (status = 202, description = "Materialization started", body = MaterializationResponse),
+        (status = 400, description = "Malformed id, a range outside the tape, or snapshot persistence is not enabled"),
+        (status = 404, description = "Simulation not found"),
+        (status = 409, description = "The simulation is already being materialized, or the service is at its limit"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn start_materialization(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<MaterializeQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager
+        .start_materialization(id, query.from_step, query.to_step)
+        .await
+    {
+        Ok(status) => HttpResponse::Accepted().json(materialization_response(id, &status)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/materialize",
+    description = "Read the state and progress of the simulation's materialization on this \
+        replica. A finished job stays readable until the next one starts.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "The materialization", body = MaterializationResponse),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "The simulation has no materialization here"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn get_materialization(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.materialization_status(id) {
+        Ok(status) => HttpResponse::Ok().json(materialization_response(id, &status)),
+        Err(error) => map_error(error),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v2/simulations/{id}/materialize",
+    description = "Cancel the simulation's materialization. The steps it already filed stay \
+        in the warehouse. Cancelling a finished job changes nothing.",
+    params(("id" = String, Path, description = "The simulation's identifier")),
+    responses(
+        (status = 200, description = "Cancelled", body = MaterializationResponse),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "The simulation has no materialization here"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn cancel_materialization(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match manager.cancel_materialization(id) {
+        Ok(status) => HttpResponse::Ok().json(materialization_response(id, &status)),
+        Err(error) => map_error(error),
+    }
 }
 
-/// Query parameters for the advance command.
-#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
-pub(crate) struct AdvanceQuery {
-    /// Optional expected cursor. When supplied, the advance proceeds only if
-    /// the simulation is at exactly this step; otherwise `412` is returned with
-    /// the actual cursor and nothing is consumed.
-    #[serde(default)]
-    pub(crate) expected_step: Option<usize>,
+#[utoipa::path(
+    post,
+    path = "/api/v2/webhooks",
+    description = "Register a webhook that hears about every simulation: `completed`, `expired` \
+        and `deleted`, plus `step` every `every_n_steps` advances when that is set. Deliveries \
+        are queued off the request path, retried with a doubling backoff, and signed: \
+        `X-OCS-Signature` is `sha256=` and the hex HMAC-SHA256 of `{X-OCS-Timestamp}.{body}` \
+        under `signing_key`. Registrations are held by this replica and are not persisted.",
+    request_body = RegisterWebhookRequest,
+    responses(
+        (status = 201, description = "Webhook registered", body = WebhookResponse),
+        (status = 400, description = "Invalid `url`, `signing_key` or `every_n_steps`"),
+        (status = 409, description = "The service is at its webhook limit"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn register_webhook(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    body: web::Json<RegisterWebhookRequest>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    match manager
+        .register_webhook(None, body.into_inner().into())
+        .await
+    {
+        Ok(webhook) => HttpResponse::Created().json(WebhookResponse::from(&webhook)),
+        Err(error) => map_error(error),
+    }
 }
 
-/// Parses a path id, reporting a malformed one as a validation failure naming
-/// the field rather than as an opaque `400`.
-fn parse_id(raw: &str) -> Result<Uuid, ChainError> {
-    Uuid::parse_str(raw).map_err(|_| ChainError::Validation {
-        field: "id".to_string(),
-        reason: format!("must be a UUID, got {raw:?}"),
-    })
+#[utoipa::path(
+    get,
+    path = "/api/v2/webhooks",
+    description = "List the global webhooks registered on this replica. Signing keys are never \
+        returned.",
+    responses(
+        (status = 200, description = "The global webhooks", body = WebhookListResponse),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn list_webhooks(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    match manager.webhooks(None).await {
+        Ok(webhooks) => HttpResponse::Ok().json(WebhookListResponse {
+            webhooks: webhooks.iter().map(WebhookResponse::from).collect(),
+        }),
+        Err(error) => map_error(error),
+    }
 }
 
 #[utoipa::path(
-    post,
-    path = "/api/v2/simulations",
-    description = "Create a deterministic rolling multi-expiration simulation. Resolves the \
-        effective seed, simulated start and step interval once, and returns them with the \
-        normalised schedules — together they are everything needed to replay the run. The \
-        configuration is immutable: changing any of it means creating a new simulation.",
-    request_body = CreateSimulationRequest,
+    delete,
+    path = "/api/v2/webhooks/{id}",
+    description = "Remove a webhook registration, global or per-simulation. Deliveries already \
+        queued for it are still made.",
+    params(("id" = String, Path, description = "The webhook's identifier")),
     responses(
-        (status = 201, description = "Simulation created", body = SimulationResponse),
-        (status = 400, description = "Invalid request; body carries `error` and the offending `field`"),
-        (status = 409, description = "A simulation with the generated id already exists"),
+        (status = 200, description = "Removed", body = Object),
+        (status = 400, description = "Malformed id"),
+        (status = 404, description = "Webhook not found"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub(crate) async fn create_simulation(
+pub(crate) async fn delete_webhook(
     req: HttpRequest,
     manager: web::Data<Arc<SimulationManager>>,
-    body: web::Json<CreateSimulationRequest>,
+    path: web::Path<WebhookPath>,
 ) -> impl Responder {
     info!("{} {}", req.method(), req.path());
 
-    let parameters = match SimulationParametersV2::try_from(body.into_inner()) {
-        Ok(parameters) => parameters,
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
         Err(error) => return map_error(error),
     };
 
-    match manager.create(parameters).await {
-        Ok(simulation) => HttpResponse::Created().json(SimulationResponse::from(&simulation)),
-        Err(error) => map_error(error),
+    if manager.remove_webhook(id) {
+        HttpResponse::Ok().json(serde_json::json!({
+            "message": format!("Webhook removed: {id}"),
+            "webhook_id": id.to_string(),
+        }))
+    } else {
+        map_error(ChainError::NotFound(format!(
+            "Webhook with id {id} not found"
+        )))
     }
 }
 
 #[utoipa::path(
-    get,
-    path = "/api/v2/simulations/{id}",
-    description = "Read a simulation's metadata and effective parameters. Does not build a \
-        snapshot and does not move the cursor.",
+    post,
+    path = "/api/v2/simulations/{id}/webhooks",
+    description = "Register a webhook on one simulation. It hears the same events as a global \
+        one, for this simulation only, signed the same way, and is dropped once the \
+        simulation is deleted or expires.",
     params(("id" = String, Path, description = "The simulation's identifier")),
+    request_body = RegisterWebhookRequest,
     responses(
-        (status = 200, description = "The simulation", body = SimulationResponse),
-        (status = 400, description = "Malformed id"),
+        (status = 201, description = "Webhook registered", body = WebhookResponse),
+        (status = 400, description = "Malformed id, or invalid `url`, `signing_key` or `every_n_steps`"),
         (status = 404, description = "Simulation not found"),
+        (status = 409, description = "The service is at its webhook limit"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub(crate) async fn get_simulation(
+pub(crate) async fn register_simulation_webhook(
     req: HttpRequest,
     manager: web::Data<Arc<SimulationManager>>,
     path: web::Path<SimulationPath>,
+    body: web::Json<RegisterWebhookRequest>,
 ) -> impl Responder {
     info!("{} {}", req.method(), req.path());
 
@@ -115,28 +1158,29 @@ pub(crate) async fn get_simulation(
         Err(error) => return map_error(error),
     };
 
-    match manager.get(id).await {
-        Ok(simulation) => HttpResponse::Ok().json(SimulationResponse::from(&simulation)),
+    match manager
+        .register_webhook(Some(id), body.into_inner().into())
+        .await
+    {
+        Ok(webhook) => HttpResponse::Created().json(WebhookResponse::from(&webhook)),
         Err(error) => map_error(error),
     }
 }
 
 #[utoipa::path(
     get,
-    path = "/api/v2/simulations/{id}/snapshot",
-    description = "Peek the snapshot at the current cursor. Safe and repeatable: it never \
-        advances the cursor and never persists anything, so calling it twice returns the \
-        same market. To advance, use POST /api/v2/simulations/{id}/step.",
+    path = "/api/v2/simulations/{id}/webhooks",
+    description = "List the webhooks registered on one simulation. Global webhooks, which also \
+        hear about it, are listed under `/api/v2/webhooks`.",
     params(("id" = String, Path, description = "The simulation's identifier")),
     responses(
-        (status = 200, description = "The snapshot at the current cursor", body = SnapshotResponse),
-        (status = 400, description = "Malformed id, or the simulation is in a terminal error state"),
+        (status = 200, description = "The simulation's webhooks", body = WebhookListResponse),
+        (status = 400, description = "Malformed id"),
         (status = 404, description = "Simulation not found"),
-        (status = 410, description = "Simulation completed; there is no current step"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub(crate) async fn peek_snapshot(
+pub(crate) async fn list_simulation_webhooks(
     req: HttpRequest,
     manager: web::Data<Arc<SimulationManager>>,
     path: web::Path<SimulationPath>,
@@ -148,41 +1192,40 @@ pub(crate) async fn peek_snapshot(
         Err(error) => return map_error(error),
     };
 
-    match manager.peek(id).await {
-        Ok((simulation, snapshot)) => {
-            HttpResponse::Ok().json(snapshot_response(&simulation, &snapshot))
-        }
+    match manager.webhooks(Some(id)).await {
+        Ok(webhooks) => HttpResponse::Ok().json(WebhookListResponse {
+            webhooks: webhooks.iter().map(WebhookResponse::from).collect(),
+        }),
         Err(error) => map_error(error),
     }
 }
 
 #[utoipa::path(
-    post,
-    path = "/api/v2/simulations/{id}/step",
-    description = "Serve the snapshot at the current cursor, then advance exactly once. A \
-        simulation with steps = N serves indices 0..N-1 over N calls; the advance that \
-        serves the last snapshot marks it completed, and any further call returns 410. \
-        Pass `expected_step` to make a retry safe: if a previous attempt already consumed \
-        the step, the call returns 412 with the actual cursor instead of consuming another.",
+    delete,
+    path = "/api/v2/simulations/{id}",
+    description = "Delete a simulation and evict everything cached for it. With If-Match, \
+        only the revision the client last saw is deleted. With `purge=true` its snapshots \
+        are also removed from the warehouse now rather than at the table TTL; a purge that \
+        fails after the delete is retried by repeating the request, which purges again and \
+        answers 404, or through POST /api/v2/snapshots/purge.",
     params(
         ("id" = String, Path, description = "The simulation's identifier"),
-        ("expected_step" = Option<usize>, Query, description = "Expected current cursor; a mismatch returns 412 without advancing")
+        ("purge" = Option<bool>, Query, description = "Also remove its warehouse rows now; defaults to false"),
+        ("If-Match" = Option<String>, Header, description = "ETags the simulation must still match, or `*`; a mismatch returns 412 without deleting")
     ),
     responses(
-        (status = 200, description = "Served the snapshot and advanced once", body = SnapshotResponse),
-        (status = 400, description = "Malformed id, or the simulation is in a terminal error state"),
+        (status = 200, description = "Deleted", body = Object),
+        (status = 400, description = "Malformed id or If-Match, or `purge` without snapshot persistence"),
         (status = 404, description = "Simulation not found"),
-        (status = 409, description = "Another request advanced the simulation first; re-read and retry"),
-        (status = 410, description = "Simulation completed; no further steps"),
-        (status = 412, description = "expected_step does not match the cursor; body carries `error` and `current_step`"),
+        (status = 412, description = "If-Match does not match; body carries `error`, `current_step` and `etag`"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub(crate) async fn advance_simulation(
+pub(crate) async fn delete_simulation(
     req: HttpRequest,
     manager: web::Data<Arc<SimulationManager>>,
     path: web::Path<SimulationPath>,
-    query: web::Query<AdvanceQuery>,
+    query: web::Query<DeleteQuery>,
 ) -> impl Responder {
     info!("{} {}", req.method(), req.path());
 
@@ -190,67 +1233,89 @@ pub(crate) async fn advance_simulation(
         Ok(id) => id,
         Err(error) => return map_error(error),
     };
+    // Refused before anything is deleted: a purge that cannot happen must not
+    // leave the client with half of what it asked for.
+    if query.purge && manager.warehouse().is_none() {
+        return map_error(ChainError::InvalidState(
+            "Snapshot persistence is not enabled; there is nothing to purge".to_string(),
+        ));
+    }
 
-    // The precondition is a transport-level check, resolved before anything is
-    // built or persisted, so a mismatch costs nothing.
-    if let Some(expected) = query.expected_step {
-        match manager.get(id).await {
-            Ok(simulation) if simulation.current_step != expected => {
-                return precondition_failed(&simulation);
+    let mut expected_version = None;
+    match etag::if_match(&req) {
+        Ok(Some(condition)) => {
+            let simulation = match manager.get(id).await {
+                Ok(simulation) => simulation,
+                Err(error) => return map_error(error),
+            };
+            if !etag::matches(&condition, &simulation) {
+                return etag::precondition_failed(&simulation);
             }
-            Ok(_) => {}
-            Err(error) => return map_error(error),
+            expected_version = etag::pinned_version(&condition, &simulation);
         }
+        Ok(None) => {}
+        Err(error) => return map_error(error),
     }
 
-    match manager.advance(id).await {
-        Ok((simulation, snapshot)) => {
-            HttpResponse::Ok().json(snapshot_response(&simulation, &snapshot))
+    let deleted = match manager.delete_expecting(id, expected_version).await {
+        Ok(deleted) => deleted,
+        Err(ChainError::Conflict(_)) if expected_version.is_some() => {
+            return if_match_lost(&manager, id).await;
         }
-        Err(error) => map_error(error),
+        Err(error) => return map_error(error),
+    };
+    // Also when nothing was deleted, so repeating a request whose purge failed
+    // finishes the job.
+    if query.purge
+        && let Err(error) = manager.purge_snapshots(&[id]).await
+    {
+        warn!(simulation_id = %id, deleted, error = %error, "Could not purge the simulation's snapshots");
+        return map_error(error);
     }
-}
 
-/// The `412` body: the same shape v1 uses for the same precondition.
-fn precondition_failed(simulation: &SessionV2) -> HttpResponse {
-    HttpResponse::PreconditionFailed().json(serde_json::json!({
-        "error": "expected_step does not match the simulation's current cursor",
-        "current_step": simulation.current_step,
-    }))
+    if deleted {
+        HttpResponse::Ok().json(serde_json::json!({
+            "message": format!("Simulation deleted successfully: {id}"),
+            "simulation_id": id.to_string(),
+        }))
+    } else {
+        map_error(ChainError::NotFound(format!(
+            "Simulation with id {id} not found"
+        )))
+    }
 }
 
 #[utoipa::path(
-    delete,
-    path = "/api/v2/simulations/{id}",
-    description = "Delete a simulation and evict everything cached for it.",
-    params(("id" = String, Path, description = "The simulation's identifier")),
+    post,
+    path = "/api/v2/snapshots/purge",
+    description = "Remove the warehouse rows of deleted simulations now, rather than at the \
+        table TTL. Every id must already be deleted: a live simulation would be filed again \
+        by its next advance, so naming one refuses the whole request. Repeating a purge is \
+        safe.",
+    request_body = PurgeSnapshotsRequest,
     responses(
-        (status = 200, description = "Deleted", body = Object),
-        (status = 400, description = "Malformed id"),
-        (status = 404, description = "Simulation not found"),
+        (status = 200, description = "Purged", body = PurgeSnapshotsResponse),
+        (status = 400, description = "Malformed `simulation_ids`, or snapshot persistence is not enabled"),
+        (status = 409, description = "A named simulation still exists; nothing was purged"),
         (status = 500, description = "Internal server error")
     )
 )]
-pub(crate) async fn delete_simulation(
+pub(crate) async fn purge_snapshots(
     req: HttpRequest,
     manager: web::Data<Arc<SimulationManager>>,
-    path: web::Path<SimulationPath>,
+    body: web::Json<PurgeSnapshotsRequest>,
 ) -> impl Responder {
     info!("{} {}", req.method(), req.path());
 
-    let id = match parse_id(&path.id) {
-        Ok(id) => id,
+    let ids = match body.ids() {
+        Ok(ids) => ids,
         Err(error) => return map_error(error),
     };
 
-    match manager.delete(id).await {
-        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
-            "message": format!("Simulation deleted successfully: {id}"),
-            "simulation_id": id.to_string(),
-        })),
-        Ok(false) => map_error(ChainError::NotFound(format!(
-            "Simulation with id {id} not found"
-        ))),
+    match manager.purge_snapshots(&ids).await {
+        Ok(()) => HttpResponse::Ok().json(PurgeSnapshotsResponse {
+            simulation_ids: ids.iter().map(Uuid::to_string).collect(),
+        }),
         Err(error) => map_error(error),
     }
 }
@@ -560,51 +1625,257 @@ mod tests {
         );
     }
 
-    /// A matching `expected_step` advances; a stale one is 412 with the actual
-    /// cursor, and nothing is consumed.
+    /// A matching `expected_step` advances; a stale one is 412 with the actual
+    /// cursor, and nothing is consumed.
+    #[actix_web::test]
+    async fn test_the_expected_step_precondition_protects_a_retry() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let step_uri =
+            |expected: usize| format!("/api/v2/simulations/{id}/step?expected_step={expected}");
+
+        let ok = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri(&step_uri(0))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(ok.status(), StatusCode::OK);
+
+        // The retry a client sends after a lost response: the cursor has moved,
+        // so the precondition refuses rather than consuming another step.
+        let stale = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri(&step_uri(0))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(stale.status(), StatusCode::PRECONDITION_FAILED);
+        let body: Value = actix_test::read_body_json(stale).await;
+        assert_eq!(body.get("current_step"), Some(&json!(1)));
+        assert!(body.get("error").is_some());
+
+        // And the cursor really did not move.
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!("/api/v2/simulations/{id}"))
+                .to_request(),
+        )
+        .await;
+        let simulation: Value = actix_test::read_body_json(response).await;
+        assert_eq!(
+            simulation.get("cursor").and_then(|c| c.get("current_step")),
+            Some(&json!(1))
+        );
+    }
+
+    fn etag_of(response: &actix_web::dev::ServiceResponse) -> String {
+        match response
+            .headers()
+            .get("ETag")
+            .and_then(|value| value.to_str().ok())
+        {
+            Some(etag) => etag.to_string(),
+            None => panic!("the response must carry an ETag"),
+        }
+    }
+
+    /// A peek under a current `If-None-Match` is 304 until the simulation
+    /// moves, and the tag names the revision rather than the response.
+    #[actix_web::test]
+    async fn test_if_none_match_answers_304_until_the_simulation_moves() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let peek_uri = format!("/api/v2/simulations/{id}/snapshot");
+        let peek = |etag: &str| {
+            actix_test::TestRequest::get()
+                .uri(&peek_uri)
+                .insert_header(("If-None-Match", etag.to_string()))
+                .to_request()
+        };
+
+        let first = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get().uri(&peek_uri).to_request(),
+        )
+        .await;
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = etag_of(&first);
+
+        let metadata = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!("/api/v2/simulations/{id}"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(etag_of(&metadata), etag, "one revision, one tag");
+
+        let cached = actix_test::call_service(&app, peek(&etag)).await;
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(etag_of(&cached), etag);
+        let weak = actix_test::call_service(&app, peek(&format!("W/{etag}"))).await;
+        assert_eq!(weak.status(), StatusCode::NOT_MODIFIED);
+
+        let advanced = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri(&format!("/api/v2/simulations/{id}/step"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(advanced.status(), StatusCode::OK);
+
+        let fresh = actix_test::call_service(&app, peek(&etag)).await;
+        assert_eq!(fresh.status(), StatusCode::OK);
+        assert_ne!(etag_of(&fresh), etag);
+    }
+
+    /// `If-Match` on an advance is the revision precondition: the tag a step
+    /// returned advances once, a replay of it is 412 with the current tag, and
+    /// a weak tag never matches.
+    #[actix_web::test]
+    async fn test_if_match_guards_an_advance() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let step_uri = format!("/api/v2/simulations/{id}/step");
+        let step = |etag: &str| {
+            actix_test::TestRequest::post()
+                .uri(&step_uri)
+                .insert_header(("If-Match", etag.to_string()))
+                .to_request()
+        };
+
+        let created = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&format!("/api/v2/simulations/{id}"))
+                .to_request(),
+        )
+        .await;
+        let etag = etag_of(&created);
+
+        let weak = actix_test::call_service(&app, step(&format!("W/{etag}"))).await;
+        assert_eq!(weak.status(), StatusCode::PRECONDITION_FAILED);
+
+        let ok = actix_test::call_service(&app, step(&etag)).await;
+        assert_eq!(ok.status(), StatusCode::OK);
+        let next = etag_of(&ok);
+        assert_ne!(next, etag);
+
+        let stale = actix_test::call_service(&app, step(&etag)).await;
+        assert_eq!(stale.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(etag_of(&stale), next);
+        let body: Value = actix_test::read_body_json(stale).await;
+        assert_eq!(body.get("current_step"), Some(&json!(1)));
+        assert_eq!(body.get("etag"), Some(&json!(next)));
+
+        let any = actix_test::call_service(&app, step("*")).await;
+        assert_eq!(any.status(), StatusCode::OK);
+    }
+
+    /// `If-Match` on a delete removes only the revision it names, and a
+    /// malformed value is refused rather than ignored.
+    #[actix_web::test]
+    async fn test_if_match_guards_a_delete() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let uri = format!("/api/v2/simulations/{id}");
+        let delete = |etag: &str| {
+            actix_test::TestRequest::delete()
+                .uri(&uri)
+                .insert_header(("If-Match", etag.to_string()))
+                .to_request()
+        };
+
+        let read =
+            actix_test::call_service(&app, actix_test::TestRequest::get().uri(&uri).to_request())
+                .await;
+        let etag = etag_of(&read);
+
+        let malformed = actix_test::call_service(&app, delete("not-quoted")).await;
+        assert_eq!(malformed.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(malformed).await;
+        assert_eq!(body.get("field"), Some(&json!("If-Match")));
+
+        let advanced = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri(&format!("{uri}/step"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(advanced.status(), StatusCode::OK);
+        let current = etag_of(&advanced);
+
+        let stale = actix_test::call_service(&app, delete(&etag)).await;
+        assert_eq!(stale.status(), StatusCode::PRECONDITION_FAILED);
+
+        let deleted = actix_test::call_service(&app, delete(&current)).await;
+        assert_eq!(deleted.status(), StatusCode::OK);
+    }
+
+    /// A manifest imports as a new simulation with the same parameters, and
+    /// an edited one is refused by its digest.
     #[actix_web::test]
-    async fn test_the_expected_step_precondition_protects_a_retry() {
+    async fn test_a_manifest_recreates_the_simulation() {
         let app = v2_service!();
-        let id = id_of(&create!(app));
-        let step_uri =
-            |expected: usize| format!("/api/v2/simulations/{id}/step?expected_step={expected}");
+        let original = create!(app);
+        let id = id_of(&original);
 
-        let ok = actix_test::call_service(
+        let response = actix_test::call_service(
             &app,
-            actix_test::TestRequest::post()
-                .uri(&step_uri(0))
+            actix_test::TestRequest::get()
+                .uri(&format!("/api/v2/simulations/{id}/manifest"))
                 .to_request(),
         )
         .await;
-        assert_eq!(ok.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::OK);
+        let manifest: Value = actix_test::read_body_json(response).await;
+        assert_eq!(manifest.get("source_id"), Some(&json!(id)));
+        for field in [
+            "crate_version",
+            "tape_generation",
+            "tzdb_version",
+            "calendar",
+        ] {
+            assert!(
+                manifest.get(field).is_some(),
+                "the manifest must carry {field}"
+            );
+        }
 
-        // The retry a client sends after a lost response: the cursor has moved,
-        // so the precondition refuses rather than consuming another step.
-        let stale = actix_test::call_service(
+        let imported = actix_test::call_service(
             &app,
             actix_test::TestRequest::post()
-                .uri(&step_uri(0))
+                .uri("/api/v2/simulations/import")
+                .set_json(&manifest)
                 .to_request(),
         )
         .await;
-        assert_eq!(stale.status(), StatusCode::PRECONDITION_FAILED);
-        let body: Value = actix_test::read_body_json(stale).await;
-        assert_eq!(body.get("current_step"), Some(&json!(1)));
-        assert!(body.get("error").is_some());
-
-        // And the cursor really did not move.
-        let response = actix_test::call_service(
+        assert_eq!(imported.status(), StatusCode::CREATED);
+        let imported: Value = actix_test::read_body_json(imported).await;
+        assert_ne!(id_of(&imported), id);
+        assert_eq!(imported.get("parameters"), original.get("parameters"));
+
+        let mut edited = manifest.clone();
+        if let Some(parameters) = edited.get_mut("parameters") {
+            parameters["seed"] = json!(43);
+        }
+        let refused = actix_test::call_service(
             &app,
-            actix_test::TestRequest::get()
-                .uri(&format!("/api/v2/simulations/{id}"))
+            actix_test::TestRequest::post()
+                .uri("/api/v2/simulations/import?force=true")
+                .set_json(&edited)
                 .to_request(),
         )
         .await;
-        let simulation: Value = actix_test::read_body_json(response).await;
-        assert_eq!(
-            simulation.get("cursor").and_then(|c| c.get("current_step")),
-            Some(&json!(1))
-        );
+        assert_eq!(refused.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(refused).await;
+        assert_eq!(body.get("field"), Some(&json!("digest")));
     }
 
     /// Walking to the end completes the simulation; anything after is 410.
@@ -804,6 +2075,610 @@ mod tests {
         assert_eq!(snapshot_of(&first).await, snapshot_of(&second).await);
     }
 
+    /// Listing filters by tag and pages with an offset the client echoes back.
+    #[actix_web::test]
+    async fn test_list_filters_and_pages() {
+        let app = v2_service!();
+        let first = create!(app);
+        let second = create!(app);
+        let mut tagged_body = reference_body();
+        tagged_body["tags"] = json!(["nightly", "desk:a"]);
+        let request = actix_test::TestRequest::post()
+            .uri("/api/v2/simulations")
+            .set_json(tagged_body)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let tagged: Value = actix_test::read_body_json(response).await;
+        assert_eq!(tagged.get("tags"), Some(&json!(["desk:a", "nightly"])));
+
+        let request = actix_test::TestRequest::get()
+            .uri("/api/v2/simulations?limit=2")
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let page: Value = actix_test::read_body_json(response).await;
+        let ids: Vec<String> = match page.get("simulations").and_then(Value::as_array) {
+            Some(simulations) => simulations.iter().map(id_of).collect(),
+            None => panic!("the page must carry simulations: {page}"),
+        };
+        assert_eq!(ids, vec![id_of(&first), id_of(&second)]);
+        assert_eq!(page.get("next_offset"), Some(&json!(2)));
+
+        let request = actix_test::TestRequest::get()
+            .uri("/api/v2/simulations?offset=2&limit=2")
+            .to_request();
+        let page: Value = actix_test::call_and_read_body_json(&app, request).await;
+        assert_eq!(page.get("next_offset"), Some(&Value::Null));
+
+        let request = actix_test::TestRequest::get()
+            .uri("/api/v2/simulations?tags=nightly&state=initialized&symbol=SPX")
+            .to_request();
+        let page: Value = actix_test::call_and_read_body_json(&app, request).await;
+        let ids: Vec<String> = match page.get("simulations").and_then(Value::as_array) {
+            Some(simulations) => simulations.iter().map(id_of).collect(),
+            None => panic!("the page must carry simulations: {page}"),
+        };
+        assert_eq!(ids, vec![id_of(&tagged)]);
+    }
+
+    /// A touch keeps the cursor but is a new revision, so a copy read before
+    /// it — whose `updated_at` is now stale — is no longer `304`.
+    #[actix_web::test]
+    async fn test_a_touch_keeps_the_cursor_and_moves_the_revision() {
+        let app = v2_service!();
+        let body = create!(app);
+        let id = id_of(&body);
+        let uri = format!("/api/v2/simulations/{id}");
+
+        let read =
+            actix_test::call_service(&app, actix_test::TestRequest::get().uri(&uri).to_request())
+                .await;
+        let etag = etag_of(&read);
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{id}/touch"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let touched_etag = etag_of(&response);
+        assert_ne!(touched_etag, etag);
+        let touched: Value = actix_test::read_body_json(response).await;
+        assert_eq!(
+            touched.get("version").and_then(Value::as_u64),
+            body.get("version").and_then(Value::as_u64).map(|v| v + 1)
+        );
+        assert_eq!(touched.get("cursor"), body.get("cursor"));
+
+        let stale = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri(&uri)
+                .insert_header(("If-None-Match", etag))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(stale.status(), StatusCode::OK);
+        assert_eq!(etag_of(&stale), touched_etag);
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{}/touch", Uuid::new_v4()))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// A playback is started, steered, read and stopped through its routes.
+    #[actix_web::test]
+    async fn test_a_playback_is_driven_through_its_routes() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let playback = format!("/api/v2/simulations/{id}/playback");
+
+        let request = actix_test::TestRequest::post()
+            .uri(&playback)
+            .set_json(json!({ "steps_per_second": 1.0, "speed": 60.0 }))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("field").and_then(Value::as_str), Some("speed"));
+
+        let request = actix_test::TestRequest::post()
+            .uri(&playback)
+            .set_json(json!({ "speed": 86400.0 }))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("state").and_then(Value::as_str), Some("playing"));
+        assert_eq!(body.get("interval_ms").and_then(Value::as_u64), Some(1_000));
+
+        let request = actix_test::TestRequest::post()
+            .uri(&playback)
+            .set_json(json!({}))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("{playback}/pause"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("state").and_then(Value::as_str), Some("paused"));
+
+        let request = actix_test::TestRequest::delete()
+            .uri(&playback)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = actix_test::TestRequest::get().uri(&playback).to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Without a warehouse a materialization is refused up front, and there is
+    /// then no job to read.
+    #[actix_web::test]
+    async fn test_a_materialization_needs_snapshot_persistence() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let materialize = format!("/api/v2/simulations/{id}/materialize");
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("{materialize}?from=0&to=1"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&materialize)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// A purge without a warehouse is refused before anything is deleted, and
+    /// a bulk purge names the field it cannot read.
+    #[actix_web::test]
+    async fn test_a_purge_needs_snapshot_persistence() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let simulation = format!("/api/v2/simulations/{id}");
+
+        let request = actix_test::TestRequest::delete()
+            .uri(&format!("{simulation}?purge=true"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = actix_test::TestRequest::get().uri(&simulation).to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK, "nothing must be deleted");
+
+        let request = actix_test::TestRequest::post()
+            .uri("/api/v2/snapshots/purge")
+            .set_json(json!({ "simulation_ids": ["not-a-uuid"] }))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(
+            body.get("field").and_then(Value::as_str),
+            Some("simulation_ids")
+        );
+    }
+
+    /// A contract's history through its route agrees with the snapshot that
+    /// served the contract, and an unknown simulation is a 404.
+    #[actix_web::test]
+    async fn test_a_contract_history_matches_the_served_snapshot() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/snapshot"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let snapshot: Value = actix_test::read_body_json(response).await;
+        let chain = &snapshot["chains"][0];
+        let contract = &chain["contracts"][0];
+        let (expires_at, strike) = match (chain["expires_at"].as_str(), contract["strike"].as_f64())
+        {
+            (Some(expires_at), Some(strike)) => (expires_at.to_string(), strike),
+            _ => panic!("the snapshot must list a contract: {snapshot}"),
+        };
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/simulations/{id}/contracts/series?expires_at={expires_at}&strike={strike}&side=call&from=0&to=0"
+            ))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let series: Value = actix_test::read_body_json(response).await;
+        assert_eq!(series["points"][0]["step"], json!(0));
+        assert_eq!(series["points"][0]["bid"], contract["call"]["bid"]);
+        assert_eq!(series["points"][0]["delta"], contract["call"]["delta"]);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/simulations/{}/contracts/series?expires_at={expires_at}&strike={strike}&side=put",
+                Uuid::new_v4()
+            ))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// Webhooks are registered, listed and removed through their routes; the
+    /// signing key never comes back, and bad input names its field.
+    #[actix_web::test]
+    async fn test_webhooks_are_managed_through_their_routes() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let hooks = format!("/api/v2/simulations/{id}/webhooks");
+        let registration = json!({
+            "url": "https://example.com/hook",
+            "signing_key": "a-signing-key-of-some-length",
+            "every_n_steps": 5
+        });
+
+        let request = actix_test::TestRequest::post()
+            .uri(&hooks)
+            .set_json(&registration)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("simulation_id"), Some(&json!(id)));
+        assert_eq!(body.get("every_n_steps"), Some(&json!(5)));
+        assert!(
+            body.get("signing_key").is_none(),
+            "the key must not be echoed: {body}"
+        );
+        let webhook_id = id_of(&body);
+
+        let request = actix_test::TestRequest::get().uri(&hooks).to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(
+            body.get("webhooks").and_then(Value::as_array).map(Vec::len),
+            Some(1)
+        );
+
+        let request = actix_test::TestRequest::get()
+            .uri("/api/v2/webhooks")
+            .to_request();
+        let body: Value =
+            actix_test::read_body_json(actix_test::call_service(&app, request).await).await;
+        assert_eq!(
+            body,
+            json!({ "webhooks": [] }),
+            "a bound webhook is not global"
+        );
+
+        let request = actix_test::TestRequest::post()
+            .uri("/api/v2/webhooks")
+            .set_json(json!({ "url": "ftp://example.com", "signing_key": "a-signing-key-of-some-length" }))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("field"), Some(&json!("url")));
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{}/webhooks", Uuid::new_v4()))
+            .set_json(&registration)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let remove = format!("/api/v2/webhooks/{webhook_id}");
+        let request = actix_test::TestRequest::delete().uri(&remove).to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let request = actix_test::TestRequest::delete().uri(&remove).to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// A create retried with its `Idempotency-Key` gets the first simulation
+    /// back rather than a second one; the key reused for another body is 422.
+    #[actix_web::test]
+    async fn test_an_idempotent_create_is_replayed() {
+        let app = v2_service!();
+        let create = |body: Value| {
+            actix_test::TestRequest::post()
+                .uri("/api/v2/simulations")
+                .insert_header(("Idempotency-Key", "create-1"))
+                .set_json(body)
+                .to_request()
+        };
+
+        let first = actix_test::call_service(&app, create(reference_body())).await;
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(first.headers().get("Idempotent-Replayed").is_none());
+        let first: Value = actix_test::read_body_json(first).await;
+
+        let retry = actix_test::call_service(&app, create(reference_body())).await;
+        assert_eq!(retry.status(), StatusCode::CREATED);
+        assert_eq!(
+            retry
+                .headers()
+                .get("Idempotent-Replayed")
+                .and_then(|value| value.to_str().ok()),
+            Some("true")
+        );
+        let retry: Value = actix_test::read_body_json(retry).await;
+        assert_eq!(
+            retry, first,
+            "the retry must be answered with the first response"
+        );
+
+        let request = actix_test::TestRequest::get()
+            .uri("/api/v2/simulations")
+            .to_request();
+        let listed: Value =
+            actix_test::read_body_json(actix_test::call_service(&app, request).await).await;
+        assert_eq!(
+            listed
+                .get("simulations")
+                .and_then(Value::as_array)
+                .map(Vec::len),
+            Some(1),
+            "only one simulation may exist"
+        );
+
+        let mut other = reference_body();
+        other["steps"] = json!(7);
+        let mismatch = actix_test::call_service(&app, create(other)).await;
+        assert_eq!(mismatch.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = actix_test::read_body_json(mismatch).await;
+        assert_eq!(body.get("field"), Some(&json!("Idempotency-Key")));
+    }
+
+    /// An advance retried with its `Idempotency-Key` and no `expected_step`
+    /// moves the cursor once; the key is bound to its path, and a malformed
+    /// key is refused.
+    #[actix_web::test]
+    async fn test_an_idempotent_advance_moves_the_cursor_once() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let step = |key: &str| {
+            actix_test::TestRequest::post()
+                .uri(&format!("/api/v2/simulations/{id}/step"))
+                .insert_header(("Idempotency-Key", key.to_string()))
+                .to_request()
+        };
+
+        let first = actix_test::call_service(&app, step("advance-1")).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        let first_etag = etag_of(&first);
+        let first: Value = actix_test::read_body_json(first).await;
+        let retry = actix_test::call_service(&app, step("advance-1")).await;
+        assert_eq!(retry.status(), StatusCode::OK);
+        assert_eq!(etag_of(&retry), first_etag, "a replay keeps its ETag");
+        let retry: Value = actix_test::read_body_json(retry).await;
+        assert_eq!(retry, first);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}"))
+            .to_request();
+        let simulation: Value =
+            actix_test::read_body_json(actix_test::call_service(&app, request).await).await;
+        assert_eq!(simulation.pointer("/cursor/current_step"), Some(&json!(1)));
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{}/step", Uuid::new_v4()))
+            .insert_header(("Idempotency-Key", "advance-1"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "a key is bound to the path it was first used on"
+        );
+
+        let response = actix_test::call_service(&app, step("has spaces")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("field"), Some(&json!("Idempotency-Key")));
+    }
+
+    /// A replayed `412` still names the current tag, and a retry that
+    /// refreshes its `If-Match` under the same key is a different request.
+    #[actix_web::test]
+    async fn test_an_idempotent_precondition_failure_keeps_its_etag() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let step = |key: &str, tag: &str| {
+            actix_test::TestRequest::post()
+                .uri(&format!("/api/v2/simulations/{id}/step"))
+                .insert_header(("Idempotency-Key", key.to_string()))
+                .insert_header(("If-Match", tag.to_string()))
+                .to_request()
+        };
+
+        let refused = actix_test::call_service(&app, step("advance-2", "\"99-99\"")).await;
+        assert_eq!(refused.status(), StatusCode::PRECONDITION_FAILED);
+        let current = etag_of(&refused);
+
+        let replayed = actix_test::call_service(&app, step("advance-2", "\"99-99\"")).await;
+        assert_eq!(replayed.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(
+            replayed
+                .headers()
+                .get("Idempotent-Replayed")
+                .and_then(|value| value.to_str().ok()),
+            Some("true")
+        );
+        assert_eq!(etag_of(&replayed), current, "a replayed 412 keeps its ETag");
+
+        let refreshed = actix_test::call_service(&app, step("advance-2", &current)).await;
+        assert_eq!(
+            refreshed.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "a new If-Match is a new request, not a replay of the 412"
+        );
+
+        let advanced = actix_test::call_service(&app, step("advance-3", &current)).await;
+        assert_eq!(advanced.status(), StatusCode::OK);
+    }
+
+    /// A seek moves the cursor forward and refuses to move it back.
+    #[actix_web::test]
+    async fn test_a_seek_moves_the_cursor_forward_only() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{id}/seek?to_step=2"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.pointer("/cursor/current_step"), Some(&json!(2)));
+
+        let request = actix_test::TestRequest::post()
+            .uri(&format!("/api/v2/simulations/{id}/seek?to_step=1"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("field").and_then(Value::as_str), Some("to_step"));
+    }
+
+    /// Waiting for the next snapshot with nothing advancing is a `204`, and a
+    /// timeout out of range is refused by name.
+    #[actix_web::test]
+    async fn test_waiting_for_the_next_snapshot_times_out_empty() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/simulations/{id}/snapshot/next?timeout_ms=10"
+            ))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!(
+                "/api/v2/simulations/{id}/snapshot/next?timeout_ms=0"
+            ))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(
+            body.get("field").and_then(Value::as_str),
+            Some("timeout_ms")
+        );
+    }
+
+    /// The stream route upgrades a WebSocket handshake for a live simulation,
+    /// and answers an unknown one or a bad filter before upgrading.
+    #[actix_web::test]
+    async fn test_the_stream_upgrades_a_websocket_handshake() {
+        let app = v2_service!();
+        let id = id_of(&create!(app));
+        let handshake = |uri: String| {
+            actix_test::TestRequest::get()
+                .uri(&uri)
+                .insert_header(("upgrade", "websocket"))
+                .insert_header(("connection", "Upgrade"))
+                .insert_header(("sec-websocket-version", "13"))
+                .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+                .to_request()
+        };
+
+        let response = actix_test::call_service(
+            &app,
+            handshake(format!("/api/v2/simulations/{id}/stream?labels=weeklies")),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+        let response = actix_test::call_service(
+            &app,
+            handshake(format!("/api/v2/simulations/{}/stream", Uuid::new_v4())),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = actix_test::call_service(
+            &app,
+            handshake(format!(
+                "/api/v2/simulations/{id}/stream?min_strike=5100&max_strike=5000"
+            )),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = actix_test::TestRequest::get()
+            .uri(&format!("/api/v2/simulations/{id}/stream"))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// A pinned simulation echoes its pin; a retention on one is refused.
+    #[actix_web::test]
+    async fn test_pinning_is_echoed_and_excludes_a_retention() {
+        let app = v2_service!();
+
+        let mut pinned = reference_body();
+        pinned["pinned"] = json!(true);
+        let request = actix_test::TestRequest::post()
+            .uri("/api/v2/simulations")
+            .set_json(pinned.clone())
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("pinned"), Some(&json!(true)));
+        assert_eq!(body.get("retention_seconds"), Some(&Value::Null));
+
+        pinned["retention_seconds"] = json!(60);
+        let request = actix_test::TestRequest::post()
+            .uri("/api/v2/simulations")
+            .set_json(pinned)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = actix_test::read_body_json(response).await;
+        assert_eq!(body.get("field"), Some(&json!("retention_seconds")));
+    }
+
+    /// A bad listing parameter is a `400` naming it.
+    #[actix_web::test]
+    async fn test_an_invalid_list_parameter_is_reported_by_name() {
+        let app = v2_service!();
+
+        for (query, field) in [
+            ("state=modified", "state"),
+            ("limit=0", "limit"),
+            ("limit=501", "limit"),
+            ("created_after=yesterday", "created_after"),
+            ("tags=a%20b", "tags"),
+        ] {
+            let request = actix_test::TestRequest::get()
+                .uri(&format!("/api/v2/simulations?{query}"))
+                .to_request();
+            let response = actix_test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{query}");
+            let body: Value = actix_test::read_body_json(response).await;
+            assert_eq!(body.get("field"), Some(&json!(field)), "{query}: {body}");
+        }
+    }
+
     /// A malformed id is a validation failure naming the field, not an opaque
     /// bad request.
     #[test]
@@ -0,0 +1,247 @@
+//! `Idempotency-Key` for the two v2 commands a retry can duplicate.
+//!
+//! A `POST /api/v2/simulations` retried after a timeout creates a second
+//! simulation, and a `POST /step` retried without `expected_step` advances
+//! twice. A client that sends `Idempotency-Key` on either gets exactly-once
+//! instead: the first response to the key is recorded, and every retry inside
+//! the window (`OCS_V2_IDEMPOTENCY_WINDOW_SECS`) is answered with that response
+//! — same status, same headers, same body, plus `Idempotent-Replayed: true` —
+//! without running the command again. The headers matter: a replayed step or
+//! `412` still carries the `ETag` the client needs for its next `If-Match`.
+//!
+//! # What binds a key
+//!
+//! The key is bound to a **fingerprint** of the request it was first used
+//! with: method, path, query string, the `If-Match` and `If-None-Match`
+//! preconditions, and body. A retry must be the same request; reusing a key for
+//! a different one is `422`, because replaying the first response to it would
+//! answer a question the client did not ask. The preconditions are part of the
+//! request because a `412` is recorded: a client that refreshes its tag and
+//! retries under the same key has asked something new, and must be told so
+//! rather than handed the stale `412`. The create body is fingerprinted as
+//! parsed, so a retry that reformats the JSON still matches.
+//!
+//! # What is recorded
+//!
+//! Only outcomes a retry should see again: every `2xx`, and every `4xx` except
+//! `409`. A `409` lost a race and committed nothing — the retry deserves to run
+//! — and a `5xx` is not an answer at all; both free the key. A retry that
+//! arrives while the first attempt is still running is `409` too, and should be
+//! retried in turn.
+
+use crate::api::rest::error::map_error;
+use crate::api::rest::responses::ValidationErrorResponse;
+use crate::session::{IdempotencyClaim, IdempotencyStore, StoredResponse};
+use crate::utils::ChainError;
+use actix_web::body::{BoxBody, to_bytes};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{CONTENT_LENGTH, CONTENT_TYPE, HeaderValue, IF_MATCH, IF_NONE_MATCH};
+use actix_web::{HttpRequest, HttpResponse};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use tracing::warn;
+
+/// The request header carrying the client's key.
+pub(crate) const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// The response header marking a replayed response.
+pub(crate) const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// The longest key a client may send.
+const MAX_KEY_LEN: usize = 255;
+
+/// Runs `respond` at most once per `Idempotency-Key`.
+///
+/// Without the header this is `respond` and nothing else. `body` is what the
+/// request's body is fingerprinted as — the parsed body re-serialised, or
+/// nothing for a command without one.
+pub(crate) async fn idempotent(
+    req: &HttpRequest,
+    store: &dyn IdempotencyStore,
+    body: &[u8],
+    respond: impl Future<Output = HttpResponse>,
+) -> HttpResponse {
+    let key = match idempotency_key(req) {
+        Ok(Some(key)) => key,
+        Ok(None) => return respond.await,
+        Err(error) => return map_error(error),
+    };
+    let fingerprint = fingerprint(req, body);
+
+    match store.claim(&key, &fingerprint).await {
+        Ok(IdempotencyClaim::Acquired) => {}
+        Ok(IdempotencyClaim::Replay(stored)) => return replay(stored),
+        Ok(IdempotencyClaim::InFlight) => {
+            return map_error(ChainError::Conflict(
+                "a request with this Idempotency-Key is still in progress; retry it shortly"
+                    .to_string(),
+            ));
+        }
+        Ok(IdempotencyClaim::Mismatch) => {
+            return HttpResponse::UnprocessableEntity().json(ValidationErrorResponse {
+                error: "this Idempotency-Key was first used with a different request".to_string(),
+                field: IDEMPOTENCY_KEY_HEADER.to_string(),
+            });
+        }
+        Err(error) => return map_error(error),
+    }
+
+    let response = respond.await;
+    if !is_recorded(response.status()) {
+        release(store, &key).await;
+        return response;
+    }
+
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/json")
+        .to_string();
+    let headers = recorded_headers(&response);
+    let (head, body) = response.into_parts();
+    let bytes = match to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            warn!(%error, "could not read a response to record it");
+            release(store, &key).await;
+            return map_error(ChainError::Internal(
+                "the response could not be read".to_string(),
+            ));
+        }
+    };
+
+    match std::str::from_utf8(&bytes) {
+        Ok(text) => {
+            let stored = StoredResponse {
+                status: status.as_u16(),
+                content_type,
+                headers,
+                body: text.to_string(),
+            };
+            // The command has run; failing the response now would invite the
+            // retry this exists to prevent. The key stays claimed until its
+            // lease lapses, so a prompt retry is refused rather than re-run.
+            if let Err(error) = store.complete(&key, &fingerprint, stored).await {
+                warn!(%error, "could not record the response to an Idempotency-Key");
+            }
+        }
+        Err(_) => release(store, &key).await,
+    }
+
+    head.set_body(bytes).map_into_boxed_body()
+}
+
+/// The request's key, if it sent one.
+fn idempotency_key(req: &HttpRequest) -> Result<Option<String>, ChainError> {
+    let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let invalid = |reason: &str| ChainError::Validation {
+        field: IDEMPOTENCY_KEY_HEADER.to_string(),
+        reason: reason.to_string(),
+    };
+    let key = value
+        .to_str()
+        .map_err(|_| invalid("must be printable ASCII"))?;
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(invalid("must be 1 to 255 characters"));
+    }
+    if !key.bytes().all(|byte| byte.is_ascii_graphic()) {
+        return Err(invalid("must be printable ASCII without spaces"));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// The hex SHA-256 of the method, path, query string, preconditions and
+/// body.
+fn fingerprint(req: &HttpRequest, body: &[u8]) -> String {
+    let precondition = |name| {
+        req.headers()
+            .get(name)
+            .map_or(&[][..], HeaderValue::as_bytes)
+    };
+    let mut hasher = Sha256::new();
+    for part in [
+        req.method().as_str().as_bytes(),
+        req.path().as_bytes(),
+        req.query_string().as_bytes(),
+        precondition(IF_MATCH),
+        precondition(IF_NONE_MATCH),
+    ] {
+        hasher.update(part);
+        hasher.update(b"\n");
+    }
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// Whether a response is an outcome a retry should see again.
+fn is_recorded(status: StatusCode) -> bool {
+    status.is_success() || (status.is_client_error() && status != StatusCode::CONFLICT)
+}
+
+/// The headers of `response` worth replaying.
+///
+/// Everything the handler set except the two the replay writes itself:
+/// `Content-Type` is stored on its own, and `Content-Length` follows the body.
+fn recorded_headers(response: &HttpResponse) -> Vec<(String, String)> {
+    response
+        .headers()
+        .iter()
+        .filter(|(name, _)| **name != CONTENT_TYPE && **name != CONTENT_LENGTH)
+        .filter_map(|(name, value)| {
+            let value = value.to_str().ok()?;
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// The recorded response, marked as a replay.
+fn replay(stored: StoredResponse) -> HttpResponse<BoxBody> {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut response = HttpResponse::build(status);
+    response.insert_header((CONTENT_TYPE, stored.content_type));
+    for header in stored.headers {
+        response.append_header(header);
+    }
+    response
+        .insert_header((IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true")))
+        .body(stored.body)
+}
+
+/// Frees a key, logging rather than failing: the response is already decided.
+async fn release(store: &dyn IdempotencyStore, key: &str) {
+    if let Err(error) = store.release(key).await {
+        warn!(%error, "could not release an Idempotency-Key");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Success and a settled refusal are replayed; a lost race and a server
+    /// error run again.
+    #[test]
+    fn test_only_settled_outcomes_are_recorded() {
+        for status in [
+            StatusCode::OK,
+            StatusCode::CREATED,
+            StatusCode::BAD_REQUEST,
+            StatusCode::NOT_FOUND,
+            StatusCode::GONE,
+            StatusCode::PRECONDITION_FAILED,
+        ] {
+            assert!(is_recorded(status), "{status} must be recorded");
+        }
+        for status in [
+            StatusCode::CONFLICT,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            assert!(!is_recorded(status), "{status} must not be recorded");
+        }
+    }
+}
@@ -1,9 +1,18 @@
+pub(crate) mod bundle;
+pub(crate) mod coding;
+mod columnar;
+pub(crate) mod continuation;
 pub(crate) mod controller;
+pub(crate) mod digest;
 mod error;
+mod etag;
+pub(crate) mod events;
 pub(crate) mod export;
 mod favicon;
+pub(crate) mod filter;
 pub(crate) mod handlers;
 pub(crate) mod handlers_v2;
+mod idempotency;
 pub(crate) mod limits;
 mod middleware;
 pub(crate) mod models;
@@ -12,7 +21,9 @@ pub(crate) mod requests;
 pub(crate) mod requests_v2;
 pub(crate) mod responses;
 pub(crate) mod responses_v2;
-mod routes;
+pub(crate) mod routes;
+pub(crate) mod series;
+pub(crate) mod stream;
 pub mod swagger;
 pub(crate) mod validation;
 
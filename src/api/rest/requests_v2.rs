@@ -10,11 +10,13 @@
 //! (`crate::session::model_v2`).
 
 use crate::api::rest::models::{ApiTimeFrame, ApiWalkType};
-use crate::session::ExpiryRule;
+use crate::session::{ExpiryRule, PlaybackPace, WebhookSpec};
+use crate::utils::ChainError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
 /// Creates a deterministic rolling multi-expiration simulation.
 ///
@@ -100,6 +102,20 @@ pub struct CreateSimulationRequest {
     /// seed, exactly as in v1.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub seed: Option<u64>,
+    /// Optional tags to find the simulation by later, each up to 64
+    /// characters of `[A-Za-z0-9_.:-]`, at most 16. Stored sorted and
+    /// deduplicated. Not a replay input: tags never change the tape.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Idle retention for this simulation, in seconds, overriding the
+    /// service's default. Bounded by `OCS_V2_MAX_RETENTION_SECS`; not allowed
+    /// together with `pinned`. Not a replay input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_seconds: Option<u64>,
+    /// Exempts the simulation from expiry, within the service's pinned quota
+    /// (`OCS_V2_MAX_PINNED`); a create beyond it is `409`. Not a replay input.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
 }
 
 impl fmt::Display for CreateSimulationRequest {
@@ -109,6 +125,128 @@ impl fmt::Display for CreateSimulationRequest {
     }
 }
 
+/// Starts server-driven playback of a simulation.
+///
+/// Names the pace one of two ways, at most one of them: a fixed
+/// `steps_per_second`, or a `speed` relative to the simulation's own step
+/// interval. Naming neither plays one step per second.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StartPlaybackRequest {
+    /// Steps to advance per wall-clock second, e.g. `1.0` or `0.2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steps_per_second: Option<f64>,
+    /// Multiple of real time: `60.0` plays a simulation with one-minute steps
+    /// at one step per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+}
+
+impl StartPlaybackRequest {
+    /// The pace this request names.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] on `speed` when both paces are named.
+    pub(crate) fn pace(&self) -> Result<PlaybackPace, ChainError> {
+        match (self.steps_per_second, self.speed) {
+            (Some(_), Some(_)) => Err(ChainError::Validation {
+                field: "speed".to_string(),
+                reason: "name either steps_per_second or speed, not both".to_string(),
+            }),
+            (Some(rate), None) => Ok(PlaybackPace::StepsPerSecond(rate)),
+            (None, Some(speed)) => Ok(PlaybackPace::Speed(speed)),
+            (None, None) => Ok(PlaybackPace::StepsPerSecond(1.0)),
+        }
+    }
+}
+
+/// Registers a webhook, on one simulation or globally.
+///
+/// `Debug` is written by hand so the signing key cannot reach a log line.
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RegisterWebhookRequest {
+    /// The `http` or `https` URL every delivery is `POST`ed to. A loopback,
+    /// private or link-local address is refused unless the operator allows
+    /// private receivers.
+    pub url: String,
+    /// The HMAC-SHA256 key deliveries are signed with, 16 to 256 characters.
+    /// Accepted once and never returned.
+    pub signing_key: String,
+    /// Also deliver a `step` event every this many advances. Omitted, only
+    /// `completed`, `expired` and `deleted` are delivered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every_n_steps: Option<usize>,
+}
+
+impl fmt::Debug for RegisterWebhookRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisterWebhookRequest")
+            .field("url", &self.url)
+            .field("signing_key", &"<redacted>")
+            .field("every_n_steps", &self.every_n_steps)
+            .finish()
+    }
+}
+
+impl From<RegisterWebhookRequest> for WebhookSpec {
+    fn from(request: RegisterWebhookRequest) -> Self {
+        WebhookSpec {
+            url: request.url,
+            signing_key: request.signing_key,
+            every_n_steps: request.every_n_steps,
+        }
+    }
+}
+
+/// The most simulations one bulk purge may name.
+///
+/// A purge is two lightweight deletes whatever its size, but each id is also a
+/// store lookup, and an unbounded list is an unbounded request.
+pub(crate) const MAX_PURGE_SIMULATIONS: usize = 1_000;
+
+/// Purges the warehouse rows of deleted simulations.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PurgeSnapshotsRequest {
+    /// The simulations to purge, 1 to 1000 of them. Each must already be
+    /// deleted.
+    pub simulation_ids: Vec<String>,
+}
+
+impl PurgeSnapshotsRequest {
+    /// The ids this request names, without duplicates and in request order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] on `simulation_ids` when the list is
+    /// empty, too long, or holds something that is not a UUID.
+    pub(crate) fn ids(&self) -> Result<Vec<Uuid>, ChainError> {
+        if self.simulation_ids.is_empty() || self.simulation_ids.len() > MAX_PURGE_SIMULATIONS {
+            return Err(ChainError::Validation {
+                field: "simulation_ids".to_string(),
+                reason: format!(
+                    "must name 1 to {MAX_PURGE_SIMULATIONS} simulations, got {}",
+                    self.simulation_ids.len()
+                ),
+            });
+        }
+
+        let mut ids: Vec<Uuid> = Vec::with_capacity(self.simulation_ids.len());
+        for raw in &self.simulation_ids {
+            let id = Uuid::parse_str(raw).map_err(|_| ChainError::Validation {
+                field: "simulation_ids".to_string(),
+                reason: format!("must hold UUIDs, got {raw:?}"),
+            })?;
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,4 +423,29 @@ mod tests {
             Err(error) => panic!("must round-trip: {error}"),
         }
     }
+
+    /// A purge list is deduplicated in order, and an empty, oversized or
+    /// malformed one is refused naming `simulation_ids`.
+    #[test]
+    fn test_a_purge_request_names_its_ids() {
+        let (first, second) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let request = PurgeSnapshotsRequest {
+            simulation_ids: vec![first.to_string(), second.to_string(), first.to_string()],
+        };
+        match request.ids() {
+            Ok(ids) => assert_eq!(ids, vec![first, second]),
+            Err(error) => panic!("the ids must parse: {error}"),
+        }
+
+        for simulation_ids in [
+            Vec::new(),
+            vec![first.to_string(); MAX_PURGE_SIMULATIONS + 1],
+            vec!["not-a-uuid".to_string()],
+        ] {
+            match (PurgeSnapshotsRequest { simulation_ids }).ids() {
+                Err(ChainError::Validation { field, .. }) => assert_eq!(field, "simulation_ids"),
+                other => panic!("the list must be refused, got {other:?}"),
+            }
+        }
+    }
 }
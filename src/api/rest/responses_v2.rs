@@ -17,14 +17,20 @@
 //! is deterministic. Surfacing the stamp would put a value in the contract that
 //! changes between two otherwise-identical replays.
 
+use crate::api::rest::digest::snapshot_digest;
 use crate::domain::series::SeriesSnapshot;
-use crate::session::{ExpiryRule, ExpiryRuleKind, SessionV2};
+use crate::infrastructure::ContractQuote;
+use crate::session::{
+    ExpiryRule, ExpiryRuleKind, MaterializationState, MaterializationStatus, PlaybackState,
+    PlaybackStatus, SessionV2, Webhook,
+};
 use chrono::{DateTime, SecondsFormat, Utc};
 use optionstratlib::chains::OptionData;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use uuid::Uuid;
 
 /// Renders an instant the way every v2 timestamp is rendered.
 ///
@@ -101,6 +107,10 @@ pub struct SimulationParametersResponse {
     /// against. A replay against a different release is still a replay — it is
     /// just one the client can now detect.
     pub tzdb_version: String,
+    /// The engine the simulation was created under: the tape generation and
+    /// the locked versions of the pricing crates. Empty for a simulation
+    /// created before it was recorded.
+    pub engine_version: String,
     /// The local time of day every expiration expires at.
     pub expiration_time: String,
     /// The normalised expiration rules, ordered by `rule_id`.
@@ -158,6 +168,25 @@ pub struct SimulationResponse {
     pub updated_at: String,
     /// The effective parameters — the replay inputs.
     pub parameters: SimulationParametersResponse,
+    /// The tags set at creation, sorted and deduplicated. Not a replay input.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The idle retention chosen at creation, in seconds, or `null` when the
+    /// service's default applies.
+    #[serde(default)]
+    pub retention_seconds: Option<u64>,
+    /// Whether the simulation is exempt from expiry.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// One page of `GET /api/v2/simulations`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SimulationListResponse {
+    /// The matching simulations, oldest first.
+    pub simulations: Vec<SimulationResponse>,
+    /// The `offset` to request the next page with, or `null` on the last page.
+    pub next_offset: Option<usize>,
 }
 
 /// The state of the underlying at one step.
@@ -234,6 +263,82 @@ pub struct SnapshotResponse {
     pub underlying: UnderlyingResponse,
     /// The live chains, ordered by expiration.
     pub chains: Vec<ExpiryChainResponse>,
+    /// Lowercase hex SHA-256 of the step's canonical rendering — the step
+    /// digest of [`crate::api::rest::digest`]. It covers the whole step, so a
+    /// filtered snapshot still carries the digest of the unfiltered one.
+    pub digest: String,
+}
+
+/// The chained digest of a step range of a simulation's tape.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TapeDigestResponse {
+    /// The simulation's unique identifier.
+    pub id: String,
+    /// The first step the digest covers, inclusive.
+    pub from_step: usize,
+    /// The last step the digest covers, inclusive.
+    pub to_step: usize,
+    /// Lowercase hex of the chained digest through `to_step`.
+    pub digest: String,
+}
+
+/// One contract at one step of its history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContractPointResponse {
+    /// The 0-based step.
+    pub step: usize,
+    /// The simulated instant of the step.
+    pub simulated_at: String,
+    /// Fractional days remaining at this step.
+    pub days_to_expiration: f64,
+    /// The per-strike implied volatility, shared by both sides.
+    pub implied_volatility: f64,
+    /// The bid on the requested side.
+    pub bid: Option<f64>,
+    /// The ask on the requested side.
+    pub ask: Option<f64>,
+    /// The mid on the requested side.
+    pub mid: Option<f64>,
+    /// The delta on the requested side.
+    pub delta: Option<f64>,
+    /// Gamma, shared by both sides.
+    pub gamma: Option<f64>,
+}
+
+/// One contract's quotes across a step range of a simulation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContractSeriesResponse {
+    /// The simulation's unique identifier.
+    pub id: String,
+    /// The contract's absolute expiration, in UTC.
+    pub expires_at: String,
+    /// The contract's strike.
+    pub strike: f64,
+    /// `call` or `put`.
+    pub side: String,
+    /// The first step the history covers, inclusive.
+    pub from_step: usize,
+    /// The last step the history covers, inclusive.
+    pub to_step: usize,
+    /// The steps that list the contract, ascending. A step where it was not
+    /// listed — expired, or outside that step's strike grid — is absent.
+    pub points: Vec<ContractPointResponse>,
+}
+
+impl From<&ContractQuote> for ContractPointResponse {
+    fn from(quote: &ContractQuote) -> Self {
+        Self {
+            step: quote.step,
+            simulated_at: render_instant(quote.simulated_at),
+            days_to_expiration: quote.days_to_expiration.to_f64(),
+            implied_volatility: quote.implied_volatility.to_f64(),
+            bid: quote.bid.map(|value| value.to_f64()),
+            ask: quote.ask.map(|value| value.to_f64()),
+            mid: quote.mid.map(|value| value.to_f64()),
+            delta: decimal_to_f64(quote.delta),
+            gamma: decimal_to_f64(quote.gamma),
+        }
+    }
 }
 
 impl From<&OptionData> for ContractResponse {
@@ -302,6 +407,7 @@ impl From<&SessionV2> for SimulationParametersResponse {
             timezone: schedule.timezone().name().to_string(),
             calendar: schedule.calendar().as_str().to_string(),
             tzdb_version: parameters.tzdb_version.clone(),
+            engine_version: parameters.engine_version.clone(),
             expiration_time: schedule.expiration_time().format("%H:%M:%S").to_string(),
             schedules: schedule.rules().iter().map(Into::into).collect(),
             initial_price: parameters.initial_price.to_f64(),
@@ -334,6 +440,9 @@ impl From<&SessionV2> for SimulationResponse {
             created_at: render_system_time(simulation.created_at),
             updated_at: render_system_time(simulation.updated_at),
             parameters: simulation.into(),
+            tags: simulation.tags.clone(),
+            retention_seconds: simulation.retention_secs,
+            pinned: simulation.pinned,
         }
     }
 }
@@ -366,6 +475,222 @@ fn render_system_time(time: std::time::SystemTime) -> String {
     render_instant(DateTime::<Utc>::from(time))
 }
 
+/// The state of a simulation's server-driven playback.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PlaybackResponse {
+    /// The simulation being played.
+    pub simulation_id: String,
+    /// `playing`, `paused` or `finished`.
+    pub state: String,
+    /// Wall-clock milliseconds between two advances.
+    pub interval_ms: u64,
+    /// When the playback was started, in real time.
+    pub started_at: String,
+    /// Steps this playback has advanced; manual advances are not counted.
+    pub steps_played: u64,
+    /// Why the playback ended; absent while it runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_reason: Option<String>,
+}
+
+/// Builds a playback response.
+///
+/// A free function for the same reason as [`snapshot_response`]: the status
+/// does not carry the id it belongs to.
+#[must_use]
+pub(crate) fn playback_response(simulation_id: Uuid, status: &PlaybackStatus) -> PlaybackResponse {
+    PlaybackResponse {
+        simulation_id: simulation_id.to_string(),
+        state: match status.state {
+            PlaybackState::Playing => "playing",
+            PlaybackState::Paused => "paused",
+            PlaybackState::Finished => "finished",
+        }
+        .to_string(),
+        interval_ms: u64::try_from(status.interval.as_millis()).unwrap_or(u64::MAX),
+        started_at: render_system_time(status.started_at),
+        steps_played: status.steps_played,
+        finished_reason: status.finished_reason.clone(),
+    }
+}
+
+/// The state and progress of a simulation's materialization.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MaterializationResponse {
+    /// The simulation being materialized.
+    pub simulation_id: String,
+    /// `running`, `completed`, `cancelled` or `failed`.
+    pub state: String,
+    /// The first step of the range, inclusive.
+    pub from_step: usize,
+    /// The last step of the range, inclusive.
+    pub to_step: usize,
+    /// How many steps the range covers.
+    pub steps_total: usize,
+    /// Steps this job priced and filed.
+    pub steps_filed: usize,
+    /// Steps the warehouse already held.
+    pub steps_skipped: usize,
+    /// The share of the range that is done, filed or skipped, from 0 to 1.
+    pub progress: f64,
+    /// When the job was started, in real time.
+    pub started_at: String,
+    /// When the job ended; absent while it runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    /// Why the job ended; absent while it runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_reason: Option<String>,
+}
+
+/// Builds a materialization response, as [`playback_response`] does.
+#[must_use]
+pub(crate) fn materialization_response(
+    simulation_id: Uuid,
+    status: &MaterializationStatus,
+) -> MaterializationResponse {
+    MaterializationResponse {
+        simulation_id: simulation_id.to_string(),
+        state: match status.state {
+            MaterializationState::Running => "running",
+            MaterializationState::Completed => "completed",
+            MaterializationState::Cancelled => "cancelled",
+            MaterializationState::Failed => "failed",
+        }
+        .to_string(),
+        from_step: status.from_step,
+        to_step: status.to_step,
+        steps_total: status.steps_total(),
+        steps_filed: status.steps_filed,
+        steps_skipped: status.steps_skipped,
+        progress: status.progress(),
+        started_at: render_system_time(status.started_at),
+        finished_at: status.finished_at.map(render_system_time),
+        finished_reason: status.finished_reason.clone(),
+    }
+}
+
+/// The simulations whose warehouse rows a bulk purge removed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PurgeSnapshotsResponse {
+    /// Every id the request named, once each.
+    pub simulation_ids: Vec<String>,
+}
+
+/// A registered webhook. The signing key is never part of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookResponse {
+    /// The registration's identifier, to remove it by.
+    pub id: String,
+    /// Where deliveries go.
+    pub url: String,
+    /// The simulation it is bound to; absent for a global registration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulation_id: Option<String>,
+    /// The `step` event cadence; absent when none was asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every_n_steps: Option<usize>,
+    /// When it was registered, in real time.
+    pub created_at: String,
+}
+
+impl From<&Webhook> for WebhookResponse {
+    fn from(webhook: &Webhook) -> Self {
+        WebhookResponse {
+            id: webhook.id.to_string(),
+            url: webhook.url.clone(),
+            simulation_id: webhook.simulation.map(|id| id.to_string()),
+            every_n_steps: webhook.every_n_steps.map(std::num::NonZeroUsize::get),
+            created_at: render_system_time(webhook.created_at),
+        }
+    }
+}
+
+/// The webhooks registered on a simulation, or the global ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookListResponse {
+    /// The registrations, oldest first.
+    pub webhooks: Vec<WebhookResponse>,
+}
+
+/// One expiration of an advance event, summarised.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ChainSummaryResponse {
+    /// The absolute expiration instant, in UTC.
+    pub expires_at: String,
+    /// Fractional days remaining.
+    pub days_to_expiration: f64,
+    /// Every rule this expiration satisfies, sorted.
+    pub labels: Vec<String>,
+    /// How many strikes the chain prices.
+    pub strikes: usize,
+    /// The strike nearest the spot; the lower one on a tie.
+    pub atm_strike: Option<f64>,
+    /// The implied volatility at [`Self::atm_strike`].
+    pub atm_implied_volatility: Option<f64>,
+}
+
+/// One committed advance, as the event feed carries it.
+///
+/// Built from the snapshot alone — nothing about the simulation's state *after*
+/// the advance — so an event replayed from the tape is identical to the one
+/// that went out live.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AdvanceEventResponse {
+    /// The simulation that advanced.
+    pub simulation_id: String,
+    /// The step the advance served.
+    pub step: usize,
+    /// The simulated instant of that step.
+    pub simulated_at: String,
+    /// The underlying price at that step.
+    pub spot: f64,
+    /// The base implied volatility at that step.
+    pub base_volatility: f64,
+    /// Per-expiration summaries, when the subscriber asked for them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chains: Option<Vec<ChainSummaryResponse>>,
+}
+
+/// Builds an advance event from the snapshot the advance served.
+#[must_use]
+pub(crate) fn advance_event(
+    simulation_id: Uuid,
+    snapshot: &SeriesSnapshot,
+    summary: bool,
+) -> AdvanceEventResponse {
+    let spot = snapshot.spot.to_f64();
+    AdvanceEventResponse {
+        simulation_id: simulation_id.to_string(),
+        step: snapshot.step,
+        simulated_at: render_instant(snapshot.simulated_at),
+        spot,
+        base_volatility: snapshot.base_volatility.to_f64(),
+        chains: summary.then(|| {
+            snapshot
+                .chains
+                .iter()
+                .map(|chain| {
+                    let contracts: Vec<ContractResponse> =
+                        chain.chain.iter().map(Into::into).collect();
+                    // Ascending strikes, so `min_by` keeps the lower of a tie.
+                    let atm = contracts
+                        .iter()
+                        .min_by(|a, b| (a.strike - spot).abs().total_cmp(&(b.strike - spot).abs()));
+                    ChainSummaryResponse {
+                        expires_at: render_instant(chain.expires_at),
+                        days_to_expiration: chain.days_to_expiration.to_f64(),
+                        labels: chain.labels.clone(),
+                        strikes: contracts.len(),
+                        atm_strike: atm.map(|contract| contract.strike),
+                        atm_implied_volatility: atm.map(|contract| contract.implied_volatility),
+                    }
+                })
+                .collect()
+        }),
+    }
+}
+
 /// Builds a snapshot response from a simulation and the snapshot it served.
 ///
 /// A free function rather than a `From` impl because it needs both, and the
@@ -400,5 +725,6 @@ pub(crate) fn snapshot_response(
                 contracts: chain.chain.iter().map(Into::into).collect(),
             })
             .collect(),
+        digest: snapshot_digest(&simulation.parameters, snapshot),
     }
 }
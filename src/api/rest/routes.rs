@@ -1,15 +1,24 @@
+use crate::api::rest::bundle::export_bundle;
+use crate::api::rest::digest::tape_digest;
+use crate::api::rest::events::simulation_events;
 use crate::api::rest::export::export_simulation;
 use crate::api::rest::get_favicon;
 use crate::api::rest::handlers::{
     advance_step, create_session, delete_session, get_current_step, replace_session, update_session,
 };
 use crate::api::rest::handlers_v2::{
-    advance_simulation, create_simulation, delete_simulation, get_simulation, json_error_handler,
-    peek_snapshot,
+    advance_simulation, cancel_materialization, create_simulation, delete_simulation,
+    delete_webhook, get_manifest, get_materialization, get_playback, get_simulation,
+    import_simulation, json_error_handler, list_simulation_webhooks, list_simulations,
+    list_webhooks, next_snapshot, pause_playback, peek_snapshot, purge_snapshots,
+    register_simulation_webhook, register_webhook, resume_playback, seek_simulation,
+    start_materialization, start_playback, stop_playback, touch_simulation,
 };
 use crate::api::rest::middleware::metrics_endpoint;
+use crate::api::rest::series::contract_series;
+use crate::api::rest::stream::stream_simulation;
 use crate::api::rest::swagger::ApiDoc;
-use crate::infrastructure::{MetricsCollector, MongoDBRepository, SimulationSnapshotRepository};
+use crate::infrastructure::{EventSink, MetricsCollector, SimulationSnapshotRepository};
 use crate::session::{SessionManager, SimulationManager};
 use actix_web::web;
 use std::sync::Arc;
@@ -29,6 +38,8 @@ use utoipa_swagger_ui::SwaggerUi;
 /// * `session_manager` - An `Arc` instance of `SessionManager`. This is wrapped in `web::Data`
 ///   to make it accessible to the route handlers. The `SessionManager` is responsible for managing
 ///   session data and operations.
+/// * `event_sink` - Where the v1 handlers record session events and chain steps: MongoDB, an
+///   NDJSON file, or nowhere, as configured.
 /// * `snapshots` - The v2 snapshot warehouse, when the operator enabled persistence. `None` is the
 ///   normal case and leaves the v2 export replaying every step.
 ///
@@ -65,7 +76,7 @@ pub fn configure_routes(
     session_manager: Arc<SessionManager>,
     simulation_manager: Arc<SimulationManager>,
     metrics_collector: Arc<MetricsCollector>,
-    mongodb_repo: Arc<MongoDBRepository>,
+    event_sink: Arc<dyn EventSink>,
 ) {
     // The export reads from the same warehouse the manager files into, taken
     // off the manager rather than threaded separately: two handles could be
@@ -75,7 +86,7 @@ pub fn configure_routes(
 
     cfg.app_data(web::Data::new(session_manager))
         .app_data(web::Data::new(metrics_collector.clone()))
-        .app_data(web::Data::new(mongodb_repo))
+        .app_data(web::Data::new(event_sink))
         .service(
             web::resource("/api/v1/chain")
                 .route(web::post().to(create_session))
@@ -92,31 +103,63 @@ pub fn configure_routes(
         );
 }
 
-/// Registers everything under `/api/v2/simulations`.
+/// Registers everything under `/api/v2/simulations` and `/api/v2/webhooks`.
 ///
 /// Split out of [`configure_routes`] so the v2 surface can be mounted on its
 /// own — which is what its tests do, exercising the real paths and the real
-/// JSON error handler without dragging in v1's MongoDB and metrics
+/// JSON error handler without dragging in v1's event sink and metrics
 /// dependencies. A route string tested here is the same string served in
 /// production, rather than a copy that can drift.
 ///
 /// # Endpoints
 ///
 /// - **POST** `/api/v2/simulations` — create a rolling simulation.
+/// - **GET** `/api/v2/simulations` — list simulations, filtered and paged.
 /// - **GET** `/api/v2/simulations/{id}` — read its metadata and effective
 ///   parameters.
+/// - **GET** `/api/v2/simulations/{id}/manifest` — export its replay manifest;
+///   **POST** `/api/v2/simulations/import` recreates a simulation from one.
 /// - **GET** `/api/v2/simulations/{id}/snapshot` — a safe, repeatable peek at
 ///   the current cursor.
 /// - **POST** `/api/v2/simulations/{id}/step` — serve the current snapshot and
 ///   advance once, with an optional `expected_step` precondition.
-/// - **DELETE** `/api/v2/simulations/{id}` — delete it and evict its caches.
+/// - **DELETE** `/api/v2/simulations/{id}` — delete it and evict its caches;
+///   `?purge=true` also removes its warehouse rows.
+/// - **POST** `/api/v2/simulations/{id}/touch` — restart its idle-retention
+///   window.
+/// - **GET** `/api/v2/simulations/{id}/snapshot/next` — wait for the next
+///   advance and return what it served.
+/// - **POST** `/api/v2/simulations/{id}/seek` — jump the cursor forward.
+/// - **POST**, **GET**, **DELETE** `/api/v2/simulations/{id}/playback` —
+///   start, read and stop server-driven playback; `/playback/pause` and
+///   `/playback/resume` steer it.
+/// - **POST**, **GET**, **DELETE** `/api/v2/simulations/{id}/materialize` —
+///   start, read and cancel filing a step range in the warehouse.
+/// - **GET** `/api/v2/simulations/{id}/stream` — upgrade to a WebSocket that
+///   pushes every served snapshot and accepts advances.
+/// - **GET** `/api/v2/simulations/{id}/events` — a Server-Sent Events feed
+///   of its advances, resumable with `Last-Event-ID`.
 /// - **GET** `/api/v2/simulations/{id}/export` — stream the complete tape, or a
-///   step range of it, as JSON or CSV.
+///   step range of it, as JSON, NDJSON, CSV, Parquet or Arrow IPC; an NDJSON
+///   export can be resumed with `after=`.
+/// - **GET** `/api/v2/simulations/{id}/export/bundle` — stream every dataset of
+///   a range, and the replay manifest, as one ZIP.
+/// - **GET** `/api/v2/simulations/{id}/digest` — the chained content digest of
+///   a step range, without the rows.
+/// - **GET** `/api/v2/simulations/{id}/contracts/series` — one contract's
+///   quotes across a step range.
+/// - **POST**, **GET** `/api/v2/simulations/{id}/webhooks` — register and list
+///   webhooks on one simulation.
+/// - **POST**, **GET** `/api/v2/webhooks` — register and list global webhooks;
+///   **DELETE** `/api/v2/webhooks/{id}` removes either kind.
+/// - **POST** `/api/v2/snapshots/purge` — remove the warehouse rows of deleted
+///   simulations, in bulk.
 ///
-/// `snapshots` is the warehouse the export reads persisted steps from. It is an
-/// `Option` because persistence is opt-in: registered, the export prefers a
-/// stored snapshot over replaying it; absent, nothing about the export changes.
-/// The handler extracts it as `Option<web::Data<_>>`, so a deployment without
+/// `snapshots` is the warehouse the export, the digest and the contract history
+/// read persisted steps from. It is an `Option` because persistence is opt-in:
+/// registered, each prefers a stored snapshot over replaying it; absent,
+/// nothing changes. The
+/// handlers extract it as `Option<web::Data<_>>`, so a deployment without
 /// ClickHouse registers nothing rather than a null.
 pub(crate) fn configure_v2_routes(
     cfg: &mut web::ServiceConfig,
@@ -133,12 +176,24 @@ pub(crate) fn configure_v2_routes(
         // without this handler actix renders them as plaintext and the
         // structured field is lost.
         .app_data(web::JsonConfig::default().error_handler(json_error_handler))
-        .service(web::resource("/api/v2/simulations").route(web::post().to(create_simulation)))
+        .service(
+            web::resource("/api/v2/simulations")
+                .route(web::post().to(create_simulation))
+                .route(web::get().to(list_simulations)),
+        )
+        // Before `{id}`, which would otherwise claim `import` as an id and
+        // answer the POST with 405.
+        .service(
+            web::resource("/api/v2/simulations/import").route(web::post().to(import_simulation)),
+        )
         .service(
             web::resource("/api/v2/simulations/{id}")
                 .route(web::get().to(get_simulation))
                 .route(web::delete().to(delete_simulation)),
         )
+        .service(
+            web::resource("/api/v2/simulations/{id}/manifest").route(web::get().to(get_manifest)),
+        )
         .service(
             web::resource("/api/v2/simulations/{id}/snapshot").route(web::get().to(peek_snapshot)),
         )
@@ -146,8 +201,67 @@ pub(crate) fn configure_v2_routes(
             web::resource("/api/v2/simulations/{id}/step")
                 .route(web::post().to(advance_simulation)),
         )
+        .service(
+            web::resource("/api/v2/simulations/{id}/touch").route(web::post().to(touch_simulation)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/snapshot/next")
+                .route(web::get().to(next_snapshot)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/seek").route(web::post().to(seek_simulation)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/playback")
+                .route(web::post().to(start_playback))
+                .route(web::get().to(get_playback))
+                .route(web::delete().to(stop_playback)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/playback/pause")
+                .route(web::post().to(pause_playback)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/playback/resume")
+                .route(web::post().to(resume_playback)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/materialize")
+                .route(web::post().to(start_materialization))
+                .route(web::get().to(get_materialization))
+                .route(web::delete().to(cancel_materialization)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/stream")
+                .route(web::get().to(stream_simulation)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/events")
+                .route(web::get().to(simulation_events)),
+        )
         .service(
             web::resource("/api/v2/simulations/{id}/export")
                 .route(web::get().to(export_simulation)),
-        );
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/export/bundle")
+                .route(web::get().to(export_bundle)),
+        )
+        .service(web::resource("/api/v2/simulations/{id}/digest").route(web::get().to(tape_digest)))
+        .service(
+            web::resource("/api/v2/simulations/{id}/contracts/series")
+                .route(web::get().to(contract_series)),
+        )
+        .service(
+            web::resource("/api/v2/simulations/{id}/webhooks")
+                .route(web::post().to(register_simulation_webhook))
+                .route(web::get().to(list_simulation_webhooks)),
+        )
+        .service(
+            web::resource("/api/v2/webhooks")
+                .route(web::post().to(register_webhook))
+                .route(web::get().to(list_webhooks)),
+        )
+        .service(web::resource("/api/v2/webhooks/{id}").route(web::delete().to(delete_webhook)))
+        .service(web::resource("/api/v2/snapshots/purge").route(web::post().to(purge_snapshots)));
 }
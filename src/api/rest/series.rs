@@ -0,0 +1,476 @@
+//! One contract's history across a v2 simulation's tape.
+//!
+//! A snapshot answers "what did the market look like at step k"; a chart or a
+//! backtest of one position asks the transpose — "what did this contract do
+//! from step a to step b". The warehouse already answers that with a single
+//! query; this module exposes it and fills what the warehouse does not hold.
+//!
+//! # Where the points come from
+//!
+//! The same preference as an export: every step the warehouse holds a complete
+//! snapshot of — priced by this engine, or untagged — is read back, and every
+//! other step is replayed from the effective parameters. The two sources are
+//! one simulated market, so both are projected through
+//! [`SnapshotRecord::contract_quote`](crate::infrastructure::SnapshotRecord::contract_quote)
+//! and the response does not say which a point came from.
+//!
+//! A step that does not list the contract has no point. Replay stops at the
+//! first step on or past the expiration, since no later step can list it; a
+//! strike outside a step's grid is only discovered by pricing that step.
+
+use crate::api::rest::error::map_error;
+use crate::api::rest::export::StepRange;
+use crate::api::rest::handlers_v2::{SimulationPath, parse_id};
+use crate::api::rest::responses_v2::{ContractPointResponse, ContractSeriesResponse};
+use crate::infrastructure::{
+    CURRENT_SNAPSHOT_GENERATION, ContractQuote, ContractSeriesQuery, ContractSide,
+    SimulationSnapshotRepository,
+};
+use crate::session::{SessionV2, SimulationManager, engine_version};
+use crate::utils::ChainError;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::{DateTime, SecondsFormat, Utc};
+use positive::Positive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, info, instrument};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// How many missing steps are priced per blocking task.
+///
+/// Small enough that a history of a short-dated contract stops pricing soon
+/// after its expiration, large enough that a long gap does not pay a task
+/// hand-off per step.
+const REPLAY_CHUNK_STEPS: usize = 64;
+
+/// Which side of the contract a history projects, or an export keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Side {
+    /// The call.
+    Call,
+    /// The put.
+    Put,
+}
+
+impl From<Side> for ContractSide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Call => ContractSide::Call,
+            Side::Put => ContractSide::Put,
+        }
+    }
+}
+
+/// Query parameters for a contract history.
+///
+/// `expires_at` and `strike` are matched exactly, so they are the values a
+/// snapshot served: its chain's `expires_at` and a contract's `strike`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SeriesQuery {
+    /// The contract's absolute expiration, RFC 3339.
+    pub(crate) expires_at: DateTime<Utc>,
+    /// The contract's strike, as a decimal.
+    pub(crate) strike: Decimal,
+    /// Which side to project.
+    pub(crate) side: Side,
+    /// First step to consider, inclusive. Defaults to `0`.
+    #[serde(default, alias = "from")]
+    pub(crate) from_step: Option<usize>,
+    /// Last step to consider, inclusive. Defaults to the final generated step.
+    #[serde(default, alias = "to")]
+    pub(crate) to_step: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/contracts/series",
+    description = "One contract's quotes across a step range: the steps that list the given \
+        expiration and strike, ascending, projected for one side. Steps the warehouse holds are \
+        read back and the rest are replayed deterministically, so the answer does not depend \
+        on what has been persisted. A step where the contract is not listed has no point. \
+        Read-only.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("expires_at" = String, Query, description = "The contract's expiration, RFC 3339, as a snapshot serves it"),
+        ("strike" = String, Query, description = "The contract's strike, as a snapshot serves it"),
+        ("side" = String, Query, description = "call or put"),
+        ("from_step" = Option<usize>, Query, description = "First step, inclusive; defaults to 0. Alias: from"),
+        ("to_step" = Option<usize>, Query, description = "Last step, inclusive; defaults to the final step. Alias: to")
+    ),
+    responses(
+        (status = 200, description = "The contract's history", body = ContractSeriesResponse),
+        (status = 400, description = "Malformed id or query, a non-positive strike or an invalid range; body carries `error` and `field` where one applies"),
+        (status = 404, description = "Simulation not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[instrument(skip(manager, snapshots, query), level = "debug")]
+pub(crate) async fn contract_series(
+    req: HttpRequest,
+    manager: web::Data<Arc<SimulationManager>>,
+    snapshots: Option<web::Data<Arc<dyn SimulationSnapshotRepository>>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<SeriesQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+
+    match contract_history(
+        &manager,
+        snapshots.map(|repository| Arc::clone(repository.get_ref())),
+        id,
+        &query,
+    )
+    .await
+    {
+        Ok(series) => HttpResponse::Ok().json(series),
+        Err(error) => map_error(error),
+    }
+}
+
+/// Validates a history request and assembles its points.
+///
+/// A warehouse that fails is logged and treated as empty: every point it would
+/// have returned can be replayed, so the failure costs time, not an answer.
+async fn contract_history(
+    manager: &SimulationManager,
+    snapshots: Option<Arc<dyn SimulationSnapshotRepository>>,
+    id: Uuid,
+    query: &SeriesQuery,
+) -> Result<ContractSeriesResponse, ChainError> {
+    let simulation = manager.get(id).await?;
+    manager.check_engine(&simulation)?;
+    let strike = Positive::new_decimal(query.strike).map_err(|_| ChainError::Validation {
+        field: "strike".to_string(),
+        reason: format!("must be positive, got {}", query.strike),
+    })?;
+    let range = StepRange::bounded(
+        query.from_step,
+        query.to_step,
+        simulation.parameters.steps,
+        manager.config().max_export_rows,
+    )?;
+    let side = ContractSide::from(query.side);
+
+    let mut points = match snapshots {
+        Some(repository) => {
+            let request = ContractSeriesQuery::new(
+                id,
+                CURRENT_SNAPSHOT_GENERATION,
+                query.expires_at,
+                strike,
+                side,
+                range.from,
+                range.to,
+            )
+            .priced_by(engine_version());
+            match repository.contract_series(request).await {
+                Ok(stored) => stored,
+                Err(error) => {
+                    debug!(simulation = %id, %error, "Replaying a contract history the warehouse could not read");
+                    Vec::new()
+                }
+            }
+        }
+        None => Vec::new(),
+    };
+
+    let held: HashSet<usize> = points.iter().map(|point| point.step).collect();
+    let missing: Vec<usize> = (range.from..=range.to)
+        .filter(|step| !held.contains(step))
+        .collect();
+    points.extend(
+        replay(
+            manager,
+            &simulation,
+            missing,
+            query.expires_at,
+            strike,
+            side,
+        )
+        .await?,
+    );
+    points.sort_by_key(|point| point.step);
+
+    Ok(ContractSeriesResponse {
+        id: simulation.id.to_string(),
+        expires_at: query.expires_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        strike: strike.to_f64(),
+        side: side.to_string(),
+        from_step: range.from,
+        to_step: range.to,
+        points: points.iter().map(ContractPointResponse::from).collect(),
+    })
+}
+
+/// Prices `steps`, ascending, and projects the contract out of each.
+///
+/// Stops at the first step on or past the expiration: simulated time only
+/// moves forward, so nothing after it can list the contract.
+async fn replay(
+    manager: &SimulationManager,
+    simulation: &SessionV2,
+    steps: Vec<usize>,
+    expires_at: DateTime<Utc>,
+    strike: Positive,
+    side: ContractSide,
+) -> Result<Vec<ContractQuote>, ChainError> {
+    let mut points = Vec::new();
+    for chunk in steps.chunks(REPLAY_CHUNK_STEPS) {
+        for record in manager
+            .materialize_records(simulation, chunk.to_vec())
+            .await?
+        {
+            if record.simulated_at >= expires_at {
+                return Ok(points);
+            }
+            points.extend(record.contract_quote(expires_at, strike, side));
+        }
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::requests_v2::CreateSimulationRequest;
+    use crate::infrastructure::{SimulationV2Config, SnapshotRecord};
+    use crate::session::{InMemorySimulationStore, SimulationOptions, SimulationParametersV2};
+    use positive::pos_or_panic;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// A warehouse that holds one contract point and records what it was
+    /// asked for.
+    #[derive(Default)]
+    struct OnePointWarehouse {
+        point: Option<ContractQuote>,
+        asked: Mutex<Vec<ContractSeriesQuery>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SimulationSnapshotRepository for OnePointWarehouse {
+        async fn persist(&self, _record: SnapshotRecord) -> Result<(), ChainError> {
+            Ok(())
+        }
+
+        async fn get(
+            &self,
+            _simulation: Uuid,
+            _generation: u64,
+            _step: usize,
+        ) -> Result<Option<SnapshotRecord>, ChainError> {
+            Ok(None)
+        }
+
+        async fn read_range(
+            &self,
+            _simulation: Uuid,
+            _generation: u64,
+            _from_step: usize,
+            _to_step: usize,
+        ) -> Result<Vec<SnapshotRecord>, ChainError> {
+            Ok(Vec::new())
+        }
+
+        async fn contract_series(
+            &self,
+            query: crate::infrastructure::ContractSeriesQuery,
+        ) -> Result<Vec<ContractQuote>, ChainError> {
+            match self.asked.lock() {
+                Ok(mut asked) => asked.push(query),
+                Err(poisoned) => poisoned.into_inner().push(query),
+            }
+            Ok(self.point.iter().cloned().collect())
+        }
+
+        async fn purge(&self, _simulations: &[Uuid]) -> Result<(), ChainError> {
+            Ok(())
+        }
+    }
+
+    /// A manager holding one three-step simulation with a narrow ladder.
+    async fn manager_with_simulation() -> (SimulationManager, Uuid) {
+        let request: CreateSimulationRequest = match serde_json::from_value(json!({
+            "symbol": "SPX",
+            "steps": 3,
+            "start_at": "2026-01-05T14:30:00Z",
+            "step_interval_seconds": 86400,
+            "timezone": "America/New_York",
+            "expiration_time": "17:00",
+            "schedules": [
+                { "rule_id": "weeklies", "kind": "weekly", "target_count": 2,
+                  "weekdays": ["Fri"] }
+            ],
+            "initial_price": 5000.0,
+            "volatility": 0.18,
+            "risk_free_rate": 0.04,
+            "dividend_yield": 0.0,
+            "method": { "Brownian": { "dt": 0.004, "drift": 0.0, "volatility": 0.18 } },
+            "time_frame": "Day",
+            "chain_size": 3,
+            "strike_interval": 25.0,
+            "spread": 0.02,
+            "seed": 42
+        })) {
+            Ok(request) => request,
+            Err(error) => panic!("the request must deserialize: {error}"),
+        };
+        let parameters = match SimulationParametersV2::try_from(request) {
+            Ok(parameters) => parameters,
+            Err(error) => panic!("the request must convert: {error}"),
+        };
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        );
+        match manager
+            .create(parameters, SimulationOptions::default())
+            .await
+        {
+            Ok(simulation) => (manager, simulation.id),
+            Err(error) => panic!("the simulation must be created: {error}"),
+        }
+    }
+
+    /// The first contract the simulation lists at step 0.
+    async fn first_contract(manager: &SimulationManager, id: Uuid) -> (DateTime<Utc>, Positive) {
+        let simulation = match manager.get(id).await {
+            Ok(simulation) => simulation,
+            Err(error) => panic!("the simulation must be readable: {error}"),
+        };
+        match manager.materialize_records(&simulation, vec![0]).await {
+            Ok(records) => {
+                match records
+                    .first()
+                    .and_then(|record| record.expirations.last())
+                    .and_then(|expiration| {
+                        expiration
+                            .quotes
+                            .first()
+                            .map(|quote| (expiration.expires_at, quote.strike))
+                    }) {
+                    Some(contract) => contract,
+                    None => panic!("step 0 must list a contract"),
+                }
+            }
+            Err(error) => panic!("step 0 must price: {error}"),
+        }
+    }
+
+    fn query(expires_at: DateTime<Utc>, strike: Positive) -> SeriesQuery {
+        SeriesQuery {
+            expires_at,
+            strike: strike.to_dec(),
+            side: Side::Put,
+            from_step: None,
+            to_step: None,
+        }
+    }
+
+    /// Without a warehouse every point is replayed, and a replayed history is
+    /// the same on every request.
+    #[tokio::test]
+    async fn test_a_history_without_a_warehouse_is_replayed() {
+        let (manager, id) = manager_with_simulation().await;
+        let (expires_at, strike) = first_contract(&manager, id).await;
+
+        let first = match contract_history(&manager, None, id, &query(expires_at, strike)).await {
+            Ok(series) => series,
+            Err(error) => panic!("the history must assemble: {error}"),
+        };
+        let second = match contract_history(&manager, None, id, &query(expires_at, strike)).await {
+            Ok(series) => series,
+            Err(error) => panic!("the history must assemble: {error}"),
+        };
+
+        assert_eq!(first, second);
+        assert_eq!(first.side, "put");
+        assert_eq!((first.from_step, first.to_step), (0, 2));
+        match first.points.first() {
+            Some(point) => assert_eq!(point.step, 0),
+            None => panic!("the contract is listed at step 0"),
+        }
+    }
+
+    /// A stored point is served as stored, the engine filter is asked for, and
+    /// the steps around it are replayed.
+    #[tokio::test]
+    async fn test_a_history_prefers_the_warehouse_and_replays_the_rest() {
+        let (manager, id) = manager_with_simulation().await;
+        let (expires_at, strike) = first_contract(&manager, id).await;
+        let simulation = match manager.get(id).await {
+            Ok(simulation) => simulation,
+            Err(error) => panic!("the simulation must be readable: {error}"),
+        };
+        let mut stored = match manager.materialize_records(&simulation, vec![1]).await {
+            Ok(records) => match records
+                .first()
+                .and_then(|record| record.contract_quote(expires_at, strike, ContractSide::Put))
+            {
+                Some(point) => point,
+                None => panic!("step 1 must list the contract"),
+            },
+            Err(error) => panic!("step 1 must price: {error}"),
+        };
+        // A value no replay produces, so the assertion can tell the sources
+        // apart.
+        stored.bid = Some(pos_or_panic!(123.0));
+        let warehouse = Arc::new(OnePointWarehouse {
+            point: Some(stored),
+            asked: Mutex::default(),
+        });
+        let repository: Arc<dyn SimulationSnapshotRepository> = warehouse.clone();
+
+        let series = match contract_history(
+            &manager,
+            Some(repository),
+            id,
+            &query(expires_at, strike),
+        )
+        .await
+        {
+            Ok(series) => series,
+            Err(error) => panic!("the history must assemble: {error}"),
+        };
+
+        let steps: Vec<usize> = series.points.iter().map(|point| point.step).collect();
+        assert_eq!(steps, vec![0, 1, 2]);
+        assert_eq!(
+            series.points.get(1).and_then(|point| point.bid),
+            Some(123.0)
+        );
+        match warehouse.asked.lock() {
+            Ok(asked) => match asked.first() {
+                Some(asked) => {
+                    assert_eq!(asked.engine.as_deref(), Some(engine_version()));
+                    assert_eq!((asked.from_step, asked.to_step), (0, 2));
+                }
+                None => panic!("the warehouse must be asked"),
+            },
+            Err(error) => panic!("the warehouse lock must not be poisoned: {error}"),
+        }
+    }
+
+    /// A strike that is not positive is a validation error naming `strike`.
+    #[tokio::test]
+    async fn test_a_non_positive_strike_is_rejected() {
+        let (manager, id) = manager_with_simulation().await;
+        let (expires_at, strike) = first_contract(&manager, id).await;
+        let mut request = query(expires_at, strike);
+        request.strike = Decimal::NEGATIVE_ONE;
+
+        match contract_history(&manager, None, id, &request).await {
+            Err(ChainError::Validation { field, .. }) => assert_eq!(field, "strike"),
+            other => panic!("a negative strike must be rejected, got {other:?}"),
+        }
+    }
+}
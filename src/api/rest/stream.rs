@@ -0,0 +1,792 @@
+//! Push delivery of a v2 simulation's snapshots over a WebSocket.
+//!
+//! `GET /api/v2/simulations/{id}/stream` upgrades to a WebSocket that carries
+//! every snapshot the simulation serves, **whoever advances it**: a client's
+//! `POST /step`, a playback, or an `advance` sent over this socket. It is a
+//! subscriber to the manager's advance events and nothing more, so it adds no
+//! second way to move a cursor — an advance over the socket is the same
+//! serve-then-advance, behind the same `expected_step` precondition and the
+//! same compare-and-swap, as the REST one.
+//!
+//! # Protocol
+//!
+//! Every frame is a JSON text frame with a `type`.
+//!
+//! From the client:
+//!
+//! - `{"type":"advance","expected_step":3}` — advance once; `expected_step` is
+//!   optional. The served snapshot arrives as an ordinary `snapshot` frame,
+//!   exactly once, like any other advance's.
+//! - `{"type":"peek"}` — the snapshot at the current cursor, on request.
+//! - `{"type":"filter","labels":["weeklies"],"min_strike":4900,"max_strike":5100}`
+//!   — replace the subscription filter.
+//!
+//! From the server:
+//!
+//! - `subscribed` — on connect and after every `filter`, echoing the filter.
+//! - `snapshot` — a [`SnapshotResponse`], cut down by the filter.
+//! - `completed` — the simulation served its last step; the socket then closes.
+//! - `lagged` — the client read too slowly and `missed` snapshots were
+//!   dropped rather than queued. The next `snapshot` is current.
+//! - `error` — a command failed. Carries the `status` the REST route would
+//!   have answered with and the same body, so `412` still brings
+//!   `current_step` and a `400` still names its `field`.
+//!
+//! # What the filter cuts
+//!
+//! `labels` keeps the expirations carrying at least one of the listed rule
+//! ids; a strike window keeps the contracts inside it, and drops an expiration
+//! it leaves empty. Both cut the **payload only**: the snapshot is priced in
+//! full either way, because it is shared with every other subscriber and with
+//! the warehouse.
+
+use crate::api::rest::error::map_error;
+use crate::api::rest::handlers_v2::{SimulationPath, parse_id, precondition_failed};
+use crate::api::rest::responses_v2::{SnapshotResponse, snapshot_response};
+use crate::session::{SessionState, SimulationManager};
+use crate::utils::ChainError;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use actix_ws::{Message, MessageStream, Session};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// How often the server pings an idle socket.
+///
+/// Keeps intermediaries from reaping a connection that is quiet because the
+/// simulation is, which for a paused playback can be indefinitely.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The largest frame a client may send.
+///
+/// Commands are a few dozen bytes; anything near this is not a command.
+const MAX_COMMAND_BYTES: usize = 64 * 1024;
+
+/// Query parameters for the stream: the initial subscription filter.
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub(crate) struct StreamQuery {
+    /// Comma-separated rule ids; only expirations carrying one are sent.
+    #[serde(default)]
+    pub(crate) labels: Option<String>,
+    /// Lowest strike sent, inclusive.
+    #[serde(default)]
+    pub(crate) min_strike: Option<f64>,
+    /// Highest strike sent, inclusive.
+    #[serde(default)]
+    pub(crate) max_strike: Option<f64>,
+}
+
+/// What a subscriber wants to receive of each snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub(crate) struct SnapshotFilter {
+    /// Rule ids; an expiration is kept when it carries any of them. Empty
+    /// keeps every expiration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) labels: Vec<String>,
+    /// Lowest strike kept, inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min_strike: Option<f64>,
+    /// Highest strike kept, inclusive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_strike: Option<f64>,
+}
+
+impl SnapshotFilter {
+    /// Checks the strike window.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `min_strike` or `max_strike`
+    /// when a bound is not a finite non-negative number, or the window is
+    /// empty.
+    pub(crate) fn validated(self) -> Result<Self, ChainError> {
+        for (field, bound) in [
+            ("min_strike", self.min_strike),
+            ("max_strike", self.max_strike),
+        ] {
+            if let Some(bound) = bound
+                && (!bound.is_finite() || bound < 0.0)
+            {
+                return Err(ChainError::Validation {
+                    field: field.to_string(),
+                    reason: format!("must be a finite non-negative number, got {bound}"),
+                });
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_strike, self.max_strike)
+            && min > max
+        {
+            return Err(ChainError::Validation {
+                field: "max_strike".to_string(),
+                reason: format!("must not be below min_strike {min}, got {max}"),
+            });
+        }
+        Ok(self)
+    }
+
+    /// Cuts a snapshot down to what the filter keeps.
+    #[must_use]
+    pub(crate) fn apply(&self, mut snapshot: SnapshotResponse) -> SnapshotResponse {
+        if !self.labels.is_empty() {
+            snapshot
+                .chains
+                .retain(|chain| chain.labels.iter().any(|label| self.labels.contains(label)));
+        }
+        if self.min_strike.is_some() || self.max_strike.is_some() {
+            for chain in &mut snapshot.chains {
+                chain.contracts.retain(|contract| {
+                    self.min_strike.is_none_or(|min| contract.strike >= min)
+                        && self.max_strike.is_none_or(|max| contract.strike <= max)
+                });
+            }
+            snapshot.chains.retain(|chain| !chain.contracts.is_empty());
+        }
+        snapshot
+    }
+}
+
+impl StreamQuery {
+    /// The filter this query names.
+    ///
+    /// # Errors
+    ///
+    /// As [`SnapshotFilter::validated`].
+    fn resolve(&self) -> Result<SnapshotFilter, ChainError> {
+        SnapshotFilter {
+            labels: self
+                .labels
+                .as_deref()
+                .map(|raw| {
+                    raw.split(',')
+                        .map(str::trim)
+                        .filter(|label| !label.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            min_strike: self.min_strike,
+            max_strike: self.max_strike,
+        }
+        .validated()
+    }
+}
+
+/// A command from the client.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum Command {
+    Advance {
+        #[serde(default)]
+        expected_step: Option<usize>,
+    },
+    Peek,
+    Filter(SnapshotFilter),
+}
+
+/// A frame to the client.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Frame<'a> {
+    Subscribed {
+        simulation_id: String,
+        filter: &'a SnapshotFilter,
+    },
+    Snapshot(SnapshotResponse),
+    Completed {
+        simulation_id: String,
+        total_steps: usize,
+    },
+    Lagged {
+        missed: u64,
+    },
+    Error {
+        status: u16,
+        #[serde(flatten)]
+        body: serde_json::Value,
+    },
+}
+
+impl Frame<'_> {
+    /// The error frame for what the REST route would have answered.
+    ///
+    /// Built from the response itself rather than from the error, so the two
+    /// transports cannot drift: a body the REST route changes changes here.
+    async fn from_response(response: HttpResponse) -> Self {
+        let status = response.status().as_u16();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_else(|| serde_json::json!({}));
+        Frame::Error { status, body }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v2/simulations/{id}/stream",
+    description = "Upgrade to a WebSocket carrying every snapshot the simulation serves, \
+        whoever advances it. Send `{\"type\":\"advance\",\"expected_step\":n}` to advance, \
+        `{\"type\":\"peek\"}` for the current snapshot, and `{\"type\":\"filter\",...}` to \
+        change the subscription. The query's `labels` and strike window cut each snapshot \
+        to the expirations and strikes wanted. A slow reader is sent `lagged` rather than \
+        queued for; the socket closes after `completed`.",
+    params(
+        ("id" = String, Path, description = "The simulation's identifier"),
+        ("labels" = Option<String>, Query, description = "Comma-separated rule ids; only expirations carrying one are sent"),
+        ("min_strike" = Option<f64>, Query, description = "Lowest strike sent, inclusive"),
+        ("max_strike" = Option<f64>, Query, description = "Highest strike sent, inclusive")
+    ),
+    responses(
+        (status = 101, description = "Upgraded to a WebSocket"),
+        (status = 400, description = "Malformed id or filter, or not a WebSocket handshake"),
+        (status = 404, description = "Simulation not found"),
+        (status = 410, description = "Simulation completed; there is nothing left to stream"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub(crate) async fn stream_simulation(
+    req: HttpRequest,
+    body: web::Payload,
+    manager: web::Data<Arc<SimulationManager>>,
+    path: web::Path<SimulationPath>,
+    query: web::Query<StreamQuery>,
+) -> impl Responder {
+    info!("{} {}", req.method(), req.path());
+
+    let id = match parse_id(&path.id) {
+        Ok(id) => id,
+        Err(error) => return map_error(error),
+    };
+    let filter = match query.resolve() {
+        Ok(filter) => filter,
+        Err(error) => return map_error(error),
+    };
+    match manager.get(id).await {
+        Ok(simulation) if simulation.state == SessionState::Completed => {
+            return map_error(ChainError::SimulatorError(
+                "simulation completed; nothing left to stream".to_string(),
+            ));
+        }
+        Ok(_) => {}
+        Err(error) => return map_error(error),
+    }
+
+    let (response, session, stream) = match actix_ws::handle(&req, body) {
+        Ok(upgrade) => upgrade,
+        Err(error) => return HttpResponse::from_error(error),
+    };
+    let stream = stream.max_frame_size(MAX_COMMAND_BYTES);
+
+    actix_web::rt::spawn(serve(
+        Arc::clone(manager.get_ref()),
+        id,
+        filter,
+        session,
+        stream,
+    ));
+    response
+}
+
+/// Runs one socket until the client leaves or the simulation completes.
+async fn serve(
+    manager: Arc<SimulationManager>,
+    id: Uuid,
+    mut filter: SnapshotFilter,
+    mut session: Session,
+    mut stream: MessageStream,
+) {
+    // Subscribed before the first frame goes out, so nothing committed after
+    // the client sees `subscribed` can be missed.
+    let mut events = manager.subscribe();
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+
+    if send(&mut session, &subscribed(id, &filter)).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let frame = match event {
+                    Ok(event) if event.simulation.id == id => {
+                        let snapshot = snapshot_response(&event.simulation, &event.snapshot);
+                        if send(&mut session, &Frame::Snapshot(filter.apply(snapshot))).await.is_err() {
+                            return;
+                        }
+                        if event.simulation.state != SessionState::Completed {
+                            continue;
+                        }
+                        Frame::Completed {
+                            simulation_id: id.to_string(),
+                            total_steps: event.simulation.total_steps,
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => Frame::Lagged { missed },
+                    Err(RecvError::Closed) => break,
+                };
+                let completed = matches!(frame, Frame::Completed { .. });
+                if send(&mut session, &frame).await.is_err() {
+                    return;
+                }
+                if completed {
+                    break;
+                }
+            }
+            message = stream.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let frame = match serde_json::from_str::<Command>(&text) {
+                        Ok(Command::Filter(next)) => match next.validated() {
+                            Ok(next) => {
+                                filter = next;
+                                subscribed(id, &filter)
+                            }
+                            Err(error) => Frame::from_response(map_error(error)).await,
+                        },
+                        Ok(command) => match execute(&manager, id, command, &filter).await {
+                            Some(frame) => frame,
+                            None => continue,
+                        },
+                        Err(error) => Frame::Error {
+                            status: 400,
+                            body: serde_json::json!({ "error": error.to_string() }),
+                        },
+                    };
+                    if send(&mut session, &frame).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(reason))) => {
+                    let _ = session.close(reason).await;
+                    return;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(error)) => {
+                    debug!(simulation_id = %id, %error, "Stream closed on a protocol error");
+                    return;
+                }
+                None => return,
+            },
+            _ = heartbeat.tick() => {
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    let _ = session.close(None).await;
+}
+
+/// Runs an `advance` or a `peek`, returning the frame to answer with, if any.
+///
+/// A successful advance answers nothing here: its snapshot reaches this
+/// socket through the advance events like every other subscriber's, and
+/// answering it directly as well would deliver it twice.
+async fn execute(
+    manager: &SimulationManager,
+    id: Uuid,
+    command: Command,
+    filter: &SnapshotFilter,
+) -> Option<Frame<'static>> {
+    match command {
+        Command::Advance { expected_step } => {
+            // The advance commits against the revision the check read, so an
+            // advance that slips in between cannot consume the step this
+            // client named; it is the same `412` as a stale `expected_step`.
+            let mut expected_version = None;
+            if let Some(expected) = expected_step {
+                match manager.get(id).await {
+                    Ok(simulation) if simulation.current_step != expected => {
+                        return Some(Frame::from_response(precondition_failed(&simulation)).await);
+                    }
+                    Ok(simulation) => expected_version = Some(simulation.version),
+                    Err(error) => return Some(Frame::from_response(map_error(error)).await),
+                }
+            }
+            let response = match manager.advance_expecting(id, expected_version).await {
+                Ok(_) => return None,
+                Err(ChainError::Conflict(_)) if expected_version.is_some() => {
+                    match manager.get(id).await {
+                        Ok(simulation) => precondition_failed(&simulation),
+                        Err(error) => map_error(error),
+                    }
+                }
+                Err(error) => map_error(error),
+            };
+            Some(Frame::from_response(response).await)
+        }
+        Command::Peek => match manager.peek(id).await {
+            Ok((simulation, snapshot)) => Some(Frame::Snapshot(
+                filter.apply(snapshot_response(&simulation, &snapshot)),
+            )),
+            Err(error) => Some(Frame::from_response(map_error(error)).await),
+        },
+        Command::Filter(_) => None,
+    }
+}
+
+fn subscribed(id: Uuid, filter: &SnapshotFilter) -> Frame<'_> {
+    Frame::Subscribed {
+        simulation_id: id.to_string(),
+        filter,
+    }
+}
+
+/// Sends one frame; an error means the client is gone.
+async fn send(session: &mut Session, frame: &Frame<'_>) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(frame) {
+        Ok(text) => session.text(text).await,
+        // Every frame is plain data; this cannot fail, and if it somehow did
+        // the client is better served by the next frame than by a dead socket.
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::responses_v2::{
+        ContractResponse, CursorResponse, ExpiryChainResponse, OptionQuoteResponse,
+        UnderlyingResponse,
+    };
+    use crate::api::rest::routes::configure_v2_routes;
+    use crate::infrastructure::SimulationV2Config;
+    use crate::session::InMemorySimulationStore;
+    use actix_web::App;
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{Value, json};
+
+    fn chain(labels: &[&str], strikes: &[f64]) -> ExpiryChainResponse {
+        ExpiryChainResponse {
+            expires_at: "2026-01-09T22:00:00Z".to_string(),
+            days_to_expiration: 4.0,
+            labels: labels.iter().map(|label| (*label).to_string()).collect(),
+            contracts: strikes
+                .iter()
+                .map(|strike| ContractResponse {
+                    strike: *strike,
+                    implied_volatility: 0.18,
+                    gamma: None,
+                    call: OptionQuoteResponse::default(),
+                    put: OptionQuoteResponse::default(),
+                })
+                .collect(),
+        }
+    }
+
+    fn snapshot() -> SnapshotResponse {
+        SnapshotResponse {
+            id: Uuid::nil().to_string(),
+            state: "in_progress".to_string(),
+            version: 1,
+            cursor: CursorResponse {
+                current_step: 0,
+                total_steps: 4,
+            },
+            simulated_at: "2026-01-05T14:30:00Z".to_string(),
+            underlying: UnderlyingResponse {
+                symbol: "SPX".to_string(),
+                price: 5000.0,
+                base_volatility: 0.18,
+            },
+            chains: vec![
+                chain(&["zero_dte"], &[4975.0, 5000.0, 5025.0]),
+                chain(&["monthlies", "weeklies"], &[4950.0, 5050.0]),
+            ],
+            digest: String::new(),
+        }
+    }
+
+    /// An empty filter sends the snapshot untouched.
+    #[test]
+    fn test_an_empty_filter_keeps_everything() {
+        assert_eq!(SnapshotFilter::default().apply(snapshot()), snapshot());
+    }
+
+    /// Labels keep the expirations carrying any of them, and a strike window
+    /// drops the contracts outside it and any expiration it empties.
+    #[test]
+    fn test_labels_and_a_strike_window_cut_the_payload() {
+        let by_label = SnapshotFilter {
+            labels: vec!["weeklies".to_string()],
+            ..SnapshotFilter::default()
+        };
+        let cut = by_label.apply(snapshot());
+        assert_eq!(cut.chains.len(), 1);
+        assert_eq!(cut.chains[0].labels, vec!["monthlies", "weeklies"]);
+
+        let by_strike = SnapshotFilter {
+            min_strike: Some(4990.0),
+            max_strike: Some(5030.0),
+            ..SnapshotFilter::default()
+        };
+        let cut = by_strike.apply(snapshot());
+        assert_eq!(cut.chains.len(), 1, "an emptied expiration is dropped");
+        let strikes: Vec<f64> = cut.chains[0].contracts.iter().map(|c| c.strike).collect();
+        assert_eq!(strikes, vec![5000.0, 5025.0]);
+    }
+
+    /// A strike window that is empty or not a number is refused by name.
+    #[test]
+    fn test_an_invalid_strike_window_is_rejected_by_name() {
+        for (filter, field) in [
+            (
+                SnapshotFilter {
+                    min_strike: Some(f64::NAN),
+                    ..SnapshotFilter::default()
+                },
+                "min_strike",
+            ),
+            (
+                SnapshotFilter {
+                    min_strike: Some(5100.0),
+                    max_strike: Some(5000.0),
+                    ..SnapshotFilter::default()
+                },
+                "max_strike",
+            ),
+        ] {
+            match filter.validated() {
+                Err(ChainError::Validation { field: named, .. }) => assert_eq!(named, field),
+                other => panic!("the window must be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    /// The query's comma-separated labels are split and trimmed.
+    #[test]
+    fn test_the_query_labels_are_split() {
+        let query = StreamQuery {
+            labels: Some("weeklies, monthlies,,".to_string()),
+            ..StreamQuery::default()
+        };
+        match query.resolve() {
+            Ok(filter) => assert_eq!(filter.labels, vec!["weeklies", "monthlies"]),
+            Err(error) => panic!("the query must resolve: {error}"),
+        }
+    }
+
+    /// Every documented command parses, and an unknown one does not.
+    #[test]
+    fn test_commands_parse_from_their_documented_shape() {
+        let parse = |text: &str| serde_json::from_str::<Command>(text);
+
+        assert!(matches!(
+            parse(r#"{"type":"advance","expected_step":3}"#),
+            Ok(Command::Advance {
+                expected_step: Some(3)
+            })
+        ));
+        assert!(matches!(
+            parse(r#"{"type":"advance"}"#),
+            Ok(Command::Advance {
+                expected_step: None
+            })
+        ));
+        assert!(matches!(parse(r#"{"type":"peek"}"#), Ok(Command::Peek)));
+        match parse(r#"{"type":"filter","labels":["weeklies"],"max_strike":5000}"#) {
+            Ok(Command::Filter(filter)) => {
+                assert_eq!(filter.labels, vec!["weeklies"]);
+                assert_eq!(filter.max_strike, Some(5000.0));
+            }
+            other => panic!("the filter command must parse, got {other:?}"),
+        }
+        assert!(parse(r#"{"type":"rewind"}"#).is_err());
+    }
+
+    /// An error frame carries the REST status and body, flattened.
+    #[actix_web::test]
+    async fn test_an_error_frame_mirrors_the_rest_response() {
+        let frame = Frame::from_response(map_error(ChainError::Validation {
+            field: "max_strike".to_string(),
+            reason: "must not be below min_strike".to_string(),
+        }))
+        .await;
+        let json = match serde_json::to_value(&frame) {
+            Ok(json) => json,
+            Err(error) => panic!("the frame must serialize: {error}"),
+        };
+        assert_eq!(json.get("type"), Some(&serde_json::json!("error")));
+        assert_eq!(json.get("status"), Some(&serde_json::json!(400)));
+        assert_eq!(json.get("field"), Some(&serde_json::json!("max_strike")));
+    }
+
+    /// Reads the next text frame as JSON, answering the server's pings on the
+    /// way; `None` once the socket closes.
+    async fn next_frame<S>(socket: &mut S) -> Option<Value>
+    where
+        S: futures::Stream<Item = Result<awc::ws::Frame, awc::error::WsProtocolError>> + Unpin,
+    {
+        loop {
+            match socket.next().await {
+                Some(Ok(awc::ws::Frame::Text(bytes))) => {
+                    return match serde_json::from_slice(&bytes) {
+                        Ok(value) => Some(value),
+                        Err(error) => panic!("every frame must be JSON: {error}"),
+                    };
+                }
+                Some(Ok(awc::ws::Frame::Close(_))) | None => return None,
+                Some(Ok(_)) => {}
+                Some(Err(error)) => panic!("the socket must not fail: {error}"),
+            }
+        }
+    }
+
+    /// Sends one command frame.
+    async fn command<S>(socket: &mut S, command: Value)
+    where
+        S: futures::Sink<awc::ws::Message, Error = awc::error::WsProtocolError> + Unpin,
+    {
+        if let Err(error) = socket
+            .send(awc::ws::Message::Text(command.to_string().into()))
+            .await
+        {
+            panic!("the command must be sent: {error}");
+        }
+    }
+
+    /// Over a real socket: every advance reaches the subscriber once,
+    /// whoever made it, cut by the filter; the socket's own commands answer
+    /// with the REST statuses; and it closes after the last step.
+    #[actix_web::test]
+    async fn test_a_socket_follows_every_advance_until_completion() {
+        let manager = Arc::new(SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        ));
+        let mut server = {
+            let manager = Arc::clone(&manager);
+            actix_test::start(move || {
+                let manager = Arc::clone(&manager);
+                App::new().configure(move |cfg| configure_v2_routes(cfg, manager, None))
+            })
+        };
+
+        let mut created = match server
+            .post("/api/v2/simulations")
+            .send_json(&json!({
+                "symbol": "SPX",
+                "steps": 3,
+                "start_at": "2026-01-05T14:30:00Z",
+                "step_interval_seconds": 86400,
+                "timezone": "America/New_York",
+                "expiration_time": "17:00",
+                "schedules": [
+                    { "rule_id": "zero_dte", "kind": "daily", "target_count": 1 },
+                    { "rule_id": "weeklies", "kind": "weekly", "target_count": 3,
+                      "weekdays": ["Mon", "Wed", "Fri"] }
+                ],
+                "initial_price": 5000.0,
+                "volatility": 0.18,
+                "risk_free_rate": 0.04,
+                "dividend_yield": 0.0,
+                "method": { "Brownian": { "dt": 0.004, "drift": 0.0, "volatility": 0.18 } },
+                "time_frame": "Day",
+                "chain_size": 3,
+                "strike_interval": 25.0,
+                "spread": 0.02,
+                "seed": 42
+            }))
+            .await
+        {
+            Ok(response) => response,
+            Err(error) => panic!("the simulation must be created: {error}"),
+        };
+        let id = match created.json::<Value>().await {
+            Ok(body) => match body.get("id").and_then(Value::as_str) {
+                Some(id) => id.to_string(),
+                None => panic!("the response must carry an id: {body}"),
+            },
+            Err(error) => panic!("the response must be JSON: {error}"),
+        };
+        let uuid = match Uuid::parse_str(&id) {
+            Ok(uuid) => uuid,
+            Err(error) => panic!("the id must be a UUID: {error}"),
+        };
+
+        let mut socket = match server
+            .ws_at(&format!("/api/v2/simulations/{id}/stream?labels=weeklies"))
+            .await
+        {
+            Ok(socket) => socket,
+            Err(error) => panic!("the handshake must succeed: {error}"),
+        };
+        let step_of = |frame: &Value| frame.pointer("/cursor/current_step").cloned();
+
+        let subscribed = next_frame(&mut socket).await;
+        assert_eq!(
+            subscribed.as_ref().and_then(|f| f.get("type")),
+            Some(&json!("subscribed"))
+        );
+
+        command(
+            &mut socket,
+            json!({ "type": "advance", "expected_step": 0 }),
+        )
+        .await;
+        let Some(frame) = next_frame(&mut socket).await else {
+            panic!("the advance must be pushed");
+        };
+        assert_eq!(frame.get("type"), Some(&json!("snapshot")));
+        assert_eq!(step_of(&frame), Some(json!(1)));
+        let chains = frame.get("chains").and_then(Value::as_array).cloned();
+        assert!(chains.is_some_and(|chains| chains.iter().all(|chain| {
+            chain
+                .get("labels")
+                .and_then(Value::as_array)
+                .is_some_and(|labels| labels.contains(&json!("weeklies")))
+        })));
+
+        if let Err(error) = manager.advance(uuid).await {
+            panic!("an advance elsewhere must succeed: {error}");
+        }
+        let Some(frame) = next_frame(&mut socket).await else {
+            panic!("an advance elsewhere must be pushed");
+        };
+        assert_eq!(step_of(&frame), Some(json!(2)));
+
+        command(
+            &mut socket,
+            json!({ "type": "advance", "expected_step": 0 }),
+        )
+        .await;
+        let Some(frame) = next_frame(&mut socket).await else {
+            panic!("a stale precondition must be answered");
+        };
+        assert_eq!(frame.get("status"), Some(&json!(412)));
+        assert_eq!(frame.get("current_step"), Some(&json!(2)));
+
+        command(&mut socket, json!({ "type": "peek" })).await;
+        let Some(frame) = next_frame(&mut socket).await else {
+            panic!("a peek must be answered");
+        };
+        assert_eq!(frame.get("type"), Some(&json!("snapshot")));
+        assert_eq!(step_of(&frame), Some(json!(2)));
+
+        command(&mut socket, json!({ "type": "advance" })).await;
+        let Some(frame) = next_frame(&mut socket).await else {
+            panic!("the last advance must be pushed");
+        };
+        assert_eq!(step_of(&frame), Some(json!(3)));
+        let Some(frame) = next_frame(&mut socket).await else {
+            panic!("completion must be announced");
+        };
+        assert_eq!(frame.get("type"), Some(&json!("completed")));
+        assert!(
+            next_frame(&mut socket).await.is_none(),
+            "the socket must close"
+        );
+    }
+}
@@ -10,11 +10,36 @@ use utoipa::OpenApi;
         crate::api::rest::handlers::update_session,
         crate::api::rest::handlers::delete_session,
         crate::api::rest::handlers_v2::create_simulation,
+        crate::api::rest::handlers_v2::list_simulations,
         crate::api::rest::handlers_v2::get_simulation,
         crate::api::rest::handlers_v2::peek_snapshot,
         crate::api::rest::handlers_v2::advance_simulation,
         crate::api::rest::handlers_v2::delete_simulation,
+        crate::api::rest::handlers_v2::purge_snapshots,
+        crate::api::rest::handlers_v2::touch_simulation,
+        crate::api::rest::handlers_v2::next_snapshot,
+        crate::api::rest::handlers_v2::seek_simulation,
+        crate::api::rest::handlers_v2::get_manifest,
+        crate::api::rest::handlers_v2::import_simulation,
+        crate::api::rest::handlers_v2::start_playback,
+        crate::api::rest::handlers_v2::get_playback,
+        crate::api::rest::handlers_v2::pause_playback,
+        crate::api::rest::handlers_v2::resume_playback,
+        crate::api::rest::handlers_v2::stop_playback,
+        crate::api::rest::handlers_v2::start_materialization,
+        crate::api::rest::handlers_v2::get_materialization,
+        crate::api::rest::handlers_v2::cancel_materialization,
+        crate::api::rest::stream::stream_simulation,
+        crate::api::rest::events::simulation_events,
         crate::api::rest::export::export_simulation,
+        crate::api::rest::bundle::export_bundle,
+        crate::api::rest::digest::tape_digest,
+        crate::api::rest::series::contract_series,
+        crate::api::rest::handlers_v2::register_webhook,
+        crate::api::rest::handlers_v2::list_webhooks,
+        crate::api::rest::handlers_v2::delete_webhook,
+        crate::api::rest::handlers_v2::register_simulation_webhook,
+        crate::api::rest::handlers_v2::list_simulation_webhooks,
     ),
     components(
         schemas(
@@ -27,9 +52,23 @@ use utoipa::OpenApi;
             crate::api::rest::responses::ValidationErrorResponse,
             crate::api::rest::requests_v2::CreateSimulationRequest,
             crate::api::rest::responses_v2::SimulationResponse,
+            crate::api::rest::responses_v2::SimulationListResponse,
+            crate::api::rest::requests_v2::StartPlaybackRequest,
+            crate::api::rest::responses_v2::PlaybackResponse,
+            crate::api::rest::responses_v2::MaterializationResponse,
+            crate::api::rest::requests_v2::PurgeSnapshotsRequest,
+            crate::api::rest::responses_v2::PurgeSnapshotsResponse,
+            crate::api::rest::requests_v2::RegisterWebhookRequest,
+            crate::api::rest::responses_v2::WebhookResponse,
+            crate::api::rest::responses_v2::WebhookListResponse,
+            crate::api::rest::responses_v2::AdvanceEventResponse,
+            crate::api::rest::responses_v2::ChainSummaryResponse,
             crate::api::rest::responses_v2::SimulationParametersResponse,
             crate::api::rest::responses_v2::ScheduleRuleResponse,
             crate::api::rest::responses_v2::SnapshotResponse,
+            crate::api::rest::responses_v2::TapeDigestResponse,
+            crate::api::rest::responses_v2::ContractSeriesResponse,
+            crate::api::rest::responses_v2::ContractPointResponse,
             crate::api::rest::responses_v2::ExpiryChainResponse,
             crate::api::rest::responses_v2::ContractResponse,
             crate::api::rest::responses_v2::OptionQuoteResponse,
@@ -457,6 +457,41 @@ impl ExpiryRule {
         })
     }
 
+    /// Builds a rule from its flat wire fields, with exactly the checks the
+    /// `Deserialize` path applies.
+    ///
+    /// For transports that do not speak JSON — the gRPC service carries the same
+    /// flat shape as a protobuf message — so a rule is held to one set of rules
+    /// however it arrives.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming the offending
+    /// `schedules.<rule_id>.<field>`, exactly as the JSON path does.
+    pub(crate) fn from_flat(
+        rule_id: String,
+        kind: String,
+        target_count: usize,
+        weekdays: Option<Vec<Weekday>>,
+        weekday: Option<Weekday>,
+        month: Option<u32>,
+    ) -> Result<Self, ChainError> {
+        validate_rule_id(&rule_id)?;
+        let target_count =
+            NonZeroUsize::new(target_count).ok_or_else(|| ChainError::Validation {
+                field: format!("schedules.{rule_id}.target_count"),
+                reason: "must be at least 1".to_string(),
+            })?;
+        Self::try_from(ExpiryRuleWire {
+            rule_id,
+            kind,
+            target_count,
+            weekdays,
+            weekday,
+            month,
+        })
+    }
+
     /// The rule's stable identifier, which becomes its label on every chain it
     /// produces.
     #[must_use]
@@ -949,6 +949,9 @@ mod tests {
             smile_curve: None,
             spread: Some(0.02),
             seed: Some(42),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         }
     }
 
@@ -764,6 +764,9 @@ mod tests {
             smile_curve: Some(0.5),
             spread: Some(0.01),
             seed: Some(SEED),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         };
         let parameters = match SimulationParametersV2::try_from(request) {
             Ok(parameters) => parameters,
@@ -61,10 +61,20 @@
 --   the crate would produce different values for the same step — see
 --   CURRENT_SNAPSHOT_GENERATION in
 --   `src/infrastructure/clickhouse/snapshots/record.rs`.
+--
+-- `engine_version` tags, it does not identify
+--   The engine that priced the row: the tape generation plus the locked
+--   versions of the pricing crates. It is deliberately outside the sorting
+--   key — a rewrite of a coordinate by another engine replaces the row rather
+--   than sitting beside it — and readers use it to decline rows their own
+--   engine did not price. Added after the table first shipped, so the
+--   repository adds it to an existing table with ADD COLUMN IF NOT EXISTS;
+--   older rows read as ''.
 CREATE TABLE IF NOT EXISTS simulation_snapshots
 (
     simulation_id           String CODEC(ZSTD(1)),
     simulation_generation   UInt64 CODEC(DoubleDelta, ZSTD(1)),
+    engine_version          LowCardinality(String) DEFAULT '' CODEC(ZSTD(1)),
     step                    UInt64 CODEC(DoubleDelta, ZSTD(1)),
     snapshot_id             String CODEC(ZSTD(1)),
     simulated_at            DateTime64(9, 'UTC') CODEC(DoubleDelta, ZSTD(1)),
@@ -1,9 +1,11 @@
 //! The storage-agnostic contract for the v2 snapshot tape.
 //!
-//! One trait with four operations, in the shape the two real query patterns
-//! need: reconstruct a whole snapshot (or a range of them) for #49's exports,
-//! and follow one `(expiration, strike, side)` across simulated time for a
-//! chart or a backtest.
+//! One trait with six operations: five in the shape the two real query
+//! patterns need — reconstruct a whole snapshot (or a range of them, or the
+//! part of a range an export filter keeps) for #49's exports, and follow one
+//! `(expiration, strike, side)` across simulated time for a chart or a
+//! backtest — and one to remove a simulation's rows on request, ahead of the
+//! table TTL.
 //!
 //! # What a reader is promised
 //!
@@ -24,7 +26,7 @@
 //!   silently truncated tape — a short answer is indistinguishable from a gap
 //!   in the data, and #49's exports page rather than guess.
 
-use super::record::{ContractQuote, ContractSide, SnapshotRecord};
+use super::record::{ContractQuote, ContractSide, ExpirationRecord, SnapshotRecord};
 use crate::utils::ChainError;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -54,6 +56,9 @@ pub struct ContractSeriesQuery {
     pub from_step: usize,
     /// Last step to consider, inclusive.
     pub to_step: usize,
+    /// When set, only steps this engine priced — or that predate the tag — are
+    /// read. `None` reads every engine's rows.
+    pub engine: Option<String>,
 }
 
 impl ContractSeriesQuery {
@@ -76,8 +81,138 @@ impl ContractSeriesQuery {
             side,
             from_step,
             to_step,
+            engine: None,
         }
     }
+
+    /// Restricts the history to steps priced by `engine`.
+    ///
+    /// Rows with an empty tag predate the tag and are kept, exactly as the
+    /// export keeps them: refusing them would turn every old tape into a
+    /// replay.
+    #[must_use = "builders do nothing unless the value is used"]
+    pub fn priced_by(mut self, engine: impl Into<String>) -> Self {
+        self.engine = Some(engine.into());
+        self
+    }
+}
+
+/// Which snapshots of a range to read, and which of their expirations.
+///
+/// The filters an export can push down: a step stride, and the expiration
+/// filters that need nothing but a stored column. Everything a quote needs the
+/// spot for — moneyness, a delta band — stays with the caller, which re-applies
+/// these too, so a repository is free to narrow less than it is asked to but
+/// never more.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotRangeQuery {
+    /// The simulation to read.
+    pub simulation: Uuid,
+    /// The generation the rows were written under. See
+    /// [`ContractSeriesQuery::generation`].
+    pub generation: u64,
+    /// First step to consider, inclusive. Also the origin of the stride.
+    pub from_step: usize,
+    /// Last step to consider, inclusive.
+    pub to_step: usize,
+    /// Reads `from_step` and every `stride`-th step after it. At least one.
+    pub stride: usize,
+    /// Keeps the expirations carrying any of these rule labels; empty keeps
+    /// every expiration.
+    pub labels: Vec<String>,
+    /// Keeps the expirations at least this many days out, inclusive.
+    pub min_days_to_expiration: Option<f64>,
+    /// Keeps the expirations at most this many days out, inclusive.
+    pub max_days_to_expiration: Option<f64>,
+}
+
+impl SnapshotRangeQuery {
+    /// Creates a query for every step of an inclusive range, unfiltered —
+    /// exactly what [`SimulationSnapshotRepository::read_range`] reads.
+    #[must_use]
+    pub fn new(simulation: Uuid, generation: u64, from_step: usize, to_step: usize) -> Self {
+        Self {
+            simulation,
+            generation,
+            from_step,
+            to_step,
+            stride: 1,
+            labels: Vec::new(),
+            min_days_to_expiration: None,
+            max_days_to_expiration: None,
+        }
+    }
+
+    /// Reads only every `stride`-th step, counted from `from_step`.
+    #[must_use = "builders do nothing unless the value is used"]
+    pub fn every(mut self, stride: usize) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    /// Keeps only the expirations carrying any of `labels`.
+    #[must_use = "builders do nothing unless the value is used"]
+    pub fn labelled(mut self, labels: Vec<String>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Keeps only the expirations whose days to expiration fall within the
+    /// inclusive bounds given.
+    #[must_use = "builders do nothing unless the value is used"]
+    pub fn expiring_within(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min_days_to_expiration = min;
+        self.max_days_to_expiration = max;
+        self
+    }
+
+    /// Whether the query drops any expiration, as opposed to whole steps only.
+    ///
+    /// A narrowed snapshot holds fewer quotes than its marker promises, so a
+    /// repository has to check completeness some other way than by counting
+    /// what it read.
+    #[must_use]
+    pub fn narrows_expirations(&self) -> bool {
+        !self.labels.is_empty()
+            || self.min_days_to_expiration.is_some()
+            || self.max_days_to_expiration.is_some()
+    }
+
+    /// Whether `step` is on the stride.
+    #[must_use]
+    pub fn keeps_step(&self, step: usize) -> bool {
+        step.checked_sub(self.from_step)
+            .and_then(|offset| offset.checked_rem(self.stride))
+            .is_some_and(|rest| rest == 0)
+    }
+
+    /// Whether an expiration passes the label and days-to-expiration filters.
+    #[must_use]
+    pub fn keeps_expiration(&self, expiration: &ExpirationRecord) -> bool {
+        let days = expiration.days_to_expiration.to_f64();
+        (self.labels.is_empty()
+            || expiration
+                .labels
+                .iter()
+                .any(|label| self.labels.contains(label)))
+            && self.min_days_to_expiration.is_none_or(|min| days >= min)
+            && self.max_days_to_expiration.is_none_or(|max| days <= max)
+    }
+
+    /// Validates the stride.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `stride` when it is zero.
+    pub fn validate(&self) -> Result<(), ChainError> {
+        if self.stride == 0 {
+            return Err(ChainError::Validation {
+                field: "stride".to_string(),
+                reason: "must be at least 1".to_string(),
+            });
+        }
+        Ok(())
+    }
 }
 
 /// Persists and reads the v2 snapshot tape.
@@ -142,6 +277,71 @@ pub trait SimulationSnapshotRepository: Send + Sync {
         to_step: usize,
     ) -> Result<Vec<SnapshotRecord>, ChainError>;
 
+    /// Lists the steps of an inclusive range that are stored complete,
+    /// ascending.
+    ///
+    /// Exactly the steps [`SimulationSnapshotRepository::read_range`] would
+    /// return, for a caller that only needs to know which are there. The
+    /// default reads the range and keeps the steps, which is correct for any
+    /// implementation; a warehouse overrides it to check each completion
+    /// marker against a count of its rows, without fetching the rows.
+    ///
+    /// # Errors
+    ///
+    /// As [`SimulationSnapshotRepository::read_range`].
+    async fn stored_steps(
+        &self,
+        simulation: Uuid,
+        generation: u64,
+        from_step: usize,
+        to_step: usize,
+    ) -> Result<Vec<usize>, ChainError> {
+        let records = self
+            .read_range(simulation, generation, from_step, to_step)
+            .await?;
+        Ok(records.into_iter().map(|record| record.step).collect())
+    }
+
+    /// Reads the steps of a range a [`SnapshotRangeQuery`] keeps, ascending,
+    /// each holding only the expirations it keeps.
+    ///
+    /// A step that was never completed is skipped exactly as
+    /// [`SimulationSnapshotRepository::read_range`] skips it; a complete step
+    /// whose expirations are all filtered out comes back with none. The
+    /// default reads the whole range and narrows it in memory, which is
+    /// correct for any implementation; a warehouse overrides it to filter
+    /// where the rows are.
+    ///
+    /// # Errors
+    ///
+    /// As [`SimulationSnapshotRepository::read_range`], and
+    /// [`ChainError::Validation`] naming `stride` when it is zero.
+    async fn read_matching(
+        &self,
+        query: SnapshotRangeQuery,
+    ) -> Result<Vec<SnapshotRecord>, ChainError> {
+        query.validate()?;
+        let records = self
+            .read_range(
+                query.simulation,
+                query.generation,
+                query.from_step,
+                query.to_step,
+            )
+            .await?;
+
+        Ok(records
+            .into_iter()
+            .filter(|record| query.keeps_step(record.step))
+            .map(|mut record| {
+                record
+                    .expirations
+                    .retain(|expiration| query.keeps_expiration(expiration));
+                record
+            })
+            .collect())
+    }
+
     /// Reads one contract's history, ordered by simulated time.
     ///
     /// Only steps whose snapshot is complete contribute, so a chart never shows
@@ -154,6 +354,20 @@ pub trait SimulationSnapshotRepository: Send + Sync {
         &self,
         query: ContractSeriesQuery,
     ) -> Result<Vec<ContractQuote>, ChainError>;
+
+    /// Removes every row of `simulations`, across every generation.
+    ///
+    /// The table TTL removes rows eventually; this removes them now, for a
+    /// deletion that must not wait for it. The snapshots are absent from every
+    /// read path once this returns, even if the storage itself is reclaimed
+    /// later. An id with no rows is not an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::ClickHouseError`] when the warehouse is
+    /// unreachable or refuses the delete. A failure can leave part of the rows
+    /// behind, never a snapshot that reads as whole; retrying is safe.
+    async fn purge(&self, simulations: &[Uuid]) -> Result<(), ChainError>;
 }
 
 #[cfg(test)]
@@ -229,47 +443,24 @@ mod tests {
                         ..=(query.simulation, query.generation, query.to_step),
                 )
                 .map(|(_, record)| record)
+                .filter(|record| {
+                    query.engine.as_deref().is_none_or(|engine| {
+                        record.engine_version.is_empty() || record.engine_version == engine
+                    })
+                })
             {
-                for expiration in &record.expirations {
-                    if expiration.expires_at != query.expires_at {
-                        continue;
-                    }
-                    for quote in &expiration.quotes {
-                        if quote.strike != query.strike {
-                            continue;
-                        }
-                        // The side is a projection, exactly as it is in the SQL
-                        // implementation: one stored row, two ways to read it.
-                        let (bid, ask, mid, delta) = match query.side {
-                            ContractSide::Call => (
-                                quote.call_bid,
-                                quote.call_ask,
-                                quote.call_mid,
-                                quote.delta_call,
-                            ),
-                            ContractSide::Put => {
-                                (quote.put_bid, quote.put_ask, quote.put_mid, quote.delta_put)
-                            }
-                        };
-                        series.push(ContractQuote {
-                            step: record.step,
-                            simulated_at: record.simulated_at,
-                            expires_at: expiration.expires_at,
-                            days_to_expiration: expiration.days_to_expiration,
-                            strike: quote.strike,
-                            side: query.side,
-                            implied_volatility: quote.implied_volatility,
-                            bid,
-                            ask,
-                            mid,
-                            delta,
-                            gamma: quote.gamma,
-                        });
-                    }
-                }
+                series.extend(record.contract_quote(query.expires_at, query.strike, query.side));
             }
             Ok(series)
         }
+
+        async fn purge(&self, simulations: &[Uuid]) -> Result<(), ChainError> {
+            self.stored
+                .lock()
+                .await
+                .retain(|(simulation, _, _), _| !simulations.contains(simulation));
+            Ok(())
+        }
     }
 
     fn instant(day: u32) -> DateTime<Utc> {
@@ -306,6 +497,7 @@ mod tests {
         SnapshotRecord::new(
             simulation,
             CURRENT_SNAPSHOT_GENERATION,
+            "tape2+test".to_string(),
             step,
             instant(5),
             "SPX".to_string(),
@@ -461,6 +653,105 @@ mod tests {
         }
     }
 
+    /// A history restricted to one engine skips the steps another engine
+    /// priced, and keeps the ones that predate the tag.
+    #[tokio::test]
+    async fn test_a_contract_history_can_be_restricted_to_one_engine() {
+        let simulation = Uuid::from_u128(5);
+        let repository = InMemorySnapshotRepository::default();
+        for (step, engine) in [(0, "tape2+test"), (1, "tape1+old"), (2, "")] {
+            let mut stored = record(simulation, step);
+            stored.engine_version = engine.to_string();
+            match repository.persist(stored).await {
+                Ok(()) => {}
+                Err(error) => panic!("the snapshot must persist: {error}"),
+            }
+        }
+
+        match repository
+            .contract_series(series_query(simulation, ContractSide::Call).priced_by("tape2+test"))
+            .await
+        {
+            Ok(series) => {
+                let steps: Vec<usize> = series.iter().map(|quote| quote.step).collect();
+                assert_eq!(steps, vec![0, 2]);
+            }
+            Err(error) => panic!("the series must read: {error}"),
+        }
+    }
+
+    /// A matching read keeps the steps on the stride and, within them, only
+    /// the expirations the filters keep — by default, over `read_range`.
+    #[tokio::test]
+    async fn test_a_matching_read_narrows_steps_and_expirations() {
+        let simulation = Uuid::from_u128(5);
+        let repository = InMemorySnapshotRepository::default();
+        for step in 0..5 {
+            let mut stored = record(simulation, step);
+            stored.expirations.push(ExpirationRecord::new(
+                instant(30),
+                pos_or_panic!(25.0),
+                vec!["monthlies".to_string()],
+                vec![quote()],
+            ));
+            if let Err(error) = repository.persist(stored).await {
+                panic!("the snapshot must persist: {error}");
+            }
+        }
+
+        let query = SnapshotRangeQuery::new(simulation, CURRENT_SNAPSHOT_GENERATION, 1, 4).every(2);
+        match repository.read_matching(query.clone()).await {
+            Ok(range) => {
+                let steps: Vec<usize> = range.iter().map(|record| record.step).collect();
+                assert_eq!(steps, vec![1, 3]);
+                assert!(range.iter().all(|record| record.expirations.len() == 2));
+            }
+            Err(error) => panic!("the range must read: {error}"),
+        }
+
+        for narrowed in [
+            query.clone().labelled(vec!["weeklies".to_string()]),
+            query.clone().expiring_within(None, Some(4.0)),
+        ] {
+            assert!(narrowed.narrows_expirations());
+            match repository.read_matching(narrowed).await {
+                Ok(range) => {
+                    assert_eq!(range.len(), 2, "a step survives losing expirations");
+                    for record in &range {
+                        let labels: Vec<&[String]> = record
+                            .expirations
+                            .iter()
+                            .map(|expiration| expiration.labels.as_slice())
+                            .collect();
+                        assert_eq!(labels, vec![["weeklies".to_string()].as_slice()]);
+                    }
+                }
+                Err(error) => panic!("the range must read: {error}"),
+            }
+        }
+
+        match repository
+            .read_matching(query.expiring_within(Some(100.0), None))
+            .await
+        {
+            Ok(range) => assert!(range.iter().all(|record| record.expirations.is_empty())),
+            Err(error) => panic!("the range must read: {error}"),
+        }
+    }
+
+    /// A zero stride is refused rather than read as "every step" or as none.
+    #[tokio::test]
+    async fn test_a_matching_read_refuses_a_zero_stride() {
+        let repository = InMemorySnapshotRepository::default();
+        let query =
+            SnapshotRangeQuery::new(Uuid::from_u128(5), CURRENT_SNAPSHOT_GENERATION, 0, 3).every(0);
+
+        match repository.read_matching(query).await {
+            Err(ChainError::Validation { field, .. }) => assert_eq!(field, "stride"),
+            other => panic!("a zero stride must be refused, got {other:?}"),
+        }
+    }
+
     /// Persisting the same coordinate twice leaves one snapshot.
     #[tokio::test]
     async fn test_persisting_twice_is_idempotent() {
@@ -482,4 +773,33 @@ mod tests {
             Err(error) => panic!("the range must read: {error}"),
         }
     }
+
+    /// A purge removes every step of the named simulations and nothing of
+    /// the others.
+    #[tokio::test]
+    async fn test_a_purge_removes_only_the_named_simulations() {
+        let (purged, kept) = (Uuid::from_u128(5), Uuid::from_u128(6));
+        let repository = InMemorySnapshotRepository::default();
+        for simulation in [purged, kept] {
+            for step in 0..2 {
+                if let Err(error) = repository.persist(record(simulation, step)).await {
+                    panic!("the snapshot must persist: {error}");
+                }
+            }
+        }
+
+        if let Err(error) = repository.purge(&[purged]).await {
+            panic!("the purge must succeed: {error}");
+        }
+
+        for (simulation, expected) in [(purged, 0), (kept, 2)] {
+            match repository
+                .read_range(simulation, CURRENT_SNAPSHOT_GENERATION, 0, 1)
+                .await
+            {
+                Ok(range) => assert_eq!(range.len(), expected),
+                Err(error) => panic!("the range must read: {error}"),
+            }
+        }
+    }
 }
@@ -58,6 +58,9 @@ pub(crate) struct SnapshotMetaRow {
     pub(crate) simulation_id: String,
     /// The generation the snapshot was produced under.
     pub(crate) simulation_generation: u64,
+    /// The engine that priced the snapshot; empty for rows written before it
+    /// was recorded.
+    pub(crate) engine_version: String,
     /// The 0-based step.
     pub(crate) step: u64,
     /// The deterministic snapshot identity, as text.
@@ -156,6 +159,8 @@ pub(crate) struct SnapshotMetaReadRow {
     pub(crate) base_volatility: i128,
     /// How many quote rows the writer said belong to this snapshot.
     pub(crate) quote_count: u64,
+    /// The engine that priced the snapshot.
+    pub(crate) engine_version: String,
 }
 
 /// The quote columns a whole-snapshot read selects, in query order.
@@ -390,6 +395,7 @@ pub(crate) fn meta_row(
     Ok(SnapshotMetaRow {
         simulation_id: record.simulation.to_string(),
         simulation_generation: record.generation,
+        engine_version: record.engine_version.clone(),
         step: to_storage_count(record.step, "step")?,
         snapshot_id: record.snapshot_id().to_string(),
         simulated_at: to_storage_instant(record.simulated_at, "simulated_at")?,
@@ -513,6 +519,7 @@ pub(crate) fn record_from_rows(
     Ok(SnapshotRecord {
         simulation,
         generation,
+        engine_version: meta.engine_version.clone(),
         step,
         simulated_at: from_storage_instant(meta.simulated_at),
         symbol: meta.symbol.clone(),
@@ -635,6 +642,7 @@ mod tests {
         SnapshotRecord::new(
             Uuid::from_u128(42),
             3,
+            "tape2+test".to_string(),
             7,
             instant(5),
             "SPX".to_string(),
@@ -669,6 +677,7 @@ mod tests {
             underlying_price: meta.underlying_price,
             base_volatility: meta.base_volatility,
             quote_count: meta.quote_count,
+            engine_version: meta.engine_version,
         };
         let quotes = quotes
             .into_iter()
@@ -279,6 +279,12 @@ pub struct SnapshotRecord {
     /// [`CURRENT_SNAPSHOT_GENERATION`] for anything this build materialises.
     /// Not the session's `version`, which is its CAS revision.
     pub generation: u64,
+    /// The engine that priced the snapshot — the tape generation and the
+    /// locked pricing dependencies, as the session layer names them. A tag,
+    /// not part of the coordinate: two engines that disagree on a number are
+    /// told apart by it, and a reader can decline rows its engine did not
+    /// price. Empty for rows written before it was recorded.
+    pub engine_version: String,
     /// The 0-based step.
     pub step: usize,
     /// The simulated instant, from the factor tape.
@@ -309,6 +315,7 @@ impl SnapshotRecord {
     pub fn new(
         simulation: Uuid,
         generation: u64,
+        engine_version: String,
         step: usize,
         simulated_at: DateTime<Utc>,
         symbol: String,
@@ -319,6 +326,7 @@ impl SnapshotRecord {
         Self {
             simulation,
             generation,
+            engine_version,
             step,
             simulated_at,
             symbol,
@@ -346,6 +354,53 @@ impl SnapshotRecord {
             .sum()
     }
 
+    /// One side of one contract at this step, or `None` when the snapshot does
+    /// not list it.
+    ///
+    /// The in-memory projection of `contract_series`: one stored row, read for
+    /// one side. A history stitched from stored and replayed steps converts
+    /// both through here, so the two sources cannot disagree on a field.
+    #[must_use]
+    pub fn contract_quote(
+        &self,
+        expires_at: DateTime<Utc>,
+        strike: Positive,
+        side: ContractSide,
+    ) -> Option<ContractQuote> {
+        let expiration = self
+            .expirations
+            .iter()
+            .find(|expiration| expiration.expires_at == expires_at)?;
+        let quote = expiration
+            .quotes
+            .iter()
+            .find(|quote| quote.strike == strike)?;
+        let (bid, ask, mid, delta) = match side {
+            ContractSide::Call => (
+                quote.call_bid,
+                quote.call_ask,
+                quote.call_mid,
+                quote.delta_call,
+            ),
+            ContractSide::Put => (quote.put_bid, quote.put_ask, quote.put_mid, quote.delta_put),
+        };
+
+        Some(ContractQuote {
+            step: self.step,
+            simulated_at: self.simulated_at,
+            expires_at,
+            days_to_expiration: expiration.days_to_expiration,
+            strike,
+            side,
+            implied_volatility: quote.implied_volatility,
+            bid,
+            ask,
+            mid,
+            delta,
+            gamma: quote.gamma,
+        })
+    }
+
     /// Checks the invariants the storage layer depends on.
     ///
     /// The fields are public, so a caller can assemble a record that never
@@ -482,6 +537,7 @@ mod tests {
         SnapshotRecord::new(
             simulation,
             generation,
+            "tape2+test".to_string(),
             step,
             instant(5),
             "SPX".to_string(),
@@ -592,6 +648,33 @@ mod tests {
         assert_eq!(record(Uuid::from_u128(9), 1, 0).quote_count(), 5);
     }
 
+    /// A contract is projected for the side asked for, and a contract the
+    /// snapshot does not list is absent rather than empty.
+    #[test]
+    fn test_a_record_projects_one_contract() {
+        let record = record(Uuid::from_u128(9), 1, 4);
+
+        match record.contract_quote(instant(6), pos_or_panic!(5000.0), ContractSide::Put) {
+            Some(quote) => {
+                assert_eq!(quote.step, 4);
+                assert_eq!(quote.side, ContractSide::Put);
+                assert_eq!(quote.bid, Some(pos_or_panic!(0.9)));
+                assert_eq!(quote.delta, Some(dec!(-0.49)));
+                assert_eq!(quote.gamma, Some(dec!(0.0031)));
+                assert_eq!(quote.days_to_expiration, pos_or_panic!(6.0));
+            }
+            None => panic!("the listed contract must project"),
+        }
+        assert_eq!(
+            record.contract_quote(instant(9), pos_or_panic!(5025.0), ContractSide::Call),
+            None
+        );
+        assert_eq!(
+            record.contract_quote(instant(7), pos_or_panic!(5000.0), ContractSide::Call),
+            None
+        );
+    }
+
     /// A well-formed record validates.
     #[test]
     fn test_a_well_formed_record_validates() {
@@ -0,0 +1,144 @@
+//! Configuration for where v1 session events and chain steps are recorded.
+//!
+//! The record is an audit trail, not state the service reads back, so MongoDB
+//! is one choice among three rather than a requirement: an append-only NDJSON
+//! file keeps the trail without a database, and `none` drops it for a CI run
+//! that only needs the API.
+
+use crate::utils::ChainError;
+use std::env;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Default file the NDJSON sink appends to, relative to the working directory.
+pub const DEFAULT_EVENT_SINK_PATH: &str = "events.ndjson";
+
+/// Where v1 events are recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventSinkKind {
+    /// The `steps` and `events` collections in MongoDB. The default, so an
+    /// existing deployment keeps its trail where it was.
+    #[default]
+    MongoDb,
+    /// One JSON line per record, appended to a local file.
+    Ndjson,
+    /// Nowhere. The service then needs no MongoDB at all.
+    Noop,
+}
+
+impl EventSinkKind {
+    /// The name the sink is configured by.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MongoDb => "mongodb",
+            Self::Ndjson => "ndjson",
+            Self::Noop => "none",
+        }
+    }
+}
+
+/// Where v1 events are recorded, and the file when that is one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventSinkConfig {
+    /// Which sink records the events.
+    pub kind: EventSinkKind,
+    /// The file the NDJSON sink appends to; unused by the others.
+    pub path: PathBuf,
+}
+
+impl Default for EventSinkConfig {
+    fn default() -> Self {
+        Self {
+            kind: EventSinkKind::default(),
+            path: PathBuf::from(DEFAULT_EVENT_SINK_PATH),
+        }
+    }
+}
+
+impl EventSinkConfig {
+    /// Reads the configuration from the environment.
+    ///
+    /// An unset or blank variable takes its default. An unknown sink fails
+    /// startup rather than falling back: a trail an operator asked for and
+    /// silently did not get is found only when it is needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `OCS_EVENT_SINK` when its value
+    /// is not one of `mongodb`, `ndjson` or `none`.
+    pub fn from_env() -> Result<Self, ChainError> {
+        let config = Self {
+            kind: parse_kind("OCS_EVENT_SINK", read("OCS_EVENT_SINK").as_deref())?,
+            path: read("OCS_EVENT_SINK_PATH")
+                .map_or_else(|| PathBuf::from(DEFAULT_EVENT_SINK_PATH), PathBuf::from),
+        };
+
+        info!(
+            kind = config.kind.as_str(),
+            path = %config.path.display(),
+            "Loaded the event sink configuration"
+        );
+        Ok(config)
+    }
+}
+
+/// Parses the sink: `mongodb`, `ndjson` or `none`, in any case.
+///
+/// Takes the raw value rather than reading it, so it can be tested without
+/// mutating the process environment.
+fn parse_kind(variable: &str, raw: Option<&str>) -> Result<EventSinkKind, ChainError> {
+    let Some(raw) = raw else {
+        return Ok(EventSinkKind::default());
+    };
+
+    match raw.to_ascii_lowercase().as_str() {
+        "mongodb" => Ok(EventSinkKind::MongoDb),
+        "ndjson" => Ok(EventSinkKind::Ndjson),
+        "none" => Ok(EventSinkKind::Noop),
+        _ => Err(ChainError::Validation {
+            field: variable.to_string(),
+            reason: format!("must be mongodb, ndjson or none, got {raw:?}"),
+        }),
+    }
+}
+
+/// Reads a variable, treating an empty or whitespace-only value as unset.
+fn read(variable: &str) -> Option<String> {
+    let raw = env::var(variable).ok()?;
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The sink defaults to MongoDB, accepts every name, and fails by name on
+    /// anything else.
+    #[test]
+    fn test_the_sink_kind_parses() {
+        for (raw, expected) in [
+            (None, EventSinkKind::MongoDb),
+            (Some("mongodb"), EventSinkKind::MongoDb),
+            (Some("NDJSON"), EventSinkKind::Ndjson),
+            (Some("none"), EventSinkKind::Noop),
+        ] {
+            match parse_kind("OCS_EVENT_SINK", raw) {
+                Ok(kind) => assert_eq!(kind, expected),
+                Err(error) => panic!("{raw:?} must parse: {error}"),
+            }
+        }
+        match parse_kind("OCS_EVENT_SINK", Some("kafka")) {
+            Err(ChainError::Validation { field, reason }) => {
+                assert_eq!(field, "OCS_EVENT_SINK");
+                assert!(reason.contains("mongodb, ndjson or none"), "{reason}");
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Configuration for the file-backed stores.
+//!
+//! Read only when `OCS_V2_STORE=file` selects them, so a deployment on Redis or
+//! MongoDB never needs a writable directory.
+
+use crate::utils::ChainError;
+use std::env;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Default directory the file-backed stores keep their state in, relative to
+/// the working directory.
+pub const DEFAULT_FILE_STORE_DIR: &str = "data";
+
+/// Where the file-backed stores keep their state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStoreConfig {
+    /// The root directory; each store takes a subdirectory of its own.
+    pub dir: PathBuf,
+}
+
+impl Default for FileStoreConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(DEFAULT_FILE_STORE_DIR),
+        }
+    }
+}
+
+impl FileStoreConfig {
+    /// Reads the configuration from the environment.
+    ///
+    /// An unset or blank `OCS_FILE_STORE_DIR` takes the default. The directory
+    /// itself is checked when a store opens it, which is where a permission
+    /// problem can be reported with the path that caused it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming the variable when its value is
+    /// not valid Unicode.
+    pub fn from_env() -> Result<Self, ChainError> {
+        let config = match env::var("OCS_FILE_STORE_DIR") {
+            Ok(raw) => Self::parse(Some(&raw)),
+            Err(env::VarError::NotPresent) => Self::parse(None),
+            Err(env::VarError::NotUnicode(_)) => {
+                return Err(ChainError::Validation {
+                    field: "OCS_FILE_STORE_DIR".to_string(),
+                    reason: "must be valid Unicode".to_string(),
+                });
+            }
+        };
+
+        info!(dir = %config.dir.display(), "Loaded the file store configuration");
+        Ok(config)
+    }
+
+    /// Builds the configuration from the raw value, so it can be tested
+    /// without mutating the process environment.
+    fn parse(raw: Option<&str>) -> Self {
+        match raw.map(str::trim).filter(|dir| !dir.is_empty()) {
+            Some(dir) => Self {
+                dir: PathBuf::from(dir),
+            },
+            None => Self::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An unset or blank directory takes the default; anything else is used
+    /// as given.
+    #[test]
+    fn test_the_directory_defaults_when_unset_or_blank() {
+        assert_eq!(FileStoreConfig::parse(None), FileStoreConfig::default());
+        assert_eq!(
+            FileStoreConfig::parse(Some("  ")),
+            FileStoreConfig::default()
+        );
+        assert_eq!(
+            FileStoreConfig::parse(Some("/var/lib/ocs")).dir,
+            PathBuf::from("/var/lib/ocs")
+        );
+    }
+}
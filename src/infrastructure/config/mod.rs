@@ -1,4 +1,8 @@
 pub mod clickhouse;
+/// Where v1 session events and chain steps are recorded.
+pub mod event_sink;
+/// Where the file-backed stores keep their state.
+pub mod file_store;
 pub mod mongo;
 pub mod redis;
 /// Operational configuration for v2 rolling simulations: retention, the
@@ -8,6 +12,8 @@ pub mod simulation_v2;
 /// how large a batch may be, how long an insert may take, and how long the rows
 /// are kept.
 pub mod snapshot;
+/// Where snapshots the warehouse queue had no room for are spooled.
+pub mod snapshot_spool;
 
 /// Redacts URL userinfo (credentials) from every URL-like substring inside a
 /// larger text (log lines, driver error messages).
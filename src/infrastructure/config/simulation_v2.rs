@@ -20,6 +20,7 @@
 //! startup with a message naming the variable, which is what
 //! `rules/global_rules.md` asks of a configuration knob.
 
+use super::snapshot::parse_bool;
 use crate::utils::ChainError;
 use std::env;
 use std::sync::OnceLock;
@@ -72,6 +73,81 @@ pub const DEFAULT_MAX_SNAPSHOT_CONTRACTS: usize = 200_000;
 /// reaching this one.
 pub const DEFAULT_MAX_CACHED_SNAPSHOT_CONTRACTS: usize = 4_000_000;
 
+/// Default ceiling on the retention a client may request for one simulation,
+/// in seconds — seven days.
+///
+/// Long enough for a reference simulation that CI compares against between
+/// weekly runs; anything meant to outlive that should be pinned instead, which
+/// is counted against a quota rather than left to accumulate.
+pub const DEFAULT_MAX_RETENTION_SECS: u64 = 7 * 24 * 3_600;
+
+/// Default number of simulations that may be pinned at once.
+///
+/// A pinned simulation is never reaped, so the quota is the only thing bounding
+/// what pinning can hold on to. Small on purpose: a pin is for a handful of
+/// reference runs, not a way to opt out of retention wholesale.
+pub const DEFAULT_MAX_PINNED: usize = 32;
+
+/// The largest pinned quota that can be configured.
+const MAX_PINNED_CEILING: usize = 10_000;
+
+/// Default number of server-driven playbacks one process may run at once.
+///
+/// Every playing simulation prices a snapshot on each tick, so this bounds the
+/// background CPU playback can take. The default admits a GUI and a handful of
+/// bots per replica; a deployment replaying a fleet raises it deliberately.
+pub const DEFAULT_MAX_PLAYBACKS: usize = 64;
+
+/// The largest playback limit that can be configured.
+const MAX_PLAYBACKS_CEILING: usize = 10_000;
+
+/// Default number of background materializations one process may run at once.
+///
+/// A materialization prices a whole tape as fast as the warehouse takes it, so
+/// each one is a core and a steady stream of inserts. A couple at a time fills
+/// a warehouse without starving the requests the service is there to serve.
+pub const DEFAULT_MAX_MATERIALIZATIONS: usize = 2;
+
+/// The largest materialization limit that can be configured.
+const MAX_MATERIALIZATIONS_CEILING: usize = 256;
+
+/// Default number of webhook registrations one process may hold.
+///
+/// Every lifecycle event is matched against each of them, and every match is a
+/// delivery with retries, so the registry is bounded like anything else a
+/// client can grow.
+pub const DEFAULT_MAX_WEBHOOKS: usize = 1_000;
+
+/// The largest webhook registry that can be configured.
+const MAX_WEBHOOKS_CEILING: usize = 100_000;
+
+/// Default number of attempts one webhook delivery gets, the first included.
+///
+/// With the doubling backoff that is half a minute of retrying, which rides out
+/// a receiver's restart without queueing for a receiver that is gone.
+pub const DEFAULT_WEBHOOK_MAX_ATTEMPTS: usize = 6;
+
+/// The most attempts a delivery can be configured to get.
+const MAX_WEBHOOK_ATTEMPTS_CEILING: usize = 20;
+
+/// Default time one webhook attempt may take, in seconds.
+pub const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// The longest webhook attempt that can be configured, in seconds.
+const MAX_WEBHOOK_TIMEOUT_SECS: u64 = 120;
+
+/// Default time a stored `Idempotency-Key` response is replayed for, in
+/// seconds — one day.
+///
+/// Long enough to cover a client's whole retry budget, including one that
+/// backs off for hours across an outage; short enough that the stored
+/// responses stay a small fraction of what the simulations themselves take.
+pub const DEFAULT_IDEMPOTENCY_WINDOW_SECS: u64 = 24 * 3_600;
+
+/// The longest idempotency window that can be configured, in seconds — seven
+/// days.
+const MAX_IDEMPOTENCY_WINDOW_SECS: u64 = 7 * 24 * 3_600;
+
 /// The longest retention window that can be configured, in seconds — thirty
 /// days.
 ///
@@ -109,6 +185,60 @@ pub const DEFAULT_MAX_EXPORT_ROWS: usize = 100_000;
 /// The largest export range that can be configured.
 const MAX_EXPORT_ROWS_CEILING: usize = 10_000_000;
 
+/// What the service does with a simulation created under a different engine.
+///
+/// The engine version names the tape generation and the locked versions of the
+/// crates that price a tape, so a mismatch means the running binary may serve
+/// numbers the simulation's creator never saw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EngineMismatchPolicy {
+    /// Serve it, logging the mismatch on every load. The default: a
+    /// dependency bump must not turn into an outage.
+    #[default]
+    Warn,
+    /// Refuse to price it — no snapshot, export or digest — with a `400`
+    /// naming `engine_version`. Reading, deleting and taking its manifest
+    /// still work, so it can be recreated under the running engine.
+    Refuse,
+}
+
+impl EngineMismatchPolicy {
+    /// The name the policy is configured by.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Refuse => "refuse",
+        }
+    }
+}
+
+/// Where v2 simulations are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SimulationStoreBackend {
+    /// Redis, beside the v1 sessions. The default: fast, and already required.
+    #[default]
+    Redis,
+    /// MongoDB, for deployments that want simulations to survive a Redis
+    /// flush or eviction.
+    MongoDb,
+    /// Files under `OCS_FILE_STORE_DIR`, for a single node. v1 sessions move
+    /// there too, so the service needs no Redis at all.
+    File,
+}
+
+impl SimulationStoreBackend {
+    /// The name the backend is configured by.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Redis => "redis",
+            Self::MongoDb => "mongodb",
+            Self::File => "file",
+        }
+    }
+}
+
 /// Operational limits for the v2 rolling-simulation surface.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SimulationV2Config {
@@ -118,6 +248,27 @@ pub struct SimulationV2Config {
     /// nothing (ADR 0001 §6), so a client that only peeks does not refresh the
     /// window. Both stores behave the same way.
     pub retention: Duration,
+    /// The longest retention a client may request for one simulation at
+    /// creation. Bounds the per-simulation override only; [`Self::retention`]
+    /// is the operator's own choice and is not held to it.
+    pub max_retention: Duration,
+    /// How many simulations may be pinned — exempt from expiry — at once.
+    pub max_pinned: usize,
+    /// How many server-driven playbacks this process may run at once.
+    pub max_playbacks: usize,
+    /// How many background materializations this process may run at once.
+    pub max_materializations: usize,
+    /// How many webhooks may be registered at once.
+    pub max_webhooks: usize,
+    /// How many attempts a webhook delivery gets before it is given up on.
+    pub webhook_max_attempts: usize,
+    /// How long one webhook attempt may take.
+    pub webhook_timeout: Duration,
+    /// Whether a webhook may deliver to a loopback, private or link-local
+    /// address. Off by default: registration is open to any caller.
+    pub webhook_allow_private_targets: bool,
+    /// How long the first response to an `Idempotency-Key` is replayed for.
+    pub idempotency_window: Duration,
     /// How often the cleanup pass runs.
     pub cleanup_interval: Duration,
     /// How many factor tapes stay resident.
@@ -130,18 +281,33 @@ pub struct SimulationV2Config {
     pub max_cached_snapshot_contracts: usize,
     /// How many steps one export request may cover.
     pub max_export_rows: usize,
+    /// Whether a simulation created under a different engine is still served.
+    pub engine_mismatch: EngineMismatchPolicy,
+    /// Which backend stores the simulations.
+    pub store: SimulationStoreBackend,
 }
 
 impl Default for SimulationV2Config {
     fn default() -> Self {
         Self {
             retention: Duration::from_secs(DEFAULT_RETENTION_SECS),
+            max_retention: Duration::from_secs(DEFAULT_MAX_RETENTION_SECS),
+            max_pinned: DEFAULT_MAX_PINNED,
+            max_playbacks: DEFAULT_MAX_PLAYBACKS,
+            max_materializations: DEFAULT_MAX_MATERIALIZATIONS,
+            max_webhooks: DEFAULT_MAX_WEBHOOKS,
+            webhook_max_attempts: DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+            webhook_timeout: Duration::from_secs(DEFAULT_WEBHOOK_TIMEOUT_SECS),
+            webhook_allow_private_targets: false,
+            idempotency_window: Duration::from_secs(DEFAULT_IDEMPOTENCY_WINDOW_SECS),
             cleanup_interval: Duration::from_secs(DEFAULT_CLEANUP_INTERVAL_SECS),
             max_cached_tapes: DEFAULT_MAX_CACHED_TAPES,
             max_cached_snapshots: DEFAULT_MAX_CACHED_SNAPSHOTS,
             max_snapshot_contracts: DEFAULT_MAX_SNAPSHOT_CONTRACTS,
             max_cached_snapshot_contracts: DEFAULT_MAX_CACHED_SNAPSHOT_CONTRACTS,
             max_export_rows: DEFAULT_MAX_EXPORT_ROWS,
+            engine_mismatch: EngineMismatchPolicy::default(),
+            store: SimulationStoreBackend::default(),
         }
     }
 }
@@ -164,6 +330,59 @@ impl SimulationV2Config {
                 DEFAULT_RETENTION_SECS,
                 MAX_RETENTION_SECS,
             )?),
+            max_retention: Duration::from_secs(parse_secs(
+                "OCS_V2_MAX_RETENTION_SECS",
+                read("OCS_V2_MAX_RETENTION_SECS").as_deref(),
+                DEFAULT_MAX_RETENTION_SECS,
+                MAX_RETENTION_SECS,
+            )?),
+            max_pinned: parse_bounded(
+                "OCS_V2_MAX_PINNED",
+                read("OCS_V2_MAX_PINNED").as_deref(),
+                DEFAULT_MAX_PINNED,
+                MAX_PINNED_CEILING,
+            )?,
+            max_playbacks: parse_bounded(
+                "OCS_V2_MAX_PLAYBACKS",
+                read("OCS_V2_MAX_PLAYBACKS").as_deref(),
+                DEFAULT_MAX_PLAYBACKS,
+                MAX_PLAYBACKS_CEILING,
+            )?,
+            max_materializations: parse_bounded(
+                "OCS_V2_MAX_MATERIALIZATIONS",
+                read("OCS_V2_MAX_MATERIALIZATIONS").as_deref(),
+                DEFAULT_MAX_MATERIALIZATIONS,
+                MAX_MATERIALIZATIONS_CEILING,
+            )?,
+            max_webhooks: parse_bounded(
+                "OCS_V2_MAX_WEBHOOKS",
+                read("OCS_V2_MAX_WEBHOOKS").as_deref(),
+                DEFAULT_MAX_WEBHOOKS,
+                MAX_WEBHOOKS_CEILING,
+            )?,
+            webhook_max_attempts: parse_bounded(
+                "OCS_V2_WEBHOOK_MAX_ATTEMPTS",
+                read("OCS_V2_WEBHOOK_MAX_ATTEMPTS").as_deref(),
+                DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+                MAX_WEBHOOK_ATTEMPTS_CEILING,
+            )?,
+            webhook_timeout: Duration::from_secs(parse_secs(
+                "OCS_V2_WEBHOOK_TIMEOUT_SECS",
+                read("OCS_V2_WEBHOOK_TIMEOUT_SECS").as_deref(),
+                DEFAULT_WEBHOOK_TIMEOUT_SECS,
+                MAX_WEBHOOK_TIMEOUT_SECS,
+            )?),
+            webhook_allow_private_targets: parse_bool(
+                "OCS_V2_WEBHOOK_ALLOW_PRIVATE_TARGETS",
+                read("OCS_V2_WEBHOOK_ALLOW_PRIVATE_TARGETS").as_deref(),
+                false,
+            )?,
+            idempotency_window: Duration::from_secs(parse_secs(
+                "OCS_V2_IDEMPOTENCY_WINDOW_SECS",
+                read("OCS_V2_IDEMPOTENCY_WINDOW_SECS").as_deref(),
+                DEFAULT_IDEMPOTENCY_WINDOW_SECS,
+                MAX_IDEMPOTENCY_WINDOW_SECS,
+            )?),
             cleanup_interval: Duration::from_secs(parse_secs(
                 "OCS_V2_CLEANUP_INTERVAL_SECS",
                 read("OCS_V2_CLEANUP_INTERVAL_SECS").as_deref(),
@@ -198,22 +417,39 @@ impl SimulationV2Config {
                 DEFAULT_MAX_EXPORT_ROWS,
                 MAX_EXPORT_ROWS_CEILING,
             )?,
+            engine_mismatch: parse_policy(
+                "OCS_V2_ENGINE_MISMATCH",
+                read("OCS_V2_ENGINE_MISMATCH").as_deref(),
+            )?,
+            store: parse_backend("OCS_V2_STORE", read("OCS_V2_STORE").as_deref())?,
         };
 
         info!(
             retention_secs = config.retention.as_secs(),
+            max_retention_secs = config.max_retention.as_secs(),
+            max_pinned = config.max_pinned,
+            max_playbacks = config.max_playbacks,
+            max_materializations = config.max_materializations,
+            max_webhooks = config.max_webhooks,
+            webhook_max_attempts = config.webhook_max_attempts,
+            webhook_timeout_secs = config.webhook_timeout.as_secs(),
+            webhook_allow_private_targets = config.webhook_allow_private_targets,
+            idempotency_window_secs = config.idempotency_window.as_secs(),
             cleanup_interval_secs = config.cleanup_interval.as_secs(),
             max_cached_tapes = config.max_cached_tapes,
             max_cached_snapshots = config.max_cached_snapshots,
             max_snapshot_contracts = config.max_snapshot_contracts,
             max_cached_snapshot_contracts = config.max_cached_snapshot_contracts,
             max_export_rows = config.max_export_rows,
+            engine_mismatch = config.engine_mismatch.as_str(),
+            store = config.store.as_str(),
             "Loaded the v2 simulation configuration"
         );
         // Publish the parsed cap for the validator, which has no config handle.
         // A second call is a no-op: the first configuration a process loads is
         // the one it runs with.
         let _ = SNAPSHOT_CONTRACT_CAP.set(config.max_snapshot_contracts);
+        let _ = RETENTION_CAP.set(config.max_retention.as_secs());
 
         Ok(config)
     }
@@ -292,6 +528,44 @@ fn parse_bounded(
     Ok(value)
 }
 
+/// Parses the engine-mismatch policy: `warn` or `refuse`, in any case.
+///
+/// Takes the raw value for the same reason as [`parse_secs`].
+fn parse_policy(variable: &str, raw: Option<&str>) -> Result<EngineMismatchPolicy, ChainError> {
+    let Some(raw) = raw else {
+        return Ok(EngineMismatchPolicy::default());
+    };
+
+    match raw.to_ascii_lowercase().as_str() {
+        "warn" => Ok(EngineMismatchPolicy::Warn),
+        "refuse" => Ok(EngineMismatchPolicy::Refuse),
+        _ => Err(ChainError::Validation {
+            field: variable.to_string(),
+            reason: format!("must be warn or refuse, got {raw:?}"),
+        }),
+    }
+}
+
+/// Parses the simulation store backend: `redis`, `mongodb` or `file`, in any
+/// case.
+///
+/// Takes the raw value for the same reason as [`parse_secs`].
+fn parse_backend(variable: &str, raw: Option<&str>) -> Result<SimulationStoreBackend, ChainError> {
+    let Some(raw) = raw else {
+        return Ok(SimulationStoreBackend::default());
+    };
+
+    match raw.to_ascii_lowercase().as_str() {
+        "redis" => Ok(SimulationStoreBackend::Redis),
+        "mongodb" => Ok(SimulationStoreBackend::MongoDb),
+        "file" => Ok(SimulationStoreBackend::File),
+        _ => Err(ChainError::Validation {
+            field: variable.to_string(),
+            reason: format!("must be redis, mongodb or file, got {raw:?}"),
+        }),
+    }
+}
+
 /// Reads a variable, treating an empty or whitespace-only value as unset.
 ///
 /// A blank value in a `.env` file is how a knob gets "commented out" in
@@ -337,6 +611,19 @@ pub fn max_snapshot_contracts() -> usize {
         .unwrap_or(&DEFAULT_MAX_SNAPSHOT_CONTRACTS)
 }
 
+/// The per-simulation retention ceiling the running service applies.
+///
+/// Published by [`SimulationV2Config::from_env`] for the same reason as
+/// [`SNAPSHOT_CONTRACT_CAP`]: the creation options are validated by a
+/// conversion with no config handle.
+static RETENTION_CAP: OnceLock<u64> = OnceLock::new();
+
+/// The longest retention a client may request, in seconds.
+#[must_use]
+pub fn max_retention_secs() -> u64 {
+    *RETENTION_CAP.get().unwrap_or(&DEFAULT_MAX_RETENTION_SECS)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,6 +641,31 @@ mod tests {
         assert_eq!(config.max_cached_tapes, DEFAULT_MAX_CACHED_TAPES);
         assert_eq!(config.max_cached_snapshots, DEFAULT_MAX_CACHED_SNAPSHOTS);
         assert_eq!(config.retention_secs(), DEFAULT_RETENTION_SECS);
+        assert_eq!(config.max_retention.as_secs(), DEFAULT_MAX_RETENTION_SECS);
+        assert_eq!(config.max_pinned, DEFAULT_MAX_PINNED);
+        assert_eq!(config.max_playbacks, DEFAULT_MAX_PLAYBACKS);
+        assert_eq!(config.max_materializations, DEFAULT_MAX_MATERIALIZATIONS);
+        assert_eq!(config.max_webhooks, DEFAULT_MAX_WEBHOOKS);
+        assert_eq!(config.webhook_max_attempts, DEFAULT_WEBHOOK_MAX_ATTEMPTS);
+        assert_eq!(
+            config.webhook_timeout,
+            Duration::from_secs(DEFAULT_WEBHOOK_TIMEOUT_SECS)
+        );
+        assert!(!config.webhook_allow_private_targets);
+        assert_eq!(
+            config.idempotency_window,
+            Duration::from_secs(DEFAULT_IDEMPOTENCY_WINDOW_SECS)
+        );
+    }
+
+    /// The default per-simulation ceiling admits the default window, so a
+    /// client echoing the service's own retention back is never refused.
+    #[test]
+    fn test_the_default_retention_ceiling_admits_the_default_window() {
+        const {
+            assert!(DEFAULT_MAX_RETENTION_SECS >= DEFAULT_RETENTION_SECS);
+            assert!(DEFAULT_MAX_RETENTION_SECS <= MAX_RETENTION_SECS);
+        }
     }
 
     /// The v2 retention outlasts v1's thirty minutes, which is the whole reason
@@ -474,6 +786,53 @@ mod tests {
         }
     }
 
+    /// The engine-mismatch policy defaults to a warning, accepts both names,
+    /// and fails by name on anything else.
+    #[test]
+    fn test_the_engine_mismatch_policy_parses() {
+        for (raw, expected) in [
+            (None, EngineMismatchPolicy::Warn),
+            (Some("warn"), EngineMismatchPolicy::Warn),
+            (Some("Refuse"), EngineMismatchPolicy::Refuse),
+        ] {
+            match parse_policy("OCS_V2_ENGINE_MISMATCH", raw) {
+                Ok(policy) => assert_eq!(policy, expected),
+                Err(error) => panic!("{raw:?} must parse: {error}"),
+            }
+        }
+        match parse_policy("OCS_V2_ENGINE_MISMATCH", Some("ignore")) {
+            Err(ChainError::Validation { field, reason }) => {
+                assert_eq!(field, "OCS_V2_ENGINE_MISMATCH");
+                assert!(reason.contains("warn or refuse"), "{reason}");
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    /// The store backend defaults to Redis, accepts every name, and fails by
+    /// name on anything else.
+    #[test]
+    fn test_the_store_backend_parses() {
+        for (raw, expected) in [
+            (None, SimulationStoreBackend::Redis),
+            (Some("redis"), SimulationStoreBackend::Redis),
+            (Some("MongoDB"), SimulationStoreBackend::MongoDb),
+            (Some("file"), SimulationStoreBackend::File),
+        ] {
+            match parse_backend("OCS_V2_STORE", raw) {
+                Ok(backend) => assert_eq!(backend, expected),
+                Err(error) => panic!("{raw:?} must parse: {error}"),
+            }
+        }
+        match parse_backend("OCS_V2_STORE", Some("postgres")) {
+            Err(ChainError::Validation { field, reason }) => {
+                assert_eq!(field, "OCS_V2_STORE");
+                assert!(reason.contains("redis, mongodb or file"), "{reason}");
+            }
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
     /// Both backends apply the same default retention.
     ///
     /// ADR 0001 §9.1 requires the in-memory and Redis stores to agree on
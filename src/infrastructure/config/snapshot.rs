@@ -198,7 +198,11 @@ impl SnapshotPersistenceConfig {
 /// either case. Anything else fails rather than being read as `false`: a
 /// misspelled `OCS_SNAPSHOT_PERSISTENCE_ENABLED=treu` that silently disabled
 /// persistence would be discovered days later, by its absence.
-fn parse_bool(variable: &str, raw: Option<&str>, default: bool) -> Result<bool, ChainError> {
+pub(super) fn parse_bool(
+    variable: &str,
+    raw: Option<&str>,
+    default: bool,
+) -> Result<bool, ChainError> {
     let Some(raw) = raw else {
         return Ok(default);
     };
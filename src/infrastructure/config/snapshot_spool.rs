@@ -0,0 +1,132 @@
+//! Configuration for the local spool in front of the snapshot warehouse.
+//!
+//! The spool is off unless `OCS_SNAPSHOT_SPOOL_DIR` names a directory, and it
+//! only matters when snapshot persistence is on: it catches the records the
+//! in-memory queue had no room for, which without it are dropped.
+
+use crate::utils::ChainError;
+use std::env;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Default cap on the spool's size on disk, in mebibytes.
+///
+/// A gigabyte holds thousands of snapshots in the reference configuration —
+/// hours of a busy deployment's advances — which covers a maintenance window
+/// without letting an outage fill the disk the service logs to.
+pub const DEFAULT_SNAPSHOT_SPOOL_MAX_MB: u64 = 1_024;
+
+/// The largest cap that can be configured, in mebibytes — one tebibyte.
+const MAX_SPOOL_MB: u64 = 1_048_576;
+
+/// Where overflowing snapshots are spooled, and how much of the disk they may
+/// take.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotSpoolConfig {
+    /// The spool's directory; `None` leaves the spool off.
+    pub dir: Option<PathBuf>,
+    /// The most bytes the spool may occupy.
+    pub max_bytes: u64,
+}
+
+impl Default for SnapshotSpoolConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_bytes: DEFAULT_SNAPSHOT_SPOOL_MAX_MB * 1024 * 1024,
+        }
+    }
+}
+
+impl SnapshotSpoolConfig {
+    /// Reads the configuration from the environment.
+    ///
+    /// An unset or blank variable takes its default; a set-but-invalid cap
+    /// fails startup, as every bound in this service does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `OCS_SNAPSHOT_SPOOL_MAX_MB`
+    /// when its value does not parse, is zero, or exceeds one tebibyte.
+    pub fn from_env() -> Result<Self, ChainError> {
+        let config = Self {
+            dir: read("OCS_SNAPSHOT_SPOOL_DIR").map(PathBuf::from),
+            max_bytes: parse_max_mb(
+                "OCS_SNAPSHOT_SPOOL_MAX_MB",
+                read("OCS_SNAPSHOT_SPOOL_MAX_MB").as_deref(),
+            )? * 1024
+                * 1024,
+        };
+
+        info!(
+            dir = config.dir.as_ref().map(|dir| dir.display().to_string()),
+            max_bytes = config.max_bytes,
+            "Loaded the snapshot spool configuration"
+        );
+        Ok(config)
+    }
+}
+
+/// Parses the cap in mebibytes.
+///
+/// Takes the raw value rather than reading it, so it can be tested without
+/// mutating the process environment.
+fn parse_max_mb(variable: &str, raw: Option<&str>) -> Result<u64, ChainError> {
+    let Some(raw) = raw else {
+        return Ok(DEFAULT_SNAPSHOT_SPOOL_MAX_MB);
+    };
+
+    match raw.parse::<u64>() {
+        Ok(value) if (1..=MAX_SPOOL_MB).contains(&value) => Ok(value),
+        _ => Err(ChainError::Validation {
+            field: variable.to_string(),
+            reason: format!("must be a whole number from 1 to {MAX_SPOOL_MB}, got {raw:?}"),
+        }),
+    }
+}
+
+/// Reads a variable, treating an empty or whitespace-only value as unset.
+fn read(variable: &str) -> Option<String> {
+    let raw = env::var(variable).ok()?;
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The cap defaults when unset, is taken as given within its bounds, and
+    /// fails by name when zero, too large or not a number.
+    #[test]
+    fn test_the_cap_parses_within_its_bounds() {
+        for (raw, expected) in [
+            (None, DEFAULT_SNAPSHOT_SPOOL_MAX_MB),
+            (Some("1"), 1),
+            (Some("1048576"), MAX_SPOOL_MB),
+        ] {
+            match parse_max_mb("OCS_SNAPSHOT_SPOOL_MAX_MB", raw) {
+                Ok(value) => assert_eq!(value, expected),
+                Err(error) => panic!("{raw:?} must parse: {error}"),
+            }
+        }
+        for raw in ["0", "1048577", "lots"] {
+            match parse_max_mb("OCS_SNAPSHOT_SPOOL_MAX_MB", Some(raw)) {
+                Err(ChainError::Validation { field, .. }) => {
+                    assert_eq!(field, "OCS_SNAPSHOT_SPOOL_MAX_MB");
+                }
+                other => panic!("{raw:?} must be refused, got {other:?}"),
+            }
+        }
+    }
+
+    /// The spool is off by default.
+    #[test]
+    fn test_the_spool_is_off_by_default() {
+        assert_eq!(SnapshotSpoolConfig::default().dir, None);
+    }
+}
@@ -93,6 +93,18 @@ impl MongoDBClient {
         db.collection(&config.events_collection)
     }
 
+    /// Gets a reference to a named collection in the configured database.
+    ///
+    /// For stores that own their collection, such as the v2 simulation store,
+    /// rather than appending to the steps or events collections.
+    pub async fn collection<T>(&self, name: &str) -> mongodb::Collection<T>
+    where
+        T: Sync + Send + Serialize + DeserializeOwned,
+    {
+        let db = self.db.lock().await;
+        db.collection(name)
+    }
+
     /// Saves a simulation step to the steps collection
     #[instrument(skip(self, step), level = "debug")]
     pub async fn save_step<T>(&self, session_id: Uuid, step: T) -> Result<(), ChainError>
@@ -0,0 +1,239 @@
+//! Where the v1 handlers record session events and chain steps.
+//!
+//! The handlers log a failed record and carry on, so a sink is an audit trail
+//! the service never reads back. That is what lets it be pluggable: MongoDB
+//! ([`crate::infrastructure::MongoDBRepository`]), an append-only NDJSON file
+//! ([`NdjsonEventSink`]), or nothing at all ([`NoopEventSink`]).
+
+use crate::api::rest::responses::{ChainResponse, SessionResponse};
+use crate::infrastructure::MetricsCollector;
+use crate::infrastructure::config::event_sink::{EventSinkConfig, EventSinkKind};
+use crate::infrastructure::repositories::mongo_repo::init_mongodb;
+use crate::utils::ChainError;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, info, instrument};
+use uuid::Uuid;
+
+/// A destination for v1 session events and chain steps.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Records a chain step served to a session.
+    async fn save_chain_step(
+        &self,
+        session_id: Uuid,
+        chain_data: ChainResponse,
+        metrics: Arc<MetricsCollector>,
+    ) -> Result<(), ChainError>;
+
+    /// Records a session's state after a lifecycle change.
+    async fn save_session_event(
+        &self,
+        session_id: Uuid,
+        session_data: SessionResponse,
+        metrics: Arc<MetricsCollector>,
+    ) -> Result<(), ChainError>;
+}
+
+/// Builds the sink the configuration selects.
+///
+/// # Errors
+///
+/// Returns the MongoDB connection error for the MongoDB sink, and
+/// [`ChainError::Internal`] when the NDJSON file cannot be opened.
+pub async fn init_event_sink(config: &EventSinkConfig) -> Result<Arc<dyn EventSink>, ChainError> {
+    let sink: Arc<dyn EventSink> = match config.kind {
+        EventSinkKind::MongoDb => init_mongodb().await?,
+        EventSinkKind::Ndjson => Arc::new(NdjsonEventSink::open(&config.path).await?),
+        EventSinkKind::Noop => Arc::new(NoopEventSink),
+    };
+    info!(kind = config.kind.as_str(), "Event sink initialized");
+    Ok(sink)
+}
+
+/// One line of the NDJSON trail.
+#[derive(Serialize)]
+struct EventLine<'a, T> {
+    /// The MongoDB collection the record would have gone to: `steps` or
+    /// `events`, so one file can be split or loaded back the same way.
+    collection: &'a str,
+    session_id: Uuid,
+    /// When the record was written, RFC 3339 UTC.
+    recorded_at: String,
+    document: T,
+}
+
+/// Appends each record as one JSON line to a local file.
+///
+/// The file is opened in append mode and every line goes out in one write
+/// under a lock, so concurrent handlers never interleave within a line and an
+/// existing trail is extended across restarts, never truncated.
+pub struct NdjsonEventSink {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl NdjsonEventSink {
+    /// Opens `path` for appending, creating it and its parent directories.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] naming the path when it cannot be
+    /// opened.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, ChainError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error(&path, &e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(|e| io_error(&path, &e))?;
+
+        info!(path = %path.display(), "Opened NDJSON event sink");
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends one record.
+    async fn append<T: Serialize>(
+        &self,
+        collection: &str,
+        session_id: Uuid,
+        document: T,
+    ) -> Result<(), ChainError> {
+        let mut line = serde_json::to_vec(&EventLine {
+            collection,
+            session_id,
+            recorded_at: Utc::now().to_rfc3339(),
+            document,
+        })
+        .map_err(|e| ChainError::Internal(format!("Failed to serialize event: {e}")))?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().await;
+        file.write_all(&line)
+            .await
+            .map_err(|e| io_error(&self.path, &e))?;
+        file.flush().await.map_err(|e| io_error(&self.path, &e))
+    }
+}
+
+#[async_trait]
+impl EventSink for NdjsonEventSink {
+    #[instrument(skip(self, chain_data, _metrics), level = "debug")]
+    async fn save_chain_step(
+        &self,
+        session_id: Uuid,
+        chain_data: ChainResponse,
+        _metrics: Arc<MetricsCollector>,
+    ) -> Result<(), ChainError> {
+        self.append("steps", session_id, chain_data).await
+    }
+
+    #[instrument(skip(self, session_data, _metrics), level = "debug")]
+    async fn save_session_event(
+        &self,
+        session_id: Uuid,
+        session_data: SessionResponse,
+        _metrics: Arc<MetricsCollector>,
+    ) -> Result<(), ChainError> {
+        self.append("events", session_id, session_data).await
+    }
+}
+
+/// Records nothing.
+pub struct NoopEventSink;
+
+#[async_trait]
+impl EventSink for NoopEventSink {
+    async fn save_chain_step(
+        &self,
+        session_id: Uuid,
+        _chain_data: ChainResponse,
+        _metrics: Arc<MetricsCollector>,
+    ) -> Result<(), ChainError> {
+        debug!(session_id = %session_id, "Discarding chain step");
+        Ok(())
+    }
+
+    async fn save_session_event(
+        &self,
+        session_id: Uuid,
+        _session_data: SessionResponse,
+        _metrics: Arc<MetricsCollector>,
+    ) -> Result<(), ChainError> {
+        debug!(session_id = %session_id, "Discarding session event");
+        Ok(())
+    }
+}
+
+/// The error for a failed file operation, naming the path.
+#[cold]
+fn io_error(path: &Path, error: &std::io::Error) -> ChainError {
+    ChainError::Internal(format!("Event sink error at {}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    /// Records are appended one per line, tagged with their collection and
+    /// session, and a reopened sink extends the trail instead of truncating it.
+    #[tokio::test]
+    async fn test_the_ndjson_sink_appends_one_line_per_record() {
+        let root = match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        };
+        let path = root.path().join("trail").join("events.ndjson");
+        let session_id = Uuid::new_v4();
+
+        for (collection, document) in [
+            ("events", json!({"state": "Initialized"})),
+            ("steps", json!({"step": 1})),
+        ] {
+            let sink = match NdjsonEventSink::open(&path).await {
+                Ok(sink) => sink,
+                Err(error) => panic!("the sink must open: {error}"),
+            };
+            if let Err(error) = sink.append(collection, session_id, document).await {
+                panic!("the append must succeed: {error}");
+            }
+        }
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) => panic!("the trail must be readable: {error}"),
+        };
+        let lines: Vec<Value> = contents
+            .lines()
+            .map(|line| match serde_json::from_str(line) {
+                Ok(value) => value,
+                Err(error) => panic!("every line must be JSON: {error}"),
+            })
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["collection"], "events");
+        assert_eq!(lines[0]["session_id"], session_id.to_string());
+        assert_eq!(lines[0]["document"]["state"], "Initialized");
+        assert_eq!(lines[1]["collection"], "steps");
+        assert_eq!(lines[1]["document"]["step"], 1);
+    }
+}
@@ -1,3 +1,5 @@
+pub(crate) mod event_sink;
 pub(crate) mod historical_repo;
 pub(crate) mod mongo_repo;
 pub(crate) mod snapshot_repo;
+pub(crate) mod snapshot_spool;
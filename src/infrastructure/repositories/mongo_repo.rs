@@ -1,3 +1,4 @@
+use async_trait::async_trait;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{info, instrument};
@@ -7,6 +8,7 @@ use crate::api::rest::responses::{ChainResponse, SessionResponse};
 use crate::infrastructure::MetricsCollector;
 use crate::infrastructure::config::mongo::MongoDBConfig;
 use crate::infrastructure::mongodb::MongoDBClient;
+use crate::infrastructure::repositories::event_sink::EventSink;
 use crate::utils::ChainError;
 
 /// Repository for managing session history in MongoDB (insert-only operations)
@@ -21,9 +23,22 @@ impl MongoDBRepository {
         Self { client }
     }
 
+    /// Saves a generic event to the events collection
+    #[instrument(skip(self, event), level = "debug")]
+    pub async fn save_generic_event<T>(&self, session_id: Uuid, event: T) -> Result<(), ChainError>
+    where
+        T: Sync + Send + serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug,
+    {
+        info!(session_id = %session_id, "Saving generic event to MongoDB");
+        self.client.save_event(session_id, event).await
+    }
+}
+
+#[async_trait]
+impl EventSink for MongoDBRepository {
     /// Saves a chain response to the steps collection
     #[instrument(skip(self, chain_data, metrics), level = "debug")]
-    pub async fn save_chain_step(
+    async fn save_chain_step(
         &self,
         session_id: Uuid,
         chain_data: ChainResponse,
@@ -44,7 +59,7 @@ impl MongoDBRepository {
 
     /// Saves a session response to the events collection
     #[instrument(skip(self, session_data, metrics), level = "debug")]
-    pub async fn save_session_event(
+    async fn save_session_event(
         &self,
         session_id: Uuid,
         session_data: SessionResponse,
@@ -62,16 +77,6 @@ impl MongoDBRepository {
 
         result
     }
-
-    /// Saves a generic event to the events collection
-    #[instrument(skip(self, event), level = "debug")]
-    pub async fn save_generic_event<T>(&self, session_id: Uuid, event: T) -> Result<(), ChainError>
-    where
-        T: Sync + Send + serde::Serialize + serde::de::DeserializeOwned + std::fmt::Debug,
-    {
-        info!(session_id = %session_id, "Saving generic event to MongoDB");
-        self.client.save_event(session_id, event).await
-    }
 }
 
 /// Initializes MongoDB client and repository, then returns the repository
@@ -41,7 +41,7 @@
 //! by a replay without the client ever noticing.
 
 use crate::infrastructure::clickhouse::snapshots::interface::{
-    ContractSeriesQuery, SimulationSnapshotRepository,
+    ContractSeriesQuery, SimulationSnapshotRepository, SnapshotRangeQuery,
 };
 use crate::infrastructure::clickhouse::snapshots::model::{
     ContractReadRow, DECIMAL_SCALE, OptionQuoteRow, QUOTES_TABLE, QuoteReadRow, SNAPSHOTS_TABLE,
@@ -73,6 +73,16 @@ const SNAPSHOTS_DDL: &str = include_str!("../clickhouse/schema/simulation_snapsh
 /// The DDL of the quotes table. See [`SNAPSHOTS_DDL`].
 const QUOTES_DDL: &str = include_str!("../clickhouse/schema/simulation_option_quotes.sql");
 
+/// Columns added to the snapshots table after it first shipped.
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves an existing table alone, so a table
+/// created by an older build is brought up to the current shape here. Each is
+/// idempotent; the default is what a row written before the column existed
+/// reads as.
+const SNAPSHOTS_MIGRATIONS: [&str; 1] = ["ALTER TABLE simulation_snapshots \
+        ADD COLUMN IF NOT EXISTS engine_version LowCardinality(String) DEFAULT '' CODEC(ZSTD(1)) \
+        AFTER simulation_generation"];
+
 /// The placeholder the retention knob replaces in the DDL.
 const RETENTION_PLACEHOLDER: &str = "{{RETENTION_DAYS}}";
 
@@ -88,7 +98,8 @@ const META_RANGE_QUERY: &str = "SELECT \
         symbol, \
         underlying_price, \
         base_volatility, \
-        quote_count \
+        quote_count, \
+        engine_version \
     FROM simulation_snapshots FINAL \
     WHERE simulation_id = {simulation:String} \
       AND simulation_generation = {generation:UInt64} \
@@ -131,6 +142,9 @@ const QUOTES_RANGE_QUERY: &str = "SELECT \
 /// snapshot. `uniqExact` over the identity columns counts *deduplicated* rows
 /// without paying for `FINAL`, which is what makes this affordable inside a
 /// contract history.
+///
+/// The marker also carries the engine filter: an empty `{engine}` reads every
+/// engine, anything else reads that engine's steps and the untagged ones.
 const COMPLETE_STEPS_SUBQUERY: &str = "SELECT marker.step \
     FROM ( \
         SELECT step, quote_count \
@@ -140,6 +154,8 @@ const COMPLETE_STEPS_SUBQUERY: &str = "SELECT marker.step \
           AND step >= {from_step:UInt64} \
           AND step <= {to_step:UInt64} \
           AND complete = true \
+          AND (empty({engine:String}) OR empty(engine_version) \
+               OR engine_version = {engine:String}) \
     ) AS marker \
     INNER JOIN ( \
         SELECT step, uniqExact((expires_at, strike)) AS stored \
@@ -152,6 +168,34 @@ const COMPLETE_STEPS_SUBQUERY: &str = "SELECT marker.step \
     ) AS counted ON marker.step = counted.step \
     WHERE marker.quote_count = counted.stored";
 
+/// Keeps the steps of a range on a matching read's stride.
+///
+/// A stride of one is a no-op, so a matching read always carries it rather
+/// than varying its text on the one value every unfiltered read has.
+const STRIDE_CONDITION: &str = "(step - {from_step:UInt64}) % {stride:UInt64} = 0";
+
+/// Keeps the expirations carrying any of a matching read's labels.
+const LABELS_CONDITION: &str = "hasAny(labels, {labels:Array(String)})";
+
+/// Keeps the expirations at least a matching read's minimum days out.
+const MIN_DAYS_CONDITION: &str = "toFloat64(days_to_expiration) >= {min_days:Float64}";
+
+/// Keeps the expirations at most a matching read's maximum days out.
+const MAX_DAYS_CONDITION: &str = "toFloat64(days_to_expiration) <= {max_days:Float64}";
+
+/// Deletes every row of a set of simulations, markers first.
+///
+/// Lightweight deletes rather than partition drops: a partition is a month of
+/// simulated time shared by every simulation in it, so no partition belongs to
+/// one simulation alone. The markers go first so that a purge interrupted
+/// between the two statements leaves quote rows no read path can reach, never
+/// a marker whose rows have gone — which would read as absent anyway, but
+/// would be logged as a torn write.
+const PURGE_STATEMENTS: [&str; 2] = [
+    "DELETE FROM simulation_snapshots WHERE has({simulations:Array(String)}, simulation_id)",
+    "DELETE FROM simulation_option_quotes WHERE has({simulations:Array(String)}, simulation_id)",
+];
+
 /// Builds the contract-history query for one side.
 ///
 /// The side chooses column names from a closed match on [`ContractSide`] —
@@ -196,6 +240,58 @@ fn contract_series_query(side: ContractSide, limit: usize) -> String {
     )
 }
 
+/// Adds conditions to a range query's `WHERE` clause, ahead of its `ORDER BY`.
+///
+/// The range queries are constants ending in exactly one `ORDER BY`, so the
+/// split is unambiguous; each condition is itself a constant, and every value
+/// it compares against is a named parameter.
+#[must_use]
+fn with_conditions(query: &str, conditions: &[&str]) -> String {
+    let (filtered, order) = query.rsplit_once(" ORDER BY ").unwrap_or((query, ""));
+    let mut sql = filtered.trim_end().to_string();
+    for condition in conditions {
+        sql.push_str(" AND ");
+        sql.push_str(condition);
+    }
+    if !order.is_empty() {
+        sql.push_str(" ORDER BY ");
+        sql.push_str(order);
+    }
+    sql
+}
+
+/// Builds the metadata and quotes queries of a matching read.
+///
+/// A query that drops expirations leaves every step holding fewer rows than
+/// its marker promises, so counting what was read can no longer tell a whole
+/// step from a torn one. Such a read restricts both queries to the steps
+/// [`COMPLETE_STEPS_SUBQUERY`] vouches for instead, and [`assemble`] is told
+/// the check has been made.
+#[must_use]
+fn matching_range_queries(query: &SnapshotRangeQuery) -> (String, String) {
+    let complete = format!("step IN ({COMPLETE_STEPS_SUBQUERY})");
+    let mut steps = vec![STRIDE_CONDITION];
+    if query.narrows_expirations() {
+        steps.push(&complete);
+    }
+
+    let mut quotes = steps.clone();
+    if !query.labels.is_empty() {
+        quotes.push(LABELS_CONDITION);
+    }
+    if query.min_days_to_expiration.is_some() {
+        quotes.push(MIN_DAYS_CONDITION);
+    }
+    if query.max_days_to_expiration.is_some() {
+        quotes.push(MAX_DAYS_CONDITION);
+    }
+
+    (
+        with_conditions(META_RANGE_QUERY, &steps),
+        with_conditions(QUOTES_RANGE_QUERY, &quotes),
+    )
+}
+
 /// Appends a row bound to a range query.
 ///
 /// Callers pass [`ClickHouseSnapshotRepository::probe_limit`], one more than a
@@ -299,10 +395,11 @@ impl ClickHouseSnapshotRepository {
         &self.config
     }
 
-    /// Creates the two tables if they are absent.
+    /// Creates the two tables if they are absent, and adds any column a table
+    /// from an older build is missing.
     ///
-    /// Idempotent, and safe to call at every startup. It will **not** alter a
-    /// table that already exists, so changing `OCS_SNAPSHOT_RETENTION_DAYS`
+    /// Idempotent, and safe to call at every startup. Beyond the added columns
+    /// it will **not** alter a table that already exists, so changing `OCS_SNAPSHOT_RETENTION_DAYS`
     /// against a live deployment needs an explicit
     /// `ALTER TABLE ... MODIFY TTL`; the DDL documents that.
     ///
@@ -322,6 +419,9 @@ impl ClickHouseSnapshotRepository {
             );
             self.client.client.query(&statement).execute().await?;
         }
+        for migration in SNAPSHOTS_MIGRATIONS {
+            self.client.client.query(migration).execute().await?;
+        }
 
         info!(
             retention_days = self.config.retention_days,
@@ -463,6 +563,36 @@ fn assemble(
     Ok(records)
 }
 
+/// Rebuilds the snapshots a narrowing matching read returned.
+///
+/// The twin of [`assemble`] for a read whose queries were restricted to
+/// complete steps in the warehouse: the rows are a filtered subset, so their
+/// count is not compared against the marker, and a step with no surviving row
+/// is a snapshot with no expirations rather than a torn one.
+///
+/// # Errors
+///
+/// As [`assemble`].
+fn assemble_narrowed(
+    simulation: Uuid,
+    generation: u64,
+    metas: &[SnapshotMetaReadRow],
+    quotes: Vec<QuoteReadRow>,
+) -> Result<Vec<SnapshotRecord>, ChainError> {
+    let mut by_step: BTreeMap<u64, Vec<QuoteReadRow>> = BTreeMap::new();
+    for row in quotes {
+        by_step.entry(row.step).or_default().push(row);
+    }
+
+    metas
+        .iter()
+        .map(|meta| {
+            let rows = by_step.remove(&meta.step).unwrap_or_default();
+            record_from_rows(simulation, generation, meta, &rows)
+        })
+        .collect()
+}
+
 /// Validates an inclusive step range.
 ///
 /// # Errors
@@ -628,6 +758,109 @@ impl SimulationSnapshotRepository for ClickHouseSnapshotRepository {
         Ok(records)
     }
 
+    #[instrument(skip(self), level = "debug")]
+    async fn stored_steps(
+        &self,
+        simulation: Uuid,
+        generation: u64,
+        from_step: usize,
+        to_step: usize,
+    ) -> Result<Vec<usize>, ChainError> {
+        let (from, to) = step_bounds(from_step, to_step)?;
+
+        // Every engine, as `read_range` reads them, and counted rather than
+        // read: the subquery compares each marker with its stored rows.
+        let sql = format!("{COMPLETE_STEPS_SUBQUERY} ORDER BY marker.step ASC");
+        let steps = self
+            .client
+            .client
+            .query(&sql)
+            .param("simulation", simulation.to_string())
+            .param("generation", generation)
+            .param("from_step", from)
+            .param("to_step", to)
+            .param("engine", "")
+            .fetch_all::<u64>()
+            .await?;
+
+        debug!(steps = steps.len(), "Listed the stored steps of a range");
+        steps
+            .into_iter()
+            .map(|step| {
+                usize::try_from(step).map_err(|_| {
+                    ChainError::Internal(format!("stored step {step} does not fit a usize"))
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(
+        skip(self, query),
+        fields(simulation = %query.simulation, stride = query.stride),
+        level = "debug"
+    )]
+    async fn read_matching(
+        &self,
+        query: SnapshotRangeQuery,
+    ) -> Result<Vec<SnapshotRecord>, ChainError> {
+        query.validate()?;
+        let (from, to) = step_bounds(query.from_step, query.to_step)?;
+        let stride = u64::try_from(query.stride).map_err(|_| ChainError::Validation {
+            field: "stride".to_string(),
+            reason: format!("{} does not fit a UInt64 column", query.stride),
+        })?;
+        let probe = self.probe_limit()?;
+        let (meta_sql, quotes_sql) = matching_range_queries(&query);
+
+        let metas = self
+            .client
+            .client
+            .query(&with_probe_limit(&meta_sql, probe))
+            .param("simulation", query.simulation.to_string())
+            .param("generation", query.generation)
+            .param("from_step", from)
+            .param("to_step", to)
+            .param("stride", stride)
+            // The completeness subquery reads every engine here: the export
+            // passes over another engine's step itself, and says so.
+            .param("engine", "")
+            .fetch_all::<SnapshotMetaReadRow>()
+            .await?;
+        self.reject_if_over_budget(metas.len(), "snapshots")?;
+
+        let mut quotes = self
+            .client
+            .client
+            .query(&with_probe_limit(&quotes_sql, probe))
+            .param("simulation", query.simulation.to_string())
+            .param("generation", query.generation)
+            .param("from_step", from)
+            .param("to_step", to)
+            .param("stride", stride)
+            .param("engine", "")
+            .param("labels", query.labels.clone());
+        if let Some(min) = query.min_days_to_expiration {
+            quotes = quotes.param("min_days", min);
+        }
+        if let Some(max) = query.max_days_to_expiration {
+            quotes = quotes.param("max_days", max);
+        }
+        let quotes = quotes.fetch_all::<QuoteReadRow>().await?;
+        self.reject_if_over_budget(quotes.len(), "quotes")?;
+
+        let records = if query.narrows_expirations() {
+            assemble_narrowed(query.simulation, query.generation, &metas, quotes)?
+        } else {
+            assemble(query.simulation, query.generation, &metas, quotes)?
+        };
+        debug!(
+            steps = records.len(),
+            "Read the matching part of a range of persisted snapshots"
+        );
+
+        Ok(records)
+    }
+
     #[instrument(
         skip(self, query),
         fields(simulation = %query.simulation, side = %query.side),
@@ -658,6 +891,7 @@ impl SimulationSnapshotRepository for ClickHouseSnapshotRepository {
             .param("to_step", to)
             .param("expires_at", expires_at)
             .param("strike", strike_text)
+            .param("engine", query.engine.as_deref().unwrap_or_default())
             .fetch_all::<ContractReadRow>()
             .await?;
 
@@ -671,6 +905,29 @@ impl SimulationSnapshotRepository for ClickHouseSnapshotRepository {
         debug!(points = series.len(), "Read a contract history");
         Ok(series)
     }
+
+    #[instrument(skip(self, simulations), fields(simulations = simulations.len()), level = "debug")]
+    async fn purge(&self, simulations: &[Uuid]) -> Result<(), ChainError> {
+        if simulations.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<String> = simulations.iter().map(Uuid::to_string).collect();
+
+        for statement in PURGE_STATEMENTS {
+            self.client
+                .client
+                .query(statement)
+                .param("simulations", &ids)
+                .execute()
+                .await?;
+        }
+
+        info!(
+            simulations = simulations.len(),
+            "Purged persisted snapshots"
+        );
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -731,6 +988,7 @@ mod tests {
         SnapshotRecord::new(
             simulation,
             2,
+            "tape2+test".to_string(),
             step,
             instant(5),
             "SPX".to_string(),
@@ -834,6 +1092,7 @@ mod tests {
             underlying_price: marker.underlying_price,
             base_volatility: marker.base_volatility,
             quote_count: marker.quote_count,
+            engine_version: marker.engine_version,
         };
         let quotes = quotes
             .into_iter()
@@ -1054,6 +1313,14 @@ mod tests {
         assert!(COMPLETE_STEPS_SUBQUERY.contains("complete = true"));
     }
 
+    /// The engine filter is bound, and an empty binding disables it rather than
+    /// matching only untagged rows.
+    #[test]
+    fn test_the_completeness_subquery_filters_on_a_bound_engine() {
+        assert!(COMPLETE_STEPS_SUBQUERY.contains("empty({engine:String}) OR"));
+        assert!(COMPLETE_STEPS_SUBQUERY.contains("engine_version = {engine:String}"));
+    }
+
     /// A contract history only ever reads complete steps.
     #[test]
     fn test_a_contract_history_filters_on_complete_steps() {
@@ -1081,6 +1348,77 @@ mod tests {
         assert!(put.contains("quote.gamma AS gamma"));
     }
 
+    /// A matching read adds its conditions ahead of the ordering, keeps the
+    /// stride on both queries, and filters expirations on the quotes only.
+    #[test]
+    fn test_a_matching_read_only_adds_bound_conditions() {
+        let query = SnapshotRangeQuery::new(Uuid::from_u128(5), 1, 0, 9).every(3);
+        let (meta, quotes) = matching_range_queries(&query);
+
+        for sql in [&meta, &quotes] {
+            assert!(
+                sql.contains(&format!("AND {STRIDE_CONDITION} ORDER BY")),
+                "{sql}"
+            );
+            assert!(!sql.contains("marker.step"), "{sql}");
+            assert!(!sql.contains('\''), "{sql}");
+        }
+        assert!(meta.ends_with("ORDER BY step ASC"));
+        assert!(quotes.ends_with("ORDER BY step ASC, expires_at ASC, strike ASC"));
+        assert!(!quotes.contains("hasAny"));
+    }
+
+    /// Dropping expirations moves the completeness check into the query,
+    /// because the rows read can no longer be counted against the marker.
+    #[test]
+    fn test_a_narrowing_read_checks_completeness_in_the_query() {
+        let query = SnapshotRangeQuery::new(Uuid::from_u128(5), 1, 0, 9)
+            .labelled(vec!["weeklies".to_string()])
+            .expiring_within(Some(1.0), Some(30.0));
+        let (meta, quotes) = matching_range_queries(&query);
+
+        for sql in [&meta, &quotes] {
+            assert!(sql.contains("AND step IN (SELECT marker.step"), "{sql}");
+            assert!(sql.contains("{engine:String}"), "{sql}");
+        }
+        assert!(quotes.contains(LABELS_CONDITION));
+        assert!(quotes.contains(MIN_DAYS_CONDITION));
+        assert!(quotes.contains(MAX_DAYS_CONDITION));
+        assert!(!meta.contains("hasAny"), "the markers carry no labels");
+    }
+
+    /// A narrowed read trusts the query's completeness check: a step whose
+    /// rows were all filtered out is a snapshot with no expirations.
+    #[test]
+    fn test_a_narrowed_assembly_does_not_count_rows() {
+        let simulation = Uuid::from_u128(1);
+        let (first_meta, mut quotes) = read_rows(&record(simulation, 0));
+        let (second_meta, _) = read_rows(&record(simulation, 1));
+        quotes.truncate(1);
+
+        match assemble_narrowed(simulation, 2, &[first_meta, second_meta], quotes) {
+            Ok(records) => {
+                let shape: Vec<(usize, usize)> = records
+                    .iter()
+                    .map(|record| (record.step, record.quote_count()))
+                    .collect();
+                assert_eq!(shape, vec![(0, 1), (1, 0)]);
+            }
+            Err(error) => panic!("a narrowed range must assemble: {error}"),
+        }
+    }
+
+    /// A purge deletes the markers before the quotes, and binds the ids rather
+    /// than interpolating them.
+    #[test]
+    fn test_a_purge_deletes_markers_first() {
+        assert!(PURGE_STATEMENTS[0].starts_with("DELETE FROM simulation_snapshots "));
+        assert!(PURGE_STATEMENTS[1].starts_with("DELETE FROM simulation_option_quotes "));
+        for statement in PURGE_STATEMENTS {
+            assert!(statement.contains("{simulations:Array(String)}"));
+        }
+    }
+
     /// Every value a caller controls is a named server-side parameter; none of
     /// them is ever interpolated into the SQL text.
     #[test]
@@ -1180,6 +1518,17 @@ mod tests {
         assert!(QUOTES_DDL.contains("INDEX idx_contract (expires_at, strike) TYPE minmax"));
     }
 
+    /// A table from before the engine tag gains the column the DDL now
+    /// declares, with the default a missing tag reads as.
+    #[test]
+    fn test_the_migrations_add_what_the_ddl_declares() {
+        assert!(
+            SNAPSHOTS_DDL.contains("engine_version          LowCardinality(String) DEFAULT ''")
+        );
+        assert!(SNAPSHOTS_MIGRATIONS[0].contains("ADD COLUMN IF NOT EXISTS engine_version"));
+        assert!(META_RANGE_QUERY.contains("engine_version"));
+    }
+
     /// The partition key is derived from the row's own content, not from when
     /// it was written.
     ///
@@ -1395,6 +1744,70 @@ mod tests {
         }
     }
 
+    /// The stored steps of a range are the steps a range read returns,
+    /// listed without reading them.
+    #[tokio::test]
+    #[ignore = "requires live ClickHouse on localhost:8123 (override via CLICKHOUSE_* env)"]
+    async fn test_the_stored_steps_match_a_range_read_against_live_clickhouse() {
+        let repository = live_repository();
+        let simulation = Uuid::new_v4();
+
+        match repository.ensure_schema().await {
+            Ok(()) => {}
+            Err(error) => panic!("the schema must be creatable: {error}"),
+        }
+
+        for step in [0, 2] {
+            if let Err(error) = repository.persist(record(simulation, step)).await {
+                panic!("the snapshot must persist: {error}");
+            }
+        }
+
+        let generation = record(simulation, 0).generation;
+        let listed = repository.stored_steps(simulation, generation, 0, 3).await;
+        let read = repository.read_range(simulation, generation, 0, 3).await;
+        cleanup(&repository, simulation).await;
+
+        match (listed, read) {
+            (Ok(listed), Ok(read)) => {
+                assert_eq!(listed, vec![0, 2]);
+                let read: Vec<usize> = read.iter().map(|record| record.step).collect();
+                assert_eq!(listed, read);
+            }
+            other => panic!("both reads must succeed, got {other:?}"),
+        }
+    }
+
+    /// A purged snapshot is gone from every read path as soon as the purge
+    /// returns.
+    #[tokio::test]
+    #[ignore = "requires live ClickHouse on localhost:8123 (override via CLICKHOUSE_* env)"]
+    async fn test_a_purge_removes_a_snapshot_from_live_clickhouse() {
+        let repository = live_repository();
+        let simulation = Uuid::new_v4();
+
+        match repository.ensure_schema().await {
+            Ok(()) => {}
+            Err(error) => panic!("the schema must be creatable: {error}"),
+        }
+
+        let original = record(simulation, 0);
+        if let Err(error) = repository.persist(original.clone()).await {
+            panic!("the snapshot must persist: {error}");
+        }
+        let purged = repository.purge(&[simulation]).await;
+        let read = repository.get(simulation, original.generation, 0).await;
+        cleanup(&repository, simulation).await;
+
+        if let Err(error) = purged {
+            panic!("the purge must succeed: {error}");
+        }
+        match read {
+            Ok(None) => {}
+            other => panic!("the purged snapshot must read as absent, got {other:?}"),
+        }
+    }
+
     /// Persisting the same snapshot twice is idempotent from every read path,
     /// immediately — before any background merge has had a chance to run.
     #[tokio::test]
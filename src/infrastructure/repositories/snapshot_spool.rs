@@ -0,0 +1,757 @@
+//! A local write-ahead spool for snapshots the warehouse queue had no room for.
+//!
+//! The manager files served snapshots through a bounded in-memory queue, and a
+//! full queue used to mean a dropped step. With a spool configured, the record
+//! that does not fit is appended here instead, and the writer drains it back
+//! into the warehouse once the queue is empty again — so a ClickHouse
+//! maintenance window costs latency on the tape, not rows.
+//!
+//! # Layout
+//!
+//! A directory of numbered segments, `spool-<seq>.ndjson`, one JSON record per
+//! line. Appends go to the newest segment, which is sealed and flushed to disk
+//! once it reaches [`SEGMENT_BYTES`]; reads start at the oldest. A segment is
+//! deleted as soon as its last record is acknowledged, so the directory holds
+//! only what the warehouse has not confirmed.
+//!
+//! # Delivery
+//!
+//! At least once. The read position lives in memory, so after a restart the
+//! oldest segment is replayed from its start, and a record the warehouse had
+//! already accepted is written again. That is harmless: a snapshot's identity
+//! is its coordinate, and the warehouse collapses a rewrite into the row it
+//! replaces.
+//!
+//! Every segment that exists when the spool opens is sealed — appends after a
+//! restart start a fresh one — so a line torn by a crash can only be the last
+//! line of a sealed segment. It is skipped with a warning, as is a line that
+//! does not parse.
+//!
+//! # Why not `Positive`'s own serde
+//!
+//! `Positive` serializes through `f64`, which would hand the warehouse a
+//! different premium than the one served. Every decimal is spooled as its exact
+//! text instead, which is the same promise the `Decimal(38, 28)` columns make.
+
+use crate::infrastructure::{ExpirationRecord, QuoteRow, SnapshotRecord};
+use crate::utils::ChainError;
+use chrono::{DateTime, Utc};
+use positive::Positive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// The size at which the segment being appended to is sealed, in bytes.
+///
+/// Bounds what a restart replays — at most one segment's worth of records the
+/// warehouse may already hold — and how much disk an acknowledged prefix keeps
+/// occupied before its segment can be deleted.
+const SEGMENT_BYTES: u64 = 16 * 1024 * 1024;
+
+/// The file the spool's directory is locked through.
+const LOCK_FILE: &str = ".lock";
+
+/// The prefix of a segment file's name.
+const SEGMENT_PREFIX: &str = "spool-";
+
+/// The extension of a segment file.
+const SEGMENT_EXTENSION: &str = "ndjson";
+
+/// Where a spooled record ends, handed out by [`SnapshotSpool::peek`] and
+/// given back to [`SnapshotSpool::ack`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpoolPosition {
+    segment: u64,
+    end: u64,
+}
+
+/// A durable, size-capped FIFO of snapshot records on local disk.
+///
+/// Every method does blocking file I/O under one lock; an async caller runs
+/// the reads off the runtime. Opening a directory takes an exclusive lock on
+/// it for as long as the spool lives, so two processes can never drain — or
+/// append to — the same segments.
+pub struct SnapshotSpool {
+    dir: PathBuf,
+    max_bytes: u64,
+    state: Mutex<SpoolState>,
+    /// Held open for the lock it carries; released when the spool is dropped.
+    _owner: File,
+}
+
+/// The segment appends currently go to.
+struct Writer {
+    segment: u64,
+    file: File,
+    len: u64,
+}
+
+struct SpoolState {
+    /// Segments no longer appended to, oldest first, with their lengths.
+    sealed: VecDeque<(u64, u64)>,
+    /// The open segment, created by the first append after it was last
+    /// sealed or drained.
+    writer: Option<Writer>,
+    /// The number the next segment is created under.
+    next_segment: u64,
+    /// How far into the oldest pending segment records have been
+    /// acknowledged.
+    read_offset: u64,
+    /// The bytes every segment currently occupies.
+    bytes: u64,
+}
+
+impl SnapshotSpool {
+    /// Opens the spool in `dir`, creating the directory if needed and picking
+    /// up whatever a previous run left in it.
+    ///
+    /// `max_bytes` caps the total size of the segments; an append that would
+    /// exceed it is refused rather than written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when the directory cannot be prepared
+    /// or listed, and [`ChainError::Conflict`] when another process holds it.
+    pub fn open(dir: impl AsRef<Path>, max_bytes: u64) -> Result<Self, ChainError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, &e))?;
+
+        let lock_path = dir.join(LOCK_FILE);
+        let owner = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| io_error(&lock_path, &e))?;
+        match owner.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(ChainError::Conflict(format!(
+                    "{} is in use by another process",
+                    dir.display()
+                )));
+            }
+            Err(TryLockError::Error(e)) => return Err(io_error(&lock_path, &e)),
+        }
+
+        let mut sealed = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| io_error(&dir, &e))? {
+            let entry = entry.map_err(|e| io_error(&dir, &e))?;
+            let Some(segment) = entry.file_name().to_str().and_then(segment_number) else {
+                continue;
+            };
+            let len = entry
+                .metadata()
+                .map_err(|e| io_error(&entry.path(), &e))?
+                .len();
+            sealed.push((segment, len));
+        }
+        sealed.sort_unstable();
+
+        let bytes = sealed.iter().map(|(_, len)| len).sum();
+        let next_segment = sealed.last().map_or(0, |(segment, _)| segment + 1);
+        info!(
+            dir = %dir.display(),
+            segments = sealed.len(),
+            bytes,
+            max_bytes,
+            "Opened the snapshot spool"
+        );
+
+        Ok(Self {
+            dir,
+            max_bytes,
+            state: Mutex::new(SpoolState {
+                sealed: sealed.into(),
+                writer: None,
+                next_segment,
+                read_offset: 0,
+                bytes,
+            }),
+            _owner: owner,
+        })
+    }
+
+    /// Appends a record.
+    ///
+    /// The line is handed to the operating system before this returns, so it
+    /// survives the process; it reaches the disk itself when its segment is
+    /// sealed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Conflict`] when the record would take the spool
+    /// past its cap — nothing is written then — and [`ChainError::Internal`]
+    /// when the write fails.
+    pub fn append(&self, record: &SnapshotRecord) -> Result<(), ChainError> {
+        let mut line = serde_json::to_vec(&SpooledRecord::from(record))
+            .map_err(|e| ChainError::Internal(format!("Failed to serialize a snapshot: {e}")))?;
+        line.push(b'\n');
+        let size = line.len() as u64;
+
+        let mut guard = self.lock()?;
+        let state = &mut *guard;
+        if state.bytes.saturating_add(size) > self.max_bytes {
+            return Err(ChainError::Conflict(format!(
+                "the snapshot spool holds {} of {} bytes and cannot take {size} more",
+                state.bytes, self.max_bytes
+            )));
+        }
+
+        let writer = match &mut state.writer {
+            Some(writer) => writer,
+            None => {
+                let segment = state.next_segment;
+                let path = self.segment_path(segment);
+                let file = OpenOptions::new()
+                    .create_new(true)
+                    .append(true)
+                    .open(&path)
+                    .map_err(|e| io_error(&path, &e))?;
+                state.next_segment += 1;
+                state.writer.insert(Writer {
+                    segment,
+                    file,
+                    len: 0,
+                })
+            }
+        };
+
+        if let Err(error) = writer.file.write_all(&line) {
+            // A short write leaves a torn line the reader could not tell from
+            // a crash's. Sealing the segment here means the next append starts
+            // clean and the reader skips the tail, as it would after a crash.
+            let path = self.segment_path(writer.segment);
+            let len = writer.file.metadata().map_or(writer.len, |meta| meta.len());
+            state.bytes += len.saturating_sub(writer.len);
+            state.sealed.push_back((writer.segment, len));
+            state.writer = None;
+            return Err(io_error(&path, &error));
+        }
+        writer.len += size;
+        state.bytes += size;
+
+        if writer.len >= SEGMENT_BYTES {
+            let path = self.segment_path(writer.segment);
+            writer.file.sync_all().map_err(|e| io_error(&path, &e))?;
+            state.sealed.push_back((writer.segment, writer.len));
+            state.writer = None;
+        }
+        Ok(())
+    }
+
+    /// Whether any record is waiting to be drained.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when the spool's lock is poisoned.
+    pub fn has_pending(&self) -> Result<bool, ChainError> {
+        let state = self.lock()?;
+        Ok(!state.sealed.is_empty()
+            || state
+                .writer
+                .as_ref()
+                .is_some_and(|writer| state.read_offset < writer.len))
+    }
+
+    /// The bytes the spool currently occupies on disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when the spool's lock is poisoned.
+    pub fn bytes(&self) -> Result<u64, ChainError> {
+        Ok(self.lock()?.bytes)
+    }
+
+    /// The oldest record not yet acknowledged, and where it ends.
+    ///
+    /// Does not consume it: until [`SnapshotSpool::ack`] is called with the
+    /// returned position, every call returns the same record. Torn and
+    /// unreadable lines on the way are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when a segment cannot be read or a
+    /// drained one cannot be deleted.
+    pub fn peek(&self) -> Result<Option<(SnapshotRecord, SpoolPosition)>, ChainError> {
+        let mut state = self.lock()?;
+        loop {
+            let (segment, len, is_sealed) = match (state.sealed.front(), &state.writer) {
+                (Some(&(segment, len)), _) => (segment, len, true),
+                (None, Some(writer)) => (writer.segment, writer.len, false),
+                (None, None) => return Ok(None),
+            };
+
+            if state.read_offset >= len {
+                if !is_sealed {
+                    return Ok(None);
+                }
+                self.release(&mut state)?;
+                continue;
+            }
+
+            let path = self.segment_path(segment);
+            let line = read_line(&path, state.read_offset, len)?;
+            let end = state.read_offset + line.len() as u64;
+
+            if line.last() != Some(&b'\n') {
+                warn!(
+                    path = %path.display(),
+                    offset = state.read_offset,
+                    "Skipping a torn line at the end of a spool segment"
+                );
+                state.read_offset = len;
+                continue;
+            }
+
+            match serde_json::from_slice::<SpooledRecord>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(SnapshotRecord::try_from)
+            {
+                Ok(record) => return Ok(Some((record, SpoolPosition { segment, end }))),
+                Err(error) => {
+                    warn!(
+                        path = %path.display(),
+                        offset = state.read_offset,
+                        error = %error,
+                        "Skipping an unreadable spooled snapshot"
+                    );
+                    state.read_offset = end;
+                }
+            }
+        }
+    }
+
+    /// Acknowledges everything up to `position`, deleting a segment once its
+    /// last record is acknowledged.
+    ///
+    /// A position that is already behind the read position — a record that
+    /// was acknowledged twice — changes nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when a drained segment cannot be
+    /// deleted.
+    pub fn ack(&self, position: SpoolPosition) -> Result<(), ChainError> {
+        let mut state = self.lock()?;
+        let (segment, len) = match (state.sealed.front(), &state.writer) {
+            (Some(&(segment, len)), _) => (segment, len),
+            (None, Some(writer)) => (writer.segment, writer.len),
+            (None, None) => return Ok(()),
+        };
+        if position.segment != segment || position.end <= state.read_offset {
+            return Ok(());
+        }
+
+        state.read_offset = position.end;
+        if state.read_offset >= len {
+            self.release(&mut state)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the oldest pending segment, which has been read to its end.
+    fn release(&self, state: &mut SpoolState) -> Result<(), ChainError> {
+        let (segment, len) = match state.sealed.pop_front() {
+            Some(sealed) => sealed,
+            // The open segment drained completely. Dropping it rather than
+            // appending on means an idle spool holds no files at all.
+            None => match state.writer.take() {
+                Some(writer) => (writer.segment, writer.len),
+                None => return Ok(()),
+            },
+        };
+
+        let path = self.segment_path(segment);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(io_error(&path, &e)),
+        }
+        state.bytes = state.bytes.saturating_sub(len);
+        state.read_offset = 0;
+        Ok(())
+    }
+
+    fn segment_path(&self, segment: u64) -> PathBuf {
+        self.dir
+            .join(format!("{SEGMENT_PREFIX}{segment:020}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, SpoolState>, ChainError> {
+        self.state.lock().map_err(|_| {
+            ChainError::Internal("Failed to acquire lock on snapshot spool".to_string())
+        })
+    }
+}
+
+/// The segment number a file name encodes, or `None` for any other file.
+fn segment_number(name: &str) -> Option<u64> {
+    name.strip_prefix(SEGMENT_PREFIX)?
+        .strip_suffix(SEGMENT_EXTENSION)?
+        .strip_suffix('.')?
+        .parse()
+        .ok()
+}
+
+/// Reads the line starting at `offset`, newline included, without reading
+/// past `len` — bytes beyond it belong to an append this reader has not been
+/// told about.
+fn read_line(path: &Path, offset: u64, len: u64) -> Result<Vec<u8>, ChainError> {
+    let mut file = File::open(path).map_err(|e| io_error(path, &e))?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| io_error(path, &e))?;
+    let mut line = Vec::new();
+    BufReader::new(file.take(len - offset))
+        .read_until(b'\n', &mut line)
+        .map_err(|e| io_error(path, &e))?;
+    Ok(line)
+}
+
+/// The error for a failed file operation, naming the path.
+#[cold]
+fn io_error(path: &Path, error: &std::io::Error) -> ChainError {
+    ChainError::Internal(format!(
+        "Snapshot spool error at {}: {error}",
+        path.display()
+    ))
+}
+
+/// A [`SnapshotRecord`] as one spool line, every decimal exact.
+#[derive(Serialize, Deserialize)]
+struct SpooledRecord {
+    simulation: Uuid,
+    generation: u64,
+    engine_version: String,
+    step: usize,
+    simulated_at: DateTime<Utc>,
+    symbol: String,
+    spot: Decimal,
+    base_volatility: Decimal,
+    expirations: Vec<SpooledExpiration>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpooledExpiration {
+    expires_at: DateTime<Utc>,
+    days_to_expiration: Decimal,
+    labels: Vec<String>,
+    quotes: Vec<SpooledQuote>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpooledQuote {
+    strike: Decimal,
+    implied_volatility: Decimal,
+    call_bid: Option<Decimal>,
+    call_ask: Option<Decimal>,
+    call_mid: Option<Decimal>,
+    put_bid: Option<Decimal>,
+    put_ask: Option<Decimal>,
+    put_mid: Option<Decimal>,
+    delta_call: Option<Decimal>,
+    delta_put: Option<Decimal>,
+    gamma: Option<Decimal>,
+}
+
+impl From<&SnapshotRecord> for SpooledRecord {
+    fn from(record: &SnapshotRecord) -> Self {
+        Self {
+            simulation: record.simulation,
+            generation: record.generation,
+            engine_version: record.engine_version.clone(),
+            step: record.step,
+            simulated_at: record.simulated_at,
+            symbol: record.symbol.clone(),
+            spot: record.spot.to_dec(),
+            base_volatility: record.base_volatility.to_dec(),
+            expirations: record
+                .expirations
+                .iter()
+                .map(|expiration| SpooledExpiration {
+                    expires_at: expiration.expires_at,
+                    days_to_expiration: expiration.days_to_expiration.to_dec(),
+                    labels: expiration.labels.clone(),
+                    quotes: expiration
+                        .quotes
+                        .iter()
+                        .map(|quote| SpooledQuote {
+                            strike: quote.strike.to_dec(),
+                            implied_volatility: quote.implied_volatility.to_dec(),
+                            call_bid: quote.call_bid.map(|value| value.to_dec()),
+                            call_ask: quote.call_ask.map(|value| value.to_dec()),
+                            call_mid: quote.call_mid.map(|value| value.to_dec()),
+                            put_bid: quote.put_bid.map(|value| value.to_dec()),
+                            put_ask: quote.put_ask.map(|value| value.to_dec()),
+                            put_mid: quote.put_mid.map(|value| value.to_dec()),
+                            delta_call: quote.delta_call,
+                            delta_put: quote.delta_put,
+                            gamma: quote.gamma,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<SpooledRecord> for SnapshotRecord {
+    type Error = String;
+
+    fn try_from(spooled: SpooledRecord) -> Result<Self, Self::Error> {
+        let expirations = spooled
+            .expirations
+            .into_iter()
+            .map(|expiration| {
+                let quotes = expiration
+                    .quotes
+                    .into_iter()
+                    .map(|quote| {
+                        Ok(QuoteRow {
+                            strike: positive(quote.strike, "strike")?,
+                            implied_volatility: positive(
+                                quote.implied_volatility,
+                                "implied_volatility",
+                            )?,
+                            call_bid: optional(quote.call_bid, "call_bid")?,
+                            call_ask: optional(quote.call_ask, "call_ask")?,
+                            call_mid: optional(quote.call_mid, "call_mid")?,
+                            put_bid: optional(quote.put_bid, "put_bid")?,
+                            put_ask: optional(quote.put_ask, "put_ask")?,
+                            put_mid: optional(quote.put_mid, "put_mid")?,
+                            delta_call: quote.delta_call,
+                            delta_put: quote.delta_put,
+                            gamma: quote.gamma,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(ExpirationRecord {
+                    expires_at: expiration.expires_at,
+                    days_to_expiration: positive(
+                        expiration.days_to_expiration,
+                        "days_to_expiration",
+                    )?,
+                    labels: expiration.labels,
+                    quotes,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(SnapshotRecord {
+            simulation: spooled.simulation,
+            generation: spooled.generation,
+            engine_version: spooled.engine_version,
+            step: spooled.step,
+            simulated_at: spooled.simulated_at,
+            symbol: spooled.symbol,
+            spot: positive(spooled.spot, "spot")?,
+            base_volatility: positive(spooled.base_volatility, "base_volatility")?,
+            expirations,
+        })
+    }
+}
+
+/// Reads back a spooled positive value.
+fn positive(value: Decimal, field: &str) -> Result<Positive, String> {
+    Positive::new_decimal(value).map_err(|error| format!("{field}: {error}"))
+}
+
+/// Reads back an optional spooled positive value.
+fn optional(value: Option<Decimal>, field: &str) -> Result<Option<Positive>, String> {
+    value.map(|value| positive(value, field)).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use positive::pos_or_panic;
+    use rust_decimal_macros::dec;
+
+    fn record(step: usize) -> SnapshotRecord {
+        let expires_at = match Utc.with_ymd_and_hms(2026, 1, 16, 21, 0, 0) {
+            chrono::LocalResult::Single(instant) => instant,
+            other => panic!("the expiry must be a valid instant: {other:?}"),
+        };
+        let quote = QuoteRow::new(pos_or_panic!(100.0), pos_or_panic!(0.2))
+            .with_call(
+                // Far more digits than an f64 carries: a spool that went
+                // through `Positive`'s serde would not give this back.
+                Some(
+                    Positive::new_decimal(dec!(4.1234567890123456789012345678))
+                        .unwrap_or_else(|error| panic!("the premium must be positive: {error}")),
+                ),
+                None,
+                None,
+                Some(dec!(0.5123456789012345678901234567)),
+            )
+            .with_gamma(Some(dec!(0.01)));
+        SnapshotRecord::new(
+            Uuid::new_v4(),
+            1,
+            "engine".to_string(),
+            step,
+            expires_at - chrono::Duration::days(30),
+            "TEST".to_string(),
+            pos_or_panic!(100.0),
+            pos_or_panic!(0.2),
+            vec![ExpirationRecord::new(
+                expires_at,
+                pos_or_panic!(30.0),
+                vec!["monthly".to_string()],
+                vec![quote],
+            )],
+        )
+    }
+
+    fn temp_dir() -> tempfile::TempDir {
+        match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        }
+    }
+
+    fn open(dir: &Path, max_bytes: u64) -> SnapshotSpool {
+        match SnapshotSpool::open(dir, max_bytes) {
+            Ok(spool) => spool,
+            Err(error) => panic!("the spool must open: {error}"),
+        }
+    }
+
+    fn drain(spool: &SnapshotSpool) -> Vec<SnapshotRecord> {
+        let mut drained = Vec::new();
+        loop {
+            match spool.peek() {
+                Ok(Some((record, position))) => {
+                    if let Err(error) = spool.ack(position) {
+                        panic!("the ack must succeed: {error}");
+                    }
+                    drained.push(record);
+                }
+                Ok(None) => return drained,
+                Err(error) => panic!("the peek must succeed: {error}"),
+            }
+        }
+    }
+
+    /// Records come back exactly and in order, a peek without an ack returns
+    /// the same record again, and a drained spool deletes its segment.
+    #[test]
+    fn test_records_drain_in_order_and_exactly() {
+        let dir = temp_dir();
+        let spool = open(dir.path(), u64::MAX);
+        let written = vec![record(0), record(1), record(2)];
+        for record in &written {
+            if let Err(error) = spool.append(record) {
+                panic!("the append must succeed: {error}");
+            }
+        }
+
+        match (spool.peek(), spool.peek()) {
+            (Ok(Some((first, _))), Ok(Some((again, _)))) => assert_eq!(first, again),
+            other => panic!("an unacknowledged record must be returned again: {other:?}"),
+        }
+        assert_eq!(drain(&spool), written);
+        assert!(matches!(spool.has_pending(), Ok(false)));
+        assert!(matches!(spool.bytes(), Ok(0)));
+        assert!(
+            !dir.path()
+                .join(format!("{SEGMENT_PREFIX}{:020}.{SEGMENT_EXTENSION}", 0))
+                .exists()
+        );
+    }
+
+    /// An append that would pass the cap is refused and leaves the spool as it
+    /// was.
+    #[test]
+    fn test_the_cap_refuses_an_append() {
+        let dir = temp_dir();
+        let spool = open(dir.path(), u64::MAX);
+        if let Err(error) = spool.append(&record(0)) {
+            panic!("the append must succeed: {error}");
+        }
+        let one = match spool.bytes() {
+            Ok(bytes) => bytes,
+            Err(error) => panic!("the size must be readable: {error}"),
+        };
+        drop(spool);
+
+        let spool = open(dir.path(), one + one / 2);
+        assert!(matches!(
+            spool.append(&record(1)),
+            Err(ChainError::Conflict(_))
+        ));
+        assert_eq!(drain(&spool).len(), 1);
+    }
+
+    /// What was spooled survives the spool being closed and reopened, and
+    /// appends after the restart go to a new segment behind the old one.
+    #[test]
+    fn test_records_survive_a_restart() {
+        let dir = temp_dir();
+        let before = record(0);
+        let spool = open(dir.path(), u64::MAX);
+        if let Err(error) = spool.append(&before) {
+            panic!("the append must succeed: {error}");
+        }
+        drop(spool);
+
+        let spool = open(dir.path(), u64::MAX);
+        assert!(matches!(spool.has_pending(), Ok(true)));
+        let after = record(1);
+        if let Err(error) = spool.append(&after) {
+            panic!("the append must succeed: {error}");
+        }
+        assert_eq!(drain(&spool), vec![before, after]);
+    }
+
+    /// A line torn by a crash and a line that does not parse are skipped; the
+    /// records around them are not.
+    #[test]
+    fn test_damaged_lines_are_skipped() {
+        let dir = temp_dir();
+        let first = record(0);
+        let spool = open(dir.path(), u64::MAX);
+        if let Err(error) = spool.append(&first) {
+            panic!("the append must succeed: {error}");
+        }
+        drop(spool);
+
+        let path = dir
+            .path()
+            .join(format!("{SEGMENT_PREFIX}{:020}.{SEGMENT_EXTENSION}", 0));
+        let mut file = match OpenOptions::new().append(true).open(&path) {
+            Ok(file) => file,
+            Err(error) => panic!("the segment must be writable: {error}"),
+        };
+        if let Err(error) = file.write_all(b"not json\n{\"simulation\":") {
+            panic!("the damage must be written: {error}");
+        }
+        drop(file);
+
+        let spool = open(dir.path(), u64::MAX);
+        let last = record(1);
+        if let Err(error) = spool.append(&last) {
+            panic!("the append must succeed: {error}");
+        }
+        assert_eq!(drain(&spool), vec![first, last]);
+    }
+
+    /// A second spool over the same directory is refused while the first is
+    /// open.
+    #[test]
+    fn test_a_directory_is_held_by_one_spool() {
+        let dir = temp_dir();
+        let _spool = open(dir.path(), u64::MAX);
+        assert!(matches!(
+            SnapshotSpool::open(dir.path(), u64::MAX),
+            Err(ChainError::Conflict(_))
+        ));
+    }
+}
@@ -16,6 +16,7 @@
 //!     V1["/api/v1/chain"]
 //!     V2["/api/v2/simulations"]
 //!     Export["/api/v2/simulations/{id}/export"]
+//!     Grpc["gRPC optionchain.v2.Simulations"]
 //!   end
 //!
 //!   subgraph session["session — lifecycle, effective parameters"]
@@ -36,9 +37,10 @@
 //!     CH[(ClickHouse)]
 //!   end
 //!
-//!   Client --> V1 & V2 & Export
+//!   Client --> V1 & V2 & Export & Grpc
 //!   V1 --> SM --> Simulator
 //!   V2 --> SIM
+//!   Grpc --> SIM
 //!   Export --> SIM
 //!   SIM --> Tape
 //!   Tape --> Series
@@ -46,6 +48,7 @@
 //!   SM -.sessions.-> Redis
 //!   SIM -.simulations.-> Redis
 //!   SM -.events.-> Mongo
+//!   SIM -.simulations, opt-in.-> Mongo
 //!   Simulator -.historical prices.-> CH
 //!   SIM -.snapshots, opt-in.-> CH
 //!   Export -.persisted rows first.-> CH
@@ -166,7 +169,7 @@
 //! API->>CH: Read the persisted range
 //! CH-->>API: Rows for the steps it has
 //! API->>Series: Replay whatever is missing
-//! API-->>Client: 200 OK (streamed JSON or CSV)
+//! API-->>Client: 200 OK (streamed JSON, NDJSON, CSV, Parquet or Arrow)
 //! ```
 //!
 //! ## REST API Endpoints
@@ -207,10 +210,31 @@
 //! | Method | Endpoint | Action |
 //! |--------|----------|--------|
 //! | POST   | /api/v2/simulations              | Create a simulation and receive every replay input |
+//! | GET    | /api/v2/simulations              | List simulations, filtered and paged |
 //! | GET    | /api/v2/simulations/{id}         | Read its metadata and effective parameters |
 //! | GET    | /api/v2/simulations/{id}/snapshot| Peek the current snapshot (safe, repeatable) |
 //! | POST   | /api/v2/simulations/{id}/step    | Serve the current snapshot, then advance once |
-//! | DELETE | /api/v2/simulations/{id}         | Delete it and evict its cached state |
+//! | DELETE | /api/v2/simulations/{id}         | Delete it and evict its cached state; `?purge=true` also removes its warehouse rows |
+//! | POST   | /api/v2/simulations/{id}/touch   | Restart its idle-retention window |
+//! | GET    | /api/v2/simulations/{id}/snapshot/next | Wait for the next advance's snapshot |
+//! | POST   | /api/v2/simulations/{id}/seek    | Jump the cursor forward to `to_step` |
+//! | POST   | /api/v2/simulations/{id}/playback | Start advancing on a wall-clock pace |
+//! | GET    | /api/v2/simulations/{id}/playback | Read the playback's state |
+//! | DELETE | /api/v2/simulations/{id}/playback | Stop the playback |
+//! | POST   | /api/v2/simulations/{id}/playback/pause | Pause the playback |
+//! | POST   | /api/v2/simulations/{id}/playback/resume | Resume the playback |
+//! | POST   | /api/v2/simulations/{id}/materialize | File a step range in the warehouse, in the background |
+//! | GET    | /api/v2/simulations/{id}/materialize | Read the materialization's progress |
+//! | DELETE | /api/v2/simulations/{id}/materialize | Cancel the materialization |
+//! | GET    | /api/v2/simulations/{id}/contracts/series | One contract's quotes across a step range |
+//! | GET    | /api/v2/simulations/{id}/stream  | WebSocket: push every served snapshot, accept advances |
+//! | GET    | /api/v2/simulations/{id}/events  | Server-Sent Events: one event per advance, resumable |
+//! | POST   | /api/v2/simulations/{id}/webhooks | Register a webhook on this simulation |
+//! | GET    | /api/v2/simulations/{id}/webhooks | List its webhooks |
+//! | POST   | /api/v2/webhooks                 | Register a webhook on every simulation |
+//! | GET    | /api/v2/webhooks                 | List the global webhooks |
+//! | DELETE | /api/v2/webhooks/{id}            | Remove a webhook |
+//! | POST   | /api/v2/snapshots/purge          | Remove the warehouse rows of deleted simulations |
 //!
 //! **Serve-then-advance**, as in v1: a simulation with `steps = N` serves
 //! indices `0..N-1` over `N` calls to `/step`, and any call after that returns
@@ -219,10 +243,144 @@
 //! retry after a lost response safe. It is deliberately distinct from `409`,
 //! which means another writer committed first.
 //!
+//! **Retries can be made exactly-once.** Send `Idempotency-Key` (up to 255
+//! printable ASCII characters) on `POST /api/v2/simulations` or `POST /step` and
+//! the first response to that key is stored — in Redis, next to the
+//! simulations — for `OCS_V2_IDEMPOTENCY_WINDOW_SECS` (a day by default). A
+//! retry with the same key and the same request gets that response back, headers
+//! and all, marked `Idempotent-Replayed: true`, instead of a second simulation
+//! or a second advance; the same key on a different request — a changed
+//! `If-Match` included — is `422`. A `409` or a `5xx` is not stored, so the
+//! retry runs afresh.
+//!
+//! **Conditional requests use the revision.** `GET /api/v2/simulations/{id}`,
+//! the snapshot peek and every served snapshot carry an `ETag` built from the
+//! revision and the cursor. Send it back as `If-None-Match` on a read to get
+//! `304` while nothing has moved — a caching proxy in front of the service can
+//! do the same — or as `If-Match` on `POST /step` or `DELETE` to act only on the
+//! revision you saw: a mismatch is `412` with the current tag, the standard
+//! HTTP form of `expected_step`.
+//!
+//! **A run travels as one file.** `GET /api/v2/simulations/{id}/manifest`
+//! returns a versioned replay manifest: the effective parameters in their exact
+//! stored form, the tzdb release, calendar, tape generation and crate version
+//! they depend on, and a SHA-256 digest over all of it. `POST` it to
+//! `/api/v2/simulations/import` — on this deployment or another — to recreate
+//! the simulation under a new id. An edited manifest is refused, and so is one
+//! written under a different tape generation or tzdb release unless the import
+//! passes `force=true`.
+//!
+//! **A tape has a fingerprint.** Every snapshot carries a `digest`: the
+//! SHA-256 of the step's rows as the CSV export renders them. Chained across
+//! steps, the last digest fingerprints the whole range, and
+//! `GET /api/v2/simulations/{id}/digest?to_step=k` returns it without streaming
+//! a row — so a CI job can assert that an upgrade left a golden tape unchanged.
+//! An export with `digest=true` carries the running chain on every row.
+//!
+//! **A simulation remembers its engine.** Creation records `engine_version` —
+//! the tape generation and the locked `optionstratlib` and `positive` releases,
+//! e.g. `tape2+optionstratlib-0.18.1+positive-0.5.1` — in the effective
+//! parameters, and every row filed in ClickHouse is tagged with the engine that
+//! priced it. A simulation loaded by a binary with a different engine is logged
+//! as drifted; with `OCS_V2_ENGINE_MISMATCH=refuse` its snapshots, exports and
+//! digests are also refused with `400` on `engine_version`, while reading,
+//! deleting and taking its manifest still work. A manifest from another engine
+//! imports only with `force=true`.
+//!
+//! **Simulations can live in MongoDB.** They are kept in Redis by default;
+//! `OCS_V2_STORE=mongodb` stores them in the `simulations_v2` collection of the
+//! configured MongoDB instead, so a Redis flush or eviction cannot take them.
+//! The contract is the same: creation never overwrites, every write is
+//! conditioned on the revision, and expired simulations are reaped by the same
+//! cleanup pass, which reports their ids.
+//!
+//! **Or on local disk.** `OCS_V2_STORE=file` keeps v1 sessions and v2
+//! simulations as one JSON file each under `OCS_FILE_STORE_DIR`, so the
+//! service runs on a laptop or a CI runner without Redis and keeps its state
+//! across restarts. Every write goes to a temporary file that is flushed and
+//! then renamed over the old one, so a crash leaves the previous document or
+//! the new one, never a torn mix; the directory is locked to one process.
+//!
 //! **A v2 simulation is immutable after creation.** There is no PATCH or PUT:
 //! changing the seed, the start, the schedules or the chain shape changes the
 //! tape, so it creates a new simulation instead of mutating one.
 //!
+//! **Simulations can be found again.** `tags` on the create body labels a
+//! simulation (up to 16 tags of `[A-Za-z0-9_.:-]`), and `GET /api/v2/simulations`
+//! lists them oldest first, filtered by `symbol`, `state`, `created_after` /
+//! `created_before` (RFC 3339) and `tags` (comma-separated, all must match).
+//! Pages are `limit` long (default 50, at most 500); pass the returned
+//! `next_offset` as `offset` to continue. Tags are not a replay input.
+//!
+//! **Retention is per simulation.** `retention_seconds` on the create body
+//! overrides the idle window (`OCS_V2_RETENTION_SECS`) for that simulation, up
+//! to `OCS_V2_MAX_RETENTION_SECS`. `POST /api/v2/simulations/{id}/touch` restarts
+//! the window without moving the cursor; like any write it is a new revision.
+//! `pinned: true` exempts a simulation from expiry altogether, within a quota
+//! of `OCS_V2_MAX_PINNED` pinned simulations; a create beyond it is `409`, and
+//! deleting a pinned simulation frees its slot.
+//!
+//! **A simulation can tick on its own.** `POST /playback` with
+//! `steps_per_second` (e.g. `1.0`) or `speed` (a multiple of real time against
+//! the simulation's own step interval, e.g. `60.0`) makes the server advance it
+//! until it completes; `/playback/pause`, `/playback/resume` and `DELETE
+//! /playback` steer it. Every tick is an ordinary advance, so a manual `/step`
+//! may still be made alongside — whichever commits first wins and the other
+//! tick is skipped. Clients read the current market with `/snapshot`, or block
+//! on `/snapshot/next?timeout_ms=` until the next advance serves one (`204` if
+//! none does in time). `POST /seek?to_step=` jumps the cursor forward without
+//! pricing the steps in between. Playback is held by the replica that started
+//! it, is not persisted, and is capped per process by `OCS_V2_MAX_PLAYBACKS`.
+//!
+//! **Snapshots can be pushed.** `GET /stream` upgrades to a WebSocket that
+//! sends every snapshot the simulation serves, whoever advanced it, as a JSON
+//! `snapshot` frame, and closes after a `completed` frame. Over the same socket
+//! a client sends `{"type":"advance","expected_step":n}`, `{"type":"peek"}` or
+//! `{"type":"filter",...}`; a failed command comes back as an `error` frame with
+//! the status and body the REST route would have returned. `labels`,
+//! `min_strike` and `max_strike` — on the query or in a `filter` — cut each
+//! snapshot to the expirations and strikes wanted. A client that reads too
+//! slowly is sent `lagged` instead of being queued for.
+//!
+//! **Or followed with Server-Sent Events.** `GET /events` is a read-only
+//! `text/event-stream` for browsers and `curl -N`: one `advance` event per
+//! committed advance with the served step's `simulated_at`, `spot` and
+//! `base_volatility`, plus a per-expiration summary (strike count, ATM strike
+//! and volatility) with `summary=true`. Each event's id is its step, so a client
+//! reconnecting with `Last-Event-ID` is first sent every step it missed —
+//! rebuilt by deterministic replay, byte-identical to the live events, up to
+//! 256 per gap — and the feed ends with a `completed` event, or with `deleted`
+//! or `expired` if the simulation goes away first. Steps a seek jumped over
+//! were never served, so they arrive as one `seek` event instead.
+//!
+//! **Or driven over gRPC.** Port `7071` serves `optionchain.v2.Simulations`
+//! (`proto/optionchain/v2/simulations.proto`): `CreateSimulation`,
+//! `GetSimulation`, `PeekSnapshot`, `AdvanceSimulation`, `DeleteSimulation` and
+//! a server-streaming `ExportSimulation`. The messages mirror
+//! `SimulationResponse` and `SnapshotResponse`, and both servers share one
+//! simulation manager — a simulation created over one protocol advances over
+//! the other under the same `expected_step` precondition and compare-and-swap.
+//! A stale `expected_step` is `FAILED_PRECONDITION`, a lost race `ABORTED`, a
+//! completed simulation `OUT_OF_RANGE`. The export's chunks concatenate to the
+//! REST download byte for byte.
+//!
+//! **Or notified by webhook.** `POST /api/v2/simulations/{id}/webhooks` — or
+//! `POST /api/v2/webhooks` for every simulation — with a `url`, a
+//! `signing_key` (16 to 256 characters, never returned) and optionally
+//! `every_n_steps` makes the service `POST` a JSON event when a simulation
+//! `completed`, `expired`, was `deleted`, or took a multiple of `every_n_steps`
+//! steps (`step`). Delivery is queued off the request path and retried with a
+//! doubling backoff up to `OCS_V2_WEBHOOK_MAX_ATTEMPTS` times; a `4xx` other
+//! than `408`/`429` is not retried, and a `3xx` is neither followed nor
+//! retried. Each request carries `X-OCS-Event`,
+//! `X-OCS-Delivery` (the same on every retry), `X-OCS-Timestamp` and
+//! `X-OCS-Signature: sha256=<hex>`, the HMAC-SHA256 of `{timestamp}.{body}`
+//! under the signing key — recompute it and reject stale timestamps to refuse
+//! forgeries and replays. A `url` on a loopback, private or link-local address
+//! is refused, at registration and again when each delivery connects, unless
+//! `OCS_V2_WEBHOOK_ALLOW_PRIVATE_TARGETS` is set. Registrations are held per
+//! process and are not persisted.
+//!
 //! **A historical v2 walk prices itself.** A `Historical` method carries no
 //! volatility of its own, so each step is priced by the realized volatility of
 //! everything observed up to that step and nothing later — the same expanding
@@ -251,6 +409,32 @@
 //! connectivity problem fails the boot rather than surfacing later. MongoDB
 //! stays event and audit only.
 //!
+//! The writes queue in memory, and a full queue or a failed write loses the
+//! step. Setting `OCS_SNAPSHOT_SPOOL_DIR` spills those steps to a local,
+//! size-capped spool instead (`OCS_SNAPSHOT_SPOOL_MAX_MB`); the writer files
+//! them once the queue has drained and the warehouse is back, across restarts,
+//! so a maintenance window does not leave gaps in the tape. Only a full spool
+//! still loses a step.
+//!
+//! Steps nobody advanced through are not in the warehouse at all. `POST
+//! /materialize?from=&to=` files them: a background job prices the range and
+//! persists every step the warehouse does not already hold, without moving the
+//! cursor. `GET /materialize` reports its progress and `DELETE` cancels it; like
+//! a playback it lives on one replica, and `OCS_V2_MAX_MATERIALIZATIONS` caps
+//! how many run at once.
+//!
+//! Rows otherwise leave the warehouse at the table TTL. When they must go now,
+//! `DELETE /api/v2/simulations/{id}?purge=true` deletes the simulation and its
+//! rows together, and `POST /api/v2/snapshots/purge` removes the rows of
+//! simulations already deleted. A simulation that still exists is never
+//! purged: its next advance would file it again.
+//!
+//! **MongoDB is optional.** The v1 handlers record session events and chain
+//! steps through an `EventSink`: MongoDB by default, an append-only NDJSON file
+//! with `OCS_EVENT_SINK=ndjson`, or nothing with `OCS_EVENT_SINK=none`. A
+//! failed record is logged and never fails the request. With a non-MongoDB
+//! sink and `OCS_V2_STORE=file`, the service runs with no external services.
+//!
 //! **Replay.** The creation response echoes the effective seed, effective
 //! start, step interval, time frame, timezone, calendar version, IANA tzdb
 //! release and normalised schedules — everything needed to reproduce the run
@@ -310,7 +494,7 @@
 //! | Parameter | Values |
 //! |-----------|--------|
 //! | `dataset` | `underlying` \| `volatility` \| `option_chains` |
-//! | `format`  | `json` \| `csv` |
+//! | `format`  | `json` \| `ndjson` \| `csv` \| `parquet` \| `arrow` |
 //! | `from_step`, `to_step` | inclusive bounds; default to the whole tape |
 //!
 //! **Read-only in the strong sense.** The export works from an immutable copy
@@ -323,16 +507,66 @@
 //! **Deterministic.** Repeating an export is byte-identical: every value is a
 //! function of the effective parameters and the cursor, timestamps render as
 //! whole-second RFC 3339, and numbers use shortest round-trip formatting with
-//! no locale. JSON is a single valid array; CSV is RFC 4180 with a header row
+//! no locale. JSON is a single valid array; NDJSON is the same row objects one
+//! per `\n`-terminated line, for consumers that read a line at a time; CSV is RFC 4180 with a header row
 //! and CRLF endings, and an absent optional is an **empty** field rather than
 //! `null` or `0`. Chain labels are joined with `|` so a shared expiration stays
 //! one column.
 //!
+//! **Parquet** keeps the CSV's columns and their order, typed: `UInt64` steps,
+//! UTC millisecond timestamps, dictionary-encoded `symbol` and `labels`, and
+//! `f64` numbers with nulls for absent quotes. It is ZSTD-compressed and
+//! written in row groups of whole steps, each streamed as soon as it closes,
+//! so it loads straight into pandas or polars without a parse step. Like the
+//! text formats it is byte-identical for identical inputs and the same build.
+//!
+//! **Arrow** (`.arrows`) is an Arrow IPC stream with the same fixed schema per
+//! dataset, sent first. Each window of up to 64 steps follows as one record
+//! batch, so zero-copy consumers can map batches as they arrive.
+//!
 //! The rows are produced on a blocking thread and handed over a bounded
 //! channel, so a long `option_chains` export never occupies an Actix worker and
 //! a slow client applies backpressure instead of accumulating priced chains in
 //! memory. `OCS_MAX_EXPORT_ROWS` bounds how many steps one request may cover.
 //!
+//! **Compressed on request.** Any format is sent gzip- or zstd-encoded when
+//! `Accept-Encoding` asks for it; zstd wins a tie, and anything else is sent
+//! unencoded. The compression runs on the producer's thread, in front of the
+//! channel, so backpressure is unchanged, and it is deterministic, so a
+//! compressed export also repeats byte for byte. An `option_chains` CSV
+//! shrinks roughly tenfold.
+//!
+//! **Everything at once.** `GET /api/v2/simulations/{id}/export/bundle` takes
+//! the same `format`, range and `digest` and streams one ZIP: `manifest.json`
+//! — the simulation's replay manifest, importable as it is — then
+//! `underlying`, `volatility` and `option_chains`, each byte-identical to its
+//! own export. The archive is written incrementally with data descriptors, so
+//! it is never held in memory and keeps the export's backpressure.
+//!
+//! **Only what the study reads.** An export can keep a sliver of the chain:
+//! `labels=` keeps one rule's expirations, `min_dte`/`max_dte` and
+//! `min_moneyness`/`max_moneyness` bound the expirations and strikes, and
+//! `side=call|put` with `min_delta`/`max_delta` keeps a delta band.
+//! `columns=` projects any dataset (the columns keep the dataset's order), and
+//! `every=k` exports every k-th step. The filters run before rendering, and
+//! the labels, DTE band and stride are pushed into the warehouse read.
+//!
+//! **Resumable downloads.** Every 256 rows an `ndjson` export writes a
+//! `{"continuation": "…"}` line. A client whose connection dropped repeats the
+//! query with `after=<token>` and gets exactly the bytes that followed that
+//! line. The token is bound to the query that issued it, with its step range
+//! resolved, so a resume with a different filter, projection, dataset or end
+//! step is refused. The other formats cannot be spliced, so they refuse
+//! `after`; every export says which kind it is in `X-OCS-Resumable`, `true`
+//! for `ndjson` and `false` otherwise.
+//!
+//! **One contract, many steps.** `GET /api/v2/simulations/{id}/contracts/series`
+//! returns one contract's quotes across a range, as a chart or a
+//! single-position backtest wants them. It takes `expires_at` and `strike` as
+//! a snapshot served them, `side=call|put`, and `from`/`to`. It prefers the
+//! warehouse and replays the rest, like the export, and a step that does not
+//! list the contract has no point.
+//!
 //! ## Request/Response Models
 //!
 //! ### 1. Create Session (POST /api/v1/chain)
@@ -914,6 +1148,7 @@
 //!
 //! class InMemorySessionStore
 //! class InRedisSessionStore
+//! class InFileSessionStore
 //!
 //! class SimulationStore {
 //! <<interface>>
@@ -926,6 +1161,8 @@
 //!
 //! class InMemorySimulationStore
 //! class InRedisSimulationStore
+//! class MongoSimulationStore
+//! class InFileSimulationStore
 //!
 //! class HistoricalDataRepository {
 //! <<interface>>
@@ -950,17 +1187,28 @@
 //! -simulation_option_quotes ReplacingMergeTree
 //! }
 //!
-//! class MongoDBRepository {
+//! class EventSink {
+//! <<interface>>
 //! +saveChainStep(step) void
-//! +saveEvent(event) void
+//! +saveSessionEvent(event) void
 //! }
 //!
+//! class MongoDBRepository
+//! class NdjsonEventSink
+//! class NoopEventSink
+//!
 //! SessionStore <|.. InMemorySessionStore: implements
 //! SessionStore <|.. InRedisSessionStore: implements
+//! SessionStore <|.. InFileSessionStore: implements
 //! SimulationStore <|.. InMemorySimulationStore: implements
 //! SimulationStore <|.. InRedisSimulationStore: implements
+//! SimulationStore <|.. MongoSimulationStore: implements
+//! SimulationStore <|.. InFileSimulationStore: implements
 //! HistoricalDataRepository <|.. ClickHouseHistoricalRepository: implements
 //! SimulationSnapshotRepository <|.. ClickHouseSnapshotRepository: implements
+//! EventSink <|.. MongoDBRepository: implements
+//! EventSink <|.. NdjsonEventSink: implements
+//! EventSink <|.. NoopEventSink: implements
 //! ```
 //!
 //! The two ClickHouse repositories point in opposite directions.
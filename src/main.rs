@@ -12,7 +12,7 @@
 //!    with a custom Redis key prefix (`optionchain:session:`) and a TTL of 1 hour.
 //! 5. Constructs a `SessionManager` to manage user sessions by wrapping the session store.
 //! 6. Starts an HTTP server using `start_server`, listening on all available interfaces (`ListenOn::All`)
-//!    at port `7070`.
+//!    at port `7070`, and the v2 gRPC server using `start_grpc_server` on port `7071`.
 //!
 //! # Returns
 //! - On success, returns `Ok(())`.
@@ -30,6 +30,7 @@
 //! # HTTP Server Details
 //! - Listening Address: `0.0.0.0` (all interfaces).
 //! - Port: `7070`.
+//! - gRPC port: `7071`, serving `optionchain.v2.Simulations` over the same simulation manager.
 //!
 //! # Example
 //! ```
@@ -57,13 +58,16 @@
 //! # Author
 //! - Generated and maintained by the developers of `optionchain_simulator`.
 
-use optionchain_simulator::api::{ListenOn, start_server};
+use optionchain_simulator::api::{ListenOn, start_grpc_server, start_server};
 use optionchain_simulator::infrastructure::{
-    ClickHouseSnapshotRepository, MetricsCollector, RedisClient, RedisConfig, SimulationV2Config,
-    init_mongodb,
+    ClickHouseSnapshotRepository, EventSinkConfig, FileStoreConfig, MetricsCollector,
+    MongoDBClient, MongoDBConfig, RedisClient, RedisConfig, SimulationStoreBackend,
+    SimulationV2Config, SnapshotSpool, SnapshotSpoolConfig, init_event_sink,
 };
 use optionchain_simulator::session::{
-    InRedisSessionStore, InRedisSimulationStore, SessionManager, SimulationManager,
+    IdempotencyStore, InFileSessionStore, InFileSimulationStore, InMemoryIdempotencyStore,
+    InRedisIdempotencyStore, InRedisSessionStore, InRedisSimulationStore, MongoSimulationStore,
+    SessionManager, SessionStore, SimulationManager, SimulationStore,
 };
 use optionstratlib::utils::setup_logger_with_level;
 use std::sync::Arc;
@@ -115,39 +119,99 @@ use tracing::{info, warn};
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     setup_logger_with_level("DEBUG");
 
-    // Create a session store
-    let redis_config = RedisConfig::default();
-    info!("Connecting to Redis at {}", redis_config);
-    let redis_client = Arc::new(RedisClient::new(redis_config).await?);
-    let redis_client_v2 = Arc::clone(&redis_client);
-    let store = Arc::new(InRedisSessionStore::new(
-        redis_client,
-        Some("optionchain:session:".to_string()), // Custom key prefix
-        Some(3600),                               // 1 hour TTL
-    ));
+    // The v2 operational limits are loaded and validated first, before
+    // anything connects or binds: an invalid knob fails startup with a message
+    // naming the variable rather than silently reverting to a default that
+    // would change how long simulations live. They also pick the stores.
+    let v2_config = SimulationV2Config::from_env()?;
+
+    // The v2 rolling simulations never share a container with the v1 sessions
+    // — their own Redis key space, MongoDB collection or directory — so a v2 id
+    // can never resolve a v1 session and a v1 document is never read back as
+    // rolling configuration (ADR 0001 section 12.2).
+    //
+    // `OCS_V2_STORE=mongodb` moves the simulations to MongoDB, where a Redis
+    // flush cannot take them; its indexes are created here for the same reason
+    // the warehouse's tables are, below. `OCS_V2_STORE=file` keeps sessions and
+    // simulations on local disk and connects to no Redis at all.
+    //
+    // Idempotency-Key responses live next to the simulations they describe, so
+    // a retry that reaches another replica still finds the first response. A
+    // file-backed service is one node, so they stay in its memory.
+    let (store, simulation_store, idempotency_store): (
+        Arc<dyn SessionStore>,
+        Arc<dyn SimulationStore>,
+        Arc<dyn IdempotencyStore>,
+    ) = match v2_config.store {
+        SimulationStoreBackend::File => {
+            let file_config = FileStoreConfig::from_env()?;
+            (
+                Arc::new(InFileSessionStore::open(&file_config.dir, Some(3600))?),
+                Arc::new(
+                    InFileSimulationStore::open(
+                        &file_config.dir,
+                        Some(v2_config.retention_secs()),
+                    )?
+                    .with_max_pinned(v2_config.max_pinned),
+                ),
+                Arc::new(InMemoryIdempotencyStore::new(v2_config.idempotency_window)),
+            )
+        }
+        backend => {
+            let redis_config = RedisConfig::default();
+            info!("Connecting to Redis at {}", redis_config);
+            let redis_client = Arc::new(RedisClient::new(redis_config).await?);
+            let store = Arc::new(InRedisSessionStore::new(
+                Arc::clone(&redis_client),
+                Some("optionchain:session:".to_string()), // Custom key prefix
+                Some(3600),                               // 1 hour TTL
+            ));
+            let simulation_store: Arc<dyn SimulationStore> = if backend
+                == SimulationStoreBackend::MongoDb
+            {
+                let mongo_client = Arc::new(MongoDBClient::new(MongoDBConfig::default()).await?);
+                let simulation_store = MongoSimulationStore::new(
+                    mongo_client,
+                    None, // the documented v2 collection
+                    Some(v2_config.retention_secs()),
+                )
+                .await
+                .with_max_pinned(v2_config.max_pinned);
+                simulation_store.ensure_indexes().await?;
+                Arc::new(simulation_store)
+            } else {
+                let simulation_store = InRedisSimulationStore::new(
+                    Arc::clone(&redis_client),
+                    None, // the documented v2 prefix
+                    Some(v2_config.retention_secs()),
+                )
+                .with_max_pinned(v2_config.max_pinned);
+                simulation_store.ensure_indexes().await?;
+                Arc::new(simulation_store)
+            };
+            let idempotency_store = Arc::new(InRedisIdempotencyStore::new(
+                redis_client,
+                None, // the documented idempotency prefix
+                v2_config.idempotency_window,
+            ));
+            (store, simulation_store, idempotency_store)
+        }
+    };
+    info!(
+        backend = v2_config.store.as_str(),
+        "v2 simulation store selected"
+    );
 
     // Create a metrics collector
     let metrics_collector = Arc::new(MetricsCollector::new()?);
-    // Create a MongoDB repository
-    let mongodb_repository = init_mongodb().await?;
+    // Where v1 session events and chain steps are recorded. MongoDB unless
+    // `OCS_EVENT_SINK` says otherwise; with `ndjson` or `none` the service
+    // starts without a MongoDB.
+    let event_sink = init_event_sink(&EventSinkConfig::from_env()?).await?;
 
     // Create a session manager
-    let session_manager = Arc::new(SessionManager::new(store.clone()));
+    let session_manager = Arc::new(SessionManager::new(store));
 
-    // The v2 rolling simulations live in their own Redis key space, so a v2 id
-    // can never resolve a v1 session and a v1 document is never read back as
-    // rolling configuration (ADR 0001 section 12.2).
-    //
-    // Their operational limits are loaded and validated here, before anything
-    // binds: an invalid knob fails startup with a message naming the variable
-    // rather than silently reverting to a default that would change how long
-    // simulations live.
-    let v2_config = SimulationV2Config::from_env()?;
-    let simulation_store = Arc::new(InRedisSimulationStore::new(
-        redis_client_v2,
-        None, // the documented v2 prefix
-        Some(v2_config.retention_secs()),
-    ));
     // Snapshot persistence is opt-in (`OCS_SNAPSHOT_PERSISTENCE_ENABLED`). When
     // it is off the manager never learns the feature exists; when it is on, the
     // tables are created here rather than on the first advance, so a schema
@@ -158,12 +222,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // export reads back through the same handle — the routes take it off the
     // manager rather than being passed a second one, because two handles could
     // be configured differently and there is only ever one warehouse.
-    let mut simulation_manager = SimulationManager::new(simulation_store, v2_config);
+    let mut simulation_manager = SimulationManager::new(simulation_store, v2_config)
+        .with_idempotency_store(idempotency_store);
     match ClickHouseSnapshotRepository::from_env()? {
         Some(warehouse) => {
             warehouse.ensure_schema().await?;
             info!("v2 snapshot persistence is enabled");
-            simulation_manager = simulation_manager.with_warehouse(Arc::new(warehouse));
+            // A spool (`OCS_SNAPSHOT_SPOOL_DIR`) catches what the queue in
+            // front of the warehouse has no room for and what the warehouse
+            // failed to take; without one, either loses the step.
+            let spool_config = SnapshotSpoolConfig::from_env()?;
+            simulation_manager = match &spool_config.dir {
+                Some(dir) => simulation_manager.with_spooled_warehouse(
+                    Arc::new(warehouse),
+                    Arc::new(SnapshotSpool::open(dir, spool_config.max_bytes)?),
+                ),
+                None => simulation_manager.with_warehouse(Arc::new(warehouse)),
+            };
         }
         None => {
             info!("v2 snapshot persistence is disabled; snapshots are served from replay only");
@@ -197,19 +272,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
     let listen_on = ListenOn::All;
     let port = 7070;
+    let grpc_port = 7071;
+
+    // The gRPC surface runs next to actix over the same manager, so either
+    // protocol can drive a simulation the other created. Whichever server
+    // stops first takes the process down with it: a half-served API is harder
+    // to notice than a dead one.
+    let grpc = tokio::spawn(start_grpc_server(
+        Arc::clone(&simulation_manager),
+        listen_on,
+        grpc_port,
+    ));
+
     // Start HTTP server
     info!("Starting HTTP server at http://{}:{}", listen_on, port);
-    match start_server(
+    let http = start_server(
         session_manager,
         simulation_manager,
         metrics_collector,
-        mongodb_repository,
+        event_sink,
         listen_on,
         port,
-    )
-    .await
-    {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.to_string().into()),
+    );
+
+    tokio::select! {
+        result = http => match result {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string().into()),
+        },
+        result = grpc => match result {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string().into()),
+            Err(e) => Err(e.to_string().into()),
+        },
     }
 }
@@ -0,0 +1,45 @@
+//! The engine version: what a tape is a pure function of, besides its inputs.
+//!
+//! Replay is deterministic for one binary, not across every binary. The tape
+//! generation covers the changes made here on purpose; the pricing and
+//! arithmetic crates can move a quote in a patch release without anyone here
+//! touching a line. The engine version names all three, so a simulation
+//! created under one build can tell when it is being served by another.
+
+use crate::session::snapshot_record::SNAPSHOT_TAPE_GENERATION;
+use std::sync::LazyLock;
+
+/// The running engine version, built once.
+static ENGINE_VERSION: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "tape{SNAPSHOT_TAPE_GENERATION}+optionstratlib-{}+positive-{}",
+        env!("OCS_OPTIONSTRATLIB_VERSION"),
+        env!("OCS_POSITIVE_VERSION"),
+    )
+});
+
+/// The engine version of the running binary, e.g.
+/// `tape2+optionstratlib-0.18.1+positive-0.5.1`.
+///
+/// Two binaries with the same engine version generate the same tape from the
+/// same parameters. The crate version is deliberately not part of it: a
+/// release that only touches the API must not strand every simulation.
+#[must_use]
+pub fn engine_version() -> &'static str {
+    ENGINE_VERSION.as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The identifier names the tape generation and both locked dependencies.
+    #[test]
+    fn test_the_engine_version_names_its_parts() {
+        let version = engine_version();
+        assert!(version.starts_with(&format!("tape{SNAPSHOT_TAPE_GENERATION}+")));
+        assert!(version.contains("+optionstratlib-"));
+        assert!(version.contains("+positive-"));
+        assert!(!version.contains("unlocked"));
+    }
+}
@@ -24,16 +24,27 @@
 
 use crate::domain::factors::FactorTape;
 use crate::domain::series::{SeriesBuilder, SeriesSnapshot, SnapshotCache};
-use crate::infrastructure::{SimulationSnapshotRepository, SimulationV2Config, SnapshotRecord};
+use crate::infrastructure::{
+    EngineMismatchPolicy, SimulationSnapshotRepository, SimulationV2Config, SnapshotRecord,
+    SnapshotSpool,
+};
+use crate::session::engine::engine_version;
+use crate::session::manifest::SimulationManifest;
+use crate::session::materialize::{MaterializationStatus, Materializations, step_range};
 use crate::session::model::SessionState;
+use crate::session::playback::{PlaybackPace, PlaybackStatus, Playbacks};
 use crate::session::snapshot_record::{snapshot_quote_count, snapshot_record};
-use crate::session::store::SimulationStore;
-use crate::session::{SessionV2, SimulationParametersV2};
+use crate::session::store::{
+    IdempotencyStore, InMemoryIdempotencyStore, SimulationFilter, SimulationPage, SimulationStore,
+};
+use crate::session::webhooks::{Webhook, WebhookEvent, WebhookSpec, Webhooks};
+use crate::session::{SessionV2, SimulationOptions, SimulationParametersV2};
 use crate::utils::ChainError;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
@@ -64,6 +75,61 @@ pub struct SimulationManager {
     /// absent from the serving path — no connection, no latency, no failure
     /// mode.
     warehouse: Option<Warehouse>,
+    /// The server-driven playbacks this manager is running.
+    playbacks: Playbacks,
+    /// The background materializations this manager is running.
+    materializations: Materializations,
+    /// The registered webhooks and the queue their deliveries wait in.
+    webhooks: Webhooks,
+    /// Where `Idempotency-Key` responses are recorded. In memory unless the
+    /// binary hands it the Redis store the simulations live in.
+    idempotency: Arc<dyn IdempotencyStore>,
+    /// Every committed advance, for whoever is waiting on one. Sent only when
+    /// someone is subscribed, so an idle channel costs the advance nothing.
+    advances: broadcast::Sender<Advanced>,
+    /// Every simulation this process deleted or reaped, for the feeds that
+    /// would otherwise wait on it forever.
+    endings: broadcast::Sender<Ended>,
+}
+
+/// One committed advance: the simulation as it was persisted and the snapshot
+/// that advance served.
+#[derive(Clone)]
+pub(crate) struct Advanced {
+    /// The simulation after the advance.
+    pub(crate) simulation: SessionV2,
+    /// The snapshot the advance served. Shared, because every subscriber gets
+    /// its own copy of the event and a snapshot is the expensive part.
+    pub(crate) snapshot: Arc<SeriesSnapshot>,
+}
+
+/// A simulation that went away, and how.
+#[derive(Clone, Copy)]
+pub(crate) struct Ended {
+    /// The simulation.
+    pub(crate) id: Uuid,
+    /// How it went.
+    pub(crate) ending: Ending,
+}
+
+/// How a simulation went away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Ending {
+    /// A client deleted it.
+    Deleted,
+    /// The retention sweep reaped it.
+    Expired,
+}
+
+impl Ending {
+    /// The ending's name, as a feed sends it.
+    #[must_use]
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Ending::Deleted => "deleted",
+            Ending::Expired => "expired",
+        }
+    }
 }
 
 /// The queue in front of the warehouse, and what is currently in it.
@@ -76,6 +142,39 @@ struct Warehouse {
     /// decremented by the writer once the record leaves the queue, so it
     /// measures what is resident rather than what has been served.
     queued_contracts: Arc<AtomicUsize>,
+    /// Where a record the queue has no room for, or whose write failed, goes
+    /// instead of being lost, when the operator configured one.
+    spool: Option<Arc<SnapshotSpool>>,
+    /// The simulations purged from the warehouse, whose records still queued
+    /// or spooled the writer discards.
+    purged: Arc<PurgedSimulations>,
+}
+
+/// The simulations whose warehouse rows this process purged.
+///
+/// A purge does not reach what is already queued or spooled, so without this a
+/// step served just before a delete could be filed just after its purge. Ids
+/// are random and never reused, so an entry cannot suppress a live simulation;
+/// it costs one id per purge for the life of the process. It is not persisted:
+/// a record spooled before a restart and drained after it is filed, and a
+/// second purge removes it.
+#[derive(Default)]
+struct PurgedSimulations(Mutex<HashSet<Uuid>>);
+
+impl PurgedSimulations {
+    fn insert(&self, ids: &[Uuid]) {
+        match self.0.lock() {
+            Ok(mut purged) => purged.extend(ids),
+            Err(poisoned) => poisoned.into_inner().extend(ids),
+        }
+    }
+
+    fn contains(&self, id: Uuid) -> bool {
+        match self.0.lock() {
+            Ok(purged) => purged.contains(&id),
+            Err(poisoned) => poisoned.into_inner().contains(&id),
+        }
+    }
 }
 
 /// How many snapshots may be waiting to be filed.
@@ -96,9 +195,40 @@ const SNAPSHOT_QUEUE_DEPTH: usize = 1_024;
 ///
 /// Neither bound is a knob. A deployment that needs to tune them is one whose
 /// warehouse cannot keep up with its advance rate, and the answer there is the
-/// warehouse, not a deeper buffer in front of it.
+/// warehouse, not a deeper buffer in front of it. The optional spool is for a
+/// warehouse that is briefly away, not one that is too slow: it only drains
+/// while the queue is empty.
 const SNAPSHOT_QUEUE_CONTRACTS: usize = 4_000_000;
 
+/// How long the writer waits before retrying a spooled snapshot the warehouse
+/// refused, and how often an idle writer looks at the spool.
+///
+/// A refusal while draining is almost always the outage that filled the spool
+/// still going on; retrying it in a tight loop would only add load to a
+/// warehouse that is trying to come back.
+const SPOOL_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many times a touch re-reads after losing a compare-and-swap race.
+///
+/// A touch only loses to another write, and every write already refreshes the
+/// window, so the retries are a courtesy; a simulation being advanced this hard
+/// is in no danger of expiring.
+const TOUCH_ATTEMPTS: u32 = 3;
+
+/// How many committed advances a slow subscriber may fall behind by.
+///
+/// A waiter that lags further misses the oldest events rather than holding
+/// them — it is waiting for the *next* snapshot, and a newer one serves it
+/// better than a stale one.
+const ADVANCE_EVENT_CAPACITY: usize = 256;
+
+/// How many endings a slow subscriber may fall behind by.
+///
+/// One sweep can reap a whole batch at once. A feed that lags past this misses
+/// the announcement, not the ending: it still finds its simulation gone on its
+/// next keep-alive.
+const ENDING_EVENT_CAPACITY: usize = 1_024;
+
 impl SimulationManager {
     /// Creates a manager over a simulation store.
     ///
@@ -117,6 +247,19 @@ impl SimulationManager {
                 config.max_cached_snapshot_contracts,
             )),
             warehouse: None,
+            playbacks: Playbacks::new(config.max_playbacks),
+            materializations: Materializations::new(config.max_materializations),
+            webhooks: Webhooks::new(
+                config.max_webhooks,
+                Webhooks::policy(
+                    config.webhook_max_attempts,
+                    config.webhook_timeout,
+                    config.webhook_allow_private_targets,
+                ),
+            ),
+            idempotency: Arc::new(InMemoryIdempotencyStore::new(config.idempotency_window)),
+            advances: broadcast::channel(ADVANCE_EVENT_CAPACITY).0,
+            endings: broadcast::channel(ENDING_EVENT_CAPACITY).0,
         }
     }
 
@@ -127,43 +270,75 @@ impl SimulationManager {
     /// have to name the feature to not use it, and the serving path should not
     /// branch on a config flag it can express as a missing dependency.
     #[must_use]
-    pub fn with_warehouse(mut self, repository: Arc<dyn SimulationSnapshotRepository>) -> Self {
-        let (sender, mut receiver) = mpsc::channel::<SnapshotRecord>(SNAPSHOT_QUEUE_DEPTH);
+    pub fn with_warehouse(self, repository: Arc<dyn SimulationSnapshotRepository>) -> Self {
+        self.attach_warehouse(repository, None)
+    }
+
+    /// Files every served snapshot in `warehouse`, spilling to `spool` what
+    /// the queue in front of it has no room for.
+    ///
+    /// Without a spool, a full queue or a failed write loses the step, which
+    /// leaves a gap in the tape until someone replays it. With one, the step
+    /// is appended to disk and the writer files it once the queue has drained
+    /// and the warehouse takes writes again — including after a restart, since
+    /// the spool keeps what it holds. Only a spool that is full as well loses
+    /// a step.
+    #[must_use]
+    pub fn with_spooled_warehouse(
+        self,
+        repository: Arc<dyn SimulationSnapshotRepository>,
+        spool: Arc<SnapshotSpool>,
+    ) -> Self {
+        self.attach_warehouse(repository, Some(spool))
+    }
+
+    fn attach_warehouse(
+        mut self,
+        repository: Arc<dyn SimulationSnapshotRepository>,
+        spool: Option<Arc<SnapshotSpool>>,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel::<SnapshotRecord>(SNAPSHOT_QUEUE_DEPTH);
         let queued_contracts = Arc::new(AtomicUsize::new(0));
-        let writer_contracts = Arc::clone(&queued_contracts);
-        let warehouse = Arc::clone(&repository);
+        let purged = Arc::new(PurgedSimulations::default());
 
         // One writer, not one task per advance: the queue bounds what a slow
         // warehouse can accumulate, and serialising the writes means two steps
         // of one simulation reach the warehouse in the order they were served.
-        tokio::spawn(async move {
-            while let Some(record) = receiver.recv().await {
-                let simulation = record.simulation;
-                let step = record.step;
-                let contracts = record.quote_count();
-
-                let result = warehouse.persist(record).await;
-                writer_contracts.fetch_sub(contracts, Ordering::SeqCst);
-
-                if let Err(error) = result {
-                    warn!(
-                        simulation_id = %simulation,
-                        step,
-                        error = %error,
-                        "Could not file the snapshot; the step can be replayed and rewritten"
-                    );
-                }
-            }
-        });
+        tokio::spawn(run_writer(
+            Arc::clone(&repository),
+            receiver,
+            Arc::clone(&queued_contracts),
+            spool.clone(),
+            Arc::clone(&purged),
+        ));
 
         self.warehouse = Some(Warehouse {
             repository,
             sender,
             queued_contracts,
+            spool,
+            purged,
         });
         self
     }
 
+    /// Records `Idempotency-Key` responses in `store` instead of in memory.
+    ///
+    /// The binary passes the Redis store when the simulations live in Redis,
+    /// so a retry that lands on another replica — or on this one after a
+    /// restart — still finds the response to its first attempt.
+    #[must_use]
+    pub fn with_idempotency_store(mut self, store: Arc<dyn IdempotencyStore>) -> Self {
+        self.idempotency = store;
+        self
+    }
+
+    /// Where `Idempotency-Key` responses are recorded.
+    #[must_use]
+    pub(crate) fn idempotency(&self) -> &dyn IdempotencyStore {
+        self.idempotency.as_ref()
+    }
+
     /// The warehouse this manager files into, if any.
     ///
     /// Exists so the export can prefer persisted snapshots over replay without
@@ -183,7 +358,8 @@ impl SimulationManager {
         self.config
     }
 
-    /// Creates a simulation from resolved parameters.
+    /// Creates a simulation from resolved parameters and its non-replay
+    /// options.
     ///
     /// The factor tape is **not** built here. Creation stays cheap and
     /// predictable, and the first peek or advance pays for the tape — which it
@@ -193,12 +369,13 @@ impl SimulationManager {
     ///
     /// Returns [`ChainError::AlreadyExists`] on an id collision, or any storage
     /// failure.
-    #[instrument(skip(self, parameters), level = "debug")]
+    #[instrument(skip(self, parameters, options), level = "debug")]
     pub(crate) async fn create(
         &self,
         parameters: SimulationParametersV2,
+        options: SimulationOptions,
     ) -> Result<SessionV2, ChainError> {
-        let simulation = SessionV2::new(parameters);
+        let simulation = SessionV2::new(parameters).with_options(options);
         self.store.create(simulation.clone()).await?;
 
         info!(
@@ -220,6 +397,101 @@ impl SimulationManager {
         self.store.get(id).await
     }
 
+    /// The replay manifest of a simulation.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when no simulation has that id.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn manifest(&self, id: Uuid) -> Result<SimulationManifest, ChainError> {
+        let simulation = self.store.get(id).await?;
+        Ok(SimulationManifest::of(&simulation))
+    }
+
+    /// Recreates a simulation from a replay manifest, under a new id and with
+    /// the default options: tags, retention and pinning are not replay inputs,
+    /// so a manifest does not carry them.
+    ///
+    /// # Errors
+    ///
+    /// As [`SimulationManifest::into_parameters`] and
+    /// [`SimulationManager::create`].
+    #[instrument(skip(self, manifest), level = "debug")]
+    pub(crate) async fn import(
+        &self,
+        manifest: SimulationManifest,
+        force: bool,
+    ) -> Result<SessionV2, ChainError> {
+        let source_id = manifest.source_id;
+        let parameters = manifest.into_parameters(force)?;
+        let simulation = self
+            .create(parameters, SimulationOptions::default())
+            .await?;
+        info!(
+            simulation_id = %simulation.id,
+            source_id = %source_id,
+            "Imported a v2 simulation from a manifest"
+        );
+        Ok(simulation)
+    }
+
+    /// Restarts a simulation's idle-retention window without moving its cursor.
+    ///
+    /// Rewrites the document with a fresh `updated_at` through the same
+    /// compare-and-swap an advance uses, and bumps the revision like any other
+    /// write: the tape is unchanged, but the document a `GET` returns is not,
+    /// and the entity tag is built from the revision. Unlike an advance a
+    /// touch is idempotent, so losing the race to a concurrent writer is
+    /// retried here rather than surfaced — re-reading picks up that writer's
+    /// commit, and touching it is exactly as correct.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] for an unknown id,
+    /// [`ChainError::Conflict`] when every attempt lost a race, or any storage
+    /// failure.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn touch(&self, id: Uuid) -> Result<SessionV2, ChainError> {
+        let mut attempt = 1;
+        loop {
+            let mut simulation = self.store.get(id).await?;
+            let expected_version = simulation.version;
+            simulation.updated_at = SystemTime::now();
+            simulation.bump_version()?;
+
+            match self
+                .store
+                .save_cas(simulation.clone(), expected_version)
+                .await
+            {
+                Ok(()) => return Ok(simulation),
+                Err(ChainError::Conflict(_)) if attempt < TOUCH_ATTEMPTS => {
+                    attempt += 1;
+                    debug!(simulation_id = %id, attempt, "Touch lost a race; re-reading");
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Lists simulations matching `filter`, one page at a time.
+    ///
+    /// Metadata only: no tape or snapshot is built, so listing costs the same
+    /// whatever the simulations' sizes.
+    ///
+    /// # Errors
+    ///
+    /// Returns any storage failure.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn list(
+        &self,
+        filter: &SimulationFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SimulationPage, ChainError> {
+        self.store.list(filter, offset, limit).await
+    }
+
     /// Builds the snapshot at the current cursor **without** advancing or
     /// persisting anything.
     ///
@@ -261,8 +533,36 @@ impl SimulationManager {
     pub(crate) async fn advance(
         &self,
         id: Uuid,
+    ) -> Result<(SessionV2, SeriesSnapshot), ChainError> {
+        self.advance_expecting(id, None).await
+    }
+
+    /// [`SimulationManager::advance`], but only from revision
+    /// `expected_version` when one is given.
+    ///
+    /// What an HTTP `If-Match` asks for: the client names the revision it
+    /// read, and the compare-and-swap commits against that revision rather
+    /// than whichever one this call happens to read.
+    ///
+    /// # Errors
+    ///
+    /// As [`SimulationManager::advance`]; a simulation already past
+    /// `expected_version` is [`ChainError::Conflict`] without anything built.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn advance_expecting(
+        &self,
+        id: Uuid,
+        expected_version: Option<u64>,
     ) -> Result<(SessionV2, SeriesSnapshot), ChainError> {
         let mut simulation = self.store.get(id).await?;
+        if let Some(expected) = expected_version
+            && simulation.version != expected
+        {
+            return Err(ChainError::Conflict(format!(
+                "Simulation {id} was modified concurrently (expected version {expected}, found {})",
+                simulation.version
+            )));
+        }
 
         // The revision read here is what the compare-and-swap below commits
         // against, so two concurrent advances that both read this snapshot
@@ -293,6 +593,8 @@ impl SimulationManager {
         // cursor that served it is durable, and persisting first would leave a
         // row for a step a losing writer never served.
         self.file_snapshot(&simulation, &snapshot);
+        self.publish(&simulation, &snapshot);
+        self.webhooks.advanced(&simulation);
 
         if simulation.state == SessionState::Completed {
             self.evict(id);
@@ -302,19 +604,353 @@ impl SimulationManager {
         Ok((simulation, snapshot))
     }
 
+    /// Moves a simulation's cursor forward to `to_step` without serving the
+    /// steps in between.
+    ///
+    /// A jump, not a fast-forward: the skipped steps are neither priced nor
+    /// filed, so seeking across a long horizon costs one write. Nothing is
+    /// lost by it — every step stays reproducible by replay — but a warehouse
+    /// will hold no rows for the steps a seek skipped, and the simulation
+    /// records them as skipped so the event feed does not later pass them off
+    /// as served (see [`SessionV2::skipped`]). Seeking to the current
+    /// step is a no-op; seeking backwards is refused, because a step a client
+    /// has already been served must not be served again under a later
+    /// revision.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] on `to_step` when it is behind the
+    /// cursor or not before the last step, [`ChainError::Conflict`] when a
+    /// concurrent advance committed first, and otherwise as
+    /// [`SimulationManager::peek`].
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn seek(&self, id: Uuid, to_step: usize) -> Result<SessionV2, ChainError> {
+        let mut simulation = self.store.get(id).await?;
+        let expected_version = simulation.version;
+        Self::reject_terminal(&simulation, "nothing to seek to")?;
+
+        if to_step < simulation.current_step || to_step >= simulation.total_steps {
+            return Err(ChainError::Validation {
+                field: "to_step".to_string(),
+                reason: format!(
+                    "must be between the current step {} and the last step {}",
+                    simulation.current_step,
+                    simulation.total_steps - 1
+                ),
+            });
+        }
+        if to_step == simulation.current_step {
+            return Ok(simulation);
+        }
+
+        simulation.seek_to(to_step);
+        simulation.state = SessionState::InProgress;
+        simulation.bump_version()?;
+        self.store
+            .save_cas(simulation.clone(), expected_version)
+            .await?;
+
+        debug!(simulation_id = %id, to_step, "Seeked forward");
+        Ok(simulation)
+    }
+
+    /// Waits for the next advance of `id` to commit and returns what it
+    /// served, or `None` when `timeout` passes first.
+    ///
+    /// Whoever advances — a playback, a client's `POST /step` — the waiter sees
+    /// it. Subscribes **before** checking the simulation, so an advance that
+    /// commits between the check and the wait is not missed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] for an unknown id, and as
+    /// [`SimulationManager::peek`] for a simulation with no next step.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn next_snapshot(
+        &self,
+        id: Uuid,
+        timeout: Duration,
+    ) -> Result<Option<(SessionV2, Arc<SeriesSnapshot>)>, ChainError> {
+        let mut receiver = self.subscribe();
+        let simulation = self.store.get(id).await?;
+        Self::reject_terminal(&simulation, "no further steps")?;
+
+        let wait = async {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.simulation.id == id => {
+                        return Some((event.simulation, event.snapshot));
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+        Ok(tokio::time::timeout(timeout, wait).await.ok().flatten())
+    }
+
+    /// Rebuilds a snapshot `simulation` has already served.
+    ///
+    /// For a subscriber catching up on steps it missed: the tape is
+    /// deterministic, so the rebuilt snapshot is the one that was served, and
+    /// nothing about the simulation changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] on `step` when the simulation has not
+    /// served it yet, and whatever the tape or snapshot build surfaces.
+    pub(crate) async fn served_snapshot(
+        &self,
+        simulation: &SessionV2,
+        step: usize,
+    ) -> Result<SeriesSnapshot, ChainError> {
+        if step >= simulation.current_step {
+            return Err(ChainError::Validation {
+                field: "step".to_string(),
+                reason: format!(
+                    "step {step} has not been served; the cursor is at {}",
+                    simulation.current_step
+                ),
+            });
+        }
+        self.snapshot_at(simulation, step).await
+    }
+
+    /// Subscribes to every committed advance, of every simulation.
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<Advanced> {
+        self.advances.subscribe()
+    }
+
+    /// Subscribes to every simulation this process deletes or reaps.
+    pub(crate) fn subscribe_endings(&self) -> broadcast::Receiver<Ended> {
+        self.endings.subscribe()
+    }
+
+    /// Tells subscribers a simulation went away.
+    fn announce_end(&self, id: Uuid, ending: Ending) {
+        // An error means nobody is subscribed; nobody to tell.
+        let _ = self.endings.send(Ended { id, ending });
+    }
+
+    /// Tells subscribers an advance committed.
+    fn publish(&self, simulation: &SessionV2, snapshot: &SeriesSnapshot) {
+        if self.advances.receiver_count() == 0 {
+            return;
+        }
+        // An error means the last subscriber left in between; nobody to tell.
+        let _ = self.advances.send(Advanced {
+            simulation: simulation.clone(),
+            snapshot: Arc::new(snapshot.clone()),
+        });
+    }
+
+    /// Starts advancing `id` on its own, at `pace`.
+    ///
+    /// See [`crate::session::playback`] for what a playback guarantees and what
+    /// it does not.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] for a pace out of range,
+    /// [`ChainError::Conflict`] when the simulation is already playing or the
+    /// playback limit is reached, and as [`SimulationManager::peek`] for a
+    /// simulation with nothing left to play.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn start_playback(
+        self: &Arc<Self>,
+        id: Uuid,
+        pace: PlaybackPace,
+    ) -> Result<PlaybackStatus, ChainError> {
+        let simulation = self.store.get(id).await?;
+        Self::reject_terminal(&simulation, "nothing left to play")?;
+
+        let interval = pace.interval(simulation.parameters.step_interval_seconds)?;
+        self.playbacks.start(Arc::clone(self), id, interval)
+    }
+
+    /// Pauses `id`'s playback.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when it has none, or
+    /// [`ChainError::InvalidState`] when it has finished.
+    pub(crate) fn pause_playback(&self, id: Uuid) -> Result<PlaybackStatus, ChainError> {
+        self.playbacks.pause(id)
+    }
+
+    /// Resumes `id`'s paused playback.
+    ///
+    /// # Errors
+    ///
+    /// As [`SimulationManager::pause_playback`].
+    pub(crate) fn resume_playback(&self, id: Uuid) -> Result<PlaybackStatus, ChainError> {
+        self.playbacks.resume(id)
+    }
+
+    /// Stops `id`'s playback. Returns whether it had one.
+    pub(crate) fn stop_playback(&self, id: Uuid) -> bool {
+        self.playbacks.stop(id)
+    }
+
+    /// The status of `id`'s playback.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when it has none.
+    pub(crate) fn playback_status(&self, id: Uuid) -> Result<PlaybackStatus, ChainError> {
+        self.playbacks.status(id)
+    }
+
+    /// Starts filing `id`'s steps `from_step..=to_step` in the warehouse in
+    /// the background, without moving the cursor.
+    ///
+    /// See [`crate::session::materialize`] for what the job does with steps
+    /// the warehouse already holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::InvalidState`] when snapshot persistence is off,
+    /// [`ChainError::Validation`] for a range outside the tape,
+    /// [`ChainError::Conflict`] when the simulation is already being
+    /// materialized or the limit is reached, and as [`SimulationManager::get`]
+    /// for a simulation that cannot be read.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn start_materialization(
+        self: &Arc<Self>,
+        id: Uuid,
+        from_step: Option<usize>,
+        to_step: Option<usize>,
+    ) -> Result<MaterializationStatus, ChainError> {
+        let warehouse = self.warehouse().ok_or_else(no_warehouse)?;
+        let simulation = self.store.get(id).await?;
+        self.check_engine(&simulation)?;
+
+        let (from, to) = step_range(from_step, to_step, simulation.parameters.steps)?;
+        self.materializations
+            .start(Arc::clone(self), warehouse, simulation, from, to)
+    }
+
+    /// Cancels `id`'s materialization. What it already filed stays filed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when it has none.
+    pub(crate) fn cancel_materialization(
+        &self,
+        id: Uuid,
+    ) -> Result<MaterializationStatus, ChainError> {
+        self.materializations.cancel(id)
+    }
+
+    /// The status of `id`'s materialization.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when it has none.
+    pub(crate) fn materialization_status(
+        &self,
+        id: Uuid,
+    ) -> Result<MaterializationStatus, ChainError> {
+        self.materializations.status(id)
+    }
+
+    /// Prices `steps` of `simulation` as warehouse records, off the runtime.
+    ///
+    /// Bypasses the snapshot cache on purpose: a materialization walks a tape
+    /// once, and caching every step would evict the snapshots clients are
+    /// actually reading. The factor tape is shared, since every replay needs
+    /// the same one.
+    ///
+    /// # Errors
+    ///
+    /// Returns any failure to build the tape or price a step.
+    pub(crate) async fn materialize_records(
+        &self,
+        simulation: &SessionV2,
+        steps: Vec<usize>,
+    ) -> Result<Vec<SnapshotRecord>, ChainError> {
+        if steps.is_empty() {
+            return Ok(Vec::new());
+        }
+        let tape = self.tape_for(simulation).await?;
+        let parameters = simulation.parameters.clone();
+        let id = simulation.id;
+
+        tokio::task::spawn_blocking(move || {
+            let builder = SeriesBuilder::new(&parameters, &tape)?;
+            steps
+                .into_iter()
+                .map(|step| {
+                    let snapshot = builder.snapshot(step)?;
+                    Ok(snapshot_record(id, &parameters.symbol, &snapshot))
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| ChainError::Internal(format!("The materialization task failed: {e}")))?
+    }
+
+    /// Registers a webhook on `simulation`, or a global one for `None`.
+    ///
+    /// See [`crate::session::webhooks`] for the events and how they are
+    /// delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] for an unknown simulation, and
+    /// otherwise as [`Webhooks::register`].
+    #[instrument(skip(self, spec), level = "debug")]
+    pub(crate) async fn register_webhook(
+        &self,
+        simulation: Option<Uuid>,
+        spec: WebhookSpec,
+    ) -> Result<Webhook, ChainError> {
+        if let Some(id) = simulation {
+            self.store.get(id).await?;
+        }
+        self.webhooks.register(simulation, spec).await
+    }
+
+    /// The webhooks registered on `simulation`, or the global ones for `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] for an unknown simulation.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn webhooks(
+        &self,
+        simulation: Option<Uuid>,
+    ) -> Result<Vec<Webhook>, ChainError> {
+        if let Some(id) = simulation {
+            self.store.get(id).await?;
+        }
+        Ok(self.webhooks.list(simulation))
+    }
+
+    /// Removes a webhook registration. Returns whether there was one.
+    pub(crate) fn remove_webhook(&self, id: Uuid) -> bool {
+        self.webhooks.remove(id)
+    }
+
     /// Queues a served snapshot for filing, if a warehouse is configured.
     ///
     /// **Off the request's clock.** A failure cannot fail the advance — the
     /// cursor has already committed and the client already has its snapshot —
-    /// and neither can a slow one delay it: the record goes into a bounded
+    /// and a slow write does not hold it up: the record goes into a bounded
     /// queue that one writer task drains.
     ///
-    /// A full queue **drops** the record with a `WARN` naming the step. That is
-    /// the same trade as a failed write, made explicit: the step stays
-    /// reproducible, replay rebuilds it, and a retry writes the same rows. What
-    /// it costs is a gap, and the honest way to find one is to compare a
-    /// simulation's cursor against what `read_range` returns — a log line can be
-    /// lost with the process, a missing row cannot.
+    /// A full queue spills the record to the spool, if one is configured. That
+    /// append is synchronous on the advance path — one buffered write, no
+    /// `fsync` — so a full queue costs the advance a file append. The writer
+    /// also spools a record whose write failed, and files both once the
+    /// warehouse is back.
+    ///
+    /// Without a spool, or with a full one, the record is **lost** with a `WARN`
+    /// naming the step. The step stays reproducible, replay rebuilds it, and a
+    /// retry writes the same rows. What it costs is a gap, and the honest way
+    /// to find one is to compare a simulation's cursor against what
+    /// `read_range` returns — a log line can be lost with the process, a
+    /// missing row cannot.
     ///
     /// Filing is idempotent because both tables sort on
     /// `(simulation, generation, step, …)` and their `ReplacingMergeTree` engine
@@ -341,12 +977,18 @@ impl SimulationManager {
         if warehouse.sender.capacity() == 0
             || queued.saturating_add(incoming) > SNAPSHOT_QUEUE_CONTRACTS
         {
-            warn!(
-                simulation_id = %simulation.id,
-                step = snapshot.step,
-                queued,
-                "The snapshot queue is full; the step was not filed and can be replayed"
-            );
+            match &warehouse.spool {
+                Some(spool) => spool_snapshot(
+                    spool,
+                    snapshot_record(simulation.id, &simulation.parameters.symbol, snapshot),
+                ),
+                None => warn!(
+                    simulation_id = %simulation.id,
+                    step = snapshot.step,
+                    queued,
+                    "The snapshot queue is full; the step was not filed and can be replayed"
+                ),
+            }
             return;
         }
 
@@ -360,12 +1002,15 @@ impl SimulationManager {
             warehouse
                 .queued_contracts
                 .fetch_sub(incoming, Ordering::SeqCst);
-            warn!(
-                simulation_id = %simulation.id,
-                step = snapshot.step,
-                error = %error,
-                "The snapshot queue is full; the step was not filed and can be replayed"
-            );
+            match &warehouse.spool {
+                Some(spool) => spool_snapshot(spool, error.into_inner()),
+                None => warn!(
+                    simulation_id = %simulation.id,
+                    step = snapshot.step,
+                    error = %error,
+                    "The snapshot queue is full; the step was not filed and can be replayed"
+                ),
+            }
         }
     }
 
@@ -376,13 +1021,78 @@ impl SimulationManager {
     /// Returns any storage failure. A missing id is `Ok(false)`, not an error.
     #[instrument(skip(self), level = "debug")]
     pub(crate) async fn delete(&self, id: Uuid) -> Result<bool, ChainError> {
-        let deleted = self.store.delete(id).await?;
+        self.delete_expecting(id, None).await
+    }
+
+    /// [`SimulationManager::delete`], but only at revision `expected_version`
+    /// when one is given.
+    ///
+    /// # Errors
+    ///
+    /// As [`SimulationManager::delete`], plus [`ChainError::Conflict`] when the
+    /// stored revision differs; nothing is deleted or evicted then.
+    #[instrument(skip(self), level = "debug")]
+    pub(crate) async fn delete_expecting(
+        &self,
+        id: Uuid,
+        expected_version: Option<u64>,
+    ) -> Result<bool, ChainError> {
+        let deleted = match expected_version {
+            Some(expected) => self.store.delete_cas(id, expected).await?,
+            None => self.store.delete(id).await?,
+        };
         // Evict regardless: a delete that found nothing may still be cleaning
         // up after a simulation the store expired on its own.
         self.evict(id);
+        self.playbacks.stop(id);
+        self.materializations.stop(id);
+        if deleted {
+            self.webhooks.notify(WebhookEvent::Deleted, id, None);
+            self.announce_end(id, Ending::Deleted);
+        }
+        self.webhooks.forget(id);
         Ok(deleted)
     }
 
+    /// Removes every warehouse row of `ids` now, rather than at the table TTL.
+    ///
+    /// Only for simulations that are gone: a live one would be filed again by
+    /// its next advance, so it is refused. What this replica still has queued
+    /// or spooled for them is discarded rather than filed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::InvalidState`] when snapshot persistence is off,
+    /// [`ChainError::Conflict`] naming the first id that still exists, and the
+    /// warehouse's error when the purge fails — which is safe to retry.
+    #[instrument(skip(self, ids), fields(simulations = ids.len()), level = "debug")]
+    pub(crate) async fn purge_snapshots(&self, ids: &[Uuid]) -> Result<(), ChainError> {
+        let warehouse = self.warehouse.as_ref().ok_or_else(no_warehouse)?;
+        for id in ids {
+            match self.store.get(*id).await {
+                Ok(_) => {
+                    return Err(ChainError::Conflict(format!(
+                        "Simulation {id} still exists; delete it before purging its snapshots"
+                    )));
+                }
+                Err(ChainError::NotFound(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        warehouse.purged.insert(ids);
+        for id in ids {
+            self.materializations.stop(*id);
+        }
+        warehouse.repository.purge(ids).await?;
+
+        info!(
+            simulations = ids.len(),
+            "Purged the warehouse rows of deleted simulations"
+        );
+        Ok(())
+    }
+
     /// Expires idle simulations and evicts everything cached for them.
     ///
     /// Returns the ids that went, which is what makes the eviction possible at
@@ -396,6 +1106,11 @@ impl SimulationManager {
         let expired = self.store.cleanup().await?;
         for id in &expired {
             self.evict(*id);
+            self.playbacks.stop(*id);
+            self.materializations.stop(*id);
+            self.webhooks.notify(WebhookEvent::Expired, *id, None);
+            self.webhooks.forget(*id);
+            self.announce_end(*id, Ending::Expired);
         }
         Ok(expired)
     }
@@ -436,16 +1151,49 @@ impl SimulationManager {
         Ok(())
     }
 
+    /// Rejects pricing a simulation created under a different engine, when the
+    /// configured policy says to.
+    ///
+    /// Under [`EngineMismatchPolicy::Warn`] this passes: the load already
+    /// logged the mismatch. Reading, deleting and taking the manifest are not
+    /// gated — they are how a refused simulation gets recreated.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] on `engine_version` under
+    /// [`EngineMismatchPolicy::Refuse`] when the recorded engine differs from
+    /// the running one.
+    pub(crate) fn check_engine(&self, simulation: &SessionV2) -> Result<(), ChainError> {
+        match (
+            self.config.engine_mismatch,
+            simulation.parameters.engine_drift(),
+        ) {
+            (EngineMismatchPolicy::Refuse, Some(recorded)) => Err(ChainError::Validation {
+                field: "engine_version".to_string(),
+                reason: format!(
+                    "simulation was created under engine {recorded}, this service runs {}; \
+                     its tape would differ. Recreate it, or import its manifest with force=true",
+                    engine_version()
+                ),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// Returns the snapshot at `step`, building whatever is missing.
     ///
     /// Locks are held only for the map operations, never across a build: the
     /// tape and the snapshot are produced outside any critical section, so a
     /// slow build cannot stall another simulation's request.
+    ///
+    /// Every snapshot path comes through here, so this is where the engine
+    /// policy is applied; see [`Self::check_engine`].
     async fn snapshot_at(
         &self,
         simulation: &SessionV2,
         step: usize,
     ) -> Result<SeriesSnapshot, ChainError> {
+        self.check_engine(simulation)?;
         if let Some(cached) = self.cached_snapshot(simulation.id, step) {
             return Ok(cached);
         }
@@ -671,15 +1419,191 @@ impl SimulationManager {
     }
 }
 
+/// The warehouse writer: files queued records as they arrive and, whenever
+/// the queue is empty, drains the spool behind it.
+///
+/// Queued records always go first. They are the live tape, and a spool being
+/// drained ahead of them would turn a backlog into a second one.
+async fn run_writer(
+    warehouse: Arc<dyn SimulationSnapshotRepository>,
+    mut receiver: mpsc::Receiver<SnapshotRecord>,
+    queued_contracts: Arc<AtomicUsize>,
+    spool: Option<Arc<SnapshotSpool>>,
+    purged: Arc<PurgedSimulations>,
+) {
+    let mut retry_at: Option<Instant> = None;
+    loop {
+        let draining = spool.as_ref().filter(|spool| {
+            retry_at.is_none_or(|at| Instant::now() >= at) && spool.has_pending().unwrap_or(false)
+        });
+
+        let received = match (draining, &spool) {
+            (Some(spool), _) => match receiver.try_recv() {
+                Ok(record) => Some(record),
+                Err(TryRecvError::Disconnected) => None,
+                Err(TryRecvError::Empty) => {
+                    retry_at = drain_spooled(warehouse.as_ref(), spool, &purged).await;
+                    continue;
+                }
+            },
+            // Wake up now and then even when nothing is queued: the spool may
+            // be waiting out a retry, or hold records from before a restart.
+            (None, Some(_)) => {
+                match tokio::time::timeout(SPOOL_RETRY_INTERVAL, receiver.recv()).await {
+                    Ok(received) => received,
+                    Err(_) => continue,
+                }
+            }
+            (None, None) => receiver.recv().await,
+        };
+        let Some(record) = received else {
+            break;
+        };
+
+        let simulation = record.simulation;
+        let step = record.step;
+        let contracts = record.quote_count();
+
+        if purged.contains(simulation) {
+            queued_contracts.fetch_sub(contracts, Ordering::SeqCst);
+            debug!(simulation_id = %simulation, step, "Discarding a snapshot of a purged simulation");
+            continue;
+        }
+
+        // With a spool, a failed write is retried from it rather than lost. A
+        // warehouse that is down fails fast, so the queue keeps draining through
+        // an outage and would otherwise never overflow into the spool at all.
+        let retained = spool.as_ref().map(|spool| (spool, record.clone()));
+        let result = warehouse.persist(record).await;
+        queued_contracts.fetch_sub(contracts, Ordering::SeqCst);
+
+        let Err(error) = result else {
+            continue;
+        };
+        match retained {
+            // Rejected as invalid, it would be rejected on every retry too.
+            Some((spool, record)) if !matches!(error, ChainError::Validation { .. }) => {
+                warn!(
+                    simulation_id = %simulation,
+                    step,
+                    error = %error,
+                    "Could not file the snapshot; spooling it for a retry"
+                );
+                spool_snapshot(spool, record);
+            }
+            _ => warn!(
+                simulation_id = %simulation,
+                step,
+                error = %error,
+                "Could not file the snapshot; the step can be replayed and rewritten"
+            ),
+        }
+    }
+}
+
+/// The error for an operation that needs the warehouse when there is none.
+#[cold]
+fn no_warehouse() -> ChainError {
+    ChainError::InvalidState(
+        "Snapshot persistence is not enabled; there is no warehouse".to_string(),
+    )
+}
+
+/// Files the oldest spooled record and acknowledges it.
+///
+/// Returns when to try again if the warehouse refused it, and `None` to carry
+/// on. A record the warehouse rejects as invalid is acknowledged anyway: it
+/// would be rejected on every retry, and left at the head of the spool it
+/// would hold back everything behind it.
+async fn drain_spooled(
+    warehouse: &dyn SimulationSnapshotRepository,
+    spool: &Arc<SnapshotSpool>,
+    purged: &PurgedSimulations,
+) -> Option<Instant> {
+    let retry = Some(Instant::now() + SPOOL_RETRY_INTERVAL);
+
+    let reader = Arc::clone(spool);
+    let (record, position) = match tokio::task::spawn_blocking(move || reader.peek()).await {
+        Ok(Ok(Some(next))) => next,
+        Ok(Ok(None)) => return None,
+        Ok(Err(error)) => {
+            warn!(error = %error, "Could not read the snapshot spool");
+            return retry;
+        }
+        Err(error) => {
+            warn!(error = %error, "The snapshot spool read failed");
+            return retry;
+        }
+    };
+
+    let simulation = record.simulation;
+    let step = record.step;
+    if purged.contains(simulation) {
+        debug!(simulation_id = %simulation, step, "Discarding a spooled snapshot of a purged simulation");
+    } else {
+        match warehouse.persist(record).await {
+            Ok(()) => debug!(simulation_id = %simulation, step, "Filed a spooled snapshot"),
+            Err(error @ ChainError::Validation { .. }) => warn!(
+                simulation_id = %simulation,
+                step,
+                error = %error,
+                "Discarding a spooled snapshot the warehouse rejects; the step can be replayed"
+            ),
+            Err(error) => {
+                warn!(
+                    simulation_id = %simulation,
+                    step,
+                    error = %error,
+                    "Could not file a spooled snapshot; it stays spooled"
+                );
+                return retry;
+            }
+        }
+    }
+
+    match spool.ack(position) {
+        Ok(()) => None,
+        Err(error) => {
+            warn!(error = %error, "Could not acknowledge a spooled snapshot");
+            retry
+        }
+    }
+}
+
+/// Appends a record the warehouse did not take to the spool: one the queue
+/// had no room for, or one whose write failed.
+///
+/// Runs on the advance path for the first, so it is one buffered append and
+/// no `fsync`. A spool that is full as well loses the step, which then needs
+/// a replay to reach the warehouse.
+fn spool_snapshot(spool: &SnapshotSpool, record: SnapshotRecord) {
+    match spool.append(&record) {
+        Ok(()) => debug!(
+            simulation_id = %record.simulation,
+            step = record.step,
+            "The step was spooled to disk for the writer to file"
+        ),
+        Err(error) => warn!(
+            simulation_id = %record.simulation,
+            step = record.step,
+            error = %error,
+            "The snapshot spool is full; the step was not filed and can be replayed"
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::api::rest::models::{ApiTimeFrame, ApiWalkType};
     use crate::api::rest::requests_v2::CreateSimulationRequest;
     use crate::infrastructure::{ContractQuote, ContractSeriesQuery, SnapshotRecord};
+    use crate::session::WebhookSpec;
     use crate::session::store::InMemorySimulationStore;
     use crate::session::{ExpiryRule, ExpiryRuleKind};
+    use crate::session::{MaterializationState, PlaybackState};
     use chrono::{TimeZone, Utc, Weekday};
+    use std::sync::atomic::AtomicBool;
 
     fn request(steps: usize) -> CreateSimulationRequest {
         let rules = vec![
@@ -726,6 +1650,9 @@ mod tests {
             smile_curve: None,
             spread: Some(0.02),
             seed: Some(42),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         }
     }
 
@@ -748,14 +1675,34 @@ mod tests {
     #[derive(Default)]
     struct RecordingWarehouse {
         filed: Mutex<Vec<(Uuid, usize)>>,
-        fail: bool,
+        records: Mutex<Vec<SnapshotRecord>>,
+        fail: AtomicBool,
     }
 
     impl RecordingWarehouse {
         fn failing() -> Self {
             Self {
-                filed: Mutex::new(Vec::new()),
-                fail: true,
+                fail: AtomicBool::new(true),
+                ..Self::default()
+            }
+        }
+
+        /// Brings a failing warehouse back: writes from here on succeed.
+        fn recover(&self) {
+            self.fail.store(false, Ordering::SeqCst);
+        }
+
+        fn held(&self, simulation: Uuid) -> usize {
+            match self.records.lock() {
+                Ok(records) => records
+                    .iter()
+                    .filter(|record| record.simulation == simulation)
+                    .count(),
+                Err(poisoned) => poisoned
+                    .into_inner()
+                    .iter()
+                    .filter(|record| record.simulation == simulation)
+                    .count(),
             }
         }
 
@@ -770,13 +1717,17 @@ mod tests {
     #[async_trait::async_trait]
     impl SimulationSnapshotRepository for RecordingWarehouse {
         async fn persist(&self, record: SnapshotRecord) -> Result<(), ChainError> {
-            if self.fail {
+            if self.fail.load(Ordering::SeqCst) {
                 return Err(ChainError::Internal("the warehouse is down".to_string()));
             }
             match self.filed.lock() {
                 Ok(mut filed) => filed.push((record.simulation, record.step)),
                 Err(poisoned) => poisoned.into_inner().push((record.simulation, record.step)),
             }
+            match self.records.lock() {
+                Ok(mut records) => records.push(record),
+                Err(poisoned) => poisoned.into_inner().push(record),
+            }
             Ok(())
         }
 
@@ -791,12 +1742,21 @@ mod tests {
 
         async fn read_range(
             &self,
-            _simulation: Uuid,
+            simulation: Uuid,
             _generation: u64,
-            _from_step: usize,
-            _to_step: usize,
+            from_step: usize,
+            to_step: usize,
         ) -> Result<Vec<SnapshotRecord>, ChainError> {
-            Ok(Vec::new())
+            let records = match self.records.lock() {
+                Ok(records) => records.clone(),
+                Err(poisoned) => poisoned.into_inner().clone(),
+            };
+            Ok(records
+                .into_iter()
+                .filter(|record| {
+                    record.simulation == simulation && (from_step..=to_step).contains(&record.step)
+                })
+                .collect())
         }
 
         async fn contract_series(
@@ -805,6 +1765,18 @@ mod tests {
         ) -> Result<Vec<ContractQuote>, ChainError> {
             Ok(Vec::new())
         }
+
+        async fn purge(&self, simulations: &[Uuid]) -> Result<(), ChainError> {
+            match self.records.lock() {
+                Ok(mut records) => {
+                    records.retain(|record| !simulations.contains(&record.simulation));
+                }
+                Err(poisoned) => poisoned
+                    .into_inner()
+                    .retain(|record| !simulations.contains(&record.simulation)),
+            }
+            Ok(())
+        }
     }
 
     /// A warehouse whose first write never completes — the shape a degraded
@@ -853,6 +1825,10 @@ mod tests {
         ) -> Result<Vec<ContractQuote>, ChainError> {
             Ok(Vec::new())
         }
+
+        async fn purge(&self, _simulations: &[Uuid]) -> Result<(), ChainError> {
+            Ok(())
+        }
     }
 
     /// Filing is detached, so a test has to let the spawned write run before it
@@ -1000,6 +1976,191 @@ mod tests {
         );
     }
 
+    fn spool(dir: &tempfile::TempDir) -> Arc<SnapshotSpool> {
+        match SnapshotSpool::open(dir.path(), u64::MAX) {
+            Ok(spool) => Arc::new(spool),
+            Err(error) => panic!("the spool must open: {error}"),
+        }
+    }
+
+    /// With a spool, what a stalled warehouse's queue has no room for is
+    /// spooled to disk instead of dropped.
+    #[tokio::test]
+    async fn test_a_stalled_warehouse_spills_to_the_spool() {
+        let dir = match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        };
+        let spool = spool(&dir);
+        let warehouse = Arc::new(StallingWarehouse::default());
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        )
+        .with_spooled_warehouse(
+            Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>,
+            Arc::clone(&spool),
+        );
+
+        for _ in 0..(SNAPSHOT_QUEUE_DEPTH + 8) {
+            let simulation = created(&manager, 2).await;
+            match manager.advance(simulation.id).await {
+                Ok(_) => {}
+                Err(error) => panic!("the advance must serve regardless: {error}"),
+            }
+        }
+        settle().await;
+
+        let mut spooled = 0;
+        loop {
+            match spool.peek() {
+                Ok(Some((_, position))) => {
+                    spooled += 1;
+                    if let Err(error) = spool.ack(position) {
+                        panic!("the ack must succeed: {error}");
+                    }
+                }
+                Ok(None) => break,
+                Err(error) => panic!("the spool must be readable: {error}"),
+            }
+        }
+        assert!(
+            spooled >= 7,
+            "the advances the queue could not hold must be spooled, got {spooled}"
+        );
+    }
+
+    /// Records left in the spool — by an outage before a restart — are filed
+    /// once the writer finds the queue empty, and leave the spool.
+    #[tokio::test]
+    async fn test_the_writer_drains_the_spool() {
+        let dir = match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        };
+        let spool = spool(&dir);
+        let simulation = Uuid::new_v4();
+        for step in 0..3 {
+            let record = SnapshotRecord::new(
+                simulation,
+                1,
+                engine_version().to_string(),
+                step,
+                Utc::now(),
+                "SPX".to_string(),
+                positive::pos_or_panic!(5000.0),
+                positive::pos_or_panic!(0.18),
+                Vec::new(),
+            );
+            if let Err(error) = spool.append(&record) {
+                panic!("the append must succeed: {error}");
+            }
+        }
+
+        let warehouse = Arc::new(RecordingWarehouse::default());
+        let _manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        )
+        .with_spooled_warehouse(
+            Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>,
+            Arc::clone(&spool),
+        );
+
+        // The reads run on the blocking pool, so yielding alone may not reach
+        // them; give the writer real time, bounded.
+        for _ in 0..200 {
+            if warehouse.filed().len() == 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            warehouse.filed(),
+            vec![(simulation, 0), (simulation, 1), (simulation, 2)]
+        );
+        assert!(matches!(spool.has_pending(), Ok(false)));
+    }
+
+    /// A purge removes a deleted simulation's rows, and refuses a simulation
+    /// that still exists — its next advance would file it again.
+    #[tokio::test]
+    async fn test_a_purge_needs_the_simulation_deleted() {
+        let warehouse = Arc::new(RecordingWarehouse::default());
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        )
+        .with_warehouse(Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>);
+        let simulation = created(&manager, 3).await;
+        if let Err(error) = manager.advance(simulation.id).await {
+            panic!("the advance must serve: {error}");
+        }
+        for _ in 0..200 {
+            if warehouse.held(simulation.id) == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        match manager.purge_snapshots(&[simulation.id]).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a live simulation must not be purged, got {other:?}"),
+        }
+        assert_eq!(warehouse.held(simulation.id), 1);
+
+        match manager.delete(simulation.id).await {
+            Ok(deleted) => assert!(deleted),
+            Err(error) => panic!("the delete must succeed: {error}"),
+        }
+        if let Err(error) = manager.purge_snapshots(&[simulation.id]).await {
+            panic!("the purge must succeed: {error}");
+        }
+        assert_eq!(warehouse.held(simulation.id), 0);
+    }
+
+    /// Without a warehouse there is nothing to purge, and the purge says so.
+    #[tokio::test]
+    async fn test_a_purge_needs_a_warehouse() {
+        match manager().purge_snapshots(&[Uuid::new_v4()]).await {
+            Err(ChainError::InvalidState(_)) => {}
+            other => panic!("the purge must be refused, got {other:?}"),
+        }
+    }
+
+    /// A spooled record of a purged simulation is discarded, not filed back
+    /// into the warehouse the purge just emptied.
+    #[tokio::test]
+    async fn test_a_purged_simulation_is_not_filed_from_the_spool() {
+        let dir = match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        };
+        let spool = spool(&dir);
+        let simulation = Uuid::new_v4();
+        let record = SnapshotRecord::new(
+            simulation,
+            1,
+            engine_version().to_string(),
+            0,
+            Utc::now(),
+            "SPX".to_string(),
+            positive::pos_or_panic!(5000.0),
+            positive::pos_or_panic!(0.18),
+            Vec::new(),
+        );
+        if let Err(error) = spool.append(&record) {
+            panic!("the append must succeed: {error}");
+        }
+        let purged = PurgedSimulations::default();
+        purged.insert(&[simulation]);
+        let warehouse = RecordingWarehouse::default();
+
+        assert_eq!(drain_spooled(&warehouse, &spool, &purged).await, None);
+        assert!(warehouse.filed().is_empty(), "nothing must be filed");
+        assert!(matches!(spool.has_pending(), Ok(false)));
+    }
+
     /// A warehouse that is down does not fail the advance. This is the whole
     /// point of filing after the commit and off the request's clock.
     #[tokio::test]
@@ -1017,48 +2178,255 @@ mod tests {
             Ok((advanced, _)) => assert_eq!(advanced.current_step, 1, "the cursor still moved"),
             Err(error) => panic!("a warehouse failure must not fail the advance: {error}"),
         }
-        settle().await;
+        settle().await;
+    }
+
+    /// With a spool, a step whose write failed is spooled rather than lost,
+    /// and lands once the warehouse takes writes again.
+    ///
+    /// A warehouse that is down fails fast, so the queue never fills during an
+    /// outage; without this, the spool would only ever see an overflow.
+    #[tokio::test]
+    async fn test_a_failed_write_is_spooled_and_filed_on_recovery() {
+        let dir = match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        };
+        let spool = spool(&dir);
+        let warehouse = Arc::new(RecordingWarehouse::failing());
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        )
+        .with_spooled_warehouse(
+            Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>,
+            Arc::clone(&spool),
+        );
+
+        let simulation = created(&manager, 3).await;
+        if let Err(error) = manager.advance(simulation.id).await {
+            panic!("the advance must serve regardless: {error}");
+        }
+        for _ in 0..200 {
+            if matches!(spool.has_pending(), Ok(true)) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        match spool.peek() {
+            Ok(Some((record, _))) => {
+                assert_eq!((record.simulation, record.step), (simulation.id, 0));
+            }
+            other => panic!("the failed step must be spooled, got {other:?}"),
+        }
+        assert!(warehouse.filed().is_empty());
+
+        // The writer retries on its backoff, so recovery takes up to one
+        // interval to be noticed.
+        warehouse.recover();
+        let deadline = Instant::now() + SPOOL_RETRY_INTERVAL * 3;
+        while warehouse.filed().is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(warehouse.filed(), vec![(simulation.id, 0)]);
+        for _ in 0..200 {
+            if matches!(spool.has_pending(), Ok(false)) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(matches!(spool.has_pending(), Ok(false)));
+    }
+
+    /// A peek serves a snapshot and files nothing: it moves no cursor, so there
+    /// is no step to file.
+    #[tokio::test]
+    async fn test_a_peek_files_nothing() {
+        let warehouse = Arc::new(RecordingWarehouse::default());
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config::default(),
+        )
+        .with_warehouse(Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>);
+
+        let simulation = created(&manager, 3).await;
+        match manager.peek(simulation.id).await {
+            Ok(_) => {}
+            Err(error) => panic!("the peek must serve: {error}"),
+        }
+        settle().await;
+
+        assert!(warehouse.filed().is_empty(), "a peek persists nothing");
+    }
+
+    /// Without a warehouse the serving path is unchanged — there is nothing to
+    /// call and nothing to fail.
+    #[tokio::test]
+    async fn test_a_manager_without_a_warehouse_serves_normally() {
+        let manager = manager();
+        let simulation = created(&manager, 2).await;
+
+        match manager.advance(simulation.id).await {
+            Ok((advanced, snapshot)) => {
+                assert_eq!(advanced.current_step, 1);
+                assert_eq!(snapshot.step, 0);
+            }
+            Err(error) => panic!("the advance must serve: {error}"),
+        }
+    }
+
+    /// Polls `id`'s materialization until it has ended, bounded.
+    async fn materialized(manager: &SimulationManager, id: Uuid) -> MaterializationStatus {
+        for _ in 0..500 {
+            match manager.materialization_status(id) {
+                Ok(status) if status.state != MaterializationState::Running => return status,
+                Ok(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                Err(error) => panic!("the materialization must be readable: {error}"),
+            }
+        }
+        panic!("the materialization must end");
+    }
+
+    /// A materialization files every step of the range and leaves the cursor
+    /// where it was.
+    #[tokio::test]
+    async fn test_a_materialization_files_every_step_without_moving_the_cursor() {
+        let warehouse = Arc::new(RecordingWarehouse::default());
+        let manager = Arc::new(
+            SimulationManager::new(
+                Arc::new(InMemorySimulationStore::new()),
+                SimulationV2Config::default(),
+            )
+            .with_warehouse(Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>),
+        );
+        let simulation = created(&manager, 5).await;
+
+        match manager
+            .start_materialization(simulation.id, None, None)
+            .await
+        {
+            Ok(status) => assert_eq!((status.from_step, status.to_step), (0, 4)),
+            Err(error) => panic!("the materialization must start: {error}"),
+        }
+        let status = materialized(&manager, simulation.id).await;
+
+        assert_eq!(status.state, MaterializationState::Completed);
+        assert_eq!((status.steps_filed, status.steps_skipped), (5, 0));
+        let mut filed = warehouse.filed();
+        filed.sort_unstable();
+        assert_eq!(
+            filed,
+            (0..5).map(|step| (simulation.id, step)).collect::<Vec<_>>()
+        );
+        match manager.get(simulation.id).await {
+            Ok(loaded) => assert_eq!(loaded.current_step, 0, "the cursor must not move"),
+            Err(error) => panic!("the simulation must load: {error}"),
+        }
+    }
+
+    /// Steps the warehouse already holds are skipped, not priced again.
+    #[tokio::test]
+    async fn test_a_materialization_skips_steps_already_filed() {
+        let warehouse = Arc::new(RecordingWarehouse::default());
+        let manager = Arc::new(
+            SimulationManager::new(
+                Arc::new(InMemorySimulationStore::new()),
+                SimulationV2Config::default(),
+            )
+            .with_warehouse(Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>),
+        );
+        let simulation = created(&manager, 5).await;
+        for _ in 0..2 {
+            if let Err(error) = manager.advance(simulation.id).await {
+                panic!("the advance must serve: {error}");
+            }
+        }
+        for _ in 0..200 {
+            if warehouse.filed().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        if let Err(error) = manager
+            .start_materialization(simulation.id, Some(1), Some(3))
+            .await
+        {
+            panic!("the materialization must start: {error}");
+        }
+        let status = materialized(&manager, simulation.id).await;
+
+        assert_eq!(status.state, MaterializationState::Completed);
+        assert_eq!((status.steps_filed, status.steps_skipped), (2, 1));
+        let mut filed = warehouse.filed();
+        filed.sort_unstable();
+        assert_eq!(
+            filed,
+            (0..4).map(|step| (simulation.id, step)).collect::<Vec<_>>()
+        );
     }
 
-    /// A peek serves a snapshot and files nothing: it moves no cursor, so there
-    /// is no step to file.
+    /// A cancelled job ends as cancelled, and a second job cannot start while
+    /// one is running.
     #[tokio::test]
-    async fn test_a_peek_files_nothing() {
-        let warehouse = Arc::new(RecordingWarehouse::default());
-        let manager = SimulationManager::new(
-            Arc::new(InMemorySimulationStore::new()),
-            SimulationV2Config::default(),
-        )
-        .with_warehouse(Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>);
+    async fn test_a_materialization_can_be_cancelled() {
+        let warehouse = Arc::new(StallingWarehouse::default());
+        let manager = Arc::new(
+            SimulationManager::new(
+                Arc::new(InMemorySimulationStore::new()),
+                SimulationV2Config::default(),
+            )
+            .with_warehouse(Arc::clone(&warehouse) as Arc<dyn SimulationSnapshotRepository>),
+        );
+        let simulation = created(&manager, 5).await;
 
-        let simulation = created(&manager, 3).await;
-        match manager.peek(simulation.id).await {
-            Ok(_) => {}
-            Err(error) => panic!("the peek must serve: {error}"),
+        if let Err(error) = manager
+            .start_materialization(simulation.id, None, None)
+            .await
+        {
+            panic!("the materialization must start: {error}");
+        }
+        match manager
+            .start_materialization(simulation.id, None, None)
+            .await
+        {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a second job must be refused, got {other:?}"),
         }
-        settle().await;
 
-        assert!(warehouse.filed().is_empty(), "a peek persists nothing");
+        match manager.cancel_materialization(simulation.id) {
+            Ok(status) => assert_eq!(status.state, MaterializationState::Cancelled),
+            Err(error) => panic!("the cancel must succeed: {error}"),
+        }
+        let status = materialized(&manager, simulation.id).await;
+        assert_eq!(status.state, MaterializationState::Cancelled);
+        assert_eq!(status.steps_filed, 0, "the stalled write never completed");
     }
 
-    /// Without a warehouse the serving path is unchanged — there is nothing to
-    /// call and nothing to fail.
+    /// Without a warehouse there is nowhere to file, and the start says so.
     #[tokio::test]
-    async fn test_a_manager_without_a_warehouse_serves_normally() {
-        let manager = manager();
+    async fn test_a_materialization_needs_a_warehouse() {
+        let manager = Arc::new(manager());
         let simulation = created(&manager, 2).await;
 
-        match manager.advance(simulation.id).await {
-            Ok((advanced, snapshot)) => {
-                assert_eq!(advanced.current_step, 1);
-                assert_eq!(snapshot.step, 0);
-            }
-            Err(error) => panic!("the advance must serve: {error}"),
+        match manager
+            .start_materialization(simulation.id, None, None)
+            .await
+        {
+            Err(ChainError::InvalidState(_)) => {}
+            other => panic!("the start must be refused, got {other:?}"),
+        }
+        match manager.materialization_status(simulation.id) {
+            Err(ChainError::NotFound(_)) => {}
+            other => panic!("no job must be registered, got {other:?}"),
         }
     }
 
     async fn created(manager: &SimulationManager, steps: usize) -> SessionV2 {
-        match manager.create(parameters(steps)).await {
+        match manager
+            .create(parameters(steps), SimulationOptions::default())
+            .await
+        {
             Ok(simulation) => simulation,
             Err(error) => panic!("the simulation must be created: {error}"),
         }
@@ -1266,6 +2634,46 @@ mod tests {
         assert!(manager.get(created.id).await.is_err());
     }
 
+    /// A stale revision is a conflict for an advance and a delete alike, and
+    /// neither touches the simulation; the current revision goes through.
+    #[tokio::test]
+    async fn test_conditional_advance_and_delete_honour_the_revision() {
+        let manager = manager();
+        let created = created(&manager, 5).await;
+        let stale = created.version + 1;
+
+        match manager.advance_expecting(created.id, Some(stale)).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a stale advance must conflict, got {other:?}"),
+        }
+        match manager.delete_expecting(created.id, Some(stale)).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a stale delete must conflict, got {other:?}"),
+        }
+        assert_eq!(
+            manager.cached_tapes(),
+            0,
+            "a refused advance builds nothing"
+        );
+
+        let advanced = match manager
+            .advance_expecting(created.id, Some(created.version))
+            .await
+        {
+            Ok((simulation, _)) => simulation,
+            Err(error) => panic!("the current revision must advance: {error}"),
+        };
+        assert_eq!(advanced.current_step, 1);
+
+        match manager
+            .delete_expecting(created.id, Some(advanced.version))
+            .await
+        {
+            Ok(deleted) => assert!(deleted),
+            Err(error) => panic!("the current revision must delete: {error}"),
+        }
+    }
+
     /// Deleting something that is not there is not an error, and still clears
     /// any cache left behind by a store that expired it on its own.
     #[tokio::test]
@@ -1278,6 +2686,73 @@ mod tests {
         }
     }
 
+    /// A simulation created under another engine is refused every snapshot
+    /// under the refuse policy, without its cursor moving, and is still
+    /// readable; under the default policy it is served.
+    #[tokio::test]
+    async fn test_a_foreign_engine_is_served_or_refused_by_policy() {
+        let mut foreign = parameters(3);
+        foreign.engine_version = "tape0+optionstratlib-0.0.0+positive-0.0.0".to_string();
+        assert!(foreign.engine_drift().is_some());
+
+        let refusing = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config {
+                engine_mismatch: EngineMismatchPolicy::Refuse,
+                ..SimulationV2Config::default()
+            },
+        );
+        let simulation = match refusing
+            .create(foreign.clone(), SimulationOptions::default())
+            .await
+        {
+            Ok(simulation) => simulation,
+            Err(error) => panic!("the simulation must be created: {error}"),
+        };
+        for result in [
+            refusing.peek(simulation.id).await,
+            refusing.advance(simulation.id).await,
+        ] {
+            match result {
+                Err(ChainError::Validation { field, .. }) => assert_eq!(field, "engine_version"),
+                other => panic!("a foreign engine must be refused, got {other:?}"),
+            }
+        }
+        match refusing.get(simulation.id).await {
+            Ok(read) => assert_eq!(read.current_step, 0),
+            Err(error) => panic!("a refused simulation must stay readable: {error}"),
+        }
+
+        let warning = manager();
+        let simulation = match warning.create(foreign, SimulationOptions::default()).await {
+            Ok(simulation) => simulation,
+            Err(error) => panic!("the simulation must be created: {error}"),
+        };
+        if let Err(error) = warning.advance(simulation.id).await {
+            panic!("the warn policy must serve a foreign engine: {error}");
+        }
+    }
+
+    /// A simulation created here records the running engine, and one from
+    /// before the engine was recorded never counts as drifted.
+    #[tokio::test]
+    async fn test_the_running_engine_is_recorded_at_creation() {
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config {
+                engine_mismatch: EngineMismatchPolicy::Refuse,
+                ..SimulationV2Config::default()
+            },
+        );
+        let simulation = created(&manager, 2).await;
+        assert_eq!(simulation.parameters.engine_version, engine_version());
+        assert!(manager.peek(simulation.id).await.is_ok());
+
+        let mut legacy = parameters(2);
+        legacy.engine_version = String::new();
+        assert_eq!(legacy.engine_drift(), None);
+    }
+
     /// An unknown id is not found, on every read path.
     #[tokio::test]
     async fn test_an_unknown_id_is_not_found() {
@@ -1331,6 +2806,46 @@ mod tests {
         assert_eq!(manager.cached_snapshots(), 0);
     }
 
+    /// A touch rescues an idle simulation from the next cleanup without
+    /// moving its cursor, and is a new revision like any other write.
+    #[tokio::test]
+    async fn test_a_touch_restarts_the_retention_window() {
+        let store = Arc::new(InMemorySimulationStore::with_idle_retention(
+            std::time::Duration::from_secs(60),
+        ));
+        let manager = SimulationManager::new(store, SimulationV2Config::default());
+        let created = created(&manager, 5).await;
+
+        let mut aged = created.clone();
+        aged.updated_at = std::time::SystemTime::now() - std::time::Duration::from_secs(3_600);
+        let expected = aged.version;
+        match manager.store.save_cas(aged, expected).await {
+            Ok(()) => {}
+            Err(error) => panic!("the aged document must save: {error}"),
+        }
+
+        let touched = match manager.touch(created.id).await {
+            Ok(touched) => touched,
+            Err(error) => panic!("the touch must succeed: {error}"),
+        };
+        assert_eq!(touched.version, created.version + 1);
+        assert_eq!(touched.current_step, created.current_step);
+
+        match manager.cleanup().await {
+            Ok(expired) => assert!(expired.is_empty(), "a touched simulation must survive"),
+            Err(error) => panic!("the cleanup must succeed: {error}"),
+        }
+    }
+
+    /// Touching an unknown id is `NotFound`, not a silent creation.
+    #[tokio::test]
+    async fn test_touching_an_unknown_id_is_not_found() {
+        match manager().touch(Uuid::new_v4()).await {
+            Err(ChainError::NotFound(_)) => {}
+            other => panic!("expected NotFound, got {other:?}"),
+        }
+    }
+
     /// A snapshot survives an eviction of its tape, because both rebuild.
     #[tokio::test]
     async fn test_an_evicted_tape_rebuilds_identically() {
@@ -1351,4 +2866,320 @@ mod tests {
         };
         assert_eq!(before, after, "a rebuild must be indistinguishable");
     }
+
+    /// A seek jumps the cursor forward in one write, and refuses to go back
+    /// or past the last step.
+    #[tokio::test]
+    async fn test_a_seek_jumps_forward_only() {
+        let manager = manager();
+        let created = created(&manager, 6).await;
+
+        let seeked = match manager.seek(created.id, 4).await {
+            Ok(simulation) => simulation,
+            Err(error) => panic!("a forward seek must succeed: {error}"),
+        };
+        assert_eq!(seeked.current_step, 4);
+        assert_eq!(seeked.state, SessionState::InProgress);
+        assert_eq!(seeked.version, created.version + 1);
+        assert_eq!(
+            manager.cached_snapshots(),
+            0,
+            "skipped steps are not priced"
+        );
+
+        match manager.seek(created.id, 4).await {
+            Ok(same) => assert_eq!(same.version, seeked.version, "a no-op must not write"),
+            Err(error) => panic!("seeking to the cursor must succeed: {error}"),
+        }
+        for to_step in [3, 6, 7] {
+            match manager.seek(created.id, to_step).await {
+                Err(ChainError::Validation { field, .. }) => assert_eq!(field, "to_step"),
+                other => panic!("seeking to {to_step} must be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    /// A waiter sees the next advance, whoever makes it, and times out to
+    /// `None` when there is none.
+    #[tokio::test]
+    async fn test_next_snapshot_waits_for_the_next_advance() {
+        let manager = Arc::new(manager());
+        let created = created(&manager, 4).await;
+
+        match manager
+            .next_snapshot(created.id, Duration::from_millis(20))
+            .await
+        {
+            Ok(None) => {}
+            other => panic!("an idle simulation must time out, got {other:?}"),
+        }
+
+        let waiter = {
+            let manager = Arc::clone(&manager);
+            tokio::spawn(async move {
+                manager
+                    .next_snapshot(created.id, Duration::from_secs(10))
+                    .await
+            })
+        };
+        // Let the waiter subscribe before advancing.
+        tokio::task::yield_now().await;
+        let served = match manager.advance(created.id).await {
+            Ok((_, snapshot)) => snapshot,
+            Err(error) => panic!("the advance must succeed: {error}"),
+        };
+
+        match waiter.await {
+            Ok(Ok(Some((simulation, snapshot)))) => {
+                assert_eq!(simulation.current_step, 1);
+                assert_eq!(*snapshot, served);
+            }
+            other => panic!("the waiter must see the advance, got {other:?}"),
+        }
+    }
+
+    /// Polls a playback until it finishes, failing after a generous bound.
+    async fn finished(manager: &SimulationManager, id: Uuid) -> PlaybackStatus {
+        for _ in 0..200 {
+            match manager.playback_status(id) {
+                Ok(status) if status.state == PlaybackState::Finished => return status,
+                Ok(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+                Err(error) => panic!("the playback must be readable: {error}"),
+            }
+        }
+        panic!("the playback did not finish");
+    }
+
+    /// A playback walks the simulation to completion on its own, then
+    /// reports why it ended.
+    #[tokio::test]
+    async fn test_a_playback_plays_to_completion() {
+        let manager = Arc::new(manager());
+        let created = created(&manager, 3).await;
+
+        match manager
+            .start_playback(created.id, PlaybackPace::StepsPerSecond(20.0))
+            .await
+        {
+            Ok(status) => assert_eq!(status.state, PlaybackState::Playing),
+            Err(error) => panic!("the playback must start: {error}"),
+        }
+
+        let status = finished(&manager, created.id).await;
+        assert_eq!(status.steps_played, 3);
+        assert_eq!(status.finished_reason.as_deref(), Some("completed"));
+        match manager.get(created.id).await {
+            Ok(simulation) => assert_eq!(simulation.state, SessionState::Completed),
+            Err(error) => panic!("the simulation must still exist: {error}"),
+        }
+    }
+
+    /// A paused playback does not tick, and a resumed one carries on.
+    #[tokio::test]
+    async fn test_a_paused_playback_holds_the_cursor() {
+        let manager = Arc::new(manager());
+        let created = created(&manager, 2).await;
+
+        if let Err(error) = manager
+            .start_playback(created.id, PlaybackPace::StepsPerSecond(20.0))
+            .await
+        {
+            panic!("the playback must start: {error}");
+        }
+        match manager.pause_playback(created.id) {
+            Ok(status) => assert_eq!(status.state, PlaybackState::Paused),
+            Err(error) => panic!("the playback must pause: {error}"),
+        }
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        match manager.get(created.id).await {
+            Ok(simulation) => assert_eq!(simulation.current_step, 0),
+            Err(error) => panic!("the simulation must be readable: {error}"),
+        }
+
+        if let Err(error) = manager.resume_playback(created.id) {
+            panic!("the playback must resume: {error}");
+        }
+        assert_eq!(finished(&manager, created.id).await.steps_played, 2);
+
+        match manager.resume_playback(created.id) {
+            Err(ChainError::InvalidState(_)) => {}
+            other => panic!("a finished playback cannot resume, got {other:?}"),
+        }
+    }
+
+    /// One live playback per simulation, and a delete stops it.
+    #[tokio::test]
+    async fn test_a_simulation_plays_once_and_a_delete_stops_it() {
+        let manager = Arc::new(manager());
+        let created = created(&manager, 100).await;
+        let pace = PlaybackPace::StepsPerSecond(1.0);
+
+        if let Err(error) = manager.start_playback(created.id, pace).await {
+            panic!("the playback must start: {error}");
+        }
+        match manager.start_playback(created.id, pace).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a second playback must conflict, got {other:?}"),
+        }
+
+        match manager.delete(created.id).await {
+            Ok(deleted) => assert!(deleted),
+            Err(error) => panic!("the delete must succeed: {error}"),
+        }
+        match manager.playback_status(created.id) {
+            Err(ChainError::NotFound(_)) => {}
+            other => panic!("a delete must stop the playback, got {other:?}"),
+        }
+        assert!(!manager.stop_playback(created.id));
+    }
+
+    /// The playback limit counts live playbacks only.
+    #[tokio::test]
+    async fn test_the_playback_limit_is_enforced() {
+        let manager = Arc::new(SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            SimulationV2Config {
+                max_playbacks: 1,
+                ..SimulationV2Config::default()
+            },
+        ));
+        let first = created(&manager, 100).await;
+        let second = created(&manager, 100).await;
+        let pace = PlaybackPace::StepsPerSecond(1.0);
+
+        if let Err(error) = manager.start_playback(first.id, pace).await {
+            panic!("the first playback must start: {error}");
+        }
+        match manager.start_playback(second.id, pace).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a playback past the limit must conflict, got {other:?}"),
+        }
+
+        assert!(manager.stop_playback(first.id));
+        if let Err(error) = manager.start_playback(second.id, pace).await {
+            panic!("a freed slot must admit a playback: {error}");
+        }
+    }
+
+    /// The configuration the webhook tests run with: their receiver listens on
+    /// loopback, which a default deployment refuses.
+    fn loopback_webhooks() -> SimulationV2Config {
+        SimulationV2Config {
+            webhook_allow_private_targets: true,
+            ..SimulationV2Config::default()
+        }
+    }
+
+    fn webhook(url: String, every_n_steps: Option<usize>) -> WebhookSpec {
+        WebhookSpec {
+            url,
+            signing_key: "a-signing-key-of-some-length".to_string(),
+            every_n_steps,
+        }
+    }
+
+    fn events(received: &[crate::session::webhooks::testing::Received]) -> Vec<String> {
+        let mut events: Vec<String> = received
+            .iter()
+            .map(|delivery| delivery.header("X-OCS-Event").to_string())
+            .collect();
+        events.sort();
+        events
+    }
+
+    /// A bound webhook hears the steps on its cadence, the completion and the
+    /// deletion, and is forgotten with the simulation.
+    #[actix_web::test]
+    async fn test_webhooks_hear_steps_completion_and_deletion() {
+        let receiver = crate::session::webhooks::testing::Receiver::start(Vec::new());
+        let manager = SimulationManager::new(
+            Arc::new(InMemorySimulationStore::new()),
+            loopback_webhooks(),
+        );
+        let created = created(&manager, 3).await;
+        if let Err(error) = manager
+            .register_webhook(Some(created.id), webhook(receiver.url(), Some(2)))
+            .await
+        {
+            panic!("the webhook must register: {error}");
+        }
+
+        for _ in 0..3 {
+            if let Err(error) = manager.advance(created.id).await {
+                panic!("the advance must succeed: {error}");
+            }
+        }
+        match manager.delete(created.id).await {
+            Ok(deleted) => assert!(deleted),
+            Err(error) => panic!("the delete must succeed: {error}"),
+        }
+
+        let received = receiver.wait_for(3).await;
+        assert_eq!(events(&received), ["completed", "deleted", "step"]);
+        let step = received
+            .iter()
+            .find(|delivery| delivery.header("X-OCS-Event") == "step")
+            .map(|delivery| delivery.json());
+        assert_eq!(
+            step.as_ref().and_then(|body| body.get("current_step")),
+            Some(&serde_json::json!(2))
+        );
+        assert!(manager.webhooks.list(Some(created.id)).is_empty());
+    }
+
+    /// The retention sweep reports each simulation it expires.
+    #[actix_web::test]
+    async fn test_webhooks_hear_an_expiry() {
+        let receiver = crate::session::webhooks::testing::Receiver::start(Vec::new());
+        let store = Arc::new(InMemorySimulationStore::with_idle_retention(
+            std::time::Duration::from_secs(1),
+        ));
+        let manager = SimulationManager::new(store, loopback_webhooks());
+        let created = created(&manager, 5).await;
+        if let Err(error) = manager
+            .register_webhook(None, webhook(receiver.url(), None))
+            .await
+        {
+            panic!("the webhook must register: {error}");
+        }
+
+        let mut aged = created.clone();
+        aged.updated_at = std::time::SystemTime::now() - std::time::Duration::from_secs(3_600);
+        let expected = aged.version;
+        if let Err(error) = manager.store.save_cas(aged, expected).await {
+            panic!("the aged document must save: {error}");
+        }
+        if let Err(error) = manager.cleanup().await {
+            panic!("the cleanup must succeed: {error}");
+        }
+
+        let received = receiver.wait_for(1).await;
+        assert_eq!(events(&received), ["expired"]);
+        assert_eq!(
+            received[0].json().get("simulation_id"),
+            Some(&serde_json::json!(created.id.to_string()))
+        );
+        assert_eq!(
+            manager.webhooks.list(None).len(),
+            1,
+            "a global webhook outlives the simulations it hears about"
+        );
+    }
+
+    /// A webhook cannot be bound to a simulation that does not exist.
+    #[tokio::test]
+    async fn test_a_webhook_on_an_unknown_simulation_is_not_found() {
+        let manager = manager();
+        match manager
+            .register_webhook(
+                Some(Uuid::new_v4()),
+                webhook("https://example.com/hook".to_string(), None),
+            )
+            .await
+        {
+            Err(ChainError::NotFound(_)) => {}
+            other => panic!("expected NotFound, got {other:?}"),
+        }
+    }
 }
@@ -0,0 +1,355 @@
+//! Replay manifests: a simulation's definition as one portable document.
+//!
+//! A manifest carries the effective parameters — the replay inputs of ADR 0001
+//! §8 — together with everything that decides whether those inputs still mean
+//! the same tape somewhere else: the tzdb release the expirations were resolved
+//! against, the calendar policy, the tape generation and the crate version that
+//! wrote it. Importing one on another deployment recreates the simulation, so a
+//! bug reproduction travels as a single file.
+//!
+//! The parameters are carried in their stored form, not the `f64` rendering
+//! of `SimulationParametersResponse`: a replay has to be exact, and a price of
+//! `5000.1` that came back as `5000.099999999999` would be a different
+//! simulation.
+//!
+//! # Integrity
+//!
+//! `digest` is the SHA-256 of the manifest's canonical JSON without the digest
+//! itself. It is a checksum, not a signature — anyone can compute it — and it
+//! exists to catch a manifest edited by hand or truncated on the way, which
+//! would otherwise import as a plausible simulation that reproduces nothing.
+//!
+//! # Refusals
+//!
+//! An import is refused, unless forced, when the manifest was written under a
+//! different tape generation, tzdb release or engine: the recreated simulation
+//! would be coherent but would not serve the tape the manifest describes.
+//! Forcing imports it anyway, resolved against this binary's release and
+//! engine — and says so in the new simulation's own `tzdb_version` and
+//! `engine_version`.
+
+use crate::domain::expiry::tzdb_version;
+use crate::session::engine::engine_version;
+use crate::session::model_v2::{SessionV2, SimulationParametersV2};
+use crate::session::snapshot_record::SNAPSHOT_TAPE_GENERATION;
+use crate::utils::ChainError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// The manifest format this binary writes and reads.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// The prefix naming the digest's algorithm.
+const DIGEST_PREFIX: &str = "sha256:";
+
+/// A simulation's definition, versioned and checksummed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationManifest {
+    /// The manifest format; see [`MANIFEST_VERSION`].
+    pub manifest_version: u32,
+    /// The crate version that wrote the manifest. Informational: two versions
+    /// with the same tape generation serve the same tape.
+    pub crate_version: String,
+    /// The tape generation the simulation's snapshots are produced under.
+    pub tape_generation: u64,
+    /// The IANA tzdb release the expirations were resolved against.
+    pub tzdb_version: String,
+    /// The calendar policy the schedule is evaluated under.
+    pub calendar: String,
+    /// The simulation the manifest was taken from. An import mints a new id.
+    pub source_id: Uuid,
+    /// The effective parameters, in their stored form.
+    pub parameters: SimulationParametersV2,
+    /// `sha256:` and the hex digest of everything above.
+    pub digest: String,
+}
+
+/// The fields the digest covers, in the order they are hashed.
+#[derive(Serialize)]
+struct Covered<'a> {
+    manifest_version: u32,
+    crate_version: &'a str,
+    tape_generation: u64,
+    tzdb_version: &'a str,
+    calendar: &'a str,
+    source_id: Uuid,
+    parameters: &'a SimulationParametersV2,
+}
+
+impl SimulationManifest {
+    /// The manifest of `simulation`.
+    #[must_use]
+    pub fn of(simulation: &SessionV2) -> Self {
+        let parameters = &simulation.parameters;
+        let mut manifest = Self {
+            manifest_version: MANIFEST_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            tape_generation: SNAPSHOT_TAPE_GENERATION,
+            tzdb_version: parameters.tzdb_version.clone(),
+            calendar: parameters.schedule.calendar().as_str().to_string(),
+            source_id: simulation.id,
+            parameters: parameters.clone(),
+            digest: String::new(),
+        };
+        manifest.digest = manifest.expected_digest();
+        manifest
+    }
+
+    /// The digest the manifest's contents hash to.
+    #[must_use]
+    pub fn expected_digest(&self) -> String {
+        let covered = Covered {
+            manifest_version: self.manifest_version,
+            crate_version: &self.crate_version,
+            tape_generation: self.tape_generation,
+            tzdb_version: &self.tzdb_version,
+            calendar: &self.calendar,
+            source_id: self.source_id,
+            parameters: &self.parameters,
+        };
+        // Serialising plain structs, strings and numbers cannot fail; an empty
+        // body would only produce a digest nothing matches.
+        let body = serde_json::to_vec(&covered).unwrap_or_default();
+        format!("{DIGEST_PREFIX}{}", hex::encode(Sha256::digest(body)))
+    }
+
+    /// Checks the manifest and returns the parameters to recreate it with.
+    ///
+    /// With `force`, a generation, tzdb or engine mismatch is accepted. The
+    /// parameters are restamped with the running tzdb release and engine
+    /// either way, since those are what the recreated simulation runs under.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming the field at fault when the
+    /// format is unknown, the digest does not match, the header disagrees with
+    /// the parameters it describes, or — without `force` — the tape
+    /// generation, tzdb release or recorded engine differs from this binary's.
+    pub fn into_parameters(self, force: bool) -> Result<SimulationParametersV2, ChainError> {
+        if self.manifest_version != MANIFEST_VERSION {
+            return Err(invalid(
+                "manifest_version",
+                format!("must be {MANIFEST_VERSION}, got {}", self.manifest_version),
+            ));
+        }
+        if self.digest != self.expected_digest() {
+            return Err(invalid(
+                "digest",
+                "does not match the manifest's contents; it was edited or truncated".to_string(),
+            ));
+        }
+        if self.tzdb_version != self.parameters.tzdb_version {
+            return Err(invalid(
+                "tzdb_version",
+                format!(
+                    "must match the parameters' tzdb_version {:?}",
+                    self.parameters.tzdb_version
+                ),
+            ));
+        }
+        let calendar = self.parameters.schedule.calendar().as_str();
+        if self.calendar != calendar {
+            return Err(invalid(
+                "calendar",
+                format!("must match the parameters' calendar {calendar:?}"),
+            ));
+        }
+
+        if !force && self.tape_generation != SNAPSHOT_TAPE_GENERATION {
+            return Err(invalid(
+                "tape_generation",
+                format!(
+                    "was written under tape generation {}, this service produces {}; \
+                     pass force=true to import it anyway",
+                    self.tape_generation, SNAPSHOT_TAPE_GENERATION
+                ),
+            ));
+        }
+        let running = tzdb_version();
+        if !force && self.tzdb_version != running {
+            return Err(invalid(
+                "tzdb_version",
+                format!(
+                    "was resolved against tzdb {}, this service runs {running}; \
+                     pass force=true to import it anyway",
+                    self.tzdb_version
+                ),
+            ));
+        }
+
+        if !force && let Some(recorded) = self.parameters.engine_drift() {
+            return Err(invalid(
+                "engine_version",
+                format!(
+                    "was created under engine {recorded}, this service runs {}; \
+                     pass force=true to import it anyway",
+                    engine_version()
+                ),
+            ));
+        }
+
+        let mut parameters = self.parameters;
+        parameters.tzdb_version = running.to_string();
+        parameters.engine_version = engine_version().to_string();
+        Ok(parameters)
+    }
+}
+
+/// A refusal naming `field`.
+fn invalid(field: &str, reason: String) -> ChainError {
+    ChainError::Validation {
+        field: field.to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::models::{ApiTimeFrame, ApiWalkType};
+    use crate::api::rest::requests_v2::CreateSimulationRequest;
+    use crate::session::{ExpiryRule, ExpiryRuleKind};
+
+    fn simulation() -> SessionV2 {
+        let rule = match ExpiryRule::new(
+            "weeklies",
+            ExpiryRuleKind::weekly([chrono::Weekday::Fri]),
+            2,
+        ) {
+            Ok(rule) => rule,
+            Err(error) => panic!("the test rule must be valid: {error}"),
+        };
+        let request = CreateSimulationRequest {
+            symbol: "SPX".to_string(),
+            steps: 10,
+            start_at: None,
+            step_interval_seconds: Some(86_400),
+            timezone: "America/New_York".to_string(),
+            calendar: None,
+            expiration_time: "16:00".to_string(),
+            schedules: vec![rule],
+            initial_price: 5000.1,
+            volatility: 0.18,
+            risk_free_rate: 0.04,
+            dividend_yield: 0.0,
+            method: ApiWalkType::Brownian {
+                dt: 1.0 / 252.0,
+                drift: 0.0,
+                volatility: 0.18,
+            },
+            time_frame: ApiTimeFrame::Day,
+            chain_size: Some(5),
+            strike_interval: Some(25.0),
+            skew_slope: None,
+            smile_curve: None,
+            spread: Some(0.02),
+            seed: Some(7),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
+        };
+        match SimulationParametersV2::try_from(request) {
+            Ok(parameters) => SessionV2::new(parameters),
+            Err(error) => panic!("the request must convert: {error}"),
+        }
+    }
+
+    fn refused_field(result: Result<SimulationParametersV2, ChainError>) -> String {
+        match result {
+            Err(ChainError::Validation { field, .. }) => field,
+            other => panic!("the manifest must be refused, got {other:?}"),
+        }
+    }
+
+    /// A manifest survives JSON and imports as the exact parameters it was
+    /// taken from.
+    #[test]
+    fn test_a_manifest_round_trips_to_the_same_parameters() {
+        let simulation = simulation();
+        let manifest = SimulationManifest::of(&simulation);
+        assert!(manifest.digest.starts_with(DIGEST_PREFIX));
+
+        let json = match serde_json::to_string(&manifest) {
+            Ok(json) => json,
+            Err(error) => panic!("the manifest must serialise: {error}"),
+        };
+        let read: SimulationManifest = match serde_json::from_str(&json) {
+            Ok(read) => read,
+            Err(error) => panic!("the manifest must deserialise: {error}"),
+        };
+        match read.into_parameters(false) {
+            Ok(parameters) => assert_eq!(parameters, simulation.parameters),
+            Err(error) => panic!("an untouched manifest must import: {error}"),
+        }
+    }
+
+    /// Any edit that does not recompute the digest is caught.
+    #[test]
+    fn test_an_edited_manifest_is_refused() {
+        let mut manifest = SimulationManifest::of(&simulation());
+        manifest.parameters.seed += 1;
+        assert_eq!(refused_field(manifest.into_parameters(true)), "digest");
+
+        let mut manifest = SimulationManifest::of(&simulation());
+        manifest.manifest_version = MANIFEST_VERSION + 1;
+        assert_eq!(
+            refused_field(manifest.into_parameters(true)),
+            "manifest_version"
+        );
+    }
+
+    /// A different tape generation or tzdb release is refused unless forced,
+    /// and a forced import is restamped with the running release.
+    #[test]
+    fn test_a_mismatch_is_refused_unless_forced() {
+        let mut manifest = SimulationManifest::of(&simulation());
+        manifest.tape_generation = SNAPSHOT_TAPE_GENERATION + 1;
+        manifest.digest = manifest.expected_digest();
+        assert_eq!(
+            refused_field(manifest.clone().into_parameters(false)),
+            "tape_generation"
+        );
+        assert!(manifest.into_parameters(true).is_ok());
+
+        let mut manifest = SimulationManifest::of(&simulation());
+        manifest.tzdb_version = "1970a".to_string();
+        manifest.parameters.tzdb_version = "1970a".to_string();
+        manifest.digest = manifest.expected_digest();
+        assert_eq!(
+            refused_field(manifest.clone().into_parameters(false)),
+            "tzdb_version"
+        );
+        match manifest.into_parameters(true) {
+            Ok(parameters) => assert_eq!(parameters.tzdb_version, tzdb_version()),
+            Err(error) => panic!("a forced import must succeed: {error}"),
+        }
+    }
+
+    /// A manifest from another engine is refused unless forced, and a forced
+    /// import runs under this one; a manifest that predates the engine tag is
+    /// imported as this engine's.
+    #[test]
+    fn test_an_engine_mismatch_is_refused_unless_forced() {
+        let mut manifest = SimulationManifest::of(&simulation());
+        manifest.parameters.engine_version = "tape0+optionstratlib-0.0.0".to_string();
+        manifest.digest = manifest.expected_digest();
+        assert_eq!(
+            refused_field(manifest.clone().into_parameters(false)),
+            "engine_version"
+        );
+        match manifest.into_parameters(true) {
+            Ok(parameters) => assert_eq!(parameters.engine_version, engine_version()),
+            Err(error) => panic!("a forced import must succeed: {error}"),
+        }
+
+        let mut manifest = SimulationManifest::of(&simulation());
+        manifest.parameters.engine_version = String::new();
+        manifest.digest = manifest.expected_digest();
+        match manifest.into_parameters(false) {
+            Ok(parameters) => assert_eq!(parameters.engine_version, engine_version()),
+            Err(error) => panic!("an untagged manifest must import: {error}"),
+        }
+    }
+}
@@ -0,0 +1,434 @@
+//! Background materialization of v2 simulations into the warehouse.
+//!
+//! Persistence files the steps an advance serves, so the warehouse holds only
+//! what some client walked. A materialization fills in the rest: a background
+//! task that prices a step range and files every step through
+//! [`SimulationSnapshotRepository::persist`], without moving the cursor.
+//!
+//! # What a job does
+//!
+//! It works through the range in chunks. For each chunk it first asks the
+//! warehouse which steps it already holds — [`stored_steps`], which checks
+//! each step's completion marker against a count of its rows rather than
+//! reading and rebuilding them — and prices only
+//! the others, off the runtime, from the same factor tape a replay uses. Every
+//! step is therefore either *filed* or *skipped*, and the status counts both.
+//! A lookup that fails is not fatal: the chunk is priced in full, and filing a
+//! step the warehouse had is a harmless rewrite of the same row.
+//!
+//! A failed write ends the job as `failed`; it does not retry. Starting the job
+//! again picks up where it stopped, because everything it did file is skipped.
+//!
+//! # What a job is not
+//!
+//! Like a playback it is **process-local and not persisted**: a restart ends
+//! it, and on several replicas it runs on the one that received the start.
+//!
+//! [`stored_steps`]: SimulationSnapshotRepository::stored_steps
+
+use crate::infrastructure::{CURRENT_SNAPSHOT_GENERATION, SimulationSnapshotRepository};
+use crate::session::SessionV2;
+use crate::session::manager_v2::SimulationManager;
+use crate::utils::ChainError;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// How many steps one chunk covers: one warehouse lookup and one pricing
+/// batch.
+///
+/// Also the cancellation grain for pricing, which runs on a blocking thread
+/// and cannot be interrupted mid-chunk.
+const MATERIALIZE_CHUNK_STEPS: usize = 64;
+
+/// Where a materialization is in its own lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MaterializationState {
+    /// Pricing and filing.
+    Running,
+    /// Every step of the range is in the warehouse.
+    Completed,
+    /// Stopped by a client before the end of the range.
+    Cancelled,
+    /// Stopped by a failure, named in
+    /// [`MaterializationStatus::finished_reason`].
+    Failed,
+}
+
+/// A snapshot of one materialization, as reported to a client.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MaterializationStatus {
+    /// The job's state.
+    pub(crate) state: MaterializationState,
+    /// The first step of the range, inclusive.
+    pub(crate) from_step: usize,
+    /// The last step of the range, inclusive.
+    pub(crate) to_step: usize,
+    /// Steps priced and filed by this job.
+    pub(crate) steps_filed: usize,
+    /// Steps the warehouse already held.
+    pub(crate) steps_skipped: usize,
+    /// When the job was started, in real time.
+    pub(crate) started_at: SystemTime,
+    /// When the job ended, once it has.
+    pub(crate) finished_at: Option<SystemTime>,
+    /// Why the job ended, once it has.
+    pub(crate) finished_reason: Option<String>,
+}
+
+impl MaterializationStatus {
+    /// How many steps the range covers.
+    pub(crate) fn steps_total(&self) -> usize {
+        self.to_step - self.from_step + 1
+    }
+
+    /// The share of the range that is done, filed or skipped, from 0 to 1.
+    pub(crate) fn progress(&self) -> f64 {
+        // Exact for any step count a simulation admits.
+        #[allow(clippy::cast_precision_loss)]
+        let progress = (self.steps_filed + self.steps_skipped) as f64 / self.steps_total() as f64;
+        progress
+    }
+}
+
+/// One registered materialization.
+struct Entry {
+    cancel: watch::Sender<bool>,
+    status: Arc<Mutex<MaterializationStatus>>,
+}
+
+impl Entry {
+    fn is_running(&self) -> bool {
+        read(&self.status).state == MaterializationState::Running
+    }
+}
+
+/// The materializations one manager is running, keyed by simulation.
+///
+/// Owned by [`SimulationManager`] for the same reason its playbacks are: a
+/// delete or a cleanup can cancel the job of the simulation it removes.
+pub(crate) struct Materializations {
+    entries: Mutex<HashMap<Uuid, Entry>>,
+    max_active: usize,
+}
+
+impl Materializations {
+    /// An empty registry admitting at most `max_active` running jobs.
+    pub(crate) fn new(max_active: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_active,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Entry>> {
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Registers a job for `simulation` over `from_step..=to_step` and spawns
+    /// it.
+    ///
+    /// A finished job is replaced; a running one is not.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Conflict`] when the simulation already has a
+    /// running job or the registry is at its limit.
+    pub(crate) fn start(
+        &self,
+        manager: Arc<SimulationManager>,
+        warehouse: Arc<dyn SimulationSnapshotRepository>,
+        simulation: SessionV2,
+        from_step: usize,
+        to_step: usize,
+    ) -> Result<MaterializationStatus, ChainError> {
+        let id = simulation.id;
+        let mut entries = self.lock();
+
+        if entries.get(&id).is_some_and(Entry::is_running) {
+            return Err(ChainError::Conflict(format!(
+                "Simulation {id} is already being materialized; cancel it first"
+            )));
+        }
+        let active = entries.values().filter(|entry| entry.is_running()).count();
+        if active >= self.max_active {
+            return Err(ChainError::Conflict(format!(
+                "The service is already running its limit of {} materializations",
+                self.max_active
+            )));
+        }
+
+        let status = Arc::new(Mutex::new(MaterializationStatus {
+            state: MaterializationState::Running,
+            from_step,
+            to_step,
+            steps_filed: 0,
+            steps_skipped: 0,
+            started_at: SystemTime::now(),
+            finished_at: None,
+            finished_reason: None,
+        }));
+        let (cancel, receiver) = watch::channel(false);
+        tokio::spawn(drive(
+            manager,
+            warehouse,
+            simulation,
+            receiver,
+            Arc::clone(&status),
+        ));
+
+        let snapshot = read(&status);
+        entries.insert(id, Entry { cancel, status });
+        info!(simulation_id = %id, from_step, to_step, "Started materialization");
+        Ok(snapshot)
+    }
+
+    /// Cancels `id`'s job and returns its status. A job that has already
+    /// finished is left as it is.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when `id` has no job.
+    pub(crate) fn cancel(&self, id: Uuid) -> Result<MaterializationStatus, ChainError> {
+        let entries = self.lock();
+        let entry = entries.get(&id).ok_or_else(|| no_materialization(id))?;
+        finish(&entry.status, MaterializationState::Cancelled, "cancelled");
+        // A job that already ended has dropped its receiver; nothing to stop.
+        let _ = entry.cancel.send(true);
+        Ok(read(&entry.status))
+    }
+
+    /// Cancels and forgets `id`'s job, if there is one.
+    pub(crate) fn stop(&self, id: Uuid) {
+        if let Some(entry) = self.lock().remove(&id) {
+            finish(
+                &entry.status,
+                MaterializationState::Cancelled,
+                "the simulation no longer exists",
+            );
+            let _ = entry.cancel.send(true);
+            debug!(simulation_id = %id, "Stopped materialization");
+        }
+    }
+
+    /// The status of `id`'s job.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when `id` has no job.
+    pub(crate) fn status(&self, id: Uuid) -> Result<MaterializationStatus, ChainError> {
+        self.lock()
+            .get(&id)
+            .map(|entry| read(&entry.status))
+            .ok_or_else(|| no_materialization(id))
+    }
+}
+
+/// Resolves a requested range against a simulation's horizon: `from_step`
+/// defaults to the first step and `to_step` to the last, both inclusive.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Validation`] naming `from_step` or `to_step` when a
+/// bound is past the tape or the range is reversed.
+pub(crate) fn step_range(
+    from_step: Option<usize>,
+    to_step: Option<usize>,
+    steps: usize,
+) -> Result<(usize, usize), ChainError> {
+    let last = steps.checked_sub(1).ok_or_else(|| {
+        ChainError::Internal(
+            "a simulation with no steps cannot exist; `steps >= 1` is validated at creation"
+                .to_string(),
+        )
+    })?;
+    let from = from_step.unwrap_or(0);
+    let to = to_step.unwrap_or(last);
+
+    if from > last {
+        return Err(ChainError::Validation {
+            field: "from_step".to_string(),
+            reason: format!("must not exceed the last step ({last}), got {from}"),
+        });
+    }
+    if to > last {
+        return Err(ChainError::Validation {
+            field: "to_step".to_string(),
+            reason: format!("must not exceed the last step ({last}), got {to}"),
+        });
+    }
+    if from > to {
+        return Err(ChainError::Validation {
+            field: "from_step".to_string(),
+            reason: format!("must not exceed to_step ({to}), got {from}"),
+        });
+    }
+    Ok((from, to))
+}
+
+fn read(status: &Mutex<MaterializationStatus>) -> MaterializationStatus {
+    match status.lock() {
+        Ok(status) => status.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+fn update(status: &Mutex<MaterializationStatus>, change: impl FnOnce(&mut MaterializationStatus)) {
+    match status.lock() {
+        Ok(mut status) => change(&mut status),
+        Err(poisoned) => change(&mut poisoned.into_inner()),
+    }
+}
+
+#[cold]
+fn no_materialization(id: Uuid) -> ChainError {
+    ChainError::NotFound(format!("Simulation {id} has no materialization"))
+}
+
+/// Records that a job ended and why. Only a running job can end: whichever
+/// of the driver and a cancellation gets there first decides the outcome.
+fn finish(
+    status: &Mutex<MaterializationStatus>,
+    state: MaterializationState,
+    reason: impl Into<String>,
+) {
+    update(status, |status| {
+        if status.state == MaterializationState::Running {
+            status.state = state;
+            status.finished_at = Some(SystemTime::now());
+            status.finished_reason = Some(reason.into());
+        }
+    });
+}
+
+/// The driver: works through the range a chunk at a time until it is done,
+/// fails, or is cancelled.
+async fn drive(
+    manager: Arc<SimulationManager>,
+    warehouse: Arc<dyn SimulationSnapshotRepository>,
+    simulation: SessionV2,
+    mut cancel: watch::Receiver<bool>,
+    status: Arc<Mutex<MaterializationStatus>>,
+) {
+    let id = simulation.id;
+    let (from_step, to_step) = {
+        let status = read(&status);
+        (status.from_step, status.to_step)
+    };
+
+    let mut from = from_step;
+    while from <= to_step {
+        let to = to_step.min(from.saturating_add(MATERIALIZE_CHUNK_STEPS - 1));
+        if *cancel.borrow() {
+            return;
+        }
+
+        let present: HashSet<usize> = match warehouse
+            .stored_steps(id, CURRENT_SNAPSHOT_GENERATION, from, to)
+            .await
+        {
+            Ok(steps) => steps.into_iter().collect(),
+            Err(error) => {
+                debug!(
+                    simulation_id = %id,
+                    from_step = from,
+                    to_step = to,
+                    error = %error,
+                    "Could not look up stored steps; pricing the whole chunk"
+                );
+                HashSet::new()
+            }
+        };
+        let missing: Vec<usize> = (from..=to).filter(|step| !present.contains(step)).collect();
+        let skipped = (to - from + 1) - missing.len();
+        update(&status, |status| status.steps_skipped += skipped);
+
+        let records = match manager.materialize_records(&simulation, missing).await {
+            Ok(records) => records,
+            Err(error) => {
+                warn!(simulation_id = %id, %error, "Materialization stopped by a failed build");
+                finish(
+                    &status,
+                    MaterializationState::Failed,
+                    format!("stopped by a failed build: {error}"),
+                );
+                return;
+            }
+        };
+
+        for record in records {
+            let step = record.step;
+            let result = tokio::select! {
+                result = warehouse.persist(record) => result,
+                _ = cancel.wait_for(|cancelled| *cancelled) => return,
+            };
+            if let Err(error) = result {
+                warn!(simulation_id = %id, step, %error, "Materialization stopped by a failed write");
+                finish(
+                    &status,
+                    MaterializationState::Failed,
+                    format!("stopped by a failed write at step {step}: {error}"),
+                );
+                return;
+            }
+            update(&status, |status| status.steps_filed += 1);
+        }
+
+        from = to + 1;
+    }
+
+    finish(&status, MaterializationState::Completed, "completed");
+    info!(simulation_id = %id, from_step, to_step, "Materialization completed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Progress counts filed and skipped steps alike, over the whole range.
+    #[test]
+    fn test_progress_counts_filed_and_skipped_steps() {
+        let status = MaterializationStatus {
+            state: MaterializationState::Running,
+            from_step: 10,
+            to_step: 19,
+            steps_filed: 3,
+            steps_skipped: 2,
+            started_at: SystemTime::now(),
+            finished_at: None,
+            finished_reason: None,
+        };
+        assert_eq!(status.steps_total(), 10);
+        assert!((status.progress() - 0.5).abs() < f64::EPSILON);
+    }
+
+    /// An open range covers the whole tape; a bound past it or a reversed
+    /// range is refused naming the bound.
+    #[test]
+    fn test_the_step_range_resolves_against_the_horizon() {
+        match step_range(None, None, 10) {
+            Ok(range) => assert_eq!(range, (0, 9)),
+            Err(error) => panic!("an open range must resolve: {error}"),
+        }
+        match step_range(Some(3), Some(3), 10) {
+            Ok(range) => assert_eq!(range, (3, 3)),
+            Err(error) => panic!("a one-step range must resolve: {error}"),
+        }
+        for (from, to, field) in [
+            (Some(10), None, "from_step"),
+            (None, Some(10), "to_step"),
+            (Some(5), Some(4), "from_step"),
+        ] {
+            match step_range(from, to, 10) {
+                Err(ChainError::Validation { field: named, .. }) => assert_eq!(named, field),
+                other => panic!("{from:?}..={to:?} must be refused, got {other:?}"),
+            }
+        }
+    }
+}
@@ -1,3 +1,6 @@
+/// The engine version a tape is generated under: the tape generation and the
+/// locked versions of the crates that price it.
+mod engine;
 /// The `manager` module serves as an organizational component within the application.
 ///
 /// This module can encompass functionalities or utilities specifically aimed at managing
@@ -22,6 +25,12 @@ mod manager;
 /// the simulation store, the per-simulation factor tapes, and the bounded
 /// snapshot cache. It is the only thing the api layer talks to for v2.
 mod manager_v2;
+/// Replay manifests: a simulation's definition as one versioned, checksummed
+/// document, and the checks an import runs before recreating it.
+mod manifest;
+/// Background materialization: a task per simulation that prices a step range
+/// and files it in the warehouse, and the registry the manager keeps them in.
+mod materialize;
 /// The `model` module is typically used to define and manage the core
 /// data structures and associated logic used by the application.
 ///
@@ -38,6 +47,9 @@ mod model;
 /// conversion from the v2 request DTO. Kept apart from `model` because
 /// `/api/v1/chain` and its stored shape are frozen (ADR 0001 section 12).
 mod model_v2;
+/// Server-driven playback: a background task per playing simulation that
+/// advances it on a wall-clock pace, and the registry the manager keeps them in.
+mod playback;
 /// The domain snapshot in the shape the ClickHouse warehouse stores, and the
 /// tape generation it is filed under.
 mod snapshot_record;
@@ -73,14 +85,29 @@ mod state_handler;
 ///
 /// Users of this module should refer to its public items to utilize its functionality effectively.
 mod store;
+/// Webhook registrations and their signed, retried deliveries, queued off the
+/// request path like snapshot filing.
+mod webhooks;
 
 pub use crate::domain::expiry::{CalendarVersion, ExpirationSchedule, ExpiryRule, ExpiryRuleKind};
+pub use engine::engine_version;
 pub use manager::SessionManager;
 pub use manager_v2::SimulationManager;
+pub(crate) use manager_v2::{Advanced, Ended, Ending};
+pub use manifest::{MANIFEST_VERSION, SimulationManifest};
+pub(crate) use materialize::{MaterializationState, MaterializationStatus};
 pub use model::{Session, SessionState, SimulationMethod, SimulationParameters};
-pub use model_v2::{SESSION_V2_SCHEMA_VERSION, SessionV2, SimulationParametersV2};
+pub(crate) use model_v2::normalize_tags;
+pub use model_v2::{
+    SESSION_V2_SCHEMA_VERSION, SessionV2, SimulationOptions, SimulationParametersV2, SkippedSteps,
+};
+pub(crate) use playback::{PlaybackPace, PlaybackState, PlaybackStatus};
 pub use store::{
-    DEFAULT_V2_KEY_PREFIX, DEFAULT_V2_RETENTION_SECS, InMemorySessionStore,
-    InMemorySimulationStore, InRedisSessionStore, InRedisSimulationStore, SessionStore,
-    SimulationStore,
+    DEFAULT_IDEMPOTENCY_KEY_PREFIX, DEFAULT_SESSIONS_DIR, DEFAULT_V2_COLLECTION, DEFAULT_V2_DIR,
+    DEFAULT_V2_KEY_PREFIX, DEFAULT_V2_MAX_PINNED, DEFAULT_V2_RETENTION_SECS, IN_FLIGHT_LEASE,
+    IdempotencyClaim, IdempotencyRecord, IdempotencyStore, InFileSessionStore,
+    InFileSimulationStore, InMemoryIdempotencyStore, InMemorySessionStore, InMemorySimulationStore,
+    InRedisIdempotencyStore, InRedisSessionStore, InRedisSimulationStore, MongoSimulationStore,
+    SessionStore, SimulationFilter, SimulationPage, SimulationStore, StoredResponse,
 };
+pub(crate) use webhooks::{Webhook, WebhookSpec};
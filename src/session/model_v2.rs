@@ -26,7 +26,8 @@ use crate::api::rest::validation::{
 };
 use crate::domain::expiry::{CalendarVersion, ExpirationSchedule, tzdb_version};
 use crate::domain::simulator::DEFAULT_CHAIN_SIZE;
-use crate::infrastructure::max_snapshot_contracts;
+use crate::infrastructure::{max_retention_secs, max_snapshot_contracts};
+use crate::session::engine::engine_version;
 use crate::session::model::{SessionState, SimulationMethod};
 use crate::utils::ChainError;
 use chrono::{DateTime, NaiveTime, TimeDelta, Timelike, Utc};
@@ -38,7 +39,7 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use tracing::warn;
 use uuid::Uuid;
 
@@ -248,6 +249,10 @@ pub struct SimulationParametersV2 {
     /// The effective RNG seed. Non-optional: a v2 simulation is always
     /// reproducible, so the seed is resolved at conversion and never `None`.
     pub seed: u64,
+    /// The engine the tape was generated under, recorded at creation; see
+    /// [`engine_version`]. Empty for a simulation created before it was
+    /// recorded, which is never reported as drifted: nothing says it did.
+    pub engine_version: String,
 }
 
 /// The deserialization shape of [`SimulationParametersV2`].
@@ -286,6 +291,8 @@ struct SimulationParametersV2Wire {
     smile_curve: Option<Decimal>,
     spread: Option<Positive>,
     seed: u64,
+    #[serde(default)]
+    engine_version: String,
 }
 
 impl TryFrom<SimulationParametersV2Wire> for SimulationParametersV2 {
@@ -311,6 +318,7 @@ impl TryFrom<SimulationParametersV2Wire> for SimulationParametersV2 {
             smile_curve: wire.smile_curve,
             spread: wire.spread,
             seed: wire.seed,
+            engine_version: wire.engine_version,
         };
         parameters.validate()?;
         Ok(parameters)
@@ -318,6 +326,16 @@ impl TryFrom<SimulationParametersV2Wire> for SimulationParametersV2 {
 }
 
 impl SimulationParametersV2 {
+    /// The engine version the simulation was created under, when the running
+    /// binary would generate a different tape from it.
+    ///
+    /// `None` when the versions agree, and when none was recorded.
+    #[must_use]
+    pub fn engine_drift(&self) -> Option<&str> {
+        let recorded = self.engine_version.as_str();
+        (!recorded.is_empty() && recorded != engine_version()).then_some(recorded)
+    }
+
     /// Re-checks every invariant the request path establishes.
     ///
     /// Called from the `Deserialize` path, so a stored document is held to the
@@ -330,6 +348,10 @@ impl SimulationParametersV2 {
     /// dependency bump into an outage. Issue #46 decides whether a mid-tape
     /// divergence should be escalated.
     ///
+    /// An `engine_version` that differs is a warning here for the same reason;
+    /// whether the simulation is still served is the manager's policy, see
+    /// [`Self::engine_drift`].
+    ///
     /// # Errors
     ///
     /// Returns [`ChainError::Validation`] naming the offending field when
@@ -418,6 +440,13 @@ impl SimulationParametersV2 {
                 "simulation was resolved against a different IANA tzdb release"
             );
         }
+        if let Some(recorded) = self.engine_drift() {
+            warn!(
+                recorded = %recorded,
+                running = %engine_version(),
+                "simulation was created under a different engine; its tape may differ"
+            );
+        }
 
         Ok(())
     }
@@ -628,6 +657,7 @@ impl TryFrom<CreateSimulationRequest> for SimulationParametersV2 {
                 .map(|value| positive_field("spread", value))
                 .transpose()?,
             seed: request.seed.unwrap_or_else(|| rand::rng().random()),
+            engine_version: engine_version().to_string(),
         };
 
         // Run the same checks the stored-document path runs, so a request and a
@@ -639,6 +669,127 @@ impl TryFrom<CreateSimulationRequest> for SimulationParametersV2 {
     }
 }
 
+/// Most tags one simulation may carry.
+///
+/// Crate-internal for the same reason as [`MIN_STEP_INTERVAL_SECONDS`].
+pub(crate) const MAX_TAGS: usize = 16;
+
+/// Longest tag, in characters.
+pub(crate) const MAX_TAG_LEN: usize = 64;
+
+/// Validates and normalises caller-supplied tags: each one non-empty, at most
+/// [`MAX_TAG_LEN`] characters of `[A-Za-z0-9_.:-]`, at most [`MAX_TAGS`] of
+/// them, returned sorted and deduplicated.
+///
+/// The charset leaves out `,` because the listing endpoint takes its tag filter
+/// as a comma-separated list, and a tag that could contain the separator could
+/// never be filtered on. Normalising here means two creations that differ only
+/// in tag order store the same document.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Validation`] naming `tags` when any tag, or the count,
+/// is out of bounds.
+pub(crate) fn normalize_tags(mut tags: Vec<String>) -> Result<Vec<String>, ChainError> {
+    let invalid = |reason: String| ChainError::Validation {
+        field: "tags".to_string(),
+        reason,
+    };
+
+    for tag in &tags {
+        if tag.is_empty() {
+            return Err(invalid("a tag must not be empty".to_string()));
+        }
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(invalid(format!(
+                "a tag must not exceed {MAX_TAG_LEN} characters, got {tag:?}"
+            )));
+        }
+        if let Some(bad) = tag
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '_' | '.' | ':' | '-'))
+        {
+            return Err(invalid(format!(
+                "a tag must contain only [A-Za-z0-9_.:-], found {bad:?} in {tag:?}"
+            )));
+        }
+    }
+
+    tags.sort();
+    tags.dedup();
+    if tags.len() > MAX_TAGS {
+        return Err(invalid(format!(
+            "must not carry more than {MAX_TAGS} distinct tags, got {}",
+            tags.len()
+        )));
+    }
+    Ok(tags)
+}
+
+/// The creation inputs that are **not** replay inputs.
+///
+/// Kept apart from [`SimulationParametersV2`] because the parameters are
+/// exactly the replay list of ADR 0001 §8, and everything else a client may
+/// say at creation — how to find the simulation again, how long to keep it —
+/// must stay out of it, or two runs that serve identical tapes would echo
+/// different replay inputs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationOptions {
+    /// Normalised tags; see [`normalize_tags`].
+    pub tags: Vec<String>,
+    /// Idle retention for this simulation, in seconds, overriding the store's
+    /// default. `None` keeps the default.
+    pub retention_secs: Option<u64>,
+    /// Whether the simulation is exempt from expiry.
+    pub pinned: bool,
+}
+
+impl TryFrom<&CreateSimulationRequest> for SimulationOptions {
+    type Error = ChainError;
+
+    /// Validates the non-replay part of a creation request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming the first field that fails.
+    fn try_from(request: &CreateSimulationRequest) -> Result<Self, Self::Error> {
+        let pinned = request.pinned;
+        let retention_secs = request.retention_seconds;
+        if let Some(seconds) = retention_secs {
+            let max = max_retention_secs();
+            if seconds == 0 || seconds > max {
+                return Err(ChainError::Validation {
+                    field: "retention_seconds".to_string(),
+                    reason: format!("must be between 1 and {max}, got {seconds}"),
+                });
+            }
+            if pinned {
+                return Err(ChainError::Validation {
+                    field: "retention_seconds".to_string(),
+                    reason: "a pinned simulation never expires, so it takes no retention"
+                        .to_string(),
+                });
+            }
+        }
+
+        Ok(Self {
+            tags: normalize_tags(request.tags.clone())?,
+            retention_secs,
+            pinned,
+        })
+    }
+}
+
+/// A run of steps a seek jumped over without serving them: `from` inclusive,
+/// `to` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedSteps {
+    /// The first step jumped over — the cursor when the seek was made.
+    pub from: usize,
+    /// The step the seek landed on, which was served normally.
+    pub to: usize,
+}
+
 /// A v2 rolling simulation session.
 ///
 /// Reuses the v1 [`SessionState`] machine, but only three of its states are
@@ -669,6 +820,36 @@ pub struct SessionV2 {
     /// Optimistic-concurrency revision, bumped immediately before every
     /// compare-and-swap save, exactly as in v1.
     pub version: u64,
+    /// Caller-supplied tags, normalised: sorted, deduplicated and within
+    /// [`MAX_TAGS`].
+    ///
+    /// Operational metadata for finding a simulation again, not a replay
+    /// input: nothing seeded reads them. Omitted from the stored document when
+    /// empty, so a simulation created without tags is byte-identical to one
+    /// written before the field existed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Idle retention for this simulation, in seconds, when the caller chose
+    /// one. `None` means the store's default window applies.
+    ///
+    /// Stored on the document rather than only as a TTL so every backend, and
+    /// every replica, applies the same window after a restart. Omitted when
+    /// unset, like `tags`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_secs: Option<u64>,
+    /// Exempt from expiry: `cleanup` never reaps a pinned simulation, and the
+    /// Redis store writes it without a TTL. Counted against the store's pinned
+    /// quota. Omitted when `false`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// The runs of steps seeks jumped over, ascending and disjoint, all behind
+    /// the cursor.
+    ///
+    /// The cursor passed them without serving them, so anything that replays
+    /// what *was* served — the event feed's catch-up — must leave them out.
+    /// Omitted when empty, like `tags`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedSteps>,
 }
 
 /// The deserialization shape of [`SessionV2`], validated on the way in.
@@ -693,6 +874,14 @@ struct SessionV2Wire {
     total_steps: usize,
     state: SessionState,
     version: u64,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    retention_secs: Option<u64>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    skipped: Vec<SkippedSteps>,
 }
 
 impl TryFrom<SessionV2Wire> for SessionV2 {
@@ -709,6 +898,10 @@ impl TryFrom<SessionV2Wire> for SessionV2 {
             total_steps: wire.total_steps,
             state: wire.state,
             version: wire.version,
+            tags: wire.tags,
+            retention_secs: wire.retention_secs,
+            pinned: wire.pinned,
+            skipped: wire.skipped,
         };
         simulation.validate()?;
         Ok(simulation)
@@ -750,9 +943,74 @@ impl SessionV2 {
             parameters,
             state: SessionState::Initialized,
             version: 0,
+            tags: Vec::new(),
+            retention_secs: None,
+            pinned: false,
+            skipped: Vec::new(),
         }
     }
 
+    /// Applies the creation options that are not replay inputs.
+    ///
+    /// A separate step from [`SessionV2::new`] so that everything seeded stays
+    /// a function of the parameters alone: the options only decide how the
+    /// simulation is found and kept, never what it serves.
+    #[must_use]
+    pub fn with_options(mut self, options: SimulationOptions) -> Self {
+        self.tags = options.tags;
+        self.retention_secs = options.retention_secs;
+        self.pinned = options.pinned;
+        self
+    }
+
+    /// This simulation's idle retention: its own when it chose one, otherwise
+    /// `default`. Meaningless for a pinned simulation, which never expires.
+    #[must_use]
+    pub fn retention_or(&self, default: Duration) -> Duration {
+        self.retention_secs.map_or(default, Duration::from_secs)
+    }
+
+    /// Moves the cursor forward to `to_step` without serving the steps in
+    /// between, and records them as skipped.
+    ///
+    /// A seek that starts where the last one landed, with no advance between,
+    /// extends that run rather than starting another. Seeking to the cursor or
+    /// behind it records nothing and leaves the cursor where it is; the manager
+    /// refuses those before they get here.
+    pub fn seek_to(&mut self, to_step: usize) {
+        if to_step <= self.current_step {
+            return;
+        }
+        match self.skipped.last_mut() {
+            Some(run) if run.to == self.current_step => run.to = to_step,
+            _ => self.skipped.push(SkippedSteps {
+                from: self.current_step,
+                to: to_step,
+            }),
+        }
+        self.current_step = to_step;
+    }
+
+    /// The run of skipped steps `step` falls in, when a seek jumped over it.
+    #[must_use]
+    pub fn skipped_run(&self, step: usize) -> Option<SkippedSteps> {
+        self.skipped
+            .iter()
+            .find(|run| run.from <= step && step < run.to)
+            .copied()
+    }
+
+    /// How many of the steps in `from..to` were served rather than skipped.
+    #[must_use]
+    pub fn served_between(&self, from: usize, to: usize) -> usize {
+        let skipped: usize = self
+            .skipped
+            .iter()
+            .map(|run| run.to.min(to).saturating_sub(run.from.max(from)))
+            .sum();
+        to.saturating_sub(from).saturating_sub(skipped)
+    }
+
     /// Re-checks every invariant a freshly-created simulation satisfies.
     ///
     /// Called from the `Deserialize` path, so a stored document cannot present
@@ -762,10 +1020,12 @@ impl SessionV2 {
     ///
     /// Returns [`ChainError::Validation`] when the document carries a
     /// `schema_version` this binary does not understand, a `total_steps` that
-    /// disagrees with its parameters, a cursor past its own horizon, or a state
-    /// unreachable for a v2 simulation (`Modified` and `Reinitialized` are the
-    /// PATCH and PUT branches, and a v2 simulation is immutable). Also
-    /// propagates the parameters' own validation.
+    /// disagrees with its parameters, a cursor past its own horizon, tags that
+    /// are not normalised, a zero retention or one on a pinned simulation,
+    /// skipped runs that are empty, unordered, overlapping or ahead of the
+    /// cursor, or a state unreachable for a v2 simulation (`Modified` and
+    /// `Reinitialized` are the PATCH and PUT branches, and a v2 simulation is
+    /// immutable). Also propagates the parameters' own validation.
     pub fn validate(&self) -> Result<(), ChainError> {
         if self.schema_version > SESSION_V2_SCHEMA_VERSION {
             return Err(ChainError::Validation {
@@ -795,6 +1055,34 @@ impl SessionV2 {
                 ),
             });
         }
+        if normalize_tags(self.tags.clone())? != self.tags {
+            return Err(ChainError::Validation {
+                field: "tags".to_string(),
+                reason: "must be sorted and free of duplicates".to_string(),
+            });
+        }
+        // The ceiling is deliberately not re-checked here: it bounds what a
+        // client may ask for, and an operator lowering it must not make the
+        // simulations already stored unreadable.
+        if self.retention_secs == Some(0) || (self.pinned && self.retention_secs.is_some()) {
+            return Err(ChainError::Validation {
+                field: "retention_secs".to_string(),
+                reason: "must be at least 1 second, and absent on a pinned simulation".to_string(),
+            });
+        }
+        let mut behind = 0;
+        for run in &self.skipped {
+            if run.from < behind || run.from >= run.to || run.to > self.current_step {
+                return Err(ChainError::Validation {
+                    field: "skipped".to_string(),
+                    reason: format!(
+                        "runs must be non-empty, ascending, disjoint and behind the cursor ({}), got {}..{}",
+                        self.current_step, run.from, run.to
+                    ),
+                });
+            }
+            behind = run.to;
+        }
         self.validate_state()
     }
 
@@ -948,6 +1236,9 @@ mod tests {
             smile_curve: Some(0.4),
             spread: Some(0.02),
             seed: Some(42),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         }
     }
 
@@ -1732,4 +2023,173 @@ mod tests {
             Err(error) => panic!("must resolve: {error}"),
         }
     }
+
+    // ---- tags ------------------------------------------------------------
+
+    /// Tags are stored sorted and deduplicated, so two creations that differ
+    /// only in tag order store the same document.
+    #[test]
+    fn test_tags_are_normalised() {
+        let mut request = reference_request();
+        request.tags = vec![
+            "nightly".to_string(),
+            "desk:a".to_string(),
+            "nightly".to_string(),
+        ];
+
+        match SimulationOptions::try_from(&request) {
+            Ok(options) => assert_eq!(options.tags, vec!["desk:a", "nightly"]),
+            Err(error) => panic!("valid tags must convert: {error}"),
+        }
+    }
+
+    /// Every kind of bad tag is refused by name.
+    #[test]
+    fn test_invalid_tags_are_rejected() {
+        let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("t{i}")).collect();
+        for tags in [
+            vec![String::new()],
+            vec!["a,b".to_string()],
+            vec!["with space".to_string()],
+            vec!["x".repeat(MAX_TAG_LEN + 1)],
+            too_many,
+        ] {
+            match normalize_tags(tags.clone()) {
+                Err(ChainError::Validation { field, .. }) => assert_eq!(field, "tags"),
+                other => panic!("{tags:?} must be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    /// A simulation without tags serializes without the field, so its stored
+    /// document is unchanged by the feature's existence.
+    #[test]
+    fn test_a_simulation_without_tags_omits_the_field() {
+        let simulation = SessionV2::new(parameters(reference_request()));
+
+        let value = match serde_json::to_value(&simulation) {
+            Ok(value) => value,
+            Err(error) => panic!("must serialize: {error}"),
+        };
+        assert!(value.get("tags").is_none(), "got {value}");
+    }
+
+    /// Stored tags that a constructor could not have produced are refused on
+    /// load.
+    #[test]
+    fn test_stored_simulation_rejects_unnormalised_tags() {
+        let mut simulation = SessionV2::new(parameters(reference_request()));
+        simulation.tags = vec!["b".to_string(), "a".to_string()];
+        let json = match serde_json::to_string(&simulation) {
+            Ok(json) => json,
+            Err(error) => panic!("must serialize: {error}"),
+        };
+
+        let error = match serde_json::from_str::<SessionV2>(&json) {
+            Ok(_) => panic!("unsorted tags must be rejected"),
+            Err(error) => error.to_string(),
+        };
+        assert!(error.contains("tags"), "got {error}");
+    }
+
+    // ---- retention -------------------------------------------------------
+
+    /// A retention outside `1..=ceiling`, or one on a pinned simulation, is
+    /// refused naming the request field.
+    #[test]
+    fn test_invalid_retention_is_rejected_by_name() {
+        let cases = [
+            (Some(0), false),
+            (Some(max_retention_secs() + 1), false),
+            (Some(60), true),
+        ];
+        for (retention_seconds, pinned) in cases {
+            let mut request = reference_request();
+            request.retention_seconds = retention_seconds;
+            request.pinned = pinned;
+            match SimulationOptions::try_from(&request) {
+                Err(ChainError::Validation { field, .. }) => {
+                    assert_eq!(field, "retention_seconds");
+                }
+                other => panic!("{retention_seconds:?}/{pinned} must be rejected, got {other:?}"),
+            }
+        }
+    }
+
+    /// Consecutive seeks extend one skipped run, an advance in between starts
+    /// another, and the served count leaves both out.
+    #[test]
+    fn test_seeks_record_the_steps_they_skip() {
+        let mut simulation = SessionV2::new(parameters(reference_request()));
+        simulation.seek_to(2);
+        simulation.seek_to(4);
+        simulation.current_step += 1;
+        simulation.seek_to(7);
+
+        assert_eq!(
+            simulation.skipped,
+            vec![
+                SkippedSteps { from: 0, to: 4 },
+                SkippedSteps { from: 5, to: 7 },
+            ]
+        );
+        assert_eq!(
+            simulation.skipped_run(3),
+            Some(SkippedSteps { from: 0, to: 4 })
+        );
+        assert_eq!(simulation.skipped_run(4), None);
+        assert_eq!(simulation.served_between(0, 10), 4);
+        assert_eq!(simulation.served_between(3, 6), 1);
+    }
+
+    /// A stored document whose skipped runs overlap or run ahead of the cursor
+    /// is refused by name.
+    #[test]
+    fn test_stored_simulation_rejects_inconsistent_skipped_runs() {
+        let mut request = reference_request();
+        request.steps = 10;
+        let mut simulation = SessionV2::new(parameters(request));
+        simulation.current_step = 5;
+        simulation.state = SessionState::InProgress;
+        for runs in [
+            vec![SkippedSteps { from: 2, to: 2 }],
+            vec![
+                SkippedSteps { from: 1, to: 3 },
+                SkippedSteps { from: 2, to: 4 },
+            ],
+            vec![SkippedSteps { from: 3, to: 6 }],
+        ] {
+            simulation.skipped = runs;
+            match simulation.validate() {
+                Err(ChainError::Validation { field, .. }) => assert_eq!(field, "skipped"),
+                other => panic!("{:?} must be refused, got {other:?}", simulation.skipped),
+            }
+        }
+    }
+
+    /// A valid retention and a pin reach the simulation, and the simulation
+    /// applies its own window over the default.
+    #[test]
+    fn test_retention_and_pin_reach_the_simulation() {
+        let mut request = reference_request();
+        request.retention_seconds = Some(120);
+        let options = match SimulationOptions::try_from(&request) {
+            Ok(options) => options,
+            Err(error) => panic!("a valid retention must convert: {error}"),
+        };
+        let simulation = SessionV2::new(parameters(reference_request())).with_options(options);
+
+        assert_eq!(simulation.retention_secs, Some(120));
+        assert_eq!(
+            simulation.retention_or(Duration::from_secs(3_600)),
+            Duration::from_secs(120)
+        );
+
+        let mut request = reference_request();
+        request.pinned = true;
+        match SimulationOptions::try_from(&request) {
+            Ok(options) => assert!(options.pinned),
+            Err(error) => panic!("a pin must convert: {error}"),
+        }
+    }
 }
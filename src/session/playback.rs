@@ -0,0 +1,510 @@
+//! Server-driven playback of v2 rolling simulations.
+//!
+//! A playback is a background task that calls [`SimulationManager::advance`]
+//! on a wall-clock pace, so a simulation "ticks" without a client sending a
+//! `POST /step` for every snapshot. It is a driver and nothing more: every
+//! step it takes goes through the same serve-then-advance and the same
+//! compare-and-swap as a manual advance, so everything a manual advance
+//! guarantees — no lost step, filing after the commit, eviction on completion
+//! — holds for a played step too.
+//!
+//! # Manual advances are not locked out
+//!
+//! A client may keep advancing a simulation that is playing. The two race on
+//! the revision exactly like two clients do, and whoever loses gets the
+//! documented `Conflict`. For the playback that loss is not an error: the
+//! winner has already moved the cursor, so the tick is simply skipped and the
+//! next one continues from wherever the cursor now is. Nothing is ever
+//! double-served, because the compare-and-swap decides.
+//!
+//! # What a playback is not
+//!
+//! It is **process-local and not persisted.** The registry lives in the
+//! manager that started it, so a restart stops every playback, and on a
+//! multi-replica deployment a playback runs on the replica that received the
+//! start. The simulation itself is in the store and unaffected; restarting a
+//! playback picks up from its cursor.
+
+use crate::session::manager_v2::SimulationManager;
+use crate::session::model::SessionState;
+use crate::utils::ChainError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// The shortest interval a playback may tick at.
+///
+/// Every tick prices a full snapshot, so this bounds the CPU one playback can
+/// take rather than the responsiveness anyone needs: twenty snapshots a second
+/// is already faster than a human can read a chain.
+pub(crate) const MIN_PLAYBACK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The longest interval a playback may tick at — one day.
+///
+/// Beyond this the playback is a scheduler for something that should be a
+/// cron job, and its task would outlive most deployments anyway.
+pub(crate) const MAX_PLAYBACK_INTERVAL: Duration = Duration::from_secs(86_400);
+
+/// How a client asks a playback to be paced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PlaybackPace {
+    /// A fixed number of steps per wall-clock second, e.g. `1.0`.
+    StepsPerSecond(f64),
+    /// A multiple of real time, relative to the simulation's own step
+    /// interval: `60.0` plays a one-minute step every second.
+    Speed(f64),
+}
+
+impl PlaybackPace {
+    /// Resolves the pace into a tick interval for a simulation whose steps
+    /// are `step_interval_seconds` apart.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `steps_per_second` or `speed`
+    /// when the value is not a positive finite number, or resolves to an
+    /// interval outside [`MIN_PLAYBACK_INTERVAL`]..=[`MAX_PLAYBACK_INTERVAL`].
+    pub(crate) fn interval(self, step_interval_seconds: u64) -> Result<Duration, ChainError> {
+        let (field, value, seconds) = match self {
+            Self::StepsPerSecond(rate) => ("steps_per_second", rate, 1.0 / rate),
+            Self::Speed(speed) => {
+                // Exact for any realistic interval: the model caps it at a year.
+                #[allow(clippy::cast_precision_loss)]
+                let step = step_interval_seconds as f64;
+                ("speed", speed, step / speed)
+            }
+        };
+
+        let out_of_range = || ChainError::Validation {
+            field: field.to_string(),
+            reason: format!(
+                "must resolve to a tick between {} ms and {} s, got {value}",
+                MIN_PLAYBACK_INTERVAL.as_millis(),
+                MAX_PLAYBACK_INTERVAL.as_secs()
+            ),
+        };
+        if !value.is_finite() || value <= 0.0 {
+            return Err(ChainError::Validation {
+                field: field.to_string(),
+                reason: format!("must be a positive number, got {value}"),
+            });
+        }
+        let interval = Duration::try_from_secs_f64(seconds).map_err(|_| out_of_range())?;
+        if !(MIN_PLAYBACK_INTERVAL..=MAX_PLAYBACK_INTERVAL).contains(&interval) {
+            return Err(out_of_range());
+        }
+        Ok(interval)
+    }
+}
+
+/// Where a playback is in its own lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PlaybackState {
+    /// Ticking.
+    Playing,
+    /// Started, currently not ticking.
+    Paused,
+    /// Ended, for the reason in [`PlaybackStatus::finished_reason`].
+    Finished,
+}
+
+/// A snapshot of one playback, as reported to a client.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PlaybackStatus {
+    /// The playback's state.
+    pub(crate) state: PlaybackState,
+    /// The tick interval.
+    pub(crate) interval: Duration,
+    /// When the playback was started, in real time.
+    pub(crate) started_at: SystemTime,
+    /// How many steps this playback has advanced. Manual advances made while
+    /// it ran are not counted.
+    pub(crate) steps_played: u64,
+    /// Why the playback ended, once it has.
+    pub(crate) finished_reason: Option<String>,
+}
+
+/// What the controller tells a running playback.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Control {
+    Play(Duration),
+    Pause,
+    Stop,
+}
+
+/// One registered playback.
+struct Entry {
+    control: watch::Sender<Control>,
+    status: Arc<Mutex<PlaybackStatus>>,
+}
+
+/// The playbacks one manager is running, keyed by simulation.
+///
+/// Owned by [`SimulationManager`] rather than standing beside it, so the one
+/// handle the API layer already holds is enough to drive both, and a delete or
+/// a cleanup can stop the playback of the simulation it removes.
+pub(crate) struct Playbacks {
+    entries: Mutex<HashMap<Uuid, Entry>>,
+    max_active: usize,
+}
+
+impl Playbacks {
+    /// An empty registry admitting at most `max_active` live playbacks.
+    pub(crate) fn new(max_active: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_active,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Entry>> {
+        match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Registers a playback for `id` ticking every `interval` and spawns its
+    /// driver.
+    ///
+    /// A finished playback is replaced; a live one is not. Registering also
+    /// forgets every other finished playback: a finished one stays readable
+    /// until the next playback starts, so the registry holds at most
+    /// `max_active` live entries plus the finished ones since the last start.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Conflict`] when `id` already has a live playback
+    /// or the registry is at its limit.
+    pub(crate) fn start(
+        &self,
+        manager: Arc<SimulationManager>,
+        id: Uuid,
+        interval: Duration,
+    ) -> Result<PlaybackStatus, ChainError> {
+        let mut entries = self.lock();
+        entries.retain(|_, entry| !entry.is_finished());
+
+        if entries.contains_key(&id) {
+            return Err(ChainError::Conflict(format!(
+                "Simulation {id} is already playing; pause, resume or stop it instead"
+            )));
+        }
+        if entries.len() >= self.max_active {
+            return Err(ChainError::Conflict(format!(
+                "The service is already running its limit of {} playbacks",
+                self.max_active
+            )));
+        }
+
+        let status = Arc::new(Mutex::new(PlaybackStatus {
+            state: PlaybackState::Playing,
+            interval,
+            started_at: SystemTime::now(),
+            steps_played: 0,
+            finished_reason: None,
+        }));
+        let (control, receiver) = watch::channel(Control::Play(interval));
+        tokio::spawn(drive(manager, id, receiver, Arc::clone(&status)));
+
+        let snapshot = read(&status);
+        entries.insert(id, Entry { control, status });
+        info!(simulation_id = %id, interval_ms = interval.as_millis(), "Started playback");
+        Ok(snapshot)
+    }
+
+    /// Pauses a live playback. Pausing a paused one is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when `id` has no playback, or
+    /// [`ChainError::InvalidState`] when it has finished.
+    pub(crate) fn pause(&self, id: Uuid) -> Result<PlaybackStatus, ChainError> {
+        self.steer(id, |_| Control::Pause, PlaybackState::Paused)
+    }
+
+    /// Resumes a paused playback at its last interval. Resuming a playing one
+    /// is a no-op.
+    ///
+    /// # Errors
+    ///
+    /// As [`Playbacks::pause`].
+    pub(crate) fn resume(&self, id: Uuid) -> Result<PlaybackStatus, ChainError> {
+        self.steer(id, Control::Play, PlaybackState::Playing)
+    }
+
+    /// Stops and forgets a playback, if there is one. Returns whether there was.
+    pub(crate) fn stop(&self, id: Uuid) -> bool {
+        match self.lock().remove(&id) {
+            Some(entry) => {
+                // A driver that already ended has dropped its receiver, which
+                // is exactly the state being asked for.
+                let _ = entry.control.send(Control::Stop);
+                debug!(simulation_id = %id, "Stopped playback");
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The status of `id`'s playback.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::NotFound`] when `id` has no playback.
+    pub(crate) fn status(&self, id: Uuid) -> Result<PlaybackStatus, ChainError> {
+        self.lock()
+            .get(&id)
+            .map(|entry| read(&entry.status))
+            .ok_or_else(|| no_playback(id))
+    }
+
+    fn steer(
+        &self,
+        id: Uuid,
+        control: impl FnOnce(Duration) -> Control,
+        state: PlaybackState,
+    ) -> Result<PlaybackStatus, ChainError> {
+        let entries = self.lock();
+        let entry = entries.get(&id).ok_or_else(|| no_playback(id))?;
+
+        // The check and the write share one hold of the status lock: a driver
+        // that finishes in between would otherwise have its `Finished`
+        // overwritten, and the entry would hold a live slot forever.
+        let mut status = match entry.status.lock() {
+            Ok(status) => status,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if status.state == PlaybackState::Finished {
+            return Err(ChainError::InvalidState(format!(
+                "The playback of simulation {id} has finished; start a new one"
+            )));
+        }
+        status.state = state;
+        let _ = entry.control.send(control(status.interval));
+        Ok(status.clone())
+    }
+}
+
+impl Entry {
+    fn is_finished(&self) -> bool {
+        read(&self.status).state == PlaybackState::Finished
+    }
+}
+
+fn read(status: &Mutex<PlaybackStatus>) -> PlaybackStatus {
+    match status.lock() {
+        Ok(status) => status.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    }
+}
+
+#[cold]
+fn no_playback(id: Uuid) -> ChainError {
+    ChainError::NotFound(format!("Simulation {id} has no playback"))
+}
+
+/// Records that a playback ended and why.
+fn finish(status: &Mutex<PlaybackStatus>, reason: impl Into<String>) {
+    let mut status = match status.lock() {
+        Ok(status) => status,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    status.state = PlaybackState::Finished;
+    status.finished_reason = Some(reason.into());
+}
+
+/// The driver: ticks until the simulation completes, disappears, fails, or the
+/// controller says stop.
+///
+/// Ticks are scheduled against a deadline rather than slept between, so the
+/// time an advance takes does not stretch the pace. A tick that overruns its
+/// successor's deadline is not made up with a burst: the schedule slips to
+/// now, which is the behaviour a client watching a live market expects.
+async fn drive(
+    manager: Arc<SimulationManager>,
+    id: Uuid,
+    mut control: watch::Receiver<Control>,
+    status: Arc<Mutex<PlaybackStatus>>,
+) {
+    let mut next: Option<Instant> = None;
+
+    loop {
+        let current = *control.borrow_and_update();
+        let interval = match current {
+            Control::Stop => return,
+            Control::Pause => {
+                next = None;
+                if control.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            Control::Play(interval) => interval,
+        };
+
+        let deadline = *next.get_or_insert_with(|| Instant::now() + interval);
+        tokio::select! {
+            changed = control.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                continue;
+            }
+            () = tokio::time::sleep_until(deadline) => {}
+        }
+        next = Some((deadline + interval).max(Instant::now()));
+
+        match manager.advance(id).await {
+            Ok((simulation, _)) => {
+                match status.lock() {
+                    Ok(mut status) => status.steps_played += 1,
+                    Err(poisoned) => poisoned.into_inner().steps_played += 1,
+                }
+                if simulation.state == SessionState::Completed {
+                    finish(&status, "completed");
+                    info!(simulation_id = %id, "Playback reached the end of the simulation");
+                    return;
+                }
+            }
+            // A manual advance committed first; it already moved the cursor
+            // this tick would have, so there is nothing to redo.
+            Err(ChainError::Conflict(_)) => {
+                debug!(simulation_id = %id, "Playback tick lost to a concurrent advance");
+            }
+            // A simulation that completed under a concurrent advance refuses
+            // this one as a `SimulatorError` — but so would a pricing failure,
+            // so the simulation itself says which it was.
+            Err(ChainError::SimulatorError(error)) => {
+                match manager.get(id).await {
+                    Ok(simulation)
+                        if simulation.state == SessionState::Completed
+                            || simulation.is_complete() =>
+                    {
+                        finish(&status, "completed");
+                    }
+                    _ => {
+                        warn!(simulation_id = %id, %error, "Playback stopped by a failed advance");
+                        finish(&status, format!("stopped by a failed advance: {error}"));
+                    }
+                }
+                return;
+            }
+            Err(ChainError::NotFound(_)) => {
+                finish(&status, "the simulation no longer exists");
+                return;
+            }
+            Err(error) => {
+                warn!(simulation_id = %id, %error, "Playback stopped by a failed advance");
+                finish(&status, format!("stopped by a failed advance: {error}"));
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Registers an entry with no driver behind it, in `state`.
+    fn register(
+        playbacks: &Playbacks,
+        id: Uuid,
+        state: PlaybackState,
+    ) -> Arc<Mutex<PlaybackStatus>> {
+        let status = Arc::new(Mutex::new(PlaybackStatus {
+            state,
+            interval: Duration::from_secs(1),
+            started_at: SystemTime::now(),
+            steps_played: 0,
+            finished_reason: None,
+        }));
+        let (control, _) = watch::channel(Control::Play(Duration::from_secs(1)));
+        playbacks.lock().insert(
+            id,
+            Entry {
+                control,
+                status: Arc::clone(&status),
+            },
+        );
+        status
+    }
+
+    /// Steering a finished playback is refused and leaves it finished, so it
+    /// can never come back to life and hold a slot.
+    #[test]
+    fn test_a_finished_playback_cannot_be_steered_back_to_life() {
+        let playbacks = Playbacks::new(1);
+        let id = Uuid::new_v4();
+        let status = register(&playbacks, id, PlaybackState::Playing);
+        finish(&status, "completed");
+
+        for steered in [playbacks.pause(id), playbacks.resume(id)] {
+            match steered {
+                Err(ChainError::InvalidState(_)) => {}
+                other => panic!("a finished playback must not be steered, got {other:?}"),
+            }
+        }
+        assert_eq!(read(&status).state, PlaybackState::Finished);
+    }
+
+    /// Starting a playback forgets the finished ones, and only live ones count
+    /// against the limit.
+    #[tokio::test]
+    async fn test_starting_a_playback_prunes_the_finished_ones() {
+        let manager = Arc::new(SimulationManager::new(
+            Arc::new(crate::session::InMemorySimulationStore::new()),
+            crate::infrastructure::SimulationV2Config::default(),
+        ));
+        let playbacks = Playbacks::new(1);
+        let finished = Uuid::new_v4();
+        let status = register(&playbacks, finished, PlaybackState::Playing);
+        finish(&status, "completed");
+
+        let id = Uuid::new_v4();
+        if let Err(error) = playbacks.start(manager, id, Duration::from_secs(60)) {
+            panic!("a finished playback must not hold the only slot: {error}");
+        }
+        assert!(matches!(
+            playbacks.status(finished),
+            Err(ChainError::NotFound(_))
+        ));
+        assert!(playbacks.stop(id));
+    }
+
+    /// Both paces resolve to the interval they describe.
+    #[test]
+    fn test_paces_resolve_to_their_interval() {
+        match PlaybackPace::StepsPerSecond(2.0).interval(86_400) {
+            Ok(interval) => assert_eq!(interval, Duration::from_millis(500)),
+            Err(error) => panic!("a valid rate must resolve: {error}"),
+        }
+        match PlaybackPace::Speed(60.0).interval(60) {
+            Ok(interval) => assert_eq!(interval, Duration::from_secs(1)),
+            Err(error) => panic!("a valid speed must resolve: {error}"),
+        }
+    }
+
+    /// A pace that is not a positive number, or ticks too fast or too slowly,
+    /// is refused naming the field it came from.
+    #[test]
+    fn test_out_of_range_paces_are_rejected_by_name() {
+        for (pace, field) in [
+            (PlaybackPace::StepsPerSecond(0.0), "steps_per_second"),
+            (PlaybackPace::StepsPerSecond(f64::NAN), "steps_per_second"),
+            (PlaybackPace::StepsPerSecond(1_000.0), "steps_per_second"),
+            (PlaybackPace::Speed(-1.0), "speed"),
+            (PlaybackPace::Speed(1e-9), "speed"),
+        ] {
+            match pace.interval(86_400) {
+                Err(ChainError::Validation { field: named, .. }) => assert_eq!(named, field),
+                other => panic!("{pace:?} must be rejected, got {other:?}"),
+            }
+        }
+    }
+}
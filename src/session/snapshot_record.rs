@@ -15,6 +15,7 @@ use crate::domain::series::SeriesSnapshot;
 use crate::infrastructure::{
     CURRENT_SNAPSHOT_GENERATION, ExpirationRecord, QuoteRow, SnapshotRecord,
 };
+use crate::session::engine::engine_version;
 
 /// The tape generation persisted snapshots are filed under.
 ///
@@ -54,6 +55,9 @@ pub(crate) fn snapshot_quote_count(snapshot: &SeriesSnapshot) -> usize {
 /// chronologically and upstream holds strikes in a `BTreeSet`, so the record
 /// reproduces the canonical order without sorting anything. That is what lets a
 /// reconstruction from the warehouse compare equal to the in-memory snapshot.
+///
+/// The record is tagged with the running engine, which is the engine that just
+/// priced it — not necessarily the one the simulation was created under.
 pub(crate) fn snapshot_record(
     simulation: uuid::Uuid,
     symbol: &str,
@@ -89,6 +93,7 @@ pub(crate) fn snapshot_record(
     SnapshotRecord {
         simulation,
         generation: SNAPSHOT_TAPE_GENERATION,
+        engine_version: engine_version().to_string(),
         step: snapshot.step,
         simulated_at: snapshot.simulated_at,
         symbol: symbol.to_string(),
@@ -152,6 +157,9 @@ mod tests {
             smile_curve: None,
             spread: Some(0.02),
             seed: Some(42),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         };
 
         match SimulationParametersV2::try_from(request) {
@@ -186,6 +194,7 @@ mod tests {
 
         assert_eq!(record.simulation, simulation);
         assert_eq!(record.generation, SNAPSHOT_TAPE_GENERATION);
+        assert_eq!(record.engine_version, engine_version());
         assert_eq!(record.step, snapshot.step);
         assert_eq!(record.simulated_at, snapshot.simulated_at);
         assert_eq!(record.symbol, parameters.symbol);
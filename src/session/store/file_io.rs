@@ -0,0 +1,279 @@
+//! Crash-safe document files shared by the file-backed stores.
+//!
+//! A store is a directory holding one `<id>.json` file per document. A write
+//! goes to a temporary file beside its target, is flushed to disk, and is then
+//! renamed over the target; a rename within one directory is atomic, so a crash
+//! leaves either the old document or the new one, never a torn mix. Temporary
+//! files a crash left behind are removed when the directory is opened.
+//!
+//! # One process per directory
+//!
+//! Every operation runs under one in-process lock, which is what makes a
+//! compare-and-swap's read and write a single step. That lock means nothing to
+//! another process, so opening a directory also takes an exclusive lock on its
+//! `.lock` file for as long as the store lives: a second process pointed at the
+//! same directory fails at startup instead of racing the first.
+
+use crate::utils::error::ChainError;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// The file a store's directory is locked through.
+const LOCK_FILE: &str = ".lock";
+
+/// The extension of a document file.
+const DOCUMENT_EXTENSION: &str = "json";
+
+/// The extension of a write in flight.
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+/// A locked directory of document files.
+pub(super) struct DocumentDir {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    documents: Documents,
+    guard: Mutex<()>,
+    /// Held open for the lock it carries; released when the store is dropped.
+    _owner: File,
+}
+
+impl DocumentDir {
+    /// Opens `root/name`, creating it if needed, and takes it for this process.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when the directory cannot be created or
+    /// cleaned, and [`ChainError::Conflict`] when another process holds it.
+    pub(super) fn open(root: &Path, name: &str) -> Result<Self, ChainError> {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).map_err(|e| io_error(&dir, &e))?;
+
+        let lock_path = dir.join(LOCK_FILE);
+        let owner = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| io_error(&lock_path, &e))?;
+        match owner.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(ChainError::Conflict(format!(
+                    "{} is in use by another process",
+                    dir.display()
+                )));
+            }
+            Err(TryLockError::Error(e)) => return Err(io_error(&lock_path, &e)),
+        }
+
+        let documents = Documents { dir };
+        documents.remove_temporaries()?;
+        info!(dir = %documents.dir.display(), "Opened file-backed store");
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                documents,
+                guard: Mutex::new(()),
+                _owner: owner,
+            }),
+        })
+    }
+
+    /// Runs `work` against the documents, exclusively and off the async
+    /// runtime: file I/O blocks, and a worker thread waiting on `fsync` is one
+    /// that serves nothing else.
+    pub(super) async fn run<R, F>(&self, work: F) -> Result<R, ChainError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&Documents) -> Result<R, ChainError> + Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        tokio::task::spawn_blocking(move || {
+            let _guard = inner.guard.lock().map_err(|_| {
+                ChainError::Internal("Failed to acquire lock on file store".to_string())
+            })?;
+            work(&inner.documents)
+        })
+        .await
+        .map_err(|e| ChainError::Internal(format!("File store task failed: {e}")))?
+    }
+}
+
+/// The documents of one directory, reachable only through [`DocumentDir::run`].
+pub(super) struct Documents {
+    dir: PathBuf,
+}
+
+impl Documents {
+    fn path(&self, id: Uuid) -> PathBuf {
+        self.dir.join(format!("{id}.{DOCUMENT_EXTENSION}"))
+    }
+
+    /// Reads the document stored under `id`, or `None` when there is none.
+    pub(super) fn read<T: DeserializeOwned>(&self, id: Uuid) -> Result<Option<T>, ChainError> {
+        let path = self.path(id);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(io_error(&path, &e)),
+        };
+        serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+            ChainError::Internal(format!("Failed to deserialize {}: {e}", path.display()))
+        })
+    }
+
+    /// Replaces the document stored under `id`, atomically and durably.
+    pub(super) fn write<T: Serialize>(&self, id: Uuid, value: &T) -> Result<(), ChainError> {
+        let path = self.path(id);
+        let bytes = serde_json::to_vec(value).map_err(|e| {
+            ChainError::Internal(format!("Failed to serialize {}: {e}", path.display()))
+        })?;
+
+        // Writes are serialized by the directory's lock, so one temporary name
+        // per document is enough.
+        let temporary = path.with_extension(TEMPORARY_EXTENSION);
+        let mut file = File::create(&temporary).map_err(|e| io_error(&temporary, &e))?;
+        file.write_all(&bytes)
+            .and_then(|()| file.sync_all())
+            .map_err(|e| io_error(&temporary, &e))?;
+        fs::rename(&temporary, &path).map_err(|e| io_error(&path, &e))?;
+        self.sync_dir()
+    }
+
+    /// Removes the document stored under `id`, reporting whether there was one.
+    pub(super) fn remove(&self, id: Uuid) -> Result<bool, ChainError> {
+        let path = self.path(id);
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                self.sync_dir()?;
+                Ok(true)
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(io_error(&path, &e)),
+        }
+    }
+
+    /// The ids of every stored document. A file that is not named after one
+    /// is not a document and is ignored.
+    pub(super) fn ids(&self) -> Result<Vec<Uuid>, ChainError> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| io_error(&self.dir, &e))?;
+        let mut ids = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(&self.dir, &e))?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(DOCUMENT_EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| Uuid::parse_str(stem).ok())
+            {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Removes the temporary files of writes a crash interrupted. Their
+    /// targets still hold the previous document, which is the one to keep.
+    fn remove_temporaries(&self) -> Result<(), ChainError> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| io_error(&self.dir, &e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| io_error(&self.dir, &e))?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some(TEMPORARY_EXTENSION) {
+                warn!(path = %path.display(), "Removing an interrupted write");
+                fs::remove_file(&path).map_err(|e| io_error(&path, &e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes a rename or removal in the directory durable. Without it a crash
+    /// right after the call could bring the old directory entry back.
+    fn sync_dir(&self) -> Result<(), ChainError> {
+        #[cfg(unix)]
+        File::open(&self.dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| io_error(&self.dir, &e))?;
+        Ok(())
+    }
+}
+
+/// The error for a failed filesystem call, naming the path it failed on.
+#[cold]
+fn io_error(path: &Path, error: &std::io::Error) -> ChainError {
+    ChainError::Internal(format!("File store error at {}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> tempfile::TempDir {
+        match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        }
+    }
+
+    fn open(root: &Path) -> DocumentDir {
+        match DocumentDir::open(root, "documents") {
+            Ok(dir) => dir,
+            Err(error) => panic!("the directory must open: {error}"),
+        }
+    }
+
+    /// A second store on the same directory is refused while the first lives,
+    /// and admitted once it is gone.
+    #[test]
+    fn test_a_directory_is_held_by_one_store_at_a_time() {
+        let root = temp_root();
+        let first = open(root.path());
+
+        match DocumentDir::open(root.path(), "documents") {
+            Err(ChainError::Conflict(reason)) => assert!(reason.contains("in use"), "{reason}"),
+            Err(error) => panic!("expected a conflict, got {error}"),
+            Ok(_) => panic!("a held directory must not open twice"),
+        }
+
+        drop(first);
+        open(root.path());
+    }
+
+    /// A write a crash interrupted is discarded on open, and the document it
+    /// would have replaced survives.
+    #[tokio::test]
+    async fn test_an_interrupted_write_leaves_the_previous_document() {
+        let root = temp_root();
+        let id = Uuid::new_v4();
+        let dir = open(root.path());
+        if let Err(error) = dir.run(move |documents| documents.write(id, &1_u32)).await {
+            panic!("the write must succeed: {error}");
+        }
+        drop(dir);
+
+        let torn = root.path().join("documents").join(format!("{id}.tmp"));
+        if let Err(error) = fs::write(&torn, b"{\"trunc") {
+            panic!("the torn write must be staged: {error}");
+        }
+
+        let dir = open(root.path());
+        assert!(!torn.exists(), "the interrupted write must be removed");
+        match dir.run(move |documents| documents.read::<u32>(id)).await {
+            Ok(value) => assert_eq!(value, Some(1)),
+            Err(error) => panic!("the previous document must read: {error}"),
+        }
+        match dir.run(|documents| documents.ids()).await {
+            Ok(ids) => assert_eq!(ids, vec![id]),
+            Err(error) => panic!("the listing must succeed: {error}"),
+        }
+    }
+}
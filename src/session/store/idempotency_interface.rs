@@ -0,0 +1,127 @@
+//! Persistence contract for `Idempotency-Key` records.
+//!
+//! A record remembers the first response a key produced, so a client retrying
+//! after a timeout gets that response back instead of a second simulation or a
+//! second advance. It is a separate trait from [`super::SimulationStore`]
+//! because it stores a different thing with a different lifetime: a record
+//! outlives neither its window nor, usefully, the simulation it describes, and
+//! it is keyed by whatever string the client chose rather than by an id the
+//! service minted.
+//!
+//! # A record's life
+//!
+//! [`IdempotencyStore::claim`] is the only entry point. The first request with
+//! a key **acquires** it: the store writes an in-flight marker carrying the
+//! request's fingerprint, for [`IN_FLIGHT_LEASE`] only. The request then either
+//! [`completes`](IdempotencyStore::complete) the record with its response,
+//! which is kept for the configured window, or
+//! [`releases`](IdempotencyStore::release) it when the outcome should not be
+//! replayed. Every later claim of the key sees what is there: a completed
+//! record to replay, a marker still in flight, or a different fingerprint.
+//!
+//! The lease is what stops a request that died mid-flight — its process
+//! killed between the claim and the completion — from locking its key for the
+//! whole window. Once it lapses the key can be claimed afresh.
+
+use crate::utils::error::ChainError;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long an acquired key stays claimed before its response is recorded.
+///
+/// Longer than any create or advance takes — the slowest, a cold advance,
+/// builds one factor tape — so a live request never loses its claim, and short
+/// enough that a crashed one frees its key within minutes.
+pub const IN_FLIGHT_LEASE: Duration = Duration::from_secs(120);
+
+/// A response as it is stored and replayed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredResponse {
+    /// The HTTP status.
+    pub status: u16,
+    /// The `Content-Type` it was sent with.
+    pub content_type: String,
+    /// Every other header the handler set, such as the `ETag` a client needs
+    /// to send its next `If-Match`, in the order they were sent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// The body, verbatim. Every response this is used for is JSON, so it is
+    /// stored as text rather than as an array of bytes.
+    pub body: String,
+}
+
+/// What is stored under a key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    /// The fingerprint of the request that claimed the key.
+    pub fingerprint: String,
+    /// Its response, or `None` while it is still in flight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<StoredResponse>,
+}
+
+/// The outcome of claiming a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// The key was free and now belongs to this request.
+    Acquired,
+    /// The key has a recorded response for this same request.
+    Replay(StoredResponse),
+    /// Another request with this key and fingerprint has not finished yet.
+    InFlight,
+    /// The key was first used for a different request.
+    Mismatch,
+}
+
+impl IdempotencyClaim {
+    /// What a claim finding `existing` under its key comes to.
+    ///
+    /// The single definition of the comparison, so both backends answer a
+    /// reused key the same way.
+    #[must_use]
+    pub fn from_existing(existing: IdempotencyRecord, fingerprint: &str) -> Self {
+        if existing.fingerprint != fingerprint {
+            return IdempotencyClaim::Mismatch;
+        }
+        match existing.response {
+            Some(response) => IdempotencyClaim::Replay(response),
+            None => IdempotencyClaim::InFlight,
+        }
+    }
+}
+
+/// A storage backend for `Idempotency-Key` records.
+///
+/// Implementations must be thread-safe and shareable (`Send + Sync`), and
+/// [`claim`](Self::claim) must be atomic: two concurrent claims of a free key
+/// must not both acquire it.
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Claims `key` for a request with `fingerprint`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChainError`] on a storage or deserialization failure.
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<IdempotencyClaim, ChainError>;
+
+    /// Records the response of the request that acquired `key`, to be replayed
+    /// for the store's window.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChainError`] on a storage failure.
+    async fn complete(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        response: StoredResponse,
+    ) -> Result<(), ChainError>;
+
+    /// Frees `key` without recording a response, so a retry runs afresh.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChainError`] on a storage failure.
+    async fn release(&self, key: &str) -> Result<(), ChainError>;
+}
@@ -0,0 +1,206 @@
+//! In-memory [`IdempotencyStore`].
+//!
+//! The backend the in-memory simulation store pairs with: a record lives as
+//! long as the process, so it protects a retry against a lost response but not
+//! against a restart — which is also all the simulations it describes survive.
+
+use crate::session::store::idempotency_interface::{
+    IN_FLIGHT_LEASE, IdempotencyClaim, IdempotencyRecord, IdempotencyStore, StoredResponse,
+};
+use crate::utils::error::ChainError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How often a claim sweeps expired records out of the map.
+///
+/// Sweeping on every claim would make each one linear in the number of stored
+/// keys; never sweeping would keep every key for the life of the process.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One stored record and when it stops counting.
+struct Entry {
+    record: IdempotencyRecord,
+    expires_at: Instant,
+}
+
+/// The map and when it was last swept.
+struct Records {
+    entries: HashMap<String, Entry>,
+    swept_at: Instant,
+}
+
+/// In-memory store for `Idempotency-Key` records.
+pub struct InMemoryIdempotencyStore {
+    records: Mutex<Records>,
+    window: Duration,
+    lease: Duration,
+}
+
+impl InMemoryIdempotencyStore {
+    /// Creates a store that replays a recorded response for `window`.
+    #[must_use]
+    pub fn new(window: Duration) -> Self {
+        Self {
+            records: Mutex::new(Records {
+                entries: HashMap::new(),
+                swept_at: Instant::now(),
+            }),
+            window,
+            lease: IN_FLIGHT_LEASE,
+        }
+    }
+
+    /// Locks the map, mapping a poisoned lock into the error boundary rather
+    /// than panicking on a request path.
+    fn lock(&self) -> Result<MutexGuard<'_, Records>, ChainError> {
+        self.records.lock().map_err(|_| {
+            ChainError::Internal("Failed to acquire lock on idempotency store".to_string())
+        })
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<IdempotencyClaim, ChainError> {
+        let now = Instant::now();
+        let mut records = self.lock()?;
+
+        if now.duration_since(records.swept_at) >= SWEEP_INTERVAL {
+            records.entries.retain(|_, entry| entry.expires_at > now);
+            records.swept_at = now;
+        }
+
+        if let Some(entry) = records.entries.get(key)
+            && entry.expires_at > now
+        {
+            return Ok(IdempotencyClaim::from_existing(
+                entry.record.clone(),
+                fingerprint,
+            ));
+        }
+
+        records.entries.insert(
+            key.to_string(),
+            Entry {
+                record: IdempotencyRecord {
+                    fingerprint: fingerprint.to_string(),
+                    response: None,
+                },
+                expires_at: now + self.lease,
+            },
+        );
+        Ok(IdempotencyClaim::Acquired)
+    }
+
+    async fn complete(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        response: StoredResponse,
+    ) -> Result<(), ChainError> {
+        let mut records = self.lock()?;
+        records.entries.insert(
+            key.to_string(),
+            Entry {
+                record: IdempotencyRecord {
+                    fingerprint: fingerprint.to_string(),
+                    response: Some(response),
+                },
+                expires_at: Instant::now() + self.window,
+            },
+        );
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> Result<(), ChainError> {
+        self.lock()?.entries.remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> StoredResponse {
+        StoredResponse {
+            status: 201,
+            content_type: "application/json".to_string(),
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    async fn claim(
+        store: &InMemoryIdempotencyStore,
+        key: &str,
+        fingerprint: &str,
+    ) -> IdempotencyClaim {
+        match store.claim(key, fingerprint).await {
+            Ok(claim) => claim,
+            Err(error) => panic!("the claim must succeed: {error}"),
+        }
+    }
+
+    /// A key is acquired once, in flight until completed, then replayed — and
+    /// refused for a different request throughout.
+    #[tokio::test]
+    async fn test_a_key_is_acquired_then_replayed() {
+        let store = InMemoryIdempotencyStore::new(Duration::from_secs(60));
+
+        assert_eq!(claim(&store, "k", "a").await, IdempotencyClaim::Acquired);
+        assert_eq!(claim(&store, "k", "a").await, IdempotencyClaim::InFlight);
+        assert_eq!(claim(&store, "k", "b").await, IdempotencyClaim::Mismatch);
+
+        if let Err(error) = store.complete("k", "a", response("{}")).await {
+            panic!("the completion must succeed: {error}");
+        }
+        assert_eq!(
+            claim(&store, "k", "a").await,
+            IdempotencyClaim::Replay(response("{}"))
+        );
+        assert_eq!(claim(&store, "k", "b").await, IdempotencyClaim::Mismatch);
+    }
+
+    /// A released key, an expired record and a lapsed lease can all be claimed
+    /// afresh.
+    #[tokio::test]
+    async fn test_a_key_frees_on_release_and_expiry() {
+        let store = InMemoryIdempotencyStore {
+            lease: Duration::ZERO,
+            ..InMemoryIdempotencyStore::new(Duration::ZERO)
+        };
+
+        assert_eq!(
+            claim(&store, "lapsed", "a").await,
+            IdempotencyClaim::Acquired
+        );
+        assert_eq!(
+            claim(&store, "lapsed", "b").await,
+            IdempotencyClaim::Acquired
+        );
+
+        if let Err(error) = store.complete("expired", "a", response("{}")).await {
+            panic!("the completion must succeed: {error}");
+        }
+        assert_eq!(
+            claim(&store, "expired", "b").await,
+            IdempotencyClaim::Acquired
+        );
+
+        let store = InMemoryIdempotencyStore::new(Duration::from_secs(60));
+        assert_eq!(
+            claim(&store, "released", "a").await,
+            IdempotencyClaim::Acquired
+        );
+        if let Err(error) = store.release("released").await {
+            panic!("the release must succeed: {error}");
+        }
+        assert_eq!(
+            claim(&store, "released", "b").await,
+            IdempotencyClaim::Acquired
+        );
+    }
+}
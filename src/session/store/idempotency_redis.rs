@@ -0,0 +1,201 @@
+//! Redis-backed [`IdempotencyStore`].
+//!
+//! One key per `Idempotency-Key`, under its own prefix —
+//! `optionchain:idempotency:v2:` by default — holding the record as JSON with a
+//! TTL: the in-flight lease while the request runs, the configured window once
+//! its response is recorded. Redis expiring the key is the whole of retention;
+//! there is no index and nothing for a sweep to do.
+//!
+//! Every replica shares the keys, so a retry landing on a different replica
+//! than the original still finds its record.
+
+use crate::infrastructure::RedisClient;
+use crate::session::store::idempotency_interface::{
+    IN_FLIGHT_LEASE, IdempotencyClaim, IdempotencyRecord, IdempotencyStore, StoredResponse,
+};
+use crate::utils::error::ChainError;
+use async_trait::async_trait;
+use redis::{AsyncCommands, RedisError};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument};
+
+/// Default Redis key prefix for idempotency records.
+pub const DEFAULT_IDEMPOTENCY_KEY_PREFIX: &str = "optionchain:idempotency:v2:";
+
+/// Claims a key: returns what is stored under it, or writes the in-flight
+/// marker and returns nothing.
+///
+/// One unit, so two concurrent claims of a free key cannot both find it free.
+///
+/// Keys: `KEYS[1]` record.
+/// Arguments: `ARGV[1]` in-flight record JSON, `ARGV[2]` lease in seconds.
+const CLAIM_SCRIPT: &str = r#"
+local existing = redis.call('GET', KEYS[1])
+if existing then
+    return existing
+end
+redis.call('SET', KEYS[1], ARGV[1], 'EX', tonumber(ARGV[2]))
+return false
+"#;
+
+/// Redis-backed store for `Idempotency-Key` records.
+pub struct InRedisIdempotencyStore {
+    client: Arc<RedisClient>,
+    key_prefix: String,
+    window: Duration,
+}
+
+impl InRedisIdempotencyStore {
+    /// Creates a Redis-backed idempotency store that replays a recorded
+    /// response for `window`.
+    ///
+    /// `key_prefix` defaults to [`DEFAULT_IDEMPOTENCY_KEY_PREFIX`].
+    #[must_use]
+    #[instrument(skip(client), level = "debug")]
+    pub fn new(client: Arc<RedisClient>, key_prefix: Option<String>, window: Duration) -> Self {
+        let prefix = key_prefix.unwrap_or_else(|| DEFAULT_IDEMPOTENCY_KEY_PREFIX.to_string());
+        info!(
+            key_prefix = %prefix,
+            window_secs = window.as_secs(),
+            "Created new Redis idempotency store"
+        );
+        Self {
+            client,
+            key_prefix: prefix,
+            window,
+        }
+    }
+
+    /// The key holding a record.
+    #[must_use]
+    #[inline]
+    fn record_key(&self, key: &str) -> String {
+        format!("{}{key}", self.key_prefix)
+    }
+
+    /// Maps a Redis error into the error boundary.
+    fn map_redis_error(err: RedisError) -> ChainError {
+        ChainError::Internal(format!("Redis error: {err}"))
+    }
+
+    /// Serializes a record.
+    fn serialize(record: &IdempotencyRecord) -> Result<String, ChainError> {
+        serde_json::to_string(record).map_err(|e| {
+            ChainError::Internal(format!("Failed to serialize idempotency record: {e}"))
+        })
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for InRedisIdempotencyStore {
+    #[instrument(skip(self), level = "debug")]
+    async fn claim(&self, key: &str, fingerprint: &str) -> Result<IdempotencyClaim, ChainError> {
+        let marker = Self::serialize(&IdempotencyRecord {
+            fingerprint: fingerprint.to_string(),
+            response: None,
+        })?;
+        let mut conn = self.client.connection_manager();
+        let existing: Option<String> = redis::Script::new(CLAIM_SCRIPT)
+            .key(self.record_key(key))
+            .arg(marker)
+            .arg(IN_FLIGHT_LEASE.as_secs().max(1))
+            .invoke_async(&mut conn)
+            .await
+            .map_err(Self::map_redis_error)?;
+
+        match existing {
+            None => {
+                debug!("Idempotency key acquired");
+                Ok(IdempotencyClaim::Acquired)
+            }
+            Some(json) => {
+                let record = serde_json::from_str::<IdempotencyRecord>(&json).map_err(|e| {
+                    error!(error = %e, "Failed to deserialize idempotency record");
+                    ChainError::Internal(format!("Failed to deserialize idempotency record: {e}"))
+                })?;
+                Ok(IdempotencyClaim::from_existing(record, fingerprint))
+            }
+        }
+    }
+
+    #[instrument(skip(self, response), level = "debug")]
+    async fn complete(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        response: StoredResponse,
+    ) -> Result<(), ChainError> {
+        let record = Self::serialize(&IdempotencyRecord {
+            fingerprint: fingerprint.to_string(),
+            response: Some(response),
+        })?;
+        let mut conn = self.client.connection_manager();
+        conn.set_ex::<_, _, ()>(self.record_key(key), record, self.window.as_secs().max(1))
+            .await
+            .map_err(Self::map_redis_error)
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn release(&self, key: &str) -> Result<(), ChainError> {
+        let mut conn = self.client.connection_manager();
+        conn.del::<_, ()>(self.record_key(key))
+            .await
+            .map_err(Self::map_redis_error)
+    }
+}
+
+/// Live integration tests for the Redis idempotency store, `#[ignore]`d like
+/// the simulation store's for the same reason: the claim script is the point,
+/// and a mock would only test the mock.
+#[cfg(test)]
+mod live_tests {
+    use super::*;
+    use crate::infrastructure::RedisConfig;
+    use tokio::test;
+
+    async fn store(prefix: &str) -> InRedisIdempotencyStore {
+        let client = RedisClient::new(RedisConfig::default())
+            .await
+            .expect("the provisioned Redis must accept a connection");
+        InRedisIdempotencyStore::new(
+            Arc::new(client),
+            Some(format!("ocs:test:{prefix}:")),
+            Duration::from_secs(60),
+        )
+    }
+
+    /// A key is acquired once, in flight until completed, then replayed —
+    /// and refused for a different request throughout.
+    #[test]
+    #[ignore = "requires a live Redis matching REDIS_*; run with -- --ignored"]
+    async fn test_a_key_is_acquired_then_replayed() {
+        let store = store("idempotency").await;
+        let key = uuid::Uuid::new_v4().to_string();
+        let response = StoredResponse {
+            status: 201,
+            content_type: "application/json".to_string(),
+            headers: Vec::new(),
+            body: "{}".to_string(),
+        };
+
+        let claim = store.claim(&key, "a").await.expect("must claim");
+        assert_eq!(claim, IdempotencyClaim::Acquired);
+        let claim = store.claim(&key, "a").await.expect("must claim");
+        assert_eq!(claim, IdempotencyClaim::InFlight);
+
+        store
+            .complete(&key, "a", response.clone())
+            .await
+            .expect("must complete");
+        let claim = store.claim(&key, "a").await.expect("must claim");
+        assert_eq!(claim, IdempotencyClaim::Replay(response));
+        let claim = store.claim(&key, "b").await.expect("must claim");
+        assert_eq!(claim, IdempotencyClaim::Mismatch);
+
+        store.release(&key).await.expect("must release");
+        let claim = store.claim(&key, "b").await.expect("must claim");
+        assert_eq!(claim, IdempotencyClaim::Acquired);
+        store.release(&key).await.expect("must release");
+    }
+}
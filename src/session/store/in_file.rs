@@ -0,0 +1,344 @@
+//! File-backed [`SessionStore`] for single-node deployments.
+//!
+//! One JSON file per session under `<root>/sessions`, written crash-safely (see
+//! `file_io`). Sessions expire like their Redis counterparts: a session idle
+//! for longer than the TTL reads as absent and is removed by the next read or
+//! cleanup pass that reaches it.
+
+use crate::session::model::Session;
+use crate::session::store::file_io::{DocumentDir, Documents};
+use crate::session::store::interface::SessionStore;
+use crate::utils::error::ChainError;
+use async_trait::async_trait;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+
+/// The directory under the store root that holds v1 sessions.
+pub const DEFAULT_SESSIONS_DIR: &str = "sessions";
+
+/// File-backed store for v1 sessions.
+pub struct InFileSessionStore {
+    documents: DocumentDir,
+    session_ttl: Duration,
+}
+
+impl InFileSessionStore {
+    /// Opens the store under `root`, creating its directory if needed.
+    ///
+    /// `session_ttl` is in seconds and defaults to 1800 (30 minutes), as for
+    /// [`crate::session::InRedisSessionStore`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when the directory cannot be prepared,
+    /// and [`ChainError::Conflict`] when another process holds it.
+    #[instrument(skip(root), level = "debug")]
+    pub fn open(root: impl AsRef<Path>, session_ttl: Option<u64>) -> Result<Self, ChainError> {
+        let ttl = session_ttl.unwrap_or(1800); // Default 30 minutes
+        let documents = DocumentDir::open(root.as_ref(), DEFAULT_SESSIONS_DIR)?;
+
+        info!(
+            root = %root.as_ref().display(),
+            session_ttl = ttl,
+            "Created new file session store"
+        );
+
+        Ok(Self {
+            documents,
+            session_ttl: Duration::from_secs(ttl),
+        })
+    }
+}
+
+/// Whether a session has been idle for longer than `ttl`.
+///
+/// A session stamped in the future — a clock step — is kept rather than
+/// expired, matching the in-memory store.
+fn is_idle(session: &Session, ttl: Duration) -> bool {
+    SystemTime::now()
+        .duration_since(session.updated_at)
+        .is_ok_and(|idle| idle > ttl)
+}
+
+/// Reads a session, treating one idle past `ttl` as absent and removing it.
+fn read_live(
+    documents: &Documents,
+    id: Uuid,
+    ttl: Duration,
+) -> Result<Option<Session>, ChainError> {
+    match documents.read::<Session>(id)? {
+        Some(session) if is_idle(&session, ttl) => {
+            documents.remove(id)?;
+            Ok(None)
+        }
+        session => Ok(session),
+    }
+}
+
+#[async_trait]
+impl SessionStore for InFileSessionStore {
+    #[instrument(skip(self), level = "debug")]
+    async fn get(&self, id: Uuid) -> Result<Session, ChainError> {
+        debug!(session_id = %id, "Getting session from file");
+
+        let ttl = self.session_ttl;
+        self.documents
+            .run(move |documents| read_live(documents, id, ttl))
+            .await?
+            .ok_or_else(|| ChainError::NotFound(format!("Session with id {id} not found")))
+    }
+
+    #[instrument(skip(self, session), level = "debug")]
+    async fn create(&self, session: Session) -> Result<(), ChainError> {
+        debug!(session_id = %session.id, "Creating session in file");
+
+        let ttl = self.session_ttl;
+        self.documents
+            .run(move |documents| {
+                if read_live(documents, session.id, ttl)?.is_some() {
+                    return Err(ChainError::AlreadyExists(format!(
+                        "Session with id {} already exists",
+                        session.id
+                    )));
+                }
+                documents.write(session.id, &session)
+            })
+            .await
+    }
+
+    #[instrument(skip(self, session), level = "debug")]
+    async fn save(&self, session: Session) -> Result<(), ChainError> {
+        debug!(session_id = %session.id, "Saving session to file");
+
+        self.documents
+            .run(move |documents| documents.write(session.id, &session))
+            .await
+    }
+
+    #[instrument(skip(self, session), level = "debug")]
+    async fn save_cas(&self, session: Session, expected_version: u64) -> Result<(), ChainError> {
+        let id = session.id;
+        debug!(session_id = %id, expected_version, "CAS-saving session to file");
+
+        // The read and the write run under the directory's lock, so two
+        // concurrent callers cannot both observe `expected_version`.
+        let ttl = self.session_ttl;
+        self.documents
+            .run(move |documents| match read_live(documents, id, ttl)? {
+                None => Err(ChainError::NotFound(format!(
+                    "Session with id {id} not found"
+                ))),
+                Some(existing) if existing.version != expected_version => {
+                    Err(ChainError::Conflict(format!(
+                        "Session {id} was modified concurrently (expected version {expected_version}, found {})",
+                        existing.version
+                    )))
+                }
+                Some(_) => documents.write(id, &session),
+            })
+            .await
+            .inspect_err(|e| debug!(session_id = %id, error = %e, "CAS save rejected"))
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn delete(&self, id: Uuid) -> Result<bool, ChainError> {
+        debug!(session_id = %id, "Deleting session from file");
+
+        let ttl = self.session_ttl;
+        self.documents
+            .run(move |documents| {
+                // An expired session is removed by the read and does not count,
+                // matching a Redis key that had already expired.
+                if read_live(documents, id, ttl)?.is_none() {
+                    return Ok(false);
+                }
+                documents.remove(id)
+            })
+            .await
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn cleanup(&self) -> Result<usize, ChainError> {
+        debug!("Cleaning up expired sessions from file");
+
+        let ttl = self.session_ttl;
+        let removed = self
+            .documents
+            .run(move |documents| {
+                let mut removed = 0;
+                for id in documents.ids()? {
+                    match documents.read::<Session>(id) {
+                        Ok(Some(session)) if is_idle(&session, ttl) => {
+                            if documents.remove(id)? {
+                                removed += 1;
+                            }
+                        }
+                        Ok(_) => {}
+                        // One unreadable file must not stop the pass; it stays
+                        // for an operator to inspect.
+                        Err(e) => {
+                            error!(session_id = %id, error = %e, "Skipping unreadable session")
+                        }
+                    }
+                }
+                Ok(removed)
+            })
+            .await?;
+
+        if removed > 0 {
+            info!(count = removed, "Removed expired sessions");
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionState, SimulationMethod, SimulationParameters};
+    use optionstratlib::utils::TimeFrame;
+    use positive::{Positive, pos_or_panic};
+    use rust_decimal::Decimal;
+
+    fn create_test_session() -> Session {
+        let params = SimulationParameters {
+            symbol: "TEST".to_string(),
+            steps: 10,
+            initial_price: pos_or_panic!(100.0),
+            days_to_expiration: pos_or_panic!(30.0),
+            volatility: pos_or_panic!(0.2),
+            risk_free_rate: Decimal::new(0, 0),
+            dividend_yield: Positive::ZERO,
+            method: SimulationMethod::GeometricBrownian {
+                dt: pos_or_panic!(1.0),
+                drift: Decimal::new(0, 0),
+                volatility: pos_or_panic!(0.2),
+            },
+            time_frame: TimeFrame::Day,
+            chain_size: Some(5),
+            strike_interval: Some(pos_or_panic!(5.0)),
+            skew_slope: None,
+            smile_curve: None,
+            spread: None,
+            seed: None,
+        };
+
+        Session {
+            id: Uuid::new_v4(),
+            created_at: SystemTime::now(),
+            updated_at: SystemTime::now(),
+            current_step: 0,
+            total_steps: 10,
+            parameters: params,
+            state: SessionState::Initialized,
+            version: 0,
+        }
+    }
+
+    fn temp_root() -> tempfile::TempDir {
+        match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        }
+    }
+
+    fn open(root: &Path, ttl: Option<u64>) -> InFileSessionStore {
+        match InFileSessionStore::open(root, ttl) {
+            Ok(store) => store,
+            Err(error) => panic!("the store must open: {error}"),
+        }
+    }
+
+    /// A session survives the store being closed and reopened — the point of
+    /// the backend — and a second create of its id is refused.
+    #[tokio::test]
+    async fn test_a_session_survives_a_restart() {
+        let root = temp_root();
+        let session = create_test_session();
+        let store = open(root.path(), None);
+        if let Err(error) = store.create(session.clone()).await {
+            panic!("the create must succeed: {error}");
+        }
+        drop(store);
+
+        let store = open(root.path(), None);
+        match store.get(session.id).await {
+            Ok(loaded) => {
+                assert_eq!(loaded.id, session.id);
+                assert_eq!(loaded.version, session.version);
+                assert_eq!(loaded.current_step, session.current_step);
+            }
+            Err(error) => panic!("the session must survive the restart: {error}"),
+        }
+        assert!(matches!(
+            store.create(session).await,
+            Err(ChainError::AlreadyExists(_))
+        ));
+    }
+
+    /// The compare-and-swap commits at the expected revision, rejects a stale
+    /// one without writing, and reports a missing session as such.
+    #[tokio::test]
+    async fn test_save_cas_honours_the_revision() {
+        let root = temp_root();
+        let store = open(root.path(), None);
+        let session = create_test_session();
+        if let Err(error) = store.create(session.clone()).await {
+            panic!("the create must succeed: {error}");
+        }
+
+        let mut advanced = session.clone();
+        advanced.current_step = 1;
+        advanced.version = 1;
+        if let Err(error) = store.save_cas(advanced.clone(), 0).await {
+            panic!("the matching revision must commit: {error}");
+        }
+        let mut stale = session.clone();
+        stale.version = 1;
+        assert!(matches!(
+            store.save_cas(stale, 0).await,
+            Err(ChainError::Conflict(_))
+        ));
+        match store.get(session.id).await {
+            Ok(loaded) => assert_eq!(loaded.current_step, 1),
+            Err(error) => panic!("the committed session must load: {error}"),
+        }
+
+        assert!(matches!(
+            store.save_cas(create_test_session(), 0).await,
+            Err(ChainError::NotFound(_))
+        ));
+    }
+
+    /// An idle session reads as absent and cleanup counts what it removed;
+    /// a fresh one is left alone.
+    #[tokio::test]
+    async fn test_idle_sessions_expire() {
+        let root = temp_root();
+        let store = open(root.path(), Some(60));
+        let mut idle = create_test_session();
+        idle.updated_at = SystemTime::now() - Duration::from_secs(120);
+        let fresh = create_test_session();
+        for session in [idle.clone(), fresh.clone()] {
+            if let Err(error) = store.save(session).await {
+                panic!("the save must succeed: {error}");
+            }
+        }
+
+        match store.cleanup().await {
+            Ok(removed) => assert_eq!(removed, 1),
+            Err(error) => panic!("the cleanup must succeed: {error}"),
+        }
+        assert!(matches!(
+            store.get(idle.id).await,
+            Err(ChainError::NotFound(_))
+        ));
+        assert!(store.get(fresh.id).await.is_ok());
+        match store.delete(fresh.id).await {
+            Ok(deleted) => assert!(deleted),
+            Err(error) => panic!("the delete must succeed: {error}"),
+        }
+    }
+}
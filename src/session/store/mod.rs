@@ -27,6 +27,13 @@ mod in_memory;
 ///
 mod in_redis;
 
+/// File-backed implementation of the v1 session store, for single-node
+/// deployments.
+mod in_file;
+
+/// Crash-safe document files shared by the file-backed stores.
+mod file_io;
+
 /// The `mod interface` statement declares a module named `interface`.
 ///
 /// A trait that defines the behavior of a session store backend.
@@ -50,9 +57,33 @@ mod v2_memory;
 /// Redis implementation of the v2 simulation store.
 mod v2_redis;
 
+/// MongoDB implementation of the v2 simulation store.
+mod v2_mongo;
+
+/// File-backed implementation of the v2 simulation store.
+mod v2_file;
+
+/// The persistence contract for `Idempotency-Key` records: the first response
+/// to a key, replayed to its retries.
+mod idempotency_interface;
+
+/// In-memory implementation of the idempotency store.
+mod idempotency_memory;
+
+/// Redis implementation of the idempotency store.
+mod idempotency_redis;
+
+pub use idempotency_interface::{
+    IN_FLIGHT_LEASE, IdempotencyClaim, IdempotencyRecord, IdempotencyStore, StoredResponse,
+};
+pub use idempotency_memory::InMemoryIdempotencyStore;
+pub use idempotency_redis::{DEFAULT_IDEMPOTENCY_KEY_PREFIX, InRedisIdempotencyStore};
+pub use in_file::{DEFAULT_SESSIONS_DIR, InFileSessionStore};
 pub use in_memory::InMemorySessionStore;
 pub use in_redis::InRedisSessionStore;
 pub use interface::SessionStore;
-pub use v2_interface::SimulationStore;
-pub use v2_memory::{DEFAULT_V2_RETENTION_SECS, InMemorySimulationStore};
+pub use v2_file::{DEFAULT_V2_DIR, InFileSimulationStore};
+pub use v2_interface::{SimulationFilter, SimulationPage, SimulationStore};
+pub use v2_memory::{DEFAULT_V2_MAX_PINNED, DEFAULT_V2_RETENTION_SECS, InMemorySimulationStore};
+pub use v2_mongo::{DEFAULT_V2_COLLECTION, MongoSimulationStore};
 pub use v2_redis::{DEFAULT_V2_KEY_PREFIX, InRedisSimulationStore};
@@ -0,0 +1,447 @@
+//! File-backed [`SimulationStore`] for single-node deployments.
+//!
+//! One JSON file per simulation under `<root>/simulations_v2`, a directory of
+//! its own for the same reason the in-memory store keeps a separate map: a v2
+//! id cannot resolve a v1 session when the two never share a container (ADR
+//! 0001 §12.2). Files are written crash-safely (see `file_io`), and the JSON
+//! is the form the Redis store writes, read back through the same validating
+//! deserialization.
+//!
+//! Expiry follows the in-memory store: a simulation idle past its retention —
+//! its own, or the store's — reads as absent, a pinned one never expires, and
+//! [`SimulationStore::cleanup`] removes what expired and reports the ids.
+//! Listing reads every file, which is the right trade for a laptop or a CI
+//! runner and the wrong one for a fleet; that is what the other backends are
+//! for.
+
+use crate::session::model_v2::SessionV2;
+use crate::session::store::file_io::{DocumentDir, Documents};
+use crate::session::store::v2_interface::{SimulationFilter, SimulationPage, SimulationStore};
+use crate::utils::error::ChainError;
+use async_trait::async_trait;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+
+/// The directory under the store root that holds v2 simulations.
+pub const DEFAULT_V2_DIR: &str = "simulations_v2";
+
+/// File-backed store for v2 rolling simulations.
+pub struct InFileSimulationStore {
+    documents: DocumentDir,
+    idle_retention: Duration,
+    max_pinned: usize,
+}
+
+impl InFileSimulationStore {
+    /// Opens the store under `root`, creating its directory if needed.
+    ///
+    /// `retention_secs` defaults to
+    /// [`super::v2_memory::DEFAULT_V2_RETENTION_SECS`], the window the other
+    /// backends apply.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when the directory cannot be prepared,
+    /// and [`ChainError::Conflict`] when another process holds it.
+    #[instrument(skip(root), level = "debug")]
+    pub fn open(root: impl AsRef<Path>, retention_secs: Option<u64>) -> Result<Self, ChainError> {
+        let retention = retention_secs.unwrap_or(super::v2_memory::DEFAULT_V2_RETENTION_SECS);
+        let documents = DocumentDir::open(root.as_ref(), DEFAULT_V2_DIR)?;
+
+        info!(
+            root = %root.as_ref().display(),
+            retention_secs = retention,
+            "Created new file simulation store"
+        );
+
+        Ok(Self {
+            documents,
+            idle_retention: Duration::from_secs(retention),
+            max_pinned: super::v2_memory::DEFAULT_V2_MAX_PINNED,
+        })
+    }
+
+    /// Sets how many simulations may be pinned at once.
+    #[must_use]
+    pub fn with_max_pinned(mut self, max_pinned: usize) -> Self {
+        self.max_pinned = max_pinned;
+        self
+    }
+
+    /// The idle retention window this store applies.
+    #[must_use]
+    pub fn idle_retention(&self) -> Duration {
+        self.idle_retention
+    }
+}
+
+/// Whether a simulation has outlived its retention.
+///
+/// A simulation stamped in the future is kept, as in the in-memory store.
+fn is_expired(simulation: &SessionV2, idle_retention: Duration) -> bool {
+    !simulation.pinned
+        && SystemTime::now()
+            .duration_since(simulation.updated_at)
+            .is_ok_and(|idle| idle > simulation.retention_or(idle_retention))
+}
+
+/// Reads a simulation, treating an expired one as absent.
+///
+/// Unlike the v1 store this does not remove it: the manager learns which
+/// simulations went away only from `cleanup`, and evicts their caches then.
+fn read_live(
+    documents: &Documents,
+    id: Uuid,
+    idle_retention: Duration,
+) -> Result<Option<SessionV2>, ChainError> {
+    Ok(documents
+        .read::<SessionV2>(id)?
+        .filter(|simulation| !is_expired(simulation, idle_retention)))
+}
+
+/// Every live simulation, skipping files that do not read.
+fn read_all(documents: &Documents, idle_retention: Duration) -> Result<Vec<SessionV2>, ChainError> {
+    let mut simulations = Vec::new();
+    for id in documents.ids()? {
+        match read_live(documents, id, idle_retention) {
+            Ok(Some(simulation)) => simulations.push(simulation),
+            Ok(None) => {}
+            // A corrupt file would fail `get` just the same; it is not worth
+            // failing the whole scan over.
+            Err(e) => error!(simulation_id = %id, error = %e, "Skipping unreadable simulation"),
+        }
+    }
+    Ok(simulations)
+}
+
+#[async_trait]
+impl SimulationStore for InFileSimulationStore {
+    #[instrument(skip(self), level = "debug")]
+    async fn get(&self, id: Uuid) -> Result<SessionV2, ChainError> {
+        debug!(simulation_id = %id, "Getting simulation from file");
+
+        let retention = self.idle_retention;
+        self.documents
+            .run(move |documents| read_live(documents, id, retention))
+            .await?
+            .ok_or_else(|| ChainError::NotFound(format!("Simulation with id {id} not found")))
+    }
+
+    #[instrument(skip(self, simulation), level = "debug")]
+    async fn create(&self, simulation: SessionV2) -> Result<(), ChainError> {
+        debug!(simulation_id = %simulation.id, "Creating simulation in file");
+
+        simulation.validate()?;
+
+        let retention = self.idle_retention;
+        let max_pinned = self.max_pinned;
+        self.documents
+            .run(move |documents| {
+                // An expired file under the same id is debris cleanup has not
+                // reached yet, and is simply overwritten.
+                if read_live(documents, simulation.id, retention)?.is_some() {
+                    return Err(ChainError::AlreadyExists(format!(
+                        "Simulation with id {} already exists",
+                        simulation.id
+                    )));
+                }
+                // Counted under the same lock as the write, so two concurrent
+                // pinned creations cannot both take the last slot.
+                if simulation.pinned {
+                    let pinned = read_all(documents, retention)?
+                        .iter()
+                        .filter(|existing| existing.pinned)
+                        .count();
+                    if pinned >= max_pinned {
+                        return Err(super::v2_memory::pinned_quota_reached(max_pinned));
+                    }
+                }
+                documents.write(simulation.id, &simulation)
+            })
+            .await
+    }
+
+    #[instrument(skip(self, simulation), level = "debug")]
+    async fn save_cas(
+        &self,
+        simulation: SessionV2,
+        expected_version: u64,
+    ) -> Result<(), ChainError> {
+        let id = simulation.id;
+        debug!(simulation_id = %id, expected_version, "CAS-saving simulation to file");
+
+        simulation.validate()?;
+
+        // The read of the stored revision and the conditional write run under
+        // the directory's lock, so they are one atomic step.
+        let retention = self.idle_retention;
+        self.documents
+            .run(move |documents| match read_live(documents, id, retention)? {
+                None => Err(ChainError::NotFound(format!(
+                    "Simulation with id {id} not found"
+                ))),
+                Some(existing) if existing.version != expected_version => {
+                    Err(ChainError::Conflict(format!(
+                        "Simulation {id} was modified concurrently (expected version {expected_version}, found {})",
+                        existing.version
+                    )))
+                }
+                Some(_) => documents.write(id, &simulation),
+            })
+            .await
+            .inspect_err(|e| debug!(simulation_id = %id, error = %e, "CAS save rejected"))
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn delete(&self, id: Uuid) -> Result<bool, ChainError> {
+        debug!(simulation_id = %id, "Deleting simulation from file");
+
+        let retention = self.idle_retention;
+        self.documents
+            .run(move |documents| {
+                // The file goes either way; only a live simulation counts as
+                // removed, matching a Redis key that had already expired.
+                let live = read_live(documents, id, retention)?.is_some();
+                Ok(documents.remove(id)? && live)
+            })
+            .await
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn delete_cas(&self, id: Uuid, expected_version: u64) -> Result<bool, ChainError> {
+        debug!(simulation_id = %id, expected_version, "CAS-deleting simulation from file");
+
+        let retention = self.idle_retention;
+        self.documents
+            .run(move |documents| match read_live(documents, id, retention)? {
+                None => Ok(false),
+                Some(existing) if existing.version != expected_version => {
+                    Err(ChainError::Conflict(format!(
+                        "Simulation {id} was modified concurrently (expected version {expected_version}, found {})",
+                        existing.version
+                    )))
+                }
+                Some(_) => documents.remove(id),
+            })
+            .await
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn list(
+        &self,
+        filter: &SimulationFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SimulationPage, ChainError> {
+        let retention = self.idle_retention;
+        let mut matching: Vec<SessionV2> = self
+            .documents
+            .run(move |documents| read_all(documents, retention))
+            .await?
+            .into_iter()
+            .filter(|simulation| filter.matches(simulation))
+            .collect();
+        matching.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+        let total = matching.len();
+        let simulations: Vec<SessionV2> = matching.into_iter().skip(offset).take(limit).collect();
+        let end = offset.saturating_add(simulations.len());
+        Ok(SimulationPage {
+            simulations,
+            next_offset: (end < total && limit > 0).then_some(end),
+        })
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn cleanup(&self) -> Result<Vec<Uuid>, ChainError> {
+        let retention = self.idle_retention;
+        let expired = self
+            .documents
+            .run(move |documents| {
+                let mut expired = Vec::new();
+                for id in documents.ids()? {
+                    match documents.read::<SessionV2>(id) {
+                        Ok(Some(simulation)) if is_expired(&simulation, retention) => {
+                            if documents.remove(id)? {
+                                expired.push(id);
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            error!(simulation_id = %id, error = %e, "Skipping unreadable simulation");
+                        }
+                    }
+                }
+                Ok(expired)
+            })
+            .await?;
+
+        if !expired.is_empty() {
+            info!(count = expired.len(), "Reaped expired simulations");
+        }
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::models::{ApiTimeFrame, ApiWalkType};
+    use crate::api::rest::requests_v2::CreateSimulationRequest;
+    use crate::domain::expiry::{ExpiryRule, ExpiryRuleKind};
+    use crate::session::model_v2::SimulationParametersV2;
+
+    fn simulation(seed: u64) -> SessionV2 {
+        let rule = match ExpiryRule::new("zero_dte", ExpiryRuleKind::Daily, 1) {
+            Ok(rule) => rule,
+            Err(error) => panic!("test rule must be valid: {error}"),
+        };
+        let request = CreateSimulationRequest {
+            symbol: "SPX".to_string(),
+            steps: 10,
+            start_at: None,
+            step_interval_seconds: Some(86_400),
+            timezone: "America/New_York".to_string(),
+            calendar: None,
+            expiration_time: "17:00".to_string(),
+            schedules: vec![rule],
+            initial_price: 5000.0,
+            volatility: 0.18,
+            risk_free_rate: 0.04,
+            dividend_yield: 0.0,
+            method: ApiWalkType::Brownian {
+                dt: 0.004,
+                drift: 0.0,
+                volatility: 0.18,
+            },
+            time_frame: ApiTimeFrame::Day,
+            chain_size: Some(15),
+            strike_interval: Some(25.0),
+            skew_slope: None,
+            smile_curve: None,
+            spread: Some(0.02),
+            seed: Some(seed),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
+        };
+        match SimulationParametersV2::try_from(request) {
+            Ok(parameters) => SessionV2::new(parameters),
+            Err(error) => panic!("the request must convert: {error}"),
+        }
+    }
+
+    fn temp_root() -> tempfile::TempDir {
+        match tempfile::tempdir() {
+            Ok(dir) => dir,
+            Err(error) => panic!("a temporary directory must be available: {error}"),
+        }
+    }
+
+    fn open(root: &Path, retention_secs: Option<u64>) -> InFileSimulationStore {
+        match InFileSimulationStore::open(root, retention_secs) {
+            Ok(store) => store,
+            Err(error) => panic!("the store must open: {error}"),
+        }
+    }
+
+    /// A simulation survives a restart unchanged — including a seed above
+    /// `i64::MAX` — and a second create of its id is refused.
+    #[tokio::test]
+    async fn test_a_simulation_survives_a_restart() {
+        let root = temp_root();
+        let original = simulation(u64::MAX);
+        let store = open(root.path(), None);
+        if let Err(error) = store.create(original.clone()).await {
+            panic!("the create must succeed: {error}");
+        }
+        drop(store);
+
+        let store = open(root.path(), None);
+        match store.get(original.id).await {
+            Ok(loaded) => assert_eq!(loaded, original),
+            Err(error) => panic!("the simulation must survive the restart: {error}"),
+        }
+        assert!(matches!(
+            store.create(original).await,
+            Err(ChainError::AlreadyExists(_))
+        ));
+    }
+
+    /// The compare-and-swap and the conditional delete both honour the
+    /// revision.
+    #[tokio::test]
+    async fn test_save_cas_and_delete_cas_honour_the_revision() {
+        let root = temp_root();
+        let store = open(root.path(), None);
+        let original = simulation(2);
+        if let Err(error) = store.create(original.clone()).await {
+            panic!("the create must succeed: {error}");
+        }
+
+        let mut advanced = original.clone();
+        advanced.current_step = 1;
+        advanced.state = crate::session::SessionState::InProgress;
+        advanced.version = 1;
+        if let Err(error) = store.save_cas(advanced.clone(), 0).await {
+            panic!("the matching revision must commit: {error}");
+        }
+        assert!(matches!(
+            store.save_cas(advanced.clone(), 0).await,
+            Err(ChainError::Conflict(_))
+        ));
+        assert!(matches!(
+            store.delete_cas(original.id, 0).await,
+            Err(ChainError::Conflict(_))
+        ));
+        match store.delete_cas(original.id, 1).await {
+            Ok(deleted) => assert!(deleted),
+            Err(error) => panic!("the matching revision must delete: {error}"),
+        }
+        assert!(matches!(
+            store.save_cas(advanced, 1).await,
+            Err(ChainError::NotFound(_))
+        ));
+    }
+
+    /// Cleanup reports what expired and spares what is live or pinned, and
+    /// the pinned quota is enforced.
+    #[tokio::test]
+    async fn test_cleanup_and_the_pinned_quota() {
+        let root = temp_root();
+        let store = open(root.path(), Some(60)).with_max_pinned(1);
+        let mut idle = simulation(3);
+        idle.updated_at = SystemTime::now() - Duration::from_secs(120);
+        let mut pinned = simulation(4);
+        pinned.pinned = true;
+        pinned.updated_at = idle.updated_at;
+        let fresh = simulation(5);
+        for simulation in [idle.clone(), pinned.clone(), fresh.clone()] {
+            if let Err(error) = store.create(simulation).await {
+                panic!("the create must succeed: {error}");
+            }
+        }
+
+        let mut second_pin = simulation(6);
+        second_pin.pinned = true;
+        assert!(matches!(
+            store.create(second_pin).await,
+            Err(ChainError::Conflict(_))
+        ));
+
+        assert!(matches!(
+            store.get(idle.id).await,
+            Err(ChainError::NotFound(_))
+        ));
+        match store.cleanup().await {
+            Ok(expired) => assert_eq!(expired, vec![idle.id]),
+            Err(error) => panic!("the cleanup must succeed: {error}"),
+        }
+        match store.list(&SimulationFilter::default(), 0, 10).await {
+            Ok(page) => assert_eq!(page.simulations.len(), 2),
+            Err(error) => panic!("the listing must succeed: {error}"),
+        }
+    }
+}
@@ -7,11 +7,62 @@
 //! document is never reinterpreted as rolling configuration. The v1 trait,
 //! including its `cleanup() -> usize` signature, is untouched.
 
+use crate::session::model::SessionState;
 use crate::session::model_v2::SessionV2;
 use crate::utils::error::ChainError;
 use async_trait::async_trait;
+use std::time::SystemTime;
 use uuid::Uuid;
 
+/// The conditions a listed simulation must meet. Every field narrows; the
+/// default filter matches everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulationFilter {
+    /// Exact, case-sensitive underlying symbol.
+    pub symbol: Option<String>,
+    /// Lifecycle state.
+    pub state: Option<SessionState>,
+    /// Inclusive lower bound on `created_at`.
+    pub created_after: Option<SystemTime>,
+    /// Exclusive upper bound on `created_at`.
+    pub created_before: Option<SystemTime>,
+    /// Tags the simulation must **all** carry.
+    pub tags: Vec<String>,
+}
+
+impl SimulationFilter {
+    /// Whether `simulation` meets every condition.
+    ///
+    /// The single definition of a match, so the backends that can only narrow
+    /// part of the filter in storage apply the rest identically.
+    #[must_use]
+    pub fn matches(&self, simulation: &SessionV2) -> bool {
+        self.symbol
+            .as_ref()
+            .is_none_or(|symbol| *symbol == simulation.parameters.symbol)
+            && self.state.is_none_or(|state| state == simulation.state)
+            && self
+                .created_after
+                .is_none_or(|after| simulation.created_at >= after)
+            && self
+                .created_before
+                .is_none_or(|before| simulation.created_at < before)
+            && self
+                .tags
+                .iter()
+                .all(|tag| simulation.tags.binary_search(tag).is_ok())
+    }
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationPage {
+    /// The matching simulations, oldest first.
+    pub simulations: Vec<SessionV2>,
+    /// The offset of the next page, or `None` when this page is the last.
+    pub next_offset: Option<usize>,
+}
+
 /// A storage backend for v2 rolling simulations.
 ///
 /// Implementations must be thread-safe and shareable (`Send + Sync`).
@@ -75,6 +126,39 @@ pub trait SimulationStore: Send + Sync {
     /// `Ok(false)`, not an error.
     async fn delete(&self, id: Uuid) -> Result<bool, ChainError>;
 
+    /// Atomically deletes a simulation only if the stored revision still
+    /// equals `expected_version`.
+    ///
+    /// The conditional form of [`delete`](Self::delete), for a caller that
+    /// decided to delete on the strength of what it read: a simulation advanced
+    /// in between is not the one it decided about.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Conflict`] when the stored revision differs (the
+    /// store is left untouched), or another [`ChainError`] on a backend
+    /// failure; a missing id is `Ok(false)`, as for `delete`.
+    async fn delete_cas(&self, id: Uuid, expected_version: u64) -> Result<bool, ChainError>;
+
+    /// Lists the simulations matching `filter`, ordered by `created_at` then
+    /// id, skipping the first `offset` matches and returning at most `limit`.
+    ///
+    /// Offset pagination over a stable order: a simulation created while a
+    /// client pages is appended at the end rather than shifting the pages it
+    /// has already read, though one removed by `delete` or `cleanup` shifts
+    /// later pages back by one.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChainError`] when the listing fails. A document that cannot
+    /// be read is skipped rather than failing the whole listing.
+    async fn list(
+        &self,
+        filter: &SimulationFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SimulationPage, ChainError>;
+
     /// Removes simulations that have been idle past their retention window and
     /// returns **their ids**.
     ///
@@ -6,7 +6,7 @@
 //! because the two never share a container.
 
 use crate::session::model_v2::SessionV2;
-use crate::session::store::v2_interface::SimulationStore;
+use crate::session::store::v2_interface::{SimulationFilter, SimulationPage, SimulationStore};
 use crate::utils::error::ChainError;
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -29,10 +29,15 @@ use uuid::Uuid;
 /// same way.
 pub use crate::infrastructure::DEFAULT_RETENTION_SECS as DEFAULT_V2_RETENTION_SECS;
 
+/// Default pinned quota for a v2 store, shared by both backends and by the
+/// configuration for the same reason as [`DEFAULT_V2_RETENTION_SECS`].
+pub use crate::infrastructure::DEFAULT_MAX_PINNED as DEFAULT_V2_MAX_PINNED;
+
 /// In-memory store for v2 rolling simulations.
 pub struct InMemorySimulationStore {
     simulations: Arc<Mutex<HashMap<Uuid, SessionV2>>>,
     idle_retention: Duration,
+    max_pinned: usize,
 }
 
 impl Default for InMemorySimulationStore {
@@ -54,9 +59,17 @@ impl InMemorySimulationStore {
         Self {
             simulations: Arc::new(Mutex::new(HashMap::new())),
             idle_retention,
+            max_pinned: DEFAULT_V2_MAX_PINNED,
         }
     }
 
+    /// Sets how many simulations may be pinned at once.
+    #[must_use]
+    pub fn with_max_pinned(mut self, max_pinned: usize) -> Self {
+        self.max_pinned = max_pinned;
+        self
+    }
+
     /// The idle retention window this store applies.
     #[must_use]
     pub fn idle_retention(&self) -> Duration {
@@ -97,6 +110,17 @@ impl SimulationStore for InMemorySimulationStore {
                 simulation.id
             )));
         }
+        // Counted under the same lock as the insert, so two concurrent pinned
+        // creations cannot both take the last slot.
+        if simulation.pinned {
+            let pinned = simulations
+                .values()
+                .filter(|existing| existing.pinned)
+                .count();
+            if pinned >= self.max_pinned {
+                return Err(pinned_quota_reached(self.max_pinned));
+            }
+        }
 
         simulations.insert(simulation.id, simulation);
         Ok(())
@@ -138,6 +162,46 @@ impl SimulationStore for InMemorySimulationStore {
         Ok(simulations.remove(&id).is_some())
     }
 
+    async fn delete_cas(&self, id: Uuid, expected_version: u64) -> Result<bool, ChainError> {
+        let mut simulations = self.lock()?;
+
+        match simulations.get(&id) {
+            None => Ok(false),
+            Some(existing) if existing.version != expected_version => {
+                Err(ChainError::Conflict(format!(
+                    "Simulation {id} was modified concurrently (expected version {expected_version}, found {})",
+                    existing.version
+                )))
+            }
+            Some(_) => Ok(simulations.remove(&id).is_some()),
+        }
+    }
+
+    async fn list(
+        &self,
+        filter: &SimulationFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SimulationPage, ChainError> {
+        let mut matching: Vec<SessionV2> = {
+            let simulations = self.lock()?;
+            simulations
+                .values()
+                .filter(|simulation| filter.matches(simulation))
+                .cloned()
+                .collect()
+        };
+        matching.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+        let total = matching.len();
+        let simulations: Vec<SessionV2> = matching.into_iter().skip(offset).take(limit).collect();
+        let end = offset.saturating_add(simulations.len());
+        Ok(SimulationPage {
+            simulations,
+            next_offset: (end < total && limit > 0).then_some(end),
+        })
+    }
+
     async fn cleanup(&self) -> Result<Vec<Uuid>, ChainError> {
         let now = SystemTime::now();
         let mut simulations = self.lock()?;
@@ -146,9 +210,10 @@ impl SimulationStore for InMemorySimulationStore {
         // skewed clock — is kept rather than expired, matching v1's behaviour.
         let expired: Vec<Uuid> = simulations
             .iter()
+            .filter(|(_, simulation)| !simulation.pinned)
             .filter_map(
                 |(id, simulation)| match now.duration_since(simulation.updated_at) {
-                    Ok(idle) if idle > self.idle_retention => Some(*id),
+                    Ok(idle) if idle > simulation.retention_or(self.idle_retention) => Some(*id),
                     _ => None,
                 },
             )
@@ -162,6 +227,15 @@ impl SimulationStore for InMemorySimulationStore {
     }
 }
 
+/// The error for a pinned creation beyond the quota. Shared with the Redis
+/// store so both backends refuse in the same words.
+#[cold]
+pub(super) fn pinned_quota_reached(max_pinned: usize) -> ChainError {
+    ChainError::Conflict(format!(
+        "The pinned quota of {max_pinned} simulations is reached; delete a pinned simulation first"
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,6 +289,9 @@ mod tests {
             smile_curve: None,
             spread: Some(0.02),
             seed: Some(42),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         }
     }
 
@@ -413,6 +490,35 @@ mod tests {
         }
     }
 
+    /// A conditional delete removes the revision it names and nothing else: a
+    /// stale revision is a conflict that leaves the simulation in place.
+    #[tokio::test]
+    async fn test_delete_cas_honours_the_revision() {
+        let store = InMemorySimulationStore::new();
+        let sim = simulation();
+        match store.create(sim.clone()).await {
+            Ok(()) => {}
+            Err(error) => panic!("must create: {error}"),
+        }
+
+        match store.delete_cas(sim.id, sim.version + 1).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a stale revision must conflict, got {other:?}"),
+        }
+        if let Err(error) = store.get(sim.id).await {
+            panic!("a refused delete must leave the simulation: {error}");
+        }
+
+        match store.delete_cas(sim.id, sim.version).await {
+            Ok(removed) => assert!(removed),
+            Err(error) => panic!("must delete: {error}"),
+        }
+        match store.delete_cas(sim.id, sim.version).await {
+            Ok(removed) => assert!(!removed),
+            Err(error) => panic!("a second delete must not error: {error}"),
+        }
+    }
+
     /// Cleanup returns the ids it expired, which is what lets the caller evict
     /// the matching domain caches.
     #[tokio::test]
@@ -478,6 +584,172 @@ mod tests {
         }
     }
 
+    /// Listing orders by creation time, pages with a next offset, and stops
+    /// offering one on the last page.
+    #[tokio::test]
+    async fn test_list_pages_in_creation_order() {
+        let store = InMemorySimulationStore::new();
+        let base = SystemTime::now();
+        let mut created = Vec::new();
+        for minutes in [2u64, 0, 1] {
+            let mut sim = simulation();
+            sim.created_at = base + Duration::from_secs(minutes * 60);
+            match store.create(sim.clone()).await {
+                Ok(()) => {}
+                Err(error) => panic!("must create: {error}"),
+            }
+            created.push(sim);
+        }
+
+        let first = match store.list(&SimulationFilter::default(), 0, 2).await {
+            Ok(page) => page,
+            Err(error) => panic!("must list: {error}"),
+        };
+        let ids: Vec<Uuid> = first.simulations.iter().map(|sim| sim.id).collect();
+        assert_eq!(ids, vec![created[1].id, created[2].id]);
+        assert_eq!(first.next_offset, Some(2));
+
+        let second = match store.list(&SimulationFilter::default(), 2, 2).await {
+            Ok(page) => page,
+            Err(error) => panic!("must list: {error}"),
+        };
+        let ids: Vec<Uuid> = second.simulations.iter().map(|sim| sim.id).collect();
+        assert_eq!(ids, vec![created[0].id]);
+        assert_eq!(second.next_offset, None);
+    }
+
+    /// Every filter narrows, and tags must all be present.
+    #[tokio::test]
+    async fn test_list_applies_every_filter() {
+        let store = InMemorySimulationStore::new();
+        let base = SystemTime::now();
+
+        let mut tagged = simulation();
+        tagged.tags = vec!["desk:a".to_string(), "nightly".to_string()];
+        tagged.created_at = base;
+        let mut advanced = simulation();
+        advanced.current_step = 1;
+        advanced.state = SessionState::InProgress;
+        advanced.tags = vec!["nightly".to_string()];
+        advanced.created_at = base + Duration::from_secs(60);
+        for sim in [&tagged, &advanced] {
+            match store.create(sim.clone()).await {
+                Ok(()) => {}
+                Err(error) => panic!("must create: {error}"),
+            }
+        }
+
+        let cases = [
+            (
+                SimulationFilter {
+                    tags: vec!["desk:a".to_string(), "nightly".to_string()],
+                    ..SimulationFilter::default()
+                },
+                vec![tagged.id],
+            ),
+            (
+                SimulationFilter {
+                    state: Some(SessionState::InProgress),
+                    ..SimulationFilter::default()
+                },
+                vec![advanced.id],
+            ),
+            (
+                SimulationFilter {
+                    created_after: Some(base + Duration::from_secs(1)),
+                    ..SimulationFilter::default()
+                },
+                vec![advanced.id],
+            ),
+            (
+                SimulationFilter {
+                    created_before: Some(base + Duration::from_secs(60)),
+                    ..SimulationFilter::default()
+                },
+                vec![tagged.id],
+            ),
+            (
+                SimulationFilter {
+                    symbol: Some("NDX".to_string()),
+                    ..SimulationFilter::default()
+                },
+                vec![],
+            ),
+        ];
+
+        for (filter, expected) in cases {
+            match store.list(&filter, 0, 10).await {
+                Ok(page) => {
+                    let ids: Vec<Uuid> = page.simulations.iter().map(|sim| sim.id).collect();
+                    assert_eq!(ids, expected, "filter {filter:?}");
+                }
+                Err(error) => panic!("must list: {error}"),
+            }
+        }
+    }
+
+    /// A simulation's own retention decides its expiry, and a pinned one is
+    /// never reaped however long it idles.
+    #[tokio::test]
+    async fn test_cleanup_honours_per_simulation_retention_and_pins() {
+        let store = InMemorySimulationStore::with_idle_retention(Duration::from_secs(60));
+        let idle = SystemTime::now() - Duration::from_secs(600);
+
+        let mut kept_longer = simulation();
+        kept_longer.retention_secs = Some(3_600);
+        kept_longer.updated_at = idle;
+        let mut kept_shorter = simulation();
+        kept_shorter.retention_secs = Some(1);
+        kept_shorter.updated_at = SystemTime::now() - Duration::from_secs(30);
+        let mut pinned = simulation();
+        pinned.pinned = true;
+        pinned.updated_at = idle;
+        for sim in [&kept_longer, &kept_shorter, &pinned] {
+            match store.create(sim.clone()).await {
+                Ok(()) => {}
+                Err(error) => panic!("must create: {error}"),
+            }
+        }
+
+        match store.cleanup().await {
+            Ok(expired) => assert_eq!(expired, vec![kept_shorter.id]),
+            Err(error) => panic!("must clean up: {error}"),
+        }
+    }
+
+    /// A pinned creation beyond the quota is a conflict; deleting a pinned
+    /// simulation frees its slot.
+    #[tokio::test]
+    async fn test_pinned_quota_is_enforced() {
+        let store = InMemorySimulationStore::new().with_max_pinned(1);
+        let mut first = simulation();
+        first.pinned = true;
+        let mut second = simulation();
+        second.pinned = true;
+
+        match store.create(first.clone()).await {
+            Ok(()) => {}
+            Err(error) => panic!("must create: {error}"),
+        }
+        match store.create(second.clone()).await {
+            Err(ChainError::Conflict(message)) => assert!(message.contains("quota")),
+            other => panic!("expected Conflict, got {other:?}"),
+        }
+        match store.create(simulation()).await {
+            Ok(()) => {}
+            Err(error) => panic!("an unpinned simulation is not counted: {error}"),
+        }
+
+        match store.delete(first.id).await {
+            Ok(removed) => assert!(removed),
+            Err(error) => panic!("must delete: {error}"),
+        }
+        match store.create(second).await {
+            Ok(()) => {}
+            Err(error) => panic!("the freed slot must be reusable: {error}"),
+        }
+    }
+
     /// The default retention is longer than v1's, because a v2 simulation is
     /// walked one request at a time over a long simulated horizon.
     #[tokio::test]
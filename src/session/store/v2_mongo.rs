@@ -0,0 +1,814 @@
+//! MongoDB-backed [`SimulationStore`] for v2 rolling simulations.
+//!
+//! Redis is a cache that happens to hold the simulations; a `FLUSHALL` or an
+//! eviction policy takes them with it. This store keeps them in MongoDB, the
+//! durable system of record the service already writes its events to, with
+//! the same contract: every write validates, `create` never overwrites,
+//! `save_cas` and `delete_cas` commit only at the expected revision, and
+//! `cleanup` reports the ids it reaped.
+//!
+//! # One document per simulation
+//!
+//! The simulation is stored as its JSON text in `document`, not as BSON
+//! fields. BSON has no unsigned 64-bit integer, and a seed is any `u64`: half
+//! of all seeds would not survive the conversion. The JSON is the exact form
+//! the Redis store writes, so it is read back through the same validating
+//! deserialization.
+//!
+//! Beside it sit the fields the store queries on: `version` for the
+//! compare-and-swap, `expires_at` for retention, `created_at` for the listing
+//! order, and `symbol` and `tags` to narrow a listing — all of them immutable
+//! after creation except the first two, which every write sets together with
+//! the document in one single-document update. MongoDB applies a
+//! single-document update atomically, which is what gives the revision check
+//! the guarantee the Redis store gets from a script.
+//!
+//! # Why no TTL index
+//!
+//! A TTL index would delete expired documents on MongoDB's own schedule,
+//! invisibly to this process, and [`SimulationStore::cleanup`] has to return
+//! the ids that went away so the manager can evict their caches. Expiry is
+//! therefore a deadline the store enforces itself: a document past its
+//! `expires_at` reads as absent everywhere, and `cleanup` removes it —
+//! conditionally, so an advance that refreshed the deadline in between wins.
+//! Deadlines are taken from the process clock; replicas sharing a collection
+//! need clocks in step to the second, which retention windows measured in
+//! minutes tolerate comfortably.
+//!
+//! # Pinned simulations
+//!
+//! A pinned simulation has no `expires_at`. The quota is checked after the
+//! insert: a pinned simulation that finds more than the quota of pinned
+//! simulations created at or before it removes itself again. Creation order is
+//! total, so of two concurrent creations racing for the last slot exactly the
+//! later one backs out — the guarantee the Redis script gives, without a
+//! transaction and so without requiring a replica set.
+
+use crate::infrastructure::MongoDBClient;
+use crate::session::model_v2::SessionV2;
+use crate::session::store::v2_interface::{SimulationFilter, SimulationPage, SimulationStore};
+use crate::utils::error::ChainError;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::bson::{self, Document, doc};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, IndexModel};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, error, info, instrument};
+use uuid::Uuid;
+
+/// Default collection for v2 simulations.
+///
+/// Kept apart from the steps and events collections: those are append-only
+/// history, while this one is rewritten in place on every advance.
+pub const DEFAULT_V2_COLLECTION: &str = "simulations_v2";
+
+/// MongoDB's duplicate-key error code.
+const DUPLICATE_KEY: i32 = 11_000;
+
+/// Maximum ids one `cleanup` pass reaps. A caller with more than this to reap
+/// reaps the rest on its next pass.
+const CLEANUP_BATCH: i64 = 1_000;
+
+/// Documents one `list` round trip reads.
+const LIST_BATCH: u32 = 500;
+
+/// A stored simulation: the document and the fields the store queries on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredSimulation {
+    /// The simulation's id, as text.
+    #[serde(rename = "_id")]
+    id: String,
+    /// The revision the compare-and-swap compares.
+    version: i64,
+    /// `created_at` in whole microseconds since the Unix epoch; the listing
+    /// order, with the id as the tie-break.
+    created_at: i64,
+    /// When the simulation expires; `None` when it is pinned.
+    expires_at: Option<bson::DateTime>,
+    /// Whether the simulation is exempt from expiry.
+    pinned: bool,
+    /// The underlying symbol, for narrowing a listing.
+    symbol: String,
+    /// The simulation's tags, for narrowing a listing.
+    tags: Vec<String>,
+    /// The simulation as JSON; see the module docs for why not BSON.
+    document: String,
+}
+
+/// `created_at` in whole microseconds since the Unix epoch.
+///
+/// The Redis store's creation score, so the two backends list in one order.
+fn created_micros(created_at: SystemTime) -> i64 {
+    created_at
+        .duration_since(UNIX_EPOCH)
+        .map(|since| i64::try_from(since.as_micros()).unwrap_or(i64::MAX))
+        .unwrap_or(0)
+}
+
+/// A revision as the `i64` BSON stores it.
+fn stored_version(version: u64) -> Result<i64, ChainError> {
+    i64::try_from(version).map_err(|_| {
+        ChainError::Internal(format!(
+            "revision {version} does not fit the MongoDB simulation store"
+        ))
+    })
+}
+
+/// The filter matching a document that has not expired at `now`.
+fn live(now: bson::DateTime) -> Document {
+    doc! { "$or": [ { "expires_at": null }, { "expires_at": { "$gt": now } } ] }
+}
+
+/// The filter matching one live simulation by id, plus `extra` conditions.
+fn live_id(id: Uuid, now: bson::DateTime, extra: Document) -> Document {
+    let mut filter = doc! { "_id": id.to_string() };
+    filter.extend(live(now));
+    filter.extend(extra);
+    filter
+}
+
+/// The part of `filter` the query can narrow on. The rest, and the exact
+/// bounds, are decided by [`SimulationFilter::matches`].
+fn list_query(filter: &SimulationFilter, now: bson::DateTime) -> Document {
+    let mut query = live(now);
+    let mut created = Document::new();
+    if let Some(after) = filter.created_after {
+        created.insert("$gte", created_micros(after));
+    }
+    if let Some(before) = filter.created_before {
+        created.insert("$lte", created_micros(before).saturating_add(1));
+    }
+    if !created.is_empty() {
+        query.insert("created_at", created);
+    }
+    if let Some(symbol) = &filter.symbol {
+        query.insert("symbol", symbol.clone());
+    }
+    if !filter.tags.is_empty() {
+        query.insert("tags", doc! { "$all": filter.tags.clone() });
+    }
+    query
+}
+
+/// Whether a driver error is a duplicate `_id`.
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write)) if write.code == DUPLICATE_KEY
+    )
+}
+
+/// MongoDB-backed store for v2 rolling simulations.
+pub struct MongoSimulationStore {
+    collection: Collection<StoredSimulation>,
+    retention_secs: u64,
+    max_pinned: usize,
+}
+
+impl MongoSimulationStore {
+    /// Creates a MongoDB-backed simulation store.
+    ///
+    /// `collection` defaults to [`DEFAULT_V2_COLLECTION`] and `retention_secs`
+    /// to [`super::v2_memory::DEFAULT_V2_RETENTION_SECS`], the window the
+    /// other backends apply. Call [`Self::ensure_indexes`] before serving.
+    #[instrument(skip(client), level = "debug")]
+    pub async fn new(
+        client: Arc<MongoDBClient>,
+        collection: Option<String>,
+        retention_secs: Option<u64>,
+    ) -> Self {
+        let name = collection.unwrap_or_else(|| DEFAULT_V2_COLLECTION.to_string());
+        let retention = retention_secs.unwrap_or(super::v2_memory::DEFAULT_V2_RETENTION_SECS);
+
+        info!(
+            collection = %name,
+            retention_secs = retention,
+            "Created new MongoDB simulation store"
+        );
+
+        Self {
+            collection: client.collection(&name).await,
+            retention_secs: retention,
+            max_pinned: super::v2_memory::DEFAULT_V2_MAX_PINNED,
+        }
+    }
+
+    /// Sets how many simulations may be pinned at once.
+    #[must_use]
+    pub fn with_max_pinned(mut self, max_pinned: usize) -> Self {
+        self.max_pinned = max_pinned;
+        self
+    }
+
+    /// The retention window applied to stored simulations, in seconds.
+    #[must_use]
+    pub fn retention_secs(&self) -> u64 {
+        self.retention_secs
+    }
+
+    /// Creates the indexes the store's queries rely on.
+    ///
+    /// Idempotent, and safe to call at every startup: the listing order, the
+    /// cleanup scan and the pinned count.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when MongoDB rejects an index or is
+    /// unreachable.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn ensure_indexes(&self) -> Result<(), ChainError> {
+        let indexes = [
+            doc! { "created_at": 1, "_id": 1 },
+            doc! { "expires_at": 1 },
+            doc! { "pinned": 1, "created_at": 1, "_id": 1 },
+        ]
+        .into_iter()
+        .map(|keys| {
+            IndexModel::builder()
+                .keys(keys)
+                .options(IndexOptions::builder().build())
+                .build()
+        });
+        self.collection
+            .create_indexes(indexes)
+            .await
+            .map_err(Self::map_mongo_error)?;
+
+        info!("Ensured the v2 simulation indexes");
+        Ok(())
+    }
+
+    /// The deadline a simulation is written with: its own retention when it
+    /// chose one, the store's otherwise, and none when it is pinned.
+    fn expires_at(&self, simulation: &SessionV2, now: bson::DateTime) -> Option<bson::DateTime> {
+        if simulation.pinned {
+            return None;
+        }
+        let ttl = simulation.retention_secs.unwrap_or(self.retention_secs);
+        let ttl_ms = i64::try_from(ttl.saturating_mul(1_000)).unwrap_or(i64::MAX);
+        Some(bson::DateTime::from_millis(
+            now.timestamp_millis().saturating_add(ttl_ms),
+        ))
+    }
+
+    /// The stored form of `simulation`, written at `now`.
+    fn stored(
+        &self,
+        simulation: &SessionV2,
+        now: bson::DateTime,
+    ) -> Result<StoredSimulation, ChainError> {
+        let document = serde_json::to_string(simulation).map_err(|e| {
+            error!(simulation_id = %simulation.id, error = %e, "Failed to serialize simulation");
+            ChainError::Internal(format!("Failed to serialize simulation: {e}"))
+        })?;
+        Ok(StoredSimulation {
+            id: simulation.id.to_string(),
+            version: stored_version(simulation.version)?,
+            created_at: created_micros(simulation.created_at),
+            expires_at: self.expires_at(simulation, now),
+            pinned: simulation.pinned,
+            symbol: simulation.parameters.symbol.clone(),
+            tags: simulation.tags.clone(),
+            document,
+        })
+    }
+
+    /// Reads a simulation back out of its stored form.
+    ///
+    /// Deserialization runs the document through the same validation a request
+    /// goes through, as the Redis store's does.
+    fn load(stored: &StoredSimulation) -> Result<SessionV2, ChainError> {
+        serde_json::from_str::<SessionV2>(&stored.document).map_err(|e| {
+            error!(simulation_id = %stored.id, error = %e, "Failed to deserialize simulation");
+            ChainError::Internal(format!("Failed to deserialize simulation: {e}"))
+        })
+    }
+
+    /// Maps a MongoDB error into the crate's error boundary.
+    #[cold]
+    fn map_mongo_error(err: mongodb::error::Error) -> ChainError {
+        ChainError::Internal(format!("MongoDB error: {err}"))
+    }
+
+    /// Whether a live simulation with this id exists.
+    async fn exists(&self, id: Uuid) -> Result<bool, ChainError> {
+        let found = self
+            .collection
+            .count_documents(live_id(id, bson::DateTime::now(), Document::new()))
+            .await
+            .map_err(Self::map_mongo_error)?;
+        Ok(found > 0)
+    }
+
+    /// Removes an expired document still holding `id`, so the id can be
+    /// created again — what Redis does on its own when a key expires.
+    async fn remove_expired(&self, id: Uuid) -> Result<bool, ChainError> {
+        let removed = self
+            .collection
+            .delete_one(doc! {
+                "_id": id.to_string(),
+                "expires_at": { "$lte": bson::DateTime::now() },
+            })
+            .await
+            .map_err(Self::map_mongo_error)?;
+        Ok(removed.deleted_count > 0)
+    }
+
+    /// Removes a pinned simulation that landed beyond the quota.
+    ///
+    /// Counts the pinned simulations created at or before it, in the listing
+    /// order; see the module docs.
+    async fn enforce_pinned_quota(&self, stored: &StoredSimulation) -> Result<(), ChainError> {
+        let ahead = self
+            .collection
+            .count_documents(doc! {
+                "pinned": true,
+                "$or": [
+                    { "created_at": { "$lt": stored.created_at } },
+                    { "created_at": stored.created_at, "_id": { "$lte": &stored.id } },
+                ],
+            })
+            .await
+            .map_err(Self::map_mongo_error)?;
+        if usize::try_from(ahead).unwrap_or(usize::MAX) <= self.max_pinned {
+            return Ok(());
+        }
+
+        self.collection
+            .delete_one(doc! { "_id": &stored.id })
+            .await
+            .map_err(Self::map_mongo_error)?;
+        Err(super::v2_memory::pinned_quota_reached(self.max_pinned))
+    }
+}
+
+#[async_trait]
+impl SimulationStore for MongoSimulationStore {
+    #[instrument(skip(self), level = "debug")]
+    async fn get(&self, id: Uuid) -> Result<SessionV2, ChainError> {
+        debug!(simulation_id = %id, "Getting simulation from MongoDB");
+
+        match self
+            .collection
+            .find_one(live_id(id, bson::DateTime::now(), Document::new()))
+            .await
+        {
+            Ok(Some(stored)) => Self::load(&stored),
+            Ok(None) => Err(ChainError::NotFound(format!(
+                "Simulation with id {id} not found"
+            ))),
+            Err(e) => {
+                error!(simulation_id = %id, error = %e, "MongoDB error while getting simulation");
+                Err(Self::map_mongo_error(e))
+            }
+        }
+    }
+
+    #[instrument(skip(self, simulation), level = "debug")]
+    async fn create(&self, simulation: SessionV2) -> Result<(), ChainError> {
+        let id = simulation.id;
+        debug!(simulation_id = %id, "Creating simulation in MongoDB");
+
+        simulation.validate()?;
+
+        let stored = self.stored(&simulation, bson::DateTime::now())?;
+        let mut inserted = self.collection.insert_one(&stored).await;
+        // A collision with a document that has already expired is not a
+        // collision: it is debris cleanup has not reached yet.
+        if let Err(error) = &inserted
+            && is_duplicate_key(error)
+            && self.remove_expired(id).await?
+        {
+            inserted = self.collection.insert_one(&stored).await;
+        }
+
+        match inserted {
+            Ok(_) => {}
+            Err(error) if is_duplicate_key(&error) => {
+                return Err(ChainError::AlreadyExists(format!(
+                    "Simulation with id {id} already exists"
+                )));
+            }
+            Err(error) => return Err(Self::map_mongo_error(error)),
+        }
+
+        if stored.pinned {
+            self.enforce_pinned_quota(&stored).await?;
+        }
+        debug!(simulation_id = %id, "Simulation created successfully");
+        Ok(())
+    }
+
+    #[instrument(skip(self, simulation), level = "debug")]
+    async fn save_cas(
+        &self,
+        simulation: SessionV2,
+        expected_version: u64,
+    ) -> Result<(), ChainError> {
+        let id = simulation.id;
+        debug!(simulation_id = %id, expected_version, "CAS-saving simulation to MongoDB");
+
+        simulation.validate()?;
+
+        let now = bson::DateTime::now();
+        let stored = self.stored(&simulation, now)?;
+        let updated = self
+            .collection
+            .update_one(
+                live_id(
+                    id,
+                    now,
+                    doc! { "version": stored_version(expected_version)? },
+                ),
+                doc! { "$set": {
+                    "version": stored.version,
+                    "expires_at": stored.expires_at,
+                    "document": &stored.document,
+                } },
+            )
+            .await
+            .map_err(Self::map_mongo_error)?;
+        if updated.matched_count > 0 {
+            return Ok(());
+        }
+
+        let error = if self.exists(id).await? {
+            ChainError::Conflict(format!(
+                "Simulation {id} was modified concurrently (expected version {expected_version})"
+            ))
+        } else {
+            ChainError::NotFound(format!("Simulation with id {id} not found"))
+        };
+        debug!(simulation_id = %id, error = %error, "CAS save rejected");
+        Err(error)
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn delete(&self, id: Uuid) -> Result<bool, ChainError> {
+        debug!(simulation_id = %id, "Deleting simulation from MongoDB");
+
+        // Unconditional, so an expired document goes too; only a live one
+        // counts as removed, matching a Redis key that had already expired.
+        let removed = self
+            .collection
+            .find_one_and_delete(doc! { "_id": id.to_string() })
+            .await
+            .map_err(Self::map_mongo_error)?;
+        let now = bson::DateTime::now();
+        Ok(removed.is_some_and(|stored| stored.expires_at.is_none_or(|at| at > now)))
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn delete_cas(&self, id: Uuid, expected_version: u64) -> Result<bool, ChainError> {
+        debug!(simulation_id = %id, expected_version, "CAS-deleting simulation from MongoDB");
+
+        let removed = self
+            .collection
+            .delete_one(live_id(
+                id,
+                bson::DateTime::now(),
+                doc! { "version": stored_version(expected_version)? },
+            ))
+            .await
+            .map_err(Self::map_mongo_error)?;
+        if removed.deleted_count > 0 {
+            return Ok(true);
+        }
+        if self.exists(id).await? {
+            return Err(ChainError::Conflict(format!(
+                "Simulation {id} was modified concurrently (expected version {expected_version})"
+            )));
+        }
+        Ok(false)
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn list(
+        &self,
+        filter: &SimulationFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SimulationPage, ChainError> {
+        let mut cursor = self
+            .collection
+            .find(list_query(filter, bson::DateTime::now()))
+            .sort(doc! { "created_at": 1, "_id": 1 })
+            .batch_size(LIST_BATCH)
+            .await
+            .map_err(Self::map_mongo_error)?;
+
+        let mut skipped = 0usize;
+        let mut simulations = Vec::new();
+        // One more than the page is collected so the caller learns whether a
+        // next page exists without a second listing.
+        while simulations.len() <= limit {
+            let Some(stored) = cursor.try_next().await.map_err(Self::map_mongo_error)? else {
+                break;
+            };
+            // A corrupt document would fail `get` just the same; it is not
+            // worth failing the listing over.
+            let simulation = match Self::load(&stored) {
+                Ok(simulation) => simulation,
+                Err(_) => continue,
+            };
+            if !filter.matches(&simulation) {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+            } else {
+                simulations.push(simulation);
+            }
+        }
+
+        let next_offset = if simulations.len() > limit {
+            simulations.truncate(limit);
+            Some(offset.saturating_add(limit))
+        } else {
+            None
+        };
+        Ok(SimulationPage {
+            simulations,
+            next_offset,
+        })
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn cleanup(&self) -> Result<Vec<Uuid>, ChainError> {
+        let now = bson::DateTime::now();
+        let candidates: Vec<StoredSimulation> = self
+            .collection
+            .find(doc! { "expires_at": { "$lte": now } })
+            .limit(CLEANUP_BATCH)
+            .await
+            .map_err(Self::map_mongo_error)?
+            .try_collect()
+            .await
+            .map_err(Self::map_mongo_error)?;
+
+        let mut expired = Vec::new();
+        for stored in candidates {
+            // Conditional on the deadline still having passed: an advance
+            // that refreshed it since the scan keeps its simulation.
+            let removed = self
+                .collection
+                .delete_one(doc! { "_id": &stored.id, "expires_at": { "$lte": now } })
+                .await
+                .map_err(Self::map_mongo_error)?;
+            // An id that is not a uuid cannot correspond to a simulation; it
+            // is gone now, so simply do not report it.
+            if removed.deleted_count > 0
+                && let Ok(id) = Uuid::parse_str(&stored.id)
+            {
+                expired.push(id);
+            }
+        }
+
+        if !expired.is_empty() {
+            info!(count = expired.len(), "Reaped expired simulations");
+        }
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Revisions cross into BSON's signed integers losslessly, and one that
+    /// cannot is an error rather than a wrapped value that could match.
+    #[test]
+    fn test_stored_version_refuses_what_bson_cannot_hold() {
+        match stored_version(7) {
+            Ok(version) => assert_eq!(version, 7),
+            Err(error) => panic!("a small revision must convert: {error}"),
+        }
+        assert!(matches!(
+            stored_version(u64::MAX),
+            Err(ChainError::Internal(_))
+        ));
+    }
+
+    /// The listing narrows on what it can and widens the creation bounds to
+    /// whole microseconds, so the exact check never loses a match.
+    #[test]
+    fn test_the_list_query_narrows_and_widens() {
+        let now = bson::DateTime::from_millis(0);
+        let filter = SimulationFilter {
+            symbol: Some("SPX".to_string()),
+            created_after: Some(UNIX_EPOCH + Duration::from_nanos(1_500)),
+            created_before: Some(UNIX_EPOCH + Duration::from_nanos(3_500)),
+            tags: vec!["ci".to_string()],
+            ..SimulationFilter::default()
+        };
+
+        let query = list_query(&filter, now);
+        assert_eq!(
+            query.get_document("created_at").ok(),
+            Some(&doc! { "$gte": 1_i64, "$lte": 4_i64 })
+        );
+        assert_eq!(query.get_str("symbol").ok(), Some("SPX"));
+        assert_eq!(
+            query.get_document("tags").ok(),
+            Some(&doc! { "$all": ["ci"] })
+        );
+        assert!(
+            query.contains_key("$or"),
+            "a listing only sees live documents"
+        );
+
+        let everything = list_query(&SimulationFilter::default(), now);
+        assert_eq!(everything.keys().collect::<Vec<_>>(), ["$or"]);
+    }
+
+    /// The creation order is the Redis store's score, so the two backends
+    /// page through the same simulations in the same order.
+    #[test]
+    fn test_the_creation_order_matches_the_redis_score() {
+        let at = UNIX_EPOCH + Duration::from_micros(1_767_000_000_123_456);
+        assert_eq!(created_micros(at), 1_767_000_000_123_456);
+        assert_eq!(created_micros(UNIX_EPOCH - Duration::from_secs(1)), 0);
+    }
+}
+
+/// Integration tests against a live MongoDB.
+///
+/// They need the server the Integration job provisions (`MONGODB_URI`), so
+/// they are `#[ignore]`d and the default suite stays hermetic. Every test
+/// uses its own collection and drops it afterwards.
+#[cfg(test)]
+mod live_tests {
+    use super::*;
+    use crate::api::rest::models::{ApiTimeFrame, ApiWalkType};
+    use crate::api::rest::requests_v2::CreateSimulationRequest;
+    use crate::infrastructure::MongoDBConfig;
+    use crate::session::model_v2::SimulationParametersV2;
+    use crate::session::{ExpiryRule, ExpiryRuleKind};
+    use std::time::Duration;
+    use tokio::test;
+
+    /// Builds a store against the provisioned MongoDB, in its own collection.
+    async fn store(name: &str, retention_secs: u64) -> MongoSimulationStore {
+        let client = MongoDBClient::new(MongoDBConfig::default())
+            .await
+            .expect("the provisioned MongoDB must accept a connection");
+        let store = MongoSimulationStore::new(
+            Arc::new(client),
+            Some(format!("ocs_test_{name}_{}", Uuid::new_v4().simple())),
+            Some(retention_secs),
+        )
+        .await;
+        store.ensure_indexes().await.expect("must create indexes");
+        store
+    }
+
+    async fn drop(store: &MongoSimulationStore) {
+        store
+            .collection
+            .drop()
+            .await
+            .expect("must drop the collection");
+    }
+
+    fn simulation(seed: u64) -> SessionV2 {
+        let rule = ExpiryRule::new("zero_dte", ExpiryRuleKind::Daily, 1)
+            .expect("the test rule must be valid");
+        let request = CreateSimulationRequest {
+            symbol: "SPX".to_string(),
+            steps: 10,
+            start_at: None,
+            step_interval_seconds: Some(86_400),
+            timezone: "America/New_York".to_string(),
+            calendar: None,
+            expiration_time: "17:00".to_string(),
+            schedules: vec![rule],
+            initial_price: 5000.0,
+            volatility: 0.18,
+            risk_free_rate: 0.04,
+            dividend_yield: 0.0,
+            method: ApiWalkType::Brownian {
+                dt: 0.004,
+                drift: 0.0,
+                volatility: 0.18,
+            },
+            time_frame: ApiTimeFrame::Day,
+            chain_size: Some(15),
+            strike_interval: Some(25.0),
+            skew_slope: None,
+            smile_curve: None,
+            spread: Some(0.02),
+            seed: Some(seed),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
+        };
+        let parameters =
+            SimulationParametersV2::try_from(request).expect("the reference request must convert");
+        SessionV2::new(parameters)
+    }
+
+    /// A simulation round-trips unchanged — including a seed above
+    /// `i64::MAX`, which BSON could not have held — and a second create of the
+    /// same id is rejected.
+    #[test]
+    #[ignore = "requires a live MongoDB matching MONGODB_URI; run with -- --ignored"]
+    async fn test_create_then_get_round_trips_through_mongodb() {
+        let store = store("roundtrip", 60).await;
+        let original = simulation(u64::MAX);
+
+        store.create(original.clone()).await.expect("must create");
+        assert_eq!(store.get(original.id).await.expect("must load"), original);
+        assert!(matches!(
+            store.create(original.clone()).await,
+            Err(ChainError::AlreadyExists(_))
+        ));
+
+        drop(&store).await;
+    }
+
+    /// The compare-and-swap commits at the expected revision and rejects the
+    /// loser, and a conditional delete honours the revision too.
+    #[test]
+    #[ignore = "requires a live MongoDB matching MONGODB_URI; run with -- --ignored"]
+    async fn test_save_cas_and_delete_cas_honour_the_revision() {
+        let store = store("cas", 60).await;
+        let original = simulation(2);
+        store.create(original.clone()).await.expect("must create");
+
+        let mut advanced = original.clone();
+        advanced.current_step = 1;
+        advanced.state = crate::session::SessionState::InProgress;
+        advanced.version = 1;
+        store
+            .save_cas(advanced.clone(), 0)
+            .await
+            .expect("must commit");
+        assert!(matches!(
+            store.save_cas(advanced.clone(), 0).await,
+            Err(ChainError::Conflict(_))
+        ));
+        assert!(matches!(
+            store.delete_cas(original.id, 0).await,
+            Err(ChainError::Conflict(_))
+        ));
+        assert!(store.delete_cas(original.id, 1).await.expect("must delete"));
+        assert!(matches!(
+            store.save_cas(advanced, 1).await,
+            Err(ChainError::NotFound(_))
+        ));
+
+        drop(&store).await;
+    }
+
+    /// Cleanup reports what expired and spares what is live or pinned.
+    #[test]
+    #[ignore = "requires a live MongoDB matching MONGODB_URI; run with -- --ignored"]
+    async fn test_cleanup_reports_expired_ids_and_spares_live_ones() {
+        let store = store("cleanup", 1).await;
+        let expiring = simulation(3);
+        let mut pinned = simulation(4);
+        pinned.pinned = true;
+        store.create(expiring.clone()).await.expect("must create");
+        store.create(pinned.clone()).await.expect("must create");
+
+        tokio::time::sleep(Duration::from_millis(1_100)).await;
+        assert!(matches!(
+            store.get(expiring.id).await,
+            Err(ChainError::NotFound(_))
+        ));
+        assert_eq!(store.cleanup().await.expect("must reap"), vec![expiring.id]);
+        assert!(store.cleanup().await.expect("must reap").is_empty());
+        assert!(store.get(pinned.id).await.is_ok());
+
+        drop(&store).await;
+    }
+
+    /// The pinned quota holds: a pinned simulation past it is refused and
+    /// leaves nothing behind.
+    #[test]
+    #[ignore = "requires a live MongoDB matching MONGODB_URI; run with -- --ignored"]
+    async fn test_the_pinned_quota_is_enforced() {
+        let store = store("pinned", 60).await.with_max_pinned(1);
+        let mut first = simulation(5);
+        first.pinned = true;
+        let mut second = simulation(6);
+        second.pinned = true;
+
+        store.create(first).await.expect("must create");
+        assert!(matches!(
+            store.create(second.clone()).await,
+            Err(ChainError::Conflict(_))
+        ));
+        assert!(matches!(
+            store.get(second.id).await,
+            Err(ChainError::NotFound(_))
+        ));
+
+        drop(&store).await;
+    }
+}
@@ -6,12 +6,12 @@
 //!
 //! # Why every write is a script
 //!
-//! A simulation is three keys: the document, a companion revision key, and a
-//! membership entry in an index. Writing them with sequential commands leaves
-//! two silent torn states — a document with no revision key, and a document
-//! absent from the index, which would be invisible to [`SimulationStore::cleanup`]
-//! forever. Each operation is therefore one Lua script, which Redis runs as a
-//! single uninterruptible unit.
+//! A simulation is several keys: the document, a companion revision key, and
+//! membership entries in the indexes. Writing them with sequential commands
+//! leaves two silent torn states — a document with no revision key, and a
+//! document absent from the index, which would be invisible to
+//! [`SimulationStore::cleanup`] forever. Each operation is therefore one Lua
+//! script, which Redis runs as a single uninterruptible unit.
 //!
 //! # Why there is an index at all
 //!
@@ -23,14 +23,42 @@
 //! deadline, so cleanup is a range query over what has actually expired rather
 //! than an existence probe per member — no `SMEMBERS` over an unbounded set, and
 //! no window in which a recreated id could be reported as expired.
+//!
+//! A second sorted set, scored by creation time, backs
+//! [`SimulationStore::list`]. It is maintained by the same scripts, so a
+//! listable simulation is always a live one and a reaped one is never listed.
+//!
+//! The listing filters have indexes of their own: one sorted set per symbol,
+//! per state and per tag, each scored by creation time like the creation
+//! index. A filtered listing intersects the sets its filter names and pages
+//! through the result by creation range, so it costs in proportion to the
+//! smallest set it names rather than to every stored simulation. Which sets a
+//! simulation is in is recorded beside it (see [`facets_key`]), so a write
+//! that changes its state or tags — or a delete, or a reap — takes it out of
+//! the old ones in the same script that puts it in the new.
+//!
+//! A simulation stored before the creation index and the facet sets existed
+//! is in none of them, and a pinned or idle one may never be written again.
+//! [`InRedisSimulationStore::ensure_indexes`] indexes every such document once,
+//! at startup, so listings — filtered or not — see the whole store.
+//!
+//! # Pinned simulations
+//!
+//! A pinned simulation is written without a TTL and kept **out** of the
+//! deadline index, so neither Redis nor `cleanup` can expire it. Its id goes
+//! into a plain set instead, whose cardinality is the pinned quota's count;
+//! checking the quota and writing the document in one script is what stops two
+//! concurrent pinned creations from both taking the last slot.
 
 use crate::infrastructure::RedisClient;
+use crate::session::model::SessionState;
 use crate::session::model_v2::SessionV2;
-use crate::session::store::v2_interface::SimulationStore;
+use crate::session::store::v2_interface::{SimulationFilter, SimulationPage, SimulationStore};
 use crate::utils::error::ChainError;
 use async_trait::async_trait;
 use redis::RedisError;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, error, info, instrument};
 use uuid::Uuid;
 
@@ -47,18 +75,48 @@ pub const DEFAULT_V2_KEY_PREFIX: &str = "optionchain:simulation:v2:";
 /// (`TIME`) rather than the client's, so a skewed caller cannot make cleanup
 /// reap early or late.
 ///
-/// Keys: `KEYS[1]` document, `KEYS[2]` revision, `KEYS[3]` index.
+/// The facet sets are rewritten rather than added to: an id is deterministic,
+/// so a simulation recreated after its document expired — but before cleanup
+/// reaped it — must not keep the memberships of its previous life.
+///
+/// Keys: `KEYS[1]` document, `KEYS[2]` revision, `KEYS[3]` index, `KEYS[4]`
+/// creation index, `KEYS[5]` pinned set, `KEYS[6]` facet record, `KEYS[7..]`
+/// the facet sets the simulation belongs in.
 /// Arguments: `ARGV[1]` document JSON, `ARGV[2]` revision string, `ARGV[3]` TTL
-/// in seconds, `ARGV[4]` simulation id.
+/// in seconds, `0` for a pinned simulation, `ARGV[4]` simulation id, `ARGV[5]`
+/// creation score, `ARGV[6]` pinned quota.
 ///
-/// Returns `1` when created, `0` when the id already exists.
+/// Returns `1` when created, `0` when the id already exists, `-1` when a pinned
+/// simulation would exceed the quota.
 const CREATE_SCRIPT: &str = r#"
-if redis.call('SET', KEYS[1], ARGV[1], 'EX', tonumber(ARGV[3]), 'NX') == false then
-    return 0
+local ttl = tonumber(ARGV[3])
+if ttl == 0 then
+    if redis.call('EXISTS', KEYS[1]) == 1 then
+        return 0
+    end
+    if redis.call('SCARD', KEYS[5]) >= tonumber(ARGV[6]) then
+        return -1
+    end
+    redis.call('SET', KEYS[1], ARGV[1])
+    redis.call('SET', KEYS[2], ARGV[2])
+    redis.call('SADD', KEYS[5], ARGV[4])
+else
+    if redis.call('SET', KEYS[1], ARGV[1], 'EX', ttl, 'NX') == false then
+        return 0
+    end
+    redis.call('SET', KEYS[2], ARGV[2], 'EX', ttl)
+    local now = tonumber(redis.call('TIME')[1])
+    redis.call('ZADD', KEYS[3], now + ttl, ARGV[4])
+end
+redis.call('ZADD', KEYS[4], ARGV[5], ARGV[4])
+for _, facet in ipairs(redis.call('SMEMBERS', KEYS[6])) do
+    redis.call('ZREM', facet, ARGV[4])
+end
+redis.call('DEL', KEYS[6])
+for i = 7, #KEYS do
+    redis.call('ZADD', KEYS[i], ARGV[5], ARGV[4])
+    redis.call('SADD', KEYS[6], KEYS[i])
 end
-redis.call('SET', KEYS[2], ARGV[2], 'EX', tonumber(ARGV[3]))
-local now = tonumber(redis.call('TIME')[1])
-redis.call('ZADD', KEYS[3], now + tonumber(ARGV[3]), ARGV[4])
 return 1
 "#;
 
@@ -73,11 +131,23 @@ return 1
 ///
 /// The same unit refreshes the TTL on both keys and the deadline in the index,
 /// so a simulation being actively walked cannot have its index entry go stale
-/// and be reaped while its document is still live.
+/// and be reaped while its document is still live. It also (re)writes the
+/// creation-index entry, which is idempotent and is what makes a simulation
+/// created before that index existed listable once it is next advanced.
+///
+/// A pinned simulation (`ARGV[4]` of `0`) is rewritten without a TTL and
+/// stays out of the deadline index, exactly as it was created.
+///
+/// The facet sets are rewritten the way [`CREATE_SCRIPT`] writes them, which
+/// moves a simulation whose state or tags changed, and indexes one written
+/// before the facet sets existed.
 ///
-/// Keys: `KEYS[1]` document, `KEYS[2]` revision, `KEYS[3]` index.
+/// Keys: `KEYS[1]` document, `KEYS[2]` revision, `KEYS[3]` index, `KEYS[4]`
+/// creation index, `KEYS[5]` facet record, `KEYS[6..]` the facet sets the
+/// simulation belongs in.
 /// Arguments: `ARGV[1]` new document JSON, `ARGV[2]` expected revision,
-/// `ARGV[3]` new revision, `ARGV[4]` TTL in seconds, `ARGV[5]` simulation id.
+/// `ARGV[3]` new revision, `ARGV[4]` TTL in seconds, `0` when pinned,
+/// `ARGV[5]` simulation id, `ARGV[6]` creation score.
 ///
 /// Returns `-1` when the document is gone, `-2` on a revision mismatch, `1` when
 /// written.
@@ -89,25 +159,60 @@ local ver = redis.call('GET', KEYS[2]) or '0'
 if ver ~= ARGV[2] then
     return -2
 end
-redis.call('SET', KEYS[1], ARGV[1], 'EX', tonumber(ARGV[4]))
-redis.call('SET', KEYS[2], ARGV[3], 'EX', tonumber(ARGV[4]))
-local now = tonumber(redis.call('TIME')[1])
-redis.call('ZADD', KEYS[3], now + tonumber(ARGV[4]), ARGV[5])
+local ttl = tonumber(ARGV[4])
+if ttl == 0 then
+    redis.call('SET', KEYS[1], ARGV[1])
+    redis.call('SET', KEYS[2], ARGV[3])
+    redis.call('ZREM', KEYS[3], ARGV[5])
+else
+    redis.call('SET', KEYS[1], ARGV[1], 'EX', ttl)
+    redis.call('SET', KEYS[2], ARGV[3], 'EX', ttl)
+    local now = tonumber(redis.call('TIME')[1])
+    redis.call('ZADD', KEYS[3], now + ttl, ARGV[5])
+end
+redis.call('ZADD', KEYS[4], ARGV[6], ARGV[5])
+for _, facet in ipairs(redis.call('SMEMBERS', KEYS[5])) do
+    redis.call('ZREM', facet, ARGV[5])
+end
+redis.call('DEL', KEYS[5])
+for i = 6, #KEYS do
+    redis.call('ZADD', KEYS[i], ARGV[6], ARGV[5])
+    redis.call('SADD', KEYS[5], KEYS[i])
+end
 return 1
 "#;
 
-/// Deletes a simulation and every trace of it.
+/// Deletes a simulation and every trace of it, optionally only at a given
+/// revision.
 ///
-/// Keys: `KEYS[1]` document, `KEYS[2]` revision, `KEYS[3]` index.
-/// Arguments: `ARGV[1]` simulation id.
+/// The revision is compared the way [`SAVE_CAS_SCRIPT`] compares it, as a
+/// string against the companion key. An empty `ARGV[2]` deletes
+/// unconditionally.
+///
+/// Keys: `KEYS[1]` document, `KEYS[2]` revision, `KEYS[3]` index, `KEYS[4]`
+/// creation index, `KEYS[5]` pinned set, `KEYS[6]` facet record.
+/// Arguments: `ARGV[1]` simulation id, `ARGV[2]` expected revision or empty.
 ///
 /// Returns `1` when a document was removed, `0` when there was nothing to
-/// remove. The revision key and the index entry go either way, so a partially
-/// expired simulation cannot leave debris behind.
+/// remove, `-2` on a revision mismatch. The revision key, every index entry
+/// and any pinned slot go whenever the document does or already has, so a
+/// partially expired simulation cannot leave debris behind.
 const DELETE_SCRIPT: &str = r#"
+if ARGV[2] ~= '' and redis.call('EXISTS', KEYS[1]) == 1 then
+    local ver = redis.call('GET', KEYS[2]) or '0'
+    if ver ~= ARGV[2] then
+        return -2
+    end
+end
 local removed = redis.call('DEL', KEYS[1])
 redis.call('DEL', KEYS[2])
 redis.call('ZREM', KEYS[3], ARGV[1])
+redis.call('ZREM', KEYS[4], ARGV[1])
+redis.call('SREM', KEYS[5], ARGV[1])
+for _, facet in ipairs(redis.call('SMEMBERS', KEYS[6])) do
+    redis.call('ZREM', facet, ARGV[1])
+end
+redis.call('DEL', KEYS[6])
 return removed
 "#;
 
@@ -120,7 +225,7 @@ return removed
 /// make the caller evict a cache it still needs and drop the id from the index
 /// permanently.
 ///
-/// Keys: `KEYS[1]` index.
+/// Keys: `KEYS[1]` index, `KEYS[2]` creation index.
 /// Arguments: `ARGV[1]` key prefix, `ARGV[2]` maximum ids to reap in one pass.
 ///
 /// Returns the reaped ids.
@@ -128,13 +233,48 @@ const CLEANUP_SCRIPT: &str = r#"
 local now = tonumber(redis.call('TIME')[1])
 local expired = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', now, 'LIMIT', 0, tonumber(ARGV[2]))
 for _, id in ipairs(expired) do
+    local facets = ARGV[1] .. id .. ':facets'
+    for _, facet in ipairs(redis.call('SMEMBERS', facets)) do
+        redis.call('ZREM', facet, id)
+    end
     redis.call('DEL', ARGV[1] .. id)
     redis.call('DEL', ARGV[1] .. id .. ':ver')
+    redis.call('DEL', facets)
     redis.call('ZREM', KEYS[1], id)
+    redis.call('ZREM', KEYS[2], id)
 end
 return expired
 "#;
 
+/// Indexes a simulation that has no facet record yet, exactly as
+/// [`SAVE_CAS_SCRIPT`] would, without rewriting it.
+///
+/// The document is compared with the one the caller read and indexed from, so
+/// a write that slips in between — which indexes the simulation itself — is
+/// never overwritten with the facets of the older document.
+///
+/// Keys: `KEYS[1]` document, `KEYS[2]` creation index, `KEYS[3]` facet record,
+/// `KEYS[4..]` the facet sets the simulation belongs in.
+/// Arguments: `ARGV[1]` the document JSON read, `ARGV[2]` simulation id,
+/// `ARGV[3]` creation score.
+///
+/// Returns `1` when indexed, `0` when the document changed, went, or was
+/// already indexed.
+const INDEX_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+if redis.call('EXISTS', KEYS[3]) == 1 then
+    return 0
+end
+redis.call('ZADD', KEYS[2], ARGV[3], ARGV[2])
+for i = 4, #KEYS do
+    redis.call('ZADD', KEYS[i], ARGV[3], ARGV[2])
+    redis.call('SADD', KEYS[3], KEYS[i])
+end
+return 1
+"#;
+
 /// Maximum ids one `cleanup` pass reaps.
 ///
 /// Bounds both the script's run time — Redis is single-threaded, so an unbounded
@@ -142,6 +282,78 @@ return expired
 /// with more than this to reap simply reaps the rest on its next pass.
 const CLEANUP_BATCH: usize = 1_000;
 
+/// Reads one batch of candidate ids for a filtered listing: the members of
+/// every facet set named, inside a creation range, oldest first.
+///
+/// The intersection is stored under a scratch key and dropped in the same
+/// unit, so nothing outlives the call. A single set needs no intersection and
+/// is ranged directly. Every facet set is scored by creation time, so the
+/// intersection's scores — `MAX` of equal values — are too.
+///
+/// Keys: `KEYS[1]` scratch key, `KEYS[2..]` the facet sets.
+/// Arguments: `ARGV[1]` and `ARGV[2]` the creation range, `ARGV[3]` offset
+/// into it, `ARGV[4]` batch size.
+///
+/// Returns the ids.
+const LIST_SCRIPT: &str = r#"
+if #KEYS == 2 then
+    return redis.call('ZRANGEBYSCORE', KEYS[2], ARGV[1], ARGV[2], 'LIMIT', ARGV[3], ARGV[4])
+end
+local intersect = {'ZINTERSTORE', KEYS[1], #KEYS - 1}
+for i = 2, #KEYS do
+    table.insert(intersect, KEYS[i])
+end
+table.insert(intersect, 'AGGREGATE')
+table.insert(intersect, 'MAX')
+redis.call(unpack(intersect))
+local ids = redis.call('ZRANGEBYSCORE', KEYS[1], ARGV[1], ARGV[2], 'LIMIT', ARGV[3], ARGV[4])
+redis.call('DEL', KEYS[1])
+return ids
+"#;
+
+/// Ids one `list` round trip reads from the index it pages through.
+///
+/// A filter on symbol, state or tags pages through the intersection of their
+/// facet sets, so nearly every candidate is a match; one naming none of them
+/// pages through the creation index. Either way each round is one bounded
+/// range read plus one `MGET`, and the documents are still checked against the
+/// filter — the creation range is widened to whole microseconds, and a
+/// document may have moved on since its id was read.
+const LIST_BATCH: usize = 500;
+
+/// The creation-index score of a simulation: `created_at` in whole
+/// microseconds since the Unix epoch.
+///
+/// Microseconds rather than nanoseconds because a score is an IEEE-754
+/// double, exact only up to `2^53`; present-day microseconds fit with room to
+/// spare, nanoseconds do not. Two simulations created within the same
+/// microsecond tie on score and Redis orders them by member, which is the id —
+/// the same tie-break the in-memory store applies.
+fn created_score(created_at: SystemTime) -> u64 {
+    created_at
+        .duration_since(UNIX_EPOCH)
+        .map(|since| u64::try_from(since.as_micros()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+/// The creation-index score range `[min, max]` that can hold a match for
+/// `filter`, as Redis range arguments.
+///
+/// Widened to whole microseconds in both directions, so it only ever narrows
+/// the candidates; [`SimulationFilter::matches`] still decides the bounds
+/// exactly.
+fn created_range(filter: &SimulationFilter) -> (String, String) {
+    let min = filter.created_after.map_or_else(
+        || "-inf".to_string(),
+        |after| created_score(after).to_string(),
+    );
+    let max = filter.created_before.map_or_else(
+        || "+inf".to_string(),
+        |before| created_score(before).saturating_add(1).to_string(),
+    );
+    (min, max)
+}
+
 /// The key holding a simulation document.
 ///
 /// A free function so the layout can be asserted without a live connection —
@@ -167,11 +379,89 @@ fn index_key(prefix: &str) -> String {
     format!("{prefix}index")
 }
 
+/// The sorted set holding every live simulation id, scored by its creation
+/// time; see [`created_score`].
+#[must_use]
+#[inline]
+fn created_index_key(prefix: &str) -> String {
+    format!("{prefix}created")
+}
+
+/// The set holding every pinned simulation id; its cardinality is the pinned
+/// quota's count.
+#[must_use]
+#[inline]
+fn pinned_key(prefix: &str) -> String {
+    format!("{prefix}pinned")
+}
+
+/// The sorted set of the simulations on `symbol`, scored by creation time.
+#[must_use]
+#[inline]
+fn symbol_key(prefix: &str, symbol: &str) -> String {
+    format!("{prefix}symbol:{symbol}")
+}
+
+/// The sorted set of the simulations in `state`, scored by creation time.
+#[must_use]
+#[inline]
+fn state_key(prefix: &str, state: SessionState) -> String {
+    let state = match state {
+        SessionState::Initialized => "Initialized",
+        SessionState::InProgress => "InProgress",
+        SessionState::Modified => "Modified",
+        SessionState::Reinitialized => "Reinitialized",
+        SessionState::Completed => "Completed",
+        SessionState::Error => "Error",
+    };
+    format!("{prefix}state:{state}")
+}
+
+/// The sorted set of the simulations carrying `tag`, scored by creation time.
+#[must_use]
+#[inline]
+fn tag_key(prefix: &str, tag: &str) -> String {
+    format!("{prefix}tag:{tag}")
+}
+
+/// The set recording which facet sets a simulation is in, so a write can take
+/// it out of the ones it has left without reading the previous document.
+#[must_use]
+#[inline]
+fn facets_key(prefix: &str, id: Uuid) -> String {
+    format!("{}:facets", simulation_key(prefix, id))
+}
+
+/// The facet sets `simulation` belongs in.
+fn simulation_facets(prefix: &str, simulation: &SessionV2) -> Vec<String> {
+    let mut facets = vec![
+        symbol_key(prefix, &simulation.parameters.symbol),
+        state_key(prefix, simulation.state),
+    ];
+    facets.extend(simulation.tags.iter().map(|tag| tag_key(prefix, tag)));
+    facets
+}
+
+/// The facet sets a match for `filter` must be in; empty when it names no
+/// symbol, state or tag.
+fn filter_facets(prefix: &str, filter: &SimulationFilter) -> Vec<String> {
+    let mut facets = Vec::new();
+    if let Some(symbol) = &filter.symbol {
+        facets.push(symbol_key(prefix, symbol));
+    }
+    if let Some(state) = filter.state {
+        facets.push(state_key(prefix, state));
+    }
+    facets.extend(filter.tags.iter().map(|tag| tag_key(prefix, tag)));
+    facets
+}
+
 /// Redis-backed store for v2 rolling simulations.
 pub struct InRedisSimulationStore {
     client: Arc<RedisClient>,
     key_prefix: String,
     retention_secs: u64,
+    max_pinned: usize,
 }
 
 impl InRedisSimulationStore {
@@ -201,7 +491,120 @@ impl InRedisSimulationStore {
             client,
             key_prefix: prefix,
             retention_secs: retention,
+            max_pinned: super::v2_memory::DEFAULT_V2_MAX_PINNED,
+        }
+    }
+
+    /// Sets how many simulations may be pinned at once.
+    #[must_use]
+    pub fn with_max_pinned(mut self, max_pinned: usize) -> Self {
+        self.max_pinned = max_pinned;
+        self
+    }
+
+    /// Indexes every stored simulation that predates the creation index and
+    /// the facet sets, and reports how many it indexed.
+    ///
+    /// Idempotent, and safe to call at every startup: a simulation already
+    /// indexed has a facet record and is skipped after one `EXISTS`, and each
+    /// one indexed goes through [`INDEX_SCRIPT`], which loses cleanly to a
+    /// concurrent write. The key space is walked with `SCAN`, so a large store
+    /// never blocks Redis; an unreadable document is logged and left alone,
+    /// exactly as a listing would skip it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Internal`] when Redis is unreachable or rejects a
+    /// command.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn ensure_indexes(&self) -> Result<usize, ChainError> {
+        let mut conn = self.client.connection_manager();
+        let pattern = format!("{}*", self.key_prefix);
+        let mut cursor = 0u64;
+        let mut indexed = 0usize;
+
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(LIST_BATCH)
+                .query_async(&mut conn)
+                .await
+                .map_err(Self::map_redis_error)?;
+
+            // Only a document key is the prefix followed by a bare id; the
+            // revision, facet-record and index keys all carry a suffix.
+            let ids: Vec<Uuid> = keys
+                .iter()
+                .filter_map(|key| key.strip_prefix(&self.key_prefix))
+                .filter_map(|rest| Uuid::parse_str(rest).ok())
+                .collect();
+            for id in ids {
+                if self.index_document(&mut conn, id).await? {
+                    indexed += 1;
+                }
+            }
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        if indexed > 0 {
+            info!(
+                count = indexed,
+                "Indexed simulations stored before the listing indexes"
+            );
+        }
+        Ok(indexed)
+    }
+
+    /// Runs [`INDEX_SCRIPT`] for one simulation that has no facet record.
+    async fn index_document(
+        &self,
+        conn: &mut redis::aio::ConnectionManager,
+        id: Uuid,
+    ) -> Result<bool, ChainError> {
+        let recorded = self
+            .client
+            .exists(&self.facets_key(id))
+            .await
+            .map_err(Self::map_redis_error)?;
+        if recorded {
+            return Ok(false);
         }
+
+        let Some(json) = self
+            .client
+            .get::<String>(&self.simulation_key(id))
+            .await
+            .map_err(Self::map_redis_error)?
+        else {
+            return Ok(false);
+        };
+        let simulation = match serde_json::from_str::<SessionV2>(&json) {
+            Ok(simulation) => simulation,
+            Err(e) => {
+                error!(simulation_id = %id, error = %e, "Skipping unreadable simulation while indexing");
+                return Ok(false);
+            }
+        };
+
+        let code: i64 = redis::Script::new(INDEX_SCRIPT)
+            .key(self.simulation_key(id))
+            .key(self.created_index_key())
+            .key(self.facets_key(id))
+            .key(simulation_facets(&self.key_prefix, &simulation))
+            .arg(json)
+            .arg(id.to_string())
+            .arg(created_score(simulation.created_at))
+            .invoke_async(conn)
+            .await
+            .map_err(Self::map_redis_error)?;
+        Ok(code == 1)
     }
 
     /// The key holding a simulation document.
@@ -225,6 +628,39 @@ impl InRedisSimulationStore {
         index_key(&self.key_prefix)
     }
 
+    /// The sorted set holding every live simulation id, scored by creation.
+    #[must_use]
+    #[inline]
+    fn created_index_key(&self) -> String {
+        created_index_key(&self.key_prefix)
+    }
+
+    /// The set holding every pinned simulation id.
+    #[must_use]
+    #[inline]
+    fn pinned_key(&self) -> String {
+        pinned_key(&self.key_prefix)
+    }
+
+    /// The set recording which facet sets a simulation is in.
+    #[must_use]
+    #[inline]
+    fn facets_key(&self, id: Uuid) -> String {
+        facets_key(&self.key_prefix, id)
+    }
+
+    /// The TTL a simulation is written with, in seconds: its own retention
+    /// when it chose one, the store's otherwise, and `0` — no expiry — when it
+    /// is pinned.
+    #[must_use]
+    fn ttl_secs(&self, simulation: &SessionV2) -> u64 {
+        if simulation.pinned {
+            0
+        } else {
+            simulation.retention_secs.unwrap_or(self.retention_secs)
+        }
+    }
+
     /// The retention window applied to stored simulations, in seconds.
     #[must_use]
     pub fn retention_secs(&self) -> u64 {
@@ -260,6 +696,32 @@ impl InRedisSimulationStore {
         }
     }
 
+    /// Runs [`DELETE_SCRIPT`], at `expected_version` when there is one.
+    async fn delete_at(&self, id: Uuid, expected_version: Option<u64>) -> Result<bool, ChainError> {
+        let mut conn = self.client.connection_manager();
+        let removed: i64 = redis::Script::new(DELETE_SCRIPT)
+            .key(self.simulation_key(id))
+            .key(self.version_key(id))
+            .key(self.index_key())
+            .key(self.created_index_key())
+            .key(self.pinned_key())
+            .key(self.facets_key(id))
+            .arg(id.to_string())
+            .arg(expected_version.map(|v| v.to_string()).unwrap_or_default())
+            .invoke_async(&mut conn)
+            .await
+            .map_err(Self::map_redis_error)?;
+
+        debug!(simulation_id = %id, removed, "Simulation delete result");
+        if removed == -2 {
+            return Err(ChainError::Conflict(format!(
+                "Simulation {id} was modified concurrently (expected version {})",
+                expected_version.unwrap_or_default()
+            )));
+        }
+        Ok(removed > 0)
+    }
+
     /// Serializes a simulation, reporting a failure through the error boundary.
     fn serialize(simulation: &SessionV2) -> Result<String, ChainError> {
         serde_json::to_string(simulation).map_err(|e| {
@@ -309,21 +771,29 @@ impl SimulationStore for InRedisSimulationStore {
             .key(self.simulation_key(id))
             .key(self.version_key(id))
             .key(self.index_key())
+            .key(self.created_index_key())
+            .key(self.pinned_key())
+            .key(self.facets_key(id))
+            .key(simulation_facets(&self.key_prefix, &simulation))
             .arg(json)
             .arg(simulation.version.to_string())
-            .arg(self.retention_secs)
+            .arg(self.ttl_secs(&simulation))
             .arg(id.to_string())
+            .arg(created_score(simulation.created_at))
+            .arg(self.max_pinned)
             .invoke_async(&mut conn)
             .await
             .map_err(Self::map_redis_error)?;
 
-        if created == 1 {
-            debug!(simulation_id = %id, "Simulation created successfully");
-            Ok(())
-        } else {
-            Err(ChainError::AlreadyExists(format!(
+        match created {
+            1 => {
+                debug!(simulation_id = %id, "Simulation created successfully");
+                Ok(())
+            }
+            -1 => Err(super::v2_memory::pinned_quota_reached(self.max_pinned)),
+            _ => Err(ChainError::AlreadyExists(format!(
                 "Simulation with id {id} already exists"
-            )))
+            ))),
         }
     }
 
@@ -344,11 +814,15 @@ impl SimulationStore for InRedisSimulationStore {
             .key(self.simulation_key(id))
             .key(self.version_key(id))
             .key(self.index_key())
+            .key(self.created_index_key())
+            .key(self.facets_key(id))
+            .key(simulation_facets(&self.key_prefix, &simulation))
             .arg(json)
             .arg(expected_version.to_string())
             .arg(simulation.version.to_string())
-            .arg(self.retention_secs)
+            .arg(self.ttl_secs(&simulation))
             .arg(id.to_string())
+            .arg(created_score(simulation.created_at))
             .invoke_async(&mut conn)
             .await
             .map_err(Self::map_redis_error)?;
@@ -361,19 +835,103 @@ impl SimulationStore for InRedisSimulationStore {
     #[instrument(skip(self), level = "debug")]
     async fn delete(&self, id: Uuid) -> Result<bool, ChainError> {
         debug!(simulation_id = %id, "Deleting simulation from Redis");
+        self.delete_at(id, None).await
+    }
 
+    #[instrument(skip(self), level = "debug")]
+    async fn delete_cas(&self, id: Uuid, expected_version: u64) -> Result<bool, ChainError> {
+        debug!(simulation_id = %id, expected_version, "CAS-deleting simulation from Redis");
+        self.delete_at(id, Some(expected_version)).await
+    }
+
+    #[instrument(skip(self), level = "debug")]
+    async fn list(
+        &self,
+        filter: &SimulationFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<SimulationPage, ChainError> {
+        let (min, max) = created_range(filter);
+        let facets = filter_facets(&self.key_prefix, filter);
+        let scratch = format!("{}list:{}", self.key_prefix, Uuid::new_v4());
         let mut conn = self.client.connection_manager();
-        let removed: i64 = redis::Script::new(DELETE_SCRIPT)
-            .key(self.simulation_key(id))
-            .key(self.version_key(id))
-            .key(self.index_key())
-            .arg(id.to_string())
-            .invoke_async(&mut conn)
-            .await
+        let mut skipped = 0usize;
+        let mut simulations = Vec::new();
+        let mut cursor = 0usize;
+
+        // One more than the page is collected so the caller learns whether a
+        // next page exists without a second listing.
+        while simulations.len() <= limit {
+            let ids: Vec<String> = if facets.is_empty() {
+                redis::cmd("ZRANGEBYSCORE")
+                    .arg(self.created_index_key())
+                    .arg(&min)
+                    .arg(&max)
+                    .arg("LIMIT")
+                    .arg(cursor)
+                    .arg(LIST_BATCH)
+                    .query_async(&mut conn)
+                    .await
+            } else {
+                redis::Script::new(LIST_SCRIPT)
+                    .key(&scratch)
+                    .key(&facets)
+                    .arg(&min)
+                    .arg(&max)
+                    .arg(cursor)
+                    .arg(LIST_BATCH)
+                    .invoke_async(&mut conn)
+                    .await
+            }
             .map_err(Self::map_redis_error)?;
+            if ids.is_empty() {
+                break;
+            }
+            cursor += ids.len();
+
+            let keys: Vec<String> = ids
+                .iter()
+                .map(|id| format!("{}{id}", self.key_prefix))
+                .collect();
+            let documents: Vec<Option<String>> = redis::cmd("MGET")
+                .arg(&keys)
+                .query_async(&mut conn)
+                .await
+                .map_err(Self::map_redis_error)?;
+
+            // A missing document expired on its TTL ahead of the next cleanup
+            // pass, which will drop its index entry; a corrupt one would fail
+            // `get` just the same. Neither is worth failing the listing over.
+            for (id, document) in ids.iter().zip(documents) {
+                let Some(json) = document else { continue };
+                let simulation = match serde_json::from_str::<SessionV2>(&json) {
+                    Ok(simulation) => simulation,
+                    Err(e) => {
+                        error!(simulation_id = %id, error = %e, "Skipping unreadable simulation in listing");
+                        continue;
+                    }
+                };
+                if !filter.matches(&simulation) {
+                    continue;
+                }
+                if skipped < offset {
+                    skipped += 1;
+                } else if simulations.len() <= limit {
+                    simulations.push(simulation);
+                }
+            }
+        }
 
-        debug!(simulation_id = %id, removed, "Simulation delete result");
-        Ok(removed > 0)
+        let next_offset = if simulations.len() > limit {
+            simulations.truncate(limit);
+            Some(offset.saturating_add(limit))
+        } else {
+            None
+        };
+        Ok(SimulationPage {
+            simulations,
+            next_offset,
+        })
     }
 
     #[instrument(skip(self), level = "debug")]
@@ -381,6 +939,7 @@ impl SimulationStore for InRedisSimulationStore {
         let mut conn = self.client.connection_manager();
         let reaped: Vec<String> = redis::Script::new(CLEANUP_SCRIPT)
             .key(self.index_key())
+            .key(self.created_index_key())
             .arg(&self.key_prefix)
             .arg(CLEANUP_BATCH)
             .invoke_async(&mut conn)
@@ -459,6 +1018,73 @@ mod tests {
             index_key(DEFAULT_V2_KEY_PREFIX),
             "optionchain:simulation:v2:index"
         );
+        assert_eq!(
+            created_index_key(DEFAULT_V2_KEY_PREFIX),
+            "optionchain:simulation:v2:created"
+        );
+        assert_eq!(
+            pinned_key(DEFAULT_V2_KEY_PREFIX),
+            "optionchain:simulation:v2:pinned"
+        );
+        assert_eq!(
+            facets_key(DEFAULT_V2_KEY_PREFIX, id),
+            "optionchain:simulation:v2:00000000-0000-0000-0000-000000000000:facets"
+        );
+        assert_eq!(
+            symbol_key(DEFAULT_V2_KEY_PREFIX, "SPX"),
+            "optionchain:simulation:v2:symbol:SPX"
+        );
+        assert_eq!(
+            state_key(DEFAULT_V2_KEY_PREFIX, SessionState::InProgress),
+            "optionchain:simulation:v2:state:InProgress"
+        );
+        assert_eq!(
+            tag_key(DEFAULT_V2_KEY_PREFIX, "batch-7"),
+            "optionchain:simulation:v2:tag:batch-7"
+        );
+    }
+
+    /// A filter names the facet set of each condition it sets, and none when
+    /// it only bounds creation time — which the creation index serves alone.
+    #[test]
+    fn test_filter_facets_name_each_condition() {
+        let filter = SimulationFilter {
+            symbol: Some("SPX".to_string()),
+            state: Some(SessionState::Completed),
+            tags: vec!["a".to_string(), "b".to_string()],
+            ..SimulationFilter::default()
+        };
+        assert_eq!(
+            filter_facets("p:", &filter),
+            vec!["p:symbol:SPX", "p:state:Completed", "p:tag:a", "p:tag:b"]
+        );
+
+        let created_only = SimulationFilter {
+            created_after: Some(UNIX_EPOCH),
+            ..SimulationFilter::default()
+        };
+        assert!(filter_facets("p:", &created_only).is_empty());
+    }
+
+    /// The creation range only ever widens the filter's bounds, so narrowing
+    /// in Redis cannot drop a simulation the exact check would have kept.
+    #[test]
+    fn test_created_range_widens_to_whole_microseconds() {
+        use std::time::Duration;
+
+        let after = UNIX_EPOCH + Duration::from_nanos(1_500);
+        let before = UNIX_EPOCH + Duration::from_nanos(3_500);
+        let filter = SimulationFilter {
+            created_after: Some(after),
+            created_before: Some(before),
+            ..SimulationFilter::default()
+        };
+
+        assert_eq!(created_range(&filter), ("1".to_string(), "4".to_string()));
+        assert_eq!(
+            created_range(&SimulationFilter::default()),
+            ("-inf".to_string(), "+inf".to_string())
+        );
     }
 
     /// The v2 prefix cannot collide with v1's key space.
@@ -550,6 +1176,9 @@ mod live_tests {
             smile_curve: None,
             spread: Some(0.02),
             seed: Some(seed),
+            tags: Vec::new(),
+            retention_seconds: None,
+            pinned: false,
         };
 
         let parameters =
@@ -709,6 +1338,36 @@ mod live_tests {
         assert!(score.is_none(), "the index entry must be gone");
     }
 
+    /// A conditional delete at a stale revision is a conflict and leaves every
+    /// key in place; at the stored revision it deletes.
+    #[test]
+    #[ignore = "requires a live Redis matching REDIS_*; run with -- --ignored"]
+    async fn test_delete_cas_honours_the_revision() {
+        let store = store("delete-cas", 60).await;
+        let sim = simulation(6);
+        store.create(sim.clone()).await.expect("must create");
+
+        let stale = store.delete_cas(sim.id, sim.version + 1).await;
+        assert!(matches!(stale, Err(ChainError::Conflict(_))), "{stale:?}");
+        store
+            .get(sim.id)
+            .await
+            .expect("a refused delete must keep it");
+
+        assert!(
+            store
+                .delete_cas(sim.id, sim.version)
+                .await
+                .expect("must delete")
+        );
+        assert!(
+            !store
+                .delete_cas(sim.id, sim.version)
+                .await
+                .expect("a second delete is fine")
+        );
+    }
+
     /// Cleanup reports the ids whose deadline has passed, and leaves a live
     /// simulation alone — the property #48's cache eviction depends on.
     #[test]
@@ -790,4 +1449,196 @@ mod live_tests {
 
         store.delete(sim.id).await.expect("must delete");
     }
+
+    /// Listing pages through the creation index in creation order, applies
+    /// the filter to the documents, and forgets a deleted simulation.
+    #[test]
+    #[ignore = "requires a live Redis matching REDIS_*; run with -- --ignored"]
+    async fn test_list_pages_in_creation_order_and_filters() {
+        let store = store("list", 60).await;
+        let mut created = Vec::new();
+        for seed in 11..14 {
+            let mut sim = simulation(seed);
+            sim.tags = vec![format!("seed-{seed}"), "suite".to_string()];
+            store.create(sim.clone()).await.expect("must create");
+            created.push(sim);
+        }
+
+        let first = store
+            .list(&SimulationFilter::default(), 0, 2)
+            .await
+            .expect("must list");
+        let ids: Vec<Uuid> = first.simulations.iter().map(|sim| sim.id).collect();
+        assert_eq!(ids, vec![created[0].id, created[1].id]);
+        assert_eq!(first.next_offset, Some(2));
+
+        let second = store
+            .list(&SimulationFilter::default(), 2, 2)
+            .await
+            .expect("must list");
+        assert_eq!(second.simulations.len(), 1);
+        assert_eq!(second.next_offset, None);
+
+        let tagged = SimulationFilter {
+            tags: vec!["seed-12".to_string()],
+            ..SimulationFilter::default()
+        };
+        let page = store.list(&tagged, 0, 10).await.expect("must list");
+        assert_eq!(page.simulations.len(), 1);
+        assert_eq!(page.simulations[0].id, created[1].id);
+
+        store.delete(created[1].id).await.expect("must delete");
+        let page = store.list(&tagged, 0, 10).await.expect("must list");
+        assert!(
+            page.simulations.is_empty(),
+            "a deleted simulation must not be listed"
+        );
+
+        for sim in &created {
+            store.delete(sim.id).await.expect("must delete");
+        }
+    }
+
+    /// A filtered listing reads the facet sets, which follow a simulation
+    /// through a change of state and tags and forget it on delete.
+    #[test]
+    #[ignore = "requires a live Redis matching REDIS_*; run with -- --ignored"]
+    async fn test_list_filters_through_the_facet_sets() {
+        let store = store("facets", 60).await;
+        let mut sim = simulation(16);
+        sim.tags = vec!["before".to_string()];
+        store.create(sim.clone()).await.expect("must create");
+
+        let by = |state: SessionState, tag: &str| SimulationFilter {
+            symbol: Some(sim.parameters.symbol.clone()),
+            state: Some(state),
+            tags: vec![tag.to_string()],
+            ..SimulationFilter::default()
+        };
+        let listed = |page: SimulationPage| -> Vec<Uuid> {
+            page.simulations.iter().map(|sim| sim.id).collect()
+        };
+
+        let page = store
+            .list(&by(sim.state, "before"), 0, 10)
+            .await
+            .expect("must list");
+        assert_eq!(listed(page), vec![sim.id]);
+
+        let mut moved = sim.clone();
+        moved.state = SessionState::InProgress;
+        moved.tags = vec!["after".to_string()];
+        let expected = moved.bump_version().expect("must bump");
+        store.save_cas(moved, expected).await.expect("must save");
+
+        let page = store
+            .list(&by(sim.state, "before"), 0, 10)
+            .await
+            .expect("must list");
+        assert!(listed(page).is_empty(), "the old sets must be left");
+        let page = store
+            .list(&by(SessionState::InProgress, "after"), 0, 10)
+            .await
+            .expect("must list");
+        assert_eq!(listed(page), vec![sim.id]);
+
+        store.delete(sim.id).await.expect("must delete");
+        let mut conn = store.client.connection_manager();
+        let left: usize = redis::cmd("ZCARD")
+            .arg(tag_key(&store.key_prefix, "after"))
+            .query_async(&mut conn)
+            .await
+            .expect("must query");
+        assert_eq!(left, 0, "a deleted simulation must leave its facet sets");
+        assert!(
+            !store
+                .client
+                .exists(&store.facets_key(sim.id))
+                .await
+                .expect("must query"),
+            "the facet record must be gone"
+        );
+    }
+
+    /// A simulation stored before the listing indexes existed is invisible to
+    /// every listing until `ensure_indexes` indexes it, once.
+    #[test]
+    #[ignore = "requires a live Redis matching REDIS_*; run with -- --ignored"]
+    async fn test_ensure_indexes_backfills_a_simulation_stored_before_them() {
+        let store = store("backfill", 60).await;
+        let mut sim = simulation(17);
+        sim.tags = vec!["legacy".to_string()];
+        store.create(sim.clone()).await.expect("must create");
+
+        // Strip the indexes the baseline never wrote.
+        let mut conn = store.client.connection_manager();
+        let mut unindex = redis::pipe();
+        unindex
+            .cmd("ZREM")
+            .arg(store.created_index_key())
+            .arg(sim.id.to_string());
+        for facet in simulation_facets(&store.key_prefix, &sim) {
+            unindex.cmd("ZREM").arg(facet).arg(sim.id.to_string());
+        }
+        unindex.cmd("DEL").arg(store.facets_key(sim.id));
+        unindex
+            .query_async::<()>(&mut conn)
+            .await
+            .expect("must unindex");
+
+        let tagged = SimulationFilter {
+            tags: vec!["legacy".to_string()],
+            ..SimulationFilter::default()
+        };
+        let unfiltered = store
+            .list(&SimulationFilter::default(), 0, 10)
+            .await
+            .expect("must list");
+        assert!(unfiltered.simulations.is_empty(), "the gap must be real");
+
+        assert_eq!(store.ensure_indexes().await.expect("must index"), 1);
+        assert_eq!(
+            store.ensure_indexes().await.expect("must index"),
+            0,
+            "indexing is idempotent"
+        );
+
+        for filter in [SimulationFilter::default(), tagged] {
+            let page = store.list(&filter, 0, 10).await.expect("must list");
+            let ids: Vec<Uuid> = page.simulations.iter().map(|sim| sim.id).collect();
+            assert_eq!(ids, vec![sim.id]);
+        }
+
+        store.delete(sim.id).await.expect("must delete");
+    }
+
+    /// A pinned simulation is written without a TTL, stays out of the deadline
+    /// index, and counts against the quota until it is deleted.
+    #[test]
+    #[ignore = "requires a live Redis matching REDIS_*; run with -- --ignored"]
+    async fn test_pinned_simulation_never_expires_and_is_quota_bound() {
+        let store = store("pinned", 1).await.with_max_pinned(1);
+        let mut pinned = simulation(14);
+        pinned.pinned = true;
+        let mut over_quota = simulation(15);
+        over_quota.pinned = true;
+
+        store.create(pinned.clone()).await.expect("must create");
+        match store.create(over_quota.clone()).await {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("expected Conflict, got {other:?}"),
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        let expired = store.cleanup().await.expect("must clean up");
+        assert!(!expired.contains(&pinned.id));
+        assert!(store.get(pinned.id).await.is_ok());
+
+        store.delete(pinned.id).await.expect("must delete");
+        store
+            .create(over_quota.clone())
+            .await
+            .expect("the freed slot must be reusable");
+        store.delete(over_quota.id).await.expect("must delete");
+    }
 }
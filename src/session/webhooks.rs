@@ -0,0 +1,1200 @@
+//! Webhook notifications for v2 simulation lifecycle events.
+//!
+//! A webhook is a URL the service `POST`s to when something happens to a
+//! simulation: it **completes** (the advance that serves its last step), it
+//! **expires** (the retention sweep reaps it), it is **deleted**, or — when the
+//! registration asks for it — every `every_n_steps` advances. A registration is
+//! either bound to one simulation or global, in which case it hears about every
+//! simulation. That is what lets a pipeline learn that a batch has finished
+//! instead of polling each simulation's state.
+//!
+//! # Off the request path
+//!
+//! Exactly like snapshot filing, delivery never runs on the clock of the call
+//! that caused it. An event is matched against the registry, serialised once,
+//! and handed to a bounded queue; one dispatcher drains it and runs at most
+//! [`MAX_CONCURRENT_DELIVERIES`] deliveries at a time. A full queue **drops**
+//! the delivery with a `WARN`, for the same reason the snapshot queue does: a
+//! receiver that is down must not become a memory leak in the service calling
+//! it. Nothing about an advance, a delete or a sweep waits on a receiver.
+//!
+//! # Retries and signatures
+//!
+//! A delivery is retried with a doubling backoff until it is acknowledged with
+//! a `2xx`, up to `OCS_V2_WEBHOOK_MAX_ATTEMPTS` attempts. A `4xx` other than
+//! `408` and `429` is final: the receiver understood the request and refused
+//! it, and asking again will not change its mind. So is a `3xx`: redirects are
+//! not followed, since a signed delivery is meant for the receiver that was
+//! registered and no other host.
+//!
+//! Every attempt carries:
+//!
+//! - `X-OCS-Event` — `completed`, `expired`, `deleted` or `step`;
+//! - `X-OCS-Delivery` — the event's id, the same on every retry, so a receiver
+//!   can discard a duplicate;
+//! - `X-OCS-Timestamp` — Unix seconds at the attempt;
+//! - `X-OCS-Signature` — `sha256=` and the hex HMAC-SHA256, keyed by the
+//!   registration's `signing_key`, of `"{timestamp}.{body}"`.
+//!
+//! Signing the timestamp with the body is what lets a receiver refuse a
+//! replayed request: it recomputes the HMAC and rejects a timestamp that is
+//! too old.
+//!
+//! # Where a delivery may go
+//!
+//! Registration is open to any caller, and a global registration hears every
+//! simulation's symbol and tags, so a receiver on a loopback, private,
+//! link-local or otherwise internal address is refused unless the operator
+//! opts in with `OCS_V2_WEBHOOK_ALLOW_PRIVATE_TARGETS`. Without that, the
+//! service would be a way to `POST` into its own network — a cloud metadata
+//! endpoint at `169.254.169.254` included.
+//!
+//! The check runs twice. Registration refuses an address literal, and a host
+//! name that resolves to a refused address, so a mistake is reported to the
+//! caller. Every delivery then resolves the host again through a resolver that
+//! drops refused addresses: a name can be re-pointed after it was registered,
+//! and only the check at connect time is one it cannot outrun. That is also why
+//! deliveries ignore `HTTP_PROXY` and `HTTPS_PROXY` — through a proxy, the
+//! only name resolved here is the proxy's. An IPv6 address that carries an
+//! IPv4 one — mapped, NAT64 or 6to4 — is judged by the address it carries.
+//!
+//! # What a registration is not
+//!
+//! It is **process-local and not persisted**, like a playback: the registry
+//! lives in the manager, so a restart forgets every registration and each
+//! replica of a multi-replica deployment notifies only its own. Deliveries are
+//! not ordered either — each carries its `occurred_at` and `current_step` so a
+//! receiver can order them itself.
+
+use crate::session::SessionV2;
+use crate::session::model::SessionState;
+use crate::utils::ChainError;
+use chrono::{DateTime, SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Semaphore, mpsc};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// How many deliveries may be waiting for the dispatcher.
+///
+/// A delivery is a few hundred bytes, so the bound is about what a dead
+/// receiver may pin rather than about memory pressure.
+const WEBHOOK_QUEUE_DEPTH: usize = 4_096;
+
+/// How many deliveries may be in flight at once, retries included.
+///
+/// A receiver that answers slowly holds one of these for every attempt's
+/// timeout, so this is what keeps a few slow receivers from opening an
+/// unbounded number of connections.
+pub(crate) const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// The wait before the first retry; each further retry waits twice as long.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest wait between two attempts.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The shortest signing key a registration may use.
+///
+/// Sixteen characters is the least that makes guessing it harder than
+/// guessing the HMAC itself.
+const MIN_SIGNING_KEY_LEN: usize = 16;
+
+/// The longest signing key a registration may use.
+const MAX_SIGNING_KEY_LEN: usize = 256;
+
+/// The longest URL a registration may name.
+const MAX_URL_LEN: usize = 2_048;
+
+/// Which lifecycle event a delivery reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WebhookEvent {
+    /// The advance that served the last step.
+    Completed,
+    /// The retention sweep reaped the simulation.
+    Expired,
+    /// A client deleted the simulation.
+    Deleted,
+    /// The simulation was advanced a multiple of `every_n_steps` times.
+    Step,
+}
+
+impl WebhookEvent {
+    /// The event's name, as sent in `X-OCS-Event` and the body.
+    #[must_use]
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::Completed => "completed",
+            WebhookEvent::Expired => "expired",
+            WebhookEvent::Deleted => "deleted",
+            WebhookEvent::Step => "step",
+        }
+    }
+}
+
+/// What a client asks to register.
+#[derive(Clone)]
+pub(crate) struct WebhookSpec {
+    /// Where to deliver, `http` or `https`.
+    pub(crate) url: String,
+    /// The HMAC key the deliveries are signed with.
+    pub(crate) signing_key: String,
+    /// Also deliver a `step` event every this many advances.
+    pub(crate) every_n_steps: Option<usize>,
+}
+
+/// One registered webhook.
+///
+/// The signing key is deliberately not readable from outside this module: it
+/// is accepted once, at registration, and never echoed back — not even by
+/// `Debug`.
+#[derive(Clone)]
+pub(crate) struct Webhook {
+    /// The registration's identifier.
+    pub(crate) id: Uuid,
+    /// Where deliveries go.
+    pub(crate) url: String,
+    /// The simulation it is bound to; `None` for a global registration.
+    pub(crate) simulation: Option<Uuid>,
+    /// The `step` event cadence, when one was asked for.
+    pub(crate) every_n_steps: Option<NonZeroUsize>,
+    /// When it was registered.
+    pub(crate) created_at: SystemTime,
+    signing_key: Arc<str>,
+}
+
+impl fmt::Debug for Webhook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Webhook")
+            .field("id", &self.id)
+            .field("url", &self.url)
+            .field("simulation", &self.simulation)
+            .field("every_n_steps", &self.every_n_steps)
+            .field("created_at", &self.created_at)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Webhook {
+    /// Whether this registration hears about `simulation`.
+    fn covers(&self, simulation: Uuid) -> bool {
+        self.simulation.is_none_or(|bound| bound == simulation)
+    }
+}
+
+/// How a delivery is attempted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DeliveryPolicy {
+    /// Attempts in total, the first included.
+    pub(crate) attempts: usize,
+    /// How long one attempt may take.
+    pub(crate) timeout: Duration,
+    /// The wait before the first retry.
+    pub(crate) backoff: Duration,
+    /// Whether a receiver may be on a loopback, private or link-local address.
+    pub(crate) allow_private_targets: bool,
+}
+
+/// One signed `POST` waiting to be made.
+struct Delivery {
+    url: String,
+    signing_key: Arc<str>,
+    event: WebhookEvent,
+    event_id: Uuid,
+    body: Arc<Vec<u8>>,
+}
+
+/// The body every delivery carries.
+#[derive(Debug, Serialize)]
+struct EventBody<'a> {
+    /// The event's id, also sent as `X-OCS-Delivery`.
+    id: Uuid,
+    event: WebhookEvent,
+    occurred_at: String,
+    simulation_id: Uuid,
+    /// Absent for `expired` and `deleted`: the simulation is gone by the time
+    /// they are sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_step: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_steps: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<&'a [String]>,
+}
+
+/// The webhook registry and the queue in front of its deliveries.
+pub(crate) struct Webhooks {
+    registry: Mutex<Vec<Webhook>>,
+    max_registrations: usize,
+    policy: DeliveryPolicy,
+    /// Started on the first registration rather than with the manager, so a
+    /// manager built outside a runtime — or one nobody registers a webhook
+    /// with — never spawns anything.
+    queue: OnceLock<mpsc::Sender<Delivery>>,
+}
+
+impl Webhooks {
+    /// Creates an empty registry.
+    #[must_use]
+    pub(crate) fn new(max_registrations: usize, policy: DeliveryPolicy) -> Self {
+        Self {
+            registry: Mutex::new(Vec::new()),
+            max_registrations,
+            policy,
+            queue: OnceLock::new(),
+        }
+    }
+
+    /// The default policy for `attempts` attempts of at most `timeout` each,
+    /// to public receivers only unless `allow_private_targets`.
+    #[must_use]
+    pub(crate) fn policy(
+        attempts: usize,
+        timeout: Duration,
+        allow_private_targets: bool,
+    ) -> DeliveryPolicy {
+        DeliveryPolicy {
+            attempts,
+            timeout,
+            backoff: RETRY_BACKOFF,
+            allow_private_targets,
+        }
+    }
+
+    /// Registers a webhook, bound to `simulation` or global.
+    ///
+    /// Must be called on a Tokio runtime: the first registration starts the
+    /// dispatcher.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Validation`] naming `url`, `signing_key` or
+    /// `every_n_steps`, and [`ChainError::Conflict`] when the registry is at
+    /// `OCS_V2_MAX_WEBHOOKS`. A `url` on a refused address is a validation
+    /// error too.
+    pub(crate) async fn register(
+        &self,
+        simulation: Option<Uuid>,
+        spec: WebhookSpec,
+    ) -> Result<Webhook, ChainError> {
+        let url = validate_url(&spec.url)?;
+        if !self.policy.allow_private_targets {
+            validate_target(&url, self.policy.timeout).await?;
+        }
+        let key_len = spec.signing_key.chars().count();
+        if !(MIN_SIGNING_KEY_LEN..=MAX_SIGNING_KEY_LEN).contains(&key_len) {
+            return Err(ChainError::Validation {
+                field: "signing_key".to_string(),
+                reason: format!(
+                    "must be {MIN_SIGNING_KEY_LEN} to {MAX_SIGNING_KEY_LEN} characters, got {key_len}"
+                ),
+            });
+        }
+        let every_n_steps = spec
+            .every_n_steps
+            .map(|every| {
+                NonZeroUsize::new(every).ok_or_else(|| ChainError::Validation {
+                    field: "every_n_steps".to_string(),
+                    reason: "must be at least 1".to_string(),
+                })
+            })
+            .transpose()?;
+
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            url: spec.url,
+            simulation,
+            every_n_steps,
+            created_at: SystemTime::now(),
+            signing_key: Arc::from(spec.signing_key),
+        };
+
+        {
+            let mut registry = self.lock();
+            if registry.len() >= self.max_registrations {
+                return Err(ChainError::Conflict(format!(
+                    "the webhook limit ({}) is reached; remove a webhook first",
+                    self.max_registrations
+                )));
+            }
+            registry.push(webhook.clone());
+        }
+        self.queue();
+        Ok(webhook)
+    }
+
+    /// The registrations bound to `simulation`, or the global ones for `None`.
+    #[must_use]
+    pub(crate) fn list(&self, simulation: Option<Uuid>) -> Vec<Webhook> {
+        self.lock()
+            .iter()
+            .filter(|webhook| webhook.simulation == simulation)
+            .cloned()
+            .collect()
+    }
+
+    /// Removes a registration. Returns whether there was one.
+    pub(crate) fn remove(&self, id: Uuid) -> bool {
+        let mut registry = self.lock();
+        let before = registry.len();
+        registry.retain(|webhook| webhook.id != id);
+        registry.len() != before
+    }
+
+    /// Drops every registration bound to a simulation that no longer exists.
+    pub(crate) fn forget(&self, simulation: Uuid) {
+        self.lock()
+            .retain(|webhook| webhook.simulation != Some(simulation));
+    }
+
+    /// Reports a committed advance: a `step` to every registration whose
+    /// cadence it lands on, and `completed` when it served the last step.
+    pub(crate) fn advanced(&self, simulation: &SessionV2) {
+        self.notify(WebhookEvent::Step, simulation.id, Some(simulation));
+        if simulation.state == SessionState::Completed {
+            self.notify(WebhookEvent::Completed, simulation.id, Some(simulation));
+        }
+    }
+
+    /// Queues `event` for every registration that covers `simulation_id`.
+    ///
+    /// `simulation` is the document when it still exists, and fills in the
+    /// body's progress fields.
+    pub(crate) fn notify(
+        &self,
+        event: WebhookEvent,
+        simulation_id: Uuid,
+        simulation: Option<&SessionV2>,
+    ) {
+        let targets: Vec<(String, Arc<str>)> = self
+            .lock()
+            .iter()
+            .filter(|webhook| webhook.covers(simulation_id))
+            .filter(|webhook| match event {
+                WebhookEvent::Step => match (webhook.every_n_steps, simulation) {
+                    (Some(every), Some(simulation)) => {
+                        simulation.current_step > 0 && simulation.current_step % every.get() == 0
+                    }
+                    _ => false,
+                },
+                _ => true,
+            })
+            .map(|webhook| (webhook.url.clone(), Arc::clone(&webhook.signing_key)))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+
+        let event_id = Uuid::new_v4();
+        let body = EventBody {
+            id: event_id,
+            event,
+            occurred_at: DateTime::<Utc>::from(SystemTime::now())
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            simulation_id,
+            symbol: simulation.map(|simulation| simulation.parameters.symbol.as_str()),
+            current_step: simulation.map(|simulation| simulation.current_step),
+            total_steps: simulation.map(|simulation| simulation.total_steps),
+            tags: simulation.map(|simulation| simulation.tags.as_slice()),
+        };
+        let body = match serde_json::to_vec(&body) {
+            Ok(body) => Arc::new(body),
+            Err(error) => {
+                warn!(%error, "could not encode a webhook event");
+                return;
+            }
+        };
+
+        let queue = self.queue();
+        for (url, signing_key) in targets {
+            let delivery = Delivery {
+                url,
+                signing_key,
+                event,
+                event_id,
+                body: Arc::clone(&body),
+            };
+            if let Err(error) = queue.try_send(delivery) {
+                warn!(
+                    simulation_id = %simulation_id,
+                    event = event.as_str(),
+                    error = %error,
+                    "The webhook queue is full; the delivery was dropped"
+                );
+            }
+        }
+    }
+
+    /// The queue, starting its dispatcher on first use.
+    fn queue(&self) -> &mpsc::Sender<Delivery> {
+        self.queue.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel(WEBHOOK_QUEUE_DEPTH);
+            tokio::spawn(dispatch(receiver, self.policy));
+            sender
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Webhook>> {
+        match self.registry.lock() {
+            Ok(registry) => registry,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Rejects a URL that is not an absolute `http` or `https` one, and returns it
+/// parsed.
+fn validate_url(url: &str) -> Result<reqwest::Url, ChainError> {
+    let invalid = |reason: String| ChainError::Validation {
+        field: "url".to_string(),
+        reason,
+    };
+    if url.len() > MAX_URL_LEN {
+        return Err(invalid(format!("must be at most {MAX_URL_LEN} characters")));
+    }
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|error| invalid(format!("must be an absolute URL, got {url:?}: {error}")))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(invalid(format!(
+            "must use http or https, got {:?}",
+            parsed.scheme()
+        )));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("must name a host".to_string()));
+    }
+    Ok(parsed)
+}
+
+/// Rejects a URL whose host is, or resolves to, an address deliveries may not
+/// go to.
+///
+/// The resolution is bounded by the delivery timeout. A name that does not
+/// resolve in time is let through: it may well resolve by the time there is
+/// something to deliver, and the resolver every delivery goes through applies
+/// the same rule then.
+async fn validate_target(url: &reqwest::Url, timeout: Duration) -> Result<(), ChainError> {
+    let refused = |address: IpAddr| ChainError::Validation {
+        field: "url".to_string(),
+        reason: format!(
+            "must not point at a loopback, private or link-local address, got {address}"
+        ),
+    };
+    let Some(host) = url.host_str() else {
+        return Ok(());
+    };
+    // An IPv6 literal keeps its brackets in the URL.
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(address) = literal.parse::<IpAddr>() {
+        return if is_public(address) {
+            Ok(())
+        } else {
+            Err(refused(address))
+        };
+    }
+
+    let port = url.port_or_known_default().unwrap_or(443);
+    match tokio::time::timeout(timeout, tokio::net::lookup_host((host, port))).await {
+        Ok(Ok(mut addresses)) => match addresses.find(|address| !is_public(address.ip())) {
+            Some(address) => Err(refused(address.ip())),
+            None => Ok(()),
+        },
+        Ok(Err(error)) => {
+            debug!(%error, host, "could not resolve a webhook host at registration");
+            Ok(())
+        }
+        Err(_) => {
+            debug!(host, "timed out resolving a webhook host at registration");
+            Ok(())
+        }
+    }
+}
+
+/// Whether a delivery may connect to `address`.
+///
+/// Refuses what is reachable only from inside the host or its network:
+/// unspecified, loopback, private, carrier-grade NAT, link-local (the cloud
+/// metadata endpoints live there), multicast and broadcast — and the same for
+/// an IPv4 address wrapped in IPv6 (see [`embedded_ipv4`]).
+fn is_public(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_public_v4(address),
+        IpAddr::V6(address) => match embedded_ipv4(address) {
+            Some(embedded) => is_public_v4(embedded),
+            None => is_public_v6(address),
+        },
+    }
+}
+
+/// The IPv4 address an IPv6 address stands in for, if any.
+///
+/// An IPv4-mapped address (`::ffff:0:0/96`) is the IPv4 address itself. A
+/// NAT64 address (`64:ff9b::/96`) and a 6to4 address (`2002::/16`) are routed
+/// by a translator or relay to the IPv4 address they carry, so they reach
+/// exactly what it would.
+fn embedded_ipv4(address: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(mapped) = address.to_ipv4_mapped() {
+        return Some(mapped);
+    }
+    let segments = address.segments();
+    let octets = address.octets();
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        return Some(Ipv4Addr::new(
+            octets[12], octets[13], octets[14], octets[15],
+        ));
+    }
+    if segments[0] == 0x2002 {
+        return Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5]));
+    }
+    None
+}
+
+fn is_public_v4(address: Ipv4Addr) -> bool {
+    let [first, second, ..] = address.octets();
+    let shared = first == 100 && (64..128).contains(&second);
+    !(address.is_unspecified()
+        || address.is_loopback()
+        || address.is_private()
+        || address.is_link_local()
+        || address.is_multicast()
+        || address.is_broadcast()
+        || shared
+        || first == 0)
+}
+
+fn is_public_v6(address: Ipv6Addr) -> bool {
+    let segments = address.segments();
+    let first = segments[0];
+    let unique_local = first & 0xfe00 == 0xfc00;
+    let link_local = first & 0xffc0 == 0xfe80;
+    // The local-use NAT64 prefix, `64:ff9b:1::/48`, places the IPv4 address
+    // wherever the operator's translator chose, so it cannot be checked.
+    let local_nat64 = segments[..3] == [0x64, 0xff9b, 1];
+    !(address.is_unspecified()
+        || address.is_loopback()
+        || address.is_multicast()
+        || unique_local
+        || link_local
+        || local_nat64)
+}
+
+/// A resolver that hands the client only public addresses, so a host name
+/// re-pointed at an internal one after registration still cannot be reached.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+                .await?
+                .filter(|address| is_public(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("{host} resolves to no public address").into());
+            }
+            let addresses: reqwest::dns::Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+/// Drains the queue, running at most [`MAX_CONCURRENT_DELIVERIES`] at once.
+async fn dispatch(mut receiver: mpsc::Receiver<Delivery>, policy: DeliveryPolicy) {
+    let client = match client(policy) {
+        Ok(client) => client,
+        Err(error) => {
+            warn!(%error, "could not build the webhook client; no webhook will be delivered");
+            return;
+        }
+    };
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES));
+
+    while let Some(delivery) = receiver.recv().await {
+        let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+            return;
+        };
+        let client = client.clone();
+        tokio::spawn(async move {
+            deliver(&client, &delivery, policy).await;
+            drop(permit);
+        });
+    }
+}
+
+/// The client every delivery is made with.
+///
+/// It follows no redirects. A delivery is signed for the receiver that was
+/// registered, and following a `3xx` would hand the signed body — and every
+/// later one — to whatever host the receiver names.
+///
+/// Unless the operator allows private receivers, it also resolves host names
+/// through [`PublicResolver`], and ignores `HTTP_PROXY` and `HTTPS_PROXY`: a
+/// proxied request resolves only the proxy's name here, and the proxy would
+/// then connect to an internal receiver on this service's behalf.
+fn client(policy: DeliveryPolicy) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(policy.timeout)
+        .redirect(reqwest::redirect::Policy::none());
+    if policy.allow_private_targets {
+        builder.build()
+    } else {
+        builder
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+    }
+}
+
+/// Makes one delivery, retrying as the policy allows. Returns whether it was
+/// acknowledged.
+async fn deliver(client: &reqwest::Client, delivery: &Delivery, policy: DeliveryPolicy) -> bool {
+    let mut backoff = policy.backoff;
+    for attempt in 1..=policy.attempts {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let signature = match sign(&delivery.signing_key, timestamp, &delivery.body) {
+            Ok(signature) => signature,
+            Err(error) => {
+                warn!(%error, url = %delivery.url, "could not sign a webhook delivery");
+                return false;
+            }
+        };
+
+        let result = client
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-OCS-Event", delivery.event.as_str())
+            .header("X-OCS-Delivery", delivery.event_id.to_string())
+            .header("X-OCS-Timestamp", timestamp.to_string())
+            .header("X-OCS-Signature", format!("sha256={signature}"))
+            .body(delivery.body.as_ref().clone())
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                debug!(url = %delivery.url, event = delivery.event.as_str(), attempt, "Delivered a webhook");
+                return true;
+            }
+            Ok(response) if is_final(response.status()) => {
+                warn!(
+                    url = %delivery.url,
+                    event = delivery.event.as_str(),
+                    status = response.status().as_u16(),
+                    "The webhook receiver refused the delivery; it is not retried"
+                );
+                return false;
+            }
+            Ok(response) => debug!(
+                url = %delivery.url,
+                attempt,
+                status = response.status().as_u16(),
+                "A webhook attempt failed"
+            ),
+            Err(error) => debug!(url = %delivery.url, attempt, %error, "A webhook attempt failed"),
+        }
+
+        if attempt < policy.attempts {
+            tokio::time::sleep(backoff).await;
+            backoff = backoff.saturating_mul(2).min(MAX_RETRY_BACKOFF);
+        }
+    }
+
+    warn!(
+        url = %delivery.url,
+        event = delivery.event.as_str(),
+        attempts = policy.attempts,
+        "Gave up on a webhook delivery"
+    );
+    false
+}
+
+/// Whether a response status means "do not ask again".
+///
+/// A redirect is one: it is not followed, and asking again gets the same
+/// answer.
+fn is_final(status: reqwest::StatusCode) -> bool {
+    status.is_redirection()
+        || (status.is_client_error()
+            && status != reqwest::StatusCode::REQUEST_TIMEOUT
+            && status != reqwest::StatusCode::TOO_MANY_REQUESTS)
+}
+
+/// The hex HMAC-SHA256 of `"{timestamp}.{body}"` under `key`.
+///
+/// # Errors
+///
+/// Returns [`ChainError::Internal`] if the key is refused, which HMAC never
+/// does for a key of any length.
+pub(crate) fn sign(key: &str, timestamp: u64, body: &[u8]) -> Result<String, ChainError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .map_err(|error| ChainError::Internal(format!("invalid HMAC key: {error}")))?;
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// A local HTTP receiver that records every delivery, for the tests of this
+/// module and of the manager that drives it.
+#[cfg(test)]
+pub(crate) mod testing {
+    use actix_web::{App, HttpRequest, HttpResponse, web};
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    /// One request the receiver was sent.
+    #[derive(Debug, Clone)]
+    pub(crate) struct Received {
+        /// Its headers, names lowercased.
+        pub(crate) headers: HashMap<String, String>,
+        /// Its body, verbatim.
+        pub(crate) body: Vec<u8>,
+    }
+
+    impl Received {
+        /// The value of header `name`.
+        pub(crate) fn header(&self, name: &str) -> &str {
+            match self.headers.get(&name.to_ascii_lowercase()) {
+                Some(value) => value,
+                None => panic!("the delivery must carry {name}: {:?}", self.headers),
+            }
+        }
+
+        /// The body, parsed.
+        pub(crate) fn json(&self) -> serde_json::Value {
+            match serde_json::from_slice(&self.body) {
+                Ok(body) => body,
+                Err(error) => panic!("the delivery body must be JSON: {error}"),
+            }
+        }
+    }
+
+    /// The receiver and what it has been sent.
+    pub(crate) struct Receiver {
+        server: actix_test::TestServer,
+        received: Arc<Mutex<Vec<Received>>>,
+    }
+
+    impl Receiver {
+        /// Starts a receiver that answers with `statuses` in turn, then `200`.
+        pub(crate) fn start(statuses: Vec<u16>) -> Self {
+            let received = Arc::new(Mutex::new(Vec::new()));
+            let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+            let server = {
+                let received = Arc::clone(&received);
+                actix_test::start(move || {
+                    let received = Arc::clone(&received);
+                    let statuses = Arc::clone(&statuses);
+                    App::new().route(
+                        "/hook",
+                        web::post().to(move |req: HttpRequest, body: web::Bytes| {
+                            let received = Arc::clone(&received);
+                            let statuses = Arc::clone(&statuses);
+                            async move {
+                                let headers = req
+                                    .headers()
+                                    .iter()
+                                    .filter_map(|(name, value)| {
+                                        value.to_str().ok().map(|value| {
+                                            (name.as_str().to_string(), value.to_string())
+                                        })
+                                    })
+                                    .collect();
+                                if let Ok(mut received) = received.lock() {
+                                    received.push(Received {
+                                        headers,
+                                        body: body.to_vec(),
+                                    });
+                                }
+                                let status = statuses
+                                    .lock()
+                                    .ok()
+                                    .and_then(|mut statuses| statuses.pop_front())
+                                    .unwrap_or(200);
+                                // A redirect points back here, so a client that
+                                // followed it would be seen delivering twice.
+                                match actix_web::http::StatusCode::from_u16(status) {
+                                    Ok(status) if status.is_redirection() => {
+                                        HttpResponse::build(status)
+                                            .insert_header(("Location", "/hook"))
+                                            .finish()
+                                    }
+                                    Ok(status) => HttpResponse::build(status).finish(),
+                                    Err(_) => HttpResponse::InternalServerError().finish(),
+                                }
+                            }
+                        }),
+                    )
+                })
+            };
+            Self { server, received }
+        }
+
+        /// The URL to register.
+        pub(crate) fn url(&self) -> String {
+            self.server.url("/hook")
+        }
+
+        /// Everything received so far.
+        pub(crate) fn received(&self) -> Vec<Received> {
+            match self.received.lock() {
+                Ok(received) => received.clone(),
+                Err(poisoned) => poisoned.into_inner().clone(),
+            }
+        }
+
+        /// Waits until at least `count` requests have arrived, and returns them.
+        pub(crate) async fn wait_for(&self, count: usize) -> Vec<Received> {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                let received = self.received();
+                if received.len() >= count {
+                    return received;
+                }
+                if Instant::now() > deadline {
+                    panic!("expected {count} deliveries, got {}", received.len());
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::Receiver;
+    use super::*;
+    use serde_json::json;
+
+    /// A policy fast enough for a test to watch its retries, allowing the
+    /// loopback receiver the tests deliver to.
+    fn fast_policy(attempts: usize) -> DeliveryPolicy {
+        DeliveryPolicy {
+            attempts,
+            timeout: Duration::from_secs(5),
+            backoff: Duration::from_millis(10),
+            allow_private_targets: true,
+        }
+    }
+
+    /// The policy a deployment runs with unless it opts in.
+    fn public_policy() -> DeliveryPolicy {
+        DeliveryPolicy {
+            allow_private_targets: false,
+            ..fast_policy(1)
+        }
+    }
+
+    fn spec(url: &str) -> WebhookSpec {
+        WebhookSpec {
+            url: url.to_string(),
+            signing_key: "a-signing-key-of-some-length".to_string(),
+            every_n_steps: None,
+        }
+    }
+
+    fn validation_field(result: Result<Webhook, ChainError>) -> String {
+        match result {
+            Err(ChainError::Validation { field, .. }) => field,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    /// The signature is the HMAC-SHA256 of `"{timestamp}.{body}"`, and moves
+    /// with the timestamp — which is what makes a replay detectable.
+    #[test]
+    fn test_a_signature_covers_the_timestamp_and_the_body() {
+        let signature = match sign("key-key-key-key-key", 1_700_000_000, b"{\"a\":1}") {
+            Ok(signature) => signature,
+            Err(error) => panic!("the body must sign: {error}"),
+        };
+        let mut mac = match Hmac::<Sha256>::new_from_slice(b"key-key-key-key-key") {
+            Ok(mac) => mac,
+            Err(error) => panic!("the key must be accepted: {error}"),
+        };
+        mac.update(b"1700000000.{\"a\":1}");
+        let expected = hex::encode(mac.finalize().into_bytes());
+        assert_eq!(signature, expected);
+
+        match sign("key-key-key-key-key", 1_700_000_001, b"{\"a\":1}") {
+            Ok(later) => assert_ne!(later, signature),
+            Err(error) => panic!("the body must sign: {error}"),
+        }
+    }
+
+    /// Each invalid input is refused naming its field, and the limit is a
+    /// conflict rather than a validation failure.
+    #[tokio::test]
+    async fn test_registration_validates_its_input_and_the_limit() {
+        let webhooks = Webhooks::new(1, fast_policy(1));
+
+        assert_eq!(
+            validation_field(
+                webhooks
+                    .register(None, spec("ftp://example.com/hook"))
+                    .await
+            ),
+            "url"
+        );
+        assert_eq!(
+            validation_field(webhooks.register(None, spec("/hook")).await),
+            "url"
+        );
+        let mut short = spec("https://example.com/hook");
+        short.signing_key = "short".to_string();
+        assert_eq!(
+            validation_field(webhooks.register(None, short).await),
+            "signing_key"
+        );
+        let mut zero = spec("https://example.com/hook");
+        zero.every_n_steps = Some(0);
+        assert_eq!(
+            validation_field(webhooks.register(None, zero).await),
+            "every_n_steps"
+        );
+
+        let first = match webhooks
+            .register(None, spec("https://example.com/hook"))
+            .await
+        {
+            Ok(webhook) => webhook,
+            Err(error) => panic!("a valid webhook must register: {error}"),
+        };
+        match webhooks
+            .register(None, spec("https://example.com/other"))
+            .await
+        {
+            Err(ChainError::Conflict(_)) => {}
+            other => panic!("a registration past the limit must conflict, got {other:?}"),
+        }
+
+        assert!(webhooks.remove(first.id));
+        assert!(!webhooks.remove(first.id));
+        assert!(webhooks.list(None).is_empty());
+    }
+
+    /// A receiver on an internal address is refused, whether it is named by
+    /// its address or by a host that resolves to one, unless the operator
+    /// allows private receivers.
+    #[tokio::test]
+    async fn test_an_internal_target_is_refused() {
+        let webhooks = Webhooks::new(100, public_policy());
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://0.0.0.0/hook",
+            "http://10.1.2.3/hook",
+            "http://172.16.0.1/hook",
+            "http://192.168.1.10/hook",
+            "http://100.64.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://[64:ff9b::a9fe:a9fe]/hook",
+            "http://[64:ff9b:1::a00:1]/hook",
+            "http://[2002:a00:1::1]/hook",
+            "http://[2002:7f00:1::]/hook",
+        ] {
+            assert_eq!(
+                validation_field(webhooks.register(None, spec(url)).await),
+                "url",
+                "{url} must be refused"
+            );
+        }
+        for url in [
+            "https://93.184.215.14/hook",
+            "https://[64:ff9b::5db8:d70e]/hook",
+            "https://[2002:5db8:d70e::1]/hook",
+        ] {
+            if let Err(error) = webhooks.register(None, spec(url)).await {
+                panic!("{url} wraps a public address and must register: {error}");
+            }
+        }
+
+        let allowing = Webhooks::new(100, fast_policy(1));
+        if let Err(error) = allowing
+            .register(None, spec("http://169.254.169.254/hook"))
+            .await
+        {
+            panic!("an operator may allow private receivers: {error}");
+        }
+    }
+
+    /// A delivery re-checks the host when it connects, so a name that resolves
+    /// to an internal address is not reached even if it was registered.
+    #[actix_web::test]
+    async fn test_a_delivery_does_not_reach_an_internal_host() {
+        let receiver = Receiver::start(Vec::new());
+        let refusing = match client(public_policy()) {
+            Ok(client) => client,
+            Err(error) => panic!("the client must build: {error}"),
+        };
+        let delivery = Delivery {
+            url: receiver.url().replace("127.0.0.1", "localhost"),
+            signing_key: Arc::from("a-signing-key-of-some-length"),
+            event: WebhookEvent::Completed,
+            event_id: Uuid::new_v4(),
+            body: Arc::new(b"{}".to_vec()),
+        };
+
+        assert!(!deliver(&refusing, &delivery, public_policy()).await);
+        assert!(receiver.received().is_empty());
+
+        // The same delivery lands once private receivers are allowed, so it
+        // was the resolver that stopped it.
+        let allowing = match client(fast_policy(1)) {
+            Ok(client) => client,
+            Err(error) => panic!("the client must build: {error}"),
+        };
+        assert!(deliver(&allowing, &delivery, fast_policy(1)).await);
+        assert_eq!(receiver.received().len(), 1);
+    }
+
+    /// A delivery carries the event in its headers and body, and its signature
+    /// verifies under the registration's key.
+    #[actix_web::test]
+    async fn test_a_delivery_is_signed_and_describes_the_event() {
+        let receiver = Receiver::start(Vec::new());
+        let webhooks = Webhooks::new(10, fast_policy(1));
+        let key = "a-signing-key-of-some-length";
+        if let Err(error) = webhooks.register(None, spec(&receiver.url())).await {
+            panic!("the webhook must register: {error}");
+        }
+
+        let simulation_id = Uuid::new_v4();
+        webhooks.notify(WebhookEvent::Deleted, simulation_id, None);
+
+        let received = receiver.wait_for(1).await;
+        let delivery = &received[0];
+        assert_eq!(delivery.header("X-OCS-Event"), "deleted");
+        assert_eq!(delivery.header("Content-Type"), "application/json");
+
+        let timestamp = match delivery.header("X-OCS-Timestamp").parse::<u64>() {
+            Ok(timestamp) => timestamp,
+            Err(error) => panic!("the timestamp must be Unix seconds: {error}"),
+        };
+        let expected = match sign(key, timestamp, &delivery.body) {
+            Ok(signature) => format!("sha256={signature}"),
+            Err(error) => panic!("the body must sign: {error}"),
+        };
+        assert_eq!(delivery.header("X-OCS-Signature"), expected);
+
+        let body = delivery.json();
+        assert_eq!(body.get("event"), Some(&json!("deleted")));
+        assert_eq!(
+            body.get("simulation_id"),
+            Some(&json!(simulation_id.to_string()))
+        );
+        assert_eq!(
+            body.get("id"),
+            Some(&json!(delivery.header("X-OCS-Delivery")))
+        );
+        assert!(
+            body.get("current_step").is_none(),
+            "a deleted simulation has no progress to report: {body}"
+        );
+    }
+
+    /// A failing receiver is retried until it acknowledges, and every attempt
+    /// carries the same delivery id.
+    #[actix_web::test]
+    async fn test_a_failed_delivery_is_retried_under_the_same_id() {
+        let receiver = Receiver::start(vec![500, 503]);
+        let webhooks = Webhooks::new(10, fast_policy(3));
+        if let Err(error) = webhooks.register(None, spec(&receiver.url())).await {
+            panic!("the webhook must register: {error}");
+        }
+
+        webhooks.notify(WebhookEvent::Expired, Uuid::new_v4(), None);
+
+        let received = receiver.wait_for(3).await;
+        let ids: Vec<&str> = received
+            .iter()
+            .map(|delivery| delivery.header("X-OCS-Delivery"))
+            .collect();
+        assert!(ids.iter().all(|id| *id == ids[0]), "ids differ: {ids:?}");
+    }
+
+    /// A `4xx` refusal is final; a `429` is not.
+    #[actix_web::test]
+    async fn test_a_refusal_is_not_retried() {
+        let client = reqwest::Client::new();
+        let delivery = |url: String| Delivery {
+            url,
+            signing_key: Arc::from("a-signing-key-of-some-length"),
+            event: WebhookEvent::Completed,
+            event_id: Uuid::new_v4(),
+            body: Arc::new(b"{}".to_vec()),
+        };
+
+        let refusing = Receiver::start(vec![400]);
+        assert!(!deliver(&client, &delivery(refusing.url()), fast_policy(3)).await);
+        assert_eq!(refusing.received().len(), 1);
+
+        let throttling = Receiver::start(vec![429]);
+        assert!(deliver(&client, &delivery(throttling.url()), fast_policy(3)).await);
+        assert_eq!(throttling.received().len(), 2);
+    }
+
+    /// A redirect is not followed, and fails the delivery without a retry.
+    #[actix_web::test]
+    async fn test_a_redirect_is_not_followed() {
+        let client = match client(fast_policy(3)) {
+            Ok(client) => client,
+            Err(error) => panic!("the client must build: {error}"),
+        };
+        let receiver = Receiver::start(vec![307]);
+        let delivery = Delivery {
+            url: receiver.url(),
+            signing_key: Arc::from("a-signing-key-of-some-length"),
+            event: WebhookEvent::Completed,
+            event_id: Uuid::new_v4(),
+            body: Arc::new(b"{}".to_vec()),
+        };
+
+        assert!(!deliver(&client, &delivery, fast_policy(3)).await);
+        assert_eq!(receiver.received().len(), 1);
+    }
+
+    /// A registration bound to one simulation hears nothing about another,
+    /// and is dropped when its simulation is forgotten.
+    #[actix_web::test]
+    async fn test_a_bound_registration_hears_only_its_simulation() {
+        let receiver = Receiver::start(Vec::new());
+        let webhooks = Webhooks::new(10, fast_policy(1));
+        let bound = Uuid::new_v4();
+        if let Err(error) = webhooks.register(Some(bound), spec(&receiver.url())).await {
+            panic!("the webhook must register: {error}");
+        }
+
+        webhooks.notify(WebhookEvent::Deleted, Uuid::new_v4(), None);
+        webhooks.notify(WebhookEvent::Deleted, bound, None);
+        let received = receiver.wait_for(1).await;
+        assert_eq!(
+            received[0].json().get("simulation_id"),
+            Some(&json!(bound.to_string()))
+        );
+
+        assert_eq!(webhooks.list(Some(bound)).len(), 1);
+        assert!(webhooks.list(None).is_empty());
+        webhooks.forget(bound);
+        assert!(webhooks.list(Some(bound)).is_empty());
+    }
+}